@@ -1,8 +1,13 @@
+use crate::rules::PatternRule;
+use crate::text::slugify;
 use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::borrow::Cow;
+use std::collections::BTreeMap;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
 
 /// ARF (Augmented Reasoning Format) file structure
 /// Stores codebase knowledge as structured TOML with what/why/how/context sections
@@ -17,11 +22,73 @@ pub struct ArfFile {
     /// How: Implementation details or process
     pub how: String,
     
+    /// Stable identifier, the slug of `what` when the entry was first
+    /// written. Learn finds the entry's file by it whatever the file is
+    /// named, so changing `writer.naming` doesn't duplicate knowledge.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    
+    /// Category directory the entry belongs in (decisions, patterns, bugs,
+    /// migrations, facts); inferred from the content when absent
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub category: Option<String>,
+    
+    /// How the entry was produced
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<ArfSource>,
+    
+    /// Reviewed by a person; learn will not silently overwrite it
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub approved: bool,
+    
+    /// Free-form labels for filtering (e.g. "security", "release")
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    
+    /// How sure the extraction is, from 0.0 to 1.0
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub confidence: Option<f64>,
+    
+    /// When the entry was first written to the knowledge base
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<DateTime<Utc>>,
+    
+    /// When the entry's content last changed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updated_at: Option<DateTime<Utc>>,
+    
+    /// Machine-checkable rules evaluated by `noggin check`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub rules: Vec<PatternRule>,
+    
     /// Optional context with additional metadata
     #[serde(default)]
     pub context: ArfContext,
 }
 
+/// How an ARF entry was produced
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum ArfSource {
+    /// Learn's analysis of file contents
+    FileAnalysis,
+    /// Learn's analysis of commit history
+    CommitAnalysis,
+    /// Written by a person
+    Manual,
+}
+
+impl ArfSource {
+    /// Source of entries from a learn prompt type (`files`, `commits`,
+    /// `releases`, `patterns`, `glossary`)
+    pub fn from_prompt_type(prompt_type: &str) -> Self {
+        match prompt_type {
+            "commits" | "releases" => ArfSource::CommitAnalysis,
+            _ => ArfSource::FileAnalysis,
+        }
+    }
+}
+
 /// Context section with metadata about the knowledge
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
 pub struct ArfContext {
@@ -33,13 +100,58 @@ pub struct ArfContext {
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub commits: Vec<String>,
     
+    /// Branches the related commits were analyzed from
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub branches: Vec<String>,
+    
+    /// Issues and pull requests, as URLs or short references like `#123`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub issues: Vec<String>,
+    
+    /// Authors of the cited commits, most involved first
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub people: Vec<String>,
+    
     /// Dependencies required
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub dependencies: Vec<String>,
     
+    /// Learn prompts the entry was synthesized from (`files`, `commits`,
+    /// `patterns`)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub prompt_types: Vec<String>,
+    
     /// Outcome or result (key-value pairs)
-    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
-    pub outcome: HashMap<String, String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub outcome: BTreeMap<String, String>,
+    
+    /// Models the entry was synthesized from
+    #[serde(default, skip_serializing_if = "Provenance::is_empty")]
+    pub provenance: Provenance,
+    
+    /// Keys this schema doesn't know (e.g. `severity`, `component`), kept
+    /// as written so nothing a model provided is lost
+    #[serde(flatten)]
+    pub extra: BTreeMap<String, toml::Value>,
+}
+
+/// Where a synthesized entry came from
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct Provenance {
+    /// Models whose output contributed to the entry
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub models: Vec<String>,
+    
+    /// For each field the models disagreed on, the models whose value won
+    /// the vote (e.g. `what = ["claude", "gemini"]`)
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub fields: BTreeMap<String, Vec<String>>,
+}
+
+impl Provenance {
+    pub fn is_empty(&self) -> bool {
+        self.models.is_empty() && self.fields.is_empty()
+    }
 }
 
 impl ArfFile {
@@ -49,9 +161,24 @@ impl ArfFile {
             what: what.into(),
             why: why.into(),
             how: how.into(),
+            id: None,
+            category: None,
+            source: None,
+            approved: false,
+            tags: Vec::new(),
+            confidence: None,
+            created_at: None,
+            updated_at: None,
+            rules: Vec::new(),
             context: ArfContext::default(),
         }
     }
+
+    /// The entry's id, or the slug of its `what` when it has none yet;
+    /// the writer files it under this
+    pub fn id_or_slug(&self) -> String {
+        self.id.clone().unwrap_or_else(|| slugify(&self.what))
+    }
     
     /// Load ARF file from TOML file
     pub fn from_toml(path: &Path) -> Result<Self> {
@@ -81,8 +208,10 @@ impl ArfFile {
         Ok(())
     }
     
-    /// Validate that required fields are present and non-empty
-    pub fn validate(&self) -> Result<()> {
+    /// Validate that required fields are present and non-empty. Any other
+    /// invalid field is repaired or dropped rather than costing the whole
+    /// entry; returns one note per change.
+    pub fn validate(&mut self) -> Result<Vec<String>> {
         if self.what.trim().is_empty() {
             anyhow::bail!("ARF file missing required field: what");
         }
@@ -95,7 +224,52 @@ impl ArfFile {
             anyhow::bail!("ARF file missing required field: how");
         }
         
-        Ok(())
+        let mut notes = Vec::new();
+        
+        if let Some(category) = &self.category {
+            if !crate::learn::writer::CATEGORY_DIRS.contains(&category.as_str()) {
+                notes.push(format!(
+                    "dropped category '{}' (must be one of {})",
+                    category,
+                    crate::learn::writer::CATEGORY_DIRS.join(", ")
+                ));
+                self.category = None;
+            }
+        }
+        
+        if let Some(confidence) = self.confidence {
+            if !(0.0..=1.0).contains(&confidence) {
+                notes.push(format!("dropped confidence {} (outside 0.0 to 1.0)", confidence));
+                self.confidence = None;
+            }
+        }
+        
+        let mut idx = 0;
+        self.rules.retain(|rule| {
+            idx += 1;
+            match rule.validate() {
+                Ok(()) => true,
+                Err(e) => {
+                    notes.push(format!("dropped rule {}: {:#}", idx, e));
+                    false
+                }
+            }
+        });
+        
+        let mut issues = Vec::new();
+        for issue in self.context.issues.drain(..) {
+            match crate::issues::normalize(&issue, None) {
+                Some(repaired) if !issues.contains(&repaired) => issues.push(repaired),
+                Some(_) => {}
+                None => notes.push(format!(
+                    "dropped issue '{}' (expected a URL or a reference like #123)",
+                    issue
+                )),
+            }
+        }
+        self.context.issues = issues;
+        
+        Ok(notes)
     }
     
     /// Add a file path to the context
@@ -108,6 +282,14 @@ impl ArfFile {
         self.context.commits.push(commit.into());
     }
     
+    /// Add an issue reference to the context, skipping duplicates
+    pub fn add_issue(&mut self, issue: impl Into<String>) {
+        let issue = issue.into();
+        if !self.context.issues.contains(&issue) {
+            self.context.issues.push(issue);
+        }
+    }
+    
     /// Add a dependency to the context
     pub fn add_dependency(&mut self, dep: impl Into<String>) {
         self.context.dependencies.push(dep.into());
@@ -117,6 +299,103 @@ impl ArfFile {
     pub fn add_outcome(&mut self, key: impl Into<String>, value: impl Into<String>) {
         self.context.outcome.insert(key.into(), value.into());
     }
+    
+    /// Value of a text field by name: `what`, `why`, `how`,
+    /// `context.outcome.<key>`, or `context.extra.<key>` (see [`extra_text`])
+    pub fn field(&self, name: &str) -> Option<Cow<'_, str>> {
+        match name {
+            "what" => Some(Cow::Borrowed(&self.what)),
+            "why" => Some(Cow::Borrowed(&self.why)),
+            "how" => Some(Cow::Borrowed(&self.how)),
+            _ => {
+                if let Some(key) = name.strip_prefix("context.outcome.") {
+                    return self.context.outcome.get(key).map(|v| Cow::Borrowed(v.as_str()));
+                }
+                let key = name.strip_prefix("context.extra.")?;
+                self.context.extra.get(key).map(extra_text)
+            }
+        }
+    }
+    
+    /// Set a text field by name (see [`ArfFile::field`]). Returns false if
+    /// `name` is not a settable field.
+    pub fn set_field(&mut self, name: &str, value: impl Into<String>) -> bool {
+        let value = value.into();
+        match name {
+            "what" => self.what = value,
+            "why" => self.why = value,
+            "how" => self.how = value,
+            _ => {
+                if let Some(key) = name.strip_prefix("context.outcome.") {
+                    self.add_outcome(key, value);
+                } else if let Some(key) = name.strip_prefix("context.extra.") {
+                    // Text stays text; anything else is read back as TOML
+                    let was_text = self.context.extra.get(key).is_none_or(|v| v.is_str());
+                    let parsed = toml::from_str::<toml::Table>(&format!("v = {}", value))
+                        .ok()
+                        .and_then(|mut table| table.remove("v"));
+                    let value = match parsed {
+                        Some(parsed) if !was_text => parsed,
+                        _ => toml::Value::String(value),
+                    };
+                    self.context.extra.insert(key.to_string(), value);
+                } else {
+                    return false;
+                }
+            }
+        }
+        true
+    }
+}
+
+/// An unknown context value as text: strings as written, anything else
+/// (numbers, arrays, tables) in its TOML form
+pub fn extra_text(value: &toml::Value) -> Cow<'_, str> {
+    match value {
+        toml::Value::String(s) => Cow::Borrowed(s),
+        other => Cow::Owned(other.to_string()),
+    }
+}
+
+/// Namespaces nested inside a category directory that hold ARFs of their
+/// own, such as the glossary under `facts/`
+pub const NESTED_ARF_DIRS: &[&str] = &["facts/glossary"];
+
+/// Every `.arf` file in the knowledge base, sorted: those directly inside
+/// a directory of `.noggin/`, plus those in [`NESTED_ARF_DIRS`]. Deeper
+/// `.arf` files (such as export templates) aren't knowledge.
+pub fn arf_paths(noggin_path: &Path) -> Vec<PathBuf> {
+    let mut paths: Vec<PathBuf> = WalkDir::new(noggin_path)
+        .min_depth(2)
+        .max_depth(3)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.path().extension().is_some_and(|ext| ext == "arf"))
+        .filter(|e| {
+            e.depth() == 2
+                || e.path()
+                    .parent()
+                    .and_then(|dir| dir.strip_prefix(noggin_path).ok())
+                    .is_some_and(|dir| NESTED_ARF_DIRS.iter().any(|nested| dir == Path::new(nested)))
+        })
+        .map(|e| e.into_path())
+        .collect();
+    paths.sort();
+    paths
+}
+
+/// Category of the ARF at `path`: the directory it's in, or for a nested
+/// namespace the category directory above it
+pub fn arf_category(path: &Path) -> String {
+    let Some(dir) = path.parent() else {
+        return "unknown".to_string();
+    };
+    let dir = NESTED_ARF_DIRS
+        .iter()
+        .find(|nested| dir.ends_with(nested))
+        .and_then(|nested| Path::new(nested).iter().next())
+        .or_else(|| dir.file_name());
+    dir.and_then(|n| n.to_str()).unwrap_or("unknown").to_string()
 }
 
 #[cfg(test)]
@@ -153,13 +432,28 @@ mod tests {
     
     #[test]
     fn test_validate_success() {
-        let arf = ArfFile::new("What", "Why", "How");
-        assert!(arf.validate().is_ok());
+        let mut arf = ArfFile::new("What", "Why", "How");
+        assert_eq!(arf.validate().unwrap(), Vec::<String>::new());
+    }
+    
+    #[test]
+    fn test_validate_repairs_and_drops_bad_fields() {
+        let mut arf = ArfFile::new("What", "Why", "How");
+        arf.confidence = Some(1.5);
+        arf.add_issue("(#42).");
+        arf.add_issue("see the tracker");
+        arf.add_issue("https://example.com/issues/7");
+        
+        let notes = arf.validate().unwrap();
+        assert_eq!(arf.confidence, None);
+        assert_eq!(arf.context.issues, vec!["#42", "https://example.com/issues/7"]);
+        assert_eq!(notes.len(), 2);
+        assert!(notes[1].contains("see the tracker"));
     }
     
     #[test]
     fn test_validate_missing_what() {
-        let arf = ArfFile::new("", "Why", "How");
+        let mut arf = ArfFile::new("", "Why", "How");
         let result = arf.validate();
         assert!(result.is_err());
         assert!(result.unwrap_err().to_string().contains("what"));
@@ -167,7 +461,7 @@ mod tests {
     
     #[test]
     fn test_validate_missing_why() {
-        let arf = ArfFile::new("What", "", "How");
+        let mut arf = ArfFile::new("What", "", "How");
         let result = arf.validate();
         assert!(result.is_err());
         assert!(result.unwrap_err().to_string().contains("why"));
@@ -175,7 +469,7 @@ mod tests {
     
     #[test]
     fn test_validate_missing_how() {
-        let arf = ArfFile::new("What", "Why", "");
+        let mut arf = ArfFile::new("What", "Why", "");
         let result = arf.validate();
         assert!(result.is_err());
         assert!(result.unwrap_err().to_string().contains("how"));
@@ -206,6 +500,99 @@ mod tests {
         assert_eq!(original, loaded);
     }
     
+    #[test]
+    fn test_unknown_context_keys_round_trip() {
+        let tmp_dir = TempDir::new().unwrap();
+        let file_path = tmp_dir.path().join("test.arf");
+        fs::write(
+            &file_path,
+            r#"what = "Retry payments"
+why = "Flaky networks"
+how = "Backoff"
+
+[context]
+files = ["src/pay.rs"]
+severity = "high"
+component = { name = "billing", owners = ["ops"] }
+"#,
+        )
+        .unwrap();
+        
+        let loaded = ArfFile::from_toml(&file_path).unwrap();
+        assert_eq!(loaded.context.files, vec!["src/pay.rs"]);
+        assert_eq!(
+            loaded.context.extra.get("severity").and_then(|v| v.as_str()),
+            Some("high")
+        );
+        assert!(loaded.context.extra["component"].is_table());
+        
+        loaded.to_toml(&file_path).unwrap();
+        assert_eq!(ArfFile::from_toml(&file_path).unwrap(), loaded);
+    }
+
+    #[test]
+    fn test_extra_fields_by_name() {
+        let mut arf = ArfFile::new("X", "Y", "Z");
+        arf.context.extra.insert("severity".to_string(), "high".into());
+        arf.context.extra.insert("retries".to_string(), toml::Value::from(3));
+
+        assert_eq!(arf.field("context.extra.severity").as_deref(), Some("high"));
+        assert_eq!(arf.field("context.extra.retries").as_deref(), Some("3"));
+
+        assert!(arf.set_field("context.extra.severity", "5"));
+        assert!(arf.set_field("context.extra.retries", "5"));
+        assert_eq!(arf.context.extra["severity"].as_str(), Some("5"));
+        assert_eq!(arf.context.extra["retries"].as_integer(), Some(5));
+    }
+
+    #[test]
+    fn test_source_from_prompt_type() {
+        assert_eq!(ArfSource::from_prompt_type("commits"), ArfSource::CommitAnalysis);
+        assert_eq!(ArfSource::from_prompt_type("releases"), ArfSource::CommitAnalysis);
+        assert_eq!(ArfSource::from_prompt_type("files"), ArfSource::FileAnalysis);
+        assert_eq!(ArfSource::from_prompt_type("patterns"), ArfSource::FileAnalysis);
+    }
+
+    #[test]
+    fn test_v2_fields_round_trip() {
+        let v1: ArfFile = toml::from_str("what = \"A\"\nwhy = \"B\"\nhow = \"C\"\n").unwrap();
+        assert!(v1.category.is_none() && v1.source.is_none() && v1.created_at.is_none());
+        assert!(!toml::to_string_pretty(&v1).unwrap().contains("source"));
+
+        let mut arf = ArfFile::new("Retry payments", "Flaky networks", "Backoff");
+        arf.category = Some("patterns".to_string());
+        arf.source = Some(ArfSource::CommitAnalysis);
+        arf.created_at = Some("2026-03-04T05:06:07Z".parse().unwrap());
+        arf.updated_at = arf.created_at;
+        arf.tags = vec!["payments".to_string()];
+
+        let contents = toml::to_string_pretty(&arf).unwrap();
+        assert!(contents.contains("source = \"commit-analysis\""));
+        assert!(contents.contains("created_at = \"2026-03-04T05:06:07Z\""));
+        assert_eq!(toml::from_str::<ArfFile>(&contents).unwrap(), arf);
+
+        arf.category = Some("ideas".to_string());
+        assert!(arf.validate().unwrap()[0].contains("category"));
+        assert_eq!(arf.category, None);
+    }
+
+    #[test]
+    fn test_serialization_is_sorted_and_stable() {
+        let mut arf = ArfFile::new("Retry payments", "Flaky networks", "Backoff");
+        for key in ["zeta", "alpha", "mid"] {
+            arf.add_outcome(key, "ok");
+            arf.context.extra.insert(format!("x_{}", key), toml::Value::from(1));
+        }
+        
+        let first = toml::to_string_pretty(&arf).unwrap();
+        let reloaded: ArfFile = toml::from_str(&first).unwrap();
+        assert_eq!(toml::to_string_pretty(&reloaded).unwrap(), first);
+        
+        let position = |needle: &str| first.find(needle).unwrap();
+        assert!(position("alpha =") < position("mid =") && position("mid =") < position("zeta ="));
+        assert!(position("x_alpha") < position("x_mid") && position("x_mid") < position("x_zeta"));
+    }
+    
     #[test]
     fn test_from_toml_missing_file() {
         let result = ArfFile::from_toml(Path::new("/nonexistent/file.arf"));
@@ -247,4 +634,32 @@ mod tests {
         assert!(context.dependencies.is_empty());
         assert!(context.outcome.is_empty());
     }
+    
+    #[test]
+    fn test_arf_paths_include_nested_namespaces() {
+        let tmp_dir = TempDir::new().unwrap();
+        let arf = ArfFile::new("Test", "Reason", "Steps");
+        for rel in [
+            "facts/glossary/manifest.arf",
+            "facts/deps.arf",
+            "decisions/pool.arf",
+            "editor/templates/fact.arf",
+            "pending/decisions/held.arf",
+        ] {
+            arf.to_toml(&tmp_dir.path().join(rel)).unwrap();
+        }
+        
+        let rels: Vec<String> = arf_paths(tmp_dir.path())
+            .iter()
+            .map(|p| p.strip_prefix(tmp_dir.path()).unwrap().to_string_lossy().replace('\\', "/"))
+            .collect();
+        assert_eq!(
+            rels,
+            vec!["decisions/pool.arf", "facts/deps.arf", "facts/glossary/manifest.arf"]
+        );
+        
+        let nested = tmp_dir.path().join("facts/glossary/manifest.arf");
+        assert_eq!(arf_category(&nested), "facts");
+        assert_eq!(arf_category(&tmp_dir.path().join("bugs/x.arf")), "bugs");
+    }
 }
@@ -0,0 +1,81 @@
+use crate::arf::ArfSource;
+use crate::config::Config;
+use crate::lock::KnowledgeLock;
+use crate::schema::validate_arf_toml;
+use crate::storage;
+use anyhow::{Context, Result};
+use std::env;
+
+/// Mark ARF files as approved so learn will not silently overwrite them.
+///
+/// Paths may be given relative to the repository or to .noggin/. With
+/// `dry_run`, reports the changes without saving them. A file that doesn't
+/// match the ARF schema is refused, since approval protects it from being
+/// rewritten by learn. Learn gives every entry it writes an id and a
+/// source, so approving a file with neither records it as `manual`.
+pub fn approve_command(paths: &[String], revoke: bool, dry_run: bool) -> Result<()> {
+    let repo_path = env::current_dir()?;
+    let noggin_path = repo_path.join(".noggin");
+
+    if !noggin_path.exists() {
+        anyhow::bail!("Not initialized. Run 'noggin init' first.");
+    }
+    let _lock = if dry_run {
+        None
+    } else {
+        Some(KnowledgeLock::acquire(&noggin_path, "approve")?)
+    };
+
+    let config = Config::load(&noggin_path).context("Failed to load config")?;
+    let backend = storage::open(&noggin_path, &config.storage);
+
+    for path in paths {
+        let arf_path = knowledge_path(path);
+        let contents = backend
+            .read(arf_path)
+            .with_context(|| format!("Failed to read ARF file: {}", path))?
+            .with_context(|| format!("ARF file not found: {}", path))?;
+
+        if !revoke {
+            let errors = validate_arf_toml(&contents)
+                .with_context(|| format!("Failed to parse TOML in: {}", path))?;
+            if let Some(first) = errors.first() {
+                anyhow::bail!(
+                    "{} doesn't match the ARF schema: {} {} ({} error(s); run 'noggin check')",
+                    path,
+                    first.field,
+                    first.message,
+                    errors.len()
+                );
+            }
+        }
+
+        let mut arf = backend
+            .read_arf(arf_path)?
+            .with_context(|| format!("ARF file not found: {}", path))?;
+        if arf.approved != revoke {
+            println!("  {} (unchanged)", path);
+            continue;
+        }
+
+        if dry_run {
+            println!("  Would {} {}", if revoke { "unapprove" } else { "approve" }, path);
+            continue;
+        }
+        arf.approved = !revoke;
+        if !revoke && arf.id.is_none() && arf.source.is_none() {
+            arf.source = Some(ArfSource::Manual);
+        }
+        backend.write_arf(arf_path, &arf)?;
+        println!("  {} {}", if revoke { "Unapproved" } else { "Approved" }, path);
+    }
+
+    Ok(())
+}
+
+/// `path` relative to the knowledge base, accepting repository-relative
+/// paths under `.noggin/`
+fn knowledge_path(path: &str) -> &str {
+    let path = path.trim_start_matches("./");
+    path.strip_prefix(".noggin/").unwrap_or(path)
+}
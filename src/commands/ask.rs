@@ -0,0 +1,385 @@
+use crate::commands::bundle::open_bundle;
+use crate::config::Config;
+use crate::issues::markdown_link;
+use crate::markdown::render_terminal;
+use crate::metrics::record_ask;
+use crate::query::{BatchQuestion, QueryEngine, QueryOptions, QueryResult, SortBy};
+use crate::saved_queries::{OutputFormat, SavedQueries};
+use crate::storage;
+use anyhow::{Context, Result};
+use colored::Colorize;
+use serde::Deserialize;
+use std::env;
+use std::fs;
+use std::io::{IsTerminal, Read};
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+/// Options for `noggin ask`
+#[derive(Debug, Clone, Default)]
+pub struct AskOptions {
+    /// Ad-hoc query string (ignored when `saved` is set)
+    pub query: Option<String>,
+    /// Name of a query in .noggin/queries.toml
+    pub saved: Option<String>,
+    /// Overrides the saved query's limit; defaults to 10
+    pub max_results: Option<usize>,
+    /// Overrides the saved query's category
+    pub category: Option<String>,
+    /// Added to the saved query's tags
+    pub tags: Vec<String>,
+    /// Overrides the saved query's minimum confidence
+    pub min_confidence: Option<f64>,
+    /// Only ARFs modified since this date (see `parse_since`)
+    pub since: Option<String>,
+    /// Result order
+    pub sort: SortBy,
+    /// Force JSON output
+    pub json: bool,
+    /// Write the answer to this file as Markdown (JSON with `json`)
+    /// instead of printing it
+    pub output: Option<PathBuf>,
+    /// Query this bundle from `noggin bundle` instead of .noggin/
+    pub bundle: Option<PathBuf>,
+    /// JSON file of questions to answer together (`-` for stdin); the
+    /// filters above apply to every question that doesn't set its own
+    pub batch: Option<PathBuf>,
+}
+
+/// A batch file entry: a bare question, or one with its own filters
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum BatchEntry {
+    Question(String),
+    Detailed(BatchQuestion),
+}
+
+const DEFAULT_MAX_RESULTS: usize = 10;
+
+pub fn ask_command(opts: AskOptions) -> Result<()> {
+    // A bundle is unpacked for the duration of the query
+    let bundle = opts.bundle.as_deref().map(open_bundle).transpose()?;
+    let noggin_path = match &bundle {
+        Some(bundle) => bundle.path().to_path_buf(),
+        None => env::current_dir()?.join(".noggin"),
+    };
+
+    if !noggin_path.exists() {
+        anyhow::bail!("Not initialized. Run 'noggin init' first.");
+    }
+    if let Some(batch) = &opts.batch {
+        return ask_batch(&opts, batch, &noggin_path, bundle.is_some());
+    }
+
+    let (title, terms, mut query_opts, mut format) = match &opts.saved {
+        Some(name) => {
+            let saved = SavedQueries::load(&noggin_path)?;
+            let query = saved.get(name)?;
+            (
+                query.question.clone(),
+                query.search_terms(),
+                query.options(DEFAULT_MAX_RESULTS),
+                query.format,
+            )
+        }
+        None => {
+            let query = opts
+                .query
+                .clone()
+                .ok_or_else(|| anyhow::anyhow!("Provide a query or --saved <name>"))?;
+            (
+                query.clone(),
+                vec![query],
+                QueryOptions::default(),
+                OutputFormat::Text,
+            )
+        }
+    };
+
+    apply_filters(&opts, &mut query_opts)?;
+    if opts.json {
+        format = OutputFormat::Json;
+    }
+
+    let started = Instant::now();
+    let engine = open_engine(&noggin_path, bundle.is_some())?;
+    let results = engine.search_any(&terms, &query_opts)?;
+    if bundle.is_none() {
+        record_ask(&noggin_path, started.elapsed());
+    }
+
+    if let Some(path) = &opts.output {
+        let contents = match format {
+            OutputFormat::Json => serde_json::to_string_pretty(&results)? + "\n",
+            _ => render_markdown(&title, &results),
+        };
+        fs::write(path, contents).with_context(|| format!("Failed to write {}", path.display()))?;
+        eprintln!("{} results for \"{}\" written to {}", results.len(), title, path.display());
+        return Ok(());
+    }
+
+    match format {
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&results)?),
+        // Styled on a terminal, raw for pipes and redirects
+        OutputFormat::Markdown if std::io::stdout().is_terminal() => {
+            println!("{}", render_terminal(&render_markdown(&title, &results)))
+        }
+        OutputFormat::Markdown => print!("{}", render_markdown(&title, &results)),
+        OutputFormat::Text => print_text(&title, &results, std::io::stdout().is_terminal()),
+    }
+
+    Ok(())
+}
+
+/// Answer every question in the `batch` file with one engine, printing
+/// (or writing to `opts.output`) a JSON array of answers
+fn ask_batch(opts: &AskOptions, batch: &Path, noggin_path: &Path, from_bundle: bool) -> Result<()> {
+    let contents = if batch == Path::new("-") {
+        let mut contents = String::new();
+        std::io::stdin()
+            .read_to_string(&mut contents)
+            .context("Failed to read questions from stdin")?;
+        contents
+    } else {
+        fs::read_to_string(batch).with_context(|| format!("Failed to read {}", batch.display()))?
+    };
+    let questions = parse_batch(&contents)
+        .with_context(|| format!("Failed to parse {} as a JSON array of questions", batch.display()))?;
+
+    let mut defaults = QueryOptions::default();
+    apply_filters(opts, &mut defaults)?;
+
+    let started = Instant::now();
+    let engine = open_engine(noggin_path, from_bundle)?;
+    let answers = engine.answer_batch(&questions, &defaults);
+    if !from_bundle {
+        record_ask(noggin_path, started.elapsed());
+    }
+
+    let json = serde_json::to_string_pretty(&answers)? + "\n";
+    match &opts.output {
+        Some(path) => {
+            fs::write(path, json).with_context(|| format!("Failed to write {}", path.display()))?;
+            eprintln!("{} answers written to {}", answers.len(), path.display());
+        }
+        None => print!("{}", json),
+    }
+    Ok(())
+}
+
+/// Questions in a batch file's JSON array
+fn parse_batch(contents: &str) -> Result<Vec<BatchQuestion>> {
+    let entries: Vec<BatchEntry> = serde_json::from_str(contents)?;
+    Ok(entries
+        .into_iter()
+        .map(|entry| match entry {
+            BatchEntry::Question(question) => BatchQuestion {
+                question,
+                ..Default::default()
+            },
+            BatchEntry::Detailed(question) => question,
+        })
+        .collect())
+}
+
+/// Apply the command-line filters on top of `query_opts`
+fn apply_filters(opts: &AskOptions, query_opts: &mut QueryOptions) -> Result<()> {
+    let filters = BatchQuestion {
+        question: String::new(),
+        category: opts.category.clone(),
+        max_results: opts.max_results,
+        tags: opts.tags.clone(),
+        min_confidence: opts.min_confidence,
+        since: opts.since.clone(),
+    };
+    *query_opts = filters.options(query_opts)?;
+    query_opts.sort = opts.sort;
+    Ok(())
+}
+
+/// Bundles are always plain files; the knowledge base uses its backend
+fn open_engine(noggin_path: &Path, from_bundle: bool) -> Result<QueryEngine> {
+    if from_bundle {
+        return Ok(QueryEngine::new(noggin_path.to_path_buf()));
+    }
+    let config = Config::load(noggin_path).context("Failed to load config")?;
+    let backend = storage::open(noggin_path, &config.storage);
+    Ok(QueryEngine::with_backend(noggin_path.to_path_buf(), backend))
+}
+
+/// Print `results` as text; on a terminal, the Markdown in each entry's
+/// what and why is rendered rather than shown raw
+fn print_text(title: &str, results: &[QueryResult], terminal: bool) {
+    let prose = |text: &str| {
+        if terminal {
+            render_terminal(text).trim_end().to_string()
+        } else {
+            text.to_string()
+        }
+    };
+
+    if results.is_empty() {
+        println!("No results for \"{}\"", title);
+        println!("Try a broader query or run {} to learn more.", "'noggin learn'".cyan());
+        return;
+    }
+
+    println!("{} results for \"{}\"\n", results.len(), title);
+
+    let mut current_category = String::new();
+    for result in results {
+        if result.category != current_category {
+            current_category = result.category.clone();
+            println!("{}", current_category.to_uppercase().bold());
+        }
+        println!("  {} {}", result.file_path.dimmed(), format!("[{}]", result.matched_fields.join(", ")).dimmed());
+        println!("  {}", prose(&result.what).cyan());
+        println!("  {}", prose(&result.why));
+        if !result.issues.is_empty() {
+            println!("  {}", result.issues.join(" ").dimmed());
+        }
+        if !result.people.is_empty() {
+            println!("  {} {}", "people:".dimmed(), result.people.join(", ").dimmed());
+        }
+        println!();
+    }
+
+    let people = people_ranking(results);
+    if !people.is_empty() {
+        let ranked: Vec<String> = people
+            .iter()
+            .map(|(name, count)| format!("{} ({})", name, count))
+            .collect();
+        println!("{} {}", "Most involved:".bold(), ranked.join(", "));
+    }
+}
+
+/// Authors across `results` with how many results list them, most first
+fn people_ranking(results: &[QueryResult]) -> Vec<(String, usize)> {
+    let mut counts: Vec<(String, usize)> = Vec::new();
+    for person in results.iter().flat_map(|r| &r.people) {
+        match counts.iter_mut().find(|(name, _)| name == person) {
+            Some((_, count)) => *count += 1,
+            None => counts.push((person.clone(), 1)),
+        }
+    }
+    counts.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+    counts
+}
+
+/// Render results as a Markdown report grouped by category
+fn render_markdown(title: &str, results: &[QueryResult]) -> String {
+    let mut out = format!("# {}\n\n", title);
+
+    if results.is_empty() {
+        out.push_str("_No matching knowledge._\n");
+        return out;
+    }
+
+    let mut current_category = String::new();
+    for result in results {
+        if result.category != current_category {
+            current_category = result.category.clone();
+            out.push_str(&format!("## {}\n\n", capitalize(&current_category)));
+        }
+        out.push_str(&format!("### {}\n\n", result.what));
+        out.push_str(&format!("**Why:** {}\n\n", result.why));
+        out.push_str(&format!("**How:** {}\n\n", result.how));
+        if !result.issues.is_empty() {
+            let links: Vec<String> = result.issues.iter().map(|i| markdown_link(i)).collect();
+            out.push_str(&format!("**Issues:** {}\n\n", links.join(", ")));
+        }
+        if !result.people.is_empty() {
+            out.push_str(&format!("**People:** {}\n\n", result.people.join(", ")));
+        }
+        out.push_str(&format!("_Source: `.noggin/{}`_\n\n", result.file_path));
+    }
+
+    out
+}
+
+fn capitalize(text: &str) -> String {
+    let mut chars = text.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(category: &str, what: &str) -> QueryResult {
+        QueryResult {
+            file_path: format!("{}/x.arf", category),
+            category: category.to_string(),
+            what: what.to_string(),
+            why: "Because".to_string(),
+            how: "Like this".to_string(),
+            tags: vec![],
+            confidence: None,
+            issues: vec![],
+            people: vec![],
+            matched_fields: vec!["what".to_string()],
+            score: 10.0,
+        }
+    }
+
+    #[test]
+    fn test_render_markdown_groups_by_category() {
+        let md = render_markdown(
+            "Release checklist",
+            &[result("decisions", "Tag releases"), result("patterns", "Bump version")],
+        );
+
+        assert!(md.starts_with("# Release checklist\n"));
+        assert!(md.contains("## Decisions\n"));
+        assert!(md.contains("## Patterns\n"));
+        assert!(md.contains("### Tag releases"));
+        assert!(md.contains("`.noggin/decisions/x.arf`"));
+        assert!(!md.contains("**Issues:**"));
+    }
+
+    #[test]
+    fn test_render_markdown_links_issues() {
+        let mut linked = result("bugs", "Retry payments");
+        linked.issues = vec!["https://github.com/o/r/issues/12".to_string(), "PAY-7".to_string()];
+        let md = render_markdown("Payments", &[linked]);
+        assert!(md.contains("**Issues:** [#12](https://github.com/o/r/issues/12), PAY-7\n"));
+    }
+
+    #[test]
+    fn test_parse_batch_accepts_strings_and_objects() {
+        let questions = parse_batch(
+            r#"["why sqlx?", {"question": "retry", "category": "bugs", "tags": ["net"]}]"#,
+        )
+        .unwrap();
+        assert_eq!(questions.len(), 2);
+        assert_eq!(questions[0].question, "why sqlx?");
+        assert!(questions[0].category.is_none());
+        assert_eq!(questions[1].category.as_deref(), Some("bugs"));
+        assert_eq!(questions[1].tags, vec!["net"]);
+
+        assert!(parse_batch(r#"{"question": "not an array"}"#).is_err());
+    }
+
+    #[test]
+    fn test_people_ranking() {
+        let mut a = result("patterns", "Billing retries");
+        a.people = vec!["Ana".to_string(), "Bo".to_string()];
+        let mut b = result("bugs", "Retry double charge");
+        b.people = vec!["Bo".to_string()];
+
+        assert_eq!(
+            people_ranking(&[a, b, result("facts", "Unrelated")]),
+            vec![("Bo".to_string(), 2), ("Ana".to_string(), 1)]
+        );
+    }
+
+    #[test]
+    fn test_render_markdown_empty() {
+        let md = render_markdown("Security posture", &[]);
+        assert!(md.contains("_No matching knowledge._"));
+    }
+}
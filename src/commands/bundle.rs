@@ -0,0 +1,245 @@
+//! `noggin bundle`: pack the knowledge base into one file
+//!
+//! A bundle is a gzipped tarball holding every ARF, the manifest, the
+//! semantic index, and saved queries, laid out as under `.noggin/`, plus a
+//! `bundle.toml` describing it. It can be attached to a release or handed
+//! to someone without repository access, who queries it with
+//! `noggin ask --bundle <file>`.
+
+use crate::commands::learn::print_planned;
+use crate::config::Config;
+use crate::storage::dry_run::plan_file;
+use crate::storage::{self, KnowledgeBackend};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use tempfile::TempDir;
+
+/// Describes the bundle; the first file in the archive
+pub const BUNDLE_INFO_FILE: &str = "bundle.toml";
+
+/// Version of the bundle layout
+pub const BUNDLE_FORMAT: u32 = 1;
+
+/// Where the manifest goes in a bundle
+const MANIFEST_FILE: &str = "manifest.toml";
+
+/// Local files under `.noggin/` that go into a bundle, when present
+const BUNDLED_FILES: &[&str] = &["index/embeddings.json", "queries.toml"];
+
+/// Options for `noggin bundle`
+#[derive(Debug, Clone)]
+pub struct BundleOptions {
+    /// File to write
+    pub output: PathBuf,
+    pub dry_run: bool,
+}
+
+/// Contents of `bundle.toml`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BundleInfo {
+    pub format: u32,
+    pub created_at: DateTime<Utc>,
+    pub noggin_version: String,
+    /// ARF files in the bundle
+    pub entries: usize,
+}
+
+pub fn bundle_command(opts: BundleOptions) -> Result<()> {
+    let noggin_path = env::current_dir()?.join(".noggin");
+    if !noggin_path.exists() {
+        anyhow::bail!("Not initialized. Run 'noggin init' first.");
+    }
+
+    let config = Config::load(&noggin_path).context("Failed to load config")?;
+    let backend = storage::open(&noggin_path, &config.storage);
+
+    if opts.dry_run {
+        let (info, bytes) = pack_bundle(&noggin_path, backend.as_ref())?;
+        let display = opts.output.display().to_string();
+        print_planned(&[plan_file(&opts.output, &display, &bytes)]);
+        println!("Would bundle {} entries into {}.", info.entries, display);
+        return Ok(());
+    }
+
+    let info = create_bundle(&noggin_path, backend.as_ref(), &opts.output)?;
+    println!(
+        "Bundled {} entries into {}. Query it with 'noggin ask --bundle {} <question>'.",
+        info.entries,
+        opts.output.display(),
+        opts.output.display()
+    );
+    Ok(())
+}
+
+/// Write the knowledge in `backend`, with the local files under
+/// `noggin_path`, to `output` as a bundle
+pub fn create_bundle(
+    noggin_path: &Path,
+    backend: &dyn KnowledgeBackend,
+    output: &Path,
+) -> Result<BundleInfo> {
+    let (info, bytes) = pack_bundle(noggin_path, backend)?;
+    if let Some(parent) = output.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+    fs::write(output, bytes).with_context(|| format!("Failed to write {}", output.display()))?;
+    Ok(info)
+}
+
+/// The knowledge in `backend` and local files under `noggin_path` as
+/// bundle bytes
+fn pack_bundle(
+    noggin_path: &Path,
+    backend: &dyn KnowledgeBackend,
+) -> Result<(BundleInfo, Vec<u8>)> {
+    let arfs = backend.list_arfs()?;
+    let info = BundleInfo {
+        format: BUNDLE_FORMAT,
+        created_at: Utc::now(),
+        noggin_version: env!("CARGO_PKG_VERSION").to_string(),
+        entries: arfs.len(),
+    };
+
+    let mut archive = tar::Builder::new(GzEncoder::new(Vec::new(), Compression::best()));
+
+    let mtime = info.created_at.timestamp().max(0) as u64;
+    let append = |archive: &mut tar::Builder<_>, name: &str, contents: &str| {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(contents.len() as u64);
+        header.set_mode(0o644);
+        header.set_mtime(mtime);
+        header.set_cksum();
+        archive
+            .append_data(&mut header, name, contents.as_bytes())
+            .with_context(|| format!("Failed to add {} to bundle", name))
+    };
+
+    let info_toml = toml::to_string_pretty(&info).context("Failed to serialize bundle info")?;
+    append(&mut archive, BUNDLE_INFO_FILE, &info_toml)?;
+
+    for rel in &arfs {
+        if let Some(contents) = backend.read(rel)? {
+            append(&mut archive, rel, &contents)?;
+        }
+    }
+    let manifest = toml::to_string_pretty(&backend.load_manifest()?)
+        .context("Failed to serialize manifest")?;
+    append(&mut archive, MANIFEST_FILE, &manifest)?;
+
+    let extras = BUNDLED_FILES
+        .iter()
+        .map(|rel| noggin_path.join(rel))
+        .filter(|path| path.is_file());
+    for path in extras {
+        let rel = path.strip_prefix(noggin_path).unwrap_or(&path);
+        archive
+            .append_path_with_name(&path, rel)
+            .with_context(|| format!("Failed to add {} to bundle", path.display()))?;
+    }
+
+    let bytes = archive
+        .into_inner()
+        .and_then(|encoder| encoder.finish())
+        .context("Failed to compress bundle")?;
+    Ok((info, bytes))
+}
+
+/// A bundle unpacked into a temporary directory, removed on drop
+pub struct OpenBundle {
+    dir: TempDir,
+    pub info: BundleInfo,
+}
+
+impl OpenBundle {
+    /// Directory laid out like `.noggin/`
+    pub fn path(&self) -> &Path {
+        self.dir.path()
+    }
+}
+
+/// Unpack the bundle at `path` for querying
+pub fn open_bundle(path: &Path) -> Result<OpenBundle> {
+    let file = File::open(path).with_context(|| format!("Failed to open bundle {}", path.display()))?;
+    let dir = TempDir::new().context("Failed to create a directory for the bundle")?;
+    // `unpack` refuses entries that would land outside `dir`
+    tar::Archive::new(GzDecoder::new(file))
+        .unpack(dir.path())
+        .with_context(|| format!("Failed to unpack bundle {}", path.display()))?;
+
+    let info_path = dir.path().join(BUNDLE_INFO_FILE);
+    let contents = fs::read_to_string(&info_path)
+        .with_context(|| format!("{} is not a noggin bundle", path.display()))?;
+    let info: BundleInfo = toml::from_str(&contents)
+        .with_context(|| format!("Failed to parse {} in {}", BUNDLE_INFO_FILE, path.display()))?;
+    if info.format > BUNDLE_FORMAT {
+        anyhow::bail!(
+            "{} uses bundle format {}; this noggin reads up to {}. Upgrade noggin.",
+            path.display(),
+            info.format,
+            BUNDLE_FORMAT
+        );
+    }
+
+    Ok(OpenBundle { dir, info })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::arf::ArfFile;
+    use crate::query::{QueryEngine, QueryOptions};
+    use crate::storage::FilesystemBackend;
+
+    #[test]
+    fn test_bundle_round_trip() {
+        let temp_dir = TempDir::new().unwrap();
+        let noggin_path = temp_dir.path().join(".noggin");
+        ArfFile::new("Use connection pooling", "Limits", "deadpool")
+            .to_toml(&noggin_path.join("decisions/pooling.arf"))
+            .unwrap();
+        ArfFile::new("Shard", "A partition", "See src/shard.rs")
+            .to_toml(&noggin_path.join("facts/glossary/shard.arf"))
+            .unwrap();
+        fs::write(noggin_path.join("manifest.toml"), "").unwrap();
+        fs::write(noggin_path.join("config.toml"), "[llm]\n").unwrap();
+
+        let output = temp_dir.path().join("dist/knowledge.tar.gz");
+        let backend = FilesystemBackend::new(&noggin_path);
+        let info = create_bundle(&noggin_path, &backend, &output).unwrap();
+        assert_eq!(info.entries, 2);
+
+        let bundle = open_bundle(&output).unwrap();
+        assert_eq!(bundle.info, info);
+        assert!(bundle.path().join("facts/glossary/shard.arf").exists());
+        assert!(bundle.path().join("manifest.toml").exists());
+        assert!(!bundle.path().join("config.toml").exists());
+
+        let engine = QueryEngine::new(bundle.path().to_path_buf());
+        let results = engine.search("pooling", &QueryOptions::default()).unwrap();
+        assert_eq!(results[0].file_path, "decisions/pooling.arf");
+    }
+
+    #[test]
+    fn test_open_rejects_other_archives() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("plain.tar.gz");
+        let file = File::create(&path).unwrap();
+        let mut archive = tar::Builder::new(GzEncoder::new(file, Compression::fast()));
+        let mut header = tar::Header::new_gnu();
+        header.set_size(2);
+        header.set_cksum();
+        archive.append_data(&mut header, "notes.txt", &b"hi"[..]).unwrap();
+        archive.into_inner().unwrap().finish().unwrap();
+
+        let err = open_bundle(&path).err().unwrap();
+        assert!(err.to_string().contains("is not a noggin bundle"));
+    }
+}
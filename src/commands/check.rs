@@ -0,0 +1,148 @@
+use crate::config::Config;
+use crate::learn::language::off_language_arfs;
+use crate::rules::{check_repository, load_ruled_arfs, Violation};
+use crate::schema::{check_knowledge_base, SchemaViolation};
+use crate::storage;
+use anyhow::{Context, Result};
+use colored::Colorize;
+use serde::Serialize;
+use std::env;
+
+/// Result of `noggin check`
+#[derive(Debug, Clone, Serialize)]
+pub struct CheckReport {
+    /// ARFs whose rules were evaluated
+    pub arfs_checked: Vec<String>,
+    pub rules_checked: usize,
+    pub violations: Vec<Violation>,
+    /// ARFs that don't match the schema; rules aren't evaluated when any exist
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub schema_errors: Vec<SchemaViolation>,
+    /// ARFs not written in `llm.output_language`; reported, not failed
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub off_language: Vec<String>,
+}
+
+/// Validate every ARF against the schema, then evaluate pattern rules
+/// against the codebase. Fails on any schema error or rule violation so
+/// it can gate CI.
+pub fn check_command(json: bool) -> Result<()> {
+    let repo_path = env::current_dir()?;
+    let noggin_path = repo_path.join(".noggin");
+
+    if !noggin_path.exists() {
+        anyhow::bail!("Not initialized. Run 'noggin init' first.");
+    }
+
+    let config = Config::load(&noggin_path).context("Failed to load config")?;
+    let backend = storage::open(&noggin_path, &config.storage);
+
+    let schema_errors = check_knowledge_base(&noggin_path, backend.as_ref())?;
+    if !schema_errors.is_empty() {
+        let report = CheckReport {
+            arfs_checked: Vec::new(),
+            rules_checked: 0,
+            violations: Vec::new(),
+            schema_errors,
+            off_language: Vec::new(),
+        };
+        if json {
+            println!("{}", serde_json::to_string_pretty(&report)?);
+        } else {
+            print_schema_errors(&report.schema_errors);
+        }
+        anyhow::bail!("{} schema error(s)", report.schema_errors.len());
+    }
+
+    let arfs = load_ruled_arfs(backend.as_ref())?;
+    let violations = check_repository(&repo_path, &arfs)?;
+
+    let off_language = off_language_arfs(backend.as_ref(), &config.llm.output_language)?;
+
+    let report = CheckReport {
+        arfs_checked: arfs.iter().map(|(path, _)| path.clone()).collect(),
+        rules_checked: arfs.iter().map(|(_, arf)| arf.rules.len()).sum(),
+        violations,
+        schema_errors: Vec::new(),
+        off_language,
+    };
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        print_report(&report);
+        print_off_language(&report.off_language, &config.llm.output_language);
+    }
+
+    if !report.violations.is_empty() {
+        anyhow::bail!("{} rule violation(s)", report.violations.len());
+    }
+
+    Ok(())
+}
+
+fn print_schema_errors(errors: &[SchemaViolation]) {
+    println!("ARF files that don't match the schema (see 'noggin schema'):\n");
+
+    let mut current = "";
+    for error in errors {
+        if error.arf != current {
+            current = &error.arf;
+            println!("{}", error.arf.bold());
+        }
+        if error.field.is_empty() {
+            println!("  {}", error.message);
+        } else {
+            println!("  {} {}", error.field.red(), error.message);
+        }
+    }
+    println!();
+}
+
+fn print_off_language(paths: &[String], language: &str) {
+    if paths.is_empty() {
+        return;
+    }
+    println!(
+        "{} {} ARF(s) don't appear to be written in {}:",
+        "warning:".yellow(),
+        paths.len(),
+        language
+    );
+    for path in paths {
+        println!("  {}", path);
+    }
+    println!("Run 'noggin translate' to rewrite them in {}.\n", language);
+}
+
+fn print_report(report: &CheckReport) {
+    if report.arfs_checked.is_empty() {
+        println!("No rules defined. Add a [[rules]] section to a pattern to enable checks.");
+        return;
+    }
+
+    println!(
+        "Checked {} rules from {} patterns\n",
+        report.rules_checked,
+        report.arfs_checked.len()
+    );
+
+    let mut current = "";
+    for violation in &report.violations {
+        if violation.arf != current {
+            current = &violation.arf;
+            println!("{} {}", violation.what.bold(), format!("({})", violation.arf).dimmed());
+        }
+        let location = match violation.line {
+            Some(line) => format!("{}:{}", violation.file, line),
+            None => violation.file.clone(),
+        };
+        println!("  {} {}", location.red(), violation.message);
+    }
+
+    if report.violations.is_empty() {
+        println!("{}", "No violations.".green());
+    } else {
+        println!();
+    }
+}
@@ -0,0 +1,339 @@
+//! `noggin ci`: unattended verify/learn for CI pipelines
+//!
+//! Emits one JSON object per line on stdout instead of spinners, writes the
+//! run report, drift report, and new ARFs to an output directory for
+//! artifact upload, and can commit and push knowledge updates back.
+
+use crate::arf::ArfFile;
+use crate::commands::learn::{run_learn, LearnOptions, LearnReport};
+use crate::config::Config;
+use crate::git::publish::{commit_paths, push_with_token};
+use crate::learn::writer::CATEGORY_DIRS;
+use crate::storage::{self, KnowledgeBackend};
+use anyhow::{Context, Result};
+use chrono::Utc;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::env;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Commit message used when publishing knowledge updates
+const COMMIT_MESSAGE: &str = "noggin: update knowledge base";
+
+/// What the CI run should do
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum CiMode {
+    /// Fail if the knowledge base is out of date; never writes
+    Verify,
+    /// Run an incremental learn and publish the results
+    Learn,
+}
+
+/// Options for `noggin ci`
+#[derive(Debug, Clone)]
+pub struct CiOptions {
+    pub mode: CiMode,
+    /// Directory for report.json, drift-report.json, and arfs.json
+    pub output_dir: PathBuf,
+    /// Commit knowledge and manifest changes after a learn run
+    pub commit: bool,
+    /// Push the knowledge commit (implies `commit`)
+    pub push: bool,
+    /// Environment variable holding the push token
+    pub token_env: String,
+    pub remote: String,
+    /// Branch to push to (defaults to the current branch)
+    pub branch: Option<String>,
+    pub max_cost: Option<f64>,
+    pub max_time: Option<u64>,
+}
+
+impl Default for CiOptions {
+    fn default() -> Self {
+        Self {
+            mode: CiMode::Verify,
+            output_dir: PathBuf::from("noggin-output"),
+            commit: false,
+            push: false,
+            token_env: "GITHUB_TOKEN".to_string(),
+            remote: "origin".to_string(),
+            branch: None,
+            max_cost: None,
+            max_time: None,
+        }
+    }
+}
+
+/// An ARF written during the run, as uploaded in arfs.json
+#[derive(Debug, Serialize)]
+struct WrittenArf {
+    path: String,
+    arf: ArfFile,
+}
+
+pub async fn ci_command(opts: CiOptions) -> Result<()> {
+    let repo_path = env::current_dir()?;
+    let result = run_ci(&repo_path, &opts).await;
+
+    if let Err(e) = &result {
+        log_event("error", "failed", json!({ "error": format!("{:#}", e) }));
+    }
+
+    result
+}
+
+async fn run_ci(repo_path: &Path, opts: &CiOptions) -> Result<()> {
+    let verify = opts.mode == CiMode::Verify;
+    log_event(
+        "info",
+        "start",
+        json!({ "mode": if verify { "verify" } else { "learn" } }),
+    );
+
+    let learn_opts = LearnOptions {
+        full: false,
+        verify,
+        max_cost: opts.max_cost,
+        max_time: opts.max_time,
+        quiet: true,
+        ..Default::default()
+    };
+    let report = run_learn(repo_path, &learn_opts).await?;
+    let noggin_path = repo_path.join(".noggin");
+    let config = Config::load(&noggin_path).context("Failed to load config")?;
+    let backend = storage::open(&noggin_path, &config.storage);
+
+    let output_dir = repo_path.join(&opts.output_dir);
+    let written = write_outputs(backend.as_ref(), &output_dir, &report)?;
+    log_event(
+        "info",
+        "outputs_written",
+        json!({ "dir": output_dir.display().to_string(), "files": written }),
+    );
+
+    for conflict in &report.conflicts {
+        log_event("warn", "conflict", json!({ "path": conflict }));
+    }
+
+    for truncation in &report.truncation {
+        log_event("warn", "prompt_truncated", json!(truncation));
+    }
+
+    for warning in &report.warnings {
+        log_event("warn", "warning", json!({ "message": warning }));
+    }
+
+    let mut commit_hash = None;
+    if !verify && (opts.commit || opts.push) {
+        let paths = knowledge_paths();
+        let paths: Vec<&str> = paths.iter().map(String::as_str).collect();
+        commit_hash = commit_paths(repo_path, &paths, COMMIT_MESSAGE)?;
+        match &commit_hash {
+            Some(hash) => log_event("info", "committed", json!({ "commit": hash })),
+            None => log_event("info", "nothing_to_commit", json!({})),
+        }
+
+        if opts.push && commit_hash.is_some() {
+            let token = env::var(&opts.token_env).ok().filter(|t| !t.is_empty());
+            if token.is_none() {
+                log_event(
+                    "warn",
+                    "token_missing",
+                    json!({ "env": opts.token_env, "message": "pushing without credentials" }),
+                );
+            }
+            push_with_token(
+                repo_path,
+                &opts.remote,
+                opts.branch.as_deref(),
+                &[config.notes.notes_ref.as_str()],
+                token.as_deref(),
+            )?;
+            log_event("info", "pushed", json!({ "remote": opts.remote }));
+        }
+    }
+
+    write_github_outputs(&output_dir, &report, commit_hash.as_deref())?;
+
+    log_event(
+        "info",
+        "finish",
+        json!({
+            "up_to_date": report.up_to_date,
+            "drift": report.drift.is_some(),
+            "arfs_written": report.arfs_written.len(),
+            "conflicts": report.conflicts.len(),
+            "requests": report.usage.requests,
+            "tokens": report.usage.tokens,
+            "estimated_cost_usd": report.usage.estimated_cost_usd,
+            "providers": report.usage.providers,
+            "deferred": report.deferred.is_some(),
+        }),
+    );
+
+    if report.drift.is_some() {
+        anyhow::bail!("Drift detected. Run 'noggin learn' to update.");
+    }
+
+    Ok(())
+}
+
+/// Write report files for artifact upload, returns the file names written
+/// What `--commit` publishes: the category directories and the manifest.
+/// Local state under .noggin/ (raw responses, caches, logs, the lock) and
+/// config.toml, which can hold API keys, are never committed.
+fn knowledge_paths() -> Vec<String> {
+    CATEGORY_DIRS
+        .iter()
+        .map(|dir| format!(".noggin/{}", dir))
+        .chain([".noggin/manifest.toml".to_string()])
+        .collect()
+}
+
+fn write_outputs(
+    backend: &dyn KnowledgeBackend,
+    output_dir: &Path,
+    report: &LearnReport,
+) -> Result<Vec<String>> {
+    fs::create_dir_all(output_dir)
+        .with_context(|| format!("Failed to create output directory {}", output_dir.display()))?;
+
+    let mut written = vec!["report.json".to_string()];
+    write_json(&output_dir.join("report.json"), report)?;
+
+    if let Some(drift) = &report.drift {
+        write_json(&output_dir.join("drift-report.json"), drift)?;
+        written.push("drift-report.json".to_string());
+    }
+
+    if !report.arfs_written.is_empty() {
+        let arfs = report
+            .arfs_written
+            .iter()
+            .map(|path| {
+                let arf = backend
+                    .read_arf(path)?
+                    .with_context(|| format!("Written ARF {} is missing", path))?;
+                Ok(WrittenArf { path: path.clone(), arf })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        write_json(&output_dir.join("arfs.json"), &arfs)?;
+        written.push("arfs.json".to_string());
+    }
+
+    Ok(written)
+}
+
+/// Append step outputs to `$GITHUB_OUTPUT` when running under GitHub Actions
+fn write_github_outputs(output_dir: &Path, report: &LearnReport, commit: Option<&str>) -> Result<()> {
+    let Ok(path) = env::var("GITHUB_OUTPUT") else {
+        return Ok(());
+    };
+
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .with_context(|| format!("Failed to open GITHUB_OUTPUT file {}", path))?;
+
+    writeln!(file, "drift={}", report.drift.is_some())?;
+    writeln!(file, "up_to_date={}", report.up_to_date)?;
+    writeln!(file, "arfs_written={}", report.arfs_written.len())?;
+    writeln!(file, "conflicts={}", report.conflicts.len())?;
+    writeln!(file, "report={}", output_dir.join("report.json").display())?;
+    writeln!(file, "commit={}", commit.unwrap_or(""))?;
+
+    Ok(())
+}
+
+fn write_json<T: Serialize + ?Sized>(path: &Path, value: &T) -> Result<()> {
+    let contents = serde_json::to_string_pretty(value)?;
+    fs::write(path, contents).with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// Print one JSON log line to stdout
+fn log_event(level: &str, event: &str, fields: Value) {
+    println!("{}", log_line(level, event, fields));
+}
+
+fn log_line(level: &str, event: &str, fields: Value) -> String {
+    let mut line = json!({
+        "ts": Utc::now().to_rfc3339(),
+        "level": level,
+        "event": event,
+    });
+    if let (Some(line), Value::Object(fields)) = (line.as_object_mut(), fields) {
+        line.extend(fields);
+    }
+    line.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::MemoryBackend;
+    use crate::commands::learn::{DriftFile, DriftReport};
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_log_line_merges_fields() {
+        let line = log_line("info", "committed", json!({ "commit": "abc123" }));
+        let parsed: Value = serde_json::from_str(&line).unwrap();
+
+        assert_eq!(parsed["level"], "info");
+        assert_eq!(parsed["event"], "committed");
+        assert_eq!(parsed["commit"], "abc123");
+        assert!(parsed["ts"].is_string());
+    }
+
+    #[test]
+    fn test_write_outputs_with_drift() {
+        let temp_dir = TempDir::new().unwrap();
+        let output_dir = temp_dir.path().join("out");
+        let report = LearnReport {
+            mode: "incremental".to_string(),
+            drift: Some(DriftReport {
+                changed_files: vec![DriftFile {
+                    path: "src/main.rs".to_string(),
+                    status: "modified".to_string(),
+                    generated: false,
+                }],
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        let backend = MemoryBackend::default();
+        let written = write_outputs(&backend, &output_dir, &report).unwrap();
+        assert_eq!(written, vec!["report.json", "drift-report.json"]);
+
+        let drift: Value =
+            serde_json::from_str(&fs::read_to_string(output_dir.join("drift-report.json")).unwrap())
+                .unwrap();
+        assert_eq!(drift["changed_files"][0]["path"], "src/main.rs");
+    }
+
+    #[test]
+    fn test_write_outputs_includes_written_arfs() {
+        let temp_dir = TempDir::new().unwrap();
+        let backend = MemoryBackend::default();
+        let arf = ArfFile::new("Uses tokio", "Async runtime", "tokio::main");
+        backend.write_arf("facts/uses-tokio.arf", &arf).unwrap();
+
+        let report = LearnReport {
+            arfs_written: vec!["facts/uses-tokio.arf".to_string()],
+            ..Default::default()
+        };
+
+        let output_dir = temp_dir.path().join("out");
+        let written = write_outputs(&backend, &output_dir, &report).unwrap();
+        assert!(written.contains(&"arfs.json".to_string()));
+
+        let arfs: Value =
+            serde_json::from_str(&fs::read_to_string(output_dir.join("arfs.json")).unwrap()).unwrap();
+        assert_eq!(arfs[0]["path"], "facts/uses-tokio.arf");
+        assert_eq!(arfs[0]["arf"]["what"], "Uses tokio");
+    }
+}
@@ -0,0 +1,218 @@
+//! `noggin conflicts explain <id>`: replay the vote behind a conflict
+//!
+//! Shows each model's value and weight, how values were grouped once
+//! normalized, and why voting ended where it did (see
+//! [`crate::synthesis::vote::explain_conflict`]). `--weight model=W`
+//! replays it with different weights, to see whether a change would have
+//! settled it. Nothing is written.
+
+use crate::conflicts::{list_conflicts, Candidate, Conflict};
+use crate::synthesis::conflict::{ConflictKind, FieldConflict};
+use crate::synthesis::vote::{explain_conflict, model_weight, VoteExplanation, MAJORITY_SCORE};
+use anyhow::{Context, Result};
+use colored::Colorize;
+use serde::Serialize;
+use std::env;
+use std::path::Path;
+
+/// Options for `noggin conflicts explain`
+#[derive(Debug, Clone, Default)]
+pub struct ConflictsExplainOptions {
+    /// Conflict ID, or its path under .noggin/
+    pub id: String,
+    /// `model=weight` overrides for the replay
+    pub weights: Vec<String>,
+    pub json: bool,
+}
+
+/// A replayed conflict
+#[derive(Debug, Clone, Serialize)]
+pub struct ConflictExplanation {
+    pub id: String,
+    pub arf_path: String,
+    /// Votes replayed per field; empty for approval conflicts
+    pub votes: Vec<VoteExplanation>,
+    /// Candidates per field of an approval conflict, which is never voted on
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub approval: Vec<ApprovalField>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ApprovalField {
+    pub field: String,
+    pub candidates: Vec<Candidate>,
+}
+
+pub fn conflicts_explain_command(opts: ConflictsExplainOptions) -> Result<()> {
+    let repo_path = env::current_dir()?;
+    let explanation = run_explain(&repo_path, &opts)?;
+
+    if opts.json {
+        println!("{}", serde_json::to_string_pretty(&explanation)?);
+        return Ok(());
+    }
+
+    println!(
+        "{} {}",
+        explanation.id.bold(),
+        format!("(.noggin/{})", explanation.arf_path).dimmed()
+    );
+    for field in &explanation.approval {
+        println!("\n  {}", field.field.cyan());
+        for candidate in &field.candidates {
+            println!("    {} {}", format!("[{}]", candidate.source).dimmed(), candidate.value);
+        }
+    }
+    if !explanation.approval.is_empty() {
+        println!(
+            "\n  The proposal contradicts an approved entry. Approved entries are never\n  \
+             overwritten by voting; run 'noggin resolve {}' to choose.",
+            explanation.id
+        );
+    }
+
+    for vote in &explanation.votes {
+        println!("\n  {}", vote.field.cyan());
+        for (i, group) in vote.groups.iter().enumerate() {
+            let voters: Vec<String> = group
+                .voters
+                .iter()
+                .map(|v| format!("{} {:.1}", v.model, v.weight))
+                .collect();
+            println!(
+                "    {}) {:.1}  {} {}",
+                i + 1,
+                group.score,
+                group.value,
+                format!("[{}]", voters.join(" + ")).dimmed()
+            );
+        }
+        println!("    {} {}", "→".bold(), vote.reason);
+    }
+    if !explanation.votes.is_empty() {
+        println!(
+            "\n  Values are compared trimmed and lowercased; a value needs a score of {:.1} to win.",
+            MAJORITY_SCORE
+        );
+    }
+    Ok(())
+}
+
+/// Replay the conflict `opts.id` in the knowledge base in `repo_path`
+pub fn run_explain(repo_path: &Path, opts: &ConflictsExplainOptions) -> Result<ConflictExplanation> {
+    let noggin_path = repo_path.join(".noggin");
+    if !noggin_path.exists() {
+        anyhow::bail!("Not initialized. Run 'noggin init' first.");
+    }
+    let overrides = opts
+        .weights
+        .iter()
+        .map(|w| parse_weight(w))
+        .collect::<Result<Vec<_>>>()?;
+    let weight = |model: &str| {
+        overrides
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(model))
+            .map_or_else(|| model_weight(model), |(_, w)| *w)
+    };
+
+    let conflict = list_conflicts(&noggin_path)?
+        .into_iter()
+        .find(|c| c.id() == opts.id || c.relative_path() == opts.id)
+        .with_context(|| {
+            format!("No open conflict '{}'. Run 'noggin resolve --list'.", opts.id)
+        })?;
+
+    let mut explanation = ConflictExplanation {
+        id: conflict.id().to_string(),
+        arf_path: conflict.arf_path().to_string(),
+        votes: Vec::new(),
+        approval: Vec::new(),
+    };
+    match &conflict {
+        Conflict::Vote(vote) => {
+            let field = FieldConflict {
+                field: vote.field.clone(),
+                kind: ConflictKind::DifferentValues,
+                values: vote
+                    .candidates
+                    .iter()
+                    .map(|c| (c.source.clone(), c.value.clone()))
+                    .collect(),
+                resolution: None,
+            };
+            explanation.votes.push(explain_conflict(&field, weight));
+        }
+        Conflict::Approval(_) => {
+            explanation.approval = conflict
+                .choices()
+                .into_iter()
+                .map(|(field, candidates)| ApprovalField { field, candidates })
+                .collect();
+        }
+    }
+    Ok(explanation)
+}
+
+/// Parse a `model=weight` override
+fn parse_weight(spec: &str) -> Result<(String, f64)> {
+    let (model, weight) = spec
+        .split_once('=')
+        .with_context(|| format!("Invalid weight '{}': expected model=weight", spec))?;
+    let weight: f64 = weight
+        .trim()
+        .parse()
+        .with_context(|| format!("Invalid weight '{}': not a number", spec))?;
+    if !weight.is_finite() || weight < 0.0 {
+        anyhow::bail!("Invalid weight '{}': must be zero or more", spec);
+    }
+    Ok((model.trim().to_string(), weight))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::conflicts::VoteConflict;
+    use crate::synthesis::vote::Resolution;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_explain_replays_vote_with_overrides() {
+        let temp_dir = TempDir::new().unwrap();
+        let noggin_path = temp_dir.path().join(".noggin");
+        VoteConflict::new(
+            "decisions/pool.arf",
+            "how",
+            vec![
+                ("claude".to_string(), "deadpool".to_string()),
+                ("gemini".to_string(), "bb8".to_string()),
+            ],
+        )
+        .save(&noggin_path)
+        .unwrap();
+
+        let mut opts = ConflictsExplainOptions {
+            id: "pool.how".to_string(),
+            ..Default::default()
+        };
+        let explanation = run_explain(temp_dir.path(), &opts).unwrap();
+        assert_eq!(explanation.arf_path, "decisions/pool.arf");
+        assert!(matches!(
+            &explanation.votes[0].resolution,
+            Resolution::HighestWeight { model, .. } if model == "claude"
+        ));
+
+        opts.weights = vec!["Gemini=2".to_string()];
+        let explanation = run_explain(temp_dir.path(), &opts).unwrap();
+        assert!(matches!(
+            &explanation.votes[0].resolution,
+            Resolution::MajorityVote { winner, .. } if winner == "bb8"
+        ));
+
+        opts.weights = vec!["gemini".to_string()];
+        assert!(run_explain(temp_dir.path(), &opts).is_err());
+        opts.id = "missing".to_string();
+        opts.weights.clear();
+        assert!(run_explain(temp_dir.path(), &opts).is_err());
+    }
+}
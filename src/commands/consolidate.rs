@@ -0,0 +1,320 @@
+//! `noggin consolidate <category>`: propose merges of near-duplicate entries
+//!
+//! Clusters a category's ARFs with the synthesis similarity grouping (at
+//! the wider `size.merge_distance`) and merges each cluster with the same
+//! field rules used for model consensus. Proposals are printed for
+//! review; `--apply` writes each merged entry over the cluster's first
+//! file, removes the rest, and points manifest commits derived from them
+//! at the kept file. Clusters containing an approved entry are shown but
+//! never applied.
+
+use crate::arf::ArfFile;
+use crate::config::{Config, SizeConfig};
+use crate::learn::writer::CATEGORY_DIRS;
+use crate::lock::KnowledgeLock;
+use crate::storage::{self, in_dir, KnowledgeBackend};
+use crate::synthesis::merger::{group_by_similarity_within, merge_arf_fields};
+use anyhow::{Context, Result};
+use colored::Colorize;
+use serde::Serialize;
+use std::env;
+
+/// Options for `noggin consolidate`
+#[derive(Debug, Clone, Default)]
+pub struct ConsolidateOptions {
+    pub category: String,
+    /// Write the merges instead of only proposing them
+    pub apply: bool,
+    pub json: bool,
+}
+
+/// Proposed merges for one category
+#[derive(Debug, Clone, Serialize)]
+pub struct ConsolidateReport {
+    pub category: String,
+    pub entries: usize,
+    /// Entry budget from `size` config
+    pub limit: usize,
+    pub proposals: Vec<MergeProposal>,
+    /// Proposals written by `--apply`
+    pub applied: usize,
+}
+
+/// A cluster of similar entries and the entry that would replace them
+#[derive(Debug, Clone, Serialize)]
+pub struct MergeProposal {
+    /// Paths relative to .noggin/, the first of which is kept
+    pub sources: Vec<String>,
+    pub merged: ArfFile,
+    /// An approved entry is in the cluster, so it needs a person to merge
+    pub blocked_by_approval: bool,
+}
+
+impl MergeProposal {
+    /// File the merged entry is written to
+    pub fn target(&self) -> &str {
+        &self.sources[0]
+    }
+}
+
+pub fn consolidate_command(opts: ConsolidateOptions) -> Result<()> {
+    let noggin_path = env::current_dir()?.join(".noggin");
+    if !noggin_path.exists() {
+        anyhow::bail!("Not initialized. Run 'noggin init' first.");
+    }
+
+    let config = Config::load(&noggin_path).context("Failed to load config")?;
+    let backend = storage::open(&noggin_path, &config.storage);
+    let mut report = plan_consolidation(backend.as_ref(), &opts.category, &config.size)?;
+    if opts.apply {
+        let _lock = KnowledgeLock::acquire(&noggin_path, "consolidate")?;
+        report.applied = apply_proposals(backend.as_ref(), &report.proposals)?;
+    }
+
+    if opts.json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+        return Ok(());
+    }
+
+    println!(
+        "{} {} entries (budget {})",
+        report.category.bold(),
+        report.entries,
+        report.limit
+    );
+    if report.proposals.is_empty() {
+        println!("No similar entries to merge.");
+        return Ok(());
+    }
+    println!();
+
+    for proposal in &report.proposals {
+        println!("{} {}", "Merge into".bold(), proposal.merged.what.cyan());
+        for source in &proposal.sources {
+            println!("  .noggin/{}", source.dimmed());
+        }
+        if proposal.blocked_by_approval {
+            println!("  {}", "contains an approved entry; merge by hand".yellow());
+        }
+        println!();
+    }
+
+    let removable: usize = report
+        .proposals
+        .iter()
+        .filter(|p| !p.blocked_by_approval)
+        .map(|p| p.sources.len() - 1)
+        .sum();
+    if opts.apply {
+        println!("Applied {} merges.", report.applied);
+    } else {
+        println!(
+            "{} merges would remove {} entries. Run {} to write them.",
+            report.proposals.len(),
+            removable,
+            format!("'noggin consolidate {} --apply'", report.category).cyan()
+        );
+    }
+
+    Ok(())
+}
+
+/// Cluster `category`'s entries and propose a merge for every cluster
+/// with more than one member
+pub fn plan_consolidation(
+    backend: &dyn KnowledgeBackend,
+    category: &str,
+    size: &SizeConfig,
+) -> Result<ConsolidateReport> {
+    if !CATEGORY_DIRS.contains(&category) {
+        anyhow::bail!(
+            "Unknown category '{}'. Expected one of: {}",
+            category,
+            CATEGORY_DIRS.join(", ")
+        );
+    }
+
+    let entries = load_category(backend, category)?;
+    let clusters = group_by_similarity_within(&entries, size.merge_distance);
+
+    let proposals = clusters
+        .into_iter()
+        .filter(|cluster| cluster.len() > 1)
+        .map(|cluster| {
+            let (mut merged, _) = merge_arf_fields(&cluster);
+            for (_, arf) in &cluster {
+                for rule in &arf.rules {
+                    if !merged.rules.contains(rule) {
+                        merged.rules.push(rule.clone());
+                    }
+                }
+            }
+            MergeProposal {
+                blocked_by_approval: cluster.iter().any(|(_, arf)| arf.approved),
+                sources: cluster.into_iter().map(|(path, _)| path).collect(),
+                merged,
+            }
+        })
+        .collect();
+
+    Ok(ConsolidateReport {
+        category: category.to_string(),
+        entries: entries.len(),
+        limit: size.limit(category),
+        proposals,
+        applied: 0,
+    })
+}
+
+/// Write unblocked proposals: the merged entry replaces the first source
+/// and the other sources are deleted. Returns how many were applied.
+pub fn apply_proposals(
+    backend: &dyn KnowledgeBackend,
+    proposals: &[MergeProposal],
+) -> Result<usize> {
+    let mut manifest = backend.load_manifest().context("Failed to load manifest")?;
+    let mut repointed = 0;
+    let mut applied = 0;
+    for proposal in proposals.iter().filter(|p| !p.blocked_by_approval) {
+        backend.write_arf(proposal.target(), &proposal.merged)?;
+        for source in &proposal.sources[1..] {
+            backend
+                .remove(source)
+                .with_context(|| format!("Failed to remove {}", source))?;
+            repointed += manifest.repoint_arf(source, proposal.target());
+        }
+        applied += 1;
+    }
+    if repointed > 0 {
+        backend.save_manifest(&manifest)?;
+    }
+    Ok(applied)
+}
+
+/// Every readable ARF in `category`, keyed by path relative to .noggin/,
+/// in path order
+fn load_category(
+    backend: &dyn KnowledgeBackend,
+    category: &str,
+) -> Result<Vec<(String, ArfFile)>> {
+    let mut entries = Vec::new();
+    for path in backend.list_arfs()? {
+        if !in_dir(&path, category) {
+            continue;
+        }
+        if let Ok(Some(arf)) = backend.read_arf(&path) {
+            entries.push((path, arf));
+        }
+    }
+    Ok(entries)
+}
+
+/// Entry count of every category over its budget, as
+/// `(category, entries, limit)`
+pub fn over_budget(
+    backend: &dyn KnowledgeBackend,
+    size: &SizeConfig,
+) -> Result<Vec<(String, usize, usize)>> {
+    let paths = backend.list_arfs()?;
+    Ok(CATEGORY_DIRS
+        .iter()
+        .filter_map(|category| {
+            let count = paths.iter().filter(|path| in_dir(path, category)).count();
+            let limit = size.limit(category);
+            (count > limit).then(|| (category.to_string(), count, limit))
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::manifest::{CommitCategory, Manifest};
+    use crate::storage::MemoryBackend;
+
+    fn write(backend: &MemoryBackend, path: &str, arf: &ArfFile) {
+        backend.write_arf(path, arf).unwrap();
+    }
+
+    #[test]
+    fn test_plan_and_apply_merges_similar_entries() {
+        let backend = MemoryBackend::default();
+        let mut first = ArfFile::new("Retry failed payments", "Card networks flake", "Backoff");
+        first.add_file("billing/retry.rs");
+        let mut second = ArfFile::new("Retry failed payment charges", "Transient errors", "Backoff");
+        second.add_file("billing/charge.rs");
+        write(&backend, "patterns/retry-failed-payments.arf", &first);
+        write(&backend, "patterns/retry-failed-payment-charges.arf", &second);
+        write(
+            &backend,
+            "patterns/use-structured-logging.arf",
+            &ArfFile::new("Use structured logging", "Searchable", "tracing"),
+        );
+
+        let report = plan_consolidation(&backend, "patterns", &SizeConfig::default()).unwrap();
+        assert_eq!(report.entries, 3);
+        assert_eq!(report.proposals.len(), 1);
+        let proposal = &report.proposals[0];
+        assert_eq!(proposal.target(), "patterns/retry-failed-payment-charges.arf");
+        assert_eq!(proposal.merged.context.files.len(), 2);
+
+        let mut manifest = Manifest::default();
+        manifest.add_commit(
+            "a1b2c3d".to_string(),
+            CommitCategory::Decision,
+            "patterns/retry-failed-payments.arf".to_string(),
+        );
+        manifest.add_commit(
+            "e4f5a6b".to_string(),
+            CommitCategory::Decision,
+            "patterns/use-structured-logging.arf".to_string(),
+        );
+        backend.save_manifest(&manifest).unwrap();
+
+        assert_eq!(apply_proposals(&backend, &report.proposals).unwrap(), 1);
+        assert!(!backend.exists("patterns/retry-failed-payments.arf"));
+        let merged = backend.read_arf(proposal.target()).unwrap().unwrap();
+        assert_eq!(merged.context.files.len(), 2);
+
+        let manifest = backend.load_manifest().unwrap();
+        assert_eq!(manifest.commits["a1b2c3d"].arf_path, proposal.target());
+        assert_eq!(
+            manifest.commits["e4f5a6b"].arf_path,
+            "patterns/use-structured-logging.arf"
+        );
+    }
+
+    #[test]
+    fn test_approved_cluster_is_not_applied() {
+        let backend = MemoryBackend::default();
+        let mut approved = ArfFile::new("Retry failed payments", "Why", "How");
+        approved.approved = true;
+        write(&backend, "bugs/a.arf", &approved);
+        write(&backend, "bugs/b.arf", &ArfFile::new("Retry failed payment", "Why", "How"));
+
+        let report = plan_consolidation(&backend, "bugs", &SizeConfig::default()).unwrap();
+        assert!(report.proposals[0].blocked_by_approval);
+        assert_eq!(apply_proposals(&backend, &report.proposals).unwrap(), 0);
+        assert!(backend.exists("bugs/b.arf"));
+    }
+
+    #[test]
+    fn test_over_budget() {
+        let backend = MemoryBackend::default();
+        for i in 0..3 {
+            let fact = ArfFile::new(format!("Fact {}", i), "w", "h");
+            write(&backend, &format!("facts/f{}.arf", i), &fact);
+        }
+        let mut size = SizeConfig::default();
+        assert!(over_budget(&backend, &size).unwrap().is_empty());
+
+        size.categories.insert("facts".to_string(), 2);
+        assert_eq!(over_budget(&backend, &size).unwrap(), vec![("facts".to_string(), 3, 2)]);
+    }
+
+    #[test]
+    fn test_unknown_category() {
+        let backend = MemoryBackend::default();
+        assert!(plan_consolidation(&backend, "widgets", &SizeConfig::default()).is_err());
+    }
+}
@@ -0,0 +1,337 @@
+//! `noggin coverage`: how much of the significant history became knowledge
+//!
+//! Learn records every significant commit it processes in the manifest,
+//! with the ARF derived from it (`arf_path`, empty when none was). This
+//! report groups those commits by the directories they touched and shows
+//! the share whose ARF still exists. Commits compacted to bare SHAs have
+//! no `arf_path`; they count as covered when an ARF cites them.
+//! Directories where no significant commit produced durable knowledge are
+//! "dark": good targets for a backfill. Significant commits learn hasn't
+//! processed yet are not counted.
+
+use crate::learn::lite::touched_paths;
+use crate::config::Config;
+use crate::storage::{self, KnowledgeBackend};
+use crate::text::short_hash;
+use anyhow::{Context, Result};
+use colored::Colorize;
+use git2::{Oid, Repository};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::env;
+use std::path::Path;
+
+/// Uncovered commits listed per dark directory in the text report
+const MAX_LISTED_COMMITS: usize = 5;
+
+/// Options for `noggin coverage`
+#[derive(Debug, Clone)]
+pub struct CoverageOptions {
+    /// Path components that make up a directory (1 groups by top level)
+    pub depth: usize,
+    pub json: bool,
+}
+
+/// Knowledge coverage of processed significant commits
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CoverageReport {
+    pub depth: usize,
+    /// Significant commits in the manifest that are still in the repository
+    pub commits: usize,
+    /// Those whose ARF still exists
+    pub covered: usize,
+    /// Manifest commits no longer in the repository (e.g. after a rebase)
+    pub missing: usize,
+    /// Least covered first
+    pub directories: Vec<DirectoryCoverage>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DirectoryCoverage {
+    /// Directory path, or "." for files at the repository root
+    pub directory: String,
+    pub significant: usize,
+    pub covered: usize,
+    pub coverage_pct: f64,
+    /// No significant commit here produced durable knowledge
+    pub dark: bool,
+    /// Short hashes of commits without knowledge, oldest first
+    pub uncovered: Vec<String>,
+}
+
+/// Running totals for one directory
+#[derive(Default)]
+struct DirectoryStats {
+    significant: usize,
+    covered: usize,
+    /// (commit time, short hash)
+    uncovered: Vec<(i64, String)>,
+}
+
+pub fn coverage_command(opts: CoverageOptions) -> Result<()> {
+    let repo_path = env::current_dir()?;
+    let noggin_path = repo_path.join(".noggin");
+    if !noggin_path.exists() {
+        anyhow::bail!(".noggin/ directory not found. Run 'noggin init' first.");
+    }
+
+    let config = Config::load(&noggin_path).context("Failed to load config")?;
+    let backend = storage::open(&noggin_path, &config.storage);
+    let report = coverage(&repo_path, backend.as_ref(), opts.depth)?;
+    if opts.json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        print_report(&report);
+    }
+    Ok(())
+}
+
+/// Build the coverage report for the repository at `repo_path` from the
+/// knowledge in `backend`
+pub fn coverage(
+    repo_path: &Path,
+    backend: &dyn KnowledgeBackend,
+    depth: usize,
+) -> Result<CoverageReport> {
+    let repo = Repository::open(repo_path).context("Failed to open git repository")?;
+    let manifest = backend.load_manifest()?;
+    let depth = depth.max(1);
+
+    let mut report = CoverageReport {
+        depth,
+        ..Default::default()
+    };
+    let mut by_directory: BTreeMap<String, DirectoryStats> = BTreeMap::new();
+
+    let cited = cited_commits(backend);
+    let processed = manifest
+        .commits
+        .values()
+        .map(|entry| {
+            let covered = !entry.arf_path.is_empty() && backend.exists(&entry.arf_path);
+            (&entry.sha, covered)
+        })
+        .chain(manifest.compacted_commits.iter().map(|sha| {
+            let covered = cited.iter().any(|c| c.len() >= 7 && sha.starts_with(c.as_str()));
+            (sha, covered)
+        }));
+    for (sha, covered) in processed {
+        let commit = match Oid::from_str(sha).and_then(|oid| repo.find_commit(oid)) {
+            Ok(commit) => commit,
+            Err(_) => {
+                report.missing += 1;
+                continue;
+            }
+        };
+        report.commits += 1;
+        if covered {
+            report.covered += 1;
+        }
+
+        let mut directories: Vec<String> = touched_paths(&repo, &commit)?
+            .iter()
+            .map(|path| directory(path, depth))
+            .collect();
+        directories.sort();
+        directories.dedup();
+
+        for dir in directories {
+            let stats = by_directory.entry(dir).or_default();
+            stats.significant += 1;
+            if covered {
+                stats.covered += 1;
+            } else {
+                stats.uncovered.push((commit.time().seconds(), short_hash(sha).to_string()));
+            }
+        }
+    }
+
+    report.directories = by_directory
+        .into_iter()
+        .map(|(directory, mut stats)| {
+            stats.uncovered.sort();
+            DirectoryCoverage {
+                directory,
+                significant: stats.significant,
+                covered: stats.covered,
+                coverage_pct: stats.covered as f64 / stats.significant as f64 * 100.0,
+                dark: stats.covered == 0,
+                uncovered: stats.uncovered.into_iter().map(|(_, short)| short).collect(),
+            }
+        })
+        .collect();
+    report.directories.sort_by(|a, b| {
+        a.coverage_pct
+            .partial_cmp(&b.coverage_pct)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then(b.significant.cmp(&a.significant))
+            .then(a.directory.cmp(&b.directory))
+    });
+
+    Ok(report)
+}
+
+/// Commits cited by any ARF in the knowledge base, full or abbreviated
+fn cited_commits(backend: &dyn KnowledgeBackend) -> Vec<String> {
+    backend
+        .list_arfs()
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|path| backend.read_arf(path).ok().flatten())
+        .flat_map(|arf| arf.context.commits)
+        .collect()
+}
+
+/// The first `depth` directories of `path`, or "." for files at the root
+fn directory(path: &str, depth: usize) -> String {
+    let parts: Vec<&str> = path.split('/').collect();
+    let dirs = &parts[..parts.len() - 1];
+    if dirs.is_empty() {
+        ".".to_string()
+    } else {
+        dirs[..depth.min(dirs.len())].join("/")
+    }
+}
+
+fn print_report(report: &CoverageReport) {
+    println!("{}", "Knowledge Coverage".bold());
+    if report.commits == 0 {
+        println!("No processed significant commits. Run 'noggin learn' first.");
+        return;
+    }
+    println!(
+        "  {} of {} significant commits produced knowledge ({:.0}%)",
+        report.covered,
+        report.commits,
+        report.covered as f64 / report.commits as f64 * 100.0
+    );
+    if report.missing > 0 {
+        println!(
+            "  {}",
+            format!(
+                "{} processed commits are no longer in the repository",
+                report.missing
+            )
+            .dimmed()
+        );
+    }
+    println!();
+
+    for dir in &report.directories {
+        let line = format!(
+            "  {:<30} {:>4.0}%  ({} of {})",
+            dir.directory, dir.coverage_pct, dir.covered, dir.significant
+        );
+        if dir.dark {
+            println!("{}  {}", line.red(), "dark".red().bold());
+            let listed: Vec<&str> = dir
+                .uncovered
+                .iter()
+                .take(MAX_LISTED_COMMITS)
+                .map(String::as_str)
+                .collect();
+            let more = dir.uncovered.len().saturating_sub(MAX_LISTED_COMMITS);
+            let suffix = if more > 0 {
+                format!(" and {} more", more)
+            } else {
+                String::new()
+            };
+            println!(
+                "      {}",
+                format!("{}{}", listed.join(", "), suffix).dimmed()
+            );
+        } else {
+            println!("{}", line);
+        }
+    }
+
+    let dark = report.directories.iter().filter(|d| d.dark).count();
+    if dark > 0 {
+        println!();
+        println!(
+            "{} dark directories. Run 'noggin learn --full' to re-analyze their history.",
+            dark
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::fixture;
+    use crate::arf::ArfFile;
+    use crate::manifest::{CommitCategory, Manifest};
+    use crate::storage::MemoryBackend;
+    use tempfile::TempDir;
+
+    fn commit_file(repo: &Repository, path: &str) -> String {
+        fixture::commit(repo, path, &[(path, path)]).to_string()
+    }
+
+    #[test]
+    fn test_directory() {
+        assert_eq!(directory("README.md", 1), ".");
+        assert_eq!(directory("src/llm/claude.rs", 1), "src");
+        assert_eq!(directory("src/llm/claude.rs", 2), "src/llm");
+        assert_eq!(directory("src/main.rs", 3), "src");
+    }
+
+    #[test]
+    fn test_coverage_flags_dark_directories() {
+        let temp_dir = TempDir::new().unwrap();
+        let repo = Repository::init(temp_dir.path()).unwrap();
+        let backend = MemoryBackend::default();
+
+        let api = commit_file(&repo, "api/handler.rs");
+        let billing = commit_file(&repo, "billing/pay.rs");
+        let deleted = commit_file(&repo, "billing/refund.rs");
+
+        let thin = ArfFile::new("Handlers are thin", "Testability", "Delegate to services");
+        backend.write_arf("patterns/thin-handlers.arf", &thin).unwrap();
+        let mut manifest = Manifest::default();
+        manifest.add_commit(
+            api,
+            CommitCategory::Decision,
+            "patterns/thin-handlers.arf".to_string(),
+        );
+        manifest.add_commit(billing, CommitCategory::Bug, String::new());
+        manifest.add_commit(deleted, CommitCategory::Bug, "bugs/gone.arf".to_string());
+        manifest.add_commit("f".repeat(40), CommitCategory::Bug, String::new());
+        backend.save_manifest(&manifest).unwrap();
+
+        let report = coverage(temp_dir.path(), &backend, 1).unwrap();
+        assert_eq!(report.commits, 3);
+        assert_eq!(report.covered, 1);
+        assert_eq!(report.missing, 1);
+
+        // Compacted commits are covered by an ARF citing them
+        let docs = commit_file(&repo, "docs/guide.md");
+        let mut cites = ArfFile::new("Guides are versioned", "Drift", "One per release");
+        cites.context.commits = vec![short_hash(&docs).to_string()];
+        backend.write_arf("facts/guides.arf", &cites).unwrap();
+        let ci = commit_file(&repo, "ci/build.yml");
+        manifest.compacted_commits.extend([docs, ci]);
+        backend.save_manifest(&manifest).unwrap();
+        let compacted = coverage(temp_dir.path(), &backend, 1).unwrap();
+        assert_eq!(compacted.commits, 5);
+        assert_eq!(compacted.covered, 2);
+        let dark: Vec<&str> = compacted
+            .directories
+            .iter()
+            .filter(|d| d.dark)
+            .map(|d| d.directory.as_str())
+            .collect();
+        assert_eq!(dark, vec!["billing", "ci"]);
+
+        let first = &report.directories[0];
+        assert_eq!(first.directory, "billing");
+        assert!(first.dark);
+        assert_eq!(first.significant, 2);
+        assert_eq!(first.uncovered.len(), 2);
+
+        let api = &report.directories[1];
+        assert_eq!(api.directory, "api");
+        assert!(!api.dark);
+        assert_eq!(api.coverage_pct, 100.0);
+    }
+}
@@ -0,0 +1,259 @@
+use crate::arf::ArfFile;
+use crate::config::Config;
+use crate::git::diff::{branch_diff, DiffSummary};
+use crate::learn::ledger::{MachineBudget, MeteredRun};
+use crate::llm::parallel::Scheduler;
+use crate::llm::provider_from_config;
+use crate::llm::stream::ignore_chunks;
+use crate::policy::NeverSend;
+use crate::query::linked_arfs;
+use crate::storage;
+use anyhow::{Context, Result};
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+/// Options for `noggin describe`
+#[derive(Debug, Clone)]
+pub struct DescribeOptions {
+    /// Branch or revision the PR will merge into
+    pub base: String,
+    /// Provider that drafts the description
+    pub provider: String,
+    /// Write the description to a file instead of stdout
+    pub output: Option<PathBuf>,
+    /// Skip the provider and emit the deterministic draft
+    pub no_llm: bool,
+}
+
+impl Default for DescribeOptions {
+    fn default() -> Self {
+        Self {
+            base: "main".to_string(),
+            provider: "claude".to_string(),
+            output: None,
+            no_llm: false,
+        }
+    }
+}
+
+/// Draft a PR description for the current branch
+pub async fn describe_command(opts: DescribeOptions) -> Result<()> {
+    let repo_path = env::current_dir()?;
+    let noggin_path = repo_path.join(".noggin");
+
+    if !noggin_path.exists() {
+        anyhow::bail!("Not initialized. Run 'noggin init' first.");
+    }
+
+    let config = Config::load(&noggin_path).context("Failed to load config")?;
+    let never_send = NeverSend::from_config(&config.privacy)?;
+    let diff = branch_diff(&repo_path, &opts.base, &never_send)?;
+    if diff.files.is_empty() {
+        anyhow::bail!("No changes between HEAD and '{}'", opts.base);
+    }
+
+    let backend = storage::open(&noggin_path, &config.storage);
+    let affected = linked_arfs(backend.as_ref(), &diff.paths())?;
+
+    let description = if opts.no_llm {
+        render_draft(&diff, &affected)
+    } else {
+        let provider = provider_from_config(&opts.provider, &config.llm)?.with_context(|| {
+            format!(
+                "Unknown provider '{}'. Available: {}",
+                opts.provider,
+                config.llm.provider_names().join(", ")
+            )
+        })?;
+        let prompt = build_describe_prompt(&diff, &affected);
+        let mut metered = MeteredRun::new(MachineBudget::for_user(), config.budget.clone());
+        metered.admit(provider.name(), &prompt)?;
+        let result = Scheduler::new(&config.llm)
+            .query(provider.as_ref(), &prompt, &ignore_chunks)
+            .await;
+        if let Ok(response) = &result {
+            metered.answered(provider.name(), response);
+        }
+        metered.finish(&repo_path);
+        let response = result
+            .with_context(|| format!("{} failed to draft the description", provider.name()))?;
+        format!("{}\n", response.trim())
+    };
+
+    match &opts.output {
+        Some(path) => {
+            fs::write(path, &description)
+                .with_context(|| format!("Failed to write {}", path.display()))?;
+            println!("Wrote {}", path.display());
+        }
+        None => print!("{}", description),
+    }
+
+    Ok(())
+}
+
+/// Prompt asking a provider to draft the description
+fn build_describe_prompt(diff: &DiffSummary, affected: &[(String, ArfFile)]) -> String {
+    let mut prompt = String::from(
+        "Draft a pull request description for the change below. \
+         Write Markdown with these sections: ## Summary, ## Changes, \
+         ## Knowledge impact, ## Testing notes.\n\n\
+         In Knowledge impact, name each affected knowledge entry listed below, \
+         say how the change relates to it, and flag any entry the change \
+         appears to contradict or that may need updating. If no entries are \
+         listed, say the change touches no recorded knowledge.\n\n\
+         Output only the description.\n\n",
+    );
+
+    prompt.push_str("--- COMMITS ---\n\n");
+    for commit in &diff.commits {
+        prompt.push_str(&format!("{} {}\n", commit.short_hash, commit.summary));
+    }
+
+    prompt.push_str("\n--- FILES ---\n\n");
+    for file in &diff.files {
+        prompt.push_str(&format!(
+            "{} [{}] +{} -{}\n",
+            file.path, file.status, file.insertions, file.deletions
+        ));
+    }
+
+    prompt.push_str("\n--- AFFECTED KNOWLEDGE ---\n\n");
+    if affected.is_empty() {
+        prompt.push_str("(none)\n");
+    }
+    for (path, arf) in affected {
+        prompt.push_str(&format!(
+            "[{}]\nWhat: {}\nWhy: {}\nHow: {}\n\n",
+            path, arf.what, arf.why, arf.how
+        ));
+    }
+
+    prompt.push_str("\n--- DIFF ---\n\n");
+    prompt.push_str(&diff.patch);
+    if diff.patch_truncated {
+        prompt.push_str("\n... (diff truncated)\n");
+    }
+
+    prompt
+}
+
+/// Description built from the diff and knowledge base alone
+fn render_draft(diff: &DiffSummary, affected: &[(String, ArfFile)]) -> String {
+    let mut out = String::from("## Summary\n\n");
+    out.push_str(&format!(
+        "{} commits, {} files changed (+{} -{}).\n\n",
+        diff.commits.len(),
+        diff.files.len(),
+        diff.total_insertions(),
+        diff.total_deletions()
+    ));
+    for commit in &diff.commits {
+        out.push_str(&format!("- {} ({})\n", commit.summary, commit.short_hash));
+    }
+
+    out.push_str("\n## Changes\n\n");
+    for file in &diff.files {
+        out.push_str(&format!(
+            "- `{}` {} (+{} -{})\n",
+            file.path, file.status, file.insertions, file.deletions
+        ));
+    }
+
+    out.push_str("\n## Knowledge impact\n\n");
+    out.push_str(&knowledge_impact(affected));
+
+    out
+}
+
+/// One line per affected ARF, e.g. "This change touches the connection
+/// pooling decision"
+fn knowledge_impact(affected: &[(String, ArfFile)]) -> String {
+    if affected.is_empty() {
+        return "This change touches no recorded knowledge.\n".to_string();
+    }
+
+    let mut out = String::new();
+    for (path, arf) in affected {
+        let kind = path
+            .split('/')
+            .next()
+            .map(singular_category)
+            .unwrap_or("entry");
+        out.push_str(&format!(
+            "- This change touches the **{}** {} (`.noggin/{}`)\n",
+            arf.what, kind, path
+        ));
+    }
+    out
+}
+
+fn singular_category(dir: &str) -> &'static str {
+    match dir {
+        "decisions" => "decision",
+        "patterns" => "pattern",
+        "bugs" => "bug fix",
+        "migrations" => "migration",
+        "facts" => "fact",
+        _ => "entry",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::diff::{FileChange, RangeCommit};
+
+    fn sample_diff() -> DiffSummary {
+        DiffSummary {
+            label: "main...HEAD".to_string(),
+            files: vec![FileChange {
+                path: "src/db/pool.rs".to_string(),
+                status: "modified".to_string(),
+                insertions: 4,
+                deletions: 1,
+            }],
+            commits: vec![RangeCommit {
+                short_hash: "abc1234".to_string(),
+                summary: "Raise pool size".to_string(),
+            }],
+            patch: "+let size = 20;\n".to_string(),
+            patch_truncated: false,
+        }
+    }
+
+    fn sample_affected() -> Vec<(String, ArfFile)> {
+        vec![(
+            "decisions/use-connection-pooling.arf".to_string(),
+            ArfFile::new("Use connection pooling", "Reduce overhead", "PgBouncer"),
+        )]
+    }
+
+    #[test]
+    fn test_render_draft_names_affected_knowledge() {
+        let draft = render_draft(&sample_diff(), &sample_affected());
+
+        assert!(draft.contains("1 commits, 1 files changed (+4 -1)"));
+        assert!(draft.contains("- Raise pool size (abc1234)"));
+        assert!(draft.contains(
+            "This change touches the **Use connection pooling** decision (`.noggin/decisions/use-connection-pooling.arf`)"
+        ));
+    }
+
+    #[test]
+    fn test_render_draft_without_knowledge() {
+        let draft = render_draft(&sample_diff(), &[]);
+        assert!(draft.contains("touches no recorded knowledge"));
+    }
+
+    #[test]
+    fn test_prompt_includes_knowledge_and_diff() {
+        let prompt = build_describe_prompt(&sample_diff(), &sample_affected());
+
+        assert!(prompt.contains("## Knowledge impact"));
+        assert!(prompt.contains("[decisions/use-connection-pooling.arf]"));
+        assert!(prompt.contains("Why: Reduce overhead"));
+        assert!(prompt.contains("+let size = 20;"));
+    }
+}
@@ -0,0 +1,176 @@
+//! `noggin diff --snapshot <name>`: how the knowledge base changed since
+//! a snapshot
+//!
+//! Compares against the current knowledge base, or another snapshot with
+//! `--to`. Field-level changes are shown when the older snapshot was
+//! tagged; otherwise changed entries are only listed.
+
+use crate::config::Config;
+use crate::snapshot::{self, diff_snapshots, Snapshot, SnapshotDiff};
+use crate::storage;
+use anyhow::{Context, Result};
+use colored::Colorize;
+use git2::Repository;
+use std::env;
+use std::path::Path;
+
+/// Options for `noggin diff`
+#[derive(Debug, Clone, Default)]
+pub struct DiffOptions {
+    /// Snapshot to compare from
+    pub snapshot: String,
+    /// Snapshot to compare to; defaults to the current knowledge base
+    pub to: Option<String>,
+    pub json: bool,
+}
+
+pub fn diff_command(opts: DiffOptions) -> Result<()> {
+    let repo_path = env::current_dir()?;
+    let diff = run_diff(&repo_path, &opts)?;
+
+    if opts.json {
+        println!("{}", serde_json::to_string_pretty(&diff)?);
+        return Ok(());
+    }
+
+    if diff.is_empty() {
+        println!("No changes between {} and {}.", diff.from, diff.to);
+        return Ok(());
+    }
+
+    println!("{} → {}", diff.from.bold(), diff.to.bold());
+    for entry in &diff.added {
+        println!("  {} {}  {}", "+".green(), entry.path, entry.what.dimmed());
+    }
+    for entry in &diff.removed {
+        println!("  {} {}  {}", "-".red(), entry.path, entry.what.dimmed());
+    }
+    for entry in &diff.changed {
+        println!("  {} {}  {}", "~".yellow(), entry.path, entry.what.dimmed());
+        for field in &entry.fields {
+            println!("      {}: {}", field.field, field.before.red());
+            println!("      {}  {}", " ".repeat(field.field.len()), field.after.green());
+        }
+    }
+    println!(
+        "{} added, {} removed, {} changed",
+        diff.added.len(),
+        diff.removed.len(),
+        diff.changed.len()
+    );
+    Ok(())
+}
+
+/// Compare a snapshot of the knowledge base in `repo_path` with another
+/// snapshot or the current state
+pub fn run_diff(repo_path: &Path, opts: &DiffOptions) -> Result<SnapshotDiff> {
+    let noggin_path = repo_path.join(".noggin");
+    if !noggin_path.exists() {
+        anyhow::bail!("Not initialized. Run 'noggin init' first.");
+    }
+
+    let config = Config::load(&noggin_path).context("Failed to load config")?;
+    let backend = storage::open(&noggin_path, &config.storage);
+    let repo = Repository::open(repo_path).ok();
+
+    let from = Snapshot::load(&noggin_path, &opts.snapshot)?;
+    let to = match &opts.to {
+        Some(name) => Snapshot::load(&noggin_path, name)?,
+        None => Snapshot::capture("working tree", backend.as_ref())?,
+    };
+
+    let read_tagged = |snapshot: &Snapshot, path: &str| {
+        let (repo, tag) = (repo.as_ref()?, snapshot.tag.as_deref()?);
+        snapshot::read_tagged_arf(repo, tag, path)
+    };
+    let diff = diff_snapshots(
+        &from,
+        &to,
+        |path| read_tagged(&from, path),
+        |path| match opts.to {
+            Some(_) => read_tagged(&to, path),
+            None => backend.read_arf(path).ok().flatten(),
+        },
+    );
+    Ok(diff)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::arf::ArfFile;
+    use crate::commands::snapshot::{run_snapshot, SnapshotOptions};
+    use tempfile::TempDir;
+
+    fn commit_knowledge(repo: &Repository) {
+        let mut index = repo.index().unwrap();
+        index
+            .add_all([".noggin"], git2::IndexAddOption::DEFAULT, None)
+            .unwrap();
+        index.write().unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let signature = git2::Signature::now("Test", "test@example.com").unwrap();
+        repo.commit(Some("HEAD"), &signature, &signature, "knowledge", &tree, &[])
+            .unwrap();
+    }
+
+    #[test]
+    fn test_tagged_snapshot_diff() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        let noggin = root.join(".noggin");
+        let repo = Repository::init(root).unwrap();
+        let pool = ArfFile::new("Use pooling", "Limits connections", "deadpool");
+        pool.to_toml(&noggin.join("decisions/pool.arf")).unwrap();
+
+        let snapshot = |name: &str, tag: bool| {
+            run_snapshot(
+                root,
+                &SnapshotOptions {
+                    name: name.to_string(),
+                    tag,
+                    force: false,
+                },
+            )
+        };
+        assert!(snapshot("v1", true).is_err(), "uncommitted .noggin/ can't be tagged");
+
+        commit_knowledge(&repo);
+        let v1 = snapshot("v1", true).unwrap();
+        assert_eq!(v1.tag.as_deref(), Some("noggin/v1"));
+        assert!(snapshot("v1", false).is_err());
+
+        let mut changed = pool.clone();
+        changed.how = "bb8".to_string();
+        changed.to_toml(&noggin.join("decisions/pool.arf")).unwrap();
+        ArfFile::new("MSRV is 1.80", "Why", "How")
+            .to_toml(&noggin.join("facts/msrv.arf"))
+            .unwrap();
+
+        let diff = run_diff(
+            root,
+            &DiffOptions {
+                snapshot: "v1".to_string(),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(diff.added.len(), 1);
+        assert_eq!(diff.changed[0].fields[0].field, "how");
+        assert_eq!(diff.changed[0].fields[0].before, "deadpool");
+        assert_eq!(diff.changed[0].fields[0].after, "bb8");
+
+        snapshot("v2", false).unwrap();
+        let diff = run_diff(
+            root,
+            &DiffOptions {
+                snapshot: "v1".to_string(),
+                to: Some("v2".to_string()),
+                json: false,
+            },
+        )
+        .unwrap();
+        assert_eq!(diff.changed.len(), 1);
+        assert!(diff.changed[0].fields.is_empty(), "v2 isn't tagged");
+    }
+}
@@ -0,0 +1,170 @@
+//! `noggin doctor`: find the provider CLIs and record where they are
+//!
+//! Looks up each CLI provider's binary (see [`crate::llm::discovery`]) and
+//! writes the absolute path to `.noggin/config.toml`, so learn spawns the
+//! same binary from cron or a GUI as from a terminal. A configured path
+//! that still exists is left alone; one that has gone missing is replaced
+//! by whatever is found now.
+
+use crate::config::Config;
+use crate::llm::discovery::{find_binary, record_binaries, CLI_PROVIDERS};
+use crate::lock::KnowledgeLock;
+use anyhow::{Context, Result};
+use colored::Colorize;
+use serde::Serialize;
+use std::env;
+use std::path::{Path, PathBuf};
+
+/// Options for `noggin doctor`
+#[derive(Debug, Clone, Default)]
+pub struct DoctorOptions {
+    pub json: bool,
+}
+
+/// Where a provider's binary was found
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BinaryStatus {
+    /// The configured path exists
+    Configured,
+    /// Found now and written to config
+    Recorded,
+    /// Not configured and not found
+    Missing,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProviderBinary {
+    pub provider: String,
+    /// Listed in `llm.providers` and not disabled
+    pub enabled: bool,
+    pub status: BinaryStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub binary: Option<PathBuf>,
+}
+
+pub fn doctor_command(opts: DoctorOptions) -> Result<()> {
+    let repo_path = env::current_dir()?;
+    let noggin_path = repo_path.join(".noggin");
+    if !noggin_path.exists() {
+        anyhow::bail!("Not initialized. Run 'noggin init' first.");
+    }
+    let _lock = KnowledgeLock::acquire(&noggin_path, "doctor")?;
+    let binaries = locate_providers(&noggin_path)?;
+
+    if opts.json {
+        println!("{}", serde_json::to_string_pretty(&binaries)?);
+        return Ok(());
+    }
+
+    println!("{}", "Providers".bold());
+    print_binaries(&binaries);
+    Ok(())
+}
+
+/// Find each CLI provider's binary and record the ones not already
+/// configured (or configured at a path that no longer exists) in
+/// `noggin_path`'s config
+pub fn locate_providers(noggin_path: &Path) -> Result<Vec<ProviderBinary>> {
+    let config = Config::load(noggin_path).context("Failed to load config")?;
+    let mut found = Vec::new();
+    let mut to_record = Vec::new();
+
+    for (provider, binary) in CLI_PROVIDERS {
+        let enabled = config.llm.providers.iter().any(|p| p == provider)
+            && config.llm.is_enabled(provider);
+        let configured = config.llm.binary(provider).filter(|path| path.is_file());
+        let (status, binary) = match configured {
+            Some(path) => (BinaryStatus::Configured, Some(path.to_path_buf())),
+            None => match find_binary(binary) {
+                Some(path) => {
+                    to_record.push((*provider, path.clone()));
+                    (BinaryStatus::Recorded, Some(path))
+                }
+                None => (BinaryStatus::Missing, config.llm.binary(provider).map(Path::to_path_buf)),
+            },
+        };
+        found.push(ProviderBinary {
+            provider: provider.to_string(),
+            enabled,
+            status,
+            binary,
+        });
+    }
+
+    record_binaries(noggin_path, &to_record)?;
+    Ok(found)
+}
+
+/// One line per provider, with a hint for enabled ones that are missing
+pub fn print_binaries(binaries: &[ProviderBinary]) {
+    for entry in binaries {
+        let path = entry
+            .binary
+            .as_ref()
+            .map(|p| p.display().to_string())
+            .unwrap_or_default();
+        match entry.status {
+            BinaryStatus::Configured => {
+                println!("  {} {:<7} {}", "✓".green(), entry.provider, path.dimmed())
+            }
+            BinaryStatus::Recorded => println!(
+                "  {} {:<7} {} {}",
+                "✓".green(),
+                entry.provider,
+                path.dimmed(),
+                "(recorded in config)".dimmed()
+            ),
+            // Without an installed CLI, gemini still runs through npx
+            BinaryStatus::Missing if entry.enabled && entry.provider == "gemini" => println!(
+                "  {} {:<7} not installed; runs @google/gemini-cli through npx",
+                "-".dimmed(),
+                entry.provider
+            ),
+            BinaryStatus::Missing if entry.enabled => println!(
+                "  {} {:<7} not found; install it or set llm.{}.binary",
+                "✗".red(),
+                entry.provider,
+                entry.provider
+            ),
+            BinaryStatus::Missing => println!(
+                "  {} {:<7} not found {}",
+                "-".dimmed(),
+                entry.provider,
+                "(disabled)".dimmed()
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_locate_providers_keeps_existing_configured_binary() {
+        let temp_dir = TempDir::new().unwrap();
+        let claude = temp_dir.path().join("claude");
+        fs::write(&claude, "").unwrap();
+        fs::write(
+            temp_dir.path().join("config.toml"),
+            format!(
+                "[llm]\nproviders = [\"claude\"]\n\n[llm.claude]\nbinary = {:?}\n",
+                claude.to_string_lossy()
+            ),
+        )
+        .unwrap();
+
+        let binaries = locate_providers(temp_dir.path()).unwrap();
+        let claude_entry = binaries.iter().find(|b| b.provider == "claude").unwrap();
+        assert_eq!(claude_entry.status, BinaryStatus::Configured);
+        assert_eq!(claude_entry.binary.as_deref(), Some(claude.as_path()));
+        assert!(claude_entry.enabled);
+        assert!(!binaries.iter().find(|b| b.provider == "codex").unwrap().enabled);
+
+        let config = Config::load(temp_dir.path()).unwrap();
+        assert_eq!(config.llm.binary("claude"), Some(claude.as_path()));
+    }
+}
@@ -0,0 +1,106 @@
+use colored::Colorize;
+
+/// A common workflow: a title and the commands that make it up
+struct Workflow {
+    title: &'static str,
+    steps: &'static [(&'static str, &'static str)],
+}
+
+const WORKFLOWS: &[Workflow] = &[
+    Workflow {
+        title: "Build a knowledge base for the current repository",
+        steps: &[
+            ("noggin init", "create .noggin/ with default config"),
+            ("noggin init --guide", "or: also survey history and suggest a scoped first learn"),
+            ("noggin doctor", "find claude, codex, and gemini, recording their paths"),
+            ("noggin learn", "analyze files and history"),
+            ("noggin learn --lite", "or: structural facts only, no provider needed"),
+            ("noggin status", "see what's scanned and what's pending"),
+        ],
+    },
+    Workflow {
+        title: "Keep it up to date",
+        steps: &[
+            ("noggin learn", "process only changed files and new commits"),
+            ("noggin learn --max-cost 2.50", "stop once estimated spend reaches $2.50"),
+            ("noggin maintain --max-commits 50", "backfill a bounded slice of history"),
+            ("noggin learn --full --by-release", "write what changed between each pair of release tags"),
+            ("noggin verify-knowledge --sample 10", "re-check the least-confident entries"),
+            ("noggin purge --expired", "drop raw responses and logs past the retention limits"),
+            ("noggin prune --dry-run", "list entries for deleted files that prune would remove"),
+            ("noggin resolve", "choose between conflicting values learn left for review"),
+            ("noggin conflicts explain <id>", "see how the models voted on a conflict"),
+            ("noggin log", "list previous learn runs and what they produced"),
+        ],
+    },
+    Workflow {
+        title: "Ask questions",
+        steps: &[
+            ("noggin ask \"why do we use sqlx?\"", "search the knowledge base"),
+            ("noggin ask --category bugs --since 30d retry", "recent bugs mentioning retry"),
+            ("noggin ask \"connection pooling\"", "ends with who is most involved, by commits and lines owned"),
+            ("noggin ask --saved onboarding", "run a question from .noggin/queries.toml"),
+            ("noggin grep 'what:pooling AND file:src/db/**'", "match fields exactly, no model involved"),
+            ("noggin graph | dot -Tsvg > knowledge.svg", "see how decisions connect to files and commits"),
+            ("noggin export --format markdown -o docs/knowledge", "publish the knowledge base with mkdocs or Docusaurus"),
+            ("noggin diff --snapshot v1.4", "see how knowledge changed since `noggin snapshot v1.4`"),
+            ("noggin bundle -o knowledge.tar.gz", "snapshot the knowledge base for a release or an auditor"),
+        ],
+    },
+    Workflow {
+        title: "Review changes",
+        steps: &[
+            ("noggin check", "evaluate pattern rules without an LLM"),
+            ("noggin translate --dry-run", "preview rewriting entries not in llm.output_language"),
+            ("noggin review-diff main..HEAD", "check a branch against documented knowledge"),
+            ("noggin describe --base main", "draft a PR description"),
+        ],
+    },
+    Workflow {
+        title: "Automate",
+        steps: &[
+            ("noggin ci --mode verify", "fail the build when the knowledge base drifts"),
+            ("noggin ci --mode learn --commit --push", "update and push the knowledge base"),
+            ("noggin hook install", "add Noggin-Knowledge trailers to commits"),
+            ("noggin serve", "expose the knowledge base over MCP"),
+            ("noggin synthesize --input responses/", "merge responses from your own LLM pipeline"),
+        ],
+    },
+    Workflow {
+        title: "Shell completions",
+        steps: &[
+            ("noggin completions bash > ~/.local/share/bash-completion/completions/noggin", "bash"),
+            ("noggin completions zsh > ~/.zfunc/_noggin", "zsh"),
+            ("noggin completions fish > ~/.config/fish/completions/noggin.fish", "fish"),
+        ],
+    },
+];
+
+/// Print common workflows
+pub fn examples_command() {
+    for (i, workflow) in WORKFLOWS.iter().enumerate() {
+        if i > 0 {
+            println!();
+        }
+        println!("{}", workflow.title.bold());
+        for (command, description) in workflow.steps {
+            println!("  {}", command.cyan());
+            println!("      {}", description.dimmed());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_examples_are_noggin_commands() {
+        for workflow in WORKFLOWS {
+            assert!(!workflow.steps.is_empty(), "{} has no steps", workflow.title);
+            for (command, _) in workflow.steps {
+                assert!(command.starts_with("noggin "), "{}", command);
+            }
+        }
+    }
+}
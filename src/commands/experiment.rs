@@ -0,0 +1,418 @@
+//! `noggin experiment`: compare two configurations on the same scope
+//!
+//! Picks one analysis scope (pending files and significant commits, as
+//! learn would), builds the prompts once, and runs them under each
+//! configuration's providers. Responses go through a shared
+//! [`ResponseCache`], so a provider both arms use is only asked once per
+//! prompt. The two synthesized outputs are then matched entry by entry.
+//! Nothing is written to the knowledge base.
+
+use crate::arf::ArfFile;
+use crate::commands::learn::{commit_diffs, history_refs, significant_commits};
+use crate::config::Config;
+use crate::conflicts::contradicting_fields;
+use crate::git::walker::{walk_commits, WalkOptions};
+use crate::learn::language::language_instruction;
+use crate::learn::ledger::{MachineBudget, MeteredRun};
+use crate::learn::prompts::{build_commit_analysis_prompt, build_file_analysis_prompts};
+use crate::learn::generated::GeneratedFiles;
+use crate::learn::scanner::scan_files;
+use crate::learn::stack::{self, prompt_context};
+use crate::learn::tokens::PromptBudget;
+use crate::llm::cache::ResponseCache;
+use crate::llm::configured_providers;
+use crate::llm::parallel::Scheduler;
+use crate::policy::NeverSend;
+use crate::storage;
+use crate::synthesis::{self, ModelOutput};
+use crate::templates::Templates;
+use anyhow::{Context, Result};
+use colored::Colorize;
+use serde::Serialize;
+use std::env;
+use std::path::{Path, PathBuf};
+
+/// Options for `noggin experiment`
+#[derive(Debug, Clone)]
+pub struct ExperimentOptions {
+    pub config_a: PathBuf,
+    pub config_b: PathBuf,
+    /// Most changed files to include in the scope
+    pub max_files: usize,
+    /// Most recent significant commits to include in the scope
+    pub max_commits: usize,
+    pub json: bool,
+}
+
+/// Side-by-side outcome of an experiment
+#[derive(Debug, Clone, Serialize)]
+pub struct ExperimentReport {
+    pub files: Vec<String>,
+    pub commits: Vec<String>,
+    pub a: ArmReport,
+    pub b: ArmReport,
+    pub comparison: Comparison,
+    /// Provider requests actually sent
+    pub requests_sent: usize,
+    /// Provider responses reused from the other arm
+    pub requests_cached: usize,
+}
+
+/// What one configuration produced
+#[derive(Debug, Clone, Serialize)]
+pub struct ArmReport {
+    pub config: String,
+    pub providers: Vec<String>,
+    pub entries: Vec<ArfFile>,
+    pub warnings: Vec<String>,
+}
+
+/// How two arms' entries line up
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Comparison {
+    /// Entries both arms produced with matching `what` and `why`
+    pub agreed: Vec<String>,
+    /// Entries on the same subject whose `why` contradicts
+    pub conflicting: Vec<EntryConflict>,
+    pub only_a: Vec<String>,
+    pub only_b: Vec<String>,
+    /// Share of all entries that found an agreeing counterpart
+    pub agreement_pct: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EntryConflict {
+    pub what: String,
+    pub why_a: String,
+    pub why_b: String,
+}
+
+pub async fn experiment_command(opts: ExperimentOptions) -> Result<()> {
+    let repo_path = env::current_dir()?;
+    let report = run_experiment(&repo_path, &opts).await?;
+
+    if opts.json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+        return Ok(());
+    }
+
+    print_report(&report);
+    Ok(())
+}
+
+/// Run both configurations over the same scope and compare the results
+pub async fn run_experiment(repo_path: &Path, opts: &ExperimentOptions) -> Result<ExperimentReport> {
+    let config_a = Config::from_file(&opts.config_a)?;
+    let config_b = Config::from_file(&opts.config_b)?;
+
+    let noggin_path = repo_path.join(".noggin");
+    let base_config = Config::load(&noggin_path).context("Failed to load config")?;
+    let backend = storage::open(&noggin_path, &base_config.storage);
+    let mut manifest = backend.load_manifest().context("Failed to load manifest")?;
+    manifest.set_path_case(base_config.manifest.path_case);
+
+    // Scope: what an incremental learn would analyze, capped
+    let generated = GeneratedFiles::from_config(&base_config.generated)?;
+    let scan_result = scan_files(
+        repo_path,
+        &manifest,
+        false,
+        &generated,
+        &[],
+        base_config.submodules.recurse,
+    )
+    .context("Failed to scan files")?;
+    let current_paths = scan_result.current_paths(&manifest);
+    let stack = stack::detect(repo_path, current_paths.iter().map(String::as_str));
+    let files: Vec<_> = scan_result
+        .representatives()
+        .into_iter()
+        .take(opts.max_files)
+        .collect();
+
+    let walk_result = walk_commits(
+        repo_path,
+        WalkOptions {
+            skip_merges: true,
+            refs: history_refs(&base_config, &[]),
+            all_branches: base_config.history.all_branches,
+            ..Default::default()
+        },
+    )
+    .context("Failed to walk git history")?;
+    let unprocessed: Vec<_> = walk_result
+        .commits
+        .into_iter()
+        .filter(|c| !c.shallow_boundary && !manifest.is_commit_processed(&c.hash))
+        .collect();
+    let repo = git2::Repository::open(repo_path)?;
+    let mut commits = significant_commits(&repo, unprocessed, &base_config.scoring);
+    if commits.len() > opts.max_commits {
+        commits.drain(..commits.len() - opts.max_commits);
+    }
+
+    if files.is_empty() && commits.is_empty() {
+        anyhow::bail!("Nothing to analyze: no changed files or unprocessed significant commits.");
+    }
+
+    let mut prompts = Vec::new();
+    let never_send = NeverSend::from_config(&base_config.privacy)?;
+    let budget = PromptBudget::from_config(&base_config);
+    if !files.is_empty() {
+        prompts.extend(
+            build_file_analysis_prompts(repo_path, &files, &never_send, &budget)
+                .into_iter()
+                .map(|batch| batch.prompt),
+        );
+    }
+    if !commits.is_empty() {
+        let diffs = commit_diffs(repo_path, &base_config, &commits, &never_send);
+        prompts.push(build_commit_analysis_prompt(&commits, &diffs, &budget));
+    }
+    let instruction = prompt_context(&stack)
+        + &language_instruction(&base_config.llm.output_language)
+        + &Templates::load(&noggin_path)?.prompt_instruction();
+    for prompt in &mut prompts {
+        prompt.push_str(&instruction);
+    }
+
+    // Both arms share the repository's limits, since they hit the same
+    // provider accounts
+    let scheduler = Scheduler::new(&base_config.llm);
+    let mut cache = ResponseCache::new();
+    let mut metered = MeteredRun::new(MachineBudget::for_user(), base_config.budget.clone());
+    let arms = async {
+        let a = run_arm(&opts.config_a, &config_a, &prompts, &scheduler, &mut cache, &mut metered)
+            .await?;
+        let b = run_arm(&opts.config_b, &config_b, &prompts, &scheduler, &mut cache, &mut metered)
+            .await?;
+        anyhow::Ok((a, b))
+    }
+    .await;
+    metered.finish(repo_path);
+    let (a, b) = arms?;
+
+    Ok(ExperimentReport {
+        files: files.iter().map(|f| f.path.clone()).collect(),
+        commits: commits.iter().map(|c| c.hash.clone()).collect(),
+        comparison: compare(&a.entries, &b.entries),
+        a,
+        b,
+        requests_sent: cache.misses(),
+        requests_cached: cache.hits(),
+    })
+}
+
+/// Send every prompt to the arm's providers and synthesize the responses
+async fn run_arm(
+    path: &Path,
+    config: &Config,
+    prompts: &[String],
+    scheduler: &Scheduler,
+    cache: &mut ResponseCache,
+    metered: &mut MeteredRun,
+) -> Result<ArmReport> {
+    let providers = configured_providers(&config.llm)?;
+    let mut warnings = Vec::new();
+    let mut outputs: Vec<ModelOutput> = Vec::new();
+
+    for prompt in prompts {
+        // Only requests the cache can't answer reach a provider
+        let sent: Vec<&str> = providers
+            .iter()
+            .map(|p| p.name())
+            .filter(|name| !cache.is_cached(name, prompt))
+            .collect();
+        for name in &sent {
+            metered.admit(name, prompt)?;
+        }
+        let result = cache.query(scheduler, &providers, prompt).await;
+        for success in result.successes.iter().filter(|s| sent.contains(&s.model.as_str())) {
+            metered.answered(&success.model, &success.response);
+        }
+        for failure in result.failures {
+            warnings.push(format!("{} failed: {}", failure.model, failure.error));
+        }
+        for success in result.successes {
+            match synthesis::parse_model_output(&success.model, &success.response) {
+                Ok(parsed) => {
+                    for warning in parsed.warnings {
+                        warnings.push(format!("{} output: {}", success.model, warning));
+                    }
+                    outputs.push(ModelOutput {
+                        model_name: success.model,
+                        arf_files: parsed.arfs,
+                    });
+                }
+                Err(e) => warnings.push(format!("Failed to parse {} output: {}", success.model, e)),
+            }
+        }
+    }
+
+    let entries = match outputs.len() {
+        0 => Vec::new(),
+        1 => outputs.remove(0).arf_files,
+        _ => match synthesis::synthesize(outputs, &config.synthesis) {
+            Ok(result) => result.unified_arfs,
+            Err(e) => {
+                warnings.push(format!("Synthesis failed: {}", e));
+                Vec::new()
+            }
+        },
+    };
+
+    Ok(ArmReport {
+        config: path.display().to_string(),
+        providers: config.llm.providers.clone(),
+        entries,
+        warnings,
+    })
+}
+
+/// Pair each entry in `a` with the first unpaired entry in `b` on the
+/// same subject (a `what` that doesn't materially differ), then sort the
+/// pairs into agreements and conflicts
+pub fn compare(a: &[ArfFile], b: &[ArfFile]) -> Comparison {
+    let mut comparison = Comparison::default();
+    let mut paired = vec![false; b.len()];
+
+    for entry in a {
+        let counterpart = b.iter().enumerate().find(|(i, other)| {
+            !paired[*i] && !contradicting_fields(entry, other).contains(&"what".to_string())
+        });
+        match counterpart {
+            Some((i, other)) => {
+                paired[i] = true;
+                if contradicting_fields(entry, other).is_empty() {
+                    comparison.agreed.push(entry.what.clone());
+                } else {
+                    comparison.conflicting.push(EntryConflict {
+                        what: entry.what.clone(),
+                        why_a: entry.why.clone(),
+                        why_b: other.why.clone(),
+                    });
+                }
+            }
+            None => comparison.only_a.push(entry.what.clone()),
+        }
+    }
+
+    comparison.only_b = b
+        .iter()
+        .zip(&paired)
+        .filter(|(_, paired)| !**paired)
+        .map(|(entry, _)| entry.what.clone())
+        .collect();
+
+    let total = a.len() + b.len();
+    comparison.agreement_pct = if total == 0 {
+        100.0
+    } else {
+        (comparison.agreed.len() * 2) as f64 / total as f64 * 100.0
+    };
+
+    comparison
+}
+
+fn print_report(report: &ExperimentReport) {
+    println!("{}", "Experiment".bold());
+    println!("  A: {}", report.a.config);
+    println!("  B: {}", report.b.config);
+    println!(
+        "  Scope: {} files, {} commits",
+        report.files.len(),
+        report.commits.len()
+    );
+    println!();
+
+    println!("  {:<12} {:<24} {}", "", "A".bold(), "B".bold());
+    println!(
+        "  {:<12} {:<24} {}",
+        "providers",
+        report.a.providers.join(","),
+        report.b.providers.join(",")
+    );
+    println!(
+        "  {:<12} {:<24} {}",
+        "entries",
+        report.a.entries.len(),
+        report.b.entries.len()
+    );
+    println!(
+        "  {:<12} {:<24} {}",
+        "warnings",
+        report.a.warnings.len(),
+        report.b.warnings.len()
+    );
+    println!();
+
+    let comparison = &report.comparison;
+    println!(
+        "{} {} agree ({:.0}%), {} conflict, {} only in A, {} only in B",
+        "Agreement:".bold(),
+        comparison.agreed.len(),
+        comparison.agreement_pct,
+        comparison.conflicting.len(),
+        comparison.only_a.len(),
+        comparison.only_b.len()
+    );
+
+    for conflict in &comparison.conflicting {
+        println!();
+        println!("  {} {}", "conflict".red(), conflict.what.cyan());
+        println!("    A: {}", conflict.why_a);
+        println!("    B: {}", conflict.why_b);
+    }
+    for (label, entries) in [("only A", &comparison.only_a), ("only B", &comparison.only_b)] {
+        if !entries.is_empty() {
+            println!();
+            for what in entries {
+                println!("  {} {}", label.yellow(), what);
+            }
+        }
+    }
+
+    println!();
+    println!(
+        "Provider requests: {} sent, {} reused from cache",
+        report.requests_sent, report.requests_cached
+    );
+
+    for (label, arm) in [("A", &report.a), ("B", &report.b)] {
+        for warning in &arm.warnings {
+            println!("  {} {}", format!("[{}]", label).dimmed(), warning);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compare_sorts_entries() {
+        let a = vec![
+            ArfFile::new("Use connection pooling", "Reduces database overhead", "PgBouncer"),
+            ArfFile::new("Retry failed payments", "Card networks are flaky", "Backoff"),
+            ArfFile::new("Structured logging", "Searchable logs", "tracing"),
+        ];
+        let b = vec![
+            ArfFile::new("Retry failed payments.", "Required by the payment processor's SLA", "Backoff"),
+            ArfFile::new("use connection pooling", "Reduces database overhead.", "Other how"),
+            ArfFile::new("Feature flags via LaunchDarkly", "Gradual rollout", "SDK"),
+        ];
+
+        let comparison = compare(&a, &b);
+        assert_eq!(comparison.agreed, vec!["Use connection pooling"]);
+        assert_eq!(comparison.conflicting.len(), 1);
+        assert_eq!(comparison.conflicting[0].what, "Retry failed payments");
+        assert_eq!(comparison.only_a, vec!["Structured logging"]);
+        assert_eq!(comparison.only_b, vec!["Feature flags via LaunchDarkly"]);
+        assert!((comparison.agreement_pct - 100.0 / 3.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_compare_empty() {
+        assert_eq!(compare(&[], &[]).agreement_pct, 100.0);
+    }
+}
@@ -0,0 +1,829 @@
+//! `noggin export`: write knowledge-base artifacts for other tools
+//!
+//! The `editor` format writes a support bundle for hand-editing `.arf`
+//! files:
+//!
+//! - `arf.schema.json`: the JSON Schema from `noggin schema`
+//! - `taplo.toml`: maps `.arf` files to the schema for taplo and Even
+//!   Better TOML; copy or merge it into the repository's `.taplo.toml`
+//! - `arf.tmLanguage.json`: a TextMate grammar that highlights `.arf` as TOML
+//! - `vscode/settings.json` and `vscode/arf.code-snippets`
+//! - `templates/<category>.arf`: a starting point for each category
+//!
+//! The `markdown` format renders the knowledge base as pages for a
+//! documentation site such as mkdocs or Docusaurus: `index.md` with a
+//! table of contents, and `<category>.md` for each category that has
+//! entries. Entries get stable anchors named after their `.arf` file, and
+//! link to entries listed under their `related` key or citing the same
+//! files.
+//!
+//! The `json` format writes a single document for other tooling:
+//! `schema_version` ([`JSON_SCHEMA_VERSION`]), `generated_at`, `stats`
+//! (manifest counts plus entries per category), and `entries`, one object
+//! per ARF with a fixed set of keys. Keys are only ever added within a
+//! schema version; renames or removals bump it.
+
+use crate::arf::{arf_category, ArfFile};
+use crate::commands::graph::related_ids;
+use crate::commands::learn::print_planned;
+use crate::config::Config;
+use crate::issues::markdown_link;
+use crate::learn::writer::{PlannedWrite, CATEGORY_DIRS};
+use crate::schema::arf_schema;
+use crate::storage::dry_run::plan_file;
+use crate::storage::{self, KnowledgeBackend};
+use crate::text::short_hash;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::json;
+use std::collections::{BTreeMap, BTreeSet};
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ExportFormat {
+    /// Schema, grammar, editor settings, snippets, and file templates
+    Editor,
+    /// Markdown pages for a documentation site
+    Markdown,
+    /// One JSON document with every entry and manifest stats
+    Json,
+}
+
+impl ExportFormat {
+    /// Where the format is written when `--output` isn't given
+    pub fn default_output(self) -> &'static str {
+        match self {
+            ExportFormat::Editor => ".noggin/editor",
+            ExportFormat::Markdown => ".noggin/site",
+            ExportFormat::Json => ".noggin/knowledge.json",
+        }
+    }
+}
+
+/// Most cross-links listed under an entry
+const MAX_RELATED: usize = 8;
+
+/// Version of the `json` export's layout
+pub const JSON_SCHEMA_VERSION: u32 = 1;
+
+/// Options for `noggin export`
+#[derive(Debug, Clone)]
+pub struct ExportOptions {
+    pub format: ExportFormat,
+    /// Directory to write into, or the file for `json`; relative paths
+    /// are resolved from the repository
+    pub output: PathBuf,
+    pub dry_run: bool,
+}
+
+/// Files an export writes, before they are written
+struct ExportFiles {
+    /// Directory they go under
+    dir: PathBuf,
+    /// Path within `dir` and contents of each
+    files: Vec<(PathBuf, String)>,
+}
+
+pub fn export_command(opts: ExportOptions) -> Result<()> {
+    let repo_path = env::current_dir()?;
+    let noggin_path = repo_path.join(".noggin");
+    if opts.format != ExportFormat::Editor && !noggin_path.exists() {
+        anyhow::bail!("Not initialized. Run 'noggin init' first.");
+    }
+    let config = Config::load(&noggin_path).context("Failed to load config")?;
+    let backend = storage::open(&noggin_path, &config.storage);
+    let files = match opts.format {
+        ExportFormat::Editor => editor_bundle(&repo_path, &opts.output)?,
+        ExportFormat::Markdown => markdown_pages(&repo_path, backend.as_ref(), &opts.output)?,
+        ExportFormat::Json => json_document(&repo_path, backend.as_ref(), &opts.output)?,
+    };
+    if opts.dry_run {
+        print_planned(&plan_files(&repo_path, &files));
+        return Ok(());
+    }
+    let written = write_files(&repo_path, files)?;
+
+    for path in &written {
+        println!("  Wrote {}", path.display());
+    }
+    match opts.format {
+        ExportFormat::Editor => println!(
+            "Editor bundle written to {}. See README.md there for setup.",
+            opts.output.display()
+        ),
+        ExportFormat::Markdown => println!(
+            "Markdown written to {}. Point your documentation site at it, starting from index.md.",
+            opts.output.display()
+        ),
+        ExportFormat::Json => println!(
+            "Knowledge base exported as JSON (schema version {}).",
+            JSON_SCHEMA_VERSION
+        ),
+    }
+    Ok(())
+}
+
+/// Write the editor support bundle into `output`, returning the files
+/// written relative to the repository
+pub fn export_editor_bundle(repo_path: &Path, output: &Path) -> Result<Vec<PathBuf>> {
+    write_files(repo_path, editor_bundle(repo_path, output)?)
+}
+
+fn editor_bundle(repo_path: &Path, output: &Path) -> Result<ExportFiles> {
+    let dir = repo_path.join(output);
+    // taplo resolves schema paths against the config file, which lives at
+    // the repository root once copied there
+    let schema_path = dir
+        .strip_prefix(repo_path)
+        .unwrap_or(&dir)
+        .join("arf.schema.json");
+    let schema_path = schema_path.to_string_lossy().replace('\\', "/");
+
+    let mut files: Vec<(PathBuf, String)> = vec![
+        (
+            "arf.schema.json".into(),
+            serde_json::to_string_pretty(&arf_schema())? + "\n",
+        ),
+        ("taplo.toml".into(), taplo_config(&schema_path)),
+        (
+            "arf.tmLanguage.json".into(),
+            serde_json::to_string_pretty(&json!({
+                "name": "ARF",
+                "scopeName": "source.arf",
+                "fileTypes": ["arf"],
+                "patterns": [{ "include": "source.toml" }]
+            }))? + "\n",
+        ),
+        (
+            "vscode/settings.json".into(),
+            serde_json::to_string_pretty(&json!({
+                "files.associations": { "*.arf": "toml" }
+            }))? + "\n",
+        ),
+        (
+            "vscode/arf.code-snippets".into(),
+            serde_json::to_string_pretty(&snippets())? + "\n",
+        ),
+        ("README.md".into(), readme(&schema_path)),
+    ];
+    for category in CATEGORY_DIRS {
+        files.push((
+            PathBuf::from("templates").join(format!("{}.arf", singular(category))),
+            template(category),
+        ));
+    }
+
+    Ok(ExportFiles { dir, files })
+}
+
+/// Write `export`, returning the paths written relative to the repository
+fn write_files(repo_path: &Path, export: ExportFiles) -> Result<Vec<PathBuf>> {
+    let mut written = Vec::new();
+    for (rel, contents) in export.files {
+        let path = export.dir.join(&rel);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+        fs::write(&path, contents)
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+        written.push(path.strip_prefix(repo_path).unwrap_or(&path).to_path_buf());
+    }
+
+    Ok(written)
+}
+
+/// What writing `export` would change, with paths relative to the
+/// repository
+fn plan_files(repo_path: &Path, export: &ExportFiles) -> Vec<PlannedWrite> {
+    export
+        .files
+        .iter()
+        .map(|(rel, contents)| {
+            let path = export.dir.join(rel);
+            let display = path.strip_prefix(repo_path).unwrap_or(&path).display().to_string();
+            plan_file(&path, &display, contents.as_bytes())
+        })
+        .collect()
+}
+
+/// An ARF placed on its category's page
+struct Entry {
+    /// Path relative to .noggin/
+    rel: String,
+    category: String,
+    anchor: String,
+    arf: ArfFile,
+}
+
+impl Entry {
+    fn link_from(&self, category: &str) -> String {
+        if self.category == category {
+            format!("#{}", self.anchor)
+        } else {
+            format!("{}.md#{}", self.category, self.anchor)
+        }
+    }
+}
+
+/// Render every ARF in `backend` as Markdown pages in `output`,
+/// returning the files written relative to the repository. Unparseable
+/// ARFs are skipped.
+pub fn export_markdown(
+    repo_path: &Path,
+    backend: &dyn KnowledgeBackend,
+    output: &Path,
+) -> Result<Vec<PathBuf>> {
+    write_files(repo_path, markdown_pages(repo_path, backend, output)?)
+}
+
+fn markdown_pages(
+    repo_path: &Path,
+    backend: &dyn KnowledgeBackend,
+    output: &Path,
+) -> Result<ExportFiles> {
+    let mut entries: Vec<Entry> = Vec::new();
+    for rel in backend.list_arfs()? {
+        let Ok(Some(arf)) = backend.read_arf(&rel) else {
+            continue;
+        };
+        let category = arf_category(Path::new(&rel));
+        let anchor = anchor_for(&rel, &category);
+        entries.push(Entry {
+            rel,
+            category,
+            anchor,
+            arf,
+        });
+    }
+    entries.sort_by(|a, b| {
+        (a.category.as_str(), a.arf.what.to_lowercase(), a.rel.as_str())
+            .cmp(&(b.category.as_str(), b.arf.what.to_lowercase(), b.rel.as_str()))
+    });
+
+    let related = cross_links(&entries);
+    let mut pages: BTreeMap<&str, Vec<usize>> = BTreeMap::new();
+    for (i, entry) in entries.iter().enumerate() {
+        pages.entry(entry.category.as_str()).or_default().push(i);
+    }
+
+    // Known categories first, in their usual order
+    let mut order: Vec<&str> = CATEGORY_DIRS
+        .iter()
+        .copied()
+        .filter(|category| pages.contains_key(category))
+        .collect();
+    order.extend(pages.keys().copied().filter(|category| !CATEGORY_DIRS.contains(category)));
+
+    let mut index = String::from("# Knowledge base\n\n");
+    if entries.is_empty() {
+        index.push_str("_No knowledge yet. Run `noggin learn` to build it._\n");
+    } else {
+        index.push_str(&format!(
+            "{} entr{} generated by `noggin export --format markdown`.\n\n",
+            entries.len(),
+            if entries.len() == 1 { "y" } else { "ies" }
+        ));
+    }
+    let mut files: Vec<(PathBuf, String)> = Vec::new();
+    for category in &order {
+        let members = &pages[category];
+        index.push_str(&format!(
+            "- [{}]({}.md) ({})\n",
+            capitalize(category),
+            category,
+            members.len()
+        ));
+        files.push((
+            PathBuf::from(format!("{}.md", category)),
+            render_page(category, members, &entries, &related),
+        ));
+    }
+    files.insert(0, ("index.md".into(), index));
+
+    Ok(ExportFiles {
+        dir: repo_path.join(output),
+        files,
+    })
+}
+
+/// The `json` export document
+#[derive(Debug, Serialize)]
+pub struct JsonExport {
+    pub schema_version: u32,
+    pub generated_at: DateTime<Utc>,
+    pub stats: JsonStats,
+    pub entries: Vec<JsonEntry>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct JsonStats {
+    pub entries: usize,
+    /// Entries per category, including empty known categories
+    pub categories: BTreeMap<String, usize>,
+    pub files_scanned: usize,
+    pub commits_processed: usize,
+    pub patterns_extracted: usize,
+    pub last_scan: Option<DateTime<Utc>>,
+}
+
+/// An ARF in the `json` export. Every key is always present; missing
+/// values are null or empty.
+#[derive(Debug, Serialize)]
+pub struct JsonEntry {
+    /// Path relative to .noggin/ without `.arf`, e.g. `decisions/pooling`
+    pub id: String,
+    pub category: String,
+    pub what: String,
+    pub why: String,
+    pub how: String,
+    pub tags: Vec<String>,
+    pub confidence: Option<f64>,
+    pub approved: bool,
+    pub created_at: Option<DateTime<Utc>>,
+    pub updated_at: Option<DateTime<Utc>>,
+    pub files: Vec<String>,
+    pub commits: Vec<String>,
+    pub branches: Vec<String>,
+    pub issues: Vec<String>,
+    pub people: Vec<String>,
+    pub dependencies: Vec<String>,
+    /// Ids of entries listed under the ARF's `related` key
+    pub related: Vec<String>,
+}
+
+/// Collect the `json` export document. Unparseable ARFs are skipped.
+pub fn build_json_export(backend: &dyn KnowledgeBackend) -> Result<JsonExport> {
+    let manifest = backend.load_manifest()?;
+    let manifest_stats = manifest.stats();
+
+    let mut categories: BTreeMap<String, usize> = CATEGORY_DIRS
+        .iter()
+        .map(|category| (category.to_string(), 0))
+        .collect();
+    let mut entries = Vec::new();
+    for rel in backend.list_arfs()? {
+        let Ok(Some(arf)) = backend.read_arf(&rel) else {
+            continue;
+        };
+        let category = arf_category(Path::new(&rel));
+        *categories.entry(category.clone()).or_default() += 1;
+
+        let related = related_ids(&arf)
+            .into_iter()
+            .map(|id| id.strip_suffix(".arf").map(str::to_string).unwrap_or(id))
+            .collect();
+        let context = arf.context;
+        entries.push(JsonEntry {
+            id: rel.strip_suffix(".arf").unwrap_or(&rel).to_string(),
+            category,
+            what: arf.what,
+            why: arf.why,
+            how: arf.how,
+            tags: arf.tags,
+            confidence: arf.confidence,
+            approved: arf.approved,
+            created_at: arf.created_at,
+            updated_at: arf.updated_at,
+            files: context.files,
+            commits: context.commits,
+            branches: context.branches,
+            issues: context.issues,
+            people: context.people,
+            dependencies: context.dependencies,
+            related,
+        });
+    }
+
+    Ok(JsonExport {
+        schema_version: JSON_SCHEMA_VERSION,
+        generated_at: Utc::now(),
+        stats: JsonStats {
+            entries: entries.len(),
+            categories,
+            files_scanned: manifest_stats.files_scanned,
+            commits_processed: manifest_stats.commits_processed,
+            patterns_extracted: manifest_stats.patterns_extracted,
+            last_scan: manifest_stats.last_scan,
+        },
+        entries,
+    })
+}
+
+/// Write the `json` export to the file `output`, returning its path
+/// relative to the repository
+pub fn export_json(
+    repo_path: &Path,
+    backend: &dyn KnowledgeBackend,
+    output: &Path,
+) -> Result<Vec<PathBuf>> {
+    write_files(repo_path, json_document(repo_path, backend, output)?)
+}
+
+fn json_document(
+    repo_path: &Path,
+    backend: &dyn KnowledgeBackend,
+    output: &Path,
+) -> Result<ExportFiles> {
+    let export = build_json_export(backend)?;
+    let path = repo_path.join(output);
+    let dir = path.parent().unwrap_or(repo_path).to_path_buf();
+    let name = path.file_name().map(PathBuf::from).unwrap_or_else(|| "knowledge.json".into());
+    Ok(ExportFiles {
+        dir,
+        files: vec![(name, serde_json::to_string_pretty(&export)? + "\n")],
+    })
+}
+
+/// Anchor for the entry at `rel`: its file name without `.arf`, prefixed
+/// with any namespace below the category (`facts/glossary/shard.arf` is
+/// `glossary-shard`)
+fn anchor_for(rel: &str, category: &str) -> String {
+    let rel = rel.strip_suffix(".arf").unwrap_or(rel);
+    let rel = rel
+        .strip_prefix(category)
+        .and_then(|rest| rest.strip_prefix('/'))
+        .unwrap_or(rel);
+    rel.replace('/', "-")
+}
+
+/// For each entry, the entries it links to: those named under its
+/// `related` key, then those citing one of its files
+fn cross_links(entries: &[Entry]) -> Vec<Vec<usize>> {
+    let by_rel: BTreeMap<&str, usize> = entries
+        .iter()
+        .enumerate()
+        .map(|(i, entry)| (entry.rel.as_str(), i))
+        .collect();
+    let mut by_file: BTreeMap<&str, BTreeSet<usize>> = BTreeMap::new();
+    for (i, entry) in entries.iter().enumerate() {
+        for file in &entry.arf.context.files {
+            let file = file.trim().trim_start_matches("./");
+            if !file.is_empty() {
+                by_file.entry(file).or_default().insert(i);
+            }
+        }
+    }
+
+    entries
+        .iter()
+        .enumerate()
+        .map(|(i, entry)| {
+            let mut links: Vec<usize> = Vec::new();
+            let explicit = related_ids(&entry.arf).into_iter().filter_map(|id| {
+                let id = if id.ends_with(".arf") { id } else { format!("{}.arf", id) };
+                by_rel.get(id.as_str()).copied()
+            });
+            let shared = entry.arf.context.files.iter().flat_map(|file| {
+                by_file
+                    .get(file.trim().trim_start_matches("./"))
+                    .into_iter()
+                    .flatten()
+                    .copied()
+            });
+            for j in explicit.chain(shared) {
+                if j != i && !links.contains(&j) && links.len() < MAX_RELATED {
+                    links.push(j);
+                }
+            }
+            links
+        })
+        .collect()
+}
+
+fn render_page(category: &str, members: &[usize], entries: &[Entry], related: &[Vec<usize>]) -> String {
+    let mut out = format!("# {}\n\n[All categories](index.md)\n\n", capitalize(category));
+    for &i in members {
+        let entry = &entries[i];
+        out.push_str(&format!("- [{}](#{})\n", one_line(&entry.arf.what), entry.anchor));
+    }
+    out.push('\n');
+
+    for &i in members {
+        let entry = &entries[i];
+        let arf = &entry.arf;
+        let context = &arf.context;
+        // An explicit anchor renders the same in mkdocs and Docusaurus
+        out.push_str(&format!("<a id=\"{}\"></a>\n\n", entry.anchor));
+        out.push_str(&format!("## {}\n\n", one_line(&arf.what)));
+        out.push_str(&format!("**Why:** {}\n\n", arf.why.trim()));
+        out.push_str(&format!("**How:** {}\n\n", arf.how.trim()));
+
+        let mut details: Vec<String> = Vec::new();
+        if !arf.tags.is_empty() {
+            details.push(format!("**Tags:** {}", code_list(&arf.tags)));
+        }
+        if !context.files.is_empty() {
+            details.push(format!("**Files:** {}", code_list(&context.files)));
+        }
+        if !context.commits.is_empty() {
+            let commits: Vec<String> = context
+                .commits
+                .iter()
+                .map(|c| short_hash(c).to_string())
+                .collect();
+            details.push(format!("**Commits:** {}", code_list(&commits)));
+        }
+        if !context.issues.is_empty() {
+            let links: Vec<String> = context.issues.iter().map(|i| markdown_link(i)).collect();
+            details.push(format!("**Issues:** {}", links.join(", ")));
+        }
+        if !context.people.is_empty() {
+            details.push(format!("**People:** {}", context.people.join(", ")));
+        }
+        if let Some(confidence) = arf.confidence {
+            details.push(format!("**Confidence:** {:.0}%", confidence * 100.0));
+        }
+        if let Some(updated) = arf.updated_at.or(arf.created_at) {
+            details.push(format!("**Updated:** {}", updated.format("%Y-%m-%d")));
+        }
+        for detail in details {
+            out.push_str(&format!("- {}\n", detail));
+        }
+
+        if !related[i].is_empty() {
+            out.push_str("\n**Related:**\n\n");
+            for &j in &related[i] {
+                let other = &entries[j];
+                out.push_str(&format!(
+                    "- [{}]({})\n",
+                    one_line(&other.arf.what),
+                    other.link_from(category)
+                ));
+            }
+        }
+        out.push_str(&format!(
+            "\n_Source: `.noggin/{}`{}_\n\n",
+            entry.rel,
+            if arf.approved { "" } else { " (not yet approved)" }
+        ));
+    }
+
+    out
+}
+
+/// `text` on a single line, for headings and link labels
+fn one_line(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn code_list(items: &[String]) -> String {
+    items
+        .iter()
+        .map(|item| format!("`{}`", item))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn capitalize(text: &str) -> String {
+    let mut chars = text.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+fn taplo_config(schema_path: &str) -> String {
+    format!(
+        "# Validate noggin ARF files against their schema.\n\
+         include = [\"**/*.toml\", \"**/*.arf\"]\n\
+         \n\
+         [[rule]]\n\
+         include = [\"**/*.arf\"]\n\
+         \n\
+         [rule.schema]\n\
+         path = \"{}\"\n",
+        schema_path
+    )
+}
+
+fn snippets() -> serde_json::Value {
+    json!({
+        "ARF entry": {
+            "prefix": "arf",
+            "scope": "toml",
+            "description": "A noggin knowledge entry",
+            "body": [
+                "what = \"${1:What this is}\"",
+                "why = \"${2:Why it is this way}\"",
+                "how = \"${3:How it is done}\"",
+                "tags = [${4}]",
+                "",
+                "[context]",
+                "files = [\"${5:path/to/file}\"]",
+                "$0"
+            ]
+        },
+        "ARF rule": {
+            "prefix": "arfrule",
+            "scope": "toml",
+            "description": "A rule evaluated by noggin check",
+            "body": [
+                "[[rules]]",
+                "kind = \"${1|forbid,require|}\"",
+                "pattern = \"${2:regex}\"",
+                "paths = [\"${3:src/**}\"]",
+                "message = \"${4:Why this matters}\"",
+                "$0"
+            ]
+        }
+    })
+}
+
+fn template(category: &str) -> String {
+    format!(
+        "# New {} entry. Save it under .noggin/{}/ and run `noggin check`.\n\
+         what = \"\"\n\
+         why = \"\"\n\
+         how = \"\"\n\
+         tags = []\n\
+         \n\
+         [context]\n\
+         files = []\n\
+         commits = []\n",
+        singular(category),
+        category
+    )
+}
+
+fn readme(schema_path: &str) -> String {
+    format!(
+        "# noggin editor support\n\
+         \n\
+         Generated by `noggin export --format editor`.\n\
+         \n\
+         - Copy `taplo.toml` to `.taplo.toml` at the repository root (or merge\n  \
+           its `[[rule]]` into an existing one). taplo and the Even Better TOML\n  \
+           extension then validate `.arf` files against `{}`.\n\
+         - VS Code: merge `vscode/settings.json` into `.vscode/settings.json` so\n  \
+           `.arf` opens as TOML, and copy `vscode/arf.code-snippets` into\n  \
+           `.vscode/` for the `arf` and `arfrule` snippets.\n\
+         - Editors using TextMate grammars (Sublime Text, TextMate, Zed) can\n  \
+           install `arf.tmLanguage.json`; it highlights `.arf` as TOML.\n\
+         - `templates/` holds an empty entry for each category.\n",
+        schema_path
+    )
+}
+
+fn singular(category: &str) -> &str {
+    category.strip_suffix('s').unwrap_or(category)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::learn::writer::WriteAction;
+    use crate::storage::FilesystemBackend;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_export_editor_bundle() {
+        let temp_dir = TempDir::new().unwrap();
+        let written = export_editor_bundle(temp_dir.path(), Path::new(".noggin/editor")).unwrap();
+        let bundle = temp_dir.path().join(".noggin/editor");
+
+        assert!(written.contains(&PathBuf::from(".noggin/editor/templates/decision.arf")));
+        let taplo: toml::Value =
+            toml::from_str(&fs::read_to_string(bundle.join("taplo.toml")).unwrap()).unwrap();
+        assert_eq!(
+            taplo["rule"][0]["schema"]["path"].as_str(),
+            Some(".noggin/editor/arf.schema.json")
+        );
+        for file in [
+            "arf.schema.json",
+            "arf.tmLanguage.json",
+            "vscode/arf.code-snippets",
+        ] {
+            let contents = fs::read_to_string(bundle.join(file)).unwrap();
+            serde_json::from_str::<serde_json::Value>(&contents).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_dry_run_plans_without_writing() {
+        let temp_dir = TempDir::new().unwrap();
+        let output = Path::new(".noggin/editor");
+        export_editor_bundle(temp_dir.path(), output).unwrap();
+        fs::write(temp_dir.path().join(".noggin/editor/taplo.toml"), "edited\n").unwrap();
+        fs::remove_file(temp_dir.path().join(".noggin/editor/README.md")).unwrap();
+
+        let planned = plan_files(temp_dir.path(), &editor_bundle(temp_dir.path(), output).unwrap());
+        let changed: Vec<(&str, WriteAction)> = planned
+            .iter()
+            .filter(|write| !write.diff.is_empty())
+            .map(|write| (write.path.as_str(), write.action))
+            .collect();
+        assert_eq!(
+            changed,
+            vec![
+                (".noggin/editor/taplo.toml", WriteAction::Update),
+                (".noggin/editor/README.md", WriteAction::Create),
+            ]
+        );
+        assert!(!temp_dir.path().join(".noggin/editor/README.md").exists());
+    }
+
+    #[test]
+    fn test_export_markdown() {
+        let temp_dir = TempDir::new().unwrap();
+        let noggin_path = temp_dir.path().join(".noggin");
+        let write = |rel: &str, arf: &ArfFile| {
+            let path = noggin_path.join(rel);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            arf.to_toml(&path).unwrap();
+        };
+
+        let mut pooling = ArfFile::new("Use connection pooling", "Limits", "deadpool");
+        pooling.add_file("src/db.rs");
+        pooling.add_issue("https://github.com/o/r/issues/12");
+        write("decisions/pooling.arf", &pooling);
+
+        let mut timeout = ArfFile::new("Connection timeout", "Pool exhausted", "Raise limit");
+        timeout.add_file("./src/db.rs");
+        write("bugs/timeout.arf", &timeout);
+
+        let mut shard = ArfFile::new("Shard", "A partition of the keyspace", "See src/shard.rs");
+        shard.tags = vec!["glossary".to_string()];
+        shard.context.extra.insert(
+            "related".to_string(),
+            toml::Value::String("decisions/pooling".to_string()),
+        );
+        write("facts/glossary/shard.arf", &shard);
+
+        let backend = FilesystemBackend::new(&noggin_path);
+        let written = export_markdown(temp_dir.path(), &backend, Path::new("site")).unwrap();
+        assert_eq!(
+            written,
+            vec![
+                PathBuf::from("site/index.md"),
+                PathBuf::from("site/decisions.md"),
+                PathBuf::from("site/bugs.md"),
+                PathBuf::from("site/facts.md"),
+            ]
+        );
+
+        let site = temp_dir.path().join("site");
+        let index = fs::read_to_string(site.join("index.md")).unwrap();
+        assert!(index.contains("- [Decisions](decisions.md) (1)"));
+        assert!(index.contains("- [Bugs](bugs.md) (1)"));
+
+        let decisions = fs::read_to_string(site.join("decisions.md")).unwrap();
+        assert!(decisions.contains("- [Use connection pooling](#pooling)"));
+        assert!(decisions.contains("<a id=\"pooling\"></a>"));
+        assert!(decisions.contains("- [Connection timeout](bugs.md#timeout)"));
+        assert!(decisions.contains("**Issues:** [#12](https://github.com/o/r/issues/12)"));
+        assert!(decisions.contains("(not yet approved)"));
+
+        let facts = fs::read_to_string(site.join("facts.md")).unwrap();
+        assert!(facts.contains("<a id=\"glossary-shard\"></a>"));
+        assert!(facts.contains("- [Use connection pooling](decisions.md#pooling)"));
+    }
+
+    #[test]
+    fn test_export_json() {
+        let temp_dir = TempDir::new().unwrap();
+        let noggin_path = temp_dir.path().join(".noggin");
+        fs::create_dir_all(noggin_path.join("decisions")).unwrap();
+
+        let mut pooling = ArfFile::new("Use connection pooling", "Limits", "deadpool");
+        pooling.add_file("src/db.rs");
+        pooling.context.extra.insert(
+            "related".to_string(),
+            toml::Value::String("bugs/timeout.arf".to_string()),
+        );
+        pooling.to_toml(&noggin_path.join("decisions/pooling.arf")).unwrap();
+        fs::write(noggin_path.join("decisions/broken.arf"), "not toml [").unwrap();
+
+        let backend = FilesystemBackend::new(&noggin_path);
+        let written =
+            export_json(temp_dir.path(), &backend, Path::new("out/knowledge.json")).unwrap();
+        assert_eq!(written, vec![PathBuf::from("out/knowledge.json")]);
+
+        let contents = fs::read_to_string(temp_dir.path().join("out/knowledge.json")).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&contents).unwrap();
+        assert_eq!(value["schema_version"], JSON_SCHEMA_VERSION);
+        assert_eq!(value["stats"]["entries"], 1);
+        assert_eq!(value["stats"]["categories"]["decisions"], 1);
+        assert_eq!(value["stats"]["categories"]["bugs"], 0);
+        assert_eq!(value["stats"]["files_scanned"], 0);
+
+        let entry = &value["entries"][0];
+        assert_eq!(entry["id"], "decisions/pooling");
+        assert_eq!(entry["files"], json!(["src/db.rs"]));
+        assert_eq!(entry["related"], json!(["bugs/timeout"]));
+        assert!(entry["confidence"].is_null());
+        assert_eq!(entry["issues"], json!([]));
+    }
+
+    #[test]
+    fn test_templates_are_arf_shaped() {
+        for category in CATEGORY_DIRS {
+            let errors = crate::schema::validate_arf_toml(&template(category)).unwrap();
+            // Only the empty what/why/how are left to fill in
+            assert_eq!(errors.len(), 3, "{}: {:?}", category, errors);
+        }
+    }
+}
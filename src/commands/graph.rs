@@ -0,0 +1,327 @@
+//! `noggin graph`: export how knowledge connects to code
+//!
+//! Builds a graph with a node per ARF, per file it cites (`context.files`),
+//! and per commit it cites (`context.commits`). An ARF may also list other
+//! entries under `related` (paths relative to .noggin/, with or without
+//! `.arf`), which become edges between ARFs. The graph is written as
+//! Graphviz DOT or as JSON for other visualization tools.
+
+use crate::arf::ArfFile;
+use crate::config::Config;
+use crate::storage::{self, KnowledgeBackend};
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+/// Commits cited with SHAs of different lengths share a node
+const COMMIT_ID_LEN: usize = 7;
+
+/// Longest ARF label, in characters
+const LABEL_LEN: usize = 60;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum GraphFormat {
+    /// Graphviz, e.g. `noggin graph | dot -Tsvg > graph.svg`
+    Dot,
+    Json,
+}
+
+/// Options for `noggin graph`
+#[derive(Debug, Clone)]
+pub struct GraphOptions {
+    pub format: GraphFormat,
+    /// File to write; stdout when unset
+    pub output: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NodeKind {
+    Arf,
+    File,
+    Commit,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Node {
+    /// `arf:<path>`, `file:<path>`, or `commit:<short sha>`
+    pub id: String,
+    pub kind: NodeKind,
+    pub label: String,
+    /// ARF category, for ARF nodes
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub category: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EdgeKind {
+    /// ARF cites a file
+    File,
+    /// ARF cites a commit
+    Commit,
+    /// ARF lists another ARF as related
+    Related,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub struct Edge {
+    pub from: String,
+    pub to: String,
+    pub kind: EdgeKind,
+}
+
+/// Nodes sorted by id, edges deduplicated and sorted
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct KnowledgeGraph {
+    pub nodes: Vec<Node>,
+    pub edges: Vec<Edge>,
+}
+
+pub fn graph_command(opts: GraphOptions) -> Result<()> {
+    let noggin_path = env::current_dir()?.join(".noggin");
+    if !noggin_path.exists() {
+        anyhow::bail!("Not initialized. Run 'noggin init' first.");
+    }
+
+    let config = Config::load(&noggin_path).context("Failed to load config")?;
+    let backend = storage::open(&noggin_path, &config.storage);
+    let graph = build_graph(backend.as_ref())?;
+    let contents = match opts.format {
+        GraphFormat::Dot => graph.to_dot(),
+        GraphFormat::Json => serde_json::to_string_pretty(&graph)? + "\n",
+    };
+
+    match opts.output {
+        Some(path) => {
+            fs::write(&path, contents)
+                .with_context(|| format!("Failed to write {}", path.display()))?;
+            eprintln!(
+                "Graph with {} nodes and {} edges written to {}",
+                graph.nodes.len(),
+                graph.edges.len(),
+                path.display()
+            );
+        }
+        None => print!("{}", contents),
+    }
+    Ok(())
+}
+
+/// Build the graph from every ARF in `backend`. Unparseable ARFs are
+/// skipped, as are `related` entries naming ARFs that don't exist.
+pub fn build_graph(backend: &dyn KnowledgeBackend) -> Result<KnowledgeGraph> {
+    let mut arfs = Vec::new();
+    for rel in backend.list_arfs()? {
+        let Ok(Some(arf)) = backend.read_arf(&rel) else {
+            continue;
+        };
+        arfs.push((rel, arf));
+    }
+
+    let known: BTreeSet<&str> = arfs.iter().map(|(rel, _)| rel.as_str()).collect();
+    let mut nodes: BTreeMap<String, Node> = BTreeMap::new();
+    let mut edges: BTreeSet<Edge> = BTreeSet::new();
+
+    for (rel, arf) in &arfs {
+        let arf_id = format!("arf:{}", rel);
+        nodes.insert(
+            arf_id.clone(),
+            Node {
+                id: arf_id.clone(),
+                kind: NodeKind::Arf,
+                label: truncate(&arf.what, LABEL_LEN),
+                category: rel.split('/').next().map(str::to_string),
+            },
+        );
+
+        for file in &arf.context.files {
+            let file = file.trim().trim_start_matches("./");
+            if file.is_empty() {
+                continue;
+            }
+            let id = format!("file:{}", file);
+            nodes.entry(id.clone()).or_insert_with(|| Node {
+                id: id.clone(),
+                kind: NodeKind::File,
+                label: file.to_string(),
+                category: None,
+            });
+            edges.insert(Edge {
+                from: arf_id.clone(),
+                to: id,
+                kind: EdgeKind::File,
+            });
+        }
+
+        for commit in &arf.context.commits {
+            let short: String = commit.trim().to_lowercase().chars().take(COMMIT_ID_LEN).collect();
+            if short.is_empty() {
+                continue;
+            }
+            let id = format!("commit:{}", short);
+            nodes.entry(id.clone()).or_insert_with(|| Node {
+                id: id.clone(),
+                kind: NodeKind::Commit,
+                label: short.clone(),
+                category: None,
+            });
+            edges.insert(Edge {
+                from: arf_id.clone(),
+                to: id,
+                kind: EdgeKind::Commit,
+            });
+        }
+
+        for related in related_ids(arf) {
+            let target = if related.ends_with(".arf") {
+                related
+            } else {
+                format!("{}.arf", related)
+            };
+            if target != *rel && known.contains(target.as_str()) {
+                edges.insert(Edge {
+                    from: arf_id.clone(),
+                    to: format!("arf:{}", target),
+                    kind: EdgeKind::Related,
+                });
+            }
+        }
+    }
+
+    Ok(KnowledgeGraph {
+        nodes: nodes.into_values().collect(),
+        edges: edges.into_iter().collect(),
+    })
+}
+
+/// Entries listed under an ARF's `related` key, a string or an array
+pub(crate) fn related_ids(arf: &ArfFile) -> Vec<String> {
+    let ids = match arf.context.extra.get("related") {
+        Some(toml::Value::String(id)) => vec![id.clone()],
+        Some(toml::Value::Array(values)) => values
+            .iter()
+            .filter_map(|value| value.as_str().map(str::to_string))
+            .collect(),
+        _ => Vec::new(),
+    };
+    ids.into_iter()
+        .map(|id| id.trim().trim_start_matches(".noggin/").to_string())
+        .filter(|id| !id.is_empty())
+        .collect()
+}
+
+fn truncate(text: &str, max: usize) -> String {
+    let text = text.trim();
+    if text.chars().count() <= max {
+        return text.to_string();
+    }
+    let cut: String = text.chars().take(max.saturating_sub(1)).collect();
+    format!("{}…", cut.trim_end())
+}
+
+impl KnowledgeGraph {
+    /// Graphviz DOT: ARFs are boxes, files notes, commits ellipses
+    pub fn to_dot(&self) -> String {
+        let mut out = String::from("digraph noggin {\n    rankdir=LR;\n");
+        for node in &self.nodes {
+            let shape = match node.kind {
+                NodeKind::Arf => "box",
+                NodeKind::File => "note",
+                NodeKind::Commit => "ellipse",
+            };
+            out.push_str(&format!(
+                "    \"{}\" [label=\"{}\", shape={}];\n",
+                escape_dot(&node.id),
+                escape_dot(&node.label),
+                shape
+            ));
+        }
+        for edge in &self.edges {
+            let style = match edge.kind {
+                EdgeKind::File => "solid",
+                EdgeKind::Commit => "dashed",
+                EdgeKind::Related => "bold",
+            };
+            out.push_str(&format!(
+                "    \"{}\" -> \"{}\" [style={}];\n",
+                escape_dot(&edge.from),
+                escape_dot(&edge.to),
+                style
+            ));
+        }
+        out.push_str("}\n");
+        out
+    }
+}
+
+fn escape_dot(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', " ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::MemoryBackend;
+
+    #[test]
+    fn test_build_graph() {
+        let backend = MemoryBackend::default();
+
+        let mut pooling = ArfFile::new("Use connection pooling", "Limits", "deadpool");
+        pooling.add_file("src/db.rs");
+        pooling.add_file("./src/db.rs");
+        pooling.add_commit("ABC1234def");
+        pooling.context.extra.insert(
+            "related".to_string(),
+            toml::Value::Array(vec!["bugs/timeout".into(), "bugs/missing".into()]),
+        );
+        backend.write_arf("decisions/pooling.arf", &pooling).unwrap();
+
+        let mut timeout = ArfFile::new("Connection timeout", "Pool exhausted", "Raise limit");
+        timeout.add_file("src/db.rs");
+        timeout.add_commit("abc1234");
+        backend.write_arf("bugs/timeout.arf", &timeout).unwrap();
+
+        let graph = build_graph(&backend).unwrap();
+
+        let ids: Vec<&str> = graph.nodes.iter().map(|n| n.id.as_str()).collect();
+        assert_eq!(
+            ids,
+            vec![
+                "arf:bugs/timeout.arf",
+                "arf:decisions/pooling.arf",
+                "commit:abc1234",
+                "file:src/db.rs",
+            ]
+        );
+        assert_eq!(graph.nodes[1].category.as_deref(), Some("decisions"));
+        assert_eq!(graph.edges.len(), 5);
+        assert!(graph.edges.contains(&Edge {
+            from: "arf:decisions/pooling.arf".to_string(),
+            to: "arf:bugs/timeout.arf".to_string(),
+            kind: EdgeKind::Related,
+        }));
+    }
+
+    #[test]
+    fn test_to_dot_escapes_labels() {
+        let graph = KnowledgeGraph {
+            nodes: vec![Node {
+                id: "arf:facts/quote.arf".to_string(),
+                kind: NodeKind::Arf,
+                label: "Say \"hi\"".to_string(),
+                category: Some("facts".to_string()),
+            }],
+            edges: Vec::new(),
+        };
+
+        let dot = graph.to_dot();
+        assert!(dot.starts_with("digraph noggin {"));
+        assert!(dot.contains("[label=\"Say \\\"hi\\\"\", shape=box]"));
+        assert_eq!(truncate("abcdef", 4), "abc…");
+    }
+}
@@ -0,0 +1,65 @@
+//! `noggin grep`: structured search over ARF fields without a model
+//!
+//! Evaluates a field-scoped expression (see [`crate::grep`]) against every
+//! indexed ARF and prints the matches, or their paths alone for scripts.
+
+use crate::config::Config;
+use crate::grep::{search, Expr};
+use crate::storage;
+use anyhow::{Context, Result};
+use colored::Colorize;
+use std::env;
+
+/// Options for `noggin grep`
+#[derive(Debug, Clone, Default)]
+pub struct GrepOptions {
+    /// Search expression, e.g. `what:pooling AND category:decisions`
+    pub expression: String,
+    /// Most matches to print; all when unset
+    pub limit: Option<usize>,
+    /// Print only the matching ARF paths
+    pub files_only: bool,
+    pub json: bool,
+}
+
+pub fn grep_command(opts: GrepOptions) -> Result<()> {
+    let repo_path = env::current_dir()?;
+    let noggin_path = repo_path.join(".noggin");
+    if !noggin_path.exists() {
+        anyhow::bail!("Not initialized. Run 'noggin init' first.");
+    }
+
+    let config = Config::load(&noggin_path).context("Failed to load config")?;
+    let backend = storage::open(&noggin_path, &config.storage);
+    let expr = Expr::parse(&opts.expression)?;
+    let mut results = search(&noggin_path, backend.as_ref(), &expr)?;
+    if let Some(limit) = opts.limit {
+        results.truncate(limit);
+    }
+
+    if opts.json {
+        println!("{}", serde_json::to_string_pretty(&results)?);
+        return Ok(());
+    }
+
+    if opts.files_only {
+        for result in &results {
+            println!(".noggin/{}", result.file_path);
+        }
+        return Ok(());
+    }
+
+    if results.is_empty() {
+        println!("No entries match \"{}\"", opts.expression);
+        return Ok(());
+    }
+    for result in &results {
+        println!(
+            "{} {} {}",
+            format!(".noggin/{}", result.file_path).dimmed(),
+            result.what.cyan(),
+            format!("[{}]", result.matched_fields.join(", ")).dimmed()
+        );
+    }
+    Ok(())
+}
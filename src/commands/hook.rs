@@ -0,0 +1,58 @@
+use crate::config::Config;
+use crate::git::hooks::{append_trailers, install_hook, staged_files};
+use crate::query::linked_arfs;
+use crate::storage;
+use anyhow::{Context, Result};
+use std::env;
+use std::fs;
+use std::path::Path;
+
+/// Trailer key listing ARFs whose files a commit touches
+pub const TRAILER_KEY: &str = "Noggin-Knowledge";
+
+const HOOK_NAME: &str = "prepare-commit-msg";
+
+/// Install the prepare-commit-msg hook into .git/hooks
+pub fn hook_install_command(force: bool) -> Result<()> {
+    let repo_path = env::current_dir()?;
+    let path = install_hook(&repo_path, HOOK_NAME, force)?;
+    println!("Installed {}", path.display());
+    Ok(())
+}
+
+/// Run as git's prepare-commit-msg hook.
+///
+/// `source` is git's second hook argument; merge and squash messages are
+/// left alone. Does nothing when the repository has no .noggin/.
+pub fn prepare_commit_msg_command(message_file: &Path, source: Option<&str>) -> Result<()> {
+    if matches!(source, Some("merge") | Some("squash")) {
+        return Ok(());
+    }
+
+    let repo_path = env::current_dir()?;
+    let noggin_path = repo_path.join(".noggin");
+    if !noggin_path.exists() {
+        return Ok(());
+    }
+
+    let config = Config::load(&noggin_path).context("Failed to load config")?;
+    let backend = storage::open(&noggin_path, &config.storage);
+    let staged = staged_files(&repo_path)?;
+    let affected: Vec<String> = linked_arfs(backend.as_ref(), &staged)?
+        .into_iter()
+        .map(|(path, _)| path)
+        .collect();
+    if affected.is_empty() {
+        return Ok(());
+    }
+
+    let message = fs::read_to_string(message_file)
+        .with_context(|| format!("Failed to read {}", message_file.display()))?;
+    let updated = append_trailers(&message, TRAILER_KEY, &affected);
+    if updated != message {
+        fs::write(message_file, updated)
+            .with_context(|| format!("Failed to write {}", message_file.display()))?;
+    }
+
+    Ok(())
+}
@@ -1,5 +1,10 @@
+use crate::commands::doctor::{locate_providers, print_binaries, BinaryStatus};
+use crate::config::Config;
+use crate::learn::survey::{survey, Estimate, Survey};
 use anyhow::{Context, Result};
+use colored::Colorize;
 use std::fs;
+use std::io::{BufRead, IsTerminal, Write};
 use std::path::Path;
 
 const NOGGIN_DIR: &str = ".noggin";
@@ -14,7 +19,16 @@ const MANIFEST_TEMPLATE: &str = r#"# Noggin manifest - tracks analyzed files and
 # Format: "commit-hash" = { processed = "YYYY-MM-DD", category = "decision|migration|bug", arf = "path/to/file.arf" }
 "#;
 
-pub fn init_command() -> Result<()> {
+/// Options for `noggin init`
+#[derive(Debug, Clone, Default)]
+pub struct InitOptions {
+    /// Survey the repository afterwards without asking
+    pub guide: bool,
+    /// Don't offer the survey
+    pub no_guide: bool,
+}
+
+pub fn init_command(opts: InitOptions) -> Result<()> {
     let noggin_path = Path::new(NOGGIN_DIR);
 
     if noggin_path.exists() {
@@ -23,23 +37,21 @@ pub fn init_command() -> Result<()> {
         );
     }
 
-    fs::create_dir(noggin_path)
-        .context("Failed to create .noggin/ directory")?;
+    create_knowledge_base(noggin_path)?;
 
     println!("Created .noggin/ directory");
-
     for subdir in SUBDIRS {
-        let subdir_path = noggin_path.join(subdir);
-        fs::create_dir(&subdir_path)
-            .with_context(|| format!("Failed to create {} directory", subdir))?;
         println!("  Created .noggin/{}/", subdir);
     }
-
-    let manifest_path = noggin_path.join("manifest.toml");
-    fs::write(&manifest_path, MANIFEST_TEMPLATE)
-        .context("Failed to create manifest.toml")?;
     println!("  Created .noggin/manifest.toml");
 
+    // Absolute paths keep providers working when run from cron or a GUI
+    let binaries = locate_providers(noggin_path)?;
+    if binaries.iter().any(|b| b.status == BinaryStatus::Recorded) {
+        println!("  Recorded provider paths in .noggin/config.toml");
+    }
+    print_binaries(&binaries);
+
     let gitignore_path = Path::new(".gitignore");
     if gitignore_path.exists() {
         let gitignore_content = fs::read_to_string(gitignore_path)
@@ -63,7 +75,123 @@ pub fn init_command() -> Result<()> {
     }
 
     println!("\n✓ Noggin initialized successfully!");
-    println!("Run 'noggin learn' to start analyzing your codebase.");
+
+    // Offered on a terminal; scripts opt in with --guide
+    let guide = !opts.no_guide
+        && (opts.guide
+            || (std::io::stdin().is_terminal()
+                && confirm(
+                    &mut std::io::stdin().lock(),
+                    &mut std::io::stdout(),
+                    "Survey the repository to plan a first learn? No provider is queried.",
+                )?));
+    if !guide {
+        println!("Run 'noggin learn' to start analyzing your codebase.");
+        return Ok(());
+    }
+
+    let config = Config::load(noggin_path).context("Failed to load config")?;
+    match survey(Path::new("."), &config) {
+        Ok(survey) => print_survey(&survey),
+        Err(e) => {
+            eprintln!("Survey failed: {:#}", e);
+            println!("Run 'noggin learn' to start analyzing your codebase.");
+        }
+    }
+    Ok(())
+}
+
+/// Ask a yes/no question, yes by default; end of input means no
+fn confirm(input: &mut impl BufRead, output: &mut impl Write, question: &str) -> Result<bool> {
+    write!(output, "\n{} [Y/n] ", question)?;
+    output.flush()?;
+    let mut line = String::new();
+    if input.read_line(&mut line)? == 0 {
+        writeln!(output)?;
+        return Ok(false);
+    }
+    Ok(matches!(line.trim().to_lowercase().as_str(), "" | "y" | "yes"))
+}
+
+fn print_survey(survey: &Survey) {
+    println!("\n{}", "Survey".bold());
+    println!(
+        "  Files:    {} to analyze, {} generated (tracked only)",
+        survey.files, survey.generated
+    );
+    if !survey.stack.is_empty() {
+        println!("  Stack:    {}", survey.stack.summary());
+    }
+    println!(
+        "  History:  {} commits, {} significant",
+        survey.commits, survey.significant
+    );
+    if survey.sampled < survey.commits {
+        println!(
+            "            {}",
+            format!("extrapolated from the oldest {} commits", survey.sampled).dimmed()
+        );
+    }
+
+    if !survey.top_commits.is_empty() {
+        println!("\n{}", "Most significant commits".bold());
+        for commit in &survey.top_commits {
+            println!(
+                "  {} {:.2} {:<8} {}",
+                commit.short_hash.dimmed(),
+                commit.significance,
+                commit.category,
+                commit.summary
+            );
+        }
+    }
+
+    println!();
+    if survey.providers.is_empty() {
+        println!("No providers are enabled; set llm.providers in .noggin/config.toml.");
+    } else {
+        println!(
+            "A full learn would send {} to {}",
+            describe(&survey.full),
+            survey.providers.join(", ")
+        );
+    }
+    println!("{}", "Start smaller:".bold());
+    println!(
+        "  {} {}",
+        format!("{:<48}", survey.starter.command()).cyan(),
+        format!("at most {}", describe(&survey.starter.estimate)).dimmed()
+    );
+    println!(
+        "  {} {}",
+        format!("{:<48}", "noggin learn --lite").cyan(),
+        "structural facts only, no provider".dimmed()
+    );
+}
+
+/// "12 files and 8 commits, ~41000 tokens, ~$0.41"
+fn describe(estimate: &Estimate) -> String {
+    format!(
+        "{} files and {} commits, ~{} tokens, ~${:.2}",
+        estimate.files, estimate.commits, estimate.tokens, estimate.cost_usd
+    )
+}
+
+/// Create an empty knowledge base (category directories and manifest)
+/// at `noggin_path`, which must not exist yet
+pub fn create_knowledge_base(noggin_path: &Path) -> Result<()> {
+    fs::create_dir_all(noggin_path)
+        .with_context(|| format!("Failed to create {}", noggin_path.display()))?;
+
+    for subdir in SUBDIRS {
+        let subdir_path = noggin_path.join(subdir);
+        fs::create_dir(&subdir_path)
+            .with_context(|| format!("Failed to create {} directory", subdir))?;
+    }
+
+    let manifest_path = noggin_path.join("manifest.toml");
+    fs::write(&manifest_path, MANIFEST_TEMPLATE)
+        .context("Failed to create manifest.toml")?;
 
     Ok(())
 }
@@ -81,7 +209,7 @@ mod tests {
         
         std::env::set_current_dir(temp_dir.path()).unwrap();
 
-        let result = init_command();
+        let result = init_command(InitOptions { no_guide: true, ..Default::default() });
         if let Err(e) = &result {
             eprintln!("init_command failed: {}", e);
         }
@@ -120,7 +248,7 @@ mod tests {
 
         fs::create_dir(".noggin").unwrap();
 
-        let result = init_command();
+        let result = init_command(InitOptions { no_guide: true, ..Default::default() });
         assert!(result.is_err());
         assert!(result.unwrap_err().to_string().contains("already exists"));
 
@@ -136,7 +264,7 @@ mod tests {
 
         fs::write(".gitignore", "*.log\ntarget/\n").unwrap();
 
-        init_command().unwrap();
+        init_command(InitOptions { no_guide: true, ..Default::default() }).unwrap();
 
         let gitignore_content = fs::read_to_string(".gitignore").unwrap();
         assert!(gitignore_content.contains("*.log"));
@@ -145,4 +273,16 @@ mod tests {
 
         std::env::set_current_dir(original_dir).unwrap();
     }
+
+    #[test]
+    fn test_confirm_defaults_to_yes() {
+        let answer = |input: &str| {
+            let mut output = Vec::new();
+            confirm(&mut std::io::Cursor::new(input), &mut output, "Survey?").unwrap()
+        };
+        assert!(answer("\n"));
+        assert!(answer("Y\n"));
+        assert!(!answer("n\n"));
+        assert!(!answer(""));
+    }
 }
@@ -1,43 +1,451 @@
 //! Learn command: orchestrates the full codebase analysis pipeline.
 //!
 //! Scans files, walks git history, invokes LLMs in parallel,
-//! synthesizes consensus, writes ARF files, and updates the manifest.
+//! synthesizes consensus, writes ARF files, and updates the manifest
+//! and the semantic search index.
 //!
 //! In incremental mode (default), only changed files and new commits are
 //! processed. Patterns referencing changed files are invalidated and
 //! re-analyzed. Deleted files are cleaned from the manifest.
-
+//!
+//! Provider usage is capped by the `[budget]` config section, and by
+//! daily caps shared across repositories on the machine (see
+//! [`crate::learn::ledger`]). Once a cap would be exceeded, remaining prompts are deferred to a checkpoint and
+//! their files/commits are left unrecorded so the next run retries them.
+//! Prompts that no provider answers usably are checkpointed the same way,
+//! and make the command exit non-zero once the rest of the run is saved.
+//!
+//! The manifest is bound to the repository it was built from. A run
+//! against a different repository (or a fork) is refused unless
+//! `force_adopt` rebinds it.
+//!
+//! With `dry_run`, providers are still queried but nothing under the
+//! knowledge base (ARFs, manifest, checkpoint, notes) is written; the
+//! report lists each planned write with its diff.
+//!
+//! Every other run holds `.noggin/lock` (see [`crate::lock`]) from start to
+//! finish, so a second learn, or any other command that writes, fails
+//! fast instead of interleaving with it.
+//!
+//! Each provider response is kept under `.noggin/raw/` for debugging
+//! unless `retention.store_raw_responses` is off, and retention limits
+//! are applied at the end of every run that writes.
+//!
+//! With `--lite`, no provider is configured or queried: deterministic
+//! analyzers (see [`crate::learn::lite`]) produce structural facts in
+//! place of model output, and the manifest is updated as usual.
+//!
+//! With `--remote`, the repository is cloned into the user cache and the
+//! knowledge base is written to a local output directory instead.
+
+use crate::arf::ArfFile;
+use crate::commands::init::create_knowledge_base;
+use crate::conflicts::VoteConflict;
+use crate::config::{Config, LlmConfig, PeopleConfig};
+use crate::git::blame::{Blame, AUTHORS_KEY};
+use crate::git::diff::{changed_paths, commit_patch, resolve_range};
+use crate::git::fingerprint::RepositoryFingerprint;
+use crate::git::remote::{clone_or_update, default_cache_dir, repo_name};
+use crate::git::notes::{write_knowledge_note, NoteEntry};
+use crate::git::releases::{group_by_release, release_tags, ReleaseGroup, RELEASE_KEY};
 use crate::git::scoring::{score_commit, ScoreCategory, ScoringConfig};
-use crate::git::walker::{walk_commits, WalkOptions};
+use crate::git::walker::{walk_commits, CommitMetadata, WalkOptions};
+use crate::index::embed::HashingEmbedder;
+use crate::index::SemanticIndex;
+use crate::issues;
+use crate::retention;
+use crate::learn::budget::{estimate_tokens, Budget, BudgetLimit, CostReport, ProviderUsage};
+use crate::learn::ledger::{default_config_dir, MachineBudget};
+use crate::learn::checkpoint::{Checkpoint, DeferredPrompt};
+use crate::learn::events::{Event, EventSink};
+use crate::learn::history::RunRecord;
+use crate::learn::language::{
+    apply_translation, build_translation_prompt, language_instruction, matches_language,
+};
 use crate::learn::prompts::{
-    build_commit_analysis_prompt, build_file_analysis_prompt,
-    build_pattern_reanalysis_prompt,
+    build_commit_analysis_prompt, build_file_analysis_prompts, build_glossary_prompt,
+    build_pattern_reanalysis_prompt, build_release_analysis_prompt, TruncationStats,
+    COMMITS_PROMPT, FILES_PROMPT, PATTERNS_PROMPT, RELEASES_PROMPT,
 };
-use crate::learn::scanner::{scan_files, FileToAnalyze};
-use crate::learn::writer::write_arfs;
-use crate::llm::claude::ClaudeClient;
-use crate::llm::codex::CodexClient;
-use crate::llm::gemini::GeminiClient;
-use crate::llm::parallel::query_all;
-use crate::llm::LLMProvider;
-use crate::manifest::{CommitCategory, Manifest};
-use crate::synthesis::{self, ModelOutput};
+use crate::learn::generated::GeneratedFiles;
+use crate::learn::glossary::{self, Term, GLOSSARY_PROMPT};
+use crate::learn::scanner::{scan_files, scan_tree, FileToAnalyze};
+use crate::learn::stack::{self, prompt_context};
+use crate::learn::tokens::PromptBudget;
+use crate::learn::lite;
+use crate::learn::writer::{apply_arfs, rename_cited_files, PlannedWrite, WriteOptions, WriteResult};
+use crate::llm::stream::ignore_chunks;
+use crate::llm::{configured_providers, LLMProvider};
+use crate::llm::parallel::{query_all_streaming, ParallelResult, Scheduler};
+use crate::lock::KnowledgeLock;
+use crate::manifest::{calculate_file_hash, CommitCategory, Manifest, TruncatedHistory};
+use crate::metrics::{record_learn, LearnSample, Metrics};
+use crate::storage::{self, KnowledgeBackend};
+use crate::storage::dry_run::DryRunBackend;
+use crate::policy::NeverSend;
+use crate::query::{parse_since, parse_until};
+use crate::synthesis::merger::ArfCategory;
+use crate::templates::Templates;
+use crate::synthesis::{self, ModelOutput, PromptOutputs};
 use anyhow::{Context, Result};
+use chrono::Utc;
+use git2::{Pathspec, PathspecFlags};
 use indicatif::{ProgressBar, ProgressStyle};
-use std::collections::HashSet;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::env;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tempfile::TempDir;
 use tracing::info;
 
+/// Options controlling a learn run
+#[derive(Debug, Clone, Default)]
+pub struct LearnOptions {
+    /// Ignore the manifest and re-analyze everything
+    pub full: bool,
+    /// Show what would be done without writing anything
+    pub verify: bool,
+    /// Run the analysis but only report what would be written
+    pub dry_run: bool,
+    /// Override `budget.max_cost_usd` from config
+    pub max_cost: Option<f64>,
+    /// Override `budget.max_time_secs` from config
+    pub max_time: Option<u64>,
+    /// Hide spinners and progress output (for CI and other non-TTY callers)
+    pub quiet: bool,
+    /// Process at most this many significant commits, oldest first; the
+    /// rest stay unprocessed for a later run
+    pub max_commits: Option<usize>,
+    /// Extra pattern IDs to re-analyze even if their files are unchanged
+    pub refresh_patterns: Vec<String>,
+    /// Extra files to re-analyze even if unchanged
+    pub refresh_files: Vec<String>,
+    /// Write git notes on processed commits even if `notes.enabled` is off
+    pub notes: bool,
+    /// Rebind a knowledge base whose fingerprint belongs to another repository
+    pub force_adopt: bool,
+    /// Knowledge base location; defaults to `<repo>/.noggin`
+    pub knowledge_dir: Option<PathBuf>,
+    /// Refs to walk, added to `history.refs` from config
+    pub refs: Vec<String>,
+    /// Walk every branch even if `history.all_branches` is off
+    pub all_branches: bool,
+    /// Restrict files and commits to these git pathspecs (e.g. a monorepo
+    /// subtree); empty means the whole repository
+    pub paths: Vec<String>,
+    /// Only walk the commits in this `base..head` range and scan the files
+    /// it changed, e.g. a pull request; replaces the configured refs
+    pub range: Option<String>,
+    /// Only walk commits by authors matching one of these (see
+    /// [`WalkOptions::authors`])
+    pub authors: Vec<String>,
+    /// Only walk commits authored since this date (see
+    /// [`parse_since`](crate::query::parse_since))
+    pub since_date: Option<String>,
+    /// Only walk commits authored up to this date (see
+    /// [`parse_until`](crate::query::parse_until))
+    pub until_date: Option<String>,
+    /// Skip providers; build structural facts with deterministic analyzers
+    pub lite: bool,
+    /// Write JSONL progress events here; `-` for stdout (see
+    /// [`crate::learn::events`])
+    pub events: Option<PathBuf>,
+    /// Analyze commits one release at a time even if
+    /// `history.group_by_release` is off
+    pub by_release: bool,
+}
+
+/// Where to fetch a repository analyzed by `learn --remote`
+#[derive(Debug, Clone)]
+pub struct RemoteOptions {
+    pub url: String,
+    /// Commits to fetch; 0 fetches full history
+    pub depth: u32,
+    /// Knowledge base directory; defaults to `./<repo-name>.noggin`
+    pub output: Option<PathBuf>,
+    /// Clone cache; defaults to `~/.cache/noggin/repos`
+    pub cache_dir: Option<PathBuf>,
+}
+
+/// Structured outcome of a learn run
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct LearnReport {
+    /// "full", "incremental", or "lite"
+    pub mode: String,
+    /// True when there was nothing to learn
+    pub up_to_date: bool,
+    /// Nothing was written; `planned` lists what would have been
+    pub dry_run: bool,
+    /// Pending work found in verify mode (nothing was written)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub drift: Option<DriftReport>,
+    pub files_analyzed: usize,
+    /// Generated files re-hashed for drift but not analyzed
+    pub files_generated: usize,
+    pub files_deleted: usize,
+    /// Tracked files found under a new path
+    pub files_renamed: usize,
+    /// Changed files identical to another, covered by its analysis
+    pub files_duplicate: usize,
+    pub commits_processed: usize,
+    /// Significant commits left for a later run by `max_commits`
+    pub commits_remaining: usize,
+    /// Repository is a shallow clone
+    pub shallow: bool,
+    /// Shallow boundary commits skipped because their diffs are unknown
+    pub shallow_boundary_commits: usize,
+    /// An earlier run saw truncated history and this one sees all of it,
+    /// so commits older than its boundaries are being backfilled
+    pub history_backfill: bool,
+    pub patterns_invalidated: usize,
+    pub arf_entries: usize,
+    /// Entries each prompt type produced, before cross-prompt dedup
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub entries_by_prompt_type: BTreeMap<String, usize>,
+    /// ARF files written or updated, relative to .noggin/
+    pub arfs_written: Vec<String>,
+    /// Conflict records created for ARFs that contradict approved ones
+    pub conflicts: Vec<String>,
+    /// Conflict records for fields voting could not settle
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub unresolved: Vec<String>,
+    /// New entries over `size.max_new_per_run`, held in `.noggin/pending/`
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub pending: Vec<String>,
+    /// New entries missing fields their category template requires, held
+    /// in `.noggin/pending/`
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub incomplete: Vec<String>,
+    /// Writes a dry run skipped, with diffs
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub planned: Vec<PlannedWrite>,
+    /// Commits that received a knowledge note
+    pub notes_written: usize,
+    pub usage: UsageSummary,
+    /// Content cut from each prompt that was sent, to fit per-prompt caps
+    pub truncation: Vec<PromptTruncation>,
+    /// Work deferred by a budget cap
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deferred: Option<Checkpoint>,
+    pub warnings: Vec<String>,
+    /// Run record saved under .noggin/reports/, relative to .noggin/
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub run_record: Option<String>,
+}
+
+/// Pending work detected by verify mode
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DriftReport {
+    pub changed_files: Vec<DriftFile>,
+    pub deleted_files: Vec<String>,
+    /// `old -> new` for each tracked file that moved
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub renamed_files: Vec<String>,
+    pub unprocessed_commits: Vec<DriftCommit>,
+    pub invalidated_patterns: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DriftFile {
+    pub path: String,
+    /// "new" or "modified"
+    pub status: String,
+    /// Generated code, which is tracked but not analyzed
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub generated: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DriftCommit {
+    pub hash: String,
+    pub short_hash: String,
+    pub summary: String,
+}
+
+/// Provider usage counted against the budget
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct UsageSummary {
+    pub requests: u32,
+    pub tokens: u64,
+    pub estimated_cost_usd: f64,
+    /// The same, broken down by provider name
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub providers: BTreeMap<String, ProviderUsage>,
+    /// Cost report saved under .noggin/reports/, relative to .noggin/
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub report_path: Option<String>,
+}
+
+/// Truncation recorded for one prompt sent to the providers
+#[derive(Debug, Clone, Serialize)]
+pub struct PromptTruncation {
+    pub prompt_type: String,
+    #[serde(flatten)]
+    pub stats: TruncationStats,
+}
+
+/// A built prompt plus the work items it covers
+struct PendingPrompt {
+    prompt_type: String,
+    prompt: String,
+    truncation: TruncationStats,
+    files: Vec<String>,
+    commits: Vec<String>,
+    patterns: Vec<String>,
+}
+
 /// Run the learn command.
 ///
 /// If `full` is true, ignores the manifest and re-analyzes everything.
 /// If `verify` is true, shows what would be done without writing anything.
 /// Returns Ok(()) on success. In verify mode, returns an error if drift
 /// is detected (for use as a CI check).
-pub async fn learn_command(full: bool, verify: bool) -> Result<()> {
+pub async fn learn_command(opts: LearnOptions) -> Result<()> {
     let repo_path = env::current_dir()?;
-    let noggin_path = repo_path.join(".noggin");
+    // Events on stdout replace the human-readable output
+    let events_on_stdout = opts.events.as_deref() == Some(Path::new("-"));
+    let opts = LearnOptions {
+        quiet: opts.quiet || events_on_stdout,
+        ..opts
+    };
+    let report = run_learn(&repo_path, &opts).await?;
+    if events_on_stdout {
+        return check_quiet_report(&report, "noggin learn");
+    }
+
+    if let Some(drift) = &report.drift {
+        print_drift(drift);
+        anyhow::bail!("Drift detected. Run 'noggin learn' to update.");
+    }
+
+    if report.up_to_date {
+        println!("Nothing to learn. Codebase is up to date.");
+        print_warnings(&report.warnings);
+        return Ok(());
+    }
+
+    print_summary(&report);
+    check_failed_prompts(&report)
+}
+
+/// The exit status of a run whose human-readable output was replaced by
+/// events on stdout; `rerun` is the command that brings drift up to date
+fn check_quiet_report(report: &LearnReport, rerun: &str) -> Result<()> {
+    if report.drift.is_some() {
+        anyhow::bail!("Drift detected. Run '{}' to update.", rerun);
+    }
+    check_failed_prompts(report)
+}
+
+/// Fail once a run is saved if prompts got no usable response, so scripts
+/// notice; their work is in the checkpoint for the next run
+fn check_failed_prompts(report: &LearnReport) -> Result<()> {
+    let failed = report.deferred.as_ref().map_or(0, |c| c.failed.len());
+    if failed > 0 {
+        anyhow::bail!(
+            "{} prompts got no usable response; their work is left for the next run",
+            failed
+        );
+    }
+    Ok(())
+}
+
+/// Clone (or refresh) a remote repository and learn from it, writing the
+/// knowledge base to a local directory that is created if missing.
+pub async fn learn_remote_command(remote: RemoteOptions, opts: LearnOptions) -> Result<()> {
+    let cache_dir = match remote.cache_dir {
+        Some(dir) => dir,
+        None => default_cache_dir()?,
+    };
+    let output = remote
+        .output
+        .unwrap_or_else(|| PathBuf::from(format!("{}.noggin", repo_name(&remote.url))));
+
+    // Events on stdout replace the human-readable output, as for local runs
+    let events_on_stdout = opts.events.as_deref() == Some(Path::new("-"));
+    if !events_on_stdout {
+        println!("Fetching {}...", remote.url);
+    }
+    let repo_path = clone_or_update(&remote.url, &cache_dir, remote.depth)?;
+
+    if !output.exists() {
+        if opts.dry_run {
+            anyhow::bail!(
+                "{} does not exist; run without --dry-run to create it.",
+                output.display()
+            );
+        }
+        create_knowledge_base(&output)?;
+        if !events_on_stdout {
+            println!("Created knowledge base at {}", output.display());
+        }
+    }
+    let output = output
+        .canonicalize()
+        .with_context(|| format!("Failed to resolve {}", output.display()))?;
+
+    let opts = LearnOptions {
+        knowledge_dir: Some(output.clone()),
+        quiet: opts.quiet || events_on_stdout,
+        ..opts
+    };
+    let report = run_learn(&repo_path, &opts).await?;
+    if events_on_stdout {
+        return check_quiet_report(&report, &format!("noggin learn --remote {}", remote.url));
+    }
+
+    if let Some(drift) = &report.drift {
+        print_drift(drift);
+        anyhow::bail!("Drift detected. Run 'noggin learn --remote {}' to update.", remote.url);
+    }
+
+    if report.up_to_date {
+        println!("Nothing to learn. {} is up to date.", output.display());
+        print_warnings(&report.warnings);
+        return Ok(());
+    }
+
+    print_summary(&report);
+    println!("  Knowledge base:        {}", output.display());
+    check_failed_prompts(&report)
+}
+
+/// Run the learn pipeline against `repo_path` and return a structured report.
+///
+/// In verify mode nothing is written and `drift` is populated when there
+/// is pending work. Printing is left to the caller, apart from progress
+/// spinners when `quiet` is false and the event stream when `events` is set.
+pub async fn run_learn(repo_path: &Path, opts: &LearnOptions) -> Result<LearnReport> {
+    let events = match &opts.events {
+        Some(path) => EventSink::open(path)?,
+        None => EventSink::default(),
+    };
+    let result = learn_pipeline(repo_path, opts, &events).await;
+    events.emit(match &result {
+        Ok(report) => Event::RunFinished {
+            up_to_date: report.up_to_date,
+            entries_written: report.arfs_written.len(),
+            warnings: report.warnings.len(),
+        },
+        Err(e) => Event::RunFailed {
+            error: format!("{:#}", e),
+        },
+    });
+    result
+}
+
+async fn learn_pipeline(
+    repo_path: &Path,
+    opts: &LearnOptions,
+    events: &EventSink,
+) -> Result<LearnReport> {
+    let LearnOptions { full, verify, dry_run, quiet, lite, .. } = *opts;
+    let repo_path = repo_path.to_path_buf();
+    let noggin_path = opts
+        .knowledge_dir
+        .clone()
+        .unwrap_or_else(|| repo_path.join(".noggin"));
 
     // Check .noggin/ exists
     if !noggin_path.exists() {
@@ -45,34 +453,198 @@ pub async fn learn_command(full: bool, verify: bool) -> Result<()> {
             ".noggin/ directory not found. Run 'noggin init' first."
         );
     }
+    let since_date = opts.since_date.as_deref().map(parse_since).transpose()?;
+    let until_date = opts.until_date.as_deref().map(parse_until).transpose()?;
+    let _lock = if verify || dry_run {
+        None
+    } else {
+        Some(KnowledgeLock::acquire(&noggin_path, "learn")?)
+    };
 
-    let manifest_path = noggin_path.join("manifest.toml");
+    // Step 1: Load config, manifest, and any checkpoint from a capped run
+    let mut config = Config::load(&noggin_path).context("Failed to load config")?;
+    if opts.max_cost.is_some() {
+        config.budget.max_cost_usd = opts.max_cost;
+    }
+    if opts.max_time.is_some() {
+        config.budget.max_time_secs = opts.max_time;
+    }
+    if opts.by_release {
+        config.history.group_by_release = true;
+    }
 
-    // Step 1: Load manifest
-    let mut manifest = Manifest::load(&manifest_path)
-        .context("Failed to load manifest")?;
+    let backend = storage::open(&noggin_path, &config.storage);
+    let mut manifest = backend.load_manifest().context("Failed to load manifest")?;
 
-    let mode = if full { "full" } else { "incremental" };
-    println!("Starting {} analysis...", mode);
+    let previous_checkpoint = Checkpoint::load(&noggin_path)
+        .context("Failed to load checkpoint")?;
+
+    let mut warnings: Vec<String> = Vec::new();
+    let merged = manifest.set_path_case(config.manifest.path_case);
+    if merged > 0 {
+        warnings.push(format!(
+            "Merged {} manifest entr{} that differed only in path case",
+            merged,
+            if merged == 1 { "y" } else { "ies" }
+        ));
+    }
+    let fingerprint = RepositoryFingerprint::detect(&repo_path)
+        .context("Failed to fingerprint repository")?;
+    let binding_changed = match &mut manifest.repository {
+        Some(stored) => match stored.check(&fingerprint) {
+            Some(mismatch) if !opts.force_adopt => anyhow::bail!(
+                "Repository mismatch: {}. Run 'noggin learn --force-adopt' to rebind this knowledge base.",
+                mismatch
+            ),
+            Some(mismatch) => {
+                warnings.push(format!("Rebound knowledge base to this repository ({})", mismatch));
+                *stored = fingerprint;
+                true
+            }
+            None => stored.fill_missing(&fingerprint),
+        },
+        None => {
+            manifest.repository = Some(fingerprint);
+            true
+        }
+    };
+    if binding_changed && !verify && !dry_run {
+        backend
+            .save_manifest(&manifest)
+            .context("Failed to save manifest")?;
+    }
+
+    let mode = if lite {
+        "lite"
+    } else if full {
+        "full"
+    } else {
+        "incremental"
+    };
+    if !quiet {
+        println!("Starting {} analysis...", mode);
+    }
+    events.emit(Event::RunStarted {
+        mode: mode.to_string(),
+        dry_run,
+    });
+
+    let mut report = LearnReport {
+        mode: mode.to_string(),
+        dry_run,
+        ..Default::default()
+    };
+
+    // With a range, only its commits are walked and only the files it
+    // touched (within `paths`, if given) are scanned
+    let range = match &opts.range {
+        Some(spec) => {
+            let repo = git2::Repository::open(&repo_path)?;
+            let (base, head) = resolve_range(&repo, spec)?;
+            let mut files = changed_paths(&repo, base, head)?;
+            if !opts.paths.is_empty() {
+                let pathspec = Pathspec::new(&opts.paths).context("Invalid --path pathspec")?;
+                let flags = if manifest.path_case.is_sensitive() {
+                    PathspecFlags::DEFAULT
+                } else {
+                    PathspecFlags::IGNORE_CASE
+                };
+                files.retain(|f| pathspec.matches_path(Path::new(f), flags));
+            }
+            if files.is_empty() {
+                anyhow::bail!("Range '{}' changes no files to analyze", spec);
+            }
+            Some((base.to_string(), head.to_string(), files))
+        }
+        None => None,
+    };
+
+    check_refs_at_head(&repo_path, &opts.refs)?;
 
     // Step 2: Scan files
-    let pb = spinner("Scanning files...");
-    let scan_result = scan_files(&repo_path, &manifest, full)
-        .context("Failed to scan files")?;
+    let pb = spinner("Scanning files...", quiet);
+    let generated = GeneratedFiles::from_config(&config.generated)?;
+    // A range's files are read from its head's tree, which needn't be
+    // checked out; prompts read them from an export of it
+    let mut range_export = None;
+    let mut scan_result = match &range {
+        Some((_, head, files)) => {
+            let repo = git2::Repository::open(&repo_path)?;
+            let tree = repo.find_commit(git2::Oid::from_str(head)?)?.tree()?;
+            range_export = Some(export_files(&repo, &tree, files)?);
+            scan_tree(&repo_path, tree.id(), &manifest, full, &generated, files)
+        }
+        None => scan_files(
+            &repo_path,
+            &manifest,
+            full,
+            &generated,
+            &opts.paths,
+            config.submodules.recurse,
+        ),
+    }
+    .context("Failed to scan files")?;
+    let content_root = range_export.as_ref().map_or(repo_path.as_path(), TempDir::path);
+    for path in &opts.refresh_files {
+        if scan_result.changed.iter().any(|f| f.path == *path) {
+            continue;
+        }
+        let full_path = content_root.join(path);
+        let (Ok(hash), Ok(metadata)) = (calculate_file_hash(&full_path), full_path.metadata()) else {
+            continue;
+        };
+        scan_result.changed.push(FileToAnalyze {
+            path: path.clone(),
+            hash,
+            size: metadata.len(),
+            is_new: false,
+            is_changed: false,
+        });
+        scan_result.unchanged = scan_result.unchanged.saturating_sub(1);
+    }
+    // Renamed files keep their entry and pattern links under the new path.
+    // Nothing is saved unless the run writes.
+    for rename in &scan_result.renamed {
+        manifest.rename_file(&rename.from, &rename.to);
+    }
+    // The detected stack is recorded with the manifest and stated in prompts
+    let current_paths = scan_result.current_paths(&manifest);
+    manifest.stack = Some(stack::detect(&repo_path, current_paths.iter().map(String::as_str)));
+    manifest.submodules = scan_result.submodules.clone();
     pb.finish_with_message(format!(
-        "Scanned {} files ({} changed, {} deleted, {} unchanged)",
+        "Scanned {} files ({} changed, {} duplicates, {} renamed, {} deleted, {} unchanged, {} generated)",
         scan_result.total,
         scan_result.changed.len(),
+        scan_result.duplicate_count(),
+        scan_result.renamed.len(),
         scan_result.deleted.len(),
-        scan_result.unchanged
+        scan_result.unchanged,
+        scan_result.generated_total
     ));
+    events.emit(Event::ScanFinished {
+        total: scan_result.total,
+        changed: scan_result.changed.len(),
+        deleted: scan_result.deleted.len(),
+        unchanged: scan_result.unchanged,
+        generated: scan_result.generated_total,
+    });
 
     // Step 3: Walk git history
-    let pb = spinner("Walking git history...");
+    let pb = spinner("Walking git history...", quiet);
     let walk_result = walk_commits(
         &repo_path,
         WalkOptions {
             skip_merges: true,
+            since_commit: range.as_ref().map(|(base, _, _)| base.clone()),
+            refs: match &range {
+                Some((_, head, _)) => vec![head.clone()],
+                None => history_refs(&config, &opts.refs),
+            },
+            all_branches: range.is_none() && (opts.all_branches || config.history.all_branches),
+            pathspec: (!opts.paths.is_empty()).then(|| opts.paths.clone()),
+            authors: opts.authors.clone(),
+            since_date,
+            until_date,
             ..Default::default()
         },
     )
@@ -89,37 +661,68 @@ pub async fn learn_command(full: bool, verify: bool) -> Result<()> {
             .collect()
     };
 
+    // Shallow boundary commits have no parents to diff against, so their
+    // scores would reflect the whole snapshot; leave them unprocessed
+    report.shallow = walk_result.shallow;
+    let (boundary, unprocessed): (Vec<_>, Vec<_>) =
+        unprocessed.into_iter().partition(|c| c.shallow_boundary);
+    report.shallow_boundary_commits = boundary.len();
+    // A filtered walk doesn't show whether all of history is reachable
+    let whole_history = range.is_none()
+        && opts.paths.is_empty()
+        && opts.authors.is_empty()
+        && since_date.is_none()
+        && until_date.is_none();
+    report.history_backfill =
+        record_truncation(&mut manifest, walk_result.shallow, whole_history, &boundary);
+
     // Score and filter to Medium+ significance
     let repo = git2::Repository::open(&repo_path)?;
-    let scoring_config = ScoringConfig::default();
-    let significant_commits: Vec<_> = unprocessed
-        .into_iter()
-        .filter(|cm| {
-            if let Ok(commit) = repo.find_commit(git2::Oid::from_str(&cm.hash).unwrap()) {
-                if let Ok(score) = score_commit(&repo, &commit, &scoring_config) {
-                    return matches!(
-                        score.category,
-                        ScoreCategory::Critical | ScoreCategory::High | ScoreCategory::Medium
-                    );
-                }
-            }
-            false
-        })
-        .collect();
+    let walked = unprocessed.len();
+    let mut significant_commits =
+        significant_commits(&repo, unprocessed, &config.scoring);
 
     pb.finish_with_message(format!(
         "Found {} significant commits",
         significant_commits.len()
     ));
+    events.emit(Event::HistoryWalked {
+        commits: walked,
+        significant: significant_commits.len(),
+    });
+
+    if let Some(max_commits) = opts.max_commits {
+        if significant_commits.len() > max_commits {
+            report.commits_remaining = significant_commits.len() - max_commits;
+            significant_commits.truncate(max_commits);
+        }
+    }
 
-    // Step 4: Detect invalidated patterns from changed/deleted files
-    let invalidated_patterns = find_invalidated_patterns(
+    // Step 4: Detect invalidated patterns from changed/deleted files,
+    // plus any whose re-analysis was deferred by a previous capped run
+    // or explicitly requested
+    let mut invalidated_patterns = find_invalidated_patterns(
         &manifest,
         &scan_result.changed,
         &scan_result.deleted,
     );
+    if let Some(checkpoint) = &previous_checkpoint {
+        for pattern_id in checkpoint.deferred_patterns() {
+            if manifest.patterns.contains_key(&pattern_id)
+                && !invalidated_patterns.contains(&pattern_id)
+            {
+                invalidated_patterns.push(pattern_id);
+            }
+        }
+    }
+    for pattern_id in &opts.refresh_patterns {
+        if manifest.patterns.contains_key(pattern_id) && !invalidated_patterns.contains(pattern_id) {
+            invalidated_patterns.push(pattern_id.clone());
+        }
+    }
+    invalidated_patterns.sort();
 
-    if !invalidated_patterns.is_empty() {
+    if !invalidated_patterns.is_empty() && !quiet {
         println!(
             "  {} patterns invalidated by file changes",
             invalidated_patterns.len()
@@ -128,230 +731,1398 @@ pub async fn learn_command(full: bool, verify: bool) -> Result<()> {
 
     // Step 5: Check if there's work to do
     let has_work = !scan_result.changed.is_empty()
+        || !scan_result.generated.is_empty()
         || !significant_commits.is_empty()
         || !scan_result.deleted.is_empty()
+        || !scan_result.renamed.is_empty()
         || !invalidated_patterns.is_empty();
 
+    let mut sample = LearnSample {
+        files_scanned: scan_result.total,
+        files_unchanged: scan_result.unchanged,
+        ..Default::default()
+    };
+
     if !has_work {
-        println!("Nothing to learn. Codebase is up to date.");
-        return Ok(());
+        if !verify && !dry_run {
+            Checkpoint::clear(&noggin_path)?;
+            record_learn(&noggin_path, &sample);
+        }
+        report.up_to_date = true;
+        report.warnings = warnings;
+        return Ok(report);
     }
 
     // Step 6: Verify mode - report drift without updating
     if verify {
-        println!("\n--- Verify Mode (no files written) ---");
+        report.drift = Some(DriftReport {
+            changed_files: scan_result
+                .changed
+                .iter()
+                .map(|f| (f, false))
+                .chain(scan_result.generated.iter().map(|f| (f, true)))
+                .map(|(f, generated)| DriftFile {
+                    path: f.path.clone(),
+                    status: if f.is_new { "new" } else { "modified" }.to_string(),
+                    generated,
+                })
+                .collect(),
+            deleted_files: scan_result.deleted.clone(),
+            renamed_files: scan_result
+                .renamed
+                .iter()
+                .map(|r| format!("{} -> {}", r.from, r.to))
+                .collect(),
+            unprocessed_commits: significant_commits
+                .iter()
+                .map(|c| DriftCommit {
+                    hash: c.hash.clone(),
+                    short_hash: c.short_hash.clone(),
+                    summary: c.message_summary.clone(),
+                })
+                .collect(),
+            invalidated_patterns: invalidated_patterns.clone(),
+        });
+        return Ok(report);
+    }
+
+    // Step 7: Build prompts; lite runs query no providers
+    let never_send = NeverSend::from_config(&config.privacy)?;
+    scan_result.prefer_sendable(&never_send);
+    // Tracked files by content hash, as of this scan
+    let tracked: BTreeMap<String, String> = manifest
+        .files
+        .values()
+        .filter(|entry| !entry.generated && !scan_result.deleted.contains(&entry.path))
+        .map(|entry| (entry.path.clone(), entry.hash.clone()))
+        .chain(scan_result.changed.iter().map(|f| (f.path.clone(), f.hash.clone())))
+        .collect();
+    let tracked_files: Vec<String> = tracked.keys().cloned().collect();
+    let prompts = if lite {
+        Vec::new()
+    } else {
+        let mut prompts = build_prompts(
+            &repo_path,
+            &config,
+            &manifest,
+            ChangedFiles {
+                root: content_root,
+                files: &scan_result.representatives(),
+            },
+            &significant_commits,
+            &invalidated_patterns,
+            &never_send,
+        );
+        // Ask for the fields category templates require, so new entries
+        // aren't held in pending/ for lack of them
+        let fields = Templates::load(&noggin_path)?.prompt_instruction();
+        for pending in &mut prompts {
+            pending.prompt.push_str(&fields);
+        }
+        // Every term is a candidate on a full run
+        let existing = (!full).then(|| glossary::existing_terms(backend.as_ref()));
+        let glossary_terms = glossary_candidates(
+            content_root,
+            &noggin_path,
+            &config,
+            &tracked,
+            &scan_result.changed,
+            &never_send,
+            existing.as_ref(),
+        );
+        if !glossary_terms.is_empty() {
+            prompts.push(PendingPrompt {
+                prompt_type: GLOSSARY_PROMPT.to_string(),
+                prompt: build_glossary_prompt(&glossary_terms)
+                    + &manifest.stack.as_ref().map(prompt_context).unwrap_or_default()
+                    + &language_instruction(&config.llm.output_language),
+                truncation: TruncationStats::default(),
+                files: Vec::new(),
+                commits: Vec::new(),
+                patterns: Vec::new(),
+            });
+        }
+        prompts
+    };
+
+    // Step 8: Invoke LLMs in parallel, within budget
+    let providers = if lite {
+        Vec::new()
+    } else {
+        configured_providers(&config.llm)?
+    };
+    let scheduler = Scheduler::new(&config.llm);
+
+    let mut prompt_outputs: Vec<PromptOutputs> = Vec::new();
+    let mut budget = Budget::new(config.budget.clone());
+    let mut budget_hit: Option<BudgetLimit> = None;
+    // Daily caps shared with runs in other repositories on this machine
+    let machine = if providers.is_empty() {
+        None
+    } else {
+        match default_config_dir().and_then(|dir| MachineBudget::load(&dir)) {
+            Ok(machine) => Some(machine),
+            Err(e) => {
+                warnings.push(format!("Machine budget unavailable: {:#}", e));
+                None
+            }
+        }
+    };
+    let provider_names: Vec<&str> = providers.iter().map(|p| p.name()).collect();
+    let mut deferred: Vec<DeferredPrompt> = Vec::new();
+    // Sent, but with no usable response to learn from
+    let mut failed: Vec<DeferredPrompt> = Vec::new();
+
+    // Prompts go out in waves as wide as the providers' concurrency. Each
+    // prompt is billed as it is admitted (failed requests are still billed
+    // for their prompt) with a response allowance reserved; between waves
+    // the real response sizes replace the allowances and the elapsed time
+    // is checked again, so every cap can stop the run part way.
+    let wave_size = providers
+        .iter()
+        .map(|provider| config.llm.max_concurrency(provider.name()))
+        .max()
+        .unwrap_or(1)
+        .max(1);
+    let total_prompts = prompts.len();
+    let mut admitted: Vec<PendingPrompt> = Vec::new();
+    let mut results = Vec::new();
+    let pb = spinner(
+        &format!("Querying LLMs ({} prompts)...", total_prompts),
+        quiet || total_prompts == 0,
+    );
+    let progress = Mutex::new(QueryProgress::new(total_prompts));
+    let mut queue = prompts.into_iter();
+    loop {
+        let mut wave: Vec<PendingPrompt> = Vec::new();
+        while wave.len() < wave_size {
+            let Some(pending) = queue.next() else {
+                break;
+            };
+            let prompt_tokens = estimate_tokens(&pending.prompt);
+            if budget_hit.is_none() {
+                budget_hit = budget.check(prompt_tokens, providers.len());
+            }
+            if budget_hit.is_none() {
+                budget_hit = machine
+                    .as_ref()
+                    .and_then(|machine| machine.check(&budget, prompt_tokens, &provider_names));
+            }
+            events.emit(Event::PromptBuilt {
+                prompt_type: pending.prompt_type.clone(),
+                tokens: prompt_tokens,
+                files: pending.files.len(),
+                commits: pending.commits.len(),
+                patterns: pending.patterns.len(),
+            });
+            if let Some(limit) = &budget_hit {
+                events.emit(Event::PromptDeferred {
+                    prompt_type: pending.prompt_type.clone(),
+                    limit: limit.to_string(),
+                });
+                deferred.push(DeferredPrompt {
+                    prompt_type: pending.prompt_type,
+                    files: pending.files,
+                    commits: pending.commits,
+                    patterns: pending.patterns,
+                });
+                continue;
+            }
+
+            if pending.truncation.is_truncated() {
+                report.truncation.push(PromptTruncation {
+                    prompt_type: pending.prompt_type.clone(),
+                    stats: pending.truncation,
+                });
+            }
+            for provider in &providers {
+                budget.admit(provider.name(), prompt_tokens);
+            }
+            wave.push(pending);
+        }
+        if wave.is_empty() {
+            break;
+        }
 
-        if !scan_result.changed.is_empty() {
-            println!("{} files changed:", scan_result.changed.len());
-            for f in &scan_result.changed {
-                let label = if f.is_new { "new" } else { "modified" };
-                println!("  {} [{}]", f.path, label);
+        // The scheduler keeps each provider within its concurrency and
+        // rate limits
+        let first = admitted.len();
+        let queries = wave.iter().enumerate().map(|(offset, pending)| {
+            let idx = first + offset;
+            let (pb, progress, scheduler, providers) = (&pb, &progress, &scheduler, &providers);
+            async move {
+                // Show output as it streams in so slow providers don't look hung
+                let on_progress = |model: &str, chars: usize| {
+                    let mut progress = progress.lock().unwrap();
+                    progress.received[idx].insert(model.to_string(), chars);
+                    pb.set_message(progress.message());
+                };
+                for provider in providers.iter() {
+                    events.emit(Event::ProviderStarted {
+                        prompt_type: pending.prompt_type.clone(),
+                        provider: provider.name().to_string(),
+                    });
+                }
+                let result =
+                    query_all_streaming(scheduler, providers, &pending.prompt, &on_progress).await;
+                emit_provider_results(events, &pending.prompt_type, providers, &result);
+
+                let mut progress = progress.lock().unwrap();
+                progress.done += 1;
+                pb.set_message(progress.message());
+                pb.println(match &result {
+                    Ok(parallel_result) => format!(
+                        "LLM {} analysis: {}/{} models responded",
+                        pending.prompt_type,
+                        parallel_result.success_count(),
+                        parallel_result.success_count() + parallel_result.failure_count()
+                    ),
+                    Err(_) => format!("LLM {} analysis failed", pending.prompt_type),
+                });
+                result
+            }
+        });
+        let wave_results = futures::future::join_all(queries).await;
+        for result in wave_results.iter().flatten() {
+            for success in &result.successes {
+                budget.record_response(&success.model, estimate_tokens(&success.response));
             }
         }
+        budget.settle();
+        // Record as the run goes so concurrent runs' checks count this wave
+        if let Some(machine) = &machine {
+            machine.record_or_warn(&repo_path, &budget);
+        }
+        admitted.extend(wave);
+        results.extend(wave_results);
+    }
+    pb.finish_and_clear();
+
+    // Handle responses in prompt order so synthesis sees a stable input
+    for (pending, result) in admitted.iter().zip(results) {
+        let prompt_type = &pending.prompt_type;
+        let mut outputs: Vec<ModelOutput> = Vec::new();
+
+        match result {
+            Ok(parallel_result) => {
+                for failure in &parallel_result.failures {
+                    warnings.push(format!(
+                        "{} failed for {} analysis: {}",
+                        failure.model, prompt_type, failure.error
+                    ));
+                }
+
+                // Parse responses into ModelOutput
+                for model_result in &parallel_result.successes {
+                    if !dry_run {
+                        if let Err(e) = retention::store_raw_response(
+                            &noggin_path,
+                            &config.retention,
+                            &model_result.model,
+                            prompt_type,
+                            &model_result.response,
+                        ) {
+                            warnings.push(format!("Failed to store raw response: {:#}", e));
+                        }
+                    }
+                    match synthesis::parse_model_output(
+                        &model_result.model,
+                        &model_result.response,
+                    ) {
+                        Ok(parsed) => {
+                            for warning in &parsed.warnings {
+                                warnings.push(format!(
+                                    "{} output for {}: {}",
+                                    model_result.model, prompt_type, warning
+                                ));
+                            }
+                            let mut arfs = parsed.arfs;
+                            if !normalize_language(
+                                &scheduler,
+                                &providers,
+                                &model_result.model,
+                                &mut arfs,
+                                &config.llm,
+                                &mut budget,
+                                &mut warnings,
+                            )
+                            .await
+                            {
+                                continue;
+                            }
+                            info!(
+                                "Parsed {} ARF entries from {} ({})",
+                                arfs.len(),
+                                model_result.model,
+                                prompt_type
+                            );
+                            outputs.push(ModelOutput {
+                                model_name: model_result.model.clone(),
+                                arf_files: arfs,
+                            });
+                        }
+                        Err(e) => {
+                            warnings.push(format!(
+                                "Failed to parse {} output for {}: {}",
+                                model_result.model, prompt_type, e
+                            ));
+                        }
+                    }
+                }
+            }
+            Err(e) => {
+                warnings.push(format!("All LLMs failed for {} analysis: {}", prompt_type, e));
+            }
+        }
+        if outputs.is_empty() {
+            failed.push(DeferredPrompt {
+                prompt_type: prompt_type.clone(),
+                files: pending.files.clone(),
+                commits: pending.commits.clone(),
+                patterns: pending.patterns.clone(),
+            });
+        }
+        prompt_outputs.push(PromptOutputs {
+            prompt_type: prompt_type.clone(),
+            outputs,
+        });
+    }
+
+    // Step 9: Synthesize consensus, or collect structural facts in lite mode
+    let mut manual_conflicts = Vec::new();
+    let mut synthesis_report = None;
+    let mut unified_arfs = if lite {
+        let pb = spinner("Collecting structural facts...", quiet);
+        let terms = if config.glossary.enabled {
+            glossary::mine_terms_cached(
+                &repo_path,
+                &noggin_path,
+                &tracked,
+                &never_send,
+                &config.glossary,
+            )
+        } else {
+            Vec::new()
+        };
+        let arfs = lite::analyze(&repo_path, &tracked_files, terms, &never_send, &config)
+            .context("Lite analysis failed")?;
+        pb.finish_with_message(format!("Collected {} structural facts", arfs.len()));
+        arfs
+    } else if prompt_outputs.iter().all(|p| p.outputs.is_empty()) {
+        warnings.push("No model outputs to synthesize".to_string());
+        Vec::new()
+    } else {
+        // Each prompt is synthesized on its own, then entries several
+        // prompts agree on are merged
+        let pb = spinner("Synthesizing consensus...", quiet);
+        match synthesis::synthesize_by_prompt(prompt_outputs, &config.synthesis) {
+            Ok(result) => {
+                let by_type: Vec<String> = result
+                    .report
+                    .by_prompt_type
+                    .iter()
+                    .map(|(prompt_type, count)| format!("{} {}", prompt_type, count))
+                    .collect();
+                pb.finish_with_message(format!(
+                    "Synthesized {} ARF entries ({}; {} cross-prompt duplicates merged, {} conflicts resolved)",
+                    result.report.total_output_arfs,
+                    by_type.join(", "),
+                    result.report.cross_prompt_duplicates,
+                    result.report.conflicts_resolved
+                ));
+                report.entries_by_prompt_type = result.report.by_prompt_type.clone();
+                events.emit(Event::SynthesisFinished {
+                    input_entries: result.report.total_input_arfs,
+                    entries: result.report.total_output_arfs,
+                    conflicts_resolved: result.report.conflicts_resolved,
+                });
+                warnings.extend(result.report.degradation_warning());
+                manual_conflicts = result.manual_conflicts;
+                synthesis_report = Some(result.report);
+                result.unified_arfs
+            }
+            Err(e) => {
+                pb.finish_with_message("Synthesis failed");
+                warnings.push(format!("Synthesis failed: {}", e));
+                Vec::new()
+            }
+        }
+    };
+
+    glossary::mark_entries(&mut unified_arfs);
+    attribute_branches(&mut unified_arfs, &significant_commits);
+    let origin_url = manifest.repository.as_ref().and_then(|r| r.origin_url.as_deref());
+    let issue_template = issues::url_template(&config.issues, origin_url);
+    attribute_issues(&mut unified_arfs, &significant_commits, issue_template.as_deref());
+    attribute_people(&mut unified_arfs, &significant_commits, &config.people);
+    attribute_file_authors(&repo, &mut unified_arfs, &config.people);
+    if config.history.group_by_release {
+        if let Ok(groups) = release_groups(&repo_path, &config, &significant_commits) {
+            attribute_releases(&mut unified_arfs, &groups);
+        }
+    }
+    attribute_duplicates(&mut unified_arfs, &scan_result.duplicates);
+
+    // Step 10: Write ARF files
+    let mut arf_locations: Vec<Option<String>> = Vec::new();
+    if !unified_arfs.is_empty() {
+        if let Some(warning) = unusual_output(&noggin_path, &config, unified_arfs.len()) {
+            warnings.push(warning);
+        }
+        sample.entries = unified_arfs.len();
+
+        let pb = spinner("Writing ARF files...", quiet);
+        let write_opts = WriteOptions {
+            // Lite facts mention markers and packages that would sway
+            // category inference, so they are always filed as facts
+            category: lite.then_some(ArfCategory::Fact),
+            dry_run,
+            max_new: (config.size.max_new_per_run > 0).then_some(config.size.max_new_per_run),
+            dedupe_similarity: (config.synthesis.dedupe_similarity > 0.0)
+                .then_some(config.synthesis.dedupe_similarity),
+            naming: config.writer.naming,
+        };
+        let write_result = apply_arfs(backend.as_ref(), &noggin_path, &unified_arfs, &write_opts)
+            .context("Failed to write ARF files")?;
+        pb.finish_with_message(format!(
+            "{} {} new, {} updated, {} skipped ARF files ({} merged into similar entries)",
+            if dry_run { "Would write" } else { "Wrote" },
+            write_result.written,
+            write_result.updated,
+            write_result.skipped,
+            write_result.merged
+        ));
+        events.emit(Event::WriteFinished {
+            written: write_result.written,
+            updated: write_result.updated,
+            skipped: write_result.skipped,
+            pending: write_result.pending.len() + write_result.incomplete.len(),
+        });
+        for conflict in &write_result.merge_conflicts {
+            warnings.push(format!("Merged into a similar entry despite differing {}", conflict));
+        }
+        report.unresolved = save_manual_conflicts(
+            &noggin_path,
+            &unified_arfs,
+            &write_result,
+            &manual_conflicts,
+            dry_run,
+        )?;
+        report.arfs_written = write_result.paths;
+        report.conflicts = write_result.conflicts;
+        report.pending = write_result.pending;
+        report.incomplete = write_result.incomplete;
+        report.planned = write_result.planned;
+        arf_locations = write_result.locations;
+    }
+
+    // Step 11: Update manifest, skipping work that was deferred or failed
+    let pb = spinner("Updating manifest...", quiet);
+
+    // Duplicates wait on their representative
+    let mut deferred_files: HashSet<String> = deferred
+        .iter()
+        .chain(&failed)
+        .flat_map(|d| d.files.iter().cloned())
+        .collect();
+    for (representative, copies) in &scan_result.duplicates {
+        if deferred_files.contains(representative) {
+            deferred_files.extend(copies.iter().cloned());
+        }
+    }
+    let deferred_commits: HashSet<String> = deferred
+        .iter()
+        .chain(&failed)
+        .flat_map(|d| d.commits.iter().cloned())
+        .collect();
+    let deferred_patterns: HashSet<String> = deferred
+        .iter()
+        .chain(&failed)
+        .flat_map(|d| d.patterns.iter().cloned())
+        .collect();
+
+    // Remove deleted files
+    let branch = scan_result.branch.as_deref();
+    for path in &scan_result.deleted {
+        manifest.remove_file(path);
+    }
+    if let Some(branch) = branch {
+        for path in &scan_result.elsewhere {
+            manifest.unmark_file_on_branch(path, branch);
+        }
+    }
+
+    // Update file hashes, recording them for the current branch
+    for file in &scan_result.restored {
+        manifest.restore_file_hash(&file.path, &file.hash);
+    }
+    for file in &scan_result.changed {
+        if deferred_files.contains(&file.path) {
+            continue;
+        }
+        manifest.add_or_update_file(file.path.clone(), file.hash.clone(), vec![]);
+    }
+    for file in &scan_result.generated {
+        manifest.add_or_update_generated_file(file.path.clone(), file.hash.clone());
+    }
+    if let Some(branch) = branch {
+        let recorded = scan_result
+            .restored
+            .iter()
+            .chain(&scan_result.changed)
+            .chain(&scan_result.generated)
+            .filter(|file| !deferred_files.contains(&file.path));
+        for file in recorded {
+            manifest.mark_file_on_branch(&file.path, branch);
+        }
+    }
+
+    // Invalidate affected patterns
+    for pattern_id in &invalidated_patterns {
+        if deferred_patterns.contains(pattern_id) {
+            continue;
+        }
+        manifest.invalidate_pattern(pattern_id);
+    }
+
+    // Update commit entries, linking each to the ARFs derived from it
+    let write_notes = opts.notes || config.notes.enabled;
+    for commit in &significant_commits {
+        if deferred_commits.contains(&commit.hash) {
+            continue;
+        }
+        let derived = derived_arfs(&commit.hash, &unified_arfs, &arf_locations);
+        let category = infer_commit_category(&commit.message_summary);
+        manifest.add_commit(
+            commit.hash.clone(),
+            category,
+            derived.first().map(|e| e.path.clone()).unwrap_or_default(),
+        );
+        if let Some(branch) = branch {
+            manifest.mark_commit_on_branch(&commit.hash, branch);
+        }
+
+        if write_notes && !dry_run && !derived.is_empty() {
+            match write_knowledge_note(&repo_path, &config.notes.notes_ref, &commit.hash, &derived) {
+                Ok(()) => report.notes_written += 1,
+                Err(e) => warnings.push(format!("{}: {:#}", commit.short_hash, e)),
+            }
+        }
+    }
+
+    let checkpoint = Checkpoint::new(budget_hit, deferred, failed);
+    let mut cost_report_path = None;
+    events.emit(Event::ManifestUpdated {
+        files: manifest.files.len(),
+        commits: manifest.commits.len(),
+        patterns: manifest.patterns.len(),
+        saved: !dry_run,
+    });
+    if dry_run {
+        let planner = DryRunBackend::new(backend.as_ref());
+        rename_cited_files(&planner, &manifest, &scan_result.renamed)?;
+        planner.save_manifest(&manifest)?;
+        report.planned.extend(planner.planned()?);
+        pb.finish_with_message("Manifest left unchanged (dry run)");
+    } else {
+        rename_cited_files(backend.as_ref(), &manifest, &scan_result.renamed)
+            .context("Failed to update entries citing renamed files")?;
+        backend
+            .save_manifest(&manifest)
+            .context("Failed to save manifest")?;
+
+        match &checkpoint {
+            Some(checkpoint) => checkpoint
+                .save(&noggin_path)
+                .context("Failed to save checkpoint")?,
+            None => Checkpoint::clear(&noggin_path)?,
+        }
+
+        pb.finish_with_message("Manifest updated");
+
+        let refreshed =
+            SemanticIndex::refresh(&noggin_path, backend.as_ref(), &HashingEmbedder::new());
+        if let Err(e) = refreshed {
+            warnings.push(format!("Failed to update search index: {:#}", e));
+        }
+        if let Err(e) = retention::expire(&noggin_path, &config.retention, false) {
+            warnings.push(format!("Failed to apply retention limits: {:#}", e));
+        }
+
+        sample.provider_requests = budget.requests();
+        sample.provider_tokens = budget.tokens();
+        record_learn(&noggin_path, &sample);
+
+        if budget.requests() > 0 {
+            let cost_report = CostReport {
+                finished_at: Utc::now(),
+                mode: report.mode.clone(),
+                requests: budget.requests(),
+                tokens: budget.tokens(),
+                estimated_cost_usd: budget.estimated_cost(),
+                providers: budget.by_provider().clone(),
+            };
+            match cost_report.save(&noggin_path) {
+                Ok(path) => {
+                    let rel = path.strip_prefix(&noggin_path).unwrap_or(&path);
+                    cost_report_path = Some(rel.to_string_lossy().into_owned());
+                }
+                Err(e) => warnings.push(format!("Failed to save cost report: {:#}", e)),
+            }
+        }
+    }
+
+    if let Some(machine) = &machine {
+        if let Err(e) = machine.record(&repo_path.display().to_string(), &budget) {
+            warnings.push(format!("Failed to update the machine budget ledger: {:#}", e));
+        }
+    }
+
+    report.files_analyzed = scan_result.changed.len() - deferred_files.len();
+    report.files_generated = scan_result.generated.len();
+    report.files_deleted = scan_result.deleted.len();
+    report.files_renamed = scan_result.renamed.len();
+    report.files_duplicate = scan_result.duplicate_count();
+    report.commits_processed = significant_commits.len() - deferred_commits.len();
+    report.patterns_invalidated = invalidated_patterns.len() - deferred_patterns.len();
+    report.arf_entries = unified_arfs.len();
+    report.usage = UsageSummary {
+        requests: budget.requests(),
+        tokens: budget.tokens(),
+        estimated_cost_usd: budget.estimated_cost(),
+        providers: budget.by_provider().clone(),
+        report_path: cost_report_path,
+    };
+    report.deferred = checkpoint;
+    report.warnings = warnings;
+
+    if !dry_run {
+        let record = run_record(&report, synthesis_report);
+        match record.save(&noggin_path) {
+            Ok(path) => {
+                let rel = path.strip_prefix(&noggin_path).unwrap_or(&path);
+                report.run_record = Some(rel.to_string_lossy().into_owned());
+            }
+            Err(e) => report.warnings.push(format!("Failed to save run record: {:#}", e)),
+        }
+    }
+
+    Ok(report)
+}
+
+/// What `noggin log` shows of a finished run
+fn run_record(report: &LearnReport, synthesis: Option<synthesis::SynthesisReport>) -> RunRecord {
+    RunRecord {
+        finished_at: Utc::now(),
+        mode: report.mode.clone(),
+        files_analyzed: report.files_analyzed,
+        commits_processed: report.commits_processed,
+        arf_entries: report.arf_entries,
+        arfs_written: report.arfs_written.len(),
+        conflicts: report.conflicts.len() + report.unresolved.len(),
+        pending: report.pending.len(),
+        requests: report.usage.requests,
+        tokens: report.usage.tokens,
+        estimated_cost_usd: report.usage.estimated_cost_usd,
+        warnings: report.warnings.clone(),
+        synthesis,
+    }
+}
+
+/// Changed files to analyze, read from `root`: the working tree, or an
+/// export of a range's head
+struct ChangedFiles<'a> {
+    root: &'a Path,
+    files: &'a [FileToAnalyze],
+}
+
+/// Prompts for the changed files, significant commits, and invalidated
+/// patterns, each ending with the output language instruction
+fn build_prompts(
+    repo_path: &Path,
+    config: &Config,
+    manifest: &Manifest,
+    changed: ChangedFiles,
+    significant_commits: &[CommitMetadata],
+    invalidated_patterns: &[String],
+    never_send: &NeverSend,
+) -> Vec<PendingPrompt> {
+    let mut prompts = Vec::new();
+
+    // Large file sets are split across prompts that fit every provider
+    let prompt_budget = PromptBudget::from_config(config);
+    for batch in build_file_analysis_prompts(changed.root, changed.files, never_send, &prompt_budget)
+    {
+        prompts.push(PendingPrompt {
+            prompt_type: FILES_PROMPT.to_string(),
+            prompt: batch.prompt,
+            truncation: batch.truncation,
+            files: batch.files,
+            commits: Vec::new(),
+            patterns: Vec::new(),
+        });
+    }
+
+    if !significant_commits.is_empty() {
+        let diffs = commit_diffs(repo_path, config, significant_commits, never_send);
+        // One prompt per release when grouping, falling back to a single
+        // prompt if the tags can't be read
+        let groups = config
+            .history
+            .group_by_release
+            .then(|| release_groups(repo_path, config, significant_commits).ok())
+            .flatten();
+        match groups {
+            Some(groups) => {
+                for group in &groups {
+                    prompts.push(PendingPrompt {
+                        prompt_type: RELEASES_PROMPT.to_string(),
+                        prompt: build_release_analysis_prompt(group, &diffs, &prompt_budget),
+                        truncation: TruncationStats::default(),
+                        files: Vec::new(),
+                        commits: group.commits.iter().map(|c| c.hash.clone()).collect(),
+                        patterns: Vec::new(),
+                    });
+                }
+            }
+            None => prompts.push(PendingPrompt {
+                prompt_type: COMMITS_PROMPT.to_string(),
+                prompt: build_commit_analysis_prompt(significant_commits, &diffs, &prompt_budget),
+                truncation: TruncationStats::default(),
+                files: Vec::new(),
+                commits: significant_commits.iter().map(|c| c.hash.clone()).collect(),
+                patterns: Vec::new(),
+            }),
+        }
+    }
+
+    // Build re-analysis prompt for invalidated patterns
+    if !invalidated_patterns.is_empty() {
+        let pattern_files = collect_pattern_files(manifest, invalidated_patterns, repo_path);
+        if !pattern_files.is_empty() {
+            let (prompt, truncation) = build_pattern_reanalysis_prompt(
+                repo_path,
+                invalidated_patterns,
+                &pattern_files,
+                never_send,
+                &prompt_budget,
+            );
+            prompts.push(PendingPrompt {
+                prompt_type: PATTERNS_PROMPT.to_string(),
+                prompt,
+                truncation,
+                files: Vec::new(),
+                commits: Vec::new(),
+                patterns: invalidated_patterns.to_vec(),
+            });
+        }
+    }
+
+    // Every prompt states the detected stack, so providers don't have to
+    // guess it from the files shown
+    let instruction = manifest.stack.as_ref().map(prompt_context).unwrap_or_default()
+        + &language_instruction(&config.llm.output_language);
+    for pending in &mut prompts {
+        pending.prompt.push_str(&instruction);
+    }
+
+    prompts
+}
+
+/// Glossary terms to ask providers to define: mined from every tracked
+/// file (only changed ones are re-read, see
+/// [`glossary::mine_terms_cached`]), limited to terms used by a changed
+/// file and not in `existing` (all terms without it), at most
+/// `glossary.max_terms`
+fn glossary_candidates(
+    repo_path: &Path,
+    noggin_path: &Path,
+    config: &Config,
+    tracked: &BTreeMap<String, String>,
+    changed: &[FileToAnalyze],
+    never_send: &NeverSend,
+    existing: Option<&HashSet<String>>,
+) -> Vec<Term> {
+    if !config.glossary.enabled || changed.is_empty() {
+        return Vec::new();
+    }
+    let changed: HashSet<&str> = changed.iter().map(|f| f.path.as_str()).collect();
+    let mut terms =
+        glossary::mine_terms_cached(repo_path, noggin_path, tracked, never_send, &config.glossary);
+    if let Some(existing) = existing {
+        terms.retain(|term| {
+            !existing.contains(&term.name.to_lowercase())
+                && term.files.iter().any(|f| changed.contains(f.as_str()))
+        });
+    }
+    terms.truncate(config.glossary.max_terms);
+    terms
+}
+
+/// Note in `manifest` whether the walked history is truncated. While it
+/// is, the boundary commits are recorded; once a walk of the whole history
+/// is no longer truncated the record is dropped, and true is returned
+/// because that run backfills what earlier runs couldn't reach.
+fn record_truncation(
+    manifest: &mut Manifest,
+    shallow: bool,
+    whole_history: bool,
+    boundary: &[CommitMetadata],
+) -> bool {
+    if shallow {
+        let since = manifest
+            .truncated_history
+            .as_ref()
+            .map_or_else(Utc::now, |truncated| truncated.since);
+        manifest.truncated_history = Some(TruncatedHistory {
+            boundaries: boundary.iter().map(|c| c.hash.clone()).collect(),
+            since,
+        });
+        return false;
+    }
+    whole_history && manifest.truncated_history.take().is_some()
+}
+
+/// Commits scoring Medium significance or higher. Commits whose hash is
+/// malformed or no longer resolves are skipped.
+pub fn significant_commits(
+    repo: &git2::Repository,
+    commits: Vec<CommitMetadata>,
+    scoring_config: &ScoringConfig,
+) -> Vec<CommitMetadata> {
+    commits
+        .into_iter()
+        .filter(|cm| {
+            let found = git2::Oid::from_str(&cm.hash).and_then(|oid| repo.find_commit(oid));
+            if let Ok(commit) = found {
+                if let Ok(score) = score_commit(repo, &commit, scoring_config) {
+                    return matches!(
+                        score.category,
+                        ScoreCategory::Critical | ScoreCategory::High | ScoreCategory::Medium
+                    );
+                }
+            }
+            false
+        })
+        .collect()
+}
+
+/// Capped diffs of `commits` for the commit prompt, by full hash; empty
+/// unless `prompts.commit_diffs` is set. Commits whose diff can't be
+/// computed (e.g. at a shallow boundary) are left out, and so are
+/// generated and vendored files, as in the scan.
+pub fn commit_diffs(
+    repo_path: &Path,
+    config: &Config,
+    commits: &[CommitMetadata],
+    never_send: &NeverSend,
+) -> HashMap<String, String> {
+    let prompts = &config.prompts;
+    let Some(repo) = prompts
+        .commit_diffs
+        .then(|| git2::Repository::open(repo_path).ok())
+        .flatten()
+    else {
+        return HashMap::new();
+    };
+    // Learn has already rejected invalid `[generated]` settings
+    let generated = GeneratedFiles::from_config(&config.generated).unwrap_or_default();
+
+    commits
+        .iter()
+        .filter(|commit| !commit.shallow_boundary)
+        .filter_map(|commit| {
+            let patch = commit_patch(
+                &repo,
+                &commit.hash,
+                prompts.max_diff_files,
+                prompts.max_diff_lines,
+                never_send,
+                &generated,
+            )
+            .ok()?;
+            Some((commit.hash.clone(), patch))
+        })
+        .collect()
+}
+
+/// Print the drift found in verify mode
+fn print_drift(drift: &DriftReport) {
+    println!("\n--- Verify Mode (no files written) ---");
+
+    if !drift.changed_files.is_empty() {
+        println!("{} files changed:", drift.changed_files.len());
+        for f in &drift.changed_files {
+            if f.generated {
+                println!("  {} [{}, generated]", f.path, f.status);
+            } else {
+                println!("  {} [{}]", f.path, f.status);
+            }
+        }
+    }
+
+    if !drift.deleted_files.is_empty() {
+        println!("{} files deleted:", drift.deleted_files.len());
+        for path in &drift.deleted_files {
+            println!("  {}", path);
+        }
+    }
+
+    if !drift.renamed_files.is_empty() {
+        println!("{} files renamed:", drift.renamed_files.len());
+        for rename in &drift.renamed_files {
+            println!("  {}", rename);
+        }
+    }
+
+    if !drift.unprocessed_commits.is_empty() {
+        println!("{} commits unprocessed:", drift.unprocessed_commits.len());
+        for c in &drift.unprocessed_commits {
+            println!("  {} {}", c.short_hash, c.summary);
+        }
+    }
+
+    if !drift.invalidated_patterns.is_empty() {
+        println!("{} patterns need re-analysis:", drift.invalidated_patterns.len());
+        for p in &drift.invalidated_patterns {
+            println!("  {}", p);
+        }
+    }
+}
+
+/// Print the "Learn Complete" summary
+fn print_summary(report: &LearnReport) {
+    println!();
+    if report.dry_run {
+        println!("=== Learn Dry Run (nothing written) ===");
+    } else {
+        println!("=== Learn Complete ===");
+    }
+    println!("  Files analyzed:        {}", report.files_analyzed);
+    if report.files_generated > 0 {
+        println!("  Generated (tracked):   {}", report.files_generated);
+    }
+    println!("  Files deleted:         {}", report.files_deleted);
+    if report.files_renamed > 0 {
+        println!("  Files renamed:         {}", report.files_renamed);
+    }
+    if report.files_duplicate > 0 {
+        println!("  Duplicates (shared):   {}", report.files_duplicate);
+    }
+    println!("  Commits processed:     {}", report.commits_processed);
+    if report.commits_remaining > 0 {
+        println!("  Commits remaining:     {}", report.commits_remaining);
+    }
+    if report.shallow {
+        println!(
+            "  Shallow clone:         history truncated, {} boundary commits skipped",
+            report.shallow_boundary_commits
+        );
+    }
+    if report.history_backfill {
+        println!("  History backfill:      full history now available; older commits included");
+    }
+    println!("  Patterns invalidated:  {}", report.patterns_invalidated);
+    println!("  ARF entries:           {}", report.arf_entries);
+    for (prompt_type, count) in &report.entries_by_prompt_type {
+        println!("    {:<20} {}", format!("from {}:", prompt_type), count);
+    }
+    if report.notes_written > 0 {
+        println!("  Git notes written:     {}", report.notes_written);
+    }
+    println!(
+        "  Provider usage:        {} requests, ~{} tokens, ~${:.2}",
+        report.usage.requests,
+        report.usage.tokens,
+        report.usage.estimated_cost_usd
+    );
+    for (provider, usage) in &report.usage.providers {
+        println!(
+            "    {:<20} {} requests, ~{} tokens, ~${:.2}",
+            format!("{}:", provider),
+            usage.requests,
+            usage.tokens(),
+            usage.estimated_cost_usd
+        );
+    }
+    if let Some(path) = &report.usage.report_path {
+        println!("  Cost report:           .noggin/{}", path);
+    }
+    if let Some(path) = &report.run_record {
+        println!("  Run record:            .noggin/{}", path);
+    }
+
+    if !report.truncation.is_empty() {
+        print_truncation(&report.truncation);
+    }
+
+    if !report.conflicts.is_empty() {
+        println!();
+        println!(
+            "{} proposed entries contradict approved knowledge and need review:",
+            report.conflicts.len()
+        );
+        for path in &report.conflicts {
+            println!("  .noggin/{}", path);
+        }
+    }
+
+    if !report.unresolved.is_empty() {
+        println!();
+        println!(
+            "{} fields the models disagreed on need a decision:",
+            report.unresolved.len()
+        );
+        for path in &report.unresolved {
+            println!("  .noggin/{}", path);
+        }
+    }
+
+    if !report.conflicts.is_empty() || !report.unresolved.is_empty() {
+        println!("Run 'noggin resolve' to choose between them.");
+    }
+
+    if !report.pending.is_empty() {
+        println!();
+        println!(
+            "{} new entries went over the per-run cap and were held for review in .noggin/pending/.",
+            report.pending.len()
+        );
+        println!("Move the ones worth keeping into their category directory; delete the rest.");
+    }
+
+    if !report.incomplete.is_empty() {
+        println!();
+        println!(
+            "{} new entries lack fields their category template requires and were held in .noggin/pending/:",
+            report.incomplete.len()
+        );
+        for path in &report.incomplete {
+            println!("  {}", path);
+        }
+        println!("Fill in the fields under [context], then move them into their category directory.");
+    }
+
+    if !report.planned.is_empty() {
+        print_planned(&report.planned);
+    }
+
+    if let Some(checkpoint) = &report.deferred {
+        print_deferred(checkpoint);
+    }
 
-        if !scan_result.deleted.is_empty() {
-            println!("{} files deleted:", scan_result.deleted.len());
-            for path in &scan_result.deleted {
-                println!("  {}", path);
-            }
-        }
+    if report.mode == "lite" {
+        println!();
+        println!("Lite run: structural facts only. Once a provider is configured, run 'noggin learn --full' for model analysis.");
+    }
 
-        if !significant_commits.is_empty() {
-            println!("{} commits unprocessed:", significant_commits.len());
-            for c in &significant_commits {
-                println!("  {} {}", c.short_hash, c.message_summary);
-            }
-        }
+    print_warnings(&report.warnings);
+}
 
-        if !invalidated_patterns.is_empty() {
-            println!("{} patterns need re-analysis:", invalidated_patterns.len());
-            for p in &invalidated_patterns {
-                println!("  {}", p);
-            }
+/// Print the writes a dry run skipped, with their diffs
+pub fn print_planned(planned: &[PlannedWrite]) {
+    println!();
+    println!("Would write {} files:", planned.len());
+    for write in planned {
+        println!("  {} .noggin/{}", write.action, write.path);
+        for line in write.diff.lines() {
+            println!("      {}", line);
         }
-
-        anyhow::bail!("Drift detected. Run 'noggin learn' to update.");
     }
+}
 
-    // Step 7: Build prompts
-    let mut prompts = Vec::new();
+/// ARFs whose context cites `commit_hash` (full or abbreviated), with
+/// the path each was written to
+fn derived_arfs(
+    commit_hash: &str,
+    arfs: &[ArfFile],
+    locations: &[Option<String>],
+) -> Vec<NoteEntry> {
+    arfs.iter()
+        .zip(locations)
+        .filter_map(|(arf, location)| {
+            let path = location.as_ref()?;
+            let cited = arf
+                .context
+                .commits
+                .iter()
+                .any(|c| c.len() >= 7 && commit_hash.starts_with(c.as_str()));
+            cited.then(|| NoteEntry {
+                path: path.clone(),
+                what: arf.what.clone(),
+            })
+        })
+        .collect()
+}
 
-    if !scan_result.changed.is_empty() {
-        let file_prompt = build_file_analysis_prompt(&repo_path, &scan_result.changed);
-        prompts.push(("files".to_string(), file_prompt));
+/// A warning when `entries` is `size.anomaly_factor` times the average of
+/// earlier runs, which usually means a model response went wrong
+fn unusual_output(noggin_path: &Path, config: &Config, entries: usize) -> Option<String> {
+    let factor = config.size.anomaly_factor;
+    if factor <= 0.0 || !config.metrics.enabled {
+        return None;
     }
+    let average = Metrics::load(noggin_path).ok()?.average_entries()?;
+    (entries as f64 > average.max(1.0) * factor).then(|| {
+        format!(
+            "This run produced {} entries, over {}x the average of {:.1}; check the model output before trusting them",
+            entries, factor, average
+        )
+    })
+}
 
-    if !significant_commits.is_empty() {
-        let commit_prompt = build_commit_analysis_prompt(&significant_commits);
-        prompts.push(("commits".to_string(), commit_prompt));
+/// Park each field voting could not settle in `.noggin/conflicts/`, next
+/// to the ARF it belongs to (in the knowledge base or `pending/`, as
+/// `written` filed it), and return the record paths. Nothing is written
+/// in a dry run.
+pub(crate) fn save_manual_conflicts(
+    noggin_path: &Path,
+    arfs: &[ArfFile],
+    written: &WriteResult,
+    conflicts: &[synthesis::ManualConflict],
+    dry_run: bool,
+) -> Result<Vec<String>> {
+    let mut paths = Vec::new();
+    for conflict in conflicts {
+        let location = arfs
+            .iter()
+            .zip(written.locations.iter().zip(&written.held))
+            .find(|(arf, _)| arf.id_or_slug() == conflict.id)
+            .and_then(|(_, (location, held))| location.as_ref().or(held.as_ref()));
+        // Entries that became approval conflicts are already parked
+        let Some(location) = location else {
+            continue;
+        };
+        let record = VoteConflict::new(location.clone(), &conflict.field, conflict.values.clone());
+        if !dry_run {
+            record.save(noggin_path)?;
+        }
+        paths.push(record.relative_path());
     }
+    Ok(paths)
+}
 
-    // Build re-analysis prompt for invalidated patterns
-    if !invalidated_patterns.is_empty() {
-        let pattern_files = collect_pattern_files(&manifest, &invalidated_patterns, &repo_path);
-        if !pattern_files.is_empty() {
-            let pattern_prompt = build_pattern_reanalysis_prompt(
-                &repo_path,
-                &invalidated_patterns,
-                &pattern_files,
-            );
-            prompts.push(("patterns".to_string(), pattern_prompt));
+/// Configured history refs followed by any extra ones from the command line
+pub fn history_refs(config: &Config, extra: &[String]) -> Vec<String> {
+    let mut refs = config.history.refs.clone();
+    for r in extra {
+        if !refs.contains(r) {
+            refs.push(r.clone());
         }
     }
+    refs
+}
 
-    // Step 8: Invoke LLMs in parallel
-    let providers: Vec<Box<dyn LLMProvider>> = vec![
-        Box::new(ClaudeClient::new()),
-        Box::new(CodexClient::new()),
-        Box::new(GeminiClient::new()),
-    ];
-
-    let mut all_model_outputs: Vec<ModelOutput> = Vec::new();
-    let mut warnings: Vec<String> = Vec::new();
-
-    for (prompt_type, prompt) in &prompts {
-        let pb = spinner(&format!("Querying LLMs ({})...", prompt_type));
+/// Files are scanned from the working tree, so a `--ref` whose tree
+/// differs from HEAD's would have them recorded as the ref's
+fn check_refs_at_head(repo_path: &Path, refs: &[String]) -> Result<()> {
+    if refs.is_empty() {
+        return Ok(());
+    }
+    let repo = git2::Repository::open(repo_path)?;
+    let head_tree = repo.head().and_then(|h| h.peel_to_tree()).ok().map(|t| t.id());
+    for name in refs {
+        let tree = repo
+            .revparse_single(name)
+            .and_then(|o| o.peel_to_tree())
+            .with_context(|| format!("Failed to resolve ref '{}'", name))?;
+        if Some(tree.id()) != head_tree {
+            anyhow::bail!(
+                "--ref '{}' differs from HEAD, and learn reads files from the working tree; \
+                 check it out first, or use `noggin git-walk --ref` to inspect its history",
+                name
+            );
+        }
+    }
+    Ok(())
+}
 
-        match query_all(&providers, prompt).await {
-            Ok(parallel_result) => {
-                pb.finish_with_message(format!(
-                    "LLM {} analysis: {}/{} models responded",
-                    prompt_type,
-                    parallel_result.success_count(),
-                    parallel_result.success_count() + parallel_result.failure_count()
-                ));
+/// Write the blobs `paths` name in `tree` to a temporary directory,
+/// skipping those the tree doesn't have
+fn export_files(repo: &git2::Repository, tree: &git2::Tree, paths: &[String]) -> Result<TempDir> {
+    let dir = TempDir::new().context("Failed to create a directory for the range's files")?;
+    for path in paths {
+        let Ok(entry) = tree.get_path(Path::new(path)) else {
+            continue;
+        };
+        let Ok(blob) = entry.to_object(repo).and_then(|o| o.peel_to_blob()) else {
+            continue;
+        };
+        let dest = dir.path().join(path);
+        if let Some(parent) = dest.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&dest, blob.content())
+            .with_context(|| format!("Failed to export {}", path))?;
+    }
+    Ok(dir)
+}
 
-                for failure in &parallel_result.failures {
-                    warnings.push(format!(
-                        "{} failed for {} analysis: {}",
-                        failure.model, prompt_type, failure.error
-                    ));
-                }
+/// Cite each duplicate file alongside the representative analyzed for it
+fn attribute_duplicates(arfs: &mut [ArfFile], duplicates: &BTreeMap<String, Vec<String>>) {
+    for arf in arfs.iter_mut() {
+        let copies: Vec<String> = arf
+            .context
+            .files
+            .iter()
+            .filter_map(|path| duplicates.get(path))
+            .flatten()
+            .filter(|copy| !arf.context.files.contains(copy))
+            .cloned()
+            .collect();
+        arf.context.files.extend(copies);
+    }
+}
 
-                // Parse responses into ModelOutput
-                for model_result in &parallel_result.successes {
-                    match synthesis::parse_model_response(
-                        &model_result.model,
-                        &model_result.response,
-                    ) {
-                        Ok(arfs) => {
-                            info!(
-                                "Parsed {} ARF entries from {} ({})",
-                                arfs.len(),
-                                model_result.model,
-                                prompt_type
-                            );
-                            all_model_outputs.push(ModelOutput {
-                                model_name: model_result.model.clone(),
-                                arf_files: arfs,
-                            });
-                        }
-                        Err(e) => {
-                            warnings.push(format!(
-                                "Failed to parse {} output for {}: {}",
-                                model_result.model, prompt_type, e
-                            ));
-                        }
-                    }
-                }
+/// Record in each ARF's context the branches of the commits it cites
+fn attribute_branches(arfs: &mut [ArfFile], commits: &[CommitMetadata]) {
+    for arf in arfs.iter_mut() {
+        for cited in &arf.context.commits {
+            if cited.len() < 7 {
+                continue;
             }
-            Err(e) => {
-                pb.finish_with_message(format!("LLM {} analysis failed", prompt_type));
-                warnings.push(format!("All LLMs failed for {} analysis: {}", prompt_type, e));
+            let branch = commits
+                .iter()
+                .find(|c| c.hash.starts_with(cited.as_str()))
+                .and_then(|c| c.branch.as_ref());
+            if let Some(branch) = branch {
+                if !arf.context.branches.contains(branch) {
+                    arf.context.branches.push(branch.clone());
+                }
             }
         }
+        arf.context.branches.sort();
     }
+}
 
-    // Step 9: Synthesize consensus
-    let unified_arfs = if all_model_outputs.is_empty() {
-        warnings.push("No model outputs to synthesize".to_string());
-        Vec::new()
-    } else if all_model_outputs.len() == 1 {
-        // Single model, skip synthesis
-        info!("Single model output, skipping synthesis");
-        all_model_outputs.remove(0).arf_files
-    } else {
-        let pb = spinner("Synthesizing consensus...");
-        match synthesis::synthesize(all_model_outputs) {
-            Ok(result) => {
-                pb.finish_with_message(format!(
-                    "Synthesized {} ARF entries ({} conflicts resolved)",
-                    result.report.total_output_arfs, result.report.conflicts_resolved
-                ));
-                result.unified_arfs
-            }
-            Err(e) => {
-                pb.finish_with_message("Synthesis failed");
-                warnings.push(format!("Synthesis failed: {}", e));
-                Vec::new()
+/// Add the issue references in each cited commit's message to the ARFs
+/// citing it, and normalize every ARF's issues against `template`
+fn attribute_issues(arfs: &mut [ArfFile], commits: &[CommitMetadata], template: Option<&str>) {
+    for arf in arfs.iter_mut() {
+        let cited: Vec<&CommitMetadata> = arf
+            .context
+            .commits
+            .iter()
+            .filter(|cited| cited.len() >= 7)
+            .filter_map(|cited| commits.iter().find(|c| c.hash.starts_with(cited.as_str())))
+            .collect();
+        for commit in cited {
+            for reference in issues::references_in(&commit.message) {
+                arf.add_issue(reference);
             }
         }
-    };
-
-    // Step 10: Write ARF files
-    if !unified_arfs.is_empty() {
-        let pb = spinner("Writing ARF files...");
-        let write_result = write_arfs(&noggin_path, &unified_arfs)
-            .context("Failed to write ARF files")?;
-        pb.finish_with_message(format!(
-            "Wrote {} new, {} updated, {} skipped ARF files",
-            write_result.written, write_result.updated, write_result.skipped
-        ));
+        issues::normalize_arf(arf, template);
     }
+}
 
-    // Step 11: Update manifest
-    let pb = spinner("Updating manifest...");
+/// `commits` grouped under the `history.release_tags` tag each was first
+/// released in
+fn release_groups(
+    repo_path: &Path,
+    config: &Config,
+    commits: &[CommitMetadata],
+) -> Result<Vec<ReleaseGroup>> {
+    let repo = git2::Repository::open(repo_path)?;
+    let releases = release_tags(&repo, &config.history.release_tags)?;
+    Ok(group_by_release(&repo, &releases, commits.to_vec()))
+}
 
-    // Remove deleted files
-    for path in &scan_result.deleted {
-        manifest.remove_file(path);
+/// Record as `context.release` the release that first shipped the commits
+/// each ARF cites, unless the model already named one. Entries about
+/// unreleased commits get none.
+fn attribute_releases(arfs: &mut [ArfFile], groups: &[ReleaseGroup]) {
+    for arf in arfs.iter_mut() {
+        if arf.context.extra.contains_key(RELEASE_KEY) {
+            continue;
+        }
+        let release = arf
+            .context
+            .commits
+            .iter()
+            .filter(|cited| cited.len() >= 7)
+            .find_map(|cited| {
+                groups
+                    .iter()
+                    .find(|g| g.commits.iter().any(|c| c.hash.starts_with(cited.as_str())))
+            })
+            .and_then(|group| group.release.as_ref());
+        if let Some(release) = release {
+            arf.context
+                .extra
+                .insert(RELEASE_KEY.to_string(), toml::Value::String(release.tag.clone()));
+        }
     }
+}
 
-    // Update file hashes
-    for file in &scan_result.changed {
-        manifest.add_or_update_file(file.path.clone(), file.hash.clone(), vec![]);
-    }
+/// Record in each ARF's context the authors of the commits it cites,
+/// those with the most cited commits first. With people disabled, any
+/// authors a model supplied are removed instead.
+fn attribute_people(arfs: &mut [ArfFile], commits: &[CommitMetadata], config: &PeopleConfig) {
+    for arf in arfs.iter_mut() {
+        if !config.enabled {
+            arf.context.people.clear();
+            continue;
+        }
 
-    // Invalidate affected patterns
-    for pattern_id in &invalidated_patterns {
-        manifest.invalidate_pattern(pattern_id);
-    }
+        let mut counts: Vec<(String, usize)> = Vec::new();
+        for cited in arf.context.commits.iter().filter(|cited| cited.len() >= 7) {
+            let Some(commit) = commits.iter().find(|c| c.hash.starts_with(cited.as_str())) else {
+                continue;
+            };
+            let name = author_name(&commit.author);
+            match counts.iter_mut().find(|(n, _)| *n == name) {
+                Some((_, count)) => *count += 1,
+                None => counts.push((name, 1)),
+            }
+        }
+        if counts.is_empty() {
+            continue;
+        }
 
-    // Update commit entries
-    for commit in &significant_commits {
-        let category = infer_commit_category(&commit.message_summary);
-        manifest.add_commit(
-            commit.hash.clone(),
-            category,
-            String::new(),
-        );
+        // Stable sort keeps first-cited order among equals
+        counts.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+        let mut people: Vec<String> = counts.into_iter().map(|(name, _)| name).collect();
+        for existing in arf.context.people.drain(..) {
+            if !people.contains(&existing) {
+                people.push(existing);
+            }
+        }
+        people.truncate(config.max_per_entry);
+        arf.context.people = people;
     }
+}
 
-    manifest
-        .save(&manifest_path)
-        .context("Failed to save manifest")?;
-
-    pb.finish_with_message("Manifest updated");
-
-    // Step 12: Print summary
-    println!();
-    println!("=== Learn Complete ===");
-    println!("  Files analyzed:        {}", scan_result.changed.len());
-    println!("  Files deleted:         {}", scan_result.deleted.len());
-    println!("  Commits processed:     {}", significant_commits.len());
-    println!("  Patterns invalidated:  {}", invalidated_patterns.len());
-    println!("  ARF entries:           {}", unified_arfs.len());
-
-    print_warnings(&warnings);
+/// Record in each ARF's outcome the authors owning most lines of the
+/// files it cites
+fn attribute_file_authors(repo: &git2::Repository, arfs: &mut [ArfFile], config: &PeopleConfig) {
+    let mut blame = Blame::new(repo, config.max_blame_files);
+    for arf in arfs.iter_mut() {
+        if !(config.enabled && config.blame) {
+            arf.context.outcome.remove(AUTHORS_KEY);
+            continue;
+        }
+        let authors = blame.dominant_authors(&arf.context.files, config.max_per_entry);
+        if !authors.is_empty() {
+            arf.add_outcome(AUTHORS_KEY, authors.join(", "));
+        }
+    }
+}
 
-    Ok(())
+/// Name part of a `Name <email>` author, without the address
+fn author_name(author: &str) -> String {
+    author
+        .split_once('<')
+        .map(|(name, _)| name)
+        .unwrap_or(author)
+        .trim()
+        .to_string()
 }
 
 /// Find patterns that need re-analysis due to changed or deleted files.
 ///
 /// Looks up each changed/deleted file in the manifest to find patterns
 /// that reference it. Returns the set of unique pattern IDs to re-analyze.
-fn find_invalidated_patterns(
+pub fn find_invalidated_patterns(
     manifest: &Manifest,
     changed: &[FileToAnalyze],
     deleted: &[String],
@@ -426,8 +2197,148 @@ fn infer_commit_category(message: &str) -> CommitCategory {
     }
 }
 
-/// Create a spinner-style progress bar
-fn spinner(message: &str) -> ProgressBar {
+/// Bring an off-language response into `llm.output_language`. With
+/// `translate_responses`, another provider (or the same one if it is the
+/// only one) rewrites the prose; otherwise, or if translation fails, the
+/// output is dropped so it can't make the merged ARFs bilingual. Returns
+/// whether the output should be kept.
+async fn normalize_language(
+    scheduler: &Scheduler,
+    providers: &[Box<dyn LLMProvider>],
+    model: &str,
+    arfs: &mut [ArfFile],
+    llm: &LlmConfig,
+    budget: &mut Budget,
+    warnings: &mut Vec<String>,
+) -> bool {
+    let language = &llm.output_language;
+    if matches_language(arfs, language) {
+        return true;
+    }
+    if !llm.translate_responses {
+        warnings.push(format!(
+            "{} did not answer in {}; discarded its output (set llm.translate_responses to translate instead)",
+            model, language
+        ));
+        return false;
+    }
+
+    let Some(translator) = providers
+        .iter()
+        .find(|p| p.name() != model)
+        .or_else(|| providers.first())
+    else {
+        return false;
+    };
+
+    let prompt = build_translation_prompt(arfs, language);
+    let result = scheduler
+        .query(translator.as_ref(), &prompt, &ignore_chunks)
+        .await;
+    let response_tokens = result.as_ref().map(|r| estimate_tokens(r)).unwrap_or(0);
+    budget.record_provider(translator.name(), estimate_tokens(&prompt), response_tokens);
+
+    match result
+        .map_err(anyhow::Error::from)
+        .and_then(|response| apply_translation(translator.name(), arfs, &response))
+    {
+        Ok(()) => {
+            warnings.push(format!(
+                "{} did not answer in {}; translated its output with {}",
+                model,
+                language,
+                translator.name()
+            ));
+            true
+        }
+        Err(e) => {
+            warnings.push(format!(
+                "{} did not answer in {} and translation failed, discarded its output: {:#}",
+                model, language, e
+            ));
+            false
+        }
+    }
+}
+
+/// Progress of the prompts in flight during step 8
+struct QueryProgress {
+    total: usize,
+    done: usize,
+    /// Characters received so far, per prompt and provider
+    received: Vec<BTreeMap<String, usize>>,
+}
+
+impl QueryProgress {
+    fn new(total: usize) -> Self {
+        Self {
+            total,
+            done: 0,
+            received: vec![BTreeMap::new(); total],
+        }
+    }
+
+    /// Spinner message: prompts finished and characters received from
+    /// each provider across all prompts
+    fn message(&self) -> String {
+        let mut by_model: BTreeMap<&str, usize> = BTreeMap::new();
+        for (model, chars) in self.received.iter().flatten() {
+            *by_model.entry(model).or_default() += chars;
+        }
+        let counts: Vec<String> = by_model
+            .iter()
+            .map(|(model, chars)| format!("{} {} chars", model, chars))
+            .collect();
+        format!(
+            "Querying LLMs ({}/{} prompts done)... {}",
+            self.done,
+            self.total,
+            counts.join(", ")
+        )
+    }
+}
+
+/// Report how each provider fared on one prompt
+fn emit_provider_results(
+    events: &EventSink,
+    prompt_type: &str,
+    providers: &[Box<dyn LLMProvider>],
+    result: &Result<ParallelResult, crate::error::Error>,
+) {
+    if !events.is_enabled() {
+        return;
+    }
+    let finished = |provider: &str, ok: bool, chars: usize, error: Option<String>| {
+        events.emit(Event::ProviderFinished {
+            prompt_type: prompt_type.to_string(),
+            provider: provider.to_string(),
+            ok,
+            chars,
+            error,
+        });
+    };
+    match result {
+        Ok(parallel_result) => {
+            for success in &parallel_result.successes {
+                finished(&success.model, true, success.response.chars().count(), None);
+            }
+            for failure in &parallel_result.failures {
+                finished(&failure.model, false, 0, Some(failure.error.clone()));
+            }
+        }
+        Err(e) => {
+            for provider in providers {
+                finished(provider.name(), false, 0, Some(e.to_string()));
+            }
+        }
+    }
+}
+
+/// Create a spinner-style progress bar (hidden when `quiet`)
+fn spinner(message: &str, quiet: bool) -> ProgressBar {
+    if quiet {
+        return ProgressBar::hidden();
+    }
     let pb = ProgressBar::new_spinner();
     pb.set_style(
         ProgressStyle::default_spinner()
@@ -439,6 +2350,47 @@ fn spinner(message: &str) -> ProgressBar {
     pb
 }
 
+/// Report knowledge dropped from prompts by the token budget
+fn print_truncation(truncation: &[PromptTruncation]) {
+    println!();
+    println!("Prompts were truncated to fit size limits:");
+    for entry in truncation {
+        let stats = &entry.stats;
+        println!(
+            "  - {} analysis: {} files omitted, {} files cut ({} lines), {} unreadable, ~{} bytes dropped",
+            entry.prompt_type,
+            stats.files_omitted,
+            stats.files_truncated,
+            stats.lines_dropped,
+            stats.files_unreadable,
+            stats.bytes_dropped
+        );
+    }
+    println!("  Dropped content was not sent to the models.");
+}
+
+/// Report work deferred by a budget cap or failed prompts
+fn print_deferred(checkpoint: &Checkpoint) {
+    let (files, commits, patterns) = checkpoint.counts();
+    println!();
+    if let Some(limit) = &checkpoint.limit {
+        println!("Budget cap reached ({}). Deferred:", limit);
+        for prompt in &checkpoint.deferred {
+            println!("  - {} analysis", prompt.prompt_type);
+        }
+    }
+    if !checkpoint.failed.is_empty() {
+        println!("No usable response, retried next run:");
+        for prompt in &checkpoint.failed {
+            println!("  - {} analysis", prompt.prompt_type);
+        }
+    }
+    println!(
+        "  {} files, {} commits, {} patterns left for the next run (see .noggin/checkpoint.toml)",
+        files, commits, patterns
+    );
+}
+
 /// Print collected warnings
 fn print_warnings(warnings: &[String]) {
     if !warnings.is_empty() {
@@ -455,6 +2407,19 @@ mod tests {
     use super::*;
     use crate::learn::scanner::FileToAnalyze;
 
+    #[test]
+    fn test_query_progress() {
+        let mut progress = QueryProgress::new(2);
+        progress.received[0].insert("gemini".to_string(), 1200);
+        progress.received[0].insert("claude".to_string(), 40);
+        progress.received[1].insert("claude".to_string(), 60);
+        progress.done = 1;
+        assert_eq!(
+            progress.message(),
+            "Querying LLMs (1/2 prompts done)... claude 100 chars, gemini 1200 chars"
+        );
+    }
+
     #[test]
     fn test_infer_commit_category_bug() {
         assert!(matches!(
@@ -591,4 +2556,400 @@ mod tests {
 
         assert!(result.is_empty());
     }
+
+    #[test]
+    fn test_derived_arfs_matches_abbreviated_hashes() {
+        let hash = "a1b2c3d4e5f60718293a4b5c6d7e8f9012345678";
+
+        let mut cited = ArfFile::new("Adopt tokio", "Async", "tokio::main");
+        cited.add_commit("a1b2c3d");
+        let mut conflicted = ArfFile::new("Drop tokio", "Sync", "std");
+        conflicted.add_commit(hash);
+        let mut other = ArfFile::new("Other", "Why", "How");
+        other.add_commit("ffffffff");
+        let mut too_short = ArfFile::new("Short", "Why", "How");
+        too_short.add_commit("a1b2");
+
+        let arfs = vec![cited, conflicted, other, too_short];
+        let locations = vec![
+            Some("decisions/adopt-tokio.arf".to_string()),
+            None,
+            Some("facts/other.arf".to_string()),
+            Some("facts/short.arf".to_string()),
+        ];
+
+        let derived = derived_arfs(hash, &arfs, &locations);
+        assert_eq!(
+            derived,
+            vec![NoteEntry {
+                path: "decisions/adopt-tokio.arf".to_string(),
+                what: "Adopt tokio".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_attribute_branches_from_cited_commits() {
+        let commit = |hash: &str, branch: Option<&str>| CommitMetadata {
+            hash: hash.to_string(),
+            short_hash: hash[..7].to_string(),
+            author: "Test <test@example.com>".to_string(),
+            timestamp: 0,
+            message: String::new(),
+            message_summary: String::new(),
+            files_changed: 0,
+            insertions: 0,
+            deletions: 0,
+            parent_hashes: vec![],
+            shallow_boundary: false,
+            branch: branch.map(str::to_string),
+        };
+        let commits = vec![
+            commit("aaaaaaa111", Some("release/2.x")),
+            commit("bbbbbbb222", Some("main")),
+            commit("ccccccc333", None),
+        ];
+
+        let mut arf = ArfFile::new("Backport fix", "Why", "How");
+        arf.add_commit("bbbbbbb");
+        arf.add_commit("aaaaaaa111");
+        arf.add_commit("ccccccc");
+        let mut arfs = vec![arf];
+
+        attribute_branches(&mut arfs, &commits);
+        assert_eq!(arfs[0].context.branches, vec!["main", "release/2.x"]);
+    }
+
+    #[test]
+    fn test_record_truncation_until_history_is_complete() {
+        let boundary = CommitMetadata {
+            hash: "aaaaaaa111".to_string(),
+            short_hash: "aaaaaaa".to_string(),
+            author: "Test <test@example.com>".to_string(),
+            timestamp: 0,
+            message: String::new(),
+            message_summary: String::new(),
+            files_changed: 0,
+            insertions: 0,
+            deletions: 0,
+            parent_hashes: vec![],
+            shallow_boundary: true,
+            branch: None,
+        };
+        let mut manifest = Manifest::default();
+
+        assert!(!record_truncation(&mut manifest, true, true, std::slice::from_ref(&boundary)));
+        let first = manifest.truncated_history.clone().unwrap();
+        assert_eq!(first.boundaries, std::collections::BTreeSet::from(["aaaaaaa111".to_string()]));
+
+        // Deepened but still shallow: new boundaries, same start
+        assert!(!record_truncation(&mut manifest, true, true, &[]));
+        let deepened = manifest.truncated_history.clone().unwrap();
+        assert!(deepened.boundaries.is_empty());
+        assert_eq!(deepened.since, first.since);
+
+        // A filtered walk can't tell, so the record stays
+        assert!(!record_truncation(&mut manifest, false, false, &[]));
+        assert!(manifest.truncated_history.is_some());
+
+        assert!(record_truncation(&mut manifest, false, true, &[]));
+        assert!(manifest.truncated_history.is_none());
+        assert!(!record_truncation(&mut manifest, false, true, &[]));
+    }
+
+    #[test]
+    fn test_significant_commits_skips_malformed_hashes() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let repo = git2::Repository::init(temp_dir.path()).unwrap();
+        let commit = CommitMetadata {
+            hash: "not-a-hash".to_string(),
+            short_hash: "not-a-h".to_string(),
+            author: "Test <test@example.com>".to_string(),
+            timestamp: 0,
+            message: "Security fix for token leak".to_string(),
+            message_summary: "Security fix for token leak".to_string(),
+            files_changed: 0,
+            insertions: 0,
+            deletions: 0,
+            parent_hashes: vec![],
+            shallow_boundary: false,
+            branch: None,
+        };
+        assert!(significant_commits(&repo, vec![commit], &ScoringConfig::default()).is_empty());
+    }
+
+    #[test]
+    fn test_manual_conflicts_follow_pending_entries() -> Result<()> {
+        let temp_dir = tempfile::TempDir::new()?;
+        let noggin_path = temp_dir.path();
+        for dir in crate::learn::writer::CATEGORY_DIRS {
+            std::fs::create_dir_all(noggin_path.join(dir))?;
+        }
+        let arfs = vec![
+            ArfFile::new("Use retry pattern", "Why", "How"),
+            ArfFile::new("Use builder pattern", "Why", "How"),
+        ];
+        let opts = WriteOptions {
+            max_new: Some(1),
+            ..Default::default()
+        };
+        let backend = crate::storage::FilesystemBackend::new(noggin_path);
+        let written = apply_arfs(&backend, noggin_path, &arfs, &opts)?;
+
+        let conflicts = vec![synthesis::ManualConflict {
+            id: "use-builder-pattern".to_string(),
+            field: "context.outcome.result".to_string(),
+            values: vec![
+                ("claude".to_string(), "faster".to_string()),
+                ("gemini".to_string(), "slower".to_string()),
+            ],
+        }];
+        let saved = save_manual_conflicts(noggin_path, &arfs, &written, &conflicts, false)?;
+        assert_eq!(saved, vec!["conflicts/use-builder-pattern.context.outcome.result.toml"]);
+        let record = std::fs::read_to_string(noggin_path.join(&saved[0]))?;
+        assert!(record.contains("pending/patterns/use-builder-pattern.arf"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_attribute_duplicates_cites_copies() {
+        let duplicates = BTreeMap::from([(
+            "api/.eslintrc".to_string(),
+            vec!["web/.eslintrc".to_string(), "worker/.eslintrc".to_string()],
+        )]);
+        let mut shared = ArfFile::new("Lint config", "Why", "How");
+        shared.add_file("api/.eslintrc");
+        shared.add_file("worker/.eslintrc");
+        let mut other = ArfFile::new("Entry point", "Why", "How");
+        other.add_file("main.rs");
+        let mut arfs = vec![shared, other];
+
+        attribute_duplicates(&mut arfs, &duplicates);
+        assert_eq!(
+            arfs[0].context.files,
+            vec!["api/.eslintrc", "worker/.eslintrc", "web/.eslintrc"]
+        );
+        assert_eq!(arfs[1].context.files, vec!["main.rs"]);
+    }
+
+    #[test]
+    fn test_attribute_releases_from_cited_commits() {
+        let commit = |hash: &str| CommitMetadata {
+            hash: hash.to_string(),
+            short_hash: hash[..7].to_string(),
+            author: "Test <test@example.com>".to_string(),
+            timestamp: 0,
+            message: String::new(),
+            message_summary: String::new(),
+            files_changed: 0,
+            insertions: 0,
+            deletions: 0,
+            parent_hashes: vec![],
+            shallow_boundary: false,
+            branch: None,
+        };
+        let groups = vec![
+            ReleaseGroup {
+                previous: None,
+                release: Some(crate::git::releases::Release {
+                    tag: "v1.0".to_string(),
+                    commit: "aaaaaaa111".to_string(),
+                    timestamp: 0,
+                    message: None,
+                }),
+                commits: vec![commit("aaaaaaa111")],
+            },
+            ReleaseGroup {
+                previous: Some("v1.0".to_string()),
+                release: None,
+                commits: vec![commit("bbbbbbb222")],
+            },
+        ];
+
+        let released = ArfFile::new("Adopt deadpool", "Why", "How");
+        let mut named = released.clone();
+        named
+            .context
+            .extra
+            .insert(RELEASE_KEY.to_string(), toml::Value::String("v0.9".to_string()));
+        let mut arfs = vec![released, named, ArfFile::new("Retry", "Why", "How")];
+        for arf in &mut arfs[..2] {
+            arf.add_commit("aaaaaaa");
+        }
+        arfs[2].add_commit("bbbbbbb");
+
+        attribute_releases(&mut arfs, &groups);
+        let releases: Vec<Option<&str>> = arfs
+            .iter()
+            .map(|arf| arf.context.extra.get(RELEASE_KEY).and_then(|v| v.as_str()))
+            .collect();
+        assert_eq!(releases, vec![Some("v1.0"), Some("v0.9"), None]);
+    }
+
+    #[test]
+    fn test_attribute_issues_from_cited_commits() {
+        let commit = CommitMetadata {
+            hash: "aaaaaaa111".to_string(),
+            short_hash: "aaaaaaa".to_string(),
+            author: "Test <test@example.com>".to_string(),
+            timestamp: 0,
+            message: "Retry failed payments (#12)\n\nFixes: #9\n".to_string(),
+            message_summary: "Retry failed payments (#12)".to_string(),
+            files_changed: 0,
+            insertions: 0,
+            deletions: 0,
+            parent_hashes: vec![],
+            shallow_boundary: false,
+            branch: None,
+        };
+
+        let mut arf = ArfFile::new("Retry payments", "Why", "How");
+        arf.add_commit("aaaaaaa");
+        arf.add_issue("not an issue");
+        let mut arfs = vec![arf];
+
+        attribute_issues(&mut arfs, &[commit], Some("https://github.com/o/r/issues/{id}"));
+        assert_eq!(
+            arfs[0].context.issues,
+            vec!["https://github.com/o/r/issues/12", "https://github.com/o/r/issues/9"]
+        );
+    }
+
+    #[test]
+    fn test_attribute_people_ranks_authors() {
+        let commit = |hash: &str, author: &str| CommitMetadata {
+            hash: hash.to_string(),
+            short_hash: hash[..7].to_string(),
+            author: author.to_string(),
+            timestamp: 0,
+            message: String::new(),
+            message_summary: String::new(),
+            files_changed: 0,
+            insertions: 0,
+            deletions: 0,
+            parent_hashes: vec![],
+            shallow_boundary: false,
+            branch: None,
+        };
+        let commits = vec![
+            commit("aaaaaaa111", "Bo <bo@example.com>"),
+            commit("bbbbbbb222", "Ana <ana@example.com>"),
+            commit("ccccccc333", "Ana <ana@example.com>"),
+        ];
+        let mut arf = ArfFile::new("Billing retries", "Why", "How");
+        for hash in ["aaaaaaa", "bbbbbbb", "ccccccc"] {
+            arf.add_commit(hash);
+        }
+
+        let mut arfs = vec![arf.clone()];
+        attribute_people(&mut arfs, &commits, &PeopleConfig::default());
+        assert_eq!(arfs[0].context.people, vec!["Ana", "Bo"]);
+
+        arf.context.people = vec!["Someone".to_string()];
+        let mut arfs = vec![arf];
+        let disabled = PeopleConfig {
+            enabled: false,
+            ..Default::default()
+        };
+        attribute_people(&mut arfs, &commits, &disabled);
+        assert!(arfs[0].context.people.is_empty());
+    }
+
+    #[test]
+    fn test_check_refs_at_head() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let repo = git2::Repository::init(temp_dir.path()).unwrap();
+        let sig = git2::Signature::now("Test", "test@example.com").unwrap();
+        let commit = |content: &str, parents: &[&git2::Commit]| {
+            let blob = repo.blob(content.as_bytes()).unwrap();
+            let mut builder = repo.treebuilder(None).unwrap();
+            builder.insert("lib.rs", blob, 0o100644).unwrap();
+            let tree = repo.find_tree(builder.write().unwrap()).unwrap();
+            let id = repo.commit(None, &sig, &sig, content, &tree, parents).unwrap();
+            repo.find_commit(id).unwrap()
+        };
+        let first = commit("fn a() {}", &[]);
+        let second = commit("fn b() {}", &[&first]);
+        repo.branch("release", &first, false).unwrap();
+        repo.branch("main", &second, false).unwrap();
+        repo.set_head("refs/heads/main").unwrap();
+
+        assert!(check_refs_at_head(temp_dir.path(), &[]).is_ok());
+        assert!(check_refs_at_head(temp_dir.path(), &["main".to_string()]).is_ok());
+        let err = check_refs_at_head(temp_dir.path(), &["release".to_string()]).unwrap_err();
+        assert!(err.to_string().contains("--ref 'release' differs from HEAD"));
+        assert!(check_refs_at_head(temp_dir.path(), &["missing".to_string()]).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_run_learn_refuses_foreign_knowledge_base() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let repo = git2::Repository::init(temp_dir.path()).unwrap();
+        repo.remote("origin", "git@github.com:someone/fork.git").unwrap();
+
+        let noggin_path = temp_dir.path().join(".noggin");
+        std::fs::create_dir(&noggin_path).unwrap();
+        let manifest = Manifest {
+            repository: Some(RepositoryFingerprint {
+                root_commit: None,
+                origin_url: Some("github.com/ducks/noggin".to_string()),
+                bound_at: chrono::Utc::now(),
+            }),
+            ..Default::default()
+        };
+        manifest.save(&noggin_path.join("manifest.toml")).unwrap();
+
+        let opts = LearnOptions {
+            quiet: true,
+            ..Default::default()
+        };
+        let err = run_learn(temp_dir.path(), &opts).await.unwrap_err();
+        assert!(err.to_string().contains("Repository mismatch"));
+        assert!(err.to_string().contains("github.com/someone/fork"));
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_leaves_manifest_untouched() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let repo = git2::Repository::init(temp_dir.path()).unwrap();
+        repo.remote("origin", "git@github.com:someone/fork.git").unwrap();
+
+        let noggin_path = temp_dir.path().join(".noggin");
+        std::fs::create_dir(&noggin_path).unwrap();
+        let manifest_path = noggin_path.join("manifest.toml");
+        let manifest = Manifest {
+            repository: Some(RepositoryFingerprint {
+                root_commit: None,
+                origin_url: Some("github.com/ducks/noggin".to_string()),
+                bound_at: chrono::Utc::now(),
+            }),
+            ..Default::default()
+        };
+        manifest.save(&manifest_path).unwrap();
+        let before = std::fs::read_to_string(&manifest_path).unwrap();
+
+        let events_dir = tempfile::TempDir::new().unwrap();
+        let events_path = events_dir.path().join("events.jsonl");
+        let opts = LearnOptions {
+            quiet: true,
+            force_adopt: true,
+            dry_run: true,
+            events: Some(events_path.clone()),
+            ..Default::default()
+        };
+        let report = run_learn(temp_dir.path(), &opts).await.unwrap();
+        assert!(report.dry_run);
+        assert!(report.up_to_date);
+        assert_eq!(std::fs::read_to_string(&manifest_path).unwrap(), before);
+
+        let events: Vec<serde_json::Value> = std::fs::read_to_string(&events_path)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        let names: Vec<&str> = events.iter().map(|e| e["event"].as_str().unwrap()).collect();
+        assert_eq!(names, vec!["run_started", "scan_finished", "history_walked", "run_finished"]);
+        assert_eq!(events[3]["up_to_date"], true);
+    }
 }
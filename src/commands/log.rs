@@ -0,0 +1,95 @@
+//! `noggin log`: previous learn runs and what they produced
+//!
+//! Reads the run records learn saves under `.noggin/reports/` (see
+//! [`crate::learn::history`]) and lists them newest first: mode, entries
+//! synthesized and written, model agreement, conflicts, and spend.
+
+use crate::learn::history::{list_runs, RunRecord};
+use crate::time::format_datetime;
+use anyhow::Result;
+use colored::Colorize;
+use std::env;
+
+/// Options for `noggin log`
+#[derive(Debug, Clone)]
+pub struct LogOptions {
+    /// Most recent runs to show
+    pub limit: usize,
+    /// Also list each run's warnings
+    pub verbose: bool,
+    pub json: bool,
+    /// Show times in UTC instead of local time
+    pub utc: bool,
+}
+
+pub fn log_command(opts: LogOptions) -> Result<()> {
+    let repo_path = env::current_dir()?;
+    let noggin_path = repo_path.join(".noggin");
+    if !noggin_path.exists() {
+        anyhow::bail!("Not initialized. Run 'noggin init' first.");
+    }
+
+    let mut runs = list_runs(&noggin_path)?;
+    runs.truncate(opts.limit);
+
+    if opts.json {
+        println!("{}", serde_json::to_string_pretty(&runs)?);
+        return Ok(());
+    }
+
+    if runs.is_empty() {
+        println!("No learn runs recorded yet. Run 'noggin learn' first.");
+        return Ok(());
+    }
+    for run in &runs {
+        print_run(run, &opts);
+    }
+    Ok(())
+}
+
+fn print_run(run: &RunRecord, opts: &LogOptions) {
+    println!(
+        "{} {}",
+        format_datetime(&run.finished_at, opts.utc).bold(),
+        run.mode.cyan()
+    );
+    println!(
+        "  {} files, {} commits analyzed; {} entries, {} written",
+        run.files_analyzed, run.commits_processed, run.arf_entries, run.arfs_written
+    );
+    if let Some(synthesis) = &run.synthesis {
+        println!(
+            "  {:.0}% model agreement ({}); {} conflicts, {} settled by vote",
+            synthesis.model_agreement_pct,
+            synthesis.models_used.join(", "),
+            synthesis.conflicts_detected,
+            synthesis.conflicts_resolved
+        );
+    }
+    if run.conflicts > 0 || run.pending > 0 {
+        println!(
+            "  {}",
+            format!(
+                "{} conflicts left for review, {} entries held in pending/",
+                run.conflicts, run.pending
+            )
+            .yellow()
+        );
+    }
+    if run.requests > 0 {
+        println!(
+            "  {} requests, {} tokens, ~${:.2}",
+            run.requests, run.tokens, run.estimated_cost_usd
+        );
+    }
+    if !run.warnings.is_empty() {
+        if opts.verbose {
+            for warning in &run.warnings {
+                println!("  {} {}", "warning:".yellow(), warning);
+            }
+        } else {
+            println!("  {}", format!("{} warnings (-v to show)", run.warnings.len()).dimmed());
+        }
+    }
+    println!();
+}
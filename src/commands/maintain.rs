@@ -0,0 +1,337 @@
+//! `noggin maintain`: bounded background upkeep for large repositories
+//!
+//! Each invocation does a capped slice of deferred work so a nightly job
+//! converges on a complete knowledge base without one expensive run:
+//!
+//! 1. gc: drop temp files and manifest entries for vanished
+//!    commits/patterns/branches
+//! 2. index: rebuild the manifest's file -> pattern links
+//! 3. refresh: re-analyze the files behind the lowest-confidence entries
+//!    (see [`crate::decay`]), then the stalest patterns
+//! 4. backfill: process the oldest unprocessed commits
+//!
+//! Steps 3 and 4 run through the learn pipeline under the configured budget.
+//! With `dry_run`, every step reports what it would change and nothing is
+//! removed or saved.
+
+use crate::commands::learn::{print_planned, run_learn, LearnOptions, LearnReport};
+use crate::config::Config;
+use crate::decay::low_confidence;
+use crate::lock::KnowledgeLock;
+use crate::storage;
+use crate::manifest::{path_key, Manifest};
+use anyhow::{Context, Result};
+use chrono::{Duration, Utc};
+use git2::{BranchType, Oid, Repository};
+use serde::Serialize;
+use std::env;
+use std::fs;
+use std::path::Path;
+
+/// CLI overrides for the `[maintain]` config section
+#[derive(Debug, Clone, Default)]
+pub struct MaintainOptions {
+    pub max_commits: Option<usize>,
+    pub stale_days: Option<u32>,
+    pub max_refresh: Option<usize>,
+    pub max_cost: Option<f64>,
+    pub max_time: Option<u64>,
+    pub json: bool,
+    /// Report what would change without touching disk
+    pub dry_run: bool,
+}
+
+/// What one maintenance run did
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct MaintainReport {
+    pub gc: GcReport,
+    /// File entries whose pattern links were repaired
+    pub index_entries_repaired: usize,
+    /// Low-confidence entries whose files were queued for re-analysis,
+    /// lowest first
+    pub decayed_entries: Vec<String>,
+    /// Stale patterns queued for refresh this run
+    pub refreshed_patterns: Vec<String>,
+    pub learn: LearnReport,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct GcReport {
+    pub temp_files_removed: usize,
+    /// Commit entries for commits no longer in the repository
+    pub commits_pruned: usize,
+    /// Pattern entries whose contributing files are all gone
+    pub patterns_pruned: usize,
+    /// Deleted branches whose records were dropped
+    pub branches_pruned: usize,
+}
+
+pub async fn maintain_command(opts: MaintainOptions) -> Result<()> {
+    let repo_path = env::current_dir()?;
+    let report = run_maintain(&repo_path, &opts).await?;
+
+    if opts.json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+        return Ok(());
+    }
+
+    println!();
+    if opts.dry_run {
+        println!("=== Maintenance Dry Run (nothing written) ===");
+    } else {
+        println!("=== Maintenance Complete ===");
+    }
+    println!("  Temp files removed:    {}", report.gc.temp_files_removed);
+    println!("  Commits pruned:        {}", report.gc.commits_pruned);
+    println!("  Patterns pruned:       {}", report.gc.patterns_pruned);
+    println!("  Branches pruned:       {}", report.gc.branches_pruned);
+    println!("  Index entries fixed:   {}", report.index_entries_repaired);
+    println!("  Decayed entries:       {}", report.decayed_entries.len());
+    println!("  Patterns refreshed:    {}", report.refreshed_patterns.len());
+    println!("  Commits backfilled:    {}", report.learn.commits_processed);
+    println!("  Commits remaining:     {}", report.learn.commits_remaining);
+    println!(
+        "  Provider usage:        {} requests, ~{} tokens, ~${:.2}",
+        report.learn.usage.requests,
+        report.learn.usage.tokens,
+        report.learn.usage.estimated_cost_usd
+    );
+
+    if let Some(checkpoint) = &report.learn.deferred {
+        if let Some(limit) = &checkpoint.limit {
+            println!("Budget cap reached ({}); remaining work resumes next run.", limit);
+        }
+        if !checkpoint.failed.is_empty() {
+            println!(
+                "{} prompts got no usable response; their work resumes next run.",
+                checkpoint.failed.len()
+            );
+        }
+    }
+
+    if !report.learn.planned.is_empty() {
+        print_planned(&report.learn.planned);
+    }
+
+    Ok(())
+}
+
+/// Run one bounded maintenance pass against `repo_path`
+pub async fn run_maintain(repo_path: &Path, opts: &MaintainOptions) -> Result<MaintainReport> {
+    let noggin_path = repo_path.join(".noggin");
+    if !noggin_path.exists() {
+        anyhow::bail!(".noggin/ directory not found. Run 'noggin init' first.");
+    }
+
+    let config = Config::load(&noggin_path).context("Failed to load config")?;
+    let max_commits = opts.max_commits.unwrap_or(config.maintain.max_commits);
+    let stale_days = opts.stale_days.unwrap_or(config.maintain.stale_days);
+    let max_refresh = opts.max_refresh.unwrap_or(config.maintain.max_refresh);
+    let max_decayed = config.maintain.max_decayed;
+
+    // Held through gc; learn takes it again itself
+    let lock = if opts.dry_run {
+        None
+    } else {
+        Some(KnowledgeLock::acquire(&noggin_path, "maintain")?)
+    };
+    let backend = storage::open(&noggin_path, &config.storage);
+    let mut manifest = backend.load_manifest().context("Failed to load manifest")?;
+
+    let mut report = MaintainReport::default();
+
+    // gc and index rebuild are local and free, so they always run in full
+    report.gc.temp_files_removed = remove_temp_files(&noggin_path, opts.dry_run)?;
+    report.gc.commits_pruned = prune_missing_commits(repo_path, &mut manifest)?;
+    report.gc.patterns_pruned = prune_orphaned_patterns(&mut manifest);
+    report.gc.branches_pruned = prune_deleted_branches(repo_path, &mut manifest)?;
+    report.index_entries_repaired = manifest.rebuild_pattern_index();
+
+    if !opts.dry_run {
+        backend
+            .save_manifest(&manifest)
+            .context("Failed to save manifest")?;
+    }
+    drop(lock);
+
+    // Decayed entries go first; their files are re-analyzed even if unchanged
+    let mut refresh_files: Vec<String> = Vec::new();
+    for entry in low_confidence(repo_path, backend.as_ref(), &manifest, &config.decay)?
+        .into_iter()
+        .filter(|entry| !entry.files.is_empty())
+        .take(max_decayed)
+    {
+        for file in &entry.files {
+            if !refresh_files.contains(file) {
+                refresh_files.push(file.clone());
+            }
+        }
+        report.decayed_entries.push(entry.path);
+    }
+
+    let cutoff = Utc::now() - Duration::days(i64::from(stale_days));
+    report.refreshed_patterns = manifest
+        .stale_patterns(cutoff)
+        .into_iter()
+        .take(max_refresh)
+        .collect();
+
+    let learn_opts = LearnOptions {
+        max_cost: opts.max_cost,
+        max_time: opts.max_time,
+        quiet: opts.json,
+        max_commits: Some(max_commits),
+        refresh_patterns: report.refreshed_patterns.clone(),
+        refresh_files,
+        dry_run: opts.dry_run,
+        ..Default::default()
+    };
+    report.learn = run_learn(repo_path, &learn_opts).await?;
+
+    Ok(report)
+}
+
+/// Remove leftover `*.tmp` files from interrupted atomic writes; with
+/// `dry_run`, only count them
+fn remove_temp_files(noggin_path: &Path, dry_run: bool) -> Result<usize> {
+    let mut removed = 0;
+    for entry in fs::read_dir(noggin_path)
+        .with_context(|| format!("Failed to read {}", noggin_path.display()))?
+    {
+        let path = entry?.path();
+        if path.is_file() && path.extension().is_some_and(|ext| ext == "tmp") {
+            if !dry_run {
+                fs::remove_file(&path)
+                    .with_context(|| format!("Failed to remove {}", path.display()))?;
+            }
+            removed += 1;
+        }
+    }
+    Ok(removed)
+}
+
+/// Drop commit entries that no longer resolve (e.g. after a history rewrite)
+fn prune_missing_commits(repo_path: &Path, manifest: &mut Manifest) -> Result<usize> {
+    let repo = Repository::open(repo_path)
+        .with_context(|| format!("Failed to open git repository at {}", repo_path.display()))?;
+
+    let resolves = |sha: &str| {
+        Oid::from_str(sha)
+            .map(|oid| repo.find_commit(oid).is_ok())
+            .unwrap_or(false)
+    };
+    let before = manifest.processed_commits();
+    manifest.commits.retain(|sha, _| resolves(sha));
+    manifest.compacted_commits.retain(|sha| resolves(sha));
+    Ok(before - manifest.processed_commits())
+}
+
+/// Drop file and commit records of local branches that no longer exist
+fn prune_deleted_branches(repo_path: &Path, manifest: &mut Manifest) -> Result<usize> {
+    let repo = Repository::open(repo_path)
+        .with_context(|| format!("Failed to open git repository at {}", repo_path.display()))?;
+    let gone =
+        manifest.prune_branches(|branch| repo.find_branch(branch, BranchType::Local).is_ok());
+    Ok(gone.len())
+}
+
+/// Drop patterns whose contributing files have all left the manifest
+pub(crate) fn prune_orphaned_patterns(manifest: &mut Manifest) -> usize {
+    let files = &manifest.files;
+    let case = manifest.path_case;
+    let before = manifest.patterns.len();
+    manifest.patterns.retain(|_, pattern| {
+        pattern.contributing_files.is_empty()
+            || pattern
+                .contributing_files
+                .iter()
+                .any(|f| files.contains_key(&path_key(f, case)))
+    });
+    before - manifest.patterns.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::manifest::CommitCategory;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_remove_temp_files() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(temp_dir.path().join("manifest.toml.tmp"), "").unwrap();
+        fs::write(temp_dir.path().join("manifest.toml"), "").unwrap();
+
+        assert_eq!(remove_temp_files(temp_dir.path(), true).unwrap(), 1);
+        assert!(temp_dir.path().join("manifest.toml.tmp").exists());
+
+        assert_eq!(remove_temp_files(temp_dir.path(), false).unwrap(), 1);
+        assert!(!temp_dir.path().join("manifest.toml.tmp").exists());
+        assert!(temp_dir.path().join("manifest.toml").exists());
+    }
+
+    #[test]
+    fn test_prune_missing_commits() {
+        let temp_dir = TempDir::new().unwrap();
+        let repo = Repository::init(temp_dir.path()).unwrap();
+        let sig = git2::Signature::now("Test", "test@example.com").unwrap();
+        let tree_id = repo.index().unwrap().write_tree().unwrap();
+        let tree = repo.find_tree(tree_id).unwrap();
+        let oid = repo.commit(Some("HEAD"), &sig, &sig, "init", &tree, &[]).unwrap();
+
+        let mut manifest = Manifest::default();
+        manifest.add_commit(oid.to_string(), CommitCategory::Decision, String::new());
+        manifest.add_commit(
+            "0123456789abcdef0123456789abcdef01234567".to_string(),
+            CommitCategory::Bug,
+            String::new(),
+        );
+        manifest
+            .compacted_commits
+            .insert("89abcdef0123456789abcdef0123456789abcdef".to_string());
+
+        assert_eq!(prune_missing_commits(temp_dir.path(), &mut manifest).unwrap(), 2);
+        assert!(manifest.is_commit_processed(&oid.to_string()));
+        assert!(manifest.compacted_commits.is_empty());
+    }
+
+    #[test]
+    fn test_prune_deleted_branches() {
+        let temp_dir = TempDir::new().unwrap();
+        let repo = Repository::init(temp_dir.path()).unwrap();
+        let head = crate::git::fixture::commit(&repo, "init", &[("a.rs", "fn a() {}")]);
+        repo.branch("feature", &repo.find_commit(head).unwrap(), false).unwrap();
+
+        let mut manifest = Manifest::default();
+        manifest.add_or_update_file("a.rs".to_string(), "h".to_string(), vec![]);
+        manifest.mark_file_on_branch("a.rs", "feature");
+        manifest.mark_file_on_branch("a.rs", "gone");
+        manifest.add_commit(head.to_string(), CommitCategory::Decision, String::new());
+        manifest.mark_commit_on_branch(&head.to_string(), "feature");
+        manifest.mark_commit_on_branch(&head.to_string(), "gone");
+        manifest.mark_commit_on_branch(&head.to_string(), "old");
+
+        assert_eq!(prune_deleted_branches(temp_dir.path(), &mut manifest).unwrap(), 2);
+        assert_eq!(manifest.files["a.rs"].branches.keys().collect::<Vec<_>>(), vec!["feature"]);
+        assert_eq!(manifest.commits[&head.to_string()].branches, vec!["feature"]);
+    }
+
+    #[test]
+    fn test_prune_orphaned_patterns() {
+        let mut manifest = Manifest::default();
+        manifest.add_or_update_file("src/a.rs".to_string(), "h".to_string(), vec![]);
+        manifest.add_or_update_pattern(
+            "kept".to_string(),
+            "Kept".to_string(),
+            vec!["src/a.rs".to_string(), "src/gone.rs".to_string()],
+        );
+        manifest.add_or_update_pattern(
+            "orphan".to_string(),
+            "Orphan".to_string(),
+            vec!["src/gone.rs".to_string()],
+        );
+
+        assert_eq!(prune_orphaned_patterns(&mut manifest), 1);
+        assert!(manifest.patterns.contains_key("kept"));
+    }
+}
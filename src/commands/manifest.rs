@@ -0,0 +1,205 @@
+//! `noggin manifest export|import`: move tracking state in and out of
+//! `.noggin/manifest.toml`
+//!
+//! An export holds the complete manifest (repository binding, files,
+//! commits, patterns, last synthesis), so importing it elsewhere restores
+//! exactly what learn and status see. JSON is meant for external tools and
+//! for migrating to another storage backend; TOML matches the file on disk.
+
+use crate::commands::learn::print_planned;
+use crate::config::Config;
+use crate::lock::KnowledgeLock;
+use crate::manifest::Manifest;
+use crate::storage::dry_run::DryRunBackend;
+use crate::storage::{self, KnowledgeBackend};
+use anyhow::{Context, Result};
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ManifestFormat {
+    Json,
+    Toml,
+}
+
+impl ManifestFormat {
+    /// Format implied by a file's extension, if any
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()? {
+            "json" => Some(ManifestFormat::Json),
+            "toml" => Some(ManifestFormat::Toml),
+            _ => None,
+        }
+    }
+}
+
+/// Options for `noggin manifest export`
+#[derive(Debug, Clone)]
+pub struct ManifestExportOptions {
+    pub format: ManifestFormat,
+    /// File to write; stdout when unset
+    pub output: Option<PathBuf>,
+}
+
+/// Options for `noggin manifest import`
+#[derive(Debug, Clone)]
+pub struct ManifestImportOptions {
+    pub input: PathBuf,
+    /// Format of `input`; inferred from its extension when unset
+    pub format: Option<ManifestFormat>,
+    /// Replace a manifest that already tracks files, commits, or patterns
+    pub force: bool,
+    pub dry_run: bool,
+}
+
+pub fn manifest_export_command(opts: ManifestExportOptions) -> Result<()> {
+    let noggin_path = noggin_dir()?;
+    let config = Config::load(&noggin_path).context("Failed to load config")?;
+    let backend = storage::open(&noggin_path, &config.storage);
+    let contents = export_manifest(backend.as_ref(), opts.format)?;
+
+    match opts.output {
+        Some(path) => {
+            fs::write(&path, contents)
+                .with_context(|| format!("Failed to write {}", path.display()))?;
+            eprintln!("Manifest exported to {}", path.display());
+        }
+        None => print!("{}", contents),
+    }
+    Ok(())
+}
+
+pub fn manifest_import_command(opts: ManifestImportOptions) -> Result<()> {
+    let noggin_path = noggin_dir()?;
+    let format = match opts
+        .format
+        .or_else(|| ManifestFormat::from_path(&opts.input))
+    {
+        Some(format) => format,
+        None => anyhow::bail!(
+            "Can't tell the format of {}; pass --format json or --format toml",
+            opts.input.display()
+        ),
+    };
+    let contents = fs::read_to_string(&opts.input)
+        .with_context(|| format!("Failed to read {}", opts.input.display()))?;
+
+    let config = Config::load(&noggin_path).context("Failed to load config")?;
+    let backend = storage::open(&noggin_path, &config.storage);
+    if opts.dry_run {
+        let dry_run = DryRunBackend::new(backend.as_ref());
+        import_manifest(&dry_run, &contents, format, opts.force)?;
+        print_planned(&dry_run.planned()?);
+        return Ok(());
+    }
+
+    let _lock = KnowledgeLock::acquire(&noggin_path, "manifest import")?;
+    let manifest = import_manifest(backend.as_ref(), &contents, format, opts.force)?;
+    let stats = manifest.stats();
+    println!(
+        "Imported manifest: {} files, {} commits, {} patterns",
+        stats.files_scanned, stats.commits_processed, stats.patterns_extracted
+    );
+    Ok(())
+}
+
+fn noggin_dir() -> Result<PathBuf> {
+    let noggin_path = env::current_dir()?.join(".noggin");
+    if !noggin_path.exists() {
+        anyhow::bail!(".noggin/ directory not found. Run 'noggin init' first.");
+    }
+    Ok(noggin_path)
+}
+
+/// `backend`'s manifest, serialized as `format`
+pub fn export_manifest(backend: &dyn KnowledgeBackend, format: ManifestFormat) -> Result<String> {
+    let manifest = backend.load_manifest()?;
+    Ok(match format {
+        ManifestFormat::Json => serde_json::to_string_pretty(&manifest)? + "\n",
+        ManifestFormat::Toml => {
+            toml::to_string_pretty(&manifest).context("Failed to serialize manifest to TOML")?
+        }
+    })
+}
+
+/// Parse `contents` as `format` and save it as `backend`'s manifest. A
+/// manifest that already tracks anything is only replaced with `force`.
+pub fn import_manifest(
+    backend: &dyn KnowledgeBackend,
+    contents: &str,
+    format: ManifestFormat,
+    force: bool,
+) -> Result<Manifest> {
+    let manifest: Manifest = match format {
+        ManifestFormat::Json => {
+            serde_json::from_str(contents).context("Failed to parse JSON manifest")?
+        }
+        ManifestFormat::Toml => {
+            toml::from_str(contents).context("Failed to parse TOML manifest")?
+        }
+    };
+
+    let existing = backend.load_manifest()?;
+    let tracked =
+        !existing.files.is_empty() || !existing.commits.is_empty() || !existing.patterns.is_empty();
+    if tracked && !force {
+        anyhow::bail!(
+            "The existing manifest already tracks {} files and {} commits. \
+             Pass --force to replace it.",
+            existing.files.len(),
+            existing.commits.len()
+        );
+    }
+
+    backend.save_manifest(&manifest)?;
+    Ok(manifest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::MemoryBackend;
+
+    fn sample(backend: &MemoryBackend) {
+        let mut manifest = Manifest::default();
+        manifest.add_or_update_file(
+            "src/main.rs".to_string(),
+            "abc123".to_string(),
+            vec!["p1".to_string()],
+        );
+        backend.save_manifest(&manifest).unwrap();
+    }
+
+    #[test]
+    fn test_json_round_trip() {
+        let source = MemoryBackend::default();
+        sample(&source);
+        let exported = export_manifest(&source, ManifestFormat::Json).unwrap();
+
+        let target = MemoryBackend::default();
+        let imported = import_manifest(&target, &exported, ManifestFormat::Json, false).unwrap();
+        assert_eq!(imported.get_file_hash("src/main.rs"), Some("abc123"));
+        assert_eq!(export_manifest(&target, ManifestFormat::Json).unwrap(), exported);
+    }
+
+    #[test]
+    fn test_import_refuses_to_replace_without_force() {
+        let backend = MemoryBackend::default();
+        sample(&backend);
+        let exported = export_manifest(&backend, ManifestFormat::Toml).unwrap();
+
+        let err = import_manifest(&backend, &exported, ManifestFormat::Toml, false).unwrap_err();
+        assert!(err.to_string().contains("--force"));
+        assert!(import_manifest(&backend, &exported, ManifestFormat::Toml, true).is_ok());
+    }
+
+    #[test]
+    fn test_format_from_path() {
+        assert_eq!(
+            ManifestFormat::from_path(Path::new("state.json")),
+            Some(ManifestFormat::Json)
+        );
+        assert_eq!(ManifestFormat::from_path(Path::new("state")), None);
+    }
+}
@@ -1,4 +1,34 @@
+pub mod approve;
+pub mod ask;
+pub mod bundle;
+pub mod check;
+pub mod conflicts;
+pub mod consolidate;
+pub mod coverage;
+pub mod ci;
+pub mod describe;
+pub mod diff;
+pub mod doctor;
+pub mod examples;
+pub mod experiment;
+pub mod export;
+pub mod graph;
+pub mod grep;
+pub mod hook;
 pub mod init;
 pub mod learn;
+pub mod log;
+pub mod maintain;
+pub mod manifest;
+pub mod prune;
+pub mod purge;
+pub mod resolve;
+pub mod review;
+pub mod schema;
 pub mod serve;
-pub mod status;
\ No newline at end of file
+pub mod snapshot;
+pub mod stats;
+pub mod status;
+pub mod synthesize;
+pub mod translate;
+pub mod verify_knowledge;
@@ -0,0 +1,334 @@
+//! `noggin prune`: drop knowledge that no longer refers to anything
+//!
+//! Removes, in order:
+//!
+//! 1. branch records for local branches that no longer exist, then
+//!    manifest entries for files gone from the worktree and from every
+//!    other branch they were recorded on
+//! 2. patterns none of whose contributing files are still tracked
+//! 3. ARFs whose cited files and commits have all disappeared. Approved
+//!    ARFs, ARFs that cite nothing, and ARFs a tracked commit points at
+//!    are kept.
+//!
+//! With `--compact-commits <DAYS>`, commit entries processed more than
+//! DAYS ago are then reduced to bare SHAs, which is enough for learn to
+//! keep skipping them.
+
+use crate::commands::maintain::prune_orphaned_patterns;
+use crate::config::Config;
+use crate::index::embed::HashingEmbedder;
+use crate::index::SemanticIndex;
+use crate::lock::KnowledgeLock;
+use crate::manifest::Manifest;
+use crate::learn::writer::PlannedWrite;
+use crate::storage::dry_run::DryRunBackend;
+use crate::storage::{self, KnowledgeBackend};
+use anyhow::{Context, Result};
+use chrono::{Duration, Utc};
+use colored::Colorize;
+use git2::{BranchType, Repository};
+use serde::Serialize;
+use std::collections::HashSet;
+use std::env;
+use std::path::Path;
+
+/// Options for `noggin prune`
+#[derive(Debug, Clone, Default)]
+pub struct PruneOptions {
+    /// Compact commit entries processed more than this many days ago
+    pub compact_days: Option<u32>,
+    pub dry_run: bool,
+    pub json: bool,
+}
+
+/// What a prune removed, or would remove with `dry_run`
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PruneReport {
+    /// Deleted branches whose records were dropped from the manifest
+    pub branches: Vec<String>,
+    /// Manifest file entries removed
+    pub files: Vec<String>,
+    /// Pattern ids removed
+    pub patterns: Vec<String>,
+    /// ARF paths removed, relative to `.noggin/`
+    pub arfs: Vec<String>,
+    /// Commit entries reduced to bare SHAs
+    pub commits_compacted: usize,
+    /// The writes a dry run skipped, with their diffs
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub planned: Vec<PlannedWrite>,
+}
+
+impl PruneReport {
+    pub fn is_empty(&self) -> bool {
+        self.branches.is_empty()
+            && self.files.is_empty()
+            && self.patterns.is_empty()
+            && self.arfs.is_empty()
+            && self.commits_compacted == 0
+    }
+}
+
+pub fn prune_command(opts: PruneOptions) -> Result<()> {
+    let repo_path = env::current_dir()?;
+    let report = run_prune(&repo_path, &opts)?;
+
+    if opts.json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+        return Ok(());
+    }
+
+    if report.is_empty() {
+        println!("Nothing to prune.");
+        return Ok(());
+    }
+
+    let verb = if opts.dry_run {
+        "Would remove"
+    } else {
+        "Removed"
+    };
+    for branch in &report.branches {
+        println!("  {} records of deleted branch {}", verb.red(), branch);
+    }
+    for path in &report.files {
+        println!("  {} file entry {}", verb.red(), path);
+    }
+    for id in &report.patterns {
+        println!("  {} pattern {}", verb.red(), id);
+    }
+    for path in &report.arfs {
+        println!("  {} {}", verb.red(), path);
+    }
+    println!(
+        "{} {} file entries, {} patterns, {} ARFs",
+        verb,
+        report.files.len(),
+        report.patterns.len(),
+        report.arfs.len()
+    );
+    if report.commits_compacted > 0 {
+        let verb = if opts.dry_run {
+            "Would compact"
+        } else {
+            "Compacted"
+        };
+        println!("{} {} commit entries", verb, report.commits_compacted);
+    }
+    if opts.dry_run {
+        crate::commands::learn::print_planned(&report.planned);
+    }
+
+    Ok(())
+}
+
+/// Prune the knowledge base of the repository at `repo_path`
+pub fn run_prune(repo_path: &Path, opts: &PruneOptions) -> Result<PruneReport> {
+    let noggin_path = repo_path.join(".noggin");
+    if !noggin_path.exists() {
+        anyhow::bail!("Not initialized. Run 'noggin init' first.");
+    }
+    let _lock = if opts.dry_run {
+        None
+    } else {
+        Some(KnowledgeLock::acquire(&noggin_path, "prune")?)
+    };
+
+    let config = Config::load(&noggin_path).context("Failed to load config")?;
+    let real = storage::open(&noggin_path, &config.storage);
+    let dry_run = opts.dry_run.then(|| DryRunBackend::new(real.as_ref()));
+    let backend: &dyn KnowledgeBackend = match &dry_run {
+        Some(dry_run) => dry_run,
+        None => real.as_ref(),
+    };
+    let mut manifest = backend.load_manifest().context("Failed to load manifest")?;
+    let repo = Repository::open(repo_path)
+        .with_context(|| format!("Failed to open git repository at {}", repo_path.display()))?;
+
+    let mut report = PruneReport {
+        branches: manifest
+            .prune_branches(|branch| repo.find_branch(branch, BranchType::Local).is_ok()),
+        ..Default::default()
+    };
+    report.files = prune_deleted_files(&repo, repo_path, &mut manifest);
+
+    let patterns_before: Vec<String> = manifest.patterns.keys().cloned().collect();
+    prune_orphaned_patterns(&mut manifest);
+    report.patterns = patterns_before
+        .into_iter()
+        .filter(|id| !manifest.patterns.contains_key(id))
+        .collect();
+
+    report.arfs = orphaned_arfs(backend, &repo, repo_path, &manifest)?;
+
+    if let Some(days) = opts.compact_days {
+        report.commits_compacted =
+            manifest.compact_commits(Utc::now() - Duration::days(i64::from(days)));
+    }
+
+    for path in &report.arfs {
+        backend.remove(path)?;
+    }
+    backend
+        .save_manifest(&manifest)
+        .context("Failed to save manifest")?;
+    if let Some(dry_run) = &dry_run {
+        report.planned = dry_run.planned()?;
+        return Ok(report);
+    }
+    if !report.arfs.is_empty() {
+        SemanticIndex::refresh(&noggin_path, backend, &HashingEmbedder::new())
+            .context("Failed to update search index")?;
+    }
+
+    Ok(report)
+}
+
+/// Drop file entries missing from the worktree, unless a branch other than
+/// the current one still records them
+fn prune_deleted_files(repo: &Repository, repo_path: &Path, manifest: &mut Manifest) -> Vec<String> {
+    let current = repo
+        .head()
+        .ok()
+        .filter(|head| head.is_branch())
+        .and_then(|head| head.shorthand().map(str::to_string));
+    let live_elsewhere = |branch: &str| {
+        Some(branch) != current.as_deref() && repo.find_branch(branch, BranchType::Local).is_ok()
+    };
+
+    let deleted: Vec<String> = manifest
+        .files
+        .values()
+        .filter(|entry| !repo_path.join(&entry.path).exists())
+        .filter(|entry| !entry.branches.keys().any(|b| live_elsewhere(b)))
+        .map(|entry| entry.path.clone())
+        .collect();
+    for path in &deleted {
+        manifest.remove_file(path);
+    }
+    deleted
+}
+
+/// ARFs whose cited files and commits are all gone
+fn orphaned_arfs(
+    backend: &dyn KnowledgeBackend,
+    repo: &Repository,
+    repo_path: &Path,
+    manifest: &Manifest,
+) -> Result<Vec<String>> {
+    let referenced: HashSet<&str> = manifest
+        .commits
+        .values()
+        .map(|entry| entry.arf_path.as_str())
+        .collect();
+    let file_exists = |file: &String| manifest.contains_file(file) || repo_path.join(file).exists();
+    let commit_exists = |sha: &String| {
+        repo.revparse_single(sha)
+            .and_then(|object| object.peel_to_commit())
+            .is_ok()
+    };
+
+    let mut orphaned = Vec::new();
+    for path in backend.list_arfs()? {
+        if referenced.contains(path.as_str()) {
+            continue;
+        }
+        // Unparseable ARFs are left for `noggin check` to report
+        let Ok(Some(arf)) = backend.read_arf(&path) else {
+            continue;
+        };
+        let context = &arf.context;
+        if arf.approved || (context.files.is_empty() && context.commits.is_empty()) {
+            continue;
+        }
+        if !context.files.iter().any(file_exists) && !context.commits.iter().any(commit_exists) {
+            orphaned.push(path);
+        }
+    }
+    Ok(orphaned)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::arf::ArfFile;
+    use crate::learn::writer::WriteAction;
+    use crate::manifest::CommitCategory;
+    use crate::storage::FilesystemBackend;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_prune_removes_stale_entries() {
+        let temp_dir = TempDir::new().unwrap();
+        let repo_path = temp_dir.path();
+        Repository::init(repo_path).unwrap();
+        let noggin_path = repo_path.join(".noggin");
+        fs::create_dir(&noggin_path).unwrap();
+        fs::create_dir(repo_path.join("src")).unwrap();
+        fs::write(repo_path.join("src/kept.rs"), "fn main() {}").unwrap();
+
+        let backend = FilesystemBackend::new(&noggin_path);
+        let mut manifest = Manifest::default();
+        manifest.add_or_update_file("src/kept.rs".to_string(), "a".to_string(), vec![]);
+        manifest.add_or_update_file("src/gone.rs".to_string(), "b".to_string(), vec![]);
+        manifest.add_or_update_pattern(
+            "orphan".to_string(),
+            "Orphan".to_string(),
+            vec!["src/gone.rs".to_string()],
+        );
+        manifest.add_commit("old".to_string(), CommitCategory::Bug, String::new());
+        manifest.commits.get_mut("old").unwrap().processed_at = Utc::now() - Duration::days(90);
+        backend.save_manifest(&manifest).unwrap();
+
+        let arf_citing = |file: &str| {
+            let mut arf = ArfFile::new("What", "Why", "How");
+            arf.context.files.push(file.to_string());
+            arf
+        };
+        backend.write_arf("patterns/gone.arf", &arf_citing("src/gone.rs")).unwrap();
+        backend.write_arf("patterns/kept.arf", &arf_citing("src/kept.rs")).unwrap();
+        let mut approved = arf_citing("src/gone.rs");
+        approved.approved = true;
+        backend.write_arf("patterns/approved.arf", &approved).unwrap();
+
+        let opts = PruneOptions {
+            compact_days: Some(30),
+            dry_run: true,
+            ..Default::default()
+        };
+        let report = run_prune(repo_path, &opts).unwrap();
+        assert_eq!(report.files, vec!["src/gone.rs"]);
+        assert_eq!(report.patterns, vec!["orphan"]);
+        assert_eq!(report.arfs, vec!["patterns/gone.arf"]);
+        assert_eq!(report.commits_compacted, 1);
+        assert!(backend.exists("patterns/gone.arf"));
+        let planned: Vec<(&str, WriteAction)> = report
+            .planned
+            .iter()
+            .map(|write| (write.path.as_str(), write.action))
+            .collect();
+        assert_eq!(
+            planned,
+            vec![
+                ("patterns/gone.arf", WriteAction::Remove),
+                ("manifest.toml", WriteAction::Update),
+            ]
+        );
+        assert!(backend.load_manifest().unwrap().contains_file("src/gone.rs"));
+
+        let opts = PruneOptions {
+            dry_run: false,
+            ..opts
+        };
+        run_prune(repo_path, &opts).unwrap();
+        let manifest = backend.load_manifest().unwrap();
+        assert!(!manifest.contains_file("src/gone.rs"));
+        assert!(manifest.patterns.is_empty());
+        assert!(manifest.is_commit_processed("old"));
+        assert!(!backend.exists("patterns/gone.arf"));
+        assert!(backend.exists("patterns/approved.arf"));
+
+        assert!(run_prune(repo_path, &opts).unwrap().is_empty());
+    }
+}
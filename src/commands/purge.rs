@@ -0,0 +1,70 @@
+//! `noggin purge`: remove raw responses, caches, logs, and checkpoints
+//!
+//! With no target flags every target is purged. `--expired` applies only
+//! the `[retention]` age and size limits, which learn also does after
+//! each run. ARF files and the manifest are never touched.
+
+use crate::config::Config;
+use crate::lock::KnowledgeLock;
+use crate::retention::{self, PurgeTarget};
+use anyhow::Result;
+use colored::Colorize;
+use std::env;
+
+/// Options for `noggin purge`
+#[derive(Debug, Clone, Default)]
+pub struct PurgeOptions {
+    /// Targets to remove; empty means all of them
+    pub targets: Vec<PurgeTarget>,
+    /// Only remove files past the retention limits
+    pub expired: bool,
+    pub dry_run: bool,
+    pub json: bool,
+}
+
+pub fn purge_command(opts: PurgeOptions) -> Result<()> {
+    let noggin_path = env::current_dir()?.join(".noggin");
+    if !noggin_path.exists() {
+        anyhow::bail!("Not initialized. Run 'noggin init' first.");
+    }
+    let _lock = if opts.dry_run {
+        None
+    } else {
+        Some(KnowledgeLock::acquire(&noggin_path, "purge")?)
+    };
+
+    let report = if opts.expired {
+        let config = Config::load(&noggin_path)?;
+        retention::expire(&noggin_path, &config.retention, opts.dry_run)?
+    } else if opts.targets.is_empty() {
+        retention::purge(&noggin_path, PurgeTarget::ALL, opts.dry_run)?
+    } else {
+        retention::purge(&noggin_path, &opts.targets, opts.dry_run)?
+    };
+
+    if opts.json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+        return Ok(());
+    }
+
+    let verb = if opts.dry_run {
+        "Would remove"
+    } else {
+        "Removed"
+    };
+    for path in &report.removed {
+        println!("  {} {}", verb.red(), path);
+    }
+    if report.removed.is_empty() {
+        println!("Nothing to purge.");
+    } else {
+        println!(
+            "{} {} files ({} KB)",
+            verb,
+            report.removed.len(),
+            report.bytes.div_ceil(1024)
+        );
+    }
+
+    Ok(())
+}
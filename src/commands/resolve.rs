@@ -0,0 +1,298 @@
+//! `noggin resolve`: decide the conflicts learn left for a person
+//!
+//! Learn parks two kinds of record in `.noggin/conflicts/`: proposals that
+//! contradict an approved ARF, and fields the models disagreed on that
+//! voting could not settle. This command walks through them, asks which
+//! candidate value to keep for each field, writes the choice into the ARF
+//! and removes the record. A conflict with a skipped field stays open.
+
+use crate::config::Config;
+use crate::conflicts::{list_conflicts, Candidate, Conflict};
+use crate::lock::KnowledgeLock;
+use crate::storage::{self, KnowledgeBackend};
+use anyhow::{Context, Result};
+use colored::Colorize;
+use std::env;
+use std::io::{self, BufRead, Write};
+use std::path::Path;
+
+/// Options for `noggin resolve`
+#[derive(Debug, Clone, Default)]
+pub struct ResolveOptions {
+    /// Only this conflict (its ID, or its path under .noggin/)
+    pub id: Option<String>,
+    /// List open conflicts without resolving any
+    pub list: bool,
+    /// Take candidate N (1-based) for every field instead of asking
+    pub pick: Option<usize>,
+    /// Print the list as JSON (with `list`)
+    pub json: bool,
+}
+
+pub fn resolve_command(opts: ResolveOptions) -> Result<()> {
+    let repo_path = env::current_dir()?;
+    let noggin_path = repo_path.join(".noggin");
+    if !noggin_path.exists() {
+        anyhow::bail!("Not initialized. Run 'noggin init' first.");
+    }
+
+    let mut conflicts = list_conflicts(&noggin_path)?;
+    if let Some(id) = &opts.id {
+        conflicts.retain(|c| c.id() == id || c.relative_path() == *id);
+        if conflicts.is_empty() {
+            anyhow::bail!("No open conflict '{}'. Run 'noggin resolve --list'.", id);
+        }
+    }
+
+    if opts.list {
+        if opts.json {
+            println!("{}", serde_json::to_string_pretty(&conflicts)?);
+        } else {
+            print_list(&conflicts);
+        }
+        return Ok(());
+    }
+
+    if conflicts.is_empty() {
+        println!("No open conflicts.");
+        return Ok(());
+    }
+
+    let _lock = KnowledgeLock::acquire(&noggin_path, "resolve")?;
+    let config = Config::load(&noggin_path).context("Failed to load config")?;
+    let backend = storage::open(&noggin_path, &config.storage);
+    let resolved = match opts.pick {
+        Some(pick) => pick_all(&noggin_path, backend.as_ref(), &conflicts, pick)?,
+        None => {
+            let stdin = io::stdin();
+            let mut input = stdin.lock();
+            walk(&noggin_path, backend.as_ref(), &conflicts, &mut input, &mut io::stdout())?
+        }
+    };
+    println!(
+        "\nResolved {} of {} conflicts.",
+        resolved.to_string().green(),
+        conflicts.len()
+    );
+    Ok(())
+}
+
+/// Resolve every conflict with candidate `pick` (1-based) for each field
+fn pick_all(
+    noggin_path: &Path,
+    backend: &dyn KnowledgeBackend,
+    conflicts: &[Conflict],
+    pick: usize,
+) -> Result<usize> {
+    for conflict in conflicts {
+        let picks = conflict
+            .choices()
+            .into_iter()
+            .map(|(field, candidates)| {
+                let candidate = pick
+                    .checked_sub(1)
+                    .and_then(|i| candidates.get(i))
+                    .with_context(|| {
+                        format!(
+                            "{} has {} candidates for {}; --pick {} is out of range",
+                            conflict.id(),
+                            candidates.len(),
+                            field,
+                            pick
+                        )
+                    })?;
+                Ok((field, candidate.value.clone()))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        conflict.resolve(noggin_path, backend, &picks)?;
+        println!("  Resolved {}", conflict.arf_path());
+    }
+    Ok(conflicts.len())
+}
+
+/// Ask on `output` which candidate to keep for each field, reading answers
+/// from `input`. Returns how many conflicts were resolved.
+fn walk(
+    noggin_path: &Path,
+    backend: &dyn KnowledgeBackend,
+    conflicts: &[Conflict],
+    input: &mut impl BufRead,
+    output: &mut impl Write,
+) -> Result<usize> {
+    let mut resolved = 0;
+
+    'conflicts: for conflict in conflicts {
+        writeln!(
+            output,
+            "\n{} {}",
+            conflict.id().bold(),
+            format!("(.noggin/{})", conflict.arf_path()).dimmed()
+        )?;
+
+        let mut picks = Vec::new();
+        for (field, candidates) in conflict.choices() {
+            writeln!(output, "  {}", field.cyan())?;
+            print_candidates(output, &candidates)?;
+            match ask(input, output, candidates.len())? {
+                Answer::Pick(i) => picks.push((field, candidates[i].value.clone())),
+                Answer::Skip => {
+                    writeln!(output, "  Skipped; the conflict stays open.")?;
+                    continue 'conflicts;
+                }
+                Answer::Quit => break 'conflicts,
+            }
+        }
+
+        conflict.resolve(noggin_path, backend, &picks)?;
+        writeln!(output, "  Updated .noggin/{}", conflict.arf_path())?;
+        resolved += 1;
+    }
+
+    Ok(resolved)
+}
+
+enum Answer {
+    /// 0-based candidate index
+    Pick(usize),
+    Skip,
+    Quit,
+}
+
+/// Prompt until a valid answer is given; end of input quits
+fn ask(input: &mut impl BufRead, output: &mut impl Write, count: usize) -> Result<Answer> {
+    loop {
+        write!(output, "  Keep which value? [1-{}, s to skip, q to quit] ", count)?;
+        output.flush()?;
+
+        let mut line = String::new();
+        if input.read_line(&mut line)? == 0 {
+            writeln!(output)?;
+            return Ok(Answer::Quit);
+        }
+        match line.trim() {
+            "s" => return Ok(Answer::Skip),
+            "q" => return Ok(Answer::Quit),
+            answer => {
+                if let Ok(n) = answer.parse::<usize>() {
+                    if (1..=count).contains(&n) {
+                        return Ok(Answer::Pick(n - 1));
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn print_candidates(output: &mut impl Write, candidates: &[Candidate]) -> Result<()> {
+    for (i, candidate) in candidates.iter().enumerate() {
+        writeln!(
+            output,
+            "    {}) {} {}",
+            i + 1,
+            format!("[{}]", candidate.source).dimmed(),
+            candidate.value
+        )?;
+    }
+    Ok(())
+}
+
+fn print_list(conflicts: &[Conflict]) {
+    if conflicts.is_empty() {
+        println!("No open conflicts.");
+        return;
+    }
+    println!("{} open conflicts\n", conflicts.len());
+    for conflict in conflicts {
+        let fields: Vec<String> = conflict.choices().into_iter().map(|(f, _)| f).collect();
+        println!(
+            "  {} {} {}",
+            conflict.id(),
+            format!("[{}]", fields.join(", ")).cyan(),
+            format!(".noggin/{}", conflict.arf_path()).dimmed()
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::FilesystemBackend;
+    use crate::arf::ArfFile;
+    use crate::conflicts::VoteConflict;
+    use std::fs;
+    use std::io::Cursor;
+    use tempfile::TempDir;
+
+    fn setup() -> TempDir {
+        let temp_dir = TempDir::new().unwrap();
+        let patterns = temp_dir.path().join("patterns");
+        fs::create_dir_all(&patterns).unwrap();
+        for (what, how) in [("Use pooling", "PgBouncer"), ("Retry payments", "Backoff")] {
+            let arf = ArfFile::new(what, "Reasons", how);
+            let slug = what.to_lowercase().replace(' ', "-");
+            arf.to_toml(&patterns.join(format!("{}.arf", slug))).unwrap();
+            VoteConflict::new(
+                format!("patterns/{}.arf", slug),
+                "how",
+                vec![
+                    ("claude".to_string(), how.to_string()),
+                    ("gemini".to_string(), format!("{} v2", how)),
+                ],
+            )
+            .save(temp_dir.path())
+            .unwrap();
+        }
+        temp_dir
+    }
+
+    fn how(temp_dir: &TempDir, slug: &str) -> String {
+        ArfFile::from_toml(&temp_dir.path().join(format!("patterns/{}.arf", slug)))
+            .unwrap()
+            .how
+    }
+
+    #[test]
+    fn test_walk_applies_choices_and_skips() {
+        let temp_dir = setup();
+        let conflicts = list_conflicts(temp_dir.path()).unwrap();
+        let backend = FilesystemBackend::new(temp_dir.path());
+
+        // Invalid answer is asked again; first conflict skipped
+        let mut input = Cursor::new("s\n7\n2\n");
+        let mut output = Vec::new();
+        let resolved =
+            walk(temp_dir.path(), &backend, &conflicts, &mut input, &mut output).unwrap();
+
+        assert_eq!(resolved, 1);
+        assert_eq!(how(&temp_dir, "retry-payments"), "Backoff");
+        assert_eq!(how(&temp_dir, "use-pooling"), "PgBouncer v2");
+        let open = list_conflicts(temp_dir.path()).unwrap();
+        assert_eq!(open.len(), 1);
+        assert_eq!(open[0].id(), "retry-payments.how");
+    }
+
+    #[test]
+    fn test_walk_stops_at_end_of_input() {
+        let temp_dir = setup();
+        let conflicts = list_conflicts(temp_dir.path()).unwrap();
+        let backend = FilesystemBackend::new(temp_dir.path());
+
+        let (mut input, mut output) = (Cursor::new(""), Vec::new());
+        let resolved =
+            walk(temp_dir.path(), &backend, &conflicts, &mut input, &mut output).unwrap();
+        assert_eq!(resolved, 0);
+        assert_eq!(list_conflicts(temp_dir.path()).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_pick_all() {
+        let temp_dir = setup();
+        let conflicts = list_conflicts(temp_dir.path()).unwrap();
+        let backend = FilesystemBackend::new(temp_dir.path());
+
+        assert!(pick_all(temp_dir.path(), &backend, &conflicts, 3).is_err());
+        assert_eq!(pick_all(temp_dir.path(), &backend, &conflicts, 1).unwrap(), 2);
+        assert_eq!(how(&temp_dir, "use-pooling"), "PgBouncer");
+        assert!(list_conflicts(temp_dir.path()).unwrap().is_empty());
+    }
+}
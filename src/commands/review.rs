@@ -0,0 +1,323 @@
+use crate::arf::ArfFile;
+use crate::config::Config;
+use crate::git::diff::{range_diff, staged_diff, DiffSummary};
+use crate::learn::ledger::{MachineBudget, MeteredRun};
+use crate::llm::parallel::Scheduler;
+use crate::llm::provider_from_config;
+use crate::llm::stream::ignore_chunks;
+use crate::policy::NeverSend;
+use crate::query::linked_arfs;
+use crate::storage;
+use anyhow::{Context, Result};
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+use std::env;
+
+/// Categories whose entries describe rules a change can violate
+const REVIEWED_CATEGORIES: &[&str] = &["decisions", "patterns"];
+
+/// Options for `noggin review-diff`
+#[derive(Debug, Clone)]
+pub struct ReviewOptions {
+    /// Revision range (`main..HEAD`) or single revision; ignored with `staged`
+    pub range: Option<String>,
+    /// Review staged changes instead of a range
+    pub staged: bool,
+    /// Provider that performs the review
+    pub provider: String,
+    /// Print the report as JSON
+    pub json: bool,
+    /// Exit with an error when any finding has error severity
+    pub strict: bool,
+}
+
+impl Default for ReviewOptions {
+    fn default() -> Self {
+        Self {
+            range: None,
+            staged: false,
+            provider: "claude".to_string(),
+            json: false,
+            strict: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    #[default]
+    Warning,
+    Info,
+}
+
+/// A possible violation of documented knowledge
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Finding {
+    #[serde(default)]
+    pub severity: Severity,
+    pub file: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub line: Option<u32>,
+    /// ARF path (relative to .noggin/) the finding cites
+    pub arf: String,
+    pub message: String,
+}
+
+/// Outcome of a review
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ReviewReport {
+    pub label: String,
+    pub files_reviewed: usize,
+    pub arfs_consulted: Vec<String>,
+    pub findings: Vec<Finding>,
+    /// Findings discarded because they cited no consulted ARF
+    pub uncited: usize,
+}
+
+impl ReviewReport {
+    pub fn errors(&self) -> usize {
+        self.findings
+            .iter()
+            .filter(|f| f.severity == Severity::Error)
+            .count()
+    }
+}
+
+/// Review a diff against the patterns and decisions covering its files
+pub async fn review_command(opts: ReviewOptions) -> Result<()> {
+    let repo_path = env::current_dir()?;
+    let noggin_path = repo_path.join(".noggin");
+
+    if !noggin_path.exists() {
+        anyhow::bail!("Not initialized. Run 'noggin init' first.");
+    }
+
+    let config = Config::load(&noggin_path).context("Failed to load config")?;
+    let never_send = NeverSend::from_config(&config.privacy)?;
+    let diff = match (&opts.range, opts.staged) {
+        (_, true) => staged_diff(&repo_path, &never_send)?,
+        (Some(range), false) => range_diff(&repo_path, range, &never_send)?,
+        (None, false) => anyhow::bail!("Provide a revision range or --staged"),
+    };
+
+    let backend = storage::open(&noggin_path, &config.storage);
+    let arfs: Vec<(String, ArfFile)> = linked_arfs(backend.as_ref(), &diff.paths())?
+        .into_iter()
+        .filter(|(path, _)| {
+            path.split('/')
+                .next()
+                .is_some_and(|dir| REVIEWED_CATEGORIES.contains(&dir))
+        })
+        .collect();
+
+    let mut report = ReviewReport {
+        label: diff.label.clone(),
+        files_reviewed: diff.files.len(),
+        arfs_consulted: arfs.iter().map(|(path, _)| path.clone()).collect(),
+        ..Default::default()
+    };
+
+    if !diff.files.is_empty() && !arfs.is_empty() {
+        let provider = provider_from_config(&opts.provider, &config.llm)?.with_context(|| {
+            format!(
+                "Unknown provider '{}'. Available: {}",
+                opts.provider,
+                config.llm.provider_names().join(", ")
+            )
+        })?;
+        let prompt = build_review_prompt(&diff, &arfs);
+        let mut metered = MeteredRun::new(MachineBudget::for_user(), config.budget.clone());
+        metered.admit(provider.name(), &prompt)?;
+        let result = Scheduler::new(&config.llm)
+            .query(provider.as_ref(), &prompt, &ignore_chunks)
+            .await;
+        if let Ok(response) = &result {
+            metered.answered(provider.name(), response);
+        }
+        metered.finish(&repo_path);
+        let response =
+            result.with_context(|| format!("{} failed to review the diff", provider.name()))?;
+
+        let findings = parse_findings(&response)?;
+        let total = findings.len();
+        report.findings = findings
+            .into_iter()
+            .filter(|f| report.arfs_consulted.contains(&f.arf))
+            .collect();
+        report.uncited = total - report.findings.len();
+    }
+
+    if opts.json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        print_report(&report);
+    }
+
+    if opts.strict && report.errors() > 0 {
+        anyhow::bail!("{} review finding(s) with error severity", report.errors());
+    }
+
+    Ok(())
+}
+
+fn build_review_prompt(diff: &DiffSummary, arfs: &[(String, ArfFile)]) -> String {
+    let mut prompt = String::from(
+        "Review the change below against the team's documented patterns and \
+         decisions. Flag only places where the change violates or works \
+         against a listed entry; do not report general style issues.\n\n\
+         Output each finding as TOML using this exact format:\n\n\
+         ```\n\
+         [[finding]]\n\
+         severity = \"error\"  # error, warning, or info\n\
+         file = \"path/to/file.rs\"\n\
+         line = 42  # optional, line in the new version\n\
+         arf = \"patterns/example.arf\"  # the entry violated, exactly as listed\n\
+         message = \"what is wrong and how to fix it\"\n\
+         ```\n\n\
+         If the change is consistent with every entry, output nothing.\n\n",
+    );
+
+    prompt.push_str("--- DOCUMENTED KNOWLEDGE ---\n\n");
+    for (path, arf) in arfs {
+        prompt.push_str(&format!(
+            "[{}]\nWhat: {}\nWhy: {}\nHow: {}\n\n",
+            path, arf.what, arf.why, arf.how
+        ));
+    }
+
+    prompt.push_str("--- CHANGED FILES ---\n\n");
+    for file in &diff.files {
+        prompt.push_str(&format!("{} [{}]\n", file.path, file.status));
+    }
+
+    prompt.push_str("\n--- DIFF ---\n\n");
+    prompt.push_str(&diff.patch);
+    if diff.patch_truncated {
+        prompt.push_str("\n... (diff truncated)\n");
+    }
+
+    prompt
+}
+
+/// Parse `[[finding]]` tables from a provider response, tolerating a
+/// surrounding code fence. An empty response means no findings.
+fn parse_findings(raw: &str) -> Result<Vec<Finding>> {
+    #[derive(Deserialize)]
+    struct Wrapper {
+        #[serde(default)]
+        finding: Vec<Finding>,
+    }
+
+    let body: String = raw
+        .trim()
+        .lines()
+        .filter(|line| !line.trim_start().starts_with("```"))
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    if body.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let wrapper: Wrapper = toml::from_str(&body).context("Failed to parse review findings")?;
+    Ok(wrapper.finding)
+}
+
+fn print_report(report: &ReviewReport) {
+    println!(
+        "Reviewed {} files in {} against {} knowledge entries\n",
+        report.files_reviewed,
+        report.label,
+        report.arfs_consulted.len()
+    );
+
+    if report.arfs_consulted.is_empty() {
+        println!("No documented patterns or decisions cover these files.");
+        return;
+    }
+
+    if report.findings.is_empty() {
+        println!("{}", "No findings.".green());
+    }
+
+    for finding in &report.findings {
+        let severity = match finding.severity {
+            Severity::Error => "error".red().bold(),
+            Severity::Warning => "warning".yellow().bold(),
+            Severity::Info => "info".cyan(),
+        };
+        let location = match finding.line {
+            Some(line) => format!("{}:{}", finding.file, line),
+            None => finding.file.clone(),
+        };
+        println!("{} {}", severity, location);
+        println!("  {}", finding.message);
+        println!("  {}", format!("see .noggin/{}", finding.arf).dimmed());
+        println!();
+    }
+
+    if report.uncited > 0 {
+        println!(
+            "{}",
+            format!("{} finding(s) without a valid citation were dropped", report.uncited).dimmed()
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_findings() {
+        let raw = r#"```toml
+[[finding]]
+severity = "error"
+file = "src/db.rs"
+line = 12
+arf = "patterns/use-pool.arf"
+message = "Opens a raw connection instead of using the pool"
+
+[[finding]]
+file = "src/api.rs"
+arf = "decisions/rest.arf"
+message = "Adds a GraphQL endpoint"
+```"#;
+
+        let findings = parse_findings(raw).unwrap();
+        assert_eq!(findings.len(), 2);
+        assert_eq!(findings[0].severity, Severity::Error);
+        assert_eq!(findings[0].line, Some(12));
+        assert_eq!(findings[1].severity, Severity::Warning);
+        assert_eq!(findings[1].line, None);
+    }
+
+    #[test]
+    fn test_parse_empty_response() {
+        assert!(parse_findings("  \n").unwrap().is_empty());
+        assert!(parse_findings("not toml [[").is_err());
+    }
+
+    #[test]
+    fn test_prompt_lists_knowledge_with_paths() {
+        let diff = DiffSummary {
+            label: "staged changes".to_string(),
+            files: vec![],
+            commits: vec![],
+            patch: "+conn = connect()\n".to_string(),
+            patch_truncated: true,
+        };
+        let arfs = vec![(
+            "patterns/use-pool.arf".to_string(),
+            ArfFile::new("Use the pool", "Limits connections", "db::pool()"),
+        )];
+
+        let prompt = build_review_prompt(&diff, &arfs);
+        assert!(prompt.contains("[patterns/use-pool.arf]"));
+        assert!(prompt.contains("[[finding]]"));
+        assert!(prompt.contains("+conn = connect()"));
+        assert!(prompt.contains("(diff truncated)"));
+    }
+}
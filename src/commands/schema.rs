@@ -0,0 +1,20 @@
+use crate::schema::arf_schema;
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::Path;
+
+/// Print the ARF JSON Schema, or write it to `output`
+pub fn schema_command(output: Option<&Path>) -> Result<()> {
+    let schema = serde_json::to_string_pretty(&arf_schema())? + "\n";
+
+    match output {
+        Some(path) => {
+            fs::write(path, schema)
+                .with_context(|| format!("Failed to write {}", path.display()))?;
+            println!("Wrote ARF schema to {}", path.display());
+        }
+        None => print!("{}", schema),
+    }
+
+    Ok(())
+}
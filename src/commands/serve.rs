@@ -1,5 +1,6 @@
+use crate::config::Config;
 use crate::mcp::NogginServer;
-use anyhow::{bail, Result};
+use anyhow::{bail, Context, Result};
 use rmcp::ServiceExt;
 use std::env;
 
@@ -11,7 +12,8 @@ pub async fn serve_command() -> Result<()> {
         bail!("Not initialized. Run 'noggin init' first.");
     }
 
-    let server = NogginServer::new(noggin_path);
+    let config = Config::load(&noggin_path).context("Failed to load config")?;
+    let server = NogginServer::new(noggin_path, config.storage);
     let service = server.serve(rmcp::transport::stdio()).await?;
     service.waiting().await?;
 
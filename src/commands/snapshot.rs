@@ -0,0 +1,86 @@
+//! `noggin snapshot <name>`: record the knowledge base as of a release
+//!
+//! See [`crate::snapshot`]. With `--tag`, HEAD is also tagged
+//! `noggin/<name>` so `noggin diff --snapshot` can show how entries
+//! changed, not just that they did.
+
+use crate::config::Config;
+use crate::lock::KnowledgeLock;
+use crate::snapshot::{self, Snapshot};
+use crate::storage;
+use crate::text::short_hash;
+use anyhow::{Context, Result};
+use colored::Colorize;
+use git2::Repository;
+use std::env;
+use std::path::Path;
+
+/// Options for `noggin snapshot`
+#[derive(Debug, Clone, Default)]
+pub struct SnapshotOptions {
+    pub name: String,
+    /// Tag HEAD as `noggin/<name>`; requires `.noggin/` to be committed
+    pub tag: bool,
+    /// Replace an existing snapshot (and tag) of the same name
+    pub force: bool,
+}
+
+pub fn snapshot_command(opts: SnapshotOptions) -> Result<()> {
+    let repo_path = env::current_dir()?;
+    let snapshot = run_snapshot(&repo_path, &opts)?;
+
+    println!(
+        "{} snapshot '{}' of {} entries",
+        "Recorded".green(),
+        snapshot.name,
+        snapshot.entries.len()
+    );
+    if let Some(tag) = &snapshot.tag {
+        println!("  Tagged {} as {}", short(snapshot.commit.as_deref()), tag);
+    }
+    Ok(())
+}
+
+/// Record a snapshot of the knowledge base in `repo_path`
+pub fn run_snapshot(repo_path: &Path, opts: &SnapshotOptions) -> Result<Snapshot> {
+    let noggin_path = repo_path.join(".noggin");
+    if !noggin_path.exists() {
+        anyhow::bail!("Not initialized. Run 'noggin init' first.");
+    }
+    snapshot::validate_name(&opts.name)?;
+    let _lock = KnowledgeLock::acquire(&noggin_path, "snapshot")?;
+
+    if !opts.force && snapshot::snapshot_path(&noggin_path, &opts.name).exists() {
+        anyhow::bail!(
+            "Snapshot '{}' already exists; pass --force to replace it",
+            opts.name
+        );
+    }
+
+    let config = Config::load(&noggin_path).context("Failed to load config")?;
+    let backend = storage::open(&noggin_path, &config.storage);
+    let mut snapshot = Snapshot::capture(&opts.name, backend.as_ref())?;
+
+    let repo = Repository::open(repo_path).context("Failed to open git repository")?;
+    snapshot.commit = repo
+        .head()
+        .ok()
+        .and_then(|head| head.target())
+        .map(|oid| oid.to_string());
+
+    if opts.tag {
+        let tag_name = format!("{}{}", snapshot::TAG_PREFIX, opts.name);
+        if !opts.force && repo.find_reference(&format!("refs/tags/{}", tag_name)).is_ok() {
+            anyhow::bail!("Tag '{}' already exists; pass --force to replace it", tag_name);
+        }
+        // Checks .noggin/ is committed before replacing an existing tag
+        snapshot.tag = Some(snapshot::tag_snapshot(&repo, &snapshot, opts.force)?);
+    }
+
+    snapshot.save(&noggin_path)?;
+    Ok(snapshot)
+}
+
+fn short(sha: Option<&str>) -> &str {
+    sha.map_or("HEAD", short_hash)
+}
@@ -0,0 +1,109 @@
+use crate::learn::ledger::{default_config_dir, Ledger};
+use crate::metrics::{iso_week, Metrics};
+use crate::time::format_datetime;
+use anyhow::Result;
+use chrono::{Duration, Local, Utc};
+use colored::Colorize;
+use std::env;
+
+/// Weeks of ask history shown by `noggin stats`
+const WEEKS_SHOWN: i64 = 8;
+
+/// Show local usage counters from `.noggin/metrics.toml`
+pub fn stats_command(json: bool, utc: bool) -> Result<()> {
+    let noggin_path = env::current_dir()?.join(".noggin");
+    if !noggin_path.exists() {
+        anyhow::bail!("Not initialized. Run 'noggin init' first.");
+    }
+
+    let metrics = Metrics::load(&noggin_path)?;
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&metrics)?);
+        return Ok(());
+    }
+
+    println!("{}", "Noggin Usage".bold());
+    println!("{}", "Recorded locally in .noggin/metrics.toml".dimmed());
+    println!();
+
+    println!("{}", "Asks".bold());
+    println!("  {} total", metrics.asks.total.to_string().cyan());
+    if let Some(latency) = metrics.average_ask_latency() {
+        println!("  {} ms average answer latency", latency.as_millis());
+    }
+    if let Some(last) = &metrics.asks.last_at {
+        println!("  last asked {}", format_datetime(last, utc).dimmed());
+    }
+    for (week, count) in recent_weeks(&metrics) {
+        println!("    {}  {:>4}  {}", week.dimmed(), count, "▇".repeat(count.min(40) as usize));
+    }
+    println!();
+
+    println!("{}", "Learn".bold());
+    println!("  {} runs", metrics.learn.runs.to_string().cyan());
+    if let Some(rate) = metrics.cache_hit_rate() {
+        println!(
+            "  {:.0}% cache hit rate ({} of {} scanned files unchanged)",
+            rate * 100.0,
+            metrics.learn.files_unchanged,
+            metrics.learn.files_scanned
+        );
+    }
+    println!(
+        "  {} provider requests, ~{} tokens",
+        metrics.learn.provider_requests, metrics.learn.provider_tokens
+    );
+    if let Some(last) = &metrics.learn.last_at {
+        println!("  last run {}", format_datetime(last, utc).dimmed());
+    }
+
+    // Shared with every repository on this machine
+    if let Ok(dir) = default_config_dir() {
+        let today = Ledger::load(&dir).unwrap_or_default().day(Local::now().date_naive());
+        if !today.providers.is_empty() {
+            let total = today.total();
+            println!();
+            println!("{}", "This machine today".bold());
+            println!(
+                "  {} provider requests, ~{} tokens, ~${:.2} across {} repositories",
+                total.requests,
+                total.tokens(),
+                total.estimated_cost_usd,
+                today.repos.len()
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Ask counts for the last `WEEKS_SHOWN` ISO weeks, oldest first
+fn recent_weeks(metrics: &Metrics) -> Vec<(String, u64)> {
+    let now = Utc::now();
+    (0..WEEKS_SHOWN)
+        .rev()
+        .map(|ago| {
+            let week = iso_week(&(now - Duration::weeks(ago)));
+            let count = metrics.asks.by_week.get(&week).copied().unwrap_or(0);
+            (week, count)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recent_weeks_fills_gaps() {
+        let mut metrics = Metrics::default();
+        metrics.asks.by_week.insert(iso_week(&Utc::now()), 3);
+        metrics.asks.by_week.insert("2001-W01".to_string(), 9);
+
+        let weeks = recent_weeks(&metrics);
+        assert_eq!(weeks.len(), WEEKS_SHOWN as usize);
+        assert_eq!(weeks.last().unwrap().1, 3);
+        assert_eq!(weeks.iter().map(|(_, n)| n).sum::<u64>(), 3);
+    }
+}
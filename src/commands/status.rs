@@ -1,27 +1,49 @@
 //! Status command: shows the state of the noggin knowledge base.
 //!
-//! Reports files scanned, pending changes, unprocessed commits,
-//! ARF file counts by category, and overall freshness.
-
+//! Reports files scanned, pending changes, unprocessed significant
+//! commits, patterns invalidated by file changes, ARF file counts by
+//! category (flagging any over their `size` budget), entries whose
+//! decayed confidence fell below `decay.low_threshold`, and overall
+//! freshness. Exits non-zero when there is drift, so scripts can use it
+//! as a check.
+
+use crate::commands::consolidate::over_budget;
+use crate::commands::learn::{find_invalidated_patterns, history_refs, significant_commits};
+use crate::config::Config;
+use crate::decay::{low_confidence, DecayedEntry};
+use crate::git::fingerprint::RepositoryFingerprint;
 use crate::git::walker::{walk_commits, WalkOptions};
+use crate::learn::generated::GeneratedFiles;
 use crate::learn::scanner::scan_files;
-use crate::manifest::Manifest;
+use crate::learn::stack::{self, Stack};
+use crate::storage::{self, in_dir, KnowledgeBackend};
+use crate::time::format_datetime;
 use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
 use colored::Colorize;
 use serde::Serialize;
 use std::env;
-use std::fs;
-use std::path::Path;
 
 /// Status information collected for display
 #[derive(Debug, Serialize)]
 struct StatusInfo {
     repo_path: String,
     initialized: bool,
+    /// Stack recorded by the last learn, or detected now if none was
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stack: Option<Stack>,
     files: FileStatus,
     commits: CommitStatus,
     knowledge: KnowledgeStatus,
+    /// Patterns whose contributing files changed or were deleted
+    invalidated_patterns: Vec<String>,
+    /// Entries below the decay threshold, lowest first
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    low_confidence: Vec<DecayedEntry>,
     up_to_date: bool,
+    /// Set when the knowledge base is bound to a different repository
+    #[serde(skip_serializing_if = "Option::is_none")]
+    repository_mismatch: Option<String>,
 }
 
 #[derive(Debug, Serialize)]
@@ -31,14 +53,31 @@ struct FileStatus {
     modified: usize,
     new: usize,
     deleted: usize,
+    /// Tracked files found under a new path
+    renamed: usize,
     unchanged: usize,
+    /// Generated files on disk: tracked for drift, never analyzed
+    generated: usize,
+    /// Generated files new or changed since the last scan
+    generated_changed: usize,
+    /// Most recent file scan, ISO-8601 in JSON
+    #[serde(skip_serializing_if = "Option::is_none")]
+    last_scan: Option<DateTime<Utc>>,
 }
 
 #[derive(Debug, Serialize)]
 struct CommitStatus {
     total: usize,
     processed: usize,
+    /// Unprocessed commits significant enough for learn to analyze
     unprocessed: usize,
+    /// Repository is a shallow clone; older history is unavailable
+    shallow: bool,
+    /// Boundary commits of a shallow clone, not counted as unprocessed
+    shallow_boundary: usize,
+    /// The last learn saw truncated history that is now complete, so the
+    /// next one backfills older commits
+    backfill: bool,
 }
 
 #[derive(Debug, Serialize)]
@@ -49,13 +88,27 @@ struct KnowledgeStatus {
     bugs: usize,
     migrations: usize,
     facts: usize,
+    /// Categories with more entries than their configured budget
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    over_budget: Vec<CategoryBudget>,
+}
+
+#[derive(Debug, Serialize)]
+struct CategoryBudget {
+    category: String,
+    entries: usize,
+    limit: usize,
 }
 
 /// Run the status command.
 ///
 /// If `verbose` is true, shows detailed file and commit listings.
 /// If `json` is true, outputs machine-readable JSON.
-pub fn status_command(verbose: bool, json: bool) -> Result<()> {
+/// Times are shown in local time unless `utc` is set.
+/// `below` lists entries under that confidence instead of
+/// `decay.low_threshold`.
+/// Returns an error after printing if there is drift.
+pub fn status_command(verbose: bool, json: bool, utc: bool, below: Option<f64>) -> Result<()> {
     let repo_path = env::current_dir()?;
     let noggin_path = repo_path.join(".noggin");
 
@@ -64,14 +117,23 @@ pub fn status_command(verbose: bool, json: bool) -> Result<()> {
             let info = StatusInfo {
                 repo_path: repo_path.display().to_string(),
                 initialized: false,
+                stack: None,
                 files: FileStatus {
-                    total: 0, scanned: 0, modified: 0, new: 0, deleted: 0, unchanged: 0,
+                    total: 0, scanned: 0, modified: 0, new: 0, deleted: 0, renamed: 0, unchanged: 0,
+                    generated: 0, generated_changed: 0,
+                    last_scan: None,
+                },
+                commits: CommitStatus {
+                    total: 0, processed: 0, unprocessed: 0, shallow: false, shallow_boundary: 0, backfill: false,
                 },
-                commits: CommitStatus { total: 0, processed: 0, unprocessed: 0 },
                 knowledge: KnowledgeStatus {
                     total_arfs: 0, decisions: 0, patterns: 0, bugs: 0, migrations: 0, facts: 0,
+                    over_budget: Vec::new(),
                 },
+                invalidated_patterns: Vec::new(),
+                low_confidence: Vec::new(),
                 up_to_date: false,
+                repository_mismatch: None,
             };
             println!("{}", serde_json::to_string_pretty(&info)?);
         } else {
@@ -84,13 +146,41 @@ pub fn status_command(verbose: bool, json: bool) -> Result<()> {
         return Ok(());
     }
 
-    let manifest_path = noggin_path.join("manifest.toml");
-    let manifest = Manifest::load(&manifest_path)
-        .context("Failed to load manifest")?;
+    let mut config = Config::load(&noggin_path).context("Failed to load config")?;
+    let backend = storage::open(&noggin_path, &config.storage);
+    let mut manifest = backend.load_manifest().context("Failed to load manifest")?;
+
+    let repository_mismatch = match &manifest.repository {
+        Some(stored) => stored
+            .check(&RepositoryFingerprint::detect(&repo_path)?)
+            .map(|mismatch| mismatch.to_string()),
+        None => None,
+    };
+
+    manifest.set_path_case(config.manifest.path_case);
+    if let Some(below) = below {
+        config.decay.low_threshold = below;
+    }
 
     // Scan files
-    let scan_result = scan_files(&repo_path, &manifest, false)
-        .context("Failed to scan files")?;
+    let generated = GeneratedFiles::from_config(&config.generated)?;
+    let scan_result = scan_files(
+        &repo_path,
+        &manifest,
+        false,
+        &generated,
+        &[],
+        config.submodules.recurse,
+    )
+    .context("Failed to scan files")?;
+    for rename in &scan_result.renamed {
+        manifest.rename_file(&rename.from, &rename.to);
+    }
+
+    let stack = manifest.stack.clone().unwrap_or_else(|| {
+        let paths = scan_result.current_paths(&manifest);
+        stack::detect(&repo_path, paths.iter().map(String::as_str))
+    });
 
     let modified_count = scan_result.changed.iter().filter(|f| f.is_changed).count();
     let new_count = scan_result.changed.iter().filter(|f| f.is_new).count();
@@ -100,53 +190,93 @@ pub fn status_command(verbose: bool, json: bool) -> Result<()> {
         &repo_path,
         WalkOptions {
             skip_merges: true,
+            refs: history_refs(&config, &[]),
+            all_branches: config.history.all_branches,
             ..Default::default()
         },
     )
     .context("Failed to walk git history")?;
 
     let total_commits = walk_result.commits.len();
-    let unprocessed_commits: Vec<_> = walk_result
+    let shallow_boundary = walk_result.boundary_commits().count();
+    let unprocessed: Vec<_> = walk_result
         .commits
-        .iter()
-        .filter(|c| !manifest.is_commit_processed(&c.hash))
+        .into_iter()
+        .filter(|c| !c.shallow_boundary && !manifest.is_commit_processed(&c.hash))
         .collect();
+    // Learn only records significant commits, so only those are pending
+    let repo = git2::Repository::open(&repo_path)?;
+    let unprocessed_commits = significant_commits(&repo, unprocessed, &config.scoring);
+
+    let invalidated_patterns =
+        find_invalidated_patterns(&manifest, &scan_result.changed, &scan_result.deleted);
 
     // Count ARF files by category
-    let knowledge = count_arf_files(&noggin_path);
+    let mut knowledge = count_arf_files(backend.as_ref())?;
+    knowledge.over_budget = over_budget(backend.as_ref(), &config.size)?
+        .into_iter()
+        .map(|(category, entries, limit)| CategoryBudget { category, entries, limit })
+        .collect();
+
+    let low_confidence = low_confidence(&repo_path, backend.as_ref(), &manifest, &config.decay)?;
 
     let up_to_date = scan_result.changed.is_empty()
+        && scan_result.generated.is_empty()
         && scan_result.deleted.is_empty()
-        && unprocessed_commits.is_empty();
+        && scan_result.renamed.is_empty()
+        && unprocessed_commits.is_empty()
+        && invalidated_patterns.is_empty();
 
     let info = StatusInfo {
         repo_path: repo_path.display().to_string(),
         initialized: true,
+        stack: (!stack.is_empty()).then_some(stack),
         files: FileStatus {
             total: scan_result.total,
             scanned: manifest.files.len(),
             modified: modified_count,
             new: new_count,
             deleted: scan_result.deleted.len(),
+            renamed: scan_result.renamed.len(),
             unchanged: scan_result.unchanged,
+            generated: scan_result.generated_total,
+            generated_changed: scan_result.generated.len(),
+            last_scan: manifest.stats().last_scan,
         },
         commits: CommitStatus {
             total: total_commits,
-            processed: manifest.commits.len(),
+            processed: manifest.processed_commits(),
             unprocessed: unprocessed_commits.len(),
+            shallow: walk_result.shallow,
+            shallow_boundary,
+            backfill: !walk_result.shallow && manifest.truncated_history.is_some(),
         },
         knowledge,
+        invalidated_patterns,
+        low_confidence,
         up_to_date,
+        repository_mismatch,
     };
 
     if json {
         println!("{}", serde_json::to_string_pretty(&info)?);
-        return Ok(());
+        return drift_result(up_to_date);
     }
 
     // Human-readable output
     println!("{}", "Noggin Status".bold());
     println!("{}", repo_path.display().to_string().dimmed());
+    if let Some(stack) = &info.stack {
+        println!("{} {}", "Stack:".bold(), stack.summary());
+    }
+    if let Some(mismatch) = &info.repository_mismatch {
+        println!(
+            "{} {}. Run {} to rebind.",
+            "Warning:".red().bold(),
+            mismatch,
+            "'noggin learn --force-adopt'".cyan()
+        );
+    }
     println!();
 
     // Files section
@@ -156,6 +286,9 @@ pub fn status_command(verbose: bool, json: bool) -> Result<()> {
         info.files.scanned.to_string().cyan(),
         info.files.total
     );
+    if let Some(last_scan) = &info.files.last_scan {
+        println!("  last scanned {}", format_datetime(last_scan, utc).dimmed());
+    }
     if info.files.modified > 0 {
         println!(
             "  {} modified",
@@ -174,9 +307,22 @@ pub fn status_command(verbose: bool, json: bool) -> Result<()> {
             info.files.deleted.to_string().red()
         );
     }
+    if info.files.renamed > 0 {
+        println!(
+            "  {} renamed",
+            info.files.renamed.to_string().yellow()
+        );
+    }
+    if info.files.generated > 0 {
+        println!(
+            "  {} generated (tracked, not analyzed), {} changed",
+            info.files.generated.to_string().dimmed(),
+            info.files.generated_changed
+        );
+    }
 
     // Verbose: list changed files
-    if verbose && !scan_result.changed.is_empty() {
+    if verbose && !(scan_result.changed.is_empty() && scan_result.generated.is_empty()) {
         for file in &scan_result.changed {
             let label = if file.is_new {
                 "new".green()
@@ -185,9 +331,16 @@ pub fn status_command(verbose: bool, json: bool) -> Result<()> {
             };
             println!("    {} [{}]", file.path.dimmed(), label);
         }
+        for file in &scan_result.generated {
+            let label = if file.is_new { "new" } else { "modified" };
+            println!("    {} [{}, {}]", file.path.dimmed(), label.yellow(), "generated".dimmed());
+        }
         for path in &scan_result.deleted {
             println!("    {} [{}]", path.dimmed(), "deleted".red());
         }
+        for rename in &scan_result.renamed {
+            println!("    {} -> {} [{}]", rename.from.dimmed(), rename.to.dimmed(), "renamed".yellow());
+        }
     }
 
     println!();
@@ -205,6 +358,19 @@ pub fn status_command(verbose: bool, json: bool) -> Result<()> {
             info.commits.unprocessed.to_string().yellow()
         );
     }
+    if info.commits.shallow {
+        println!(
+            "  {} shallow clone: history truncated at {} boundary commits",
+            "note:".dimmed(),
+            info.commits.shallow_boundary
+        );
+    }
+    if info.commits.backfill {
+        println!(
+            "  {} full history now available; the next learn backfills commits the shallow clone lacked",
+            "note:".dimmed()
+        );
+    }
 
     // Verbose: list unprocessed commits
     if verbose && !unprocessed_commits.is_empty() {
@@ -249,6 +415,16 @@ pub fn status_command(verbose: bool, json: bool) -> Result<()> {
             println!("  {}", non_empty.join(", "));
         }
     }
+    for budget in &info.knowledge.over_budget {
+        println!(
+            "  {} {} has {} entries, over its budget of {}. Run {} to merge similar entries.",
+            "Warning:".yellow().bold(),
+            budget.category,
+            budget.entries,
+            budget.limit,
+            format!("'noggin consolidate {}'", budget.category).cyan()
+        );
+    }
 
     // Patterns in manifest
     if !manifest.patterns.is_empty() {
@@ -257,6 +433,39 @@ pub fn status_command(verbose: bool, json: bool) -> Result<()> {
             manifest.patterns.len().to_string().cyan()
         );
     }
+    if !info.invalidated_patterns.is_empty() {
+        println!(
+            "  {} patterns invalidated by file changes",
+            info.invalidated_patterns.len().to_string().yellow()
+        );
+        if verbose {
+            for pattern_id in &info.invalidated_patterns {
+                println!("    {}", pattern_id.dimmed());
+            }
+        }
+    }
+    if !info.low_confidence.is_empty() {
+        println!(
+            "  {} entries below confidence {:.2}",
+            info.low_confidence.len().to_string().yellow(),
+            config.decay.low_threshold
+        );
+        let shown = if verbose { info.low_confidence.len() } else { 5 };
+        for entry in info.low_confidence.iter().take(shown) {
+            let note = if entry.changed_files.is_empty() {
+                String::new()
+            } else {
+                " (files changed)".to_string()
+            };
+            println!(
+                "    {:.2} {}{}",
+                entry.effective,
+                entry.path.dimmed(),
+                note.yellow()
+            );
+        }
+        println!("  Run {} to re-analyze them.", "'noggin maintain'".cyan());
+    }
 
     println!();
 
@@ -275,16 +484,31 @@ pub fn status_command(verbose: bool, json: bool) -> Result<()> {
             } else {
                 None
             },
+            if !scan_result.generated.is_empty() {
+                Some(format!("{} changed generated files", scan_result.generated.len()))
+            } else {
+                None
+            },
             if !scan_result.deleted.is_empty() {
                 Some(format!("{} deleted files", scan_result.deleted.len()))
             } else {
                 None
             },
+            if !scan_result.renamed.is_empty() {
+                Some(format!("{} renamed files", scan_result.renamed.len()))
+            } else {
+                None
+            },
             if !unprocessed_commits.is_empty() {
                 Some(format!("{} unprocessed commits", unprocessed_commits.len()))
             } else {
                 None
             },
+            if !info.invalidated_patterns.is_empty() {
+                Some(format!("{} invalidated patterns", info.invalidated_patterns.len()))
+            } else {
+                None
+            },
         ]
         .into_iter()
         .flatten()
@@ -301,19 +525,20 @@ pub fn status_command(verbose: bool, json: bool) -> Result<()> {
         );
     }
 
-    Ok(())
+    drift_result(up_to_date)
 }
 
-/// Count .arf files in each category subdirectory
-fn count_arf_files(noggin_path: &Path) -> KnowledgeStatus {
-    let categories = [
-        ("decisions", 0),
-        ("patterns", 0),
-        ("bugs", 0),
-        ("migrations", 0),
-        ("facts", 0),
-    ];
+/// Fail the command when there is pending work, for use in scripts
+fn drift_result(up_to_date: bool) -> Result<()> {
+    if up_to_date {
+        Ok(())
+    } else {
+        anyhow::bail!("Knowledge base has drift")
+    }
+}
 
+/// Count .arf files in each category subdirectory
+fn count_arf_files(backend: &dyn KnowledgeBackend) -> Result<KnowledgeStatus> {
     let mut status = KnowledgeStatus {
         total_arfs: 0,
         decisions: 0,
@@ -321,39 +546,31 @@ fn count_arf_files(noggin_path: &Path) -> KnowledgeStatus {
         bugs: 0,
         migrations: 0,
         facts: 0,
+        over_budget: Vec::new(),
     };
 
-    for (dir_name, _) in &categories {
-        let dir_path = noggin_path.join(dir_name);
-        if let Ok(entries) = fs::read_dir(&dir_path) {
-            let count = entries
-                .filter_map(|e| e.ok())
-                .filter(|e| {
-                    e.path()
-                        .extension()
-                        .map(|ext| ext == "arf")
-                        .unwrap_or(false)
-                })
-                .count();
-
-            match *dir_name {
-                "decisions" => status.decisions = count,
-                "patterns" => status.patterns = count,
-                "bugs" => status.bugs = count,
-                "migrations" => status.migrations = count,
-                "facts" => status.facts = count,
-                _ => {}
-            }
-            status.total_arfs += count;
+    let paths = backend.list_arfs()?;
+    for dir_name in ["decisions", "patterns", "bugs", "migrations", "facts"] {
+        let count = paths.iter().filter(|path| in_dir(path, dir_name)).count();
+        match dir_name {
+            "decisions" => status.decisions = count,
+            "patterns" => status.patterns = count,
+            "bugs" => status.bugs = count,
+            "migrations" => status.migrations = count,
+            "facts" => status.facts = count,
+            _ => {}
         }
+        status.total_arfs += count;
     }
 
-    status
+    Ok(status)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::storage::FilesystemBackend;
+    use std::fs;
     use tempfile::TempDir;
 
     fn setup_noggin_dir(temp_dir: &TempDir) {
@@ -373,7 +590,8 @@ mod tests {
         let temp_dir = TempDir::new().unwrap();
         setup_noggin_dir(&temp_dir);
 
-        let result = count_arf_files(&temp_dir.path().join(".noggin"));
+        let result = count_arf_files(&FilesystemBackend::new(&temp_dir.path().join(".noggin")))
+            .unwrap();
 
         assert_eq!(result.total_arfs, 0);
         assert_eq!(result.decisions, 0);
@@ -412,7 +630,7 @@ mod tests {
         // Write a non-arf file that should be ignored
         fs::write(noggin.join("decisions/notes.txt"), "not an arf").unwrap();
 
-        let result = count_arf_files(&noggin);
+        let result = count_arf_files(&FilesystemBackend::new(&noggin)).unwrap();
 
         assert_eq!(result.total_arfs, 4);
         assert_eq!(result.decisions, 2);
@@ -429,7 +647,7 @@ mod tests {
         fs::create_dir_all(&noggin).unwrap();
         // Don't create subdirectories
 
-        let result = count_arf_files(&noggin);
+        let result = count_arf_files(&FilesystemBackend::new(&noggin)).unwrap();
 
         assert_eq!(result.total_arfs, 0);
     }
@@ -439,18 +657,26 @@ mod tests {
         let info = StatusInfo {
             repo_path: "/tmp/test".to_string(),
             initialized: true,
+            stack: None,
             files: FileStatus {
                 total: 50,
                 scanned: 45,
                 modified: 3,
                 new: 2,
                 deleted: 1,
+                renamed: 0,
                 unchanged: 42,
+                generated: 4,
+                generated_changed: 1,
+                last_scan: Some("2026-01-02T03:04:05Z".parse().unwrap()),
             },
             commits: CommitStatus {
                 total: 100,
                 processed: 95,
                 unprocessed: 5,
+                shallow: false,
+                shallow_boundary: 0,
+                backfill: false,
             },
             knowledge: KnowledgeStatus {
                 total_arfs: 10,
@@ -459,8 +685,16 @@ mod tests {
                 bugs: 1,
                 migrations: 1,
                 facts: 1,
+                over_budget: vec![CategoryBudget {
+                    category: "patterns".to_string(),
+                    entries: 4,
+                    limit: 3,
+                }],
             },
+            invalidated_patterns: vec!["error-handling".to_string()],
+            low_confidence: Vec::new(),
             up_to_date: false,
+            repository_mismatch: None,
         };
 
         let json = serde_json::to_string_pretty(&info).unwrap();
@@ -468,6 +702,15 @@ mod tests {
         assert!(json.contains("\"modified\": 3"));
         assert!(json.contains("\"unprocessed\": 5"));
         assert!(json.contains("\"total_arfs\": 10"));
+        assert!(json.contains("\"over_budget\""));
         assert!(json.contains("\"up_to_date\": false"));
+        assert!(json.contains("\"error-handling\""));
+        assert!(!json.contains("low_confidence"));
+    }
+
+    #[test]
+    fn test_drift_result() {
+        assert!(drift_result(true).is_ok());
+        assert!(drift_result(false).is_err());
     }
 }
@@ -0,0 +1,398 @@
+//! `noggin synthesize`: run the consensus engine on outputs produced
+//! elsewhere
+//!
+//! Teams with their own LLM orchestration can send noggin's prompts
+//! through it and hand the raw responses back. They are parsed,
+//! synthesized, and written to the knowledge base exactly as `learn`
+//! would, without noggin calling any provider.
+//!
+//! Input is either a directory of response files or JSON:
+//!
+//! - In a directory, `<prompt_type>-<model>.txt` holds one raw response,
+//!   the layout `.noggin/raw/` uses (a leading timestamp is ignored). A
+//!   file named only `<model>.txt` is an `external` prompt. `*.json`
+//!   files are read as below.
+//! - JSON, from a file or stdin, is an array of
+//!   `{"model": "...", "prompt_type": "...", "response": "..."}`, with
+//!   `prompt_type` optional.
+
+use crate::commands::learn::{print_planned, save_manual_conflicts};
+use crate::config::Config;
+use crate::learn::glossary;
+use crate::learn::prompts::PROMPT_TYPES;
+use crate::learn::writer::{apply_arfs, PlannedWrite, WriteOptions};
+use crate::lock::KnowledgeLock;
+use crate::storage;
+use crate::synthesis::{self, ModelOutput, PromptOutputs, SynthesisReport};
+use anyhow::{Context, Result};
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+/// Prompt type of responses that don't name one
+pub const EXTERNAL_PROMPT: &str = "external";
+
+/// Options for `noggin synthesize`
+#[derive(Debug, Clone, Default)]
+pub struct SynthesizeOptions {
+    /// Directory or JSON file of responses; stdin when None or `-`
+    pub input: Option<PathBuf>,
+    /// Report what would be written without writing it
+    pub dry_run: bool,
+    pub json: bool,
+}
+
+/// One raw response from an external model
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExternalOutput {
+    pub model: String,
+    #[serde(default = "default_prompt_type")]
+    pub prompt_type: String,
+    pub response: String,
+}
+
+fn default_prompt_type() -> String {
+    EXTERNAL_PROMPT.to_string()
+}
+
+/// What a synthesize run did
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SynthesizeReport {
+    /// Responses read
+    pub responses: usize,
+    /// Responses that yielded no entries
+    pub unparsed: usize,
+    pub synthesis: Option<SynthesisReport>,
+    pub written: usize,
+    pub updated: usize,
+    pub skipped: usize,
+    /// Paths written or updated, relative to .noggin/
+    pub paths: Vec<String>,
+    /// Conflict records created instead of overwriting approved ARFs
+    pub conflicts: Vec<String>,
+    /// New entries held in `pending/`
+    pub pending: Vec<String>,
+    /// Fields voting could not settle, left for `noggin resolve`
+    pub unresolved: Vec<String>,
+    pub warnings: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub planned: Vec<PlannedWrite>,
+}
+
+pub fn synthesize_command(opts: SynthesizeOptions) -> Result<()> {
+    let noggin_path = env::current_dir()?.join(".noggin");
+    if !noggin_path.exists() {
+        anyhow::bail!("Not initialized. Run 'noggin init' first.");
+    }
+
+    let config = Config::load(&noggin_path).context("Failed to load config")?;
+    let outputs = match opts.input.as_deref().filter(|p| *p != Path::new("-")) {
+        Some(path) => read_input(path)?,
+        None => {
+            let mut contents = String::new();
+            io::stdin()
+                .read_to_string(&mut contents)
+                .context("Failed to read stdin")?;
+            serde_json::from_str(&contents).context("Failed to parse responses from stdin")?
+        }
+    };
+    let _lock = if opts.dry_run {
+        None
+    } else {
+        Some(KnowledgeLock::acquire(&noggin_path, "synthesize")?)
+    };
+    let report = synthesize_outputs(&noggin_path, outputs, &config, opts.dry_run)?;
+
+    if opts.json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+        return Ok(());
+    }
+    print_report(&report, opts.dry_run);
+    Ok(())
+}
+
+/// Read responses from a directory of response files or a JSON file
+pub fn read_input(path: &Path) -> Result<Vec<ExternalOutput>> {
+    if !path.is_dir() {
+        return read_json(path);
+    }
+
+    let mut files: Vec<PathBuf> = fs::read_dir(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|p| p.is_file())
+        .collect();
+    files.sort();
+
+    let mut outputs = Vec::new();
+    for file in files {
+        if file.extension().is_some_and(|ext| ext == "json") {
+            outputs.extend(read_json(&file)?);
+            continue;
+        }
+        let Some(stem) = file.file_stem().and_then(|s| s.to_str()) else {
+            continue;
+        };
+        if stem.starts_with('.') {
+            continue;
+        }
+        let response = fs::read_to_string(&file)
+            .with_context(|| format!("Failed to read {}", file.display()))?;
+        let (prompt_type, model) = split_name(stem);
+        outputs.push(ExternalOutput {
+            model,
+            prompt_type,
+            response,
+        });
+    }
+    Ok(outputs)
+}
+
+fn read_json(path: &Path) -> Result<Vec<ExternalOutput>> {
+    let contents =
+        fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    serde_json::from_str(&contents)
+        .with_context(|| format!("Failed to parse responses from {}", path.display()))
+}
+
+/// Prompt type and model from `[timestamp-]<prompt_type>-<model>`. The
+/// model is everything after the prompt type, so names like `gpt-4o` stay
+/// whole; a name without a known prompt type is all model.
+fn split_name(stem: &str) -> (String, String) {
+    let parts: Vec<&str> = stem.split('-').collect();
+    let found = parts[..parts.len() - 1]
+        .iter()
+        .position(|part| PROMPT_TYPES.contains(part));
+    match found {
+        Some(idx) => (parts[idx].to_string(), parts[idx + 1..].join("-")),
+        None => (EXTERNAL_PROMPT.to_string(), stem.to_string()),
+    }
+}
+
+/// Parse `outputs`, synthesize them prompt by prompt, and write the
+/// result to the knowledge base at `noggin_path`
+pub fn synthesize_outputs(
+    noggin_path: &Path,
+    outputs: Vec<ExternalOutput>,
+    config: &Config,
+    dry_run: bool,
+) -> Result<SynthesizeReport> {
+    let mut report = SynthesizeReport {
+        responses: outputs.len(),
+        ..Default::default()
+    };
+
+    let mut prompts: BTreeMap<String, Vec<ModelOutput>> = BTreeMap::new();
+    for output in outputs {
+        match synthesis::parse_model_output(&output.model, &output.response) {
+            Ok(parsed) => {
+                for warning in &parsed.warnings {
+                    report.warnings.push(format!(
+                        "{} output for {}: {}",
+                        output.model, output.prompt_type, warning
+                    ));
+                }
+                prompts.entry(output.prompt_type).or_default().push(ModelOutput {
+                    model_name: output.model,
+                    arf_files: parsed.arfs,
+                });
+            }
+            Err(e) => {
+                report.unparsed += 1;
+                report.warnings.push(format!(
+                    "Failed to parse {} output for {}: {}",
+                    output.model, output.prompt_type, e
+                ));
+            }
+        }
+    }
+    if prompts.is_empty() {
+        anyhow::bail!("No response contained ARF entries");
+    }
+
+    let prompt_outputs = prompts
+        .into_iter()
+        .map(|(prompt_type, outputs)| PromptOutputs {
+            prompt_type,
+            outputs,
+        })
+        .collect();
+    let result = synthesis::synthesize_by_prompt(prompt_outputs, &config.synthesis)
+        .context("Synthesis failed")?;
+    report.warnings.extend(result.report.degradation_warning());
+    let mut arfs = result.unified_arfs;
+    glossary::mark_entries(&mut arfs);
+
+    let write_opts = WriteOptions {
+        category: None,
+        dry_run,
+        max_new: (config.size.max_new_per_run > 0).then_some(config.size.max_new_per_run),
+        dedupe_similarity: (config.synthesis.dedupe_similarity > 0.0)
+            .then_some(config.synthesis.dedupe_similarity),
+        naming: config.writer.naming,
+    };
+    let backend = storage::open(noggin_path, &config.storage);
+    let written = apply_arfs(backend.as_ref(), noggin_path, &arfs, &write_opts)
+        .context("Failed to write ARF files")?;
+    report.unresolved = save_manual_conflicts(
+        noggin_path,
+        &arfs,
+        &written,
+        &result.manual_conflicts,
+        dry_run,
+    )?;
+    report.synthesis = Some(result.report);
+    report.written = written.written;
+    report.updated = written.updated;
+    report.skipped = written.skipped;
+    report.paths = written.paths;
+    report.conflicts = written.conflicts;
+    report.pending = written.pending;
+    report.pending.extend(written.incomplete);
+    report.planned = written.planned;
+    Ok(report)
+}
+
+fn print_report(report: &SynthesizeReport, dry_run: bool) {
+    if let Some(synthesis) = &report.synthesis {
+        println!(
+            "Synthesized {} entries from {} responses ({} input entries, {} conflicts resolved)",
+            synthesis.total_output_arfs,
+            report.responses - report.unparsed,
+            synthesis.total_input_arfs,
+            synthesis.conflicts_resolved
+        );
+    }
+    println!(
+        "{} {} new, {} updated, {} skipped ARF files",
+        if dry_run { "Would write" } else { "Wrote" },
+        report.written,
+        report.updated,
+        report.skipped
+    );
+    if dry_run {
+        print_planned(&report.planned);
+    }
+    if !report.conflicts.is_empty() {
+        println!(
+            "{} {} approved entries contradicted; see .noggin/conflicts/",
+            "!".yellow(),
+            report.conflicts.len()
+        );
+    }
+    if !report.pending.is_empty() {
+        println!("{} entries held in .noggin/pending/", report.pending.len());
+    }
+    if !report.unresolved.is_empty() {
+        println!(
+            "{} fields need a decision; run 'noggin resolve'",
+            report.unresolved.len()
+        );
+    }
+    for warning in &report.warnings {
+        eprintln!("{} {}", "warning:".yellow(), warning);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::arf::{arf_paths, ArfFile};
+    use tempfile::TempDir;
+
+    const POOLING: &str =
+        "what = \"Use connection pooling\"\nwhy = \"Limits connections\"\nhow = \"deadpool\"\n";
+
+    #[test]
+    fn test_read_input_directory() {
+        let temp_dir = TempDir::new().unwrap();
+        let dir = temp_dir.path();
+        fs::write(dir.join("20260301T101010000-files-claude.txt"), POOLING).unwrap();
+        fs::write(dir.join("commits-gemini.txt"), POOLING).unwrap();
+        fs::write(dir.join("codex.txt"), POOLING).unwrap();
+        fs::write(
+            dir.join("more.json"),
+            r#"[{"model": "local", "response": "what = \"x\""}]"#,
+        )
+        .unwrap();
+
+        let outputs = read_input(dir).unwrap();
+        let names: Vec<(&str, &str)> = outputs
+            .iter()
+            .map(|o| (o.prompt_type.as_str(), o.model.as_str()))
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                ("files", "claude"),
+                ("external", "codex"),
+                ("commits", "gemini"),
+                ("external", "local"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_split_name_keeps_hyphenated_models() {
+        assert_eq!(
+            split_name("20260301T101010000-files-gpt-4o"),
+            ("files".to_string(), "gpt-4o".to_string())
+        );
+        assert_eq!(
+            split_name("releases-claude"),
+            ("releases".to_string(), "claude".to_string())
+        );
+        assert_eq!(
+            split_name("glossary-gpt-4o"),
+            ("glossary".to_string(), "gpt-4o".to_string())
+        );
+        assert_eq!(
+            split_name("gpt-4o"),
+            ("external".to_string(), "gpt-4o".to_string())
+        );
+    }
+
+    #[test]
+    fn test_synthesize_outputs_writes_consensus() {
+        let temp_dir = TempDir::new().unwrap();
+        let noggin_path = temp_dir.path().join(".noggin");
+        fs::create_dir_all(&noggin_path).unwrap();
+        let output = |model: &str, response: &str| ExternalOutput {
+            model: model.to_string(),
+            prompt_type: "files".to_string(),
+            response: response.to_string(),
+        };
+        let outputs = vec![
+            output("claude", POOLING),
+            output("gemini", POOLING),
+            output("codex", "not toml at all"),
+        ];
+
+        let report =
+            synthesize_outputs(&noggin_path, outputs.clone(), &Config::default(), true).unwrap();
+        assert_eq!(report.written, 1);
+        assert!(arf_paths(&noggin_path).is_empty());
+
+        let report = synthesize_outputs(&noggin_path, outputs, &Config::default(), false).unwrap();
+        assert_eq!(report.responses, 3);
+        assert_eq!(report.unparsed, 1);
+        assert_eq!(report.synthesis.as_ref().unwrap().total_output_arfs, 1);
+        let arf = ArfFile::from_toml(&noggin_path.join(&report.paths[0])).unwrap();
+        assert_eq!(arf.what, "Use connection pooling");
+        assert_eq!(arf.context.provenance.models, vec!["claude", "gemini"]);
+
+        let err = synthesize_outputs(
+            &noggin_path,
+            vec![output("codex", "nope")],
+            &Config::default(),
+            false,
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("No response contained ARF entries"));
+    }
+}
@@ -0,0 +1,262 @@
+//! `noggin translate`: rewrite off-language ARFs in `llm.output_language`
+//!
+//! Finds entries whose prose doesn't appear to be in the configured
+//! language (see [`crate::learn::language`]) and asks a provider to
+//! translate their what/why/how, one entry per request. Everything else
+//! in the ARF is kept. Approved entries are listed but left alone, since
+//! a reviewer signed off on their wording.
+
+use crate::config::Config;
+use crate::learn::language::{
+    apply_translation, build_translation_prompt, matches_language, off_language_arfs,
+};
+use crate::learn::ledger::{MachineBudget, MeteredRun};
+use crate::llm::parallel::Scheduler;
+use crate::llm::stream::ignore_chunks;
+use crate::llm::{configured_providers, provider_from_config, LLMProvider};
+use crate::lock::KnowledgeLock;
+use crate::storage::{self, KnowledgeBackend};
+use anyhow::{Context, Result};
+use chrono::Utc;
+use colored::Colorize;
+use serde::Serialize;
+use std::env;
+use std::path::Path;
+
+/// Options for `noggin translate`
+#[derive(Debug, Clone, Default)]
+pub struct TranslateOptions {
+    /// Provider that translates; defaults to the first configured one
+    pub provider: Option<String>,
+    /// Query the provider but write nothing
+    pub dry_run: bool,
+    pub json: bool,
+}
+
+/// What a translate run changed
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct TranslateReport {
+    pub language: String,
+    pub dry_run: bool,
+    /// ARFs rewritten (or that would be, with `dry_run`)
+    pub translated: Vec<String>,
+    /// Off-language ARFs left alone because they are approved
+    pub approved: Vec<String>,
+    pub failed: Vec<TranslateFailure>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TranslateFailure {
+    pub path: String,
+    pub error: String,
+}
+
+pub async fn translate_command(opts: TranslateOptions) -> Result<()> {
+    let repo_path = env::current_dir()?;
+    let report = run_translate(&repo_path, &opts).await?;
+
+    if opts.json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+        if !report.failed.is_empty() {
+            anyhow::bail!("{} entries could not be translated", report.failed.len());
+        }
+        return Ok(());
+    }
+
+    if report.translated.is_empty() && report.approved.is_empty() && report.failed.is_empty() {
+        println!("Every entry is already in {}.", report.language);
+        return Ok(());
+    }
+
+    let verb = if opts.dry_run {
+        "Would translate"
+    } else {
+        "Translated"
+    };
+    for path in &report.translated {
+        println!("  {} {}", verb.green(), path);
+    }
+    for path in &report.approved {
+        println!("  {} {} (approved)", "Skipped".yellow(), path);
+    }
+    for failure in &report.failed {
+        println!("  {} {}: {}", "Failed".red(), failure.path, failure.error);
+    }
+    println!(
+        "{} {} entries into {}",
+        verb,
+        report.translated.len(),
+        report.language
+    );
+
+    if !report.failed.is_empty() {
+        anyhow::bail!("{} entries could not be translated", report.failed.len());
+    }
+    Ok(())
+}
+
+/// Translate the off-language ARFs of the knowledge base in `repo_path`
+pub async fn run_translate(repo_path: &Path, opts: &TranslateOptions) -> Result<TranslateReport> {
+    let noggin_path = repo_path.join(".noggin");
+    if !noggin_path.exists() {
+        anyhow::bail!("Not initialized. Run 'noggin init' first.");
+    }
+    let _lock = if opts.dry_run {
+        None
+    } else {
+        Some(KnowledgeLock::acquire(&noggin_path, "translate")?)
+    };
+
+    let config = Config::load(&noggin_path).context("Failed to load config")?;
+    let provider = match &opts.provider {
+        Some(name) => provider_from_config(name, &config.llm)?.with_context(|| {
+            format!(
+                "Unknown provider '{}'. Available: {}",
+                name,
+                config.llm.provider_names().join(", ")
+            )
+        })?,
+        None => configured_providers(&config.llm)?
+            .into_iter()
+            .next()
+            .context("No providers configured")?,
+    };
+    let backend = storage::open(&noggin_path, &config.storage);
+    let scheduler = Scheduler::new(&config.llm);
+    let mut metered = MeteredRun::new(MachineBudget::for_user(), config.budget.clone());
+
+    let report = translate_arfs(
+        backend.as_ref(),
+        &scheduler,
+        provider.as_ref(),
+        &mut metered,
+        &config.llm.output_language,
+        opts.dry_run,
+    )
+    .await;
+    metered.finish(repo_path);
+    report
+}
+
+async fn translate_arfs(
+    backend: &dyn KnowledgeBackend,
+    scheduler: &Scheduler,
+    provider: &dyn LLMProvider,
+    metered: &mut MeteredRun,
+    language: &str,
+    dry_run: bool,
+) -> Result<TranslateReport> {
+    let mut report = TranslateReport {
+        language: language.to_string(),
+        dry_run,
+        ..Default::default()
+    };
+
+    for path in off_language_arfs(backend, language)? {
+        let Some(mut arf) = backend.read_arf(&path)? else {
+            continue;
+        };
+        if arf.approved {
+            report.approved.push(path);
+            continue;
+        }
+
+        let prompt = build_translation_prompt(std::slice::from_ref(&arf), language);
+        metered.admit(provider.name(), &prompt)?;
+        let result = scheduler
+            .query(provider, &prompt, &ignore_chunks)
+            .await
+            .map_err(anyhow::Error::from)
+            .and_then(|response| {
+                metered.answered(provider.name(), &response);
+                apply_translation(provider.name(), std::slice::from_mut(&mut arf), &response)
+            })
+            .and_then(|()| {
+                if matches_language(std::slice::from_ref(&arf), language) {
+                    Ok(())
+                } else {
+                    anyhow::bail!("{} did not answer in {}", provider.name(), language)
+                }
+            });
+        if let Err(e) = result {
+            report.failed.push(TranslateFailure {
+                path,
+                error: format!("{:#}", e),
+            });
+            continue;
+        }
+
+        if !dry_run {
+            arf.updated_at = Some(Utc::now());
+            backend.write_arf(&path, &arf)?;
+        }
+        report.translated.push(path);
+    }
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::arf::ArfFile;
+    use crate::config::LlmConfig;
+    use crate::error::Error;
+    use crate::storage::MemoryBackend;
+
+    struct Translator;
+
+    #[async_trait::async_trait]
+    impl LLMProvider for Translator {
+        async fn query(&self, _prompt: &str) -> Result<String, Error> {
+            Ok("[[entry]]\n\
+                what = \"Errors are propagated with anyhow\"\n\
+                why = \"It keeps the call sites short and attaches context to each failure\"\n\
+                how = \"Every fallible function returns anyhow::Result with context\"\n"
+                .to_string())
+        }
+
+        fn name(&self) -> &str {
+            "translator"
+        }
+    }
+
+    fn german() -> ArfFile {
+        let mut arf = ArfFile::new(
+            "Fehler werden mit anyhow weitergereicht",
+            "Das hält die Aufrufstellen kurz und fügt jedem Fehler Kontext hinzu",
+            "Jede fehlbare Funktion gibt anyhow::Result zurück und umhüllt Fehler mit context()",
+        );
+        arf.add_file("src/error.rs");
+        arf
+    }
+
+    #[tokio::test]
+    async fn test_translate_arfs() {
+        let backend = MemoryBackend::default();
+        backend.write_arf("patterns/errors.arf", &german()).unwrap();
+        let mut approved = german();
+        approved.approved = true;
+        backend.write_arf("patterns/approved.arf", &approved).unwrap();
+        let scheduler = Scheduler::new(&LlmConfig::default());
+        let mut metered = MeteredRun::new(None, Default::default());
+
+        let report = translate_arfs(&backend, &scheduler, &Translator, &mut metered, "English", true)
+            .await
+            .unwrap();
+        assert_eq!(report.translated, vec!["patterns/errors.arf"]);
+        assert_eq!(report.approved, vec!["patterns/approved.arf"]);
+        assert_eq!(backend.read_arf("patterns/errors.arf").unwrap(), Some(german()));
+
+        translate_arfs(&backend, &scheduler, &Translator, &mut metered, "English", false)
+            .await
+            .unwrap();
+        let arf = backend.read_arf("patterns/errors.arf").unwrap().unwrap();
+        assert_eq!(arf.what, "Errors are propagated with anyhow");
+        assert_eq!(arf.context.files, vec!["src/error.rs"]);
+        assert_eq!(
+            off_language_arfs(&backend, "English").unwrap(),
+            vec!["patterns/approved.arf"]
+        );
+    }
+}
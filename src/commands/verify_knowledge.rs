@@ -0,0 +1,581 @@
+//! `noggin verify-knowledge`: ask providers whether existing ARFs still hold
+//!
+//! Picks the entries with the lowest decayed confidence (see
+//! [`crate::decay`]) among those citing tracked files, sends each with the
+//! current contents of the files it cites, and asks every configured
+//! provider for a verdict. The majority verdict decides the outcome:
+//!
+//! - accurate: confidence is raised and the cited files' scan time is
+//!   refreshed, so decay starts over
+//! - inaccurate: a conflict record with the suggested correction is
+//!   opened in `.noggin/conflicts/` for review; the ARF is left alone
+//! - unsure (or a tie): nothing changes
+//!
+//! Every run's verdicts are saved to `.noggin/reports/`. Provider usage
+//! is capped by the `[budget]` config section like learn.
+
+use crate::arf::ArfFile;
+use crate::commands::learn::UsageSummary;
+use crate::config::Config;
+use crate::conflicts::{contradicting_fields, ConflictRecord};
+use crate::decay::{rank_entries, DecayedEntry};
+use crate::learn::budget::{estimate_tokens, Budget, REPORTS_DIR};
+use crate::learn::ledger::MachineBudget;
+use crate::learn::prompts::build_verification_prompt;
+use crate::learn::scanner::FileToAnalyze;
+use crate::learn::tokens::PromptBudget;
+use crate::llm::configured_providers;
+use crate::llm::parallel::{query_all, Scheduler};
+use crate::lock::KnowledgeLock;
+use crate::policy::NeverSend;
+use crate::storage;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::fs;
+use std::path::Path;
+
+/// Confidence added to an entry each time providers confirm it
+pub const CONFIRMATION_BOOST: f64 = 0.1;
+
+/// Options for `noggin verify-knowledge`
+#[derive(Debug, Clone)]
+pub struct VerifyKnowledgeOptions {
+    /// Entries to check, lowest confidence first
+    pub sample: usize,
+    /// Query providers but write nothing
+    pub dry_run: bool,
+    pub json: bool,
+}
+
+/// A provider's judgement of one entry
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Verdict {
+    Accurate,
+    Inaccurate,
+    Unsure,
+}
+
+/// Replacement fields suggested for an inaccurate entry
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Correction {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub what: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub why: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub how: Option<String>,
+}
+
+/// A parsed provider answer
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProviderVerdict {
+    pub model: String,
+    pub verdict: Verdict,
+    #[serde(default)]
+    pub reason: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub correction: Option<Correction>,
+}
+
+/// What happened to one entry
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EntryVerification {
+    /// ARF path relative to .noggin/
+    pub path: String,
+    pub what: String,
+    pub outcome: Verdict,
+    /// Effective confidence before verification
+    pub effective_confidence: f64,
+    /// Confidence written back after a confirmation
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub new_confidence: Option<f64>,
+    /// Conflict record opened for review, relative to .noggin/
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub review: Option<String>,
+    pub verdicts: Vec<ProviderVerdict>,
+}
+
+/// Outcome of a verification run
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct VerifyKnowledgeReport {
+    pub dry_run: bool,
+    pub entries: Vec<EntryVerification>,
+    /// Selected entries left unchecked because a budget cap was reached
+    pub skipped: usize,
+    pub usage: UsageSummary,
+    /// Verdicts saved under .noggin/reports/, relative to .noggin/
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub report_path: Option<String>,
+    pub warnings: Vec<String>,
+}
+
+/// Verdicts as saved under `.noggin/reports/`
+#[derive(Debug, Serialize)]
+struct SavedVerification<'a> {
+    checked_at: DateTime<Utc>,
+    entries: &'a [EntryVerification],
+}
+
+pub async fn verify_knowledge_command(opts: VerifyKnowledgeOptions) -> Result<()> {
+    let repo_path = env::current_dir()?;
+    let report = run_verify_knowledge(&repo_path, &opts).await?;
+
+    if opts.json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+        return Ok(());
+    }
+
+    print_report(&report);
+    Ok(())
+}
+
+/// Verify up to `opts.sample` entries against the code in `repo_path`
+pub async fn run_verify_knowledge(
+    repo_path: &Path,
+    opts: &VerifyKnowledgeOptions,
+) -> Result<VerifyKnowledgeReport> {
+    let noggin_path = repo_path.join(".noggin");
+    if !noggin_path.exists() {
+        anyhow::bail!(".noggin/ directory not found. Run 'noggin init' first.");
+    }
+    let _lock = if opts.dry_run {
+        None
+    } else {
+        Some(KnowledgeLock::acquire(&noggin_path, "verify-knowledge")?)
+    };
+
+    let config = Config::load(&noggin_path).context("Failed to load config")?;
+    let backend = storage::open(&noggin_path, &config.storage);
+    let mut manifest = backend.load_manifest().context("Failed to load manifest")?;
+    let never_send = NeverSend::from_config(&config.privacy)?;
+    let prompt_budget = PromptBudget::from_config(&config);
+    let providers = configured_providers(&config.llm)?;
+    let scheduler = Scheduler::new(&config.llm);
+
+    let candidates = verifiable(
+        rank_entries(repo_path, backend.as_ref(), &manifest, &config.decay, Utc::now())?,
+        opts.sample,
+    );
+
+    let mut report = VerifyKnowledgeReport {
+        dry_run: opts.dry_run,
+        ..Default::default()
+    };
+    let mut budget = Budget::new(config.budget.clone());
+    // Daily caps shared with runs in other repositories on this machine
+    let machine = MachineBudget::for_user();
+    let provider_names: Vec<&str> = providers.iter().map(|p| p.name()).collect();
+
+    for (idx, candidate) in candidates.iter().enumerate() {
+        let Some(mut arf) = backend.read_arf(&candidate.path)? else {
+            continue;
+        };
+
+        let files: Vec<FileToAnalyze> = candidate
+            .files
+            .iter()
+            .filter_map(|path| {
+                let metadata = repo_path.join(path).metadata().ok()?;
+                Some(FileToAnalyze {
+                    path: path.clone(),
+                    hash: manifest.get_file_hash(path).unwrap_or_default().to_string(),
+                    size: metadata.len(),
+                    is_new: false,
+                    is_changed: candidate.changed_files.contains(path),
+                })
+            })
+            .collect();
+        let (prompt, _) =
+            build_verification_prompt(repo_path, &arf, &files, &never_send, &prompt_budget);
+
+        let prompt_tokens = estimate_tokens(&prompt);
+        let over_daily = machine
+            .as_ref()
+            .and_then(|machine| machine.check(&budget, prompt_tokens, &provider_names));
+        if budget.check(prompt_tokens, providers.len()).is_some() || over_daily.is_some() {
+            report.skipped = candidates.len() - idx;
+            break;
+        }
+
+        let result = query_all(&scheduler, &providers, &prompt).await;
+        for provider in &providers {
+            let response_tokens = result
+                .as_ref()
+                .ok()
+                .and_then(|r| r.successes.iter().find(|s| s.model == provider.name()))
+                .map(|s| estimate_tokens(&s.response))
+                .unwrap_or(0);
+            budget.record_provider(provider.name(), prompt_tokens, response_tokens);
+        }
+
+        let mut verdicts = Vec::new();
+        match result {
+            Ok(parallel_result) => {
+                for failure in parallel_result.failures {
+                    report.warnings.push(format!(
+                        "{} failed for {}: {}",
+                        failure.model, candidate.path, failure.error
+                    ));
+                }
+                for success in parallel_result.successes {
+                    match parse_verdict(&success.model, &success.response) {
+                        Ok(verdict) => verdicts.push(verdict),
+                        Err(e) => report.warnings.push(format!(
+                            "Failed to parse {} verdict for {}: {:#}",
+                            success.model, candidate.path, e
+                        )),
+                    }
+                }
+            }
+            Err(e) => report.warnings.push(format!(
+                "All providers failed for {}: {}",
+                candidate.path, e
+            )),
+        }
+
+        let mut entry = EntryVerification {
+            path: candidate.path.clone(),
+            what: arf.what.clone(),
+            outcome: combine(&verdicts),
+            effective_confidence: candidate.effective,
+            new_confidence: None,
+            review: None,
+            verdicts,
+        };
+
+        match entry.outcome {
+            Verdict::Accurate => {
+                let confidence = confirmed_confidence(arf.confidence);
+                entry.new_confidence = Some(confidence);
+                if !opts.dry_run {
+                    arf.confidence = Some(confidence);
+                    backend.write_arf(&candidate.path, &arf)?;
+                    // Unchanged files count as re-verified
+                    for file in &candidate.files {
+                        if candidate.changed_files.contains(file) {
+                            continue;
+                        }
+                        if let Some(tracked) = manifest.get_file_mut(file) {
+                            tracked.last_scanned = Utc::now();
+                        }
+                    }
+                }
+            }
+            Verdict::Inaccurate => {
+                let correction = entry
+                    .verdicts
+                    .iter()
+                    .find(|v| v.verdict == Verdict::Inaccurate)
+                    .and_then(|v| v.correction.clone())
+                    .unwrap_or_default();
+                let proposed = apply_correction(&arf, &correction);
+                let mut fields = contradicting_fields(&arf, &proposed);
+                if fields.is_empty() {
+                    fields.push("how".to_string());
+                }
+                let record =
+                    ConflictRecord::new(candidate.path.clone(), fields, arf.clone(), proposed);
+                if !opts.dry_run {
+                    record.save(&noggin_path)?;
+                }
+                entry.review = Some(record.relative_path());
+            }
+            Verdict::Unsure => {}
+        }
+
+        report.entries.push(entry);
+    }
+
+    if !opts.dry_run && !report.entries.is_empty() {
+        backend
+            .save_manifest(&manifest)
+            .context("Failed to save manifest")?;
+        let saved = SavedVerification {
+            checked_at: Utc::now(),
+            entries: &report.entries,
+        };
+        let dir = noggin_path.join(REPORTS_DIR);
+        fs::create_dir_all(&dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+        let path = dir.join(format!(
+            "verify-{}.json",
+            saved.checked_at.format("%Y%m%dT%H%M%S%3fZ")
+        ));
+        fs::write(&path, serde_json::to_string_pretty(&saved)?)
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+        let rel = path.strip_prefix(&noggin_path).unwrap_or(&path);
+        report.report_path = Some(rel.to_string_lossy().into_owned());
+    }
+
+    if let Some(machine) = &machine {
+        machine.record_or_warn(repo_path, &budget);
+    }
+
+    report.usage.requests = budget.requests();
+    report.usage.tokens = budget.tokens();
+    report.usage.estimated_cost_usd = budget.estimated_cost();
+    report.usage.providers = budget.by_provider().clone();
+
+    Ok(report)
+}
+
+/// Parse a provider's TOML verdict, tolerating a surrounding code fence
+pub fn parse_verdict(model: &str, response: &str) -> Result<ProviderVerdict> {
+    #[derive(Deserialize)]
+    struct Answer {
+        verdict: String,
+        #[serde(default)]
+        reason: String,
+        #[serde(default)]
+        correction: Option<Correction>,
+    }
+
+    let body = strip_code_fence(response);
+    let answer: Answer = toml::from_str(body).context("response is not a TOML verdict")?;
+    let verdict = match answer.verdict.trim().to_lowercase().as_str() {
+        "accurate" => Verdict::Accurate,
+        "inaccurate" => Verdict::Inaccurate,
+        "unsure" => Verdict::Unsure,
+        other => anyhow::bail!("unknown verdict '{}'", other),
+    };
+
+    Ok(ProviderVerdict {
+        model: model.to_string(),
+        verdict,
+        reason: answer.reason.trim().to_string(),
+        correction: answer.correction.filter(|_| verdict == Verdict::Inaccurate),
+    })
+}
+
+/// The text inside the first ``` fence, or all of it if there is none
+fn strip_code_fence(response: &str) -> &str {
+    let trimmed = response.trim();
+    let Some(start) = trimmed.find("```") else {
+        return trimmed;
+    };
+    let after = &trimmed[start + 3..];
+    // Skip a language tag on the opening fence
+    let body = after.split_once('\n').map_or(after, |(_, rest)| rest);
+    body.find("```").map_or(body, |end| &body[..end]).trim()
+}
+
+/// Majority verdict; a tie or no verdicts at all is unsure
+pub fn combine(verdicts: &[ProviderVerdict]) -> Verdict {
+    let count = |verdict| verdicts.iter().filter(|v| v.verdict == verdict).count();
+    let accurate = count(Verdict::Accurate);
+    let inaccurate = count(Verdict::Inaccurate);
+    if accurate > inaccurate {
+        Verdict::Accurate
+    } else if inaccurate > accurate {
+        Verdict::Inaccurate
+    } else {
+        Verdict::Unsure
+    }
+}
+
+/// Confidence after a confirmation; an entry without one counts as fully
+/// confident
+pub fn confirmed_confidence(confidence: Option<f64>) -> f64 {
+    confidence.map_or(1.0, |c| (c + CONFIRMATION_BOOST).min(1.0))
+}
+
+/// `arf` with the non-empty fields of `correction` applied
+pub fn apply_correction(arf: &ArfFile, correction: &Correction) -> ArfFile {
+    let mut proposed = arf.clone();
+    proposed.approved = false;
+    for (field, value) in [
+        (&mut proposed.what, &correction.what),
+        (&mut proposed.why, &correction.why),
+        (&mut proposed.how, &correction.how),
+    ] {
+        if let Some(value) = value.as_deref().map(str::trim).filter(|v| !v.is_empty()) {
+            *field = value.to_string();
+        }
+    }
+    proposed
+}
+
+fn print_report(report: &VerifyKnowledgeReport) {
+    if report.entries.is_empty() && report.skipped == 0 {
+        println!("No entries to verify.");
+        return;
+    }
+
+    println!();
+    if report.dry_run {
+        println!("=== Verification Dry Run (nothing written) ===");
+    } else {
+        println!("=== Verification Complete ===");
+    }
+
+    for entry in &report.entries {
+        let label = match entry.outcome {
+            Verdict::Accurate => "confirmed".green(),
+            Verdict::Inaccurate => "contradicted".red(),
+            Verdict::Unsure => "unsure".yellow(),
+        };
+        println!("  {:<12} {}", label, entry.path);
+        if let Some(confidence) = entry.new_confidence {
+            println!(
+                "               confidence {:.2} -> {:.2}",
+                entry.effective_confidence, confidence
+            );
+        }
+        if let Some(review) = &entry.review {
+            println!("               review .noggin/{}", review);
+        }
+        for verdict in &entry.verdicts {
+            if !verdict.reason.is_empty() {
+                println!(
+                    "               {}: {}",
+                    verdict.model.dimmed(),
+                    verdict.reason
+                );
+            }
+        }
+    }
+
+    let count = |outcome| {
+        report
+            .entries
+            .iter()
+            .filter(|e| e.outcome == outcome)
+            .count()
+    };
+    println!();
+    println!(
+        "{} confirmed, {} contradicted, {} unsure",
+        count(Verdict::Accurate),
+        count(Verdict::Inaccurate),
+        count(Verdict::Unsure)
+    );
+    if report.skipped > 0 {
+        println!("{} entries skipped: budget cap reached", report.skipped);
+    }
+    println!(
+        "Provider usage: {} requests, ~{} tokens, ~${:.2}",
+        report.usage.requests, report.usage.tokens, report.usage.estimated_cost_usd
+    );
+    if let Some(path) = &report.report_path {
+        println!("Verdicts saved to .noggin/{}", path);
+    }
+
+    for warning in &report.warnings {
+        println!("  {} {}", "warning:".yellow(), warning);
+    }
+}
+
+/// The first `sample` of `ranked` that cite tracked files; entries with
+/// none have no source to be checked against
+fn verifiable(ranked: Vec<DecayedEntry>, sample: usize) -> Vec<DecayedEntry> {
+    ranked
+        .into_iter()
+        .filter(|entry| !entry.files.is_empty())
+        .take(sample)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn verdict(verdict: Verdict) -> ProviderVerdict {
+        ProviderVerdict {
+            model: "test".to_string(),
+            verdict,
+            reason: String::new(),
+            correction: None,
+        }
+    }
+
+    #[test]
+    fn test_parse_verdict() {
+        let response = "```toml\nverdict = \"Inaccurate\"\nreason = \"Pool size is now 20\"\n\n[correction]\nhow = \"Pool of 20\"\n```";
+        let parsed = parse_verdict("claude", response).unwrap();
+        assert_eq!(parsed.verdict, Verdict::Inaccurate);
+        assert_eq!(parsed.reason, "Pool size is now 20");
+        assert_eq!(
+            parsed.correction.unwrap().how.as_deref(),
+            Some("Pool of 20")
+        );
+
+        let parsed = parse_verdict(
+            "claude",
+            "verdict = \"accurate\"\n[correction]\nwhat = \"x\"",
+        )
+        .unwrap();
+        assert_eq!(parsed.verdict, Verdict::Accurate);
+        assert!(parsed.correction.is_none());
+
+        assert!(parse_verdict("claude", "verdict = \"maybe\"").is_err());
+        assert!(parse_verdict("claude", "Looks fine to me.").is_err());
+    }
+
+    #[test]
+    fn test_combine() {
+        assert_eq!(combine(&[]), Verdict::Unsure);
+        assert_eq!(
+            combine(&[verdict(Verdict::Accurate), verdict(Verdict::Unsure)]),
+            Verdict::Accurate
+        );
+        assert_eq!(
+            combine(&[verdict(Verdict::Accurate), verdict(Verdict::Inaccurate)]),
+            Verdict::Unsure
+        );
+        assert_eq!(
+            combine(&[
+                verdict(Verdict::Inaccurate),
+                verdict(Verdict::Inaccurate),
+                verdict(Verdict::Accurate)
+            ]),
+            Verdict::Inaccurate
+        );
+    }
+
+    #[test]
+    fn test_verifiable_skips_entries_without_files() {
+        let entry = |path: &str, files: &[&str]| DecayedEntry {
+            path: path.to_string(),
+            what: path.to_string(),
+            confidence: 1.0,
+            effective: 0.5,
+            verified_at: None,
+            files: files.iter().map(|f| f.to_string()).collect(),
+            changed_files: Vec::new(),
+        };
+        let ranked = vec![
+            entry("facts/a.arf", &[]),
+            entry("facts/b.arf", &["src/b.rs"]),
+            entry("facts/c.arf", &["src/c.rs"]),
+        ];
+        let picked: Vec<String> = verifiable(ranked, 1).into_iter().map(|e| e.path).collect();
+        assert_eq!(picked, vec!["facts/b.arf"]);
+    }
+
+    #[test]
+    fn test_confirmed_confidence() {
+        assert!((confirmed_confidence(Some(0.5)) - 0.6).abs() < 1e-9);
+        assert_eq!(confirmed_confidence(Some(0.95)), 1.0);
+        assert_eq!(confirmed_confidence(None), 1.0);
+    }
+
+    #[test]
+    fn test_apply_correction() {
+        let mut arf = ArfFile::new("Connection pooling", "Reduces overhead", "Pool of 10");
+        arf.approved = true;
+        let correction = Correction {
+            what: None,
+            why: Some("  ".to_string()),
+            how: Some("Pool of 20".to_string()),
+        };
+
+        let proposed = apply_correction(&arf, &correction);
+        assert_eq!(proposed.what, "Connection pooling");
+        assert_eq!(proposed.why, "Reduces overhead");
+        assert_eq!(proposed.how, "Pool of 20");
+        assert!(!proposed.approved);
+    }
+}
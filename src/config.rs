@@ -1,49 +1,1400 @@
 use crate::git::scoring::ScoringConfig;
+use crate::llm::custom::OutputFormat;
+use crate::learn::writer::FileNaming;
+use crate::llm::chaos::ChaosConfig;
+use crate::llm::retry::RetryPolicy;
+use crate::manifest::PathCase;
+use crate::storage::StorageBackend;
+use crate::synthesis::merger::Clustering;
+use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Config {
     #[serde(default)]
     pub scoring: ScoringConfig,
     #[serde(default)]
     pub llm: LlmConfig,
+    #[serde(default)]
+    pub budget: BudgetConfig,
+    #[serde(default)]
+    pub maintain: MaintainConfig,
+    #[serde(default)]
+    pub notes: NotesConfig,
+    #[serde(default)]
+    pub history: HistoryConfig,
+    #[serde(default)]
+    pub metrics: MetricsConfig,
+    #[serde(default)]
+    pub size: SizeConfig,
+    #[serde(default)]
+    pub issues: IssuesConfig,
+    #[serde(default)]
+    pub people: PeopleConfig,
+    #[serde(default)]
+    pub privacy: PrivacyConfig,
+    #[serde(default)]
+    pub generated: GeneratedConfig,
+    #[serde(default)]
+    pub manifest: ManifestConfig,
+    #[serde(default)]
+    pub submodules: SubmodulesConfig,
+    #[serde(default)]
+    pub glossary: GlossaryConfig,
+    #[serde(default)]
+    pub retention: RetentionConfig,
+    #[serde(default)]
+    pub prompts: PromptsConfig,
+    #[serde(default)]
+    pub decay: DecayConfig,
+    #[serde(default)]
+    pub synthesis: SynthesisConfig,
+    #[serde(default)]
+    pub writer: WriterConfig,
+    #[serde(default)]
+    pub storage: StorageConfig,
+}
+
+impl Config {
+    /// Load `.noggin/config.toml`, returns defaults if the file doesn't exist
+    pub fn load(noggin_path: &Path) -> Result<Self> {
+        let path = noggin_path.join("config.toml");
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        Self::from_file(&path)
+    }
+
+    /// Load a config file at an arbitrary path, e.g. an experiment variant
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let contents = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read config from {}", path.display()))?;
+
+        toml::from_str(&contents)
+            .with_context(|| format!("Failed to parse config from {}", path.display()))
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LlmConfig {
+    /// Providers queried during analysis, in order. A provider whose
+    /// section sets `enabled = false` is skipped.
+    #[serde(default = "default_providers")]
+    pub providers: Vec<String>,
+    /// Language ARF prose is written in; prompts ask for it and responses
+    /// in another language are caught before synthesis
+    #[serde(default = "default_output_language")]
+    pub output_language: String,
+    /// Translate off-language responses with another provider instead of
+    /// discarding them
+    #[serde(default)]
+    pub translate_responses: bool,
+    /// Requests in flight at once per provider, unless its section sets
+    /// its own `max_concurrency`
+    #[serde(default = "default_max_concurrency")]
+    pub max_concurrency: usize,
+    /// Times a rate-limited request is retried after waiting out the limit
+    #[serde(default = "default_rate_limit_retries")]
+    pub rate_limit_retries: u32,
+    /// Pause after a rate limit that doesn't say how long to wait
+    #[serde(default = "default_rate_limit_wait_secs")]
+    pub rate_limit_wait_secs: u64,
+    /// Retries for transient failures, unless a provider sets its own
+    #[serde(default)]
+    pub retry: RetryPolicy,
+    /// Fault probabilities used when `NOGGIN_CHAOS` is set
+    #[serde(default)]
+    pub chaos: ChaosConfig,
     #[serde(default)]
     pub claude: ClaudeConfig,
+    #[serde(default)]
+    pub codex: CodexConfig,
+    #[serde(default)]
+    pub gemini: GeminiConfig,
+    #[serde(default)]
+    pub openai: OpenAiConfig,
+    /// Command-backed providers, listed in `providers` by name
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub custom: Vec<CustomProviderConfig>,
+}
+
+fn default_providers() -> Vec<String> {
+    crate::llm::DEFAULT_PROVIDERS.iter().map(|name| name.to_string()).collect()
+}
+
+fn default_output_language() -> String {
+    "English".to_string()
+}
+
+fn default_max_concurrency() -> usize {
+    2
+}
+
+fn default_rate_limit_retries() -> u32 {
+    3
+}
+
+fn default_rate_limit_wait_secs() -> u64 {
+    60
 }
 
 impl Default for LlmConfig {
     fn default() -> Self {
         Self {
+            providers: default_providers(),
+            output_language: default_output_language(),
+            translate_responses: false,
+            max_concurrency: default_max_concurrency(),
+            rate_limit_retries: default_rate_limit_retries(),
+            rate_limit_wait_secs: default_rate_limit_wait_secs(),
+            retry: RetryPolicy::default(),
+            chaos: ChaosConfig::default(),
             claude: ClaudeConfig::default(),
+            codex: CodexConfig::default(),
+            gemini: GeminiConfig::default(),
+            openai: OpenAiConfig::default(),
+            custom: Vec::new(),
         }
     }
 }
 
+impl LlmConfig {
+    /// Whether the named provider's section leaves it enabled. Unknown
+    /// names count as enabled so the registry can report them.
+    pub fn is_enabled(&self, name: &str) -> bool {
+        match name {
+            "claude" => self.claude.enabled,
+            "codex" => self.codex.enabled,
+            "gemini" => self.gemini.enabled,
+            "openai" => self.openai.enabled,
+            other => self.custom_provider(other).is_none_or(|c| c.enabled),
+        }
+    }
+
+    /// The binary configured for a built-in CLI provider, if set
+    pub fn binary(&self, name: &str) -> Option<&Path> {
+        match name {
+            "claude" => self.claude.binary.as_deref(),
+            "codex" => self.codex.binary.as_deref(),
+            "gemini" => self.gemini.binary.as_deref(),
+            _ => None,
+        }
+    }
+
+    /// The named provider's `max_prompt_tokens`, if set
+    pub fn max_prompt_tokens(&self, name: &str) -> Option<usize> {
+        match name {
+            "claude" => self.claude.max_prompt_tokens,
+            "codex" => self.codex.max_prompt_tokens,
+            "gemini" => self.gemini.max_prompt_tokens,
+            "openai" => self.openai.max_prompt_tokens,
+            other => self.custom_provider(other).and_then(|c| c.max_prompt_tokens),
+        }
+    }
+
+    /// Requests the named provider may have in flight at once
+    pub fn max_concurrency(&self, name: &str) -> usize {
+        let own = match name {
+            "claude" => self.claude.max_concurrency,
+            "codex" => self.codex.max_concurrency,
+            "gemini" => self.gemini.max_concurrency,
+            "openai" => self.openai.max_concurrency,
+            other => self.custom_provider(other).and_then(|c| c.max_concurrency),
+        };
+        own.unwrap_or(self.max_concurrency).max(1)
+    }
+
+    /// The retry policy for the named provider: its own `retry` table,
+    /// else `llm.retry` (with a legacy `max_retries` applied)
+    pub fn retry_policy(&self, name: &str) -> RetryPolicy {
+        let (own, max_retries) = match name {
+            "claude" => (&self.claude.retry, self.claude.max_retries),
+            "codex" => (&self.codex.retry, None),
+            "gemini" => (&self.gemini.retry, None),
+            "openai" => (&self.openai.retry, self.openai.max_retries),
+            other => match self.custom_provider(other) {
+                Some(custom) => (&custom.retry, None),
+                None => (&None, None),
+            },
+        };
+        match (own, max_retries) {
+            (Some(policy), _) => policy.clone(),
+            (None, Some(max_attempts)) => RetryPolicy {
+                max_attempts,
+                ..self.retry.clone()
+            },
+            (None, None) => self.retry.clone(),
+        }
+    }
+
+    /// The `[[llm.custom]]` entry with this name
+    pub fn custom_provider(&self, name: &str) -> Option<&CustomProviderConfig> {
+        self.custom.iter().find(|c| c.name == name)
+    }
+
+    /// Built-in provider names followed by custom ones
+    pub fn provider_names(&self) -> Vec<String> {
+        crate::llm::PROVIDER_NAMES
+            .iter()
+            .map(|name| name.to_string())
+            .chain(self.custom.iter().map(|c| c.name.clone()))
+            .collect()
+    }
+}
+
+fn default_provider_enabled() -> bool {
+    true
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ClaudeConfig {
+    #[serde(default = "default_provider_enabled")]
+    pub enabled: bool,
+    /// Prompt size this provider accepts; defaults to `prompts.max_tokens`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_prompt_tokens: Option<usize>,
+    /// Requests in flight at once; defaults to `llm.max_concurrency`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_concurrency: Option<usize>,
+    /// Retries for transient failures; defaults to `llm.retry`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry: Option<RetryPolicy>,
     #[serde(default = "default_timeout")]
     pub timeout_secs: u64,
-    #[serde(default = "default_max_retries")]
-    pub max_retries: u32,
+    /// Absolute path to the CLI, recorded by `noggin init` and `noggin
+    /// doctor`; searched for on PATH and in common install locations
+    /// when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub binary: Option<PathBuf>,
+    /// Older spelling of `retry.max_attempts`, used when `retry` is unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_retries: Option<u32>,
 }
 
 fn default_timeout() -> u64 {
     30
 }
 
-fn default_max_retries() -> u32 {
+impl Default for ClaudeConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_provider_enabled(),
+            max_prompt_tokens: None,
+            max_concurrency: None,
+            retry: None,
+            timeout_secs: default_timeout(),
+            binary: None,
+            max_retries: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CodexConfig {
+    #[serde(default = "default_provider_enabled")]
+    pub enabled: bool,
+    /// Prompt size this provider accepts; defaults to `prompts.max_tokens`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_prompt_tokens: Option<usize>,
+    /// Requests in flight at once; defaults to `llm.max_concurrency`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_concurrency: Option<usize>,
+    /// Retries for transient failures; defaults to `llm.retry`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry: Option<RetryPolicy>,
+    #[serde(default = "default_codex_timeout")]
+    pub timeout_secs: u64,
+    /// Absolute path to the CLI, recorded by `noggin init` and `noggin
+    /// doctor`; searched for on PATH and in common install locations
+    /// when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub binary: Option<PathBuf>,
+}
+
+fn default_codex_timeout() -> u64 {
+    120
+}
+
+impl Default for CodexConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_provider_enabled(),
+            max_prompt_tokens: None,
+            max_concurrency: None,
+            retry: None,
+            timeout_secs: default_codex_timeout(),
+            binary: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GeminiConfig {
+    #[serde(default = "default_provider_enabled")]
+    pub enabled: bool,
+    /// Prompt size this provider accepts; defaults to `prompts.max_tokens`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_prompt_tokens: Option<usize>,
+    /// Requests in flight at once; defaults to `llm.max_concurrency`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_concurrency: Option<usize>,
+    /// Retries for transient failures; defaults to `llm.retry`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry: Option<RetryPolicy>,
+    #[serde(default = "default_gemini_timeout")]
+    pub timeout_secs: u64,
+    /// Absolute path to the CLI, recorded by `noggin init` and `noggin
+    /// doctor`; searched for on PATH and in common install locations
+    /// when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub binary: Option<PathBuf>,
+}
+
+fn default_gemini_timeout() -> u64 {
+    300
+}
+
+impl Default for GeminiConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_provider_enabled(),
+            max_prompt_tokens: None,
+            max_concurrency: None,
+            retry: None,
+            timeout_secs: default_gemini_timeout(),
+            binary: None,
+        }
+    }
+}
+
+/// Settings for the `openai` provider. The API key is better left to the
+/// `OPENAI_API_KEY` environment variable than written here.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OpenAiConfig {
+    #[serde(default = "default_provider_enabled")]
+    pub enabled: bool,
+    /// Prompt size this provider accepts; defaults to `prompts.max_tokens`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_prompt_tokens: Option<usize>,
+    /// Requests in flight at once; defaults to `llm.max_concurrency`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_concurrency: Option<usize>,
+    /// Retries for transient failures; defaults to `llm.retry`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry: Option<RetryPolicy>,
+    #[serde(default = "default_openai_model")]
+    pub model: String,
+    #[serde(default = "default_openai_base_url")]
+    pub base_url: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_key: Option<String>,
+    #[serde(default = "default_openai_timeout")]
+    pub timeout_secs: u64,
+    /// Older spelling of `retry.max_attempts`, used when `retry` is unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_retries: Option<u32>,
+}
+
+fn default_openai_model() -> String {
+    "gpt-4o-mini".to_string()
+}
+
+fn default_openai_base_url() -> String {
+    "https://api.openai.com/v1".to_string()
+}
+
+fn default_openai_timeout() -> u64 {
+    60
+}
+
+impl Default for OpenAiConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_provider_enabled(),
+            max_prompt_tokens: None,
+            max_concurrency: None,
+            retry: None,
+            model: default_openai_model(),
+            base_url: default_openai_base_url(),
+            api_key: None,
+            timeout_secs: default_openai_timeout(),
+            max_retries: None,
+        }
+    }
+}
+
+/// A provider that runs a local command (see [`crate::llm::custom`])
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CustomProviderConfig {
+    pub name: String,
+    /// Command template using `{prompt_file}` or `{prompt}`; without
+    /// either, the prompt is written to stdin
+    pub command: String,
+    #[serde(default = "default_provider_enabled")]
+    pub enabled: bool,
+    /// Prompt size this provider accepts; defaults to `prompts.max_tokens`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_prompt_tokens: Option<usize>,
+    /// Requests in flight at once; defaults to `llm.max_concurrency`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_concurrency: Option<usize>,
+    /// Retries for transient failures; defaults to `llm.retry`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry: Option<RetryPolicy>,
+    #[serde(default)]
+    pub output: OutputFormat,
+    /// For JSON output: pointer to the response string, e.g. "/result"
+    #[serde(default)]
+    pub json_pointer: String,
+    /// Regex whose first capture group becomes the response
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub extract: Option<String>,
+    #[serde(default = "default_custom_timeout")]
+    pub timeout_secs: u64,
+}
+
+fn default_custom_timeout() -> u64 {
+    300
+}
+
+/// Prompt size limits, in estimated tokens (see [`crate::learn::tokens`]).
+///
+/// File batches larger than `max_tokens` are split across prompts; each
+/// file's contents are cut to `max_file_tokens`. No more than
+/// `max_file_bytes` of a file is read, however large it is.
+///
+/// With `commit_diffs`, commit prompts also carry each commit's unified
+/// diff: up to `max_diff_files` files, each cut to `max_diff_lines` lines.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PromptsConfig {
+    #[serde(default = "default_prompt_max_tokens")]
+    pub max_tokens: usize,
+    #[serde(default = "default_prompt_max_file_tokens")]
+    pub max_file_tokens: usize,
+    #[serde(default = "default_prompt_max_file_bytes")]
+    pub max_file_bytes: u64,
+    #[serde(default)]
+    pub commit_diffs: bool,
+    #[serde(default = "default_prompt_max_diff_files")]
+    pub max_diff_files: usize,
+    #[serde(default = "default_prompt_max_diff_lines")]
+    pub max_diff_lines: usize,
+}
+
+fn default_prompt_max_tokens() -> usize {
+    100_000
+}
+
+fn default_prompt_max_file_tokens() -> usize {
+    8_000
+}
+
+fn default_prompt_max_file_bytes() -> u64 {
+    1024 * 1024
+}
+
+fn default_prompt_max_diff_files() -> usize {
+    10
+}
+
+fn default_prompt_max_diff_lines() -> usize {
+    40
+}
+
+impl Default for PromptsConfig {
+    fn default() -> Self {
+        Self {
+            max_tokens: default_prompt_max_tokens(),
+            max_file_tokens: default_prompt_max_file_tokens(),
+            max_file_bytes: default_prompt_max_file_bytes(),
+            commit_diffs: false,
+            max_diff_files: default_prompt_max_diff_files(),
+            max_diff_lines: default_prompt_max_diff_lines(),
+        }
+    }
+}
+
+/// Hard caps on provider usage for a single learn run.
+///
+/// Every cap is optional; an unset cap is unlimited.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BudgetConfig {
+    /// Maximum provider requests (one prompt sent to one provider)
+    #[serde(default)]
+    pub max_requests: Option<u32>,
+    /// Maximum estimated tokens (prompts plus responses)
+    #[serde(default)]
+    pub max_tokens: Option<u64>,
+    /// Maximum wall-clock time for the run, in seconds
+    #[serde(default)]
+    pub max_time_secs: Option<u64>,
+    /// Maximum estimated spend, in USD
+    #[serde(default)]
+    pub max_cost_usd: Option<f64>,
+    /// Blended price used to estimate spend from token counts
+    #[serde(default = "default_cost_per_million_tokens")]
+    pub cost_per_million_tokens: f64,
+    /// Per-provider prices per million tokens, by provider name; others
+    /// use `cost_per_million_tokens`
+    #[serde(default)]
+    pub provider_costs: BTreeMap<String, f64>,
+    /// Response tokens set aside for each request when its prompt is
+    /// admitted, counted against the caps until the real response arrives
+    #[serde(default = "default_reserve_response_tokens")]
+    pub reserve_response_tokens: u64,
+}
+
+fn default_cost_per_million_tokens() -> f64 {
+    5.0
+}
+
+fn default_reserve_response_tokens() -> u64 {
+    2_000
+}
+
+impl Default for BudgetConfig {
+    fn default() -> Self {
+        Self {
+            max_requests: None,
+            max_tokens: None,
+            max_time_secs: None,
+            max_cost_usd: None,
+            cost_per_million_tokens: default_cost_per_million_tokens(),
+            provider_costs: BTreeMap::new(),
+            reserve_response_tokens: default_reserve_response_tokens(),
+        }
+    }
+}
+
+/// Git notes linking processed commits to their ARFs
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NotesConfig {
+    /// Write a note on each processed significant commit
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_notes_ref")]
+    pub notes_ref: String,
+}
+
+fn default_notes_ref() -> String {
+    crate::git::notes::DEFAULT_NOTES_REF.to_string()
+}
+
+impl Default for NotesConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            notes_ref: default_notes_ref(),
+        }
+    }
+}
+
+/// Which refs learn and status walk for commit history, and how learn
+/// groups the commits it analyzes
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryConfig {
+    /// Branches or revspecs to walk, e.g. `["main", "release/2.x"]`;
+    /// empty walks HEAD only
+    #[serde(default)]
+    pub refs: Vec<String>,
+    /// Also walk every local and remote-tracking branch
+    #[serde(default)]
+    pub all_branches: bool,
+    /// Analyze commits one release at a time, as `learn --by-release` does
+    #[serde(default)]
+    pub group_by_release: bool,
+    /// Glob selecting release tags
+    #[serde(default = "default_release_tags")]
+    pub release_tags: String,
+}
+
+fn default_release_tags() -> String {
+    "v*".to_string()
+}
+
+impl Default for HistoryConfig {
+    fn default() -> Self {
+        Self {
+            refs: Vec::new(),
+            all_branches: false,
+            group_by_release: false,
+            release_tags: default_release_tags(),
+        }
+    }
+}
+
+/// Entry-count budgets per knowledge category.
+///
+/// A category over budget is flagged by `status`; `noggin consolidate`
+/// proposes merges to bring it back down. `max_new_per_run` and
+/// `anomaly_factor` guard a single learn run against a response that
+/// floods the knowledge base with junk.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SizeConfig {
+    /// Budget for categories without an override
+    #[serde(default = "default_max_entries")]
+    pub max_entries: usize,
+    /// Per-category overrides, e.g. `{ bugs = 500 }`
+    #[serde(default)]
+    pub categories: BTreeMap<String, usize>,
+    /// Entries whose `what` differs by fewer than this many edits are
+    /// proposed for merging
+    #[serde(default = "default_merge_distance")]
+    pub merge_distance: usize,
+    /// New ARF files one learn run may add; the rest are held in
+    /// `.noggin/pending/` for review. 0 means no cap.
+    #[serde(default = "default_max_new_per_run")]
+    pub max_new_per_run: usize,
+    /// Warn when a run produces this many times the average entries of
+    /// earlier runs. 0 disables the warning.
+    #[serde(default = "default_anomaly_factor")]
+    pub anomaly_factor: f64,
+}
+
+fn default_max_entries() -> usize {
+    200
+}
+
+fn default_max_new_per_run() -> usize {
+    200
+}
+
+fn default_anomaly_factor() -> f64 {
+    10.0
+}
+
+fn default_merge_distance() -> usize {
+    8
+}
+
+impl SizeConfig {
+    /// Entry budget for `category`
+    pub fn limit(&self, category: &str) -> usize {
+        self.categories
+            .get(category)
+            .copied()
+            .unwrap_or(self.max_entries)
+    }
+}
+
+impl Default for SizeConfig {
+    fn default() -> Self {
+        Self {
+            max_entries: default_max_entries(),
+            categories: BTreeMap::new(),
+            merge_distance: default_merge_distance(),
+            max_new_per_run: default_max_new_per_run(),
+            anomaly_factor: default_anomaly_factor(),
+        }
+    }
+}
+
+/// Linking ARFs to an issue tracker
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct IssuesConfig {
+    /// URL for an issue with `{id}` in place of its number or key, e.g.
+    /// `https://acme.atlassian.net/browse/{id}`. Inferred from `origin`
+    /// for GitHub, GitLab, Codeberg, and Gitea when unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url_template: Option<String>,
+}
+
+/// Recording commit authors on ARFs (`context.people`). Disable to keep
+/// names out of the knowledge base.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PeopleConfig {
+    #[serde(default = "default_people_enabled")]
+    pub enabled: bool,
+    /// Most authors kept per ARF
+    #[serde(default = "default_max_people")]
+    pub max_per_entry: usize,
+    /// Blame the files an entry cites and record the authors owning most
+    /// of their lines in `context.outcome.authors`
+    #[serde(default = "default_people_blame")]
+    pub blame: bool,
+    /// Most files blamed per learn; entries citing only files past it get
+    /// no authors
+    #[serde(default = "default_max_blame_files")]
+    pub max_blame_files: usize,
+}
+
+fn default_people_enabled() -> bool {
+    true
+}
+
+fn default_max_people() -> usize {
     3
 }
 
-impl Default for ClaudeConfig {
+fn default_people_blame() -> bool {
+    true
+}
+
+fn default_max_blame_files() -> usize {
+    200
+}
+
+impl Default for PeopleConfig {
     fn default() -> Self {
         Self {
-            timeout_secs: default_timeout(),
-            max_retries: default_max_retries(),
+            enabled: default_people_enabled(),
+            max_per_entry: default_max_people(),
+            blame: default_people_blame(),
+            max_blame_files: default_max_blame_files(),
+        }
+    }
+}
+
+/// Content that must stay on this machine
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PrivacyConfig {
+    /// Paths whose contents are never put in a prompt, even when changed.
+    /// They are still scanned and tracked. See [`crate::policy`].
+    #[serde(default = "default_never_send")]
+    pub never_send: Vec<String>,
+}
+
+fn default_never_send() -> Vec<String> {
+    ["*.pem", "*.key", ".env", ".env.*", "secrets/"]
+        .iter()
+        .map(|pattern| pattern.to_string())
+        .collect()
+}
+
+impl Default for PrivacyConfig {
+    fn default() -> Self {
+        Self {
+            never_send: default_never_send(),
+        }
+    }
+}
+
+/// How generated code is recognized (see [`crate::learn::generated`]).
+/// Generated files are hashed and tracked for drift but never analyzed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GeneratedConfig {
+    /// Path globs of generated files, written like `privacy.never_send`
+    #[serde(default = "default_generated_paths")]
+    pub paths: Vec<String>,
+    /// Text near the top of a file that marks it as generated
+    #[serde(default = "default_generated_markers")]
+    pub markers: Vec<String>,
+    /// Treat files .gitattributes marks `linguist-generated`,
+    /// `linguist-vendored`, or `export-ignore` as generated. Commit scoring
+    /// has its own switch for these, `scoring.honor_attributes`.
+    #[serde(default = "default_generated_linguist")]
+    pub linguist: bool,
+}
+
+fn default_generated_paths() -> Vec<String> {
+    [
+        "*.pb.go",
+        "*_pb2.py",
+        "*.g.dart",
+        "*.min.js",
+        "*.generated.*",
+        "Cargo.lock",
+        "package-lock.json",
+        "yarn.lock",
+        "pnpm-lock.yaml",
+        "go.sum",
+    ]
+    .iter()
+    .map(|pattern| pattern.to_string())
+    .collect()
+}
+
+fn default_generated_markers() -> Vec<String> {
+    vec!["@generated".to_string(), "DO NOT EDIT".to_string()]
+}
+
+fn default_generated_linguist() -> bool {
+    true
+}
+
+impl Default for GeneratedConfig {
+    fn default() -> Self {
+        Self {
+            paths: default_generated_paths(),
+            markers: default_generated_markers(),
+            linguist: default_generated_linguist(),
         }
     }
-}
\ No newline at end of file
+}
+
+/// How files are keyed in `.noggin/manifest.toml`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ManifestConfig {
+    /// `insensitive` when collaborators share a knowledge base across
+    /// case-insensitive filesystems, so `Src/Lib.rs` and `src/lib.rs`
+    /// are tracked once. Applied, and duplicates merged, on the next learn.
+    #[serde(default)]
+    pub path_case: PathCase,
+}
+
+/// Git submodules checked out under the repository
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SubmodulesConfig {
+    /// Scan the files of checked-out submodules along with the
+    /// repository's own, instead of skipping them. Each submodule is
+    /// recorded in its own manifest section.
+    #[serde(default)]
+    pub recurse: bool,
+}
+
+/// The project glossary under `facts/glossary/` (see
+/// [`crate::learn::glossary`])
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GlossaryConfig {
+    #[serde(default = "default_glossary_enabled")]
+    pub enabled: bool,
+    /// Most candidate terms defined per learn run
+    #[serde(default = "default_glossary_max_terms")]
+    pub max_terms: usize,
+    /// Files an acronym or type name must appear in to count as a term
+    #[serde(default = "default_glossary_min_files")]
+    pub min_files: usize,
+}
+
+fn default_glossary_enabled() -> bool {
+    true
+}
+
+fn default_glossary_max_terms() -> usize {
+    40
+}
+
+fn default_glossary_min_files() -> usize {
+    3
+}
+
+impl Default for GlossaryConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_glossary_enabled(),
+            max_terms: default_glossary_max_terms(),
+            min_files: default_glossary_min_files(),
+        }
+    }
+}
+
+/// Limits on local artifacts kept under .noggin/ (see [`crate::retention`])
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RetentionConfig {
+    /// Keep each provider response under .noggin/raw/; turn off where
+    /// responses must not be stored
+    #[serde(default = "default_store_raw_responses")]
+    pub store_raw_responses: bool,
+    /// Remove raw responses, cache, and log files older than this
+    #[serde(default = "default_max_age_days")]
+    pub max_age_days: Option<u64>,
+    /// Cap on their combined size; the oldest go first
+    #[serde(default = "default_max_size_mb")]
+    pub max_size_mb: Option<u64>,
+}
+
+fn default_store_raw_responses() -> bool {
+    true
+}
+
+fn default_max_age_days() -> Option<u64> {
+    Some(30)
+}
+
+fn default_max_size_mb() -> Option<u64> {
+    Some(100)
+}
+
+impl Default for RetentionConfig {
+    fn default() -> Self {
+        Self {
+            store_raw_responses: default_store_raw_responses(),
+            max_age_days: default_max_age_days(),
+            max_size_mb: default_max_size_mb(),
+        }
+    }
+}
+
+/// How confidence fades for knowledge that isn't re-verified (see
+/// [`crate::decay`])
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DecayConfig {
+    /// Days after which an entry's confidence has halved
+    #[serde(default = "default_half_life_days")]
+    pub half_life_days: u32,
+    /// Extra factor for entries whose files changed since they were analyzed
+    #[serde(default = "default_changed_factor")]
+    pub changed_factor: f64,
+    /// Entries whose effective confidence falls below this are reported
+    #[serde(default = "default_low_threshold")]
+    pub low_threshold: f64,
+}
+
+fn default_half_life_days() -> u32 {
+    180
+}
+
+fn default_changed_factor() -> f64 {
+    0.5
+}
+
+fn default_low_threshold() -> f64 {
+    0.5
+}
+
+impl Default for DecayConfig {
+    fn default() -> Self {
+        Self {
+            half_life_days: default_half_life_days(),
+            changed_factor: default_changed_factor(),
+            low_threshold: default_low_threshold(),
+        }
+    }
+}
+
+/// How learn merges model outputs into consensus entries (see
+/// [`crate::synthesis`])
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SynthesisConfig {
+    /// Entries whose `what` differs by fewer than this many edits are
+    /// treated as the same knowledge
+    #[serde(default = "default_synthesis_merge_distance")]
+    pub merge_distance: usize,
+    /// `representative` compares an entry with each cluster's first member;
+    /// `linkage` with every member, so chains of near matches merge
+    #[serde(default)]
+    pub clustering: Clustering,
+    /// Only merge entries of the same inferred category (decision, bug,
+    /// migration, pattern, fact)
+    #[serde(default = "default_infer_categories")]
+    pub infer_categories: bool,
+    /// Drop clusters fewer than this many models agree on. Capped at the
+    /// number of models that answered, so a single provider still works.
+    #[serde(default = "default_min_models")]
+    pub min_models: usize,
+    /// A new entry at least this similar (cosine, 0.0 to 1.0) to an
+    /// unapproved entry already in the knowledge base is merged into it
+    /// instead of getting its own file. 0 disables the check.
+    #[serde(default = "default_dedupe_similarity")]
+    pub dedupe_similarity: f64,
+    /// Most entries synthesized per prompt; the least specific ones beyond
+    /// it are left out. 0 means no limit.
+    #[serde(default = "default_synthesis_max_entries")]
+    pub max_entries: usize,
+    /// Above this many entries in a group, only entries sharing a file are
+    /// compared for merging. 0 always compares every pair.
+    #[serde(default = "default_partition_above")]
+    pub partition_above: usize,
+}
+
+fn default_synthesis_merge_distance() -> usize {
+    3
+}
+
+fn default_infer_categories() -> bool {
+    true
+}
+
+fn default_min_models() -> usize {
+    1
+}
+
+fn default_dedupe_similarity() -> f64 {
+    0.75
+}
+
+fn default_synthesis_max_entries() -> usize {
+    2000
+}
+
+fn default_partition_above() -> usize {
+    500
+}
+
+impl Default for SynthesisConfig {
+    fn default() -> Self {
+        Self {
+            merge_distance: default_synthesis_merge_distance(),
+            clustering: Clustering::default(),
+            infer_categories: default_infer_categories(),
+            min_models: default_min_models(),
+            dedupe_similarity: default_dedupe_similarity(),
+            max_entries: default_synthesis_max_entries(),
+            partition_above: default_partition_above(),
+        }
+    }
+}
+
+/// How learn names new ARF files (see [`crate::learn::writer`])
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WriterConfig {
+    /// `slug`, `date-slug`, `sequence`, or `hash`. Existing files keep
+    /// their names; entries are matched by the `id` stored inside them.
+    #[serde(default)]
+    pub naming: FileNaming,
+}
+
+/// Where ARFs and the manifest are kept (see [`crate::storage`])
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StorageConfig {
+    /// Only `filesystem` for now
+    #[serde(default)]
+    pub backend: StorageBackend,
+}
+
+/// Local usage counters in `.noggin/metrics.toml`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MetricsConfig {
+    #[serde(default = "default_metrics_enabled")]
+    pub enabled: bool,
+}
+
+fn default_metrics_enabled() -> bool {
+    true
+}
+
+impl Default for MetricsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_metrics_enabled(),
+        }
+    }
+}
+
+/// Per-invocation limits for `noggin maintain`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MaintainConfig {
+    /// Maximum unprocessed commits to backfill per run
+    #[serde(default = "default_max_commits")]
+    pub max_commits: usize,
+    /// Patterns not refreshed for this many days are considered stale
+    #[serde(default = "default_stale_days")]
+    pub stale_days: u32,
+    /// Maximum stale patterns to refresh per run
+    #[serde(default = "default_max_refresh")]
+    pub max_refresh: usize,
+    /// Maximum low-confidence entries whose files are re-analyzed per run
+    #[serde(default = "default_max_decayed")]
+    pub max_decayed: usize,
+}
+
+fn default_max_commits() -> usize {
+    50
+}
+
+fn default_stale_days() -> u32 {
+    30
+}
+
+fn default_max_refresh() -> usize {
+    10
+}
+
+fn default_max_decayed() -> usize {
+    10
+}
+
+impl Default for MaintainConfig {
+    fn default() -> Self {
+        Self {
+            max_commits: default_max_commits(),
+            stale_days: default_stale_days(),
+            max_refresh: default_max_refresh(),
+            max_decayed: default_max_decayed(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_load_missing_config_uses_defaults() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = Config::load(temp_dir.path()).unwrap();
+
+        assert!(config.budget.max_requests.is_none());
+        assert_eq!(config.budget.cost_per_million_tokens, 5.0);
+        assert_eq!(config.llm.claude.timeout_secs, 30);
+    }
+
+    #[test]
+    fn test_load_budget_section() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(
+            temp_dir.path().join("config.toml"),
+            "[budget]\nmax_requests = 12\nmax_time_secs = 600\nmax_cost_usd = 1.5\n\n[budget.provider_costs]\nclaude = 15.0\n",
+        )
+        .unwrap();
+
+        let config = Config::load(temp_dir.path()).unwrap();
+
+        assert_eq!(config.budget.max_requests, Some(12));
+        assert_eq!(config.budget.max_time_secs, Some(600));
+        assert_eq!(config.budget.max_cost_usd, Some(1.5));
+        assert!(config.budget.max_tokens.is_none());
+        assert_eq!(config.budget.provider_costs.get("claude"), Some(&15.0));
+        assert_eq!(config.maintain.max_commits, 50);
+    }
+
+    #[test]
+    fn test_load_maintain_section() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(
+            temp_dir.path().join("config.toml"),
+            "[maintain]\nmax_commits = 5\nstale_days = 7\n",
+        )
+        .unwrap();
+
+        let config = Config::load(temp_dir.path()).unwrap();
+
+        assert_eq!(config.maintain.max_commits, 5);
+        assert_eq!(config.maintain.stale_days, 7);
+        assert_eq!(config.maintain.max_refresh, 10);
+        assert_eq!(config.maintain.max_decayed, 10);
+    }
+
+    #[test]
+    fn test_load_decay_section() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(
+            temp_dir.path().join("config.toml"),
+            "[decay]\nhalf_life_days = 30\n",
+        )
+        .unwrap();
+
+        let config = Config::load(temp_dir.path()).unwrap();
+
+        assert_eq!(config.decay.half_life_days, 30);
+        assert_eq!(config.decay.changed_factor, 0.5);
+        assert_eq!(config.decay.low_threshold, 0.5);
+    }
+
+    #[test]
+    fn test_max_concurrency_per_provider() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(
+            temp_dir.path().join("config.toml"),
+            "[llm]\nmax_concurrency = 4\n\n[llm.gemini]\nmax_concurrency = 1\n",
+        )
+        .unwrap();
+
+        let config = Config::load(temp_dir.path()).unwrap();
+
+        assert_eq!(config.llm.max_concurrency("gemini"), 1);
+        assert_eq!(config.llm.max_concurrency("claude"), 4);
+        assert_eq!(config.llm.rate_limit_retries, 3);
+    }
+
+    #[test]
+    fn test_retry_policy_per_provider() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(
+            temp_dir.path().join("config.toml"),
+            "[llm.retry]\nmax_attempts = 5\njitter = 0.0\n\n\
+             [llm.claude]\nmax_retries = 2\n\n\
+             [llm.gemini.retry]\nmax_attempts = 1\n",
+        )
+        .unwrap();
+
+        let config = Config::load(temp_dir.path()).unwrap();
+
+        assert_eq!(config.llm.retry_policy("codex").max_attempts, 5);
+        assert_eq!(config.llm.retry_policy("codex").jitter, 0.0);
+        let claude = config.llm.retry_policy("claude");
+        assert_eq!(claude.max_attempts, 2);
+        assert_eq!(claude.jitter, 0.0);
+        let gemini = config.llm.retry_policy("gemini");
+        assert_eq!(gemini.max_attempts, 1);
+        assert_eq!(gemini.jitter, RetryPolicy::default().jitter);
+    }
+
+    #[test]
+    fn test_load_openai_provider() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(
+            temp_dir.path().join("config.toml"),
+            "[llm]\nproviders = [\"claude\", \"openai\"]\n\n[llm.openai]\nmodel = \"gpt-4.1\"\n",
+        )
+        .unwrap();
+
+        let config = Config::load(temp_dir.path()).unwrap();
+
+        assert_eq!(config.llm.openai.model, "gpt-4.1");
+        assert_eq!(config.llm.openai.timeout_secs, 60);
+        let providers = crate::llm::configured_providers(&config.llm).unwrap();
+        assert_eq!(providers[1].name(), "openai");
+        assert!(!Config::default().llm.providers.contains(&"openai".to_string()));
+    }
+
+    #[test]
+    fn test_load_provider_registry() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(
+            temp_dir.path().join("config.toml"),
+            "[llm]\nproviders = [\"gemini\", \"codex\", \"claude\"]\n\n\
+             [llm.codex]\nenabled = false\n\n[llm.gemini]\ntimeout_secs = 600\n",
+        )
+        .unwrap();
+
+        let config = Config::load(temp_dir.path()).unwrap();
+
+        assert_eq!(config.llm.gemini.timeout_secs, 600);
+        assert_eq!(config.llm.codex.timeout_secs, 120);
+        let names: Vec<_> = crate::llm::configured_providers(&config.llm)
+            .unwrap()
+            .iter()
+            .map(|p| p.name().to_string())
+            .collect();
+        assert_eq!(names, vec!["gemini", "claude"]);
+    }
+
+    #[test]
+    fn test_load_custom_provider() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(
+            temp_dir.path().join("config.toml"),
+            "[llm]\nproviders = [\"claude\", \"local\"]\n\n\
+             [[llm.custom]]\nname = \"local\"\ncommand = \"llama-run {prompt_file}\"\n\
+             output = \"json\"\njson_pointer = \"/response\"\n",
+        )
+        .unwrap();
+
+        let config = Config::load(temp_dir.path()).unwrap();
+
+        let custom = config.llm.custom_provider("local").unwrap();
+        assert_eq!(custom.output, OutputFormat::Json);
+        assert_eq!(custom.timeout_secs, 300);
+        assert!(config.llm.provider_names().contains(&"local".to_string()));
+        let providers = crate::llm::configured_providers(&config.llm).unwrap();
+        assert_eq!(providers[1].name(), "local");
+    }
+
+    #[test]
+    fn test_load_chaos_section() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(
+            temp_dir.path().join("config.toml"),
+            "[llm.chaos]\nmalformed = 0.5\nseed = 42\n",
+        )
+        .unwrap();
+
+        let config = Config::load(temp_dir.path()).unwrap();
+
+        assert_eq!(config.llm.chaos.malformed, 0.5);
+        assert_eq!(config.llm.chaos.seed, Some(42));
+        assert_eq!(config.llm.chaos.delay_ms, 2000);
+    }
+
+    #[test]
+    fn test_load_storage_section() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("config.toml");
+        fs::write(&path, "[storage]\nbackend = \"filesystem\"\n").unwrap();
+        let config = Config::load(temp_dir.path()).unwrap();
+        assert_eq!(config.storage.backend, StorageBackend::Filesystem);
+
+        fs::write(&path, "[storage]\nbackend = \"sqlite\"\n").unwrap();
+        let err = Config::load(temp_dir.path()).unwrap_err();
+        assert!(format!("{:#}", err).contains("unknown variant `sqlite`"));
+    }
+
+    #[test]
+    fn test_load_synthesis_section() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(
+            temp_dir.path().join("config.toml"),
+            "[synthesis]\nmerge_distance = 6\nclustering = \"linkage\"\nmin_models = 2\nmax_entries = 300\n",
+        )
+        .unwrap();
+
+        let config = Config::load(temp_dir.path()).unwrap();
+
+        assert_eq!(config.synthesis.merge_distance, 6);
+        assert_eq!(config.synthesis.clustering, Clustering::Linkage);
+        assert!(config.synthesis.infer_categories);
+        assert_eq!(config.synthesis.min_models, 2);
+        assert_eq!(config.synthesis.max_entries, 300);
+        assert_eq!(config.synthesis.partition_above, 500);
+    }
+
+    #[test]
+    fn test_load_generated_section() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(
+            temp_dir.path().join("config.toml"),
+            "[generated]\npaths = [\"gen/\"]\nlinguist = false\n",
+        )
+        .unwrap();
+
+        let config = Config::load(temp_dir.path()).unwrap();
+
+        assert_eq!(config.generated.paths, vec!["gen/"]);
+        assert_eq!(config.generated.markers, vec!["@generated", "DO NOT EDIT"]);
+        assert!(!config.generated.linguist);
+    }
+
+    #[test]
+    fn test_load_manifest_section() {
+        let temp_dir = TempDir::new().unwrap();
+        assert_eq!(Config::load(temp_dir.path()).unwrap().manifest.path_case, PathCase::Sensitive);
+
+        fs::write(
+            temp_dir.path().join("config.toml"),
+            "[manifest]\npath_case = \"insensitive\"\n",
+        )
+        .unwrap();
+
+        let config = Config::load(temp_dir.path()).unwrap();
+
+        assert_eq!(config.manifest.path_case, PathCase::Insensitive);
+    }
+
+    #[test]
+    fn test_load_glossary_section() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(
+            temp_dir.path().join("config.toml"),
+            "[glossary]\nmax_terms = 10\n",
+        )
+        .unwrap();
+
+        let config = Config::load(temp_dir.path()).unwrap();
+
+        assert!(config.glossary.enabled);
+        assert_eq!(config.glossary.max_terms, 10);
+        assert_eq!(config.glossary.min_files, 3);
+    }
+
+    #[test]
+    fn test_load_writer_section() {
+        let temp_dir = TempDir::new().unwrap();
+        assert_eq!(Config::load(temp_dir.path()).unwrap().writer.naming, FileNaming::Slug);
+
+        fs::write(
+            temp_dir.path().join("config.toml"),
+            "[writer]\nnaming = \"date-slug\"\n",
+        )
+        .unwrap();
+
+        let config = Config::load(temp_dir.path()).unwrap();
+
+        assert_eq!(config.writer.naming, FileNaming::DateSlug);
+    }
+
+    #[test]
+    fn test_load_malformed_config() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(temp_dir.path().join("config.toml"), "[budget\n").unwrap();
+
+        let result = Config::load(temp_dir.path());
+        assert!(result.is_err());
+        assert!(result.unwrap_err().to_string().contains("Failed to parse config"));
+    }
+}
@@ -0,0 +1,397 @@
+//! Conflict records for knowledge that needs human review.
+//!
+//! When learn produces an ARF that contradicts an approved one, the
+//! approved file is left alone and the proposal is parked in
+//! `.noggin/conflicts/<slug>.toml` until someone reviews it. A field the
+//! models disagreed on and voting could not settle is parked the same way,
+//! as `.noggin/conflicts/<slug>.<field>.toml` with every candidate value.
+//! `noggin resolve` applies the chosen values and removes the record.
+
+use crate::arf::ArfFile;
+use crate::storage::KnowledgeBackend;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+const CONFLICTS_DIR: &str = "conflicts";
+
+/// A proposed ARF that contradicts an approved one
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConflictRecord {
+    /// Stable ID, the slug of the approved ARF's filename
+    pub id: String,
+    pub created_at: DateTime<Utc>,
+    /// Approved ARF path, relative to .noggin/
+    pub existing_path: String,
+    /// Fields that differ materially (what, why)
+    pub fields: Vec<String>,
+    pub existing: ArfFile,
+    pub proposed: ArfFile,
+}
+
+impl ConflictRecord {
+    pub fn new(
+        existing_path: impl Into<String>,
+        fields: Vec<String>,
+        existing: ArfFile,
+        proposed: ArfFile,
+    ) -> Self {
+        let existing_path = existing_path.into();
+        let id = Path::new(&existing_path)
+            .file_stem()
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_else(|| existing_path.clone());
+
+        Self {
+            id,
+            created_at: Utc::now(),
+            existing_path,
+            fields,
+            existing,
+            proposed,
+        }
+    }
+
+    /// Path of this record relative to .noggin/
+    pub fn relative_path(&self) -> String {
+        format!("{}/{}.toml", CONFLICTS_DIR, self.id)
+    }
+
+    /// Write the record, replacing any earlier proposal for the same ARF
+    pub fn save(&self, noggin_path: &Path) -> Result<()> {
+        save_record(noggin_path, &self.relative_path(), self)
+    }
+}
+
+/// A field voting could not settle during synthesis
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VoteConflict {
+    /// Stable ID, `<ARF slug>.<field>`
+    pub id: String,
+    pub created_at: DateTime<Utc>,
+    /// ARF the field belongs to, relative to .noggin/
+    pub arf_path: String,
+    /// Field name, e.g. `how` or `context.outcome.result`
+    pub field: String,
+    /// Every value the models proposed
+    pub candidates: Vec<Candidate>,
+}
+
+/// One value offered for a conflicting field
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Candidate {
+    /// Model that proposed it, or `approved`/`proposed` for an approval
+    /// conflict
+    pub source: String,
+    pub value: String,
+}
+
+impl VoteConflict {
+    /// `values` are (model_name, value) pairs
+    pub fn new(
+        arf_path: impl Into<String>,
+        field: impl Into<String>,
+        values: Vec<(String, String)>,
+    ) -> Self {
+        let arf_path = arf_path.into();
+        let field = field.into();
+        let stem = Path::new(&arf_path)
+            .file_stem()
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_else(|| arf_path.clone());
+
+        Self {
+            id: format!("{}.{}", stem, field),
+            created_at: Utc::now(),
+            arf_path,
+            field,
+            candidates: values
+                .into_iter()
+                .map(|(source, value)| Candidate { source, value })
+                .collect(),
+        }
+    }
+
+    /// Path of this record relative to .noggin/
+    pub fn relative_path(&self) -> String {
+        format!("{}/{}.toml", CONFLICTS_DIR, self.id)
+    }
+
+    /// Write the record, replacing any earlier one for the same field
+    pub fn save(&self, noggin_path: &Path) -> Result<()> {
+        save_record(noggin_path, &self.relative_path(), self)
+    }
+}
+
+/// An open record in `.noggin/conflicts/`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Conflict {
+    Vote(VoteConflict),
+    Approval(Box<ConflictRecord>),
+}
+
+impl Conflict {
+    pub fn load(path: &Path) -> Result<Self> {
+        let contents = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read conflict record {}", path.display()))?;
+        toml::from_str(&contents)
+            .with_context(|| format!("Failed to parse conflict record {}", path.display()))
+    }
+
+    pub fn id(&self) -> &str {
+        match self {
+            Conflict::Vote(vote) => &vote.id,
+            Conflict::Approval(record) => &record.id,
+        }
+    }
+
+    /// ARF the conflict is about, relative to .noggin/
+    pub fn arf_path(&self) -> &str {
+        match self {
+            Conflict::Vote(vote) => &vote.arf_path,
+            Conflict::Approval(record) => &record.existing_path,
+        }
+    }
+
+    /// Path of the record relative to .noggin/
+    pub fn relative_path(&self) -> String {
+        match self {
+            Conflict::Vote(vote) => vote.relative_path(),
+            Conflict::Approval(record) => record.relative_path(),
+        }
+    }
+
+    /// Each field to decide, with its candidate values
+    pub fn choices(&self) -> Vec<(String, Vec<Candidate>)> {
+        match self {
+            Conflict::Vote(vote) => vec![(vote.field.clone(), vote.candidates.clone())],
+            Conflict::Approval(record) => record
+                .fields
+                .iter()
+                .map(|field| {
+                    let candidate = |source: &str, arf: &ArfFile| Candidate {
+                        source: source.to_string(),
+                        value: arf.field(field).unwrap_or_default().to_string(),
+                    };
+                    (
+                        field.clone(),
+                        vec![
+                            candidate("approved", &record.existing),
+                            candidate("proposed", &record.proposed),
+                        ],
+                    )
+                })
+                .collect(),
+        }
+    }
+
+    /// Set each `(field, value)` pick on the ARF in `backend`, then delete
+    /// the record from `noggin_path`.
+    ///
+    /// The ARF keeps its approval: a person made the choice.
+    pub fn resolve(
+        &self,
+        noggin_path: &Path,
+        backend: &dyn KnowledgeBackend,
+        picks: &[(String, String)],
+    ) -> Result<()> {
+        let mut arf = match (backend.read_arf(self.arf_path()), self) {
+            (Ok(Some(arf)), _) => arf,
+            (_, Conflict::Approval(record)) => record.existing.clone(),
+            (Ok(None), Conflict::Vote(_)) => {
+                anyhow::bail!("{} no longer exists", self.arf_path())
+            }
+            (Err(e), Conflict::Vote(_)) => return Err(e),
+        };
+        for (field, value) in picks {
+            if !arf.set_field(field, value.as_str()) {
+                anyhow::bail!("Unknown ARF field '{}' in {}", field, self.relative_path());
+            }
+        }
+        arf.updated_at = Some(Utc::now());
+        backend.write_arf(self.arf_path(), &arf)?;
+
+        let record_path = noggin_path.join(self.relative_path());
+        fs::remove_file(&record_path)
+            .with_context(|| format!("Failed to remove {}", record_path.display()))
+    }
+}
+
+/// Load all open conflict records, sorted by ID
+pub fn list_conflicts(noggin_path: &Path) -> Result<Vec<Conflict>> {
+    let dir = noggin_path.join(CONFLICTS_DIR);
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut records = Vec::new();
+    for entry in fs::read_dir(&dir).with_context(|| format!("Failed to read {}", dir.display()))? {
+        let path = entry?.path();
+        if path.extension().is_some_and(|ext| ext == "toml") {
+            records.push(Conflict::load(&path)?);
+        }
+    }
+
+    records.sort_by(|a, b| a.id().cmp(b.id()));
+    Ok(records)
+}
+
+fn save_record<T: Serialize>(noggin_path: &Path, relative: &str, record: &T) -> Result<()> {
+    let path = noggin_path.join(relative);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create directory {}", parent.display()))?;
+    }
+
+    let contents =
+        toml::to_string_pretty(record).context("Failed to serialize conflict record to TOML")?;
+    fs::write(&path, contents)
+        .with_context(|| format!("Failed to write conflict record {}", path.display()))
+}
+
+/// Fields where `proposed` contradicts `existing`.
+///
+/// Only `what` and `why` count; a different `how` or context is treated
+/// as an elaboration, not a contradiction.
+pub fn contradicting_fields(existing: &ArfFile, proposed: &ArfFile) -> Vec<String> {
+    let mut fields = Vec::new();
+    if materially_differs(&existing.what, &proposed.what) {
+        fields.push("what".to_string());
+    }
+    if materially_differs(&existing.why, &proposed.why) {
+        fields.push("why".to_string());
+    }
+    fields
+}
+
+/// True if more than a fifth of the normalized text changed. Case,
+/// punctuation, and whitespace differences are ignored.
+fn materially_differs(a: &str, b: &str) -> bool {
+    let a = normalize(a);
+    let b = normalize(b);
+    if a == b {
+        return false;
+    }
+
+    let longest = a.chars().count().max(b.chars().count());
+    edit_distance::edit_distance(&a, &b) * 5 > longest
+}
+
+fn normalize(text: &str) -> String {
+    text.to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::FilesystemBackend;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_rewording_is_not_a_contradiction() {
+        let existing = ArfFile::new("Use PostgreSQL", "Need transactions.", "sqlx");
+        let proposed = ArfFile::new("use postgresql", "Need transactions", "diesel");
+        assert!(contradicting_fields(&existing, &proposed).is_empty());
+    }
+
+    #[test]
+    fn test_different_why_is_a_contradiction() {
+        let existing = ArfFile::new("Use PostgreSQL", "Need transactions", "sqlx");
+        let proposed = ArfFile::new("Use PostgreSQL", "Cheapest hosted option available", "sqlx");
+        assert_eq!(contradicting_fields(&existing, &proposed), vec!["why"]);
+    }
+
+    #[test]
+    fn test_save_and_list() {
+        let temp_dir = TempDir::new().unwrap();
+        let record = ConflictRecord::new(
+            "decisions/use-postgresql.arf",
+            vec!["why".to_string()],
+            ArfFile::new("Use PostgreSQL", "Need transactions", "sqlx"),
+            ArfFile::new("Use PostgreSQL", "Cheapest option", "sqlx"),
+        );
+        assert_eq!(record.relative_path(), "conflicts/use-postgresql.toml");
+        record.save(temp_dir.path()).unwrap();
+
+        let records = list_conflicts(temp_dir.path()).unwrap();
+        assert_eq!(records.len(), 1);
+        match &records[0] {
+            Conflict::Approval(record) => {
+                assert_eq!(record.existing_path, "decisions/use-postgresql.arf");
+                assert_eq!(record.proposed.why, "Cheapest option");
+            }
+            other => panic!("Expected an approval conflict, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_resolve_vote_conflict() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::create_dir_all(temp_dir.path().join("patterns")).unwrap();
+        ArfFile::new("Use pooling", "Performance", "PgBouncer")
+            .to_toml(&temp_dir.path().join("patterns/use-pooling.arf"))
+            .unwrap();
+
+        let vote = VoteConflict::new(
+            "patterns/use-pooling.arf",
+            "how",
+            vec![
+                ("claude".to_string(), "PgBouncer".to_string()),
+                ("gemini".to_string(), "pgpool".to_string()),
+            ],
+        );
+        assert_eq!(vote.relative_path(), "conflicts/use-pooling.how.toml");
+        vote.save(temp_dir.path()).unwrap();
+
+        let records = list_conflicts(temp_dir.path()).unwrap();
+        assert!(matches!(records[0], Conflict::Vote(_)));
+        let choices = records[0].choices();
+        assert_eq!(choices[0].0, "how");
+        assert_eq!(choices[0].1[1].value, "pgpool");
+
+        let backend = FilesystemBackend::new(temp_dir.path());
+        let picks = [("how".to_string(), "pgpool".to_string())];
+        records[0].resolve(temp_dir.path(), &backend, &picks).unwrap();
+        let arf = ArfFile::from_toml(&temp_dir.path().join("patterns/use-pooling.arf")).unwrap();
+        assert_eq!(arf.how, "pgpool");
+        assert!(list_conflicts(temp_dir.path()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_resolve_approval_conflict_keeps_approval() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::create_dir_all(temp_dir.path().join("decisions")).unwrap();
+        let mut existing = ArfFile::new("Use PostgreSQL", "Need transactions", "sqlx");
+        existing.approved = true;
+        existing
+            .to_toml(&temp_dir.path().join("decisions/use-postgresql.arf"))
+            .unwrap();
+
+        let record = ConflictRecord::new(
+            "decisions/use-postgresql.arf",
+            vec!["why".to_string()],
+            existing,
+            ArfFile::new("Use PostgreSQL", "Cheapest option", "sqlx"),
+        );
+        record.save(temp_dir.path()).unwrap();
+        let record = Conflict::Approval(Box::new(record));
+        let choices = record.choices();
+        assert_eq!(choices[0].1[0].source, "approved");
+        assert_eq!(choices[0].1[1].value, "Cheapest option");
+
+        let backend = FilesystemBackend::new(temp_dir.path());
+        let picks = [("why".to_string(), "Cheapest option".to_string())];
+        record.resolve(temp_dir.path(), &backend, &picks).unwrap();
+        let arf = ArfFile::from_toml(&temp_dir.path().join("decisions/use-postgresql.arf")).unwrap();
+        assert_eq!(arf.why, "Cheapest option");
+        assert!(arf.approved);
+        assert!(list_conflicts(temp_dir.path()).unwrap().is_empty());
+    }
+}
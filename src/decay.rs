@@ -0,0 +1,319 @@
+//! Confidence decay for knowledge that hasn't been re-verified.
+//!
+//! An entry counts as verified when learn last analyzed the files it
+//! cites (their manifest `last_scanned`, oldest first), or, for entries
+//! citing no tracked files, when the commits linked to it were
+//! processed. From then on its confidence halves every
+//! `decay.half_life_days`, and is multiplied by `decay.changed_factor`
+//! once any cited file has changed on disk since. Entries without a
+//! verification record keep their stated confidence; entries without a
+//! confidence start from 1.0.
+
+use crate::arf::ArfFile;
+use crate::config::DecayConfig;
+use crate::manifest::{calculate_file_hash, FileEntry, Manifest};
+use crate::storage::KnowledgeBackend;
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::path::Path;
+
+/// An ARF with its confidence after decay
+#[derive(Debug, Clone, Serialize)]
+pub struct DecayedEntry {
+    /// Path relative to .noggin/
+    pub path: String,
+    pub what: String,
+    /// Confidence stated in the ARF (1.0 if it has none)
+    pub confidence: f64,
+    pub effective: f64,
+    /// When the entry's evidence was last analyzed, if known
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub verified_at: Option<DateTime<Utc>>,
+    /// Cited files tracked in the manifest
+    pub files: Vec<String>,
+    /// Cited files that changed or disappeared since they were analyzed
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub changed_files: Vec<String>,
+}
+
+/// `confidence` after `age_days` without re-verification
+pub fn effective_confidence(
+    confidence: f64,
+    age_days: f64,
+    changed: bool,
+    config: &DecayConfig,
+) -> f64 {
+    let mut effective = confidence;
+    if config.half_life_days > 0 {
+        effective *= 0.5f64.powf(age_days.max(0.0) / f64::from(config.half_life_days));
+    }
+    if changed {
+        effective *= config.changed_factor;
+    }
+    effective.clamp(0.0, 1.0)
+}
+
+/// Decay one ARF stored at `path` (relative to .noggin/) as of `now`
+pub fn decay_entry(
+    repo_path: &Path,
+    manifest: &Manifest,
+    path: &str,
+    arf: &ArfFile,
+    config: &DecayConfig,
+    now: DateTime<Utc>,
+) -> DecayedEntry {
+    // Compared by `path_key` so `path_case` is honored
+    let cited: Vec<String> = arf.context.files.iter().map(|f| manifest.path_key(f)).collect();
+    let tracked: Vec<&FileEntry> = manifest
+        .files
+        .values()
+        .filter(|entry| {
+            let key = manifest.path_key(&entry.path);
+            cited.iter().any(|cited| covers(cited, &key))
+        })
+        .collect();
+
+    let verified_at = match tracked.iter().map(|entry| entry.last_scanned).min() {
+        Some(scanned) => Some(scanned),
+        None => manifest
+            .commits
+            .values()
+            .filter(|commit| commit.arf_path == path)
+            .map(|commit| commit.processed_at)
+            .max(),
+    };
+
+    // Only files cited by name are rehashed; a cited directory dates the
+    // entry but would mean hashing everything under it
+    let mut files: Vec<String> = tracked
+        .iter()
+        .filter(|entry| cited.contains(&manifest.path_key(&entry.path)))
+        .map(|entry| entry.path.clone())
+        .collect();
+    files.sort();
+    let changed_files: Vec<String> = files
+        .iter()
+        .filter(|file| {
+            let current = calculate_file_hash(&repo_path.join(file)).ok();
+            current.as_deref() != manifest.get_file_hash(file)
+        })
+        .cloned()
+        .collect();
+
+    let confidence = arf.confidence.unwrap_or(1.0);
+    let age_days = verified_at
+        .map(|at| (now - at).num_seconds() as f64 / 86_400.0)
+        .unwrap_or(0.0);
+
+    DecayedEntry {
+        path: path.to_string(),
+        what: arf.what.clone(),
+        confidence,
+        effective: effective_confidence(confidence, age_days, !changed_files.is_empty(), config),
+        verified_at,
+        files,
+        changed_files,
+    }
+}
+
+/// Every ARF in `backend`, lowest effective confidence first
+pub fn rank_entries(
+    repo_path: &Path,
+    backend: &dyn KnowledgeBackend,
+    manifest: &Manifest,
+    config: &DecayConfig,
+    now: DateTime<Utc>,
+) -> Result<Vec<DecayedEntry>> {
+    let mut entries = Vec::new();
+
+    for path in backend.list_arfs()? {
+        let Ok(Some(arf)) = backend.read_arf(&path) else {
+            continue;
+        };
+        entries.push(decay_entry(repo_path, manifest, &path, &arf, config, now));
+    }
+
+    entries.sort_by(|a, b| {
+        a.effective
+            .total_cmp(&b.effective)
+            .then(a.path.cmp(&b.path))
+    });
+    Ok(entries)
+}
+
+/// Entries from `rank_entries` whose effective confidence is below the
+/// configured threshold
+pub fn low_confidence(
+    repo_path: &Path,
+    backend: &dyn KnowledgeBackend,
+    manifest: &Manifest,
+    config: &DecayConfig,
+) -> Result<Vec<DecayedEntry>> {
+    Ok(rank_entries(repo_path, backend, manifest, config, Utc::now())?
+        .into_iter()
+        .filter(|entry| entry.effective < config.low_threshold)
+        .collect())
+}
+
+/// A cited path matches the file itself or, for a directory, any file
+/// under it
+fn covers(cited: &str, file: &str) -> bool {
+    let cited = cited.trim_start_matches("./").trim_end_matches('/');
+    !cited.is_empty()
+        && (file == cited
+            || file
+                .strip_prefix(cited)
+                .is_some_and(|rest| rest.starts_with('/')))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::manifest::{CommitCategory, PathCase};
+    use crate::storage::MemoryBackend;
+    use chrono::Duration;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn config() -> DecayConfig {
+        DecayConfig {
+            half_life_days: 100,
+            changed_factor: 0.5,
+            low_threshold: 0.5,
+        }
+    }
+
+    #[test]
+    fn test_effective_confidence() {
+        let config = config();
+        assert_eq!(effective_confidence(0.8, 0.0, false, &config), 0.8);
+        assert!((effective_confidence(0.8, 100.0, false, &config) - 0.4).abs() < 1e-9);
+        assert!((effective_confidence(0.8, 200.0, true, &config) - 0.1).abs() < 1e-9);
+
+        let no_decay = DecayConfig {
+            half_life_days: 0,
+            ..config
+        };
+        assert_eq!(effective_confidence(0.8, 1000.0, false, &no_decay), 0.8);
+    }
+
+    #[test]
+    fn test_decay_entry_uses_oldest_scan_and_detects_changes() {
+        let repo = TempDir::new().unwrap();
+        fs::create_dir_all(repo.path().join("src")).unwrap();
+        fs::write(repo.path().join("src/a.rs"), "fn a() {}").unwrap();
+        fs::write(repo.path().join("src/b.rs"), "fn b() {}").unwrap();
+
+        let now = Utc::now();
+        let mut manifest = Manifest::default();
+        for (path, days) in [("src/a.rs", 50), ("src/b.rs", 100)] {
+            let hash = calculate_file_hash(&repo.path().join(path)).unwrap();
+            manifest.add_or_update_file(path.to_string(), hash, vec![]);
+            manifest.files.get_mut(path).unwrap().last_scanned = now - Duration::days(days);
+        }
+
+        let mut arf = ArfFile::new("Parsing", "Speed", "Hand-written");
+        arf.add_file("src/a.rs");
+        arf.add_file("src/");
+        let entry = decay_entry(
+            repo.path(),
+            &manifest,
+            "facts/parsing.arf",
+            &arf,
+            &config(),
+            now,
+        );
+        assert_eq!(entry.verified_at, Some(now - Duration::days(100)));
+        assert_eq!(entry.files, vec!["src/a.rs"]);
+        assert!(entry.changed_files.is_empty());
+        assert!((entry.effective - 0.5).abs() < 1e-9);
+
+        fs::write(repo.path().join("src/a.rs"), "fn a() { todo!() }").unwrap();
+        let entry = decay_entry(
+            repo.path(),
+            &manifest,
+            "facts/parsing.arf",
+            &arf,
+            &config(),
+            now,
+        );
+        assert_eq!(entry.changed_files, vec!["src/a.rs"]);
+        assert!((entry.effective - 0.25).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_decay_entry_honors_path_case() {
+        let repo = TempDir::new().unwrap();
+        fs::create_dir_all(repo.path().join("Src")).unwrap();
+        fs::write(repo.path().join("Src/Lib.rs"), "fn a() {}").unwrap();
+
+        let now = Utc::now();
+        let mut manifest = Manifest::default();
+        manifest.set_path_case(PathCase::Insensitive);
+        let hash = calculate_file_hash(&repo.path().join("Src/Lib.rs")).unwrap();
+        manifest.add_or_update_file("Src/Lib.rs".to_string(), hash, vec![]);
+
+        let mut arf = ArfFile::new("Parsing", "Speed", "Hand-written");
+        arf.add_file("src/lib.rs");
+        let entry = decay_entry(repo.path(), &manifest, "facts/x.arf", &arf, &config(), now);
+        assert_eq!(entry.files, vec!["Src/Lib.rs"]);
+        assert!(entry.changed_files.is_empty());
+    }
+
+    #[test]
+    fn test_decay_entry_falls_back_to_commits() {
+        let now = Utc::now();
+        let mut manifest = Manifest::default();
+        manifest.add_commit(
+            "abc".to_string(),
+            CommitCategory::Decision,
+            "decisions/x.arf".to_string(),
+        );
+        manifest.commits.get_mut("abc").unwrap().processed_at = now - Duration::days(100);
+
+        let mut arf = ArfFile::new("X", "Y", "Z");
+        arf.confidence = Some(0.6);
+        let entry = decay_entry(
+            Path::new("."),
+            &manifest,
+            "decisions/x.arf",
+            &arf,
+            &config(),
+            now,
+        );
+        assert!((entry.effective - 0.3).abs() < 1e-9);
+
+        let entry = decay_entry(
+            Path::new("."),
+            &manifest,
+            "decisions/other.arf",
+            &arf,
+            &config(),
+            now,
+        );
+        assert_eq!(entry.verified_at, None);
+        assert_eq!(entry.effective, 0.6);
+    }
+
+    #[test]
+    fn test_rank_entries_lowest_first() {
+        let repo = TempDir::new().unwrap();
+        let backend = MemoryBackend::default();
+        let mut high = ArfFile::new("High", "Y", "Z");
+        high.confidence = Some(0.9);
+        backend.write_arf("facts/high.arf", &high).unwrap();
+        let mut low = ArfFile::new("Low", "Y", "Z");
+        low.confidence = Some(0.2);
+        backend.write_arf("facts/low.arf", &low).unwrap();
+
+        let manifest = Manifest::default();
+        let ranked = rank_entries(repo.path(), &backend, &manifest, &config(), Utc::now()).unwrap();
+        assert_eq!(ranked[0].path, "facts/low.arf");
+        assert_eq!(ranked[1].path, "facts/high.arf");
+
+        let low = low_confidence(repo.path(), &backend, &manifest, &config()).unwrap();
+        assert_eq!(low.len(), 1);
+        assert_eq!(low[0].what, "Low");
+    }
+}
@@ -0,0 +1,158 @@
+//! Line authorship of files, for attributing entries to the people who
+//! wrote the code they describe
+//!
+//! Commit authors (`context.people`) only cover entries learned from
+//! history. [`Blame`] credits entries learned from files instead: the
+//! authors owning the most lines of the files an entry cites, as of HEAD
+//! and through `.mailmap`, are recorded in `context.outcome.authors` (see
+//! [`AUTHORS_KEY`]) so `ask` can answer who knows about something.
+//!
+//! Blaming walks a file's history, so a run blames a bounded number of
+//! files, and only the first [`MAX_BLAME_LINES`] lines of each.
+
+use anyhow::{Context, Result};
+use git2::{BlameOptions, Repository};
+use std::collections::HashMap;
+use std::path::Path;
+
+/// `context.outcome` key listing the dominant authors of an entry's files,
+/// comma separated, most lines first
+pub const AUTHORS_KEY: &str = "authors";
+
+/// Lines blamed per file, from the top
+pub const MAX_BLAME_LINES: usize = 2000;
+
+/// Blames files on demand, each file at most once and at most `max_files`
+/// of them
+pub struct Blame<'repo> {
+    repo: &'repo Repository,
+    max_files: usize,
+    /// Lines per author for each file blamed; empty when it couldn't be
+    cache: HashMap<String, Vec<(String, usize)>>,
+}
+
+impl<'repo> Blame<'repo> {
+    pub fn new(repo: &'repo Repository, max_files: usize) -> Self {
+        Self {
+            repo,
+            max_files,
+            cache: HashMap::new(),
+        }
+    }
+
+    /// Up to `max` authors owning the most lines across `paths`, most
+    /// first. Files not committed yet, and files past the limit, contribute
+    /// nobody.
+    pub fn dominant_authors(&mut self, paths: &[String], max: usize) -> Vec<String> {
+        let mut totals: Vec<(String, usize)> = Vec::new();
+        for path in paths {
+            if !self.cache.contains_key(path) && self.cache.len() >= self.max_files {
+                continue;
+            }
+            let repo = self.repo;
+            let lines = self
+                .cache
+                .entry(path.clone())
+                .or_insert_with(|| file_authors(repo, path).unwrap_or_default());
+            for (name, count) in lines.iter() {
+                match totals.iter_mut().find(|(n, _)| n == name) {
+                    Some((_, total)) => *total += count,
+                    None => totals.push((name.clone(), *count)),
+                }
+            }
+        }
+        // Stable sort keeps first-seen order among equals
+        totals.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+        totals.into_iter().take(max).map(|(name, _)| name).collect()
+    }
+}
+
+/// Lines of `path` at HEAD per author name, most first, counting the
+/// first [`MAX_BLAME_LINES`]
+pub fn file_authors(repo: &Repository, path: &str) -> Result<Vec<(String, usize)>> {
+    let mut opts = BlameOptions::new();
+    opts.use_mailmap(true);
+    // libgit2 stretches the last hunk to a max_line past the end of the
+    // file, so only set it for files that are longer
+    if head_line_count(repo, path).is_some_and(|lines| lines > MAX_BLAME_LINES) {
+        opts.max_line(MAX_BLAME_LINES);
+    }
+    let blame = repo
+        .blame_file(Path::new(path), Some(&mut opts))
+        .with_context(|| format!("Failed to blame {}", path))?;
+
+    let mut counts: Vec<(String, usize)> = Vec::new();
+    for hunk in blame.iter() {
+        let signature = hunk.final_signature();
+        let Some(name) = signature.name().map(str::trim).filter(|n| !n.is_empty()) else {
+            continue;
+        };
+        match counts.iter_mut().find(|(n, _)| n == name) {
+            Some((_, count)) => *count += hunk.lines_in_hunk(),
+            None => counts.push((name.to_string(), hunk.lines_in_hunk())),
+        }
+    }
+    counts.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+    Ok(counts)
+}
+
+/// Lines in `path` as of HEAD, None when it isn't committed
+fn head_line_count(repo: &Repository, path: &str) -> Option<usize> {
+    let tree = repo.head().ok()?.peel_to_tree().ok()?;
+    let blob = tree.get_path(Path::new(path)).ok()?.to_object(repo).ok()?.peel_to_blob().ok()?;
+    Some(String::from_utf8_lossy(blob.content()).lines().count())
+}
+
+/// Names listed under [`AUTHORS_KEY`]
+pub fn parse_authors(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::fixture;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn commit_as(repo: &Repository, name: &str, path: &str, content: &str) -> Result<()> {
+        fixture::commit_as(repo, name, "Change", &[(path, content)]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_dominant_authors_by_lines_owned() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let repo = Repository::init(temp_dir.path())?;
+
+        commit_as(&repo, "Ana", "pool.rs", "a\nb\nc\nd\n")?;
+        commit_as(&repo, "Bo", "pool.rs", "a\nb\nc\nd\ne\n")?;
+        commit_as(&repo, "Bo", "retry.rs", "x\ny\n")?;
+        commit_as(&repo, "Cy", "retry.rs", "x\ny\nz\n")?;
+        fs::write(temp_dir.path().join("draft.rs"), "uncommitted\n")?;
+
+        assert_eq!(
+            file_authors(&repo, "pool.rs")?,
+            vec![("Ana".to_string(), 4), ("Bo".to_string(), 1)]
+        );
+
+        let mut blame = Blame::new(&repo, 10);
+        let paths = vec!["pool.rs".to_string(), "retry.rs".to_string(), "draft.rs".to_string()];
+        assert_eq!(blame.dominant_authors(&paths, 3), vec!["Ana", "Bo", "Cy"]);
+        assert_eq!(blame.dominant_authors(&paths[1..], 1), vec!["Bo"]);
+        assert!(blame.dominant_authors(&paths[2..], 3).is_empty());
+
+        // Past the limit only files already blamed count
+        let mut blame = Blame::new(&repo, 1);
+        assert_eq!(blame.dominant_authors(&paths[..1], 3), vec!["Ana", "Bo"]);
+        assert_eq!(blame.dominant_authors(&paths, 3), vec!["Ana", "Bo"]);
+
+        assert_eq!(parse_authors("Ana, Bo,, "), vec!["Ana", "Bo"]);
+        Ok(())
+    }
+}
@@ -0,0 +1,240 @@
+//! Repository fingerprints binding a knowledge base to its repository
+//!
+//! The manifest records the root commit and `origin` URL of the
+//! repository it was built from. A `.noggin/` copied into a fork or an
+//! unrelated repository no longer matches, and learn refuses to mix
+//! knowledge until the binding is adopted explicitly.
+
+use crate::text::short_hash;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use git2::{Repository, Sort};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::Path;
+
+/// Identity of the repository a knowledge base was built from
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RepositoryFingerprint {
+    /// Oldest root commit reachable from HEAD
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub root_commit: Option<String>,
+    /// Normalized `origin` remote URL (`host/owner/repo`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub origin_url: Option<String>,
+    pub bound_at: DateTime<Utc>,
+}
+
+/// Why a fingerprint does not match the current checkout
+#[derive(Debug, Clone, PartialEq)]
+pub enum FingerprintMismatch {
+    /// Histories share no root commit: a different repository
+    RootCommit { expected: String, found: String },
+    /// Same history, different origin: typically a fork
+    Origin { expected: String, found: String },
+}
+
+impl fmt::Display for FingerprintMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FingerprintMismatch::RootCommit { expected, found } => write!(
+                f,
+                "knowledge base was built from a repository rooted at {}, this one is rooted at {}",
+                short_hash(expected),
+                short_hash(found)
+            ),
+            FingerprintMismatch::Origin { expected, found } => write!(
+                f,
+                "knowledge base was built from {}, this checkout's origin is {}",
+                expected, found
+            ),
+        }
+    }
+}
+
+impl RepositoryFingerprint {
+    /// Fingerprint the repository at `repo_path`. Either field may be
+    /// missing for a repository with no commits or no `origin` remote.
+    pub fn detect(repo_path: &Path) -> Result<Self> {
+        let repo = Repository::open(repo_path)
+            .with_context(|| format!("Failed to open git repository at {}", repo_path.display()))?;
+
+        let origin_url = repo
+            .find_remote("origin")
+            .ok()
+            .and_then(|remote| remote.url().map(normalize_remote_url));
+
+        Ok(Self {
+            root_commit: root_commit(&repo)?,
+            origin_url,
+            bound_at: Utc::now(),
+        })
+    }
+
+    /// Compare a stored fingerprint against the current one. Fields
+    /// unknown on either side are not compared.
+    pub fn check(&self, current: &RepositoryFingerprint) -> Option<FingerprintMismatch> {
+        if let (Some(expected), Some(found)) = (&self.root_commit, &current.root_commit) {
+            if expected != found {
+                return Some(FingerprintMismatch::RootCommit {
+                    expected: expected.clone(),
+                    found: found.clone(),
+                });
+            }
+        }
+
+        if let (Some(expected), Some(found)) = (&self.origin_url, &current.origin_url) {
+            if expected != found {
+                return Some(FingerprintMismatch::Origin {
+                    expected: expected.clone(),
+                    found: found.clone(),
+                });
+            }
+        }
+
+        None
+    }
+
+    /// Take fields from `current` that this fingerprint lacks, e.g. an
+    /// `origin` remote added after the knowledge base was bound.
+    /// Returns true if anything changed.
+    pub fn fill_missing(&mut self, current: &RepositoryFingerprint) -> bool {
+        let mut changed = false;
+        if self.root_commit.is_none() && current.root_commit.is_some() {
+            self.root_commit = current.root_commit.clone();
+            changed = true;
+        }
+        if self.origin_url.is_none() && current.origin_url.is_some() {
+            self.origin_url = current.origin_url.clone();
+            changed = true;
+        }
+        changed
+    }
+}
+
+/// The oldest root commit reachable from HEAD, or None for an unborn
+/// branch or a shallow clone (whose oldest commit is not the real root)
+fn root_commit(repo: &Repository) -> Result<Option<String>> {
+    if repo.head().is_err() || repo.is_shallow() {
+        return Ok(None);
+    }
+
+    let mut revwalk = repo.revwalk().context("Failed to create revision walker")?;
+    revwalk.push_head().context("Failed to push HEAD to revwalk")?;
+    revwalk
+        .set_sorting(Sort::TOPOLOGICAL | Sort::TIME | Sort::REVERSE)
+        .context("Failed to set revwalk sorting")?;
+
+    for oid in revwalk {
+        let oid = oid.context("Failed to get commit OID")?;
+        let commit = repo.find_commit(oid)?;
+        if commit.parent_count() == 0 {
+            return Ok(Some(oid.to_string()));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Reduce a remote URL to `host/path` so SSH and HTTPS clones of the
+/// same repository compare equal.
+pub fn normalize_remote_url(url: &str) -> String {
+    let url = url.trim();
+    let rest = match url.split_once("://") {
+        Some((_, rest)) => rest.to_string(),
+        // scp-like syntax: git@host:owner/repo
+        None => match url.split_once(':') {
+            Some((host, path)) if !host.contains('/') => format!("{}/{}", host, path),
+            _ => url.to_string(),
+        },
+    };
+
+    let rest = match rest.split_once('@') {
+        Some((userinfo, host)) if !userinfo.contains('/') => host.to_string(),
+        _ => rest,
+    };
+
+    let rest = rest.trim_end_matches('/');
+    let rest = rest.strip_suffix(".git").unwrap_or(rest);
+
+    match rest.split_once('/') {
+        Some((host, path)) => format!("{}/{}", host.to_lowercase(), path),
+        None => rest.to_lowercase(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::fixture;
+    use tempfile::TempDir;
+
+    fn commit(repo: &Repository, message: &str) -> git2::Oid {
+        fixture::commit(repo, message, &[])
+    }
+
+    #[test]
+    fn test_normalize_remote_url() {
+        assert_eq!(
+            normalize_remote_url("git@github.com:ducks/noggin.git"),
+            "github.com/ducks/noggin"
+        );
+        assert_eq!(
+            normalize_remote_url("https://GitHub.com/ducks/noggin/"),
+            "github.com/ducks/noggin"
+        );
+        assert_eq!(
+            normalize_remote_url("ssh://git@github.com/ducks/noggin.git"),
+            "github.com/ducks/noggin"
+        );
+        assert_eq!(
+            normalize_remote_url("https://token@github.com/ducks/noggin"),
+            "github.com/ducks/noggin"
+        );
+    }
+
+    #[test]
+    fn test_detect_root_commit_and_origin() {
+        let temp_dir = TempDir::new().unwrap();
+        let repo = Repository::init(temp_dir.path()).unwrap();
+
+        let empty = RepositoryFingerprint::detect(temp_dir.path()).unwrap();
+        assert_eq!(empty.root_commit, None);
+        assert_eq!(empty.origin_url, None);
+
+        let root = commit(&repo, "Initial commit");
+        commit(&repo, "Second commit");
+        repo.remote("origin", "git@github.com:ducks/noggin.git").unwrap();
+
+        let fingerprint = RepositoryFingerprint::detect(temp_dir.path()).unwrap();
+        assert_eq!(fingerprint.root_commit, Some(root.to_string()));
+        assert_eq!(fingerprint.origin_url.as_deref(), Some("github.com/ducks/noggin"));
+    }
+
+    #[test]
+    fn test_check_mismatch() {
+        let stored = RepositoryFingerprint {
+            root_commit: Some("a".repeat(40)),
+            origin_url: Some("github.com/ducks/noggin".to_string()),
+            bound_at: Utc::now(),
+        };
+
+        let mut current = stored.clone();
+        assert_eq!(stored.check(&current), None);
+
+        current.origin_url = None;
+        assert_eq!(stored.check(&current), None);
+
+        current.origin_url = Some("github.com/someone/noggin".to_string());
+        assert!(matches!(
+            stored.check(&current),
+            Some(FingerprintMismatch::Origin { .. })
+        ));
+
+        current.root_commit = Some("b".repeat(40));
+        assert!(matches!(
+            stored.check(&current),
+            Some(FingerprintMismatch::RootCommit { .. })
+        ));
+    }
+}
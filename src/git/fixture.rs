@@ -0,0 +1,36 @@
+//! Git history for tests
+//!
+//! Helpers that commit files on top of HEAD, shared by the test modules
+//! that need a real repository.
+
+use git2::{Commit, Oid, Repository, Signature};
+use std::fs;
+use std::path::Path;
+
+/// Write `files` (path, contents) into the work tree, stage them, and
+/// commit the index on top of HEAD as `author`. The author's email is the
+/// first word of the name, lowercased, at example.com.
+pub fn commit_as(repo: &Repository, author: &str, message: &str, files: &[(&str, &str)]) -> Oid {
+    let root = repo.workdir().expect("test repository has a work tree");
+    let mut index = repo.index().unwrap();
+    for (path, contents) in files {
+        let full = root.join(path);
+        fs::create_dir_all(full.parent().unwrap()).unwrap();
+        fs::write(&full, contents).unwrap();
+        index.add_path(Path::new(path)).unwrap();
+    }
+    index.write().unwrap();
+
+    let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+    let user = author.split_whitespace().next().unwrap_or("test").to_lowercase();
+    let signature = Signature::now(author, &format!("{}@example.com", user)).unwrap();
+    let parent = repo.head().ok().and_then(|h| h.peel_to_commit().ok());
+    let parents: Vec<&Commit> = parent.iter().collect();
+    repo.commit(Some("HEAD"), &signature, &signature, message, &tree, &parents)
+        .unwrap()
+}
+
+/// [`commit_as`] by "Test"
+pub fn commit(repo: &Repository, message: &str, files: &[(&str, &str)]) -> Oid {
+    commit_as(repo, "Test", message, files)
+}
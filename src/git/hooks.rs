@@ -0,0 +1,233 @@
+//! Git hook support: staged-change inspection, commit-message trailers,
+//! and hook installation
+
+use anyhow::{Context, Result};
+use git2::Repository;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Marker line identifying hooks written by noggin
+const HOOK_MARKER: &str = "# Installed by noggin";
+
+/// Files with staged changes (index vs HEAD), including deletions
+pub fn staged_files(repo_path: &Path) -> Result<Vec<String>> {
+    let repo = Repository::open(repo_path)
+        .with_context(|| format!("Failed to open git repository at {}", repo_path.display()))?;
+
+    let head_tree = match repo.head() {
+        Ok(head) => Some(head.peel_to_tree().context("Failed to resolve HEAD tree")?),
+        Err(_) => None, // initial commit
+    };
+    let index = repo.index().context("Failed to read git index")?;
+
+    let diff = repo
+        .diff_tree_to_index(head_tree.as_ref(), Some(&index), None)
+        .context("Failed to diff index against HEAD")?;
+
+    let mut files: Vec<String> = diff
+        .deltas()
+        .flat_map(|delta| [delta.old_file().path(), delta.new_file().path()])
+        .flatten()
+        .map(|p| p.to_string_lossy().into_owned())
+        .collect();
+    files.sort();
+    files.dedup();
+
+    Ok(files)
+}
+
+/// Append `key: value` trailers to a commit message.
+///
+/// Trailers go after the last non-comment line, joining an existing
+/// trailer block if there is one. Values already present are skipped,
+/// so re-running the hook (e.g. on amend) doesn't duplicate them.
+pub fn append_trailers(message: &str, key: &str, values: &[String]) -> String {
+    let lines: Vec<&str> = message.lines().collect();
+    let content_end = lines
+        .iter()
+        .rposition(|l| !l.starts_with('#') && !l.trim().is_empty())
+        .map(|i| i + 1)
+        .unwrap_or(0);
+
+    let (body, rest) = lines.split_at(content_end);
+
+    let new: Vec<String> = values
+        .iter()
+        .map(|v| format!("{}: {}", key, v))
+        .filter(|t| !body.contains(&t.as_str()))
+        .collect();
+    if new.is_empty() {
+        return message.to_string();
+    }
+
+    let last_paragraph: Vec<&&str> = body
+        .iter()
+        .rev()
+        .take_while(|l| !l.trim().is_empty())
+        .collect();
+    let ends_with_trailers = body.len() > last_paragraph.len()
+        && !last_paragraph.is_empty()
+        && last_paragraph.iter().all(|l| is_trailer(l));
+
+    let mut out: Vec<String> = body.iter().map(|l| l.to_string()).collect();
+    if !out.is_empty() && !ends_with_trailers {
+        out.push(String::new());
+    }
+    out.extend(new);
+    out.extend(rest.iter().map(|l| l.to_string()));
+
+    let mut result = out.join("\n");
+    if message.ends_with('\n') || !rest.is_empty() {
+        result.push('\n');
+    }
+    result
+}
+
+/// `Token: value` where the token has no spaces
+fn is_trailer(line: &str) -> bool {
+    match line.split_once(": ") {
+        Some((token, _)) => {
+            !token.is_empty() && token.chars().all(|c| c.is_alphanumeric() || c == '-')
+        }
+        None => false,
+    }
+}
+
+/// Write a hook that runs `noggin hook <name>`.
+///
+/// Refuses to replace a hook noggin didn't write unless `force` is set.
+pub fn install_hook(repo_path: &Path, name: &str, force: bool) -> Result<PathBuf> {
+    let repo = Repository::open(repo_path)
+        .with_context(|| format!("Failed to open git repository at {}", repo_path.display()))?;
+    let hooks_dir = hooks_dir(&repo)?;
+    let hook_path = hooks_dir.join(name);
+
+    if hook_path.exists() && !force {
+        let existing = fs::read_to_string(&hook_path).unwrap_or_default();
+        if !existing.contains(HOOK_MARKER) {
+            anyhow::bail!(
+                "{} already exists and wasn't installed by noggin. Use --force to replace it.",
+                hook_path.display()
+            );
+        }
+    }
+
+    fs::create_dir_all(&hooks_dir)
+        .with_context(|| format!("Failed to create {}", hooks_dir.display()))?;
+
+    // The hook must never block a commit, so failures are swallowed
+    let script = format!(
+        "#!/bin/sh\n{}\ncommand -v noggin >/dev/null 2>&1 || exit 0\nnoggin hook {} \"$@\" || true\n",
+        HOOK_MARKER, name
+    );
+    fs::write(&hook_path, script)
+        .with_context(|| format!("Failed to write {}", hook_path.display()))?;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&hook_path, fs::Permissions::from_mode(0o755))
+            .with_context(|| format!("Failed to make {} executable", hook_path.display()))?;
+    }
+
+    Ok(hook_path)
+}
+
+/// Where git looks for hooks: `core.hooksPath` if set (relative paths are
+/// taken from the top of the working tree, as git does), else `.git/hooks`
+fn hooks_dir(repo: &Repository) -> Result<PathBuf> {
+    let config = repo.config().context("Failed to read git config")?;
+    match config.get_path("core.hooksPath") {
+        Ok(path) if path.is_absolute() => Ok(path),
+        Ok(path) => Ok(repo.workdir().unwrap_or_else(|| repo.path()).join(path)),
+        Err(e) if e.code() == git2::ErrorCode::NotFound => Ok(repo.path().join("hooks")),
+        Err(e) => Err(e).context("Failed to read core.hooksPath"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn trailers(values: &[&str]) -> Vec<String> {
+        values.iter().map(|v| v.to_string()).collect()
+    }
+
+    #[test]
+    fn test_append_to_plain_message() {
+        let msg = "Fix pool timeout\n\n# Please enter the commit message\n# Lines starting with '#' are ignored\n";
+        let out = append_trailers(msg, "Noggin-Knowledge", &trailers(&["decisions/pool.arf"]));
+
+        assert_eq!(
+            out,
+            "Fix pool timeout\n\nNoggin-Knowledge: decisions/pool.arf\n\n# Please enter the commit message\n# Lines starting with '#' are ignored\n"
+        );
+    }
+
+    #[test]
+    fn test_append_joins_existing_trailer_block() {
+        let msg = "Fix pool timeout\n\nSigned-off-by: A <a@example.com>\n";
+        let out = append_trailers(msg, "Noggin-Knowledge", &trailers(&["facts/a.arf"]));
+
+        assert_eq!(
+            out,
+            "Fix pool timeout\n\nSigned-off-by: A <a@example.com>\nNoggin-Knowledge: facts/a.arf\n"
+        );
+    }
+
+    #[test]
+    fn test_append_is_idempotent() {
+        let msg = "Fix\n\nNoggin-Knowledge: facts/a.arf\n";
+        let out = append_trailers(msg, "Noggin-Knowledge", &trailers(&["facts/a.arf"]));
+        assert_eq!(out, msg);
+    }
+
+    #[test]
+    fn test_append_to_empty_message() {
+        let out = append_trailers("", "Noggin-Knowledge", &trailers(&["facts/a.arf"]));
+        assert_eq!(out, "Noggin-Knowledge: facts/a.arf");
+    }
+
+    #[test]
+    fn test_staged_files() {
+        let temp_dir = TempDir::new().unwrap();
+        let repo = Repository::init(temp_dir.path()).unwrap();
+        fs::write(temp_dir.path().join("a.rs"), "fn a() {}\n").unwrap();
+        fs::write(temp_dir.path().join("b.rs"), "fn b() {}\n").unwrap();
+
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new("a.rs")).unwrap();
+        index.write().unwrap();
+
+        assert_eq!(staged_files(temp_dir.path()).unwrap(), vec!["a.rs"]);
+    }
+
+    #[test]
+    fn test_install_refuses_foreign_hook() {
+        let temp_dir = TempDir::new().unwrap();
+        let repo = Repository::init(temp_dir.path()).unwrap();
+        let hook = repo.path().join("hooks/prepare-commit-msg");
+        fs::create_dir_all(hook.parent().unwrap()).unwrap();
+        fs::write(&hook, "#!/bin/sh\necho custom\n").unwrap();
+
+        assert!(install_hook(temp_dir.path(), "prepare-commit-msg", false).is_err());
+
+        install_hook(temp_dir.path(), "prepare-commit-msg", true).unwrap();
+        // Reinstalling over our own hook is fine
+        install_hook(temp_dir.path(), "prepare-commit-msg", false).unwrap();
+        assert!(fs::read_to_string(&hook).unwrap().contains("noggin hook prepare-commit-msg"));
+    }
+
+    #[test]
+    fn test_install_honors_core_hooks_path() {
+        let temp_dir = TempDir::new().unwrap();
+        let repo = Repository::init(temp_dir.path()).unwrap();
+        repo.config().unwrap().set_str("core.hooksPath", ".githooks").unwrap();
+
+        let hook = install_hook(temp_dir.path(), "post-commit", false).unwrap();
+        assert_eq!(hook, temp_dir.path().join(".githooks/post-commit"));
+        assert!(hook.exists());
+        assert!(!repo.path().join("hooks/post-commit").exists());
+    }
+}
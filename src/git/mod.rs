@@ -1,2 +1,12 @@
+pub mod blame;
+pub mod diff;
+#[cfg(test)]
+pub mod fixture;
+pub mod fingerprint;
+pub mod hooks;
+pub mod notes;
+pub mod publish;
+pub mod releases;
+pub mod remote;
 pub mod scoring;
 pub mod walker;
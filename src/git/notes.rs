@@ -0,0 +1,137 @@
+//! Git notes linking processed commits to the knowledge derived from them
+//!
+//! Notes live under `refs/notes/noggin` by default, so
+//! `git log --notes=noggin` shows extracted knowledge inline without
+//! reading `.noggin/`.
+
+use crate::git::publish::signature;
+use anyhow::{Context, Result};
+use git2::{Oid, Repository};
+use std::path::Path;
+
+/// Default notes ref for knowledge links
+pub const DEFAULT_NOTES_REF: &str = "refs/notes/noggin";
+
+/// An ARF derived from a commit
+#[derive(Debug, Clone, PartialEq)]
+pub struct NoteEntry {
+    /// ARF path relative to .noggin/
+    pub path: String,
+    pub what: String,
+}
+
+/// Write (or replace) the knowledge note on `commit_hash`
+pub fn write_knowledge_note(
+    repo_path: &Path,
+    notes_ref: &str,
+    commit_hash: &str,
+    entries: &[NoteEntry],
+) -> Result<()> {
+    let repo = Repository::open(repo_path)
+        .with_context(|| format!("Failed to open git repository at {}", repo_path.display()))?;
+    let oid = Oid::from_str(commit_hash)
+        .with_context(|| format!("Invalid commit hash: {}", commit_hash))?;
+    let sig = signature(&repo)?;
+
+    repo.note(&sig, &sig, Some(notes_ref), oid, &format_note(entries), true)
+        .with_context(|| format!("Failed to write note on {}", commit_hash))?;
+
+    Ok(())
+}
+
+/// Read the knowledge note on `commit_hash`, if any
+pub fn read_knowledge_note(repo_path: &Path, notes_ref: &str, commit_hash: &str) -> Result<Option<String>> {
+    let repo = Repository::open(repo_path)
+        .with_context(|| format!("Failed to open git repository at {}", repo_path.display()))?;
+    let oid = Oid::from_str(commit_hash)
+        .with_context(|| format!("Invalid commit hash: {}", commit_hash))?;
+
+    let message = match repo.find_note(Some(notes_ref), oid) {
+        Ok(note) => note.message().map(str::to_string),
+        Err(e) if e.code() == git2::ErrorCode::NotFound => None,
+        Err(e) => {
+            return Err(e).with_context(|| format!("Failed to read note on {}", commit_hash))
+        }
+    };
+
+    Ok(message)
+}
+
+/// Render the note body: one block per ARF with its path and summary
+fn format_note(entries: &[NoteEntry]) -> String {
+    let mut note = format!(
+        "noggin: {} knowledge {}\n",
+        entries.len(),
+        if entries.len() == 1 { "entry" } else { "entries" }
+    );
+
+    for entry in entries {
+        note.push_str(&format!("\n.noggin/{}\n  {}\n", entry.path, entry.what));
+    }
+
+    note
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn repo_with_commit() -> (TempDir, String) {
+        let temp_dir = TempDir::new().unwrap();
+        let repo = Repository::init(temp_dir.path()).unwrap();
+        let sig = git2::Signature::now("Test", "test@example.com").unwrap();
+        let tree_id = repo.index().unwrap().write_tree().unwrap();
+        let tree = repo.find_tree(tree_id).unwrap();
+        let oid = repo.commit(Some("HEAD"), &sig, &sig, "init", &tree, &[]).unwrap();
+        (temp_dir, oid.to_string())
+    }
+
+    #[test]
+    fn test_write_and_read_note() {
+        let (temp_dir, hash) = repo_with_commit();
+        let entries = vec![NoteEntry {
+            path: "decisions/use-tokio.arf".to_string(),
+            what: "Use tokio for async".to_string(),
+        }];
+
+        write_knowledge_note(temp_dir.path(), DEFAULT_NOTES_REF, &hash, &entries).unwrap();
+        let note = read_knowledge_note(temp_dir.path(), DEFAULT_NOTES_REF, &hash)
+            .unwrap()
+            .unwrap();
+
+        assert!(note.starts_with("noggin: 1 knowledge entry\n"));
+        assert!(note.contains(".noggin/decisions/use-tokio.arf\n  Use tokio for async"));
+    }
+
+    #[test]
+    fn test_rewrite_replaces_note() {
+        let (temp_dir, hash) = repo_with_commit();
+        let first = NoteEntry {
+            path: "facts/a.arf".to_string(),
+            what: "A".to_string(),
+        };
+        let second = NoteEntry {
+            path: "facts/b.arf".to_string(),
+            what: "B".to_string(),
+        };
+
+        write_knowledge_note(temp_dir.path(), DEFAULT_NOTES_REF, &hash, &[first]).unwrap();
+        write_knowledge_note(temp_dir.path(), DEFAULT_NOTES_REF, &hash, &[second.clone(), second])
+            .unwrap();
+
+        let note = read_knowledge_note(temp_dir.path(), DEFAULT_NOTES_REF, &hash)
+            .unwrap()
+            .unwrap();
+        assert!(note.starts_with("noggin: 2 knowledge entries"));
+        assert!(!note.contains("facts/a.arf"));
+    }
+
+    #[test]
+    fn test_missing_note() {
+        let (temp_dir, hash) = repo_with_commit();
+        assert!(read_knowledge_note(temp_dir.path(), DEFAULT_NOTES_REF, &hash)
+            .unwrap()
+            .is_none());
+    }
+}
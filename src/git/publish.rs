@@ -0,0 +1,319 @@
+//! Committing and pushing knowledge updates back to the repository
+//!
+//! Used by `noggin ci` to publish ARF and manifest changes after an
+//! unattended learn run. `.noggin/` is usually gitignored by `noggin init`,
+//! so only the paths asked for are staged, by name, and nothing else under
+//! `.noggin/` (raw responses, caches, logs, config with API keys) is.
+
+use anyhow::{Context, Result};
+use git2::{
+    Cred, Index, IndexEntry, IndexTime, Oid, PushOptions, RemoteCallbacks, Repository, Signature,
+};
+use std::fs;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+/// Username paired with a token for HTTPS pushes (accepted by GitHub)
+const TOKEN_USERNAME: &str = "x-access-token";
+
+/// Commit `paths` (files or directories, relative to the repository) as
+/// they are on disk, ignored or not, on top of HEAD.
+///
+/// The commit is HEAD's tree with only `paths` replaced, so changes the
+/// user has staged stay out of it and stay staged; afterwards the on-disk
+/// index is brought up to date for `paths` alone.
+///
+/// Returns the new commit hash, or None if the tree matches HEAD.
+pub fn commit_paths(repo_path: &Path, paths: &[&str], message: &str) -> Result<Option<String>> {
+    let repo = Repository::open(repo_path)
+        .with_context(|| format!("Failed to open git repository at {}", repo_path.display()))?;
+    let workdir = repo
+        .workdir()
+        .context("Repository has no working directory")?
+        .to_path_buf();
+
+    let parent = match repo.head() {
+        Ok(head) => Some(head.peel_to_commit().context("Failed to resolve HEAD commit")?),
+        Err(_) => None,
+    };
+
+    let mut index = Index::new().context("Failed to create index")?;
+    if let Some(parent) = &parent {
+        index
+            .read_tree(&parent.tree().context("Failed to read HEAD tree")?)
+            .context("Failed to load HEAD tree")?;
+    }
+    for path in paths {
+        stage_path(&repo, &mut index, &workdir, Path::new(path))
+            .with_context(|| format!("Failed to stage {}", path))?;
+    }
+
+    let tree_id = index.write_tree_to(&repo).context("Failed to write tree")?;
+    if let Some(parent) = &parent {
+        if parent.tree_id() == tree_id {
+            return Ok(None);
+        }
+    }
+    let tree = repo.find_tree(tree_id).context("Failed to find written tree")?;
+
+    let signature = signature(&repo)?;
+
+    let parents: Vec<_> = parent.iter().collect();
+    let oid = repo
+        .commit(Some("HEAD"), &signature, &signature, message, &tree, &parents)
+        .context("Failed to create commit")?;
+
+    sync_index(&repo, &index, paths).context("Failed to update git index")?;
+    Ok(Some(oid.to_string()))
+}
+
+/// Replace whatever `index` holds at or under `path` with the files there
+/// on disk
+fn stage_path(repo: &Repository, index: &mut Index, workdir: &Path, path: &Path) -> Result<()> {
+    remove_under(index, path)?;
+
+    let full = workdir.join(path);
+    let files: Vec<PathBuf> = if full.is_dir() {
+        WalkDir::new(&full)
+            .sort_by_file_name()
+            .into_iter()
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.file_type().is_file())
+            .map(|entry| entry.into_path())
+            .collect()
+    } else if full.is_file() {
+        vec![full]
+    } else {
+        Vec::new()
+    };
+
+    for file in files {
+        let relative = file
+            .strip_prefix(workdir)
+            .context("Knowledge file outside the repository")?;
+        let metadata = fs::metadata(&file)?;
+        let id = repo
+            .blob_path(&file)
+            .with_context(|| format!("Failed to store {}", file.display()))?;
+        index.add(&file_entry(relative, id, &metadata))?;
+    }
+    Ok(())
+}
+
+/// Remove the entry at `path` and every entry under it
+fn remove_under(index: &mut Index, path: &Path) -> Result<()> {
+    index.remove_dir(path, 0)?;
+    if index.get_path(path, 0).is_some() {
+        index.remove_path(path)?;
+    }
+    Ok(())
+}
+
+/// An index entry for a regular file stored as blob `id`
+fn file_entry(relative: &Path, id: Oid, metadata: &fs::Metadata) -> IndexEntry {
+    let path = relative.to_string_lossy().replace('\\', "/").into_bytes();
+    #[cfg(unix)]
+    let executable = {
+        use std::os::unix::fs::PermissionsExt;
+        metadata.permissions().mode() & 0o111 != 0
+    };
+    #[cfg(not(unix))]
+    let executable = false;
+    IndexEntry {
+        ctime: IndexTime::new(0, 0),
+        mtime: IndexTime::new(0, 0),
+        dev: 0,
+        ino: 0,
+        mode: if executable { 0o100755 } else { 0o100644 },
+        uid: 0,
+        gid: 0,
+        file_size: metadata.len() as u32,
+        id,
+        flags: path.len().min(0xfff) as u16,
+        flags_extended: 0,
+        path,
+    }
+}
+
+/// Make the on-disk index match the committed `index` for `paths`,
+/// leaving every other entry (and anything staged there) alone
+fn sync_index(repo: &Repository, index: &Index, paths: &[&str]) -> Result<()> {
+    let mut disk = repo.index()?;
+    for path in paths {
+        remove_under(&mut disk, Path::new(path))?;
+        let prefix = format!("{}/", path.trim_end_matches('/'));
+        for entry in index.iter() {
+            if entry.path == path.as_bytes() || entry.path.starts_with(prefix.as_bytes()) {
+                disk.add(&entry)?;
+            }
+        }
+    }
+    disk.write()?;
+    Ok(())
+}
+
+/// Signature from the repository's git config, falling back to "noggin"
+/// on CI machines with no user configured
+pub fn signature(repo: &Repository) -> Result<Signature<'static>> {
+    repo.signature()
+        .or_else(|_| Signature::now("noggin", "noggin@localhost"))
+        .context("Failed to create commit signature")
+}
+
+/// Push `branch` (or the current branch) to `remote`, plus any
+/// `extra_refs` (e.g. a notes ref) under the same name.
+///
+/// When `token` is set it is offered as HTTPS credentials; otherwise the
+/// push relies on the remote needing no authentication.
+pub fn push_with_token(
+    repo_path: &Path,
+    remote: &str,
+    branch: Option<&str>,
+    extra_refs: &[&str],
+    token: Option<&str>,
+) -> Result<()> {
+    let repo = Repository::open(repo_path)
+        .with_context(|| format!("Failed to open git repository at {}", repo_path.display()))?;
+
+    let branch = match branch {
+        Some(b) => b.to_string(),
+        None => {
+            let head = repo.head().context("Failed to resolve HEAD")?;
+            if !head.is_branch() {
+                anyhow::bail!("HEAD is detached; pass --branch to choose where to push");
+            }
+            head.shorthand()
+                .context("Current branch name is not valid UTF-8")?
+                .to_string()
+        }
+    };
+
+    let mut remote_handle = repo
+        .find_remote(remote)
+        .with_context(|| format!("Remote '{}' not found", remote))?;
+
+    let mut callbacks = RemoteCallbacks::new();
+    if let Some(token) = token {
+        let token = token.to_string();
+        callbacks.credentials(move |_url, _username, _allowed| {
+            Cred::userpass_plaintext(TOKEN_USERNAME, &token)
+        });
+    }
+
+    let mut push_options = PushOptions::new();
+    push_options.remote_callbacks(callbacks);
+
+    let mut refspecs = vec![format!("HEAD:refs/heads/{}", branch)];
+    for reference in extra_refs {
+        if repo.find_reference(reference).is_ok() {
+            refspecs.push(format!("{0}:{0}", reference));
+        }
+    }
+
+    remote_handle
+        .push(&refspecs, Some(&mut push_options))
+        .with_context(|| format!("Failed to push to {}/{}", remote, branch))?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn init_repo() -> (TempDir, Repository) {
+        let temp_dir = TempDir::new().unwrap();
+        let repo = Repository::init(temp_dir.path()).unwrap();
+        {
+            let mut config = repo.config().unwrap();
+            config.set_str("user.name", "Test User").unwrap();
+            config.set_str("user.email", "test@example.com").unwrap();
+        }
+
+        fs::write(temp_dir.path().join(".gitignore"), ".noggin/\n").unwrap();
+        commit_paths(temp_dir.path(), &[".gitignore"], "Initial commit").unwrap();
+
+        (temp_dir, repo)
+    }
+
+    #[test]
+    fn test_commit_ignored_paths() {
+        let (temp_dir, repo) = init_repo();
+        let noggin = temp_dir.path().join(".noggin");
+        fs::create_dir_all(noggin.join("facts")).unwrap();
+        fs::create_dir_all(noggin.join("raw")).unwrap();
+        fs::write(noggin.join("facts/a.arf"), "what = \"a\"\n").unwrap();
+        fs::write(noggin.join("raw/response.txt"), "raw").unwrap();
+        fs::write(noggin.join("config.toml"), "api_key = \"secret\"\n").unwrap();
+
+        let hash = commit_paths(temp_dir.path(), &[".noggin/facts"], "Update knowledge").unwrap();
+        assert!(hash.is_some());
+
+        let head = repo.head().unwrap().peel_to_commit().unwrap();
+        assert_eq!(head.message(), Some("Update knowledge"));
+        let tree = head.tree().unwrap();
+        assert!(tree.get_path(Path::new(".noggin/facts/a.arf")).is_ok());
+        assert!(tree.get_path(Path::new(".noggin/raw")).is_err());
+        assert!(tree.get_path(Path::new(".noggin/config.toml")).is_err());
+
+        // A removed entry is removed from the next commit
+        fs::remove_file(noggin.join("facts/a.arf")).unwrap();
+        fs::write(noggin.join("facts/b.arf"), "what = \"b\"\n").unwrap();
+        commit_paths(temp_dir.path(), &[".noggin/facts"], "Replace knowledge").unwrap();
+        let tree = repo.head().unwrap().peel_to_commit().unwrap().tree().unwrap();
+        assert!(tree.get_path(Path::new(".noggin/facts/a.arf")).is_err());
+        assert!(tree.get_path(Path::new(".noggin/facts/b.arf")).is_ok());
+    }
+
+    #[test]
+    fn test_commit_leaves_user_staging_alone() {
+        let (temp_dir, repo) = init_repo();
+        fs::write(temp_dir.path().join("wip.rs"), "fn wip() {}\n").unwrap();
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new("wip.rs")).unwrap();
+        index.write().unwrap();
+        fs::create_dir_all(temp_dir.path().join(".noggin/facts")).unwrap();
+        fs::write(temp_dir.path().join(".noggin/facts/a.arf"), "what = \"a\"\n").unwrap();
+
+        commit_paths(temp_dir.path(), &[".noggin/facts"], "Update knowledge").unwrap();
+
+        let tree = repo.head().unwrap().peel_to_commit().unwrap().tree().unwrap();
+        assert!(tree.get_path(Path::new(".noggin/facts/a.arf")).is_ok());
+        assert!(tree.get_path(Path::new("wip.rs")).is_err());
+        // Still staged, and the published file isn't staged for deletion
+        let statuses = repo.statuses(None).unwrap();
+        let staged: Vec<(String, git2::Status)> = statuses
+            .iter()
+            .map(|s| (s.path().unwrap().to_string(), s.status()))
+            .filter(|(_, status)| !status.is_wt_new())
+            .collect();
+        assert_eq!(staged, vec![("wip.rs".to_string(), git2::Status::INDEX_NEW)]);
+    }
+
+    #[test]
+    fn test_commit_without_changes_is_noop() {
+        let (temp_dir, _repo) = init_repo();
+        fs::create_dir_all(temp_dir.path().join(".noggin")).unwrap();
+        fs::write(temp_dir.path().join(".noggin/manifest.toml"), "").unwrap();
+
+        let paths = [".noggin/manifest.toml"];
+        assert!(commit_paths(temp_dir.path(), &paths, "first").unwrap().is_some());
+        assert!(commit_paths(temp_dir.path(), &paths, "second").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_push_to_local_remote() {
+        let (temp_dir, repo) = init_repo();
+        let remote_dir = TempDir::new().unwrap();
+        let bare = Repository::init_bare(remote_dir.path()).unwrap();
+        repo.remote("origin", remote_dir.path().to_str().unwrap()).unwrap();
+
+        push_with_token(temp_dir.path(), "origin", Some("main"), &[], None).unwrap();
+
+        let local_head = repo.head().unwrap().target().unwrap();
+        let pushed = bare.find_reference("refs/heads/main").unwrap().target().unwrap();
+        assert_eq!(local_head, pushed);
+    }
+}
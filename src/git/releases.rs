@@ -0,0 +1,207 @@
+//! Release tags, for analyzing history one release at a time
+//!
+//! [`release_tags`] lists tags matching `history.release_tags` in the order
+//! they were cut, and [`group_by_release`] buckets commits under the first
+//! release that contains them. `learn --by-release` sends one prompt per
+//! bucket, so entries describe what changed between two releases rather
+//! than commit by commit.
+
+use crate::git::walker::CommitMetadata;
+use anyhow::{Context, Result};
+use git2::{Oid, Repository};
+use std::collections::HashMap;
+
+/// `context` key holding the release an entry describes
+pub const RELEASE_KEY: &str = "release";
+
+/// A release tag
+#[derive(Debug, Clone, PartialEq)]
+pub struct Release {
+    pub tag: String,
+    /// Commit the tag points at
+    pub commit: String,
+    /// Commit time (Unix timestamp)
+    pub timestamp: i64,
+    /// First line of an annotated tag's message
+    pub message: Option<String>,
+}
+
+/// Commits first released in `release`, or not released yet when it is None
+#[derive(Debug, Clone)]
+pub struct ReleaseGroup {
+    /// Tag of the release before this one
+    pub previous: Option<String>,
+    pub release: Option<Release>,
+    /// In walk order
+    pub commits: Vec<CommitMetadata>,
+}
+
+impl ReleaseGroup {
+    /// Heading such as "Changes between v1.2 and v1.3"
+    pub fn title(&self) -> String {
+        match (&self.previous, &self.release) {
+            (Some(previous), Some(release)) => {
+                format!("Changes between {} and {}", previous, release.tag)
+            }
+            (None, Some(release)) => format!("Changes up to {}", release.tag),
+            (Some(previous), None) => format!("Unreleased changes since {}", previous),
+            (None, None) => "Unreleased changes".to_string(),
+        }
+    }
+}
+
+/// Tags matching the glob `pattern` that point at a commit, oldest commit
+/// first
+pub fn release_tags(repo: &Repository, pattern: &str) -> Result<Vec<Release>> {
+    let names = repo
+        .tag_names(Some(pattern))
+        .context("Failed to list tags")?;
+    let mut releases = Vec::new();
+    for name in names.iter().flatten() {
+        let Ok(reference) = repo.find_reference(&format!("refs/tags/{}", name)) else {
+            continue;
+        };
+        let Ok(commit) = reference.peel_to_commit() else {
+            continue;
+        };
+        let message = reference
+            .peel_to_tag()
+            .ok()
+            .and_then(|tag| tag.message().map(|m| m.lines().next().unwrap_or("").trim().to_string()))
+            .filter(|m| !m.is_empty());
+        releases.push(Release {
+            tag: name.to_string(),
+            commit: commit.id().to_string(),
+            timestamp: commit.time().seconds(),
+            message,
+        });
+    }
+    releases.sort_by(|a, b| a.timestamp.cmp(&b.timestamp).then_with(|| a.tag.cmp(&b.tag)));
+    Ok(releases)
+}
+
+/// Bucket `commits` under the oldest of `releases` that contains each.
+/// Groups come in release order, unreleased commits last; releases with no
+/// commits among `commits` are left out.
+pub fn group_by_release(
+    repo: &Repository,
+    releases: &[Release],
+    commits: Vec<CommitMetadata>,
+) -> Vec<ReleaseGroup> {
+    let first_release = first_releases(repo, releases);
+    let mut buckets: Vec<Vec<CommitMetadata>> = vec![Vec::new(); releases.len() + 1];
+    for commit in commits {
+        let index = Oid::from_str(&commit.hash)
+            .ok()
+            .and_then(|oid| first_release.get(&oid).copied());
+        buckets[index.unwrap_or(releases.len())].push(commit);
+    }
+
+    buckets
+        .into_iter()
+        .enumerate()
+        .filter(|(_, commits)| !commits.is_empty())
+        .map(|(i, commits)| ReleaseGroup {
+            previous: i.checked_sub(1).map(|p| releases[p].tag.clone()),
+            release: releases.get(i).cloned(),
+            commits,
+        })
+        .collect()
+}
+
+/// Map each commit in a release to the index of the oldest release
+/// containing it, walking each release's history back to the releases
+/// before it, so every commit is visited once
+fn first_releases(repo: &Repository, releases: &[Release]) -> HashMap<Oid, usize> {
+    let oids: Vec<Option<Oid>> = releases
+        .iter()
+        .map(|r| Oid::from_str(&r.commit).ok())
+        .collect();
+    let mut first_release = HashMap::new();
+    for (index, oid) in oids.iter().enumerate() {
+        let Some(oid) = oid else {
+            continue;
+        };
+        let Ok(mut revwalk) = repo.revwalk() else {
+            continue;
+        };
+        if revwalk.push(*oid).is_err() {
+            continue;
+        }
+        for earlier in oids[..index].iter().flatten() {
+            let _ = revwalk.hide(*earlier);
+        }
+        // Missing parents in a shallow clone end the walk early
+        for commit in revwalk.map_while(|r| r.ok()) {
+            first_release.entry(commit).or_insert(index);
+        }
+    }
+    first_release
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::fixture;
+    use crate::text::short_hash;
+    use git2::Signature;
+    use tempfile::TempDir;
+
+    fn commit(repo: &Repository, message: &str) -> Oid {
+        fixture::commit(repo, message, &[])
+    }
+
+    fn metadata(oid: Oid) -> CommitMetadata {
+        CommitMetadata {
+            hash: oid.to_string(),
+            short_hash: short_hash(&oid.to_string()).to_string(),
+            author: "Test".to_string(),
+            timestamp: 0,
+            message: String::new(),
+            message_summary: String::new(),
+            files_changed: 0,
+            insertions: 0,
+            deletions: 0,
+            parent_hashes: Vec::new(),
+            shallow_boundary: false,
+            branch: None,
+        }
+    }
+
+    #[test]
+    fn test_group_commits_by_release() {
+        let temp_dir = TempDir::new().unwrap();
+        let repo = Repository::init(temp_dir.path()).unwrap();
+        let sig = Signature::now("Test", "test@example.com").unwrap();
+
+        let first = commit(&repo, "first");
+        let v1 = repo.find_object(first, None).unwrap();
+        repo.tag("v1.0", &v1, &sig, "First release\n\nNotes", false).unwrap();
+        let second = commit(&repo, "second");
+        let third = commit(&repo, "third");
+        repo.tag_lightweight("v1.1", &repo.find_object(third, None).unwrap(), false)
+            .unwrap();
+        repo.tag_lightweight("noggin/snap", &repo.find_object(third, None).unwrap(), false)
+            .unwrap();
+        let fourth = commit(&repo, "fourth");
+
+        let releases = release_tags(&repo, "v*").unwrap();
+        let tags: Vec<&str> = releases.iter().map(|r| r.tag.as_str()).collect();
+        assert_eq!(tags, vec!["v1.0", "v1.1"]);
+        assert_eq!(releases[0].message.as_deref(), Some("First release"));
+        assert_eq!(releases[1].message, None);
+
+        let commits = [first, second, third, fourth].map(metadata).to_vec();
+        let groups = group_by_release(&repo, &releases, commits);
+        let summary: Vec<(String, usize)> =
+            groups.iter().map(|g| (g.title(), g.commits.len())).collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("Changes up to v1.0".to_string(), 1),
+                ("Changes between v1.0 and v1.1".to_string(), 2),
+                ("Unreleased changes since v1.1".to_string(), 1),
+            ]
+        );
+    }
+}
@@ -0,0 +1,138 @@
+//! Cloning remote repositories for analysis without a local checkout
+//!
+//! `noggin learn --remote <url>` keeps one clone per URL under the user
+//! cache directory and refreshes it on later runs. Clones are shallow by
+//! default; the walker treats the truncated history as complete.
+
+use crate::git::fingerprint::normalize_remote_url;
+use anyhow::{Context, Result};
+use git2::build::{CheckoutBuilder, RepoBuilder};
+use git2::{FetchOptions, Repository, ResetType};
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Commits fetched when no depth is given
+pub const DEFAULT_CLONE_DEPTH: u32 = 100;
+
+/// Cache directory for remote clones: `$XDG_CACHE_HOME/noggin/repos`,
+/// falling back to `~/.cache/noggin/repos`
+pub fn default_cache_dir() -> Result<PathBuf> {
+    let base = match env::var_os("XDG_CACHE_HOME").filter(|v| !v.is_empty()) {
+        Some(dir) => PathBuf::from(dir),
+        None => {
+            let home = env::var_os("HOME").context("Neither XDG_CACHE_HOME nor HOME is set")?;
+            PathBuf::from(home).join(".cache")
+        }
+    };
+    Ok(base.join("noggin").join("repos"))
+}
+
+/// Directory name for a URL's clone, e.g. `github.com-ducks-noggin`
+pub fn cache_key(url: &str) -> String {
+    normalize_remote_url(url)
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '.' || c == '_' { c } else { '-' })
+        .collect()
+}
+
+/// Repository name from a URL, e.g. `noggin`
+pub fn repo_name(url: &str) -> String {
+    let normalized = normalize_remote_url(url);
+    normalized
+        .rsplit('/')
+        .next()
+        .filter(|name| !name.is_empty())
+        .unwrap_or("repo")
+        .to_string()
+}
+
+/// Clone `url` into `cache_dir`, or fetch and reset an existing clone to
+/// the remote's default branch. `depth` of 0 fetches full history.
+/// Returns the path of the working copy.
+pub fn clone_or_update(url: &str, cache_dir: &Path, depth: u32) -> Result<PathBuf> {
+    let path = cache_dir.join(cache_key(url));
+
+    if path.join(".git").exists() {
+        update_clone(&path, depth)
+            .with_context(|| format!("Failed to update cached clone at {}", path.display()))?;
+        return Ok(path);
+    }
+
+    fs::create_dir_all(cache_dir)
+        .with_context(|| format!("Failed to create cache directory {}", cache_dir.display()))?;
+
+    RepoBuilder::new()
+        .fetch_options(fetch_options(depth))
+        .clone(url, &path)
+        .with_context(|| format!("Failed to clone {}", url))?;
+
+    Ok(path)
+}
+
+fn update_clone(path: &Path, depth: u32) -> Result<()> {
+    let repo = Repository::open(path)?;
+    let mut remote = repo.find_remote("origin").context("Cached clone has no origin")?;
+
+    remote
+        .fetch(&["HEAD"], Some(&mut fetch_options(depth)), None)
+        .context("Failed to fetch origin")?;
+
+    let fetch_head = repo
+        .find_reference("FETCH_HEAD")
+        .context("Fetch did not produce FETCH_HEAD")?;
+    let target = fetch_head.peel_to_commit()?;
+
+    repo.reset(
+        target.as_object(),
+        ResetType::Hard,
+        Some(CheckoutBuilder::new().force()),
+    )
+    .context("Failed to reset to fetched HEAD")?;
+
+    Ok(())
+}
+
+fn fetch_options<'a>(depth: u32) -> FetchOptions<'a> {
+    let mut options = FetchOptions::new();
+    if depth > 0 {
+        options.depth(depth.min(i32::MAX as u32) as i32);
+    }
+    options
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::fixture;
+    use tempfile::TempDir;
+
+    fn commit_file(repo: &Repository, name: &str, message: &str) {
+        fixture::commit(repo, message, &[(name, message)]);
+    }
+
+    #[test]
+    fn test_cache_key_and_repo_name() {
+        assert_eq!(cache_key("git@github.com:ducks/noggin.git"), "github.com-ducks-noggin");
+        assert_eq!(cache_key("https://github.com/ducks/noggin"), "github.com-ducks-noggin");
+        assert_eq!(repo_name("https://github.com/ducks/noggin.git"), "noggin");
+    }
+
+    #[test]
+    fn test_clone_then_update() {
+        let origin_dir = TempDir::new().unwrap();
+        let origin = Repository::init(origin_dir.path()).unwrap();
+        commit_file(&origin, "a.txt", "first");
+
+        let cache_dir = TempDir::new().unwrap();
+        let url = origin_dir.path().to_str().unwrap();
+
+        let clone = clone_or_update(url, cache_dir.path(), 0).unwrap();
+        assert_eq!(fs::read_to_string(clone.join("a.txt")).unwrap(), "first");
+
+        commit_file(&origin, "b.txt", "second");
+        let again = clone_or_update(url, cache_dir.path(), 0).unwrap();
+        assert_eq!(again, clone);
+        assert_eq!(fs::read_to_string(clone.join("b.txt")).unwrap(), "second");
+    }
+}
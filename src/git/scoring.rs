@@ -1,7 +1,13 @@
 //! Commit significance scoring based on diff size, file patterns, and message keywords.
+//!
+//! Files .gitattributes marks generated, vendored, or export-ignore (see
+//! [`EXCLUDING_ATTRIBUTES`](crate::learn::generated::EXCLUDING_ATTRIBUTES))
+//! count as trivial changes and never match a file pattern, so a vendored
+//! `src/` tree doesn't make a dependency bump look like core work.
 
+use crate::learn::generated::has_excluding_attribute;
 use git2::{Commit, Diff, Repository};
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 use std::collections::HashMap;
 
 /// Categories of commit significance
@@ -55,71 +61,136 @@ pub struct CommitScore {
     pub factors: Vec<ScoreFactor>,
 }
 
-/// Configuration for commit scoring
+/// Configuration for commit scoring, the `[scoring]` section of
+/// `.noggin/config.toml`
+///
+/// Weights left out keep their defaults. Entries under
+/// `[scoring.file_patterns]` and `[scoring.message_keywords]` are added to
+/// the built-in ones, replacing any with the same key; a weight of 0 turns
+/// a built-in off.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ScoringConfig {
+    #[serde(default = "default_diff_weight")]
     pub diff_weight: f32,
+    #[serde(default = "default_pattern_weight")]
     pub pattern_weight: f32,
+    #[serde(default = "default_message_weight")]
     pub message_weight: f32,
+    /// Path substrings and how significant a change under them is
+    #[serde(default = "default_file_patterns", deserialize_with = "merge_file_patterns")]
     pub file_patterns: HashMap<String, f32>,
+    /// Commit message keywords (case-insensitive) and their significance
+    #[serde(default = "default_message_keywords", deserialize_with = "merge_message_keywords")]
     pub message_keywords: HashMap<String, f32>,
+    /// Count files .gitattributes marks `linguist-generated`,
+    /// `linguist-vendored`, or `export-ignore` as trivial changes. Separate
+    /// from `generated.linguist`, which decides whether learn analyzes them.
+    #[serde(default = "default_honor_attributes")]
+    pub honor_attributes: bool,
+}
+
+fn default_diff_weight() -> f32 {
+    0.3
+}
+
+fn default_pattern_weight() -> f32 {
+    0.4
+}
+
+fn default_message_weight() -> f32 {
+    0.3
+}
+
+fn default_honor_attributes() -> bool {
+    true
+}
+
+fn default_file_patterns() -> HashMap<String, f32> {
+    let mut file_patterns = HashMap::new();
+    file_patterns.insert("migrations/".to_string(), 1.0);
+    file_patterns.insert("schema/".to_string(), 1.0);
+    file_patterns.insert("core/".to_string(), 1.0);
+    file_patterns.insert("lib/fundamentals/".to_string(), 1.0);
+    file_patterns.insert("security/".to_string(), 1.0);
+    file_patterns.insert("src/".to_string(), 0.8);
+    file_patterns.insert("app/models/".to_string(), 0.8);
+    file_patterns.insert("app/controllers/".to_string(), 0.8);
+    file_patterns.insert("config/".to_string(), 0.8);
+    file_patterns.insert("tests/".to_string(), 0.5);
+    file_patterns.insert("specs/".to_string(), 0.5);
+    file_patterns.insert("test/".to_string(), 0.5);
+    file_patterns.insert("spec/".to_string(), 0.5);
+    file_patterns.insert("docs/architecture/".to_string(), 0.5);
+    file_patterns.insert("docs/".to_string(), 0.3);
+    file_patterns.insert("README".to_string(), 0.3);
+    file_patterns.insert("examples/".to_string(), 0.3);
+    file_patterns.insert(".gitignore".to_string(), 0.1);
+    file_patterns.insert(".editorconfig".to_string(), 0.1);
+    file_patterns
+}
+
+fn default_message_keywords() -> HashMap<String, f32> {
+    let mut message_keywords = HashMap::new();
+    message_keywords.insert("breaking change".to_string(), 1.0);
+    message_keywords.insert("security fix".to_string(), 1.0);
+    message_keywords.insert("cve-".to_string(), 1.0);
+    message_keywords.insert("vulnerability".to_string(), 1.0);
+    message_keywords.insert("refactor".to_string(), 0.8);
+    message_keywords.insert("architecture".to_string(), 0.8);
+    message_keywords.insert("migration".to_string(), 0.8);
+    message_keywords.insert("deprecate".to_string(), 0.8);
+    message_keywords.insert("feature".to_string(), 0.6);
+    message_keywords.insert("enhancement".to_string(), 0.6);
+    message_keywords.insert("optimize".to_string(), 0.6);
+    message_keywords.insert("performance".to_string(), 0.6);
+    message_keywords.insert("fix".to_string(), 0.4);
+    message_keywords.insert("bug".to_string(), 0.4);
+    message_keywords.insert("update".to_string(), 0.4);
+    message_keywords.insert("typo".to_string(), 0.2);
+    message_keywords.insert("whitespace".to_string(), 0.2);
+    message_keywords.insert("formatting".to_string(), 0.2);
+    message_keywords.insert("docs".to_string(), 0.2);
+    message_keywords
+}
+
+fn merge_file_patterns<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<HashMap<String, f32>, D::Error> {
+    let mut file_patterns = default_file_patterns();
+    file_patterns.extend(HashMap::<String, f32>::deserialize(deserializer)?);
+    Ok(file_patterns)
+}
+
+/// Keywords are matched lowercased, so `Refactor` replaces `refactor`
+fn merge_message_keywords<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<HashMap<String, f32>, D::Error> {
+    let mut message_keywords = default_message_keywords();
+    message_keywords.extend(
+        HashMap::<String, f32>::deserialize(deserializer)?
+            .into_iter()
+            .map(|(keyword, score)| (keyword.to_lowercase(), score)),
+    );
+    Ok(message_keywords)
 }
 
 impl Default for ScoringConfig {
     fn default() -> Self {
-        let mut file_patterns = HashMap::new();
-        file_patterns.insert("migrations/".to_string(), 1.0);
-        file_patterns.insert("schema/".to_string(), 1.0);
-        file_patterns.insert("core/".to_string(), 1.0);
-        file_patterns.insert("lib/fundamentals/".to_string(), 1.0);
-        file_patterns.insert("security/".to_string(), 1.0);
-        file_patterns.insert("src/".to_string(), 0.8);
-        file_patterns.insert("app/models/".to_string(), 0.8);
-        file_patterns.insert("app/controllers/".to_string(), 0.8);
-        file_patterns.insert("config/".to_string(), 0.8);
-        file_patterns.insert("tests/".to_string(), 0.5);
-        file_patterns.insert("specs/".to_string(), 0.5);
-        file_patterns.insert("test/".to_string(), 0.5);
-        file_patterns.insert("spec/".to_string(), 0.5);
-        file_patterns.insert("docs/architecture/".to_string(), 0.5);
-        file_patterns.insert("docs/".to_string(), 0.3);
-        file_patterns.insert("README".to_string(), 0.3);
-        file_patterns.insert("examples/".to_string(), 0.3);
-        file_patterns.insert(".gitignore".to_string(), 0.1);
-        file_patterns.insert(".editorconfig".to_string(), 0.1);
-        
-        let mut message_keywords = HashMap::new();
-        message_keywords.insert("breaking change".to_string(), 1.0);
-        message_keywords.insert("security fix".to_string(), 1.0);
-        message_keywords.insert("cve-".to_string(), 1.0);
-        message_keywords.insert("vulnerability".to_string(), 1.0);
-        message_keywords.insert("refactor".to_string(), 0.8);
-        message_keywords.insert("architecture".to_string(), 0.8);
-        message_keywords.insert("migration".to_string(), 0.8);
-        message_keywords.insert("deprecate".to_string(), 0.8);
-        message_keywords.insert("feature".to_string(), 0.6);
-        message_keywords.insert("enhancement".to_string(), 0.6);
-        message_keywords.insert("optimize".to_string(), 0.6);
-        message_keywords.insert("performance".to_string(), 0.6);
-        message_keywords.insert("fix".to_string(), 0.4);
-        message_keywords.insert("bug".to_string(), 0.4);
-        message_keywords.insert("update".to_string(), 0.4);
-        message_keywords.insert("typo".to_string(), 0.2);
-        message_keywords.insert("whitespace".to_string(), 0.2);
-        message_keywords.insert("formatting".to_string(), 0.2);
-        message_keywords.insert("docs".to_string(), 0.2);
-        
         Self {
-            diff_weight: 0.3,
-            pattern_weight: 0.4,
-            message_weight: 0.3,
-            file_patterns,
-            message_keywords,
+            diff_weight: default_diff_weight(),
+            pattern_weight: default_pattern_weight(),
+            message_weight: default_message_weight(),
+            file_patterns: default_file_patterns(),
+            message_keywords: default_message_keywords(),
+            honor_attributes: default_honor_attributes(),
         }
     }
 }
 
-/// Score a commit's significance
+/// Score a commit's significance.
+///
+/// Critical message keywords (score 1.0, e.g. BREAKING CHANGE, CVE-)
+/// override a low diff size and always yield at least Critical.
 pub fn score_commit(
     repo: &Repository,
     commit: &Commit,
@@ -127,13 +198,17 @@ pub fn score_commit(
 ) -> anyhow::Result<CommitScore> {
     let mut factors = Vec::new();
     
-    let diff_score = score_diff_size(repo, commit, &mut factors)?;
+    let diff_score = score_diff_size(repo, commit, config, &mut factors)?;
     let pattern_score = score_file_patterns(repo, commit, config, &mut factors)?;
     let message_score = score_message(commit, config, &mut factors);
     
-    let significance = (diff_score * config.diff_weight)
+    let mut significance = (diff_score * config.diff_weight)
         + (pattern_score * config.pattern_weight)
         + (message_score * config.message_weight);
+
+    if message_score >= 1.0 {
+        significance = significance.max(0.8);
+    }
     
     let category = ScoreCategory::from_score(significance);
     
@@ -147,24 +222,17 @@ pub fn score_commit(
 fn score_diff_size(
     repo: &Repository,
     commit: &Commit,
+    config: &ScoringConfig,
     factors: &mut Vec<ScoreFactor>,
 ) -> anyhow::Result<f32> {
-    let parent_count = commit.parent_count();
-    
-    if parent_count == 0 || parent_count > 1 {
+    let Some(diff) = first_parent_diff(repo, commit)? else {
         return Ok(0.5);
-    }
-    
-    let parent = commit.parent(0)?;
-    let parent_tree = parent.tree()?;
-    let commit_tree = commit.tree()?;
-    
-    let diff = repo.diff_tree_to_tree(Some(&parent_tree), Some(&commit_tree), None)?;
+    };
     let stats = diff.stats()?;
     
     let total_lines = stats.insertions() + stats.deletions();
     
-    let is_trivial_change = is_trivial_diff(&diff)?;
+    let is_trivial_change = is_trivial_diff(repo, &diff, config)?;
     let multiplier = if is_trivial_change { 0.5 } else { 1.0 };
     
     let base_score = match total_lines {
@@ -185,7 +253,26 @@ fn score_diff_size(
     Ok(score)
 }
 
-fn is_trivial_diff(diff: &Diff) -> anyhow::Result<bool> {
+/// Diff a commit against its parent (or the empty tree for a root commit).
+/// Returns None for merge commits, which have no single meaningful diff.
+fn first_parent_diff<'r>(repo: &'r Repository, commit: &Commit) -> anyhow::Result<Option<Diff<'r>>> {
+    let parent_tree = match commit.parent_count() {
+        0 => None,
+        1 => Some(commit.parent(0)?.tree()?),
+        _ => return Ok(None),
+    };
+    let commit_tree = commit.tree()?;
+
+    let diff = repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&commit_tree), None)?;
+    Ok(Some(diff))
+}
+
+/// True if `path` is excluded by .gitattributes and `config` honors that
+fn is_excluded(repo: &Repository, path: &std::path::Path, config: &ScoringConfig) -> bool {
+    config.honor_attributes && has_excluding_attribute(repo, path)
+}
+
+fn is_trivial_diff(repo: &Repository, diff: &Diff, config: &ScoringConfig) -> anyhow::Result<bool> {
     let stats = diff.stats()?;
     let total = stats.insertions() + stats.deletions();
     
@@ -200,10 +287,11 @@ fn is_trivial_diff(diff: &Diff) -> anyhow::Result<bool> {
         &mut |delta, _| {
             total_files += 1;
             if let Some(path) = delta.new_file().path() {
-                if let Some(ext) = path.extension() {
-                    if ext == "md" || ext == "txt" || ext == "rst" {
-                        trivial_files += 1;
-                    }
+                let docs = path
+                    .extension()
+                    .is_some_and(|ext| ext == "md" || ext == "txt" || ext == "rst");
+                if docs || is_excluded(repo, path, config) {
+                    trivial_files += 1;
                 }
             }
             true
@@ -222,17 +310,9 @@ fn score_file_patterns(
     config: &ScoringConfig,
     factors: &mut Vec<ScoreFactor>,
 ) -> anyhow::Result<f32> {
-    let parent_count = commit.parent_count();
-    
-    if parent_count == 0 || parent_count > 1 {
+    let Some(diff) = first_parent_diff(repo, commit)? else {
         return Ok(0.5);
-    }
-    
-    let parent = commit.parent(0)?;
-    let parent_tree = parent.tree()?;
-    let commit_tree = commit.tree()?;
-    
-    let diff = repo.diff_tree_to_tree(Some(&parent_tree), Some(&commit_tree), None)?;
+    };
     
     let mut max_score = 0.0;
     let mut max_pattern = String::new();
@@ -240,6 +320,9 @@ fn score_file_patterns(
     diff.foreach(
         &mut |delta, _| {
             if let Some(path) = delta.new_file().path() {
+                if is_excluded(repo, path, config) {
+                    return true;
+                }
                 let path_str = path.to_string_lossy();
                 
                 for (pattern, score) in &config.file_patterns {
@@ -297,7 +380,50 @@ fn score_message(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::git::fixture;
+    use git2::Oid;
+    use tempfile::TempDir;
+
+    /// Commit `files` (path, contents) on top of HEAD
+    fn commit(repo: &Repository, message: &str, files: &[(&str, &str)]) -> Oid {
+        fixture::commit(repo, message, files)
+    }
+
+    #[test]
+    fn test_root_commit_scored_against_empty_tree() {
+        let temp_dir = TempDir::new().unwrap();
+        let repo = Repository::init(temp_dir.path()).unwrap();
+        let oid = commit(&repo, "Initial schema", &[("migrations/001.sql", "create table users;\n")]);
+
+        let score = score_commit(&repo, &repo.find_commit(oid).unwrap(), &ScoringConfig::default())
+            .unwrap();
+        assert!(score.factors.iter().any(|f| matches!(
+            f,
+            ScoreFactor::FilePattern { pattern, .. } if pattern == "migrations/"
+        )));
+        assert!(score
+            .factors
+            .iter()
+            .any(|f| matches!(f, ScoreFactor::DiffSize { lines: 1, .. })));
+    }
     
+    #[test]
+    fn test_critical_keyword_overrides_small_diff() {
+        let temp_dir = TempDir::new().unwrap();
+        let repo = Repository::init(temp_dir.path()).unwrap();
+        commit(&repo, "Initial", &[("README.md", "# App\n")]);
+        let oid = commit(
+            &repo,
+            "Drop the v1 API\n\nBREAKING CHANGE: clients must use /v2",
+            &[("README.md", "# App\nUse /v2.\n")],
+        );
+
+        let score = score_commit(&repo, &repo.find_commit(oid).unwrap(), &ScoringConfig::default())
+            .unwrap();
+        assert_eq!(score.category, ScoreCategory::Critical);
+        assert_eq!(score.significance, 0.8);
+    }
+
     #[test]
     fn test_score_category_from_score() {
         assert_eq!(ScoreCategory::from_score(0.9), ScoreCategory::Critical);
@@ -318,4 +444,24 @@ mod tests {
         assert_eq!(config.file_patterns.get("migrations/"), Some(&1.0));
         assert_eq!(config.message_keywords.get("breaking change"), Some(&1.0));
     }
+
+    #[test]
+    fn test_config_merges_over_defaults() {
+        let config: ScoringConfig = toml::from_str(
+            "message_weight = 0.5\n\n[file_patterns]\n\"billing/\" = 1.0\n\"src/\" = 0.0\n\n[message_keywords]\nHotfix = 0.9\n",
+        )
+        .unwrap();
+
+        assert_eq!(config.message_weight, 0.5);
+        assert_eq!(config.diff_weight, 0.3);
+        assert!(config.honor_attributes);
+        let ignoring: ScoringConfig = toml::from_str("honor_attributes = false\n").unwrap();
+        assert!(!ignoring.honor_attributes);
+        assert_eq!(ignoring.diff_weight, 0.3);
+        assert_eq!(config.file_patterns.get("billing/"), Some(&1.0));
+        assert_eq!(config.file_patterns.get("src/"), Some(&0.0));
+        assert_eq!(config.file_patterns.get("migrations/"), Some(&1.0));
+        assert_eq!(config.message_keywords.get("hotfix"), Some(&0.9));
+        assert_eq!(config.message_keywords.get("refactor"), Some(&0.8));
+    }
 }
@@ -4,12 +4,23 @@
 //! - Chronological walking (oldest to newest)
 //! - Incremental processing via manifest tracking
 //! - Diff statistics (files changed, insertions, deletions)
-//! - Merge commit filtering
+//! - Merge commit filtering, and filtering by path, author, or date
 //! - Pagination for large repositories
-
+//! - Multiple starting refs (or every branch), with shared history walked
+//!   once and each commit attributed to the first ref that reaches it
+//! - Shallow clones and grafted history: boundary commits (listed in
+//!   `.git/shallow`, or whose parents are missing from the object store)
+//!   are flagged and get no diff stats, since their real parents are not
+//!   available
+//! - Submodules: a moved submodule pointer is left out of diff stats
+
+use crate::text::short_hash;
 use anyhow::{Context, Result};
-use git2::{DiffOptions, Oid, Repository, Revwalk, Sort};
+use chrono::{DateTime, Utc};
+use git2::{BranchType, DiffFindOptions, DiffOptions, ErrorCode, Oid, Repository, Revwalk, Sort};
 use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
 use std::path::Path;
 
 /// Metadata extracted from a single commit
@@ -35,6 +46,13 @@ pub struct CommitMetadata {
     pub deletions: u32,
     /// Parent commit hashes (multiple for merge commits)
     pub parent_hashes: Vec<String>,
+    /// Commit sits at the edge of a shallow clone; its parents are missing
+    /// and its diff stats are left at zero
+    #[serde(default)]
+    pub shallow_boundary: bool,
+    /// Ref the commit was reached from, when walking explicit refs
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub branch: Option<String>,
 }
 
 /// Options for walking commits
@@ -42,12 +60,49 @@ pub struct CommitMetadata {
 pub struct WalkOptions {
     /// Skip merge commits (commits with > 1 parent)
     pub skip_merges: bool,
-    /// Only process commits after this hash (for incremental walks)
+    /// Only process commits after this hash, exclusive (for incremental walks)
     pub since_commit: Option<String>,
     /// Maximum number of commits to process (for pagination)
     pub limit: Option<usize>,
-    /// Filter commits touching specific paths
+    /// Only return commits touching these git pathspecs; diff stats count
+    /// only the matching paths
     pub pathspec: Option<Vec<String>>,
+    /// Refs to walk from (branch names or any revspec); empty means HEAD
+    pub refs: Vec<String>,
+    /// Walk every local and remote-tracking branch, after `refs`
+    pub all_branches: bool,
+    /// Only commits whose author ("Name <email>") contains one of these,
+    /// ignoring case; empty means any author
+    pub authors: Vec<String>,
+    /// Only commits authored at or after this time
+    pub since_date: Option<DateTime<Utc>>,
+    /// Only commits authored before this time
+    pub until_date: Option<DateTime<Utc>>,
+}
+
+impl WalkOptions {
+    /// Whether the author and date filters admit `commit`
+    fn admits(&self, commit: &git2::Commit) -> bool {
+        let author = commit.author();
+        let when = author.when().seconds();
+        if self.since_date.is_some_and(|since| when < since.timestamp())
+            || self.until_date.is_some_and(|until| when >= until.timestamp())
+        {
+            return false;
+        }
+        if self.authors.is_empty() {
+            return true;
+        }
+        let who = format!(
+            "{} <{}>",
+            author.name().unwrap_or_default(),
+            author.email().unwrap_or_default()
+        )
+        .to_lowercase();
+        self.authors
+            .iter()
+            .any(|wanted| who.contains(&wanted.to_lowercase()))
+    }
 }
 
 /// Result of walking commits with optional continuation token
@@ -55,8 +110,19 @@ pub struct WalkOptions {
 pub struct WalkResult {
     /// Commits processed in this walk
     pub commits: Vec<CommitMetadata>,
-    /// Hash to resume from for next batch (if limit was reached)
+    /// Hash to resume from for next batch (if limit was reached); pass it
+    /// as `since_commit` to continue after the last returned commit
     pub next_hash: Option<String>,
+    /// History is truncated (a shallow clone, or parents missing from the
+    /// object store), so older commits are unavailable
+    pub shallow: bool,
+}
+
+impl WalkResult {
+    /// Commits at the shallow boundary
+    pub fn boundary_commits(&self) -> impl Iterator<Item = &CommitMetadata> {
+        self.commits.iter().filter(|c| c.shallow_boundary)
+    }
 }
 
 /// Walk repository commits in chronological order and extract metadata
@@ -64,20 +130,22 @@ pub fn walk_commits(repo_path: &Path, options: WalkOptions) -> Result<WalkResult
     let repo = Repository::open(repo_path)
         .with_context(|| format!("Failed to open git repository at {}", repo_path.display()))?;
 
-    // Set up revision walker
-    let revwalk = setup_revwalk(&repo, &options)
-        .context("Failed to set up revision walker")?;
+    let roots = resolve_roots(&repo, &options)?;
+    let attribution = attribute_commits(&repo, &roots, &options)?;
+
+    let boundaries = shallow_boundaries(&repo);
+    let mut shallow = repo.is_shallow() || !boundaries.is_empty();
+    let oids = commit_oids(&repo, &options, &roots, &mut shallow)?;
 
     let mut commits = Vec::new();
     let mut next_hash = None;
 
-    for oid_result in revwalk {
-        let oid = oid_result.context("Failed to get commit OID")?;
+    for oid in oids {
 
-        // Check limit
+        // Check limit; resume point is the last commit returned
         if let Some(limit) = options.limit {
             if commits.len() >= limit {
-                next_hash = Some(oid.to_string());
+                next_hash = commits.last().map(|c: &CommitMetadata| c.hash.clone());
                 break;
             }
         }
@@ -89,19 +157,175 @@ pub fn walk_commits(repo_path: &Path, options: WalkOptions) -> Result<WalkResult
         if options.skip_merges && commit.parent_count() > 1 {
             continue;
         }
+        if !options.admits(&commit) {
+            continue;
+        }
 
-        // Extract metadata
-        let metadata = extract_commit_metadata(&repo, &commit, &options)
+        // Extract metadata. Grafted or partially fetched history can leave
+        // a commit's parents missing without a `.git/shallow` entry.
+        let boundary = boundaries.contains(&oid.to_string()) || has_missing_parent(&commit);
+        shallow |= boundary;
+        let mut metadata = extract_commit_metadata(&repo, &commit, &options, boundary)
             .with_context(|| format!("Failed to extract metadata for commit {}", oid))?;
+        metadata.branch = attribution.get(&oid).cloned();
+
+        // Diff stats only count paths in the pathspec, so a commit that
+        // touched none of them has no files changed
+        if options.pathspec.is_some() && metadata.files_changed == 0 && !boundary {
+            continue;
+        }
 
         commits.push(metadata);
     }
 
-    Ok(WalkResult { commits, next_hash })
+    Ok(WalkResult {
+        commits,
+        next_hash,
+        shallow,
+    })
+}
+
+/// Count the commits a walk with `options` would return, without
+/// extracting their metadata. The limit and pathspec aren't applied.
+pub fn count_commits(repo_path: &Path, options: &WalkOptions) -> Result<usize> {
+    let repo = Repository::open(repo_path)
+        .with_context(|| format!("Failed to open git repository at {}", repo_path.display()))?;
+    let roots = resolve_roots(&repo, options)?;
+    let mut shallow = repo.is_shallow() || !shallow_boundaries(&repo).is_empty();
+    let oids = commit_oids(&repo, options, &roots, &mut shallow)?;
+
+    let mut count = 0;
+    for oid in oids {
+        let commit = repo.find_commit(oid)
+            .with_context(|| format!("Failed to find commit {}", oid))?;
+        if !(options.skip_merges && commit.parent_count() > 1) && options.admits(&commit) {
+            count += 1;
+        }
+    }
+    Ok(count)
+}
+
+/// Commits to walk, oldest first. The sorted revwalk fails outright when
+/// an ancestor is missing from the object store; then what is reachable
+/// is walked instead and `shallow` is set.
+fn commit_oids(
+    repo: &Repository,
+    options: &WalkOptions,
+    roots: &[(String, Oid)],
+    shallow: &mut bool,
+) -> Result<Vec<Oid>> {
+    let revwalk = setup_revwalk(repo, options, roots)
+        .context("Failed to set up revision walker")?;
+
+    match revwalk.collect::<Result<_, _>>() {
+        Ok(oids) => Ok(oids),
+        Err(e) if *shallow || e.code() == ErrorCode::NotFound => {
+            *shallow = true;
+            let starts: Vec<Oid> = if roots.is_empty() {
+                default_root(repo).into_iter().collect()
+            } else {
+                roots.iter().map(|(_, oid)| *oid).collect()
+            };
+            let hidden = options.since_commit.as_deref().map(Oid::from_str).transpose()?;
+            Ok(reachable_oldest_first(repo, &starts, hidden))
+        }
+        Err(e) => Err(e).context("Failed to get commit OID"),
+    }
+}
+
+/// Hashes of the boundary commits listed in `.git/shallow`, plus the
+/// commits `.git/info/grafts` gives made-up parents
+pub fn shallow_boundaries(repo: &Repository) -> HashSet<String> {
+    ["shallow", "info/grafts"]
+        .iter()
+        .filter_map(|file| fs::read_to_string(repo.path().join(file)).ok())
+        .flat_map(|contents| {
+            // A graft line is the commit followed by its parents
+            contents
+                .lines()
+                .filter_map(|line| line.split_whitespace().next())
+                .map(str::to_string)
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+/// Whether any parent of `commit` is missing from the object store
+fn has_missing_parent(commit: &git2::Commit) -> bool {
+    (0..commit.parent_count()).any(|i| commit.parent(i).is_err())
 }
 
-/// Set up revision walker with proper sorting and starting point
-fn setup_revwalk<'a>(repo: &'a Repository, options: &WalkOptions) -> Result<Revwalk<'a>> {
+/// Resolve the configured refs (and every branch, if requested) to
+/// commits, keeping the first occurrence of each. Empty means HEAD.
+fn resolve_roots(repo: &Repository, options: &WalkOptions) -> Result<Vec<(String, Oid)>> {
+    let mut roots: Vec<(String, Oid)> = Vec::new();
+
+    for name in &options.refs {
+        let oid = repo
+            .revparse_single(name)
+            .and_then(|object| object.peel_to_commit())
+            .with_context(|| format!("Failed to resolve ref '{}'", name))?
+            .id();
+        if !roots.iter().any(|(n, _)| n == name) {
+            roots.push((name.clone(), oid));
+        }
+    }
+
+    if options.all_branches {
+        for kind in [BranchType::Local, BranchType::Remote] {
+            for branch in repo.branches(Some(kind)).context("Failed to list branches")? {
+                let (branch, _) = branch.context("Failed to read branch")?;
+                let Some(name) = branch.name()?.map(str::to_string) else {
+                    continue;
+                };
+                // Skip symbolic refs like origin/HEAD
+                if name.ends_with("/HEAD") || roots.iter().any(|(n, _)| *n == name) {
+                    continue;
+                }
+                if let Ok(commit) = branch.get().peel_to_commit() {
+                    roots.push((name, commit.id()));
+                }
+            }
+        }
+    }
+
+    Ok(roots)
+}
+
+/// Map each commit reachable from `roots` to the first root reaching it.
+/// Empty when walking HEAD only.
+fn attribute_commits(
+    repo: &Repository,
+    roots: &[(String, Oid)],
+    options: &WalkOptions,
+) -> Result<HashMap<Oid, String>> {
+    let mut attribution = HashMap::new();
+
+    for (idx, (name, oid)) in roots.iter().enumerate() {
+        let mut revwalk = repo.revwalk().context("Failed to create revision walker")?;
+        revwalk.push(*oid)?;
+        for (_, earlier) in &roots[..idx] {
+            revwalk.hide(*earlier)?;
+        }
+        if let Some(since_hash) = &options.since_commit {
+            revwalk.hide(Oid::from_str(since_hash)?)?;
+        }
+
+        // Missing parents in a shallow clone end this ref's walk early
+        for oid in revwalk.map_while(|r| r.ok()) {
+            attribution.entry(oid).or_insert_with(|| name.clone());
+        }
+    }
+
+    Ok(attribution)
+}
+
+/// Set up revision walker with proper sorting and starting points
+fn setup_revwalk<'a>(
+    repo: &'a Repository,
+    options: &WalkOptions,
+    roots: &[(String, Oid)],
+) -> Result<Revwalk<'a>> {
     let mut revwalk = repo.revwalk()
         .context("Failed to create revision walker")?;
 
@@ -109,50 +333,91 @@ fn setup_revwalk<'a>(repo: &'a Repository, options: &WalkOptions) -> Result<Revw
     revwalk.set_sorting(Sort::TOPOLOGICAL | Sort::REVERSE)
         .context("Failed to set revwalk sorting")?;
 
-    // Determine starting point
+    if !roots.is_empty() {
+        // Shared history is only yielded once by the revwalk
+        for (name, oid) in roots {
+            revwalk.push(*oid)
+                .with_context(|| format!("Failed to push {} to revwalk", name))?;
+        }
+    } else {
+        match default_root(repo) {
+            Some(oid) => revwalk.push(oid).context("Failed to push HEAD to revwalk")?,
+            None => return Ok(revwalk),
+        }
+    }
+
+    // Exclude the since commit and its ancestors (for incremental walks)
     if let Some(since_hash) = &options.since_commit {
-        // Start from specific commit (for incremental walks)
         let oid = Oid::from_str(since_hash)
             .with_context(|| format!("Invalid commit hash: {}", since_hash))?;
-        revwalk.push(oid)
-            .with_context(|| format!("Failed to push commit {} to revwalk", since_hash))?;
-    } else {
-        // Start from HEAD
-        match repo.head() {
-            Ok(_head) => {
-                revwalk.push_head()
-                    .context("Failed to push HEAD to revwalk")?;
-            }
-            Err(_) => {
-                // Detached HEAD or empty repo - try main/master
-                if let Ok(_reference) = repo.find_reference("refs/heads/main") {
-                    revwalk.push_ref("refs/heads/main")
-                        .context("Failed to push main branch to revwalk")?;
-                } else if let Ok(_reference) = repo.find_reference("refs/heads/master") {
-                    revwalk.push_ref("refs/heads/master")
-                        .context("Failed to push master branch to revwalk")?;
-                } else {
-                    // Empty repository - return empty walk
-                    return Ok(revwalk);
-                }
-            }
-        }
+        revwalk.hide(oid)
+            .with_context(|| format!("Failed to hide commit {} from revwalk", since_hash))?;
     }
 
     Ok(revwalk)
 }
 
+/// HEAD's commit, falling back to main/master. None for an empty
+/// repository, where there is nothing to walk.
+fn default_root(repo: &Repository) -> Option<Oid> {
+    repo.head()
+        .or_else(|_| repo.find_reference("refs/heads/main"))
+        .or_else(|_| repo.find_reference("refs/heads/master"))
+        .ok()
+        .and_then(|reference| reference.peel_to_commit().ok())
+        .map(|commit| commit.id())
+}
+
+/// Commits reachable from `starts` but not from `hidden`, parents before
+/// children, following only parents present in the object store
+fn reachable_oldest_first(repo: &Repository, starts: &[Oid], hidden: Option<Oid>) -> Vec<Oid> {
+    let mut seen = HashSet::new();
+    if let Some(hidden) = hidden {
+        postorder(repo, &[hidden], &mut seen);
+    }
+    postorder(repo, starts, &mut seen)
+}
+
+/// Depth-first postorder from `starts`, skipping commits in `seen` (and
+/// adding every commit visited to it)
+fn postorder(repo: &Repository, starts: &[Oid], seen: &mut HashSet<Oid>) -> Vec<Oid> {
+    let mut order = Vec::new();
+    // (commit, parents already pushed)
+    let mut stack: Vec<(Oid, bool)> = starts.iter().rev().map(|oid| (*oid, false)).collect();
+    while let Some((oid, expanded)) = stack.pop() {
+        if expanded {
+            order.push(oid);
+            continue;
+        }
+        if !seen.insert(oid) {
+            continue;
+        }
+        let Ok(commit) = repo.find_commit(oid) else {
+            continue;
+        };
+        stack.push((oid, true));
+        stack.extend(
+            commit
+                .parent_ids()
+                .filter(|parent| !seen.contains(parent))
+                .map(|parent| (parent, false)),
+        );
+    }
+    order
+}
+
 /// Extract metadata from a single commit
 fn extract_commit_metadata(
     repo: &Repository,
     commit: &git2::Commit,
     options: &WalkOptions,
+    shallow_boundary: bool,
 ) -> Result<CommitMetadata> {
     let hash = commit.id().to_string();
     let short_hash = commit.as_object()
         .short_id()
-        .map(|buf| buf.as_str().unwrap_or(&hash[..7]).to_string())
-        .unwrap_or_else(|_| hash[..7].to_string());
+        .map(|buf| buf.as_str().unwrap_or(short_hash(&hash)).to_string())
+        .unwrap_or_else(|_| short_hash(&hash).to_string());
 
     let author = commit.author();
     let author_str = format!(
@@ -170,9 +435,14 @@ fn extract_commit_metadata(
         .map(|p| p.id().to_string())
         .collect();
 
-    // Calculate diff statistics
-    let (files_changed, insertions, deletions) = calculate_diff_stats(repo, commit, options)
-        .unwrap_or((0, 0, 0)); // If diff fails, use zeros (e.g., initial commit)
+    // Calculate diff statistics. A shallow boundary would diff against the
+    // empty tree and count the whole snapshot, so it gets zeros instead.
+    let (files_changed, insertions, deletions) = if shallow_boundary {
+        (0, 0, 0)
+    } else {
+        calculate_diff_stats(repo, commit, options)
+            .unwrap_or((0, 0, 0)) // If diff fails, use zeros (e.g., initial commit)
+    };
 
     Ok(CommitMetadata {
         hash,
@@ -185,10 +455,13 @@ fn extract_commit_metadata(
         insertions,
         deletions,
         parent_hashes,
+        shallow_boundary,
+        branch: None,
     })
 }
 
-/// Calculate diff statistics for a commit
+/// Calculate diff statistics for a commit. Renamed files count once, with
+/// only the lines that changed.
 fn calculate_diff_stats(
     repo: &Repository,
     commit: &git2::Commit,
@@ -208,7 +481,9 @@ fn calculate_diff_stats(
     };
 
     // Create diff options with pathspec filter if provided
+    // A submodule pointer moving is not a change to this repository's files
     let mut diff_opts = DiffOptions::new();
+    diff_opts.ignore_submodules(true);
     if let Some(pathspecs) = &options.pathspec {
         for pathspec in pathspecs {
             diff_opts.pathspec(pathspec);
@@ -216,11 +491,13 @@ fn calculate_diff_stats(
     }
 
     // Calculate diff
-    let diff = repo.diff_tree_to_tree(
+    let mut diff = repo.diff_tree_to_tree(
         parent_tree.as_ref(),
         Some(&current_tree),
         Some(&mut diff_opts),
     ).context("Failed to create diff")?;
+    diff.find_similar(Some(DiffFindOptions::new().renames(true)))
+        .context("Failed to detect renames")?;
 
     let stats = diff.stats()
         .context("Failed to calculate diff stats")?;
@@ -235,6 +512,7 @@ fn calculate_diff_stats(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::git::fixture;
     use std::fs;
     use tempfile::TempDir;
 
@@ -251,35 +529,7 @@ mod tests {
     }
 
     fn create_commit(repo: &Repository, message: &str, content: &str) -> Result<Oid> {
-        let repo_path = repo.path().parent().unwrap();
-        let file_path = repo_path.join("test.txt");
-        fs::write(&file_path, content)?;
-
-        let mut index = repo.index()?;
-        index.add_path(Path::new("test.txt"))?;
-        index.write()?;
-
-        let tree_id = index.write_tree()?;
-        let tree = repo.find_tree(tree_id)?;
-
-        let signature = repo.signature()?;
-        let parent_commit = repo.head().ok().and_then(|h| h.peel_to_commit().ok());
-        let parents = if let Some(ref p) = parent_commit {
-            vec![p]
-        } else {
-            vec![]
-        };
-
-        let oid = repo.commit(
-            Some("HEAD"),
-            &signature,
-            &signature,
-            message,
-            &tree,
-            &parents,
-        )?;
-
-        Ok(oid)
+        Ok(fixture::commit_as(repo, "Test User", message, &[("test.txt", content)]))
     }
 
     #[test]
@@ -307,6 +557,82 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_diff_stats_count_renames_once() -> Result<()> {
+        let (_temp, repo) = create_test_repo()?;
+        let contents: String = (0..20).map(|i| format!("line {}\n", i)).collect();
+        create_commit(&repo, "Add file", &contents)?;
+
+        let repo_path = repo.path().parent().unwrap();
+        fs::rename(repo_path.join("test.txt"), repo_path.join("renamed.txt"))?;
+        let mut index = repo.index()?;
+        index.remove_path(Path::new("test.txt"))?;
+        index.add_path(Path::new("renamed.txt"))?;
+        index.write()?;
+        let tree = repo.find_tree(index.write_tree()?)?;
+        let signature = repo.signature()?;
+        let parent = repo.head()?.peel_to_commit()?;
+        repo.commit(Some("HEAD"), &signature, &signature, "Rename file", &tree, &[&parent])?;
+
+        let result = walk_commits(repo_path, WalkOptions::default())?;
+        let rename = &result.commits[1];
+        assert_eq!(rename.message_summary, "Rename file");
+        assert_eq!((rename.files_changed, rename.insertions, rename.deletions), (1, 0, 0));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_walk_commits_pathspec() -> Result<()> {
+        let (_temp, repo) = create_test_repo()?;
+        create_commit(&repo, "First commit", "content1")?;
+        create_commit(&repo, "Second commit", "content2")?;
+        let repo_path = repo.path().parent().unwrap();
+
+        let walk = |pathspec: &str| {
+            walk_commits(
+                repo_path,
+                WalkOptions {
+                    pathspec: Some(vec![pathspec.to_string()]),
+                    ..Default::default()
+                },
+            )
+        };
+        assert_eq!(walk("*.txt")?.commits.len(), 2);
+        assert!(walk("services/api")?.commits.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_walk_commits_author_and_date() -> Result<()> {
+        let (_temp, repo) = create_test_repo()?;
+        let oid = create_commit(&repo, "First commit", "content1")?;
+        let authored = repo.find_commit(oid)?.author().when().seconds();
+        let at = |secs: i64| DateTime::from_timestamp(secs, 0);
+        let repo_path = repo.path().parent().unwrap();
+        let walk = |options: WalkOptions| walk_commits(repo_path, options).map(|r| r.commits.len());
+
+        let authors = |author: &str| WalkOptions {
+            authors: vec![author.to_string()],
+            ..Default::default()
+        };
+        assert_eq!(walk(authors("TEST user"))?, 1);
+        assert_eq!(walk(authors("@example.com"))?, 1);
+        assert_eq!(walk(authors("someone else"))?, 0);
+
+        let window = |since: i64, until: i64| WalkOptions {
+            since_date: at(since),
+            until_date: at(until),
+            ..Default::default()
+        };
+        assert_eq!(walk(window(authored, authored + 1))?, 1);
+        assert_eq!(walk(window(authored + 1, authored + 60))?, 0);
+        assert_eq!(walk(window(authored - 60, authored))?, 0);
+
+        Ok(())
+    }
+
     #[test]
     fn test_commit_metadata_extraction() -> Result<()> {
         let (_temp, repo) = create_test_repo()?;
@@ -332,10 +658,10 @@ mod tests {
         let (_temp, repo) = create_test_repo()?;
 
         // Initial commit with 3 lines
-        create_commit(&repo, "Initial", "line1\nline2\nline3")?;
+        create_commit(&repo, "Initial", "line1\nline2\nline3\n")?;
 
         // Second commit: add 2 lines, remove 1 line
-        create_commit(&repo, "Update", "line1\nline3\nline4\nline5")?;
+        create_commit(&repo, "Update", "line1\nline3\nline4\nline5\n")?;
 
         let result = walk_commits(repo.path().parent().unwrap(), WalkOptions::default())?;
 
@@ -386,6 +712,7 @@ mod tests {
             limit: Some(2),
             ..Default::default()
         };
+        assert_eq!(count_commits(repo.path().parent().unwrap(), &options)?, 3);
 
         let result = walk_commits(repo.path().parent().unwrap(), options)?;
 
@@ -395,6 +722,38 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_pagination_resumes_after_next_hash() -> Result<()> {
+        let (_temp, repo) = create_test_repo()?;
+        let repo_path = repo.path().parent().unwrap();
+
+        create_commit(&repo, "First", "content1")?;
+        let second = create_commit(&repo, "Second", "content2")?;
+        create_commit(&repo, "Third", "content3")?;
+
+        let page = walk_commits(repo_path, WalkOptions {
+            limit: Some(2),
+            ..Default::default()
+        })?;
+        // The resume point is the last commit returned, not the next one
+        assert_eq!(page.next_hash, Some(second.to_string()));
+
+        // since_commit is exclusive, so the next page starts after it
+        let rest = walk_commits(repo_path, WalkOptions {
+            since_commit: page.next_hash,
+            ..Default::default()
+        })?;
+        let summaries: Vec<&str> = rest
+            .commits
+            .iter()
+            .map(|c| c.message_summary.as_str())
+            .collect();
+        assert_eq!(summaries, vec!["Third"]);
+        assert!(rest.next_hash.is_none());
+
+        Ok(())
+    }
+
     #[test]
     fn test_empty_repository() -> Result<()> {
         let temp_dir = TempDir::new()?;
@@ -407,4 +766,184 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_shallow_boundary_has_no_diff_stats() -> Result<()> {
+        let (_temp, repo) = create_test_repo()?;
+
+        create_commit(&repo, "First", "line1\n")?;
+        let boundary = create_commit(&repo, "Second", "line1\nline2\n")?;
+        create_commit(&repo, "Third", "line1\nline2\nline3\n")?;
+        fs::write(repo.path().join("shallow"), format!("{}\n", boundary))?;
+
+        let result = walk_commits(repo.path().parent().unwrap(), WalkOptions::default())?;
+
+        assert!(result.shallow);
+        let flagged: Vec<_> = result.boundary_commits().collect();
+        assert_eq!(flagged.len(), 1);
+        assert_eq!(flagged[0].hash, boundary.to_string());
+        assert_eq!(flagged[0].files_changed, 0);
+        assert_eq!(flagged[0].insertions, 0);
+
+        let third = result.commits.last().unwrap();
+        assert_eq!(third.message_summary, "Third");
+        assert!(!third.shallow_boundary);
+        assert_eq!(third.insertions, 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_grafted_and_missing_parents_are_boundaries() -> Result<()> {
+        let (_temp, repo) = create_test_repo()?;
+        let workdir = repo.path().parent().unwrap().to_path_buf();
+
+        let first = create_commit(&repo, "First", "line1\n")?;
+        let grafted = create_commit(&repo, "Second", "line1\nline2\n")?;
+        create_commit(&repo, "Third", "line1\nline2\nline3\n")?;
+        fs::create_dir_all(repo.path().join("info"))?;
+        fs::write(repo.path().join("info/grafts"), format!("{}\n", grafted))?;
+
+        let result = walk_commits(&workdir, WalkOptions::default())?;
+        assert!(result.shallow);
+        let flagged: Vec<_> = result.boundary_commits().map(|c| c.hash.clone()).collect();
+        assert_eq!(flagged, vec![grafted.to_string()]);
+        assert!(!result.commits.iter().any(|c| c.hash == first.to_string()));
+
+        // Objects lost without any graft or shallow entry
+        fs::remove_file(repo.path().join("info/grafts"))?;
+        let hex = first.to_string();
+        fs::remove_file(repo.path().join("objects").join(&hex[..2]).join(&hex[2..]))?;
+
+        let result = walk_commits(&workdir, WalkOptions::default())?;
+        assert!(result.shallow);
+        let summaries: Vec<_> = result.commits.iter().map(|c| c.message_summary.as_str()).collect();
+        assert_eq!(summaries, vec!["Second", "Third"]);
+        assert!(result.commits[0].shallow_boundary);
+        assert_eq!(result.commits[0].insertions, 0);
+        assert_eq!(result.commits[1].insertions, 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_multiple_refs_deduplicate_and_attribute() -> Result<()> {
+        let (_temp, repo) = create_test_repo()?;
+
+        let base = create_commit(&repo, "Base", "base")?;
+        let head_name = repo.head()?.shorthand().unwrap().to_string();
+
+        // Commit on a release branch forked from base
+        let base_commit = repo.find_commit(base)?;
+        repo.branch("release", &base_commit, false)?;
+        let signature = repo.signature()?;
+        let tree = base_commit.tree()?;
+        let release_oid = repo.commit(
+            Some("refs/heads/release"),
+            &signature,
+            &signature,
+            "Release fix",
+            &tree,
+            &[&base_commit],
+        )?;
+
+        create_commit(&repo, "Mainline", "main")?;
+
+        let options = WalkOptions {
+            refs: vec![head_name.clone(), "release".to_string()],
+            ..Default::default()
+        };
+        let result = walk_commits(repo.path().parent().unwrap(), options)?;
+
+        assert_eq!(result.commits.len(), 3);
+        let release = result
+            .commits
+            .iter()
+            .find(|c| c.hash == release_oid.to_string())
+            .unwrap();
+        assert_eq!(release.branch.as_deref(), Some("release"));
+        assert_eq!(result.commits[0].branch.as_deref(), Some(head_name.as_str()));
+
+        let all = walk_commits(
+            repo.path().parent().unwrap(),
+            WalkOptions {
+                all_branches: true,
+                ..Default::default()
+            },
+        )?;
+        assert_eq!(all.commits.len(), 3);
+
+        // Default walk only follows HEAD and records no branch
+        let head_only = walk_commits(repo.path().parent().unwrap(), WalkOptions::default())?;
+        assert_eq!(head_only.commits.len(), 2);
+        assert!(head_only.commits.iter().all(|c| c.branch.is_none()));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_submodule_bump_has_no_diff_stats() -> Result<()> {
+        let (_temp, repo) = create_test_repo()?;
+        create_commit(&repo, "Base", "base")?;
+
+        // Commit a gitlink for `vendor/lib`, then move it
+        let signature = repo.signature()?;
+        for (message, pointer) in [
+            ("Add lib", "1111111111111111111111111111111111111111"),
+            ("Bump lib", "2222222222222222222222222222222222222222"),
+        ] {
+            let head = repo.head()?.peel_to_commit()?;
+            let mut vendor = repo.treebuilder(None)?;
+            vendor.insert("lib", Oid::from_str(pointer)?, git2::FileMode::Commit.into())?;
+            let vendor = vendor.write()?;
+            let mut root = repo.treebuilder(Some(&head.tree()?))?;
+            root.insert("vendor", vendor, git2::FileMode::Tree.into())?;
+            let tree = repo.find_tree(root.write()?)?;
+            repo.commit(Some("HEAD"), &signature, &signature, message, &tree, &[&head])?;
+        }
+
+        let result = walk_commits(repo.path().parent().unwrap(), WalkOptions::default())?;
+        let bump = result.commits.last().unwrap();
+        assert_eq!(bump.message_summary, "Bump lib");
+        assert_eq!(bump.files_changed, 0);
+        assert_eq!(bump.insertions + bump.deletions, 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_walk_tag_or_sha_instead_of_head() -> Result<()> {
+        let (_temp, repo) = create_test_repo()?;
+
+        let first = create_commit(&repo, "First", "one")?;
+        let tagged = create_commit(&repo, "Second", "two")?;
+        let signature = repo.signature()?;
+        repo.tag("v1.0", repo.find_commit(tagged)?.as_object(), &signature, "v1.0", false)?;
+        create_commit(&repo, "Third", "three")?;
+
+        let walk = |name: &str| {
+            walk_commits(
+                repo.path().parent().unwrap(),
+                WalkOptions {
+                    refs: vec![name.to_string()],
+                    ..Default::default()
+                },
+            )
+        };
+
+        // An annotated tag is peeled to its commit
+        let by_tag = walk("v1.0")?;
+        assert_eq!(by_tag.commits.len(), 2);
+        assert_eq!(by_tag.commits[1].hash, tagged.to_string());
+        assert_eq!(by_tag.commits[1].branch.as_deref(), Some("v1.0"));
+
+        let short = first.to_string()[..7].to_string();
+        let by_sha = walk(&short)?;
+        assert_eq!(by_sha.commits.len(), 1);
+        assert_eq!(by_sha.commits[0].hash, first.to_string());
+
+        assert!(walk("no-such-ref").is_err());
+
+        Ok(())
+    }
 }
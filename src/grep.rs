@@ -0,0 +1,568 @@
+//! Field-scoped search expressions for `noggin grep`.
+//!
+//! An expression is a list of terms joined by `AND` (also implied between
+//! adjacent terms), `OR` and `NOT`, with parentheses for grouping:
+//!
+//! ```text
+//! what:pooling AND category:decisions AND file:src/db/**
+//! (tag:retry OR tag:backoff) NOT category:facts
+//! why:"connection limits" confidence:>=0.7
+//! ```
+//!
+//! A term without a field searches what, why, how, and tags. Text fields
+//! match case-insensitive substrings; `file:` and `path:` take globs (or a
+//! directory prefix); `confidence:` takes a comparison. Matching entries
+//! come from the semantic [`crate::index`], which also orders them when the
+//! expression has free text. No model is involved.
+
+use crate::arf::ArfFile;
+use crate::index::embed::HashingEmbedder;
+use crate::index::SemanticIndex;
+use crate::query::QueryResult;
+use crate::rules::glob_to_regex;
+use crate::storage::KnowledgeBackend;
+use anyhow::{Context, Result};
+use regex::Regex;
+use std::path::Path;
+
+/// Field names accepted before `:`
+pub const FIELDS: &[&str] = &[
+    "what", "why", "how", "tag", "category", "file", "path", "issue", "person", "confidence",
+];
+
+/// A parsed search expression
+#[derive(Debug)]
+pub enum Expr {
+    Term(Term),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+}
+
+/// One `field:value` test
+#[derive(Debug)]
+pub enum Term {
+    /// Free text in what, why, how, or tags
+    Text(String),
+    What(String),
+    Why(String),
+    How(String),
+    Tag(String),
+    Category(String),
+    File(PathPattern),
+    Path(PathPattern),
+    Issue(String),
+    Person(String),
+    Confidence(Comparison, f64),
+}
+
+/// A glob, or a plain path that also matches everything under it
+#[derive(Debug)]
+pub enum PathPattern {
+    Glob(Regex),
+    Prefix(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+    Equal,
+}
+
+/// An entry the expression matched
+#[derive(Debug, Clone)]
+pub struct Entry<'a> {
+    /// Path relative to .noggin/
+    pub path: &'a str,
+    pub category: &'a str,
+    pub arf: &'a ArfFile,
+}
+
+impl Expr {
+    /// Parse `input`, reporting the first syntax error
+    pub fn parse(input: &str) -> Result<Self> {
+        let tokens = tokenize(input)?;
+        if tokens.is_empty() {
+            anyhow::bail!("Empty search expression");
+        }
+        let mut parser = Parser { tokens, pos: 0 };
+        let expr = parser.or()?;
+        if let Some(token) = parser.peek() {
+            anyhow::bail!("Unexpected {} in search expression", token.describe());
+        }
+        Ok(expr)
+    }
+
+    /// Whether `entry` satisfies the expression. Fields of positive terms
+    /// that matched are added to `matched`.
+    pub fn matches(&self, entry: &Entry, matched: &mut Vec<String>) -> bool {
+        match self {
+            Expr::Term(term) => term.matches(entry, matched),
+            Expr::And(a, b) => a.matches(entry, matched) && b.matches(entry, matched),
+            Expr::Or(a, b) => {
+                let left = a.matches(entry, matched);
+                b.matches(entry, matched) || left
+            }
+            Expr::Not(inner) => !inner.matches(entry, &mut Vec::new()),
+        }
+    }
+
+    /// Free text and what/why/how values outside `NOT`, joined for ranking
+    pub fn ranking_text(&self) -> String {
+        let mut words = Vec::new();
+        self.collect_text(&mut words);
+        words.join(" ")
+    }
+
+    fn collect_text<'a>(&'a self, words: &mut Vec<&'a str>) {
+        match self {
+            Expr::Term(Term::Text(v) | Term::What(v) | Term::Why(v) | Term::How(v)) => words.push(v),
+            Expr::Term(_) | Expr::Not(_) => {}
+            Expr::And(a, b) | Expr::Or(a, b) => {
+                a.collect_text(words);
+                b.collect_text(words);
+            }
+        }
+    }
+}
+
+impl Term {
+    fn parse(word: &str) -> Result<Self> {
+        let (field, value) = match word.split_once(':') {
+            Some((field, value)) if FIELDS.contains(&field) => (field, value),
+            Some((field, _)) if !field.is_empty() && field.chars().all(|c| c.is_ascii_alphabetic()) => {
+                anyhow::bail!(
+                    "Unknown field '{}' (expected one of: {})",
+                    field,
+                    FIELDS.join(", ")
+                )
+            }
+            _ => return Ok(Term::Text(word.to_lowercase())),
+        };
+        if value.is_empty() {
+            anyhow::bail!("Missing value after '{}:'", field);
+        }
+
+        let text = value.to_lowercase();
+        Ok(match field {
+            "what" => Term::What(text),
+            "why" => Term::Why(text),
+            "how" => Term::How(text),
+            "tag" => Term::Tag(text),
+            "category" => Term::Category(text),
+            "file" => Term::File(PathPattern::parse(value)?),
+            "path" => Term::Path(PathPattern::parse(value)?),
+            "issue" => Term::Issue(text),
+            "person" => Term::Person(text),
+            "confidence" => {
+                let (comparison, number) = Comparison::split(value);
+                let threshold = number
+                    .parse::<f64>()
+                    .with_context(|| format!("Invalid confidence '{}'", value))?;
+                Term::Confidence(comparison, threshold)
+            }
+            _ => unreachable!("field list checked above"),
+        })
+    }
+
+    fn matches(&self, entry: &Entry, matched: &mut Vec<String>) -> bool {
+        let arf = entry.arf;
+        let contains = |haystack: &str, needle: &str| haystack.to_lowercase().contains(needle);
+
+        let (field, hit) = match self {
+            Term::Text(v) => {
+                let mut hit = false;
+                for (field, text) in [("what", &arf.what), ("why", &arf.why), ("how", &arf.how)] {
+                    if contains(text, v) {
+                        push_unique(matched, field);
+                        hit = true;
+                    }
+                }
+                if arf.tags.iter().any(|t| contains(t, v)) {
+                    push_unique(matched, "tags");
+                    hit = true;
+                }
+                return hit;
+            }
+            Term::What(v) => ("what", contains(&arf.what, v)),
+            Term::Why(v) => ("why", contains(&arf.why, v)),
+            Term::How(v) => ("how", contains(&arf.how, v)),
+            Term::Tag(v) => ("tags", arf.tags.iter().any(|t| t.eq_ignore_ascii_case(v))),
+            Term::Category(v) => ("category", entry.category.eq_ignore_ascii_case(v)),
+            Term::File(pattern) => (
+                "files",
+                arf.context.files.iter().any(|f| pattern.matches(f)),
+            ),
+            Term::Path(pattern) => ("path", pattern.matches(entry.path)),
+            Term::Issue(v) => ("issues", arf.context.issues.iter().any(|i| contains(i, v))),
+            Term::Person(v) => ("people", arf.context.people.iter().any(|p| contains(p, v))),
+            Term::Confidence(comparison, threshold) => (
+                "confidence",
+                arf.confidence.is_some_and(|c| comparison.holds(c, *threshold)),
+            ),
+        };
+        if hit {
+            push_unique(matched, field);
+        }
+        hit
+    }
+}
+
+fn push_unique(matched: &mut Vec<String>, field: &str) {
+    if !matched.iter().any(|m| m == field) {
+        matched.push(field.to_string());
+    }
+}
+
+impl PathPattern {
+    fn parse(value: &str) -> Result<Self> {
+        let value = value.trim_start_matches("./");
+        if value.contains(['*', '?']) {
+            Ok(PathPattern::Glob(glob_to_regex(value)?))
+        } else {
+            Ok(PathPattern::Prefix(value.trim_end_matches('/').to_string()))
+        }
+    }
+
+    fn matches(&self, path: &str) -> bool {
+        let path = path.trim_start_matches("./").trim_end_matches('/');
+        match self {
+            PathPattern::Glob(regex) => regex.is_match(path),
+            PathPattern::Prefix(prefix) => {
+                path == prefix
+                    || path
+                        .strip_prefix(prefix.as_str())
+                        .is_some_and(|rest| rest.starts_with('/'))
+            }
+        }
+    }
+}
+
+impl Comparison {
+    /// Split a leading operator off `value`; no operator means `>=`
+    fn split(value: &str) -> (Self, &str) {
+        for (op, comparison) in [
+            (">=", Comparison::GreaterOrEqual),
+            ("<=", Comparison::LessOrEqual),
+            (">", Comparison::Greater),
+            ("<", Comparison::Less),
+            ("=", Comparison::Equal),
+        ] {
+            if let Some(rest) = value.strip_prefix(op) {
+                return (comparison, rest);
+            }
+        }
+        (Comparison::GreaterOrEqual, value)
+    }
+
+    fn holds(self, value: f64, threshold: f64) -> bool {
+        match self {
+            Comparison::Less => value < threshold,
+            Comparison::LessOrEqual => value <= threshold,
+            Comparison::Greater => value > threshold,
+            Comparison::GreaterOrEqual => value >= threshold,
+            Comparison::Equal => (value - threshold).abs() < 1e-9,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Token {
+    Word(String),
+    And,
+    Or,
+    Not,
+    Open,
+    Close,
+}
+
+impl Token {
+    fn describe(&self) -> String {
+        match self {
+            Token::Word(word) => format!("'{}'", word),
+            Token::And => "AND".to_string(),
+            Token::Or => "OR".to_string(),
+            Token::Not => "NOT".to_string(),
+            Token::Open => "'('".to_string(),
+            Token::Close => "')'".to_string(),
+        }
+    }
+}
+
+/// Split on whitespace and parentheses. Double quotes group words and
+/// keep operators literal.
+fn tokenize(input: &str) -> Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut word = String::new();
+    let mut quoted = false;
+    let mut chars = input.chars();
+
+    let finish = |word: &mut String, quoted: &mut bool, tokens: &mut Vec<Token>| {
+        if word.is_empty() && !*quoted {
+            return;
+        }
+        let token = match word.as_str() {
+            "AND" if !*quoted => Token::And,
+            "OR" if !*quoted => Token::Or,
+            "NOT" if !*quoted => Token::Not,
+            _ => Token::Word(std::mem::take(word)),
+        };
+        word.clear();
+        *quoted = false;
+        tokens.push(token);
+    };
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                quoted = true;
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some(c) => word.push(c),
+                        None => anyhow::bail!("Unclosed quote in search expression"),
+                    }
+                }
+            }
+            '(' | ')' => {
+                finish(&mut word, &mut quoted, &mut tokens);
+                tokens.push(if c == '(' { Token::Open } else { Token::Close });
+            }
+            c if c.is_whitespace() => finish(&mut word, &mut quoted, &mut tokens),
+            c => word.push(c),
+        }
+    }
+    finish(&mut word, &mut quoted, &mut tokens);
+
+    Ok(tokens)
+}
+
+/// Recursive descent: OR binds loosest, then AND (explicit or implied),
+/// then NOT
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<&Token> {
+        self.pos += 1;
+        self.tokens.get(self.pos - 1)
+    }
+
+    fn or(&mut self) -> Result<Expr> {
+        let mut expr = self.and()?;
+        while self.peek() == Some(&Token::Or) {
+            self.pos += 1;
+            expr = Expr::Or(Box::new(expr), Box::new(self.and()?));
+        }
+        Ok(expr)
+    }
+
+    fn and(&mut self) -> Result<Expr> {
+        let mut expr = self.not()?;
+        loop {
+            match self.peek() {
+                Some(Token::And) => self.pos += 1,
+                Some(Token::Word(_) | Token::Not | Token::Open) => {}
+                _ => return Ok(expr),
+            }
+            expr = Expr::And(Box::new(expr), Box::new(self.not()?));
+        }
+    }
+
+    fn not(&mut self) -> Result<Expr> {
+        if self.peek() == Some(&Token::Not) {
+            self.pos += 1;
+            return Ok(Expr::Not(Box::new(self.not()?)));
+        }
+        self.primary()
+    }
+
+    fn primary(&mut self) -> Result<Expr> {
+        match self.next() {
+            Some(Token::Word(word)) => {
+                let word = word.clone();
+                Ok(Expr::Term(Term::parse(&word)?))
+            }
+            Some(Token::Open) => {
+                let expr = self.or()?;
+                match self.next() {
+                    Some(Token::Close) => Ok(expr),
+                    _ => anyhow::bail!("Missing ')' in search expression"),
+                }
+            }
+            Some(token) => anyhow::bail!("Expected a term, found {}", token.describe()),
+            None => anyhow::bail!("Search expression ends early; expected a term"),
+        }
+    }
+}
+
+/// Entries in `backend` matching `expr`, from the search index under
+/// `noggin_path`, refreshed first. With free text, entries are ordered by
+/// similarity to it (score 0-100); otherwise by path with score 0.
+pub fn search(
+    noggin_path: &Path,
+    backend: &dyn KnowledgeBackend,
+    expr: &Expr,
+) -> Result<Vec<QueryResult>> {
+    let embedder = HashingEmbedder::new();
+    let (index, _) = SemanticIndex::refresh(noggin_path, backend, &embedder)
+        .context("Failed to refresh search index")?;
+
+    let text = expr.ranking_text();
+    let candidates: Vec<(String, f32)> = if text.is_empty() {
+        index.entries.keys().map(|path| (path.clone(), 0.0)).collect()
+    } else {
+        index.search(&text, &embedder)
+    };
+
+    let mut results = Vec::new();
+    for (path, similarity) in candidates {
+        let Ok(Some(arf)) = backend.read_arf(&path) else {
+            continue;
+        };
+        let category = path.split('/').next().unwrap_or("unknown").to_string();
+        let entry = Entry {
+            path: &path,
+            category: &category,
+            arf: &arf,
+        };
+
+        let mut matched = Vec::new();
+        if !expr.matches(&entry, &mut matched) {
+            continue;
+        }
+        let score = f64::from(similarity.max(0.0)) * 100.0;
+        results.push(QueryResult::new(path, category, arf, matched, score));
+    }
+
+    Ok(results)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::FilesystemBackend;
+    use tempfile::TempDir;
+
+    fn entry_matches(query: &str, path: &str, arf: &ArfFile) -> bool {
+        let expr = Expr::parse(query).unwrap();
+        let category = path.split('/').next().unwrap();
+        let entry = Entry { path, category, arf };
+        expr.matches(&entry, &mut Vec::new())
+    }
+
+    fn pooling() -> ArfFile {
+        let mut arf = ArfFile::new(
+            "Use connection pooling",
+            "Postgres connection limits",
+            "PgBouncer in transaction mode",
+        );
+        arf.tags = vec!["database".to_string()];
+        arf.confidence = Some(0.8);
+        arf.context.files = vec!["src/db/pool.rs".to_string()];
+        arf.context.issues = vec!["#42".to_string()];
+        arf
+    }
+
+    #[test]
+    fn test_field_terms() {
+        let arf = pooling();
+        let path = "decisions/use-connection-pooling.arf";
+
+        assert!(entry_matches("what:pooling AND category:decisions AND file:src/db/**", path, &arf));
+        assert!(entry_matches("what:POOLING category:decisions", path, &arf));
+        assert!(!entry_matches("what:pooling category:bugs", path, &arf));
+        assert!(entry_matches("file:src/db", path, &arf));
+        assert!(!entry_matches("file:src/d", path, &arf));
+        assert!(!entry_matches("file:src/*.rs", path, &arf));
+        assert!(entry_matches("tag:Database issue:42", path, &arf));
+        assert!(entry_matches("path:decisions/*", path, &arf));
+        assert!(entry_matches("why:\"connection limits\"", path, &arf));
+        assert!(entry_matches("confidence:>=0.8", path, &arf));
+        assert!(!entry_matches("confidence:<0.5", path, &arf));
+        assert!(entry_matches("pgbouncer", path, &arf));
+    }
+
+    #[test]
+    fn test_operators() {
+        let arf = pooling();
+        let path = "decisions/use-connection-pooling.arf";
+
+        assert!(entry_matches("tag:retry OR tag:database", path, &arf));
+        assert!(!entry_matches("NOT tag:database", path, &arf));
+        assert!(entry_matches("pooling NOT category:facts", path, &arf));
+        // AND binds tighter than OR
+        assert!(entry_matches("tag:retry AND what:x OR what:pooling", path, &arf));
+        assert!(!entry_matches("tag:retry AND (what:x OR what:pooling)", path, &arf));
+        // Quoted operators are plain text
+        assert!(!entry_matches("\"OR\"", path, &arf));
+    }
+
+    #[test]
+    fn test_matched_fields() {
+        let arf = pooling();
+        let expr = Expr::parse("connection OR tag:database NOT how:redis").unwrap();
+        let entry = Entry {
+            path: "decisions/a.arf",
+            category: "decisions",
+            arf: &arf,
+        };
+        let mut matched = Vec::new();
+        assert!(expr.matches(&entry, &mut matched));
+        assert_eq!(matched, vec!["what", "why", "tags"]);
+        assert_eq!(expr.ranking_text(), "connection");
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        for (query, message) in [
+            ("", "Empty"),
+            ("colour:red", "Unknown field 'colour'"),
+            ("what:", "Missing value"),
+            ("(what:x", "Missing ')'"),
+            ("what:x AND", "ends early"),
+            ("what:x )", "Unexpected ')'"),
+            ("\"open", "Unclosed quote"),
+            ("confidence:high", "Invalid confidence"),
+        ] {
+            let err = Expr::parse(query).unwrap_err().to_string();
+            assert!(err.contains(message), "{}: {}", query, err);
+        }
+        // A colon after something that isn't a field name is plain text
+        assert!(Expr::parse("10:30").is_ok());
+    }
+
+    #[test]
+    fn test_search_ranks_free_text() {
+        let temp_dir = TempDir::new().unwrap();
+        pooling()
+            .to_toml(&temp_dir.path().join("decisions/use-connection-pooling.arf"))
+            .unwrap();
+        ArfFile::new("Retry payment webhooks", "Providers time out", "Exponential backoff")
+            .to_toml(&temp_dir.path().join("patterns/retry-payment-webhooks.arf"))
+            .unwrap();
+        let backend = FilesystemBackend::new(temp_dir.path());
+        let run = |query: &str| search(temp_dir.path(), &backend, &Expr::parse(query).unwrap());
+
+        let all = run("NOT category:facts").unwrap();
+        assert_eq!(all.len(), 2);
+        assert_eq!(all[0].file_path, "decisions/use-connection-pooling.arf");
+        assert_eq!(all[0].score, 0.0);
+
+        let found = run("retry OR pooling").unwrap();
+        assert_eq!(found.len(), 2);
+        assert!(found[0].score > 0.0);
+
+        let none = run("category:bugs").unwrap();
+        assert!(none.is_empty());
+    }
+}
@@ -0,0 +1,162 @@
+//! Text embeddings for the semantic index
+//!
+//! The built-in [`HashingEmbedder`] needs no model or network: it hashes
+//! word unigrams, bigrams, and character trigrams into a fixed-size
+//! vector (the "hashing trick"), so texts sharing vocabulary or word
+//! stems land near each other. Another [`Embedder`] can be swapped in
+//! without changing the index format; vectors from different embedders
+//! are never mixed.
+
+/// Turns text into a unit-length vector
+pub trait Embedder {
+    /// Identifier stored with the index; a change forces a rebuild
+    fn name(&self) -> &str;
+
+    fn dims(&self) -> usize;
+
+    fn embed(&self, text: &str) -> Vec<f32>;
+}
+
+/// Feature-hashing embedder over words, word pairs, and character trigrams
+#[derive(Debug, Clone)]
+pub struct HashingEmbedder {
+    dims: usize,
+}
+
+const DEFAULT_DIMS: usize = 512;
+
+const STOP_WORDS: &[&str] = &[
+    "a", "an", "and", "are", "as", "at", "be", "by", "do", "does", "for", "from", "how", "in",
+    "is", "it", "of", "on", "or", "that", "the", "this", "to", "was", "we", "what", "when",
+    "where", "which", "who", "why", "with",
+];
+
+impl HashingEmbedder {
+    pub fn new() -> Self {
+        Self { dims: DEFAULT_DIMS }
+    }
+}
+
+impl Default for HashingEmbedder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Embedder for HashingEmbedder {
+    fn name(&self) -> &str {
+        "hashing-v1"
+    }
+
+    fn dims(&self) -> usize {
+        self.dims
+    }
+
+    fn embed(&self, text: &str) -> Vec<f32> {
+        let mut vector = vec![0.0f32; self.dims];
+        let tokens = tokenize(text);
+
+        for token in &tokens {
+            self.add(&mut vector, token, 1.0);
+
+            let padded: Vec<char> = format!("^{}$", token).chars().collect();
+            for trigram in padded.windows(3) {
+                self.add(&mut vector, &trigram.iter().collect::<String>(), 0.25);
+            }
+        }
+        for pair in tokens.windows(2) {
+            self.add(&mut vector, &format!("{} {}", pair[0], pair[1]), 0.5);
+        }
+
+        normalize(&mut vector);
+        vector
+    }
+}
+
+impl HashingEmbedder {
+    fn add(&self, vector: &mut [f32], feature: &str, weight: f32) {
+        let hash = fnv1a(feature.as_bytes());
+        let slot = (hash % self.dims as u64) as usize;
+        let sign = if hash >> 63 == 0 { 1.0 } else { -1.0 };
+        vector[slot] += sign * weight;
+    }
+}
+
+/// Lowercased, stemmed words with stop words removed
+pub fn tokenize(text: &str) -> Vec<String> {
+    text.to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| word.chars().count() > 1 && !STOP_WORDS.contains(word))
+        .map(stem)
+        .collect()
+}
+
+/// Strip a few common English suffixes so "retries", "retrying", and
+/// "retried" share features with "retry"
+fn stem(word: &str) -> String {
+    if word.chars().count() <= 4 {
+        return word.to_string();
+    }
+    for (suffix, replacement) in [("ies", "y"), ("ied", "y"), ("ing", ""), ("ed", ""), ("es", ""), ("s", "")] {
+        if let Some(stripped) = word.strip_suffix(suffix) {
+            if stripped.chars().count() >= 3 {
+                return format!("{}{}", stripped, replacement);
+            }
+        }
+    }
+    word.to_string()
+}
+
+fn fnv1a(bytes: &[u8]) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in bytes {
+        hash ^= u64::from(*byte);
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
+fn normalize(vector: &mut [f32]) {
+    let norm = vector.iter().map(|v| v * v).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        for v in vector.iter_mut() {
+            *v /= norm;
+        }
+    }
+}
+
+/// Cosine similarity of two unit vectors (their dot product)
+pub fn cosine(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tokenize_and_stem() {
+        assert_eq!(tokenize("Why do we retry the payments?"), vec!["retry", "payment"]);
+        assert_eq!(stem("retries"), "retry");
+        assert_eq!(stem("retrying"), "retry");
+        assert_eq!(stem("bus"), "bus");
+    }
+
+    #[test]
+    fn test_related_text_is_closer() {
+        let embedder = HashingEmbedder::new();
+        let query = embedder.embed("how are failed payments retried?");
+        let related = embedder.embed("Retry failed payment charges with exponential backoff");
+        let unrelated = embedder.embed("Use structured logging with tracing spans");
+
+        assert!(cosine(&query, &related) > cosine(&query, &unrelated));
+        assert!((cosine(&related, &related) - 1.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_empty_text_embeds_to_zero() {
+        let embedder = HashingEmbedder::new();
+        let vector = embedder.embed("the of and");
+        assert!(vector.iter().all(|v| *v == 0.0));
+    }
+}
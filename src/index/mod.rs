@@ -0,0 +1,267 @@
+//! Semantic search index over ARF entries.
+//!
+//! Stores one embedding per ARF in `.noggin/index/embeddings.json`, keyed
+//! by path relative to `.noggin/`. Each entry records a hash of the text
+//! it was computed from, so [`SemanticIndex::refresh`] only re-embeds
+//! ARFs that were added or changed since the last run and drops entries
+//! for ARFs that were removed. Learn refreshes the index after writing;
+//! queries refresh it again so hand edits are picked up. ARFs are read
+//! through the knowledge backend; the index itself is local data and
+//! always lives under `.noggin/`.
+
+pub mod embed;
+
+use crate::arf::ArfFile;
+use crate::storage::KnowledgeBackend;
+use anyhow::{Context, Result};
+use embed::{cosine, Embedder};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const INDEX_DIR: &str = "index";
+const INDEX_FILE: &str = "embeddings.json";
+
+/// Contents of `.noggin/index/embeddings.json`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SemanticIndex {
+    /// Embedder the vectors came from; a different one forces a rebuild
+    pub model: String,
+    pub dims: usize,
+    pub entries: BTreeMap<String, IndexEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexEntry {
+    /// SHA-256 of the embedded text
+    pub hash: String,
+    pub vector: Vec<f32>,
+}
+
+/// What a refresh changed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct IndexUpdate {
+    pub added: usize,
+    pub updated: usize,
+    pub removed: usize,
+}
+
+impl IndexUpdate {
+    pub fn is_empty(&self) -> bool {
+        self.added + self.updated + self.removed == 0
+    }
+}
+
+impl SemanticIndex {
+    /// Load the index, or an empty one if it doesn't exist yet
+    pub fn load(noggin_path: &Path) -> Result<Self> {
+        let path = index_path(noggin_path);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let contents = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read index from {}", path.display()))?;
+        serde_json::from_str(&contents)
+            .with_context(|| format!("Failed to parse index from {}", path.display()))
+    }
+
+    pub fn save(&self, noggin_path: &Path) -> Result<()> {
+        let path = index_path(noggin_path);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+        let contents = serde_json::to_string(self).context("Failed to serialize index")?;
+        fs::write(&path, contents)
+            .with_context(|| format!("Failed to write index to {}", path.display()))
+    }
+
+    /// Load the index, bring it in line with the ARFs in `backend`, and
+    /// save it if anything changed
+    pub fn refresh(
+        noggin_path: &Path,
+        backend: &dyn KnowledgeBackend,
+        embedder: &dyn Embedder,
+    ) -> Result<(Self, IndexUpdate)> {
+        let mut index = Self::load(noggin_path).unwrap_or_default();
+        let update = index.update(backend, embedder)?;
+        if !update.is_empty() {
+            index.save(noggin_path)?;
+        }
+        Ok((index, update))
+    }
+
+    /// Re-embed new and changed ARFs and drop entries whose ARF is gone
+    pub fn update(
+        &mut self,
+        backend: &dyn KnowledgeBackend,
+        embedder: &dyn Embedder,
+    ) -> Result<IndexUpdate> {
+        let mut update = IndexUpdate::default();
+
+        if self.model != embedder.name() || self.dims != embedder.dims() {
+            self.model = embedder.name().to_string();
+            self.dims = embedder.dims();
+            self.entries.clear();
+        }
+
+        let mut seen = Vec::new();
+        for (rel_path, arf) in arf_files(backend)? {
+            let text = embedding_text(&arf);
+            let hash = format!("{:x}", Sha256::digest(text.as_bytes()));
+            seen.push(rel_path.clone());
+
+            match self.entries.get(&rel_path) {
+                Some(existing) if existing.hash == hash => continue,
+                Some(_) => update.updated += 1,
+                None => update.added += 1,
+            }
+            self.entries.insert(
+                rel_path,
+                IndexEntry {
+                    hash,
+                    vector: embedder.embed(&text),
+                },
+            );
+        }
+
+        let before = self.entries.len();
+        self.entries.retain(|path, _| seen.contains(path));
+        update.removed = before - self.entries.len();
+
+        Ok(update)
+    }
+
+    /// Embed `arf` as the entry at `rel_path`, for an ARF written since the
+    /// last update
+    pub fn insert(&mut self, rel_path: &str, arf: &ArfFile, embedder: &dyn Embedder) {
+        let text = embedding_text(arf);
+        let hash = format!("{:x}", Sha256::digest(text.as_bytes()));
+        self.entries.insert(
+            rel_path.to_string(),
+            IndexEntry {
+                hash,
+                vector: embedder.embed(&text),
+            },
+        );
+    }
+
+    /// Every indexed path with its similarity to `query`, most similar first
+    pub fn search(&self, query: &str, embedder: &dyn Embedder) -> Vec<(String, f32)> {
+        let query = embedder.embed(query);
+        let mut scored: Vec<(String, f32)> = self
+            .entries
+            .iter()
+            .map(|(path, entry)| (path.clone(), cosine(&query, &entry.vector)))
+            .collect();
+        scored.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+        scored
+    }
+
+    /// Every indexed path with its similarity to `arf`, embedded the same
+    /// way as indexed entries, most similar first
+    pub fn similar_to(&self, arf: &ArfFile, embedder: &dyn Embedder) -> Vec<(String, f32)> {
+        self.search(&embedding_text(arf), embedder)
+    }
+}
+
+fn index_path(noggin_path: &Path) -> PathBuf {
+    noggin_path.join(INDEX_DIR).join(INDEX_FILE)
+}
+
+/// Text an ARF is embedded from. `what` is repeated so the subject
+/// outweighs the longer explanation fields.
+fn embedding_text(arf: &ArfFile) -> String {
+    format!(
+        "{}\n{}\n{}\n{}\n{}",
+        arf.what,
+        arf.what,
+        arf.why,
+        arf.how,
+        arf.tags.join(" ")
+    )
+}
+
+/// Readable ARFs in `backend`, keyed by relative path
+fn arf_files(backend: &dyn KnowledgeBackend) -> Result<Vec<(String, ArfFile)>> {
+    Ok(backend
+        .list_arfs()?
+        .into_iter()
+        .filter_map(|path| {
+            let arf = backend.read_arf(&path).ok()??;
+            Some((path, arf))
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::FilesystemBackend;
+    use embed::HashingEmbedder;
+    use tempfile::TempDir;
+
+    fn write(noggin: &Path, path: &str, arf: &ArfFile) {
+        arf.to_toml(&noggin.join(path)).unwrap();
+    }
+
+    #[test]
+    fn test_refresh_is_incremental() {
+        let temp_dir = TempDir::new().unwrap();
+        let noggin = temp_dir.path();
+        let backend = FilesystemBackend::new(noggin);
+        let embedder = HashingEmbedder::new();
+        write(noggin, "patterns/retry.arf", &ArfFile::new("Retry failed payments", "Flaky networks", "Backoff"));
+        write(noggin, "facts/logging.arf", &ArfFile::new("Structured logging", "Searchable", "tracing"));
+
+        let (index, update) = SemanticIndex::refresh(noggin, &backend, &embedder).unwrap();
+        assert_eq!(update, IndexUpdate { added: 2, updated: 0, removed: 0 });
+        assert_eq!(index.entries.len(), 2);
+        assert!(noggin.join("index/embeddings.json").exists());
+
+        let (_, update) = SemanticIndex::refresh(noggin, &backend, &embedder).unwrap();
+        assert!(update.is_empty());
+
+        write(noggin, "patterns/retry.arf", &ArfFile::new("Retry failed payments", "Processor SLA", "Backoff"));
+        fs::remove_file(noggin.join("facts/logging.arf")).unwrap();
+        let (index, update) = SemanticIndex::refresh(noggin, &backend, &embedder).unwrap();
+        assert_eq!(update, IndexUpdate { added: 0, updated: 1, removed: 1 });
+        assert_eq!(index.entries.keys().collect::<Vec<_>>(), vec!["patterns/retry.arf"]);
+    }
+
+    #[test]
+    fn test_search_ranks_by_meaning() {
+        let temp_dir = TempDir::new().unwrap();
+        let noggin = temp_dir.path();
+        let backend = FilesystemBackend::new(noggin);
+        let embedder = HashingEmbedder::new();
+        write(noggin, "patterns/retry.arf", &ArfFile::new("Retry failed payment charges", "Card networks are flaky", "Exponential backoff"));
+        write(noggin, "facts/logging.arf", &ArfFile::new("Structured logging", "Searchable logs", "tracing spans"));
+
+        let (index, _) = SemanticIndex::refresh(noggin, &backend, &embedder).unwrap();
+        let results = index.search("why are payments retried?", &embedder);
+        assert_eq!(results[0].0, "patterns/retry.arf");
+        assert!(results[0].1 > results[1].1);
+    }
+
+    #[test]
+    fn test_model_change_rebuilds() {
+        let temp_dir = TempDir::new().unwrap();
+        let noggin = temp_dir.path();
+        write(noggin, "facts/a.arf", &ArfFile::new("A fact", "w", "h"));
+
+        let mut index = SemanticIndex {
+            model: "other-model".to_string(),
+            dims: 3,
+            ..Default::default()
+        };
+        let update = index
+            .update(&FilesystemBackend::new(noggin), &HashingEmbedder::new())
+            .unwrap();
+        assert_eq!(update.added, 1);
+        assert_eq!(index.model, "hashing-v1");
+    }
+}
@@ -0,0 +1,209 @@
+//! Issue-tracker references on ARFs (`context.issues`).
+//!
+//! References are collected from commit messages: trailers such as
+//! `Fixes: #12` or `Refs: PROJ-7`, closing keywords in the body ("closes
+//! #12"), and the pull request number GitHub appends to squash-merge
+//! subjects ("Add retries (#123)") or puts in merge commit subjects.
+//! Short references are expanded to URLs with the configured
+//! `issues.url_template`, or one inferred from the `origin` forge.
+
+use crate::arf::ArfFile;
+use crate::config::IssuesConfig;
+use regex::Regex;
+use std::sync::OnceLock;
+
+/// Trailer keys whose values are issue references
+const TRAILER_KEYS: &[&str] = &["fixes", "closes", "resolves", "refs", "references", "issue", "see"];
+
+fn short_reference() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"^(#\d+|[A-Z][A-Z0-9]+-\d+)$").unwrap())
+}
+
+/// URL template for `{id}`: the configured one, else one inferred from a
+/// normalized `origin` URL (`host/owner/repo`) on a known forge
+pub fn url_template(config: &IssuesConfig, origin_url: Option<&str>) -> Option<String> {
+    if let Some(template) = &config.url_template {
+        return Some(template.clone());
+    }
+
+    let origin = origin_url?;
+    let host = origin.split('/').next()?;
+    if host.contains("gitlab") {
+        Some(format!("https://{}/-/issues/{{id}}", origin))
+    } else if host == "github.com" || host == "codeberg.org" || host.contains("gitea") {
+        Some(format!("https://{}/issues/{{id}}", origin))
+    } else {
+        None
+    }
+}
+
+/// True for a URL or a short reference (`#123`, `PROJ-123`)
+pub fn is_valid(reference: &str) -> bool {
+    let reference = reference.trim();
+    reference.starts_with("https://")
+        || reference.starts_with("http://")
+        || short_reference().is_match(reference)
+}
+
+/// Clean up a reference and expand a short one with `template`.
+/// Unrecognized references return None.
+pub fn normalize(reference: &str, template: Option<&str>) -> Option<String> {
+    let reference = reference
+        .trim()
+        .trim_end_matches(['.', ',', ';', ')'])
+        .trim_start_matches('(');
+    if !is_valid(reference) {
+        return None;
+    }
+    if reference.starts_with("http") {
+        return Some(reference.to_string());
+    }
+
+    let id = reference.trim_start_matches('#');
+    Some(match template {
+        Some(template) => template.replace("{id}", id),
+        None => reference.to_string(),
+    })
+}
+
+/// Issue and pull request references in a commit message, in order of
+/// appearance and without duplicates
+pub fn references_in(message: &str) -> Vec<String> {
+    static KEYWORDS: OnceLock<Regex> = OnceLock::new();
+    static PULL_REQUEST: OnceLock<Regex> = OnceLock::new();
+    let keywords = KEYWORDS.get_or_init(|| {
+        Regex::new(r"(?i)\b(?:fix(?:e[sd])?|close[sd]?|resolve[sd]?)\s+(#\d+|[A-Z][A-Z0-9]+-\d+)\b").unwrap()
+    });
+    let pull_request = PULL_REQUEST.get_or_init(|| {
+        Regex::new(r"^(?:Merge pull request (#\d+)|.*\((#\d+)\)\s*$)").unwrap()
+    });
+
+    let mut found: Vec<String> = Vec::new();
+    let mut push = |reference: &str| {
+        if is_valid(reference) && !found.iter().any(|r| r == reference) {
+            found.push(reference.to_string());
+        }
+    };
+
+    if let Some(caps) = message.lines().next().and_then(|summary| pull_request.captures(summary)) {
+        if let Some(number) = caps.get(1).or_else(|| caps.get(2)) {
+            push(number.as_str());
+        }
+    }
+
+    for line in message.lines() {
+        if let Some((key, value)) = line.split_once(':') {
+            if TRAILER_KEYS.contains(&key.trim().to_lowercase().as_str()) {
+                for reference in value.split([',', ' ']).filter(|r| !r.is_empty()) {
+                    push(reference.trim());
+                }
+                continue;
+            }
+        }
+        for caps in keywords.captures_iter(line) {
+            push(&caps[1]);
+        }
+    }
+
+    found
+}
+
+/// Normalize an ARF's issues in place, dropping unrecognized ones and
+/// duplicates
+pub fn normalize_arf(arf: &mut ArfFile, template: Option<&str>) {
+    let mut issues: Vec<String> = Vec::new();
+    for reference in &arf.context.issues {
+        if let Some(issue) = normalize(reference, template) {
+            if !issues.contains(&issue) {
+                issues.push(issue);
+            }
+        }
+    }
+    arf.context.issues = issues;
+}
+
+/// Markdown link for a reference: the URL's last path segment as the
+/// label, or the reference itself when it isn't a URL
+pub fn markdown_link(reference: &str) -> String {
+    if !reference.starts_with("http") {
+        return reference.to_string();
+    }
+    let label = reference.trim_end_matches('/').rsplit('/').next().unwrap_or(reference);
+    let label = if label.chars().all(|c| c.is_ascii_digit()) {
+        format!("#{}", label)
+    } else {
+        label.to_string()
+    };
+    format!("[{}]({})", label, reference)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_url_template() {
+        let none = IssuesConfig::default();
+        assert_eq!(
+            url_template(&none, Some("github.com/ducks/noggin")).unwrap(),
+            "https://github.com/ducks/noggin/issues/{id}"
+        );
+        assert_eq!(
+            url_template(&none, Some("gitlab.com/group/app")).unwrap(),
+            "https://gitlab.com/group/app/-/issues/{id}"
+        );
+        assert!(url_template(&none, Some("git.internal/app")).is_none());
+
+        let jira = IssuesConfig {
+            url_template: Some("https://acme.atlassian.net/browse/{id}".to_string()),
+        };
+        assert_eq!(
+            url_template(&jira, Some("github.com/ducks/noggin")).unwrap(),
+            "https://acme.atlassian.net/browse/{id}"
+        );
+    }
+
+    #[test]
+    fn test_normalize() {
+        let template = Some("https://github.com/ducks/noggin/issues/{id}");
+        assert_eq!(
+            normalize("#42.", template).unwrap(),
+            "https://github.com/ducks/noggin/issues/42"
+        );
+        assert_eq!(normalize("#42", None).unwrap(), "#42");
+        assert_eq!(
+            normalize("PAY-7", Some("https://acme.atlassian.net/browse/{id}")).unwrap(),
+            "https://acme.atlassian.net/browse/PAY-7"
+        );
+        assert_eq!(
+            normalize(" https://example.com/t/9 ", template).unwrap(),
+            "https://example.com/t/9"
+        );
+        assert!(normalize("see the wiki", template).is_none());
+    }
+
+    #[test]
+    fn test_references_in() {
+        let message = "Retry failed payments (#123)\n\nCharges were lost on timeouts; this closes #40.\n\nFixes: #41, PAY-7\nSigned-off-by: Dev <dev@example.com>\n";
+        assert_eq!(references_in(message), vec!["#123", "#40", "#41", "PAY-7"]);
+        assert_eq!(
+            references_in("Merge pull request #88 from ducks/retries"),
+            vec!["#88"]
+        );
+        assert!(references_in("Refactor scanner").is_empty());
+    }
+
+    #[test]
+    fn test_markdown_link() {
+        assert_eq!(
+            markdown_link("https://github.com/ducks/noggin/issues/42"),
+            "[#42](https://github.com/ducks/noggin/issues/42)"
+        );
+        assert_eq!(
+            markdown_link("https://acme.atlassian.net/browse/PAY-7"),
+            "[PAY-7](https://acme.atlassian.net/browse/PAY-7)"
+        );
+        assert_eq!(markdown_link("#42"), "#42");
+    }
+}
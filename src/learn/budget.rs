@@ -0,0 +1,399 @@
+//! Per-run budget enforcement for provider usage.
+//!
+//! Tracks requests, estimated tokens, estimated cost, and elapsed time
+//! during a learn run. The pipeline checks the budget before issuing
+//! each prompt and defers remaining work once a cap would be exceeded.
+//! A response's size isn't known until it arrives, so each admitted
+//! request holds `budget.reserve_response_tokens` against the caps until
+//! its response is recorded and the reservation [settled](Budget::settle).
+//!
+//! Usage is also broken down per provider, priced with
+//! `budget.provider_costs` where set, and each run's breakdown can be
+//! saved under `.noggin/reports/`.
+
+use crate::config::BudgetConfig;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// Directory under .noggin/ holding per-run cost reports
+pub const REPORTS_DIR: &str = "reports";
+
+/// Which cap stopped the run
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BudgetLimit {
+    Requests,
+    Tokens,
+    Time,
+    Cost,
+    /// A machine-wide daily cap (see [`crate::learn::ledger`])
+    Daily,
+}
+
+impl fmt::Display for BudgetLimit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BudgetLimit::Requests => write!(f, "max requests"),
+            BudgetLimit::Tokens => write!(f, "max tokens"),
+            BudgetLimit::Time => write!(f, "max time"),
+            BudgetLimit::Cost => write!(f, "max cost"),
+            BudgetLimit::Daily => write!(f, "daily machine budget"),
+        }
+    }
+}
+
+/// Usage attributed to one provider
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ProviderUsage {
+    pub requests: u32,
+    pub prompt_tokens: u64,
+    pub response_tokens: u64,
+    pub estimated_cost_usd: f64,
+}
+
+impl ProviderUsage {
+    pub fn tokens(&self) -> u64 {
+        self.prompt_tokens + self.response_tokens
+    }
+}
+
+/// Spend of one learn run, as saved under `.noggin/reports/`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CostReport {
+    pub finished_at: DateTime<Utc>,
+    /// Learn mode: "full", "incremental", or "lite"
+    pub mode: String,
+    pub requests: u32,
+    pub tokens: u64,
+    pub estimated_cost_usd: f64,
+    pub providers: BTreeMap<String, ProviderUsage>,
+}
+
+impl CostReport {
+    /// Write the report to `.noggin/reports/cost-<timestamp>.json` and
+    /// return its path
+    pub fn save(&self, noggin_path: &Path) -> Result<PathBuf> {
+        let dir = noggin_path.join(REPORTS_DIR);
+        fs::create_dir_all(&dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+        let path = dir.join(format!(
+            "cost-{}.json",
+            self.finished_at.format("%Y%m%dT%H%M%S%3fZ")
+        ));
+        let json = serde_json::to_string_pretty(self)?;
+        fs::write(&path, json).with_context(|| format!("Failed to write {}", path.display()))?;
+        Ok(path)
+    }
+}
+
+/// Running usage totals checked against the configured caps
+#[derive(Debug)]
+pub struct Budget {
+    limits: BudgetConfig,
+    started: Instant,
+    requests: u32,
+    tokens: u64,
+    cost: f64,
+    providers: BTreeMap<String, ProviderUsage>,
+    /// Response tokens held for admitted requests, by provider
+    reserved: BTreeMap<String, u64>,
+}
+
+impl Budget {
+    /// Start tracking a run against the given caps
+    pub fn new(limits: BudgetConfig) -> Self {
+        Self {
+            limits,
+            started: Instant::now(),
+            requests: 0,
+            tokens: 0,
+            cost: 0.0,
+            providers: BTreeMap::new(),
+            reserved: BTreeMap::new(),
+        }
+    }
+
+    /// Check whether sending a prompt of `prompt_tokens` to `providers`
+    /// providers would exceed any cap. Returns the first cap hit.
+    pub fn check(&self, prompt_tokens: u64, providers: usize) -> Option<BudgetLimit> {
+        if let Some(max_time) = self.limits.max_time_secs {
+            if self.elapsed() >= Duration::from_secs(max_time) {
+                return Some(BudgetLimit::Time);
+            }
+        }
+
+        if let Some(max_requests) = self.limits.max_requests {
+            if self.requests + providers as u32 > max_requests {
+                return Some(BudgetLimit::Requests);
+            }
+        }
+
+        let request_tokens = (prompt_tokens + self.limits.reserve_response_tokens) * providers as u64;
+        let projected_tokens = self.tokens + self.reserved_tokens() + request_tokens;
+
+        if let Some(max_tokens) = self.limits.max_tokens {
+            if projected_tokens > max_tokens {
+                return Some(BudgetLimit::Tokens);
+            }
+        }
+
+        if let Some(max_cost) = self.limits.max_cost_usd {
+            let projected_cost =
+                self.cost + self.cost_of(self.reserved_tokens()) + self.cost_of(request_tokens);
+            if projected_cost > max_cost {
+                return Some(BudgetLimit::Cost);
+            }
+        }
+
+        None
+    }
+
+    /// Record a prompt sent to `provider` and reserve the response
+    /// allowance for it until [`settle`](Self::settle)
+    pub fn admit(&mut self, provider: &str, prompt_tokens: u64) {
+        self.record_provider(provider, prompt_tokens, 0);
+        *self.reserved.entry(provider.to_string()).or_default() +=
+            self.limits.reserve_response_tokens;
+    }
+
+    /// Release the reservations of admitted requests, once the responses
+    /// that arrived have been recorded with
+    /// [`record_response`](Self::record_response)
+    pub fn settle(&mut self) {
+        self.reserved.clear();
+    }
+
+    /// Response tokens held for `provider`'s outstanding requests
+    pub fn reserved(&self, provider: &str) -> u64 {
+        self.reserved.get(provider).copied().unwrap_or(0)
+    }
+
+    fn reserved_tokens(&self) -> u64 {
+        self.reserved.values().sum()
+    }
+
+    /// Response tokens set aside for each new request
+    pub fn response_allowance(&self) -> u64 {
+        self.limits.reserve_response_tokens
+    }
+
+    /// Record usage after a prompt was sent, priced at the blended rate
+    pub fn record(&mut self, requests: u32, tokens: u64) {
+        self.requests += requests;
+        self.tokens += tokens;
+        self.cost += self.cost_of(tokens);
+    }
+
+    /// Record one request to `provider`, priced at its configured rate
+    pub fn record_provider(&mut self, provider: &str, prompt_tokens: u64, response_tokens: u64) {
+        let tokens = prompt_tokens + response_tokens;
+        let cost = tokens as f64 / 1_000_000.0 * self.price_of(provider);
+        self.requests += 1;
+        self.tokens += tokens;
+        self.cost += cost;
+
+        let usage = self.providers.entry(provider.to_string()).or_default();
+        usage.requests += 1;
+        usage.prompt_tokens += prompt_tokens;
+        usage.response_tokens += response_tokens;
+        usage.estimated_cost_usd += cost;
+    }
+
+    /// Add response tokens to a request already recorded with
+    /// [`record_provider`](Self::record_provider)
+    pub fn record_response(&mut self, provider: &str, response_tokens: u64) {
+        let cost = response_tokens as f64 / 1_000_000.0 * self.price_of(provider);
+        self.tokens += response_tokens;
+        self.cost += cost;
+
+        let usage = self.providers.entry(provider.to_string()).or_default();
+        usage.response_tokens += response_tokens;
+        usage.estimated_cost_usd += cost;
+    }
+
+    /// Usage recorded per provider, by name
+    pub fn by_provider(&self) -> &BTreeMap<String, ProviderUsage> {
+        &self.providers
+    }
+
+    /// Number of provider requests issued so far
+    pub fn requests(&self) -> u32 {
+        self.requests
+    }
+
+    /// Estimated tokens consumed so far
+    pub fn tokens(&self) -> u64 {
+        self.tokens
+    }
+
+    /// Estimated spend so far, in USD
+    pub fn estimated_cost(&self) -> f64 {
+        self.cost
+    }
+
+    /// Time since the run started
+    pub fn elapsed(&self) -> Duration {
+        self.started.elapsed()
+    }
+
+    fn cost_of(&self, tokens: u64) -> f64 {
+        tokens as f64 / 1_000_000.0 * self.limits.cost_per_million_tokens
+    }
+
+    /// Price per million tokens for `provider`
+    pub fn price_of(&self, provider: &str) -> f64 {
+        self.limits
+            .provider_costs
+            .get(provider)
+            .copied()
+            .unwrap_or(self.limits.cost_per_million_tokens)
+    }
+}
+
+/// Token estimate from [`crate::learn::tokens`]
+pub fn estimate_tokens(text: &str) -> u64 {
+    crate::learn::tokens::count_tokens(text) as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unlimited_budget_never_stops() {
+        let budget = Budget::new(BudgetConfig::default());
+        assert_eq!(budget.check(1_000_000, 3), None);
+    }
+
+    #[test]
+    fn test_request_cap() {
+        let mut budget = Budget::new(BudgetConfig {
+            max_requests: Some(4),
+            ..Default::default()
+        });
+
+        assert_eq!(budget.check(10, 3), None);
+        budget.record(3, 30);
+        assert_eq!(budget.check(10, 3), Some(BudgetLimit::Requests));
+        assert_eq!(budget.check(10, 1), None);
+    }
+
+    #[test]
+    fn test_token_cap() {
+        let budget = Budget::new(BudgetConfig {
+            max_tokens: Some(100),
+            reserve_response_tokens: 0,
+            ..Default::default()
+        });
+
+        assert_eq!(budget.check(30, 3), None);
+        assert_eq!(budget.check(40, 3), Some(BudgetLimit::Tokens));
+    }
+
+    #[test]
+    fn test_reserved_responses_count_until_settled() {
+        let mut budget = Budget::new(BudgetConfig {
+            max_tokens: Some(1_000),
+            reserve_response_tokens: 300,
+            ..Default::default()
+        });
+
+        assert_eq!(budget.check(100, 2), None);
+        budget.admit("claude", 100);
+        budget.admit("gemini", 100);
+        assert_eq!(budget.reserved("claude"), 300);
+        // 800 held, so another 100-token prompt with its allowance won't fit
+        assert_eq!(budget.check(100, 1), Some(BudgetLimit::Tokens));
+
+        budget.record_response("claude", 50);
+        budget.settle();
+        assert_eq!(budget.tokens(), 250);
+        assert_eq!(budget.check(100, 1), None);
+    }
+
+    #[test]
+    fn test_cost_cap() {
+        let mut budget = Budget::new(BudgetConfig {
+            max_cost_usd: Some(1.0),
+            cost_per_million_tokens: 10.0,
+            reserve_response_tokens: 0,
+            ..Default::default()
+        });
+
+        budget.record(1, 90_000);
+        assert!((budget.estimated_cost() - 0.9).abs() < 1e-9);
+        assert_eq!(budget.check(5_000, 1), None);
+        assert_eq!(budget.check(20_000, 1), Some(BudgetLimit::Cost));
+    }
+
+    #[test]
+    fn test_provider_usage() {
+        let mut budget = Budget::new(BudgetConfig {
+            cost_per_million_tokens: 10.0,
+            provider_costs: BTreeMap::from([("claude".to_string(), 20.0)]),
+            ..Default::default()
+        });
+
+        budget.record_provider("claude", 40_000, 10_000);
+        budget.record_provider("gemini", 40_000, 20_000);
+        budget.record_provider("claude", 5_000, 5_000);
+
+        let claude = &budget.by_provider()["claude"];
+        assert_eq!(claude.requests, 2);
+        assert_eq!(claude.tokens(), 60_000);
+        assert!((claude.estimated_cost_usd - 1.2).abs() < 1e-9);
+        assert!((budget.by_provider()["gemini"].estimated_cost_usd - 0.6).abs() < 1e-9);
+        assert_eq!(budget.requests(), 3);
+        assert_eq!(budget.tokens(), 120_000);
+        assert!((budget.estimated_cost() - 1.8).abs() < 1e-9);
+
+        // A response added later counts toward the same request
+        budget.record_provider("gemini", 10_000, 0);
+        budget.record_response("gemini", 10_000);
+        let gemini = &budget.by_provider()["gemini"];
+        assert_eq!(gemini.requests, 2);
+        assert_eq!(gemini.response_tokens, 30_000);
+        assert_eq!(budget.requests(), 4);
+        assert!((budget.estimated_cost() - 2.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_save_cost_report() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let report = CostReport {
+            finished_at: "2026-03-04T05:06:07Z".parse().unwrap(),
+            mode: "incremental".to_string(),
+            requests: 1,
+            tokens: 100,
+            estimated_cost_usd: 0.001,
+            providers: BTreeMap::new(),
+        };
+
+        let path = report.save(dir.path()).unwrap();
+        assert!(path.ends_with("reports/cost-20260304T050607000Z.json"));
+        let saved: CostReport = serde_json::from_str(&fs::read_to_string(path).unwrap()).unwrap();
+        assert_eq!(saved.mode, "incremental");
+    }
+
+    #[test]
+    fn test_time_cap() {
+        let budget = Budget::new(BudgetConfig {
+            max_time_secs: Some(0),
+            ..Default::default()
+        });
+
+        assert_eq!(budget.check(1, 1), Some(BudgetLimit::Time));
+    }
+
+    #[test]
+    fn test_estimate_tokens() {
+        assert_eq!(estimate_tokens(""), 0);
+        assert_eq!(estimate_tokens("abcde"), 1);
+        assert_eq!(estimate_tokens("abcdef"), 2);
+    }
+}
@@ -0,0 +1,191 @@
+//! Checkpoint of work deferred by a budget-capped learn run, or lost to
+//! prompts no provider answered usably.
+//!
+//! Deferred files and commits are simply left out of the manifest, so the
+//! next run picks them up again. The checkpoint records what was skipped
+//! (for reporting) and carries invalidated pattern IDs, which would
+//! otherwise be lost once the changed files are recorded as scanned.
+
+use crate::learn::budget::BudgetLimit;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+pub const CHECKPOINT_FILE: &str = "checkpoint.toml";
+
+/// Work left over from a run that hit a budget cap or had prompts fail
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Checkpoint {
+    pub created_at: DateTime<Utc>,
+    /// The cap that stopped the run, None if it ran out of nothing
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub limit: Option<BudgetLimit>,
+    /// Prompts never sent because of `limit`
+    #[serde(default)]
+    pub deferred: Vec<DeferredPrompt>,
+    /// Prompts sent that got no usable response from any provider
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub failed: Vec<DeferredPrompt>,
+}
+
+/// A prompt whose work is left for the next run
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DeferredPrompt {
+    /// Prompt type (files, commits, patterns)
+    pub prompt_type: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub files: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub commits: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub patterns: Vec<String>,
+}
+
+impl Checkpoint {
+    /// None when there is nothing to carry over
+    pub fn new(
+        limit: Option<BudgetLimit>,
+        deferred: Vec<DeferredPrompt>,
+        failed: Vec<DeferredPrompt>,
+    ) -> Option<Self> {
+        if limit.is_none() && failed.is_empty() {
+            return None;
+        }
+        Some(Self {
+            created_at: Utc::now(),
+            limit,
+            deferred,
+            failed,
+        })
+    }
+
+    /// Deferred and failed prompts together
+    pub fn prompts(&self) -> impl Iterator<Item = &DeferredPrompt> {
+        self.deferred.iter().chain(&self.failed)
+    }
+
+    /// Load the checkpoint from .noggin/, if one exists
+    pub fn load(noggin_path: &Path) -> Result<Option<Self>> {
+        let path = noggin_path.join(CHECKPOINT_FILE);
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let contents = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read checkpoint from {}", path.display()))?;
+
+        let checkpoint = toml::from_str(&contents)
+            .with_context(|| format!("Failed to parse checkpoint from {}", path.display()))?;
+
+        Ok(Some(checkpoint))
+    }
+
+    /// Write the checkpoint to .noggin/checkpoint.toml
+    pub fn save(&self, noggin_path: &Path) -> Result<()> {
+        let path = noggin_path.join(CHECKPOINT_FILE);
+        let contents = toml::to_string_pretty(self)
+            .context("Failed to serialize checkpoint to TOML")?;
+
+        fs::write(&path, contents)
+            .with_context(|| format!("Failed to write checkpoint to {}", path.display()))
+    }
+
+    /// Remove the checkpoint after a run completes all work
+    pub fn clear(noggin_path: &Path) -> Result<()> {
+        let path = noggin_path.join(CHECKPOINT_FILE);
+        if path.exists() {
+            fs::remove_file(&path)
+                .with_context(|| format!("Failed to remove checkpoint {}", path.display()))?;
+        }
+        Ok(())
+    }
+
+    /// All pattern IDs whose re-analysis was deferred
+    pub fn deferred_patterns(&self) -> Vec<String> {
+        self.prompts()
+            .flat_map(|p| p.patterns.iter().cloned())
+            .collect()
+    }
+
+    /// Total files, commits, and patterns deferred or failed
+    pub fn counts(&self) -> (usize, usize, usize) {
+        self.prompts().fold((0, 0, 0), |(f, c, p), d| {
+            (f + d.files.len(), c + d.commits.len(), p + d.patterns.len())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn sample() -> Checkpoint {
+        Checkpoint::new(
+            Some(BudgetLimit::Cost),
+            vec![
+                DeferredPrompt {
+                    prompt_type: "commits".to_string(),
+                    commits: vec!["abc123".to_string(), "def456".to_string()],
+                    ..Default::default()
+                },
+                DeferredPrompt {
+                    prompt_type: "patterns".to_string(),
+                    files: vec!["src/errors.rs".to_string()],
+                    patterns: vec!["error-handling".to_string()],
+                    ..Default::default()
+                },
+            ],
+            vec![DeferredPrompt {
+                prompt_type: "files".to_string(),
+                files: vec!["src/db.rs".to_string()],
+                ..Default::default()
+            }],
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_save_and_load_roundtrip() {
+        let temp_dir = TempDir::new().unwrap();
+        sample().save(temp_dir.path()).unwrap();
+
+        let loaded = Checkpoint::load(temp_dir.path()).unwrap().unwrap();
+        assert_eq!(loaded.limit, Some(BudgetLimit::Cost));
+        assert_eq!(loaded.deferred.len(), 2);
+        assert_eq!(loaded.failed.len(), 1);
+        assert_eq!(loaded.deferred_patterns(), vec!["error-handling"]);
+    }
+
+    #[test]
+    fn test_nothing_to_carry_over() {
+        assert!(Checkpoint::new(None, Vec::new(), Vec::new()).is_none());
+        let failed_only = Checkpoint::new(None, Vec::new(), sample().failed).unwrap();
+        assert_eq!(failed_only.counts(), (1, 0, 0));
+    }
+
+    #[test]
+    fn test_load_missing_checkpoint() {
+        let temp_dir = TempDir::new().unwrap();
+        assert!(Checkpoint::load(temp_dir.path()).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_clear_removes_file() {
+        let temp_dir = TempDir::new().unwrap();
+        sample().save(temp_dir.path()).unwrap();
+
+        Checkpoint::clear(temp_dir.path()).unwrap();
+        assert!(Checkpoint::load(temp_dir.path()).unwrap().is_none());
+
+        // Clearing twice is fine
+        Checkpoint::clear(temp_dir.path()).unwrap();
+    }
+
+    #[test]
+    fn test_counts() {
+        assert_eq!(sample().counts(), (2, 2, 1));
+    }
+}
@@ -0,0 +1,281 @@
+//! Decoding file contents for prompts.
+//!
+//! Source files aren't always UTF-8. Rather than dropping a file that
+//! fails strict decoding, [`decode`] picks an encoding from its byte order
+//! mark or its bytes: UTF-16 with a BOM, UTF-8 (replacing the odd invalid
+//! byte), or Windows-1252 for legacy 8-bit text. The prompt builder notes
+//! any encoding other than plain UTF-8 next to the file, and reads at most
+//! [`crate::learn::tokens::PromptBudget::max_file_bytes`] of each file.
+
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+
+/// Encoding a file's contents were decoded from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    Utf8,
+    /// UTF-8 starting with a byte order mark, which is dropped
+    Utf8Bom,
+    Utf16Le,
+    Utf16Be,
+    /// Guessed for bytes that are mostly not UTF-8
+    Windows1252,
+}
+
+impl Encoding {
+    pub fn label(self) -> &'static str {
+        match self {
+            Encoding::Utf8 => "UTF-8",
+            Encoding::Utf8Bom => "UTF-8 with BOM",
+            Encoding::Utf16Le => "UTF-16LE",
+            Encoding::Utf16Be => "UTF-16BE",
+            Encoding::Windows1252 => "Windows-1252",
+        }
+    }
+}
+
+/// Decoded file contents
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Decoded {
+    pub text: String,
+    pub encoding: Encoding,
+    /// Some bytes could not be decoded and were replaced with U+FFFD
+    pub lossy: bool,
+}
+
+impl Decoded {
+    /// Note for the prompt about how the text was decoded, if it wasn't
+    /// clean UTF-8
+    pub fn note(&self) -> Option<String> {
+        match (self.encoding, self.lossy) {
+            (Encoding::Utf8, false) => None,
+            (Encoding::Utf8, true) => {
+                Some("(contains bytes that are not valid UTF-8; they were replaced)".to_string())
+            }
+            (encoding, false) => Some(format!("(decoded from {})", encoding.label())),
+            (encoding, true) => Some(format!(
+                "(decoded from {}; undecodable bytes were replaced)",
+                encoding.label()
+            )),
+        }
+    }
+}
+
+/// Read at most `max_bytes` of `path`. The flag is true when the whole
+/// file was read.
+pub fn read_prefix(path: &Path, max_bytes: u64) -> io::Result<(Vec<u8>, bool)> {
+    let mut bytes = Vec::new();
+    File::open(path)?
+        .take(max_bytes.saturating_add(1))
+        .read_to_end(&mut bytes)?;
+    let complete = bytes.len() as u64 <= max_bytes;
+    bytes.truncate(max_bytes.min(bytes.len() as u64) as usize);
+    Ok((bytes, complete))
+}
+
+/// True if `bytes` start with a UTF-16 byte order mark. Such text has
+/// NUL bytes but isn't binary.
+pub fn has_utf16_bom(bytes: &[u8]) -> bool {
+    bytes.starts_with(&[0xFF, 0xFE]) || bytes.starts_with(&[0xFE, 0xFF])
+}
+
+/// Decode `bytes`, detecting the encoding. `complete` is false when the
+/// bytes are a prefix of a longer file, so a character cut off at the end
+/// is dropped rather than counted as invalid.
+pub fn decode(bytes: &[u8], complete: bool) -> Decoded {
+    if let Some(rest) = bytes.strip_prefix(&[0xEF, 0xBB, 0xBF]) {
+        let mut decoded = decode_utf8(rest, complete);
+        decoded.encoding = Encoding::Utf8Bom;
+        return decoded;
+    }
+    if let Some(rest) = bytes.strip_prefix(&[0xFF, 0xFE]) {
+        return decode_utf16(rest, Encoding::Utf16Le, complete);
+    }
+    if let Some(rest) = bytes.strip_prefix(&[0xFE, 0xFF]) {
+        return decode_utf16(rest, Encoding::Utf16Be, complete);
+    }
+
+    // A few stray bytes in otherwise UTF-8 text are damage; mostly
+    // invalid sequences mean a legacy 8-bit encoding
+    let (valid, invalid) = utf8_sequences(bytes, complete);
+    if invalid == 0 || valid > invalid {
+        decode_utf8(bytes, complete)
+    } else {
+        decode_windows_1252(bytes)
+    }
+}
+
+fn decode_utf8(bytes: &[u8], complete: bool) -> Decoded {
+    let bytes = if complete { bytes } else { trim_partial_utf8(bytes) };
+    match std::str::from_utf8(bytes) {
+        Ok(text) => Decoded {
+            text: text.to_string(),
+            encoding: Encoding::Utf8,
+            lossy: false,
+        },
+        Err(_) => Decoded {
+            text: String::from_utf8_lossy(bytes).into_owned(),
+            encoding: Encoding::Utf8,
+            lossy: true,
+        },
+    }
+}
+
+/// `bytes` without an incomplete UTF-8 sequence at the very end
+fn trim_partial_utf8(bytes: &[u8]) -> &[u8] {
+    match std::str::from_utf8(bytes) {
+        Err(e) if e.error_len().is_none() => &bytes[..e.valid_up_to()],
+        _ => bytes,
+    }
+}
+
+/// Counts of valid multi-byte UTF-8 sequences and of invalid ones
+fn utf8_sequences(bytes: &[u8], complete: bool) -> (usize, usize) {
+    let mut valid = 0;
+    let mut invalid = 0;
+    let mut rest = bytes;
+    loop {
+        match std::str::from_utf8(rest) {
+            Ok(text) => {
+                valid += text.chars().filter(|c| !c.is_ascii()).count();
+                return (valid, invalid);
+            }
+            Err(e) => {
+                let (good, after) = rest.split_at(e.valid_up_to());
+                valid += std::str::from_utf8(good)
+                    .map_or(0, |text| text.chars().filter(|c| !c.is_ascii()).count());
+                match e.error_len() {
+                    Some(len) => {
+                        invalid += 1;
+                        rest = &after[len..];
+                    }
+                    None => {
+                        invalid += usize::from(complete);
+                        return (valid, invalid);
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn decode_utf16(bytes: &[u8], encoding: Encoding, complete: bool) -> Decoded {
+    let units = bytes.chunks_exact(2).map(|pair| match encoding {
+        Encoding::Utf16Be => u16::from_be_bytes([pair[0], pair[1]]),
+        _ => u16::from_le_bytes([pair[0], pair[1]]),
+    });
+    let mut lossy = complete && bytes.len() % 2 == 1;
+    let mut text = String::with_capacity(bytes.len() / 2);
+    let mut chars = char::decode_utf16(units).peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            Ok(c) => text.push(c),
+            // A surrogate cut off at the end of a prefix isn't damage
+            Err(_) if !complete && chars.peek().is_none() => {}
+            Err(_) => {
+                text.push(char::REPLACEMENT_CHARACTER);
+                lossy = true;
+            }
+        }
+    }
+    Decoded { text, encoding, lossy }
+}
+
+/// Windows-1252 code points for bytes 0x80-0x9F; `None` where undefined
+const WINDOWS_1252_HIGH: [Option<char>; 32] = [
+    Some('€'), None, Some('‚'), Some('ƒ'), Some('„'), Some('…'), Some('†'), Some('‡'),
+    Some('ˆ'), Some('‰'), Some('Š'), Some('‹'), Some('Œ'), None, Some('Ž'), None,
+    None, Some('‘'), Some('’'), Some('“'), Some('”'), Some('•'), Some('–'), Some('—'),
+    Some('˜'), Some('™'), Some('š'), Some('›'), Some('œ'), None, Some('ž'), Some('Ÿ'),
+];
+
+fn decode_windows_1252(bytes: &[u8]) -> Decoded {
+    let mut lossy = false;
+    let text = bytes
+        .iter()
+        .map(|&b| match b {
+            0x80..=0x9F => WINDOWS_1252_HIGH[usize::from(b - 0x80)].unwrap_or_else(|| {
+                lossy = true;
+                char::REPLACEMENT_CHARACTER
+            }),
+            // The rest of Windows-1252 coincides with Latin-1
+            _ => char::from(b),
+        })
+        .collect();
+    Decoded {
+        text,
+        encoding: Encoding::Windows1252,
+        lossy,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_decode_utf8_and_bom() {
+        let plain = decode("héllo".as_bytes(), true);
+        assert_eq!(plain.text, "héllo");
+        assert_eq!(plain.encoding, Encoding::Utf8);
+        assert!(plain.note().is_none());
+
+        let bom = decode(b"\xEF\xBB\xBFfn main() {}", true);
+        assert_eq!(bom.text, "fn main() {}");
+        assert_eq!(bom.note().unwrap(), "(decoded from UTF-8 with BOM)");
+    }
+
+    #[test]
+    fn test_decode_utf16() {
+        let mut le = vec![0xFF, 0xFE];
+        le.extend("hé".encode_utf16().flat_map(u16::to_le_bytes));
+        let decoded = decode(&le, true);
+        assert_eq!(decoded.text, "hé");
+        assert_eq!(decoded.encoding, Encoding::Utf16Le);
+
+        let mut be = vec![0xFE, 0xFF];
+        be.extend("x€".encode_utf16().flat_map(u16::to_be_bytes));
+        assert_eq!(decode(&be, true).text, "x€");
+        assert!(has_utf16_bom(&be) && !has_utf16_bom(b"plain"));
+    }
+
+    #[test]
+    fn test_decode_legacy_and_damaged_text() {
+        // "café – déjà" in Windows-1252
+        let legacy = decode(b"caf\xE9 \x96 d\xE9j\xE0", true);
+        assert_eq!(legacy.text, "café – déjà");
+        assert_eq!(legacy.encoding, Encoding::Windows1252);
+        assert!(!legacy.lossy);
+
+        // UTF-8 with one stray byte stays UTF-8
+        let mut damaged = "naïve ünïcode ".as_bytes().to_vec();
+        damaged.push(0xFF);
+        let decoded = decode(&damaged, true);
+        assert_eq!(decoded.encoding, Encoding::Utf8);
+        assert!(decoded.lossy);
+        assert!(decoded.text.starts_with("naïve ünïcode "));
+    }
+
+    #[test]
+    fn test_prefix_drops_cut_character() {
+        let bytes = "ab€".as_bytes();
+        let decoded = decode(&bytes[..3], false);
+        assert_eq!(decoded.text, "ab");
+        assert!(!decoded.lossy);
+        // At the real end of a file the byte is kept
+        assert_eq!(decode(&bytes[..3], true).text, "abâ");
+    }
+
+    #[test]
+    fn test_read_prefix() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("big.txt");
+        std::fs::write(&path, "0123456789").unwrap();
+
+        assert_eq!(read_prefix(&path, 4).unwrap(), (b"0123".to_vec(), false));
+        assert_eq!(read_prefix(&path, 10).unwrap(), (b"0123456789".to_vec(), true));
+        assert!(read_prefix(&temp_dir.path().join("missing"), 4).is_err());
+    }
+}
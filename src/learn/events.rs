@@ -0,0 +1,228 @@
+//! Machine-readable progress for `learn --events <path>`.
+//!
+//! Each pipeline stage writes one JSON object per line, so IDE extensions
+//! and CI dashboards can show progress without parsing spinner text:
+//!
+//! ```text
+//! {"ts":"2026-03-04T05:06:07Z","event":"scan_finished","total":120,"changed":4,...}
+//! {"ts":"2026-03-04T05:06:09Z","event":"provider_started","prompt_type":"files","provider":"claude"}
+//! ```
+//!
+//! The path may be a file, a named pipe, or `-` for stdout. Events are
+//! best effort: a reader that goes away never fails the run.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::fs::OpenOptions;
+use std::io::{self, Write};
+use std::path::Path;
+use std::sync::Mutex;
+use tracing::debug;
+
+/// One pipeline event
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event {
+    RunStarted {
+        mode: String,
+        dry_run: bool,
+    },
+    ScanFinished {
+        total: usize,
+        changed: usize,
+        deleted: usize,
+        unchanged: usize,
+        generated: usize,
+    },
+    HistoryWalked {
+        commits: usize,
+        significant: usize,
+    },
+    PromptBuilt {
+        prompt_type: String,
+        tokens: u64,
+        files: usize,
+        commits: usize,
+        patterns: usize,
+    },
+    /// A budget cap left the prompt for a later run
+    PromptDeferred {
+        prompt_type: String,
+        limit: String,
+    },
+    ProviderStarted {
+        prompt_type: String,
+        provider: String,
+    },
+    ProviderFinished {
+        prompt_type: String,
+        provider: String,
+        ok: bool,
+        /// Characters of response received
+        chars: usize,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        error: Option<String>,
+    },
+    SynthesisFinished {
+        input_entries: usize,
+        entries: usize,
+        conflicts_resolved: usize,
+    },
+    WriteFinished {
+        written: usize,
+        updated: usize,
+        skipped: usize,
+        pending: usize,
+    },
+    ManifestUpdated {
+        files: usize,
+        commits: usize,
+        patterns: usize,
+        saved: bool,
+    },
+    RunFinished {
+        up_to_date: bool,
+        entries_written: usize,
+        warnings: usize,
+    },
+    RunFailed {
+        error: String,
+    },
+}
+
+#[derive(Serialize)]
+struct Line<'a> {
+    ts: DateTime<Utc>,
+    #[serde(flatten)]
+    event: &'a Event,
+}
+
+/// Where events go; does nothing unless opened on a path
+#[derive(Default)]
+pub struct EventSink {
+    out: Option<Mutex<Box<dyn Write + Send>>>,
+}
+
+impl EventSink {
+    /// Write events to `path`, or stdout for `-`. The file is truncated;
+    /// a named pipe is opened for writing and blocks until read.
+    pub fn open(path: &Path) -> Result<Self> {
+        let out: Box<dyn Write + Send> = if path == Path::new("-") {
+            Box::new(io::stdout())
+        } else {
+            let file = OpenOptions::new()
+                .write(true)
+                .create(true)
+                .truncate(true)
+                .open(path)
+                .with_context(|| format!("Failed to open event stream {}", path.display()))?;
+            Box::new(file)
+        };
+        Ok(Self {
+            out: Some(Mutex::new(out)),
+        })
+    }
+
+    /// A sink that writes to `out`
+    pub fn from_writer(out: Box<dyn Write + Send>) -> Self {
+        Self {
+            out: Some(Mutex::new(out)),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.out.is_some()
+    }
+
+    /// Write `event` as a line and flush it
+    pub fn emit(&self, event: Event) {
+        let Some(out) = &self.out else {
+            return;
+        };
+        let line = Line {
+            ts: Utc::now(),
+            event: &event,
+        };
+        let Ok(json) = serde_json::to_string(&line) else {
+            return;
+        };
+        let mut out = out.lock().unwrap_or_else(|e| e.into_inner());
+        if let Err(e) = writeln!(out, "{}", json).and_then(|_| out.flush()) {
+            debug!("Failed to write learn event: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    /// Writer that keeps what was written for inspection
+    #[derive(Clone, Default)]
+    struct Shared(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Shared {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_events_are_json_lines() {
+        let buffer = Shared::default();
+        let sink = EventSink::from_writer(Box::new(buffer.clone()));
+        sink.emit(Event::PromptBuilt {
+            prompt_type: "files".to_string(),
+            tokens: 1200,
+            files: 3,
+            commits: 0,
+            patterns: 1,
+        });
+        sink.emit(Event::ProviderFinished {
+            prompt_type: "files".to_string(),
+            provider: "claude".to_string(),
+            ok: true,
+            chars: 480,
+            error: None,
+        });
+        EventSink::default().emit(Event::RunFailed {
+            error: "ignored".to_string(),
+        });
+
+        let written = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+        let lines: Vec<serde_json::Value> = written
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["event"], "prompt_built");
+        assert_eq!(lines[0]["tokens"], 1200);
+        assert!(lines[0]["ts"].is_string());
+        assert_eq!(lines[1]["event"], "provider_finished");
+        assert!(lines[1].get("error").is_none());
+    }
+
+    #[test]
+    fn test_open_writes_to_file() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("events.jsonl");
+        std::fs::write(&path, "stale\n").unwrap();
+
+        let sink = EventSink::open(&path).unwrap();
+        assert!(sink.is_enabled());
+        sink.emit(Event::RunStarted {
+            mode: "incremental".to_string(),
+            dry_run: false,
+        });
+        let written = std::fs::read_to_string(&path).unwrap();
+        assert!(written.starts_with("{\"ts\":"));
+        assert!(written.contains("\"event\":\"run_started\""));
+        assert!(!written.contains("stale"));
+    }
+}
@@ -0,0 +1,147 @@
+//! Generated-code detection for the scanner.
+//!
+//! A file counts as generated when its path matches `generated.paths`, when
+//! .gitattributes marks it as not the project's own source (see
+//! [`EXCLUDING_ATTRIBUTES`]), or when one of `generated.markers` (such as
+//! `@generated`) appears near its top. Generated files are hashed and
+//! tracked in the manifest so drift is still reported, but they never reach
+//! a prompt and never invalidate patterns. Commit scoring down-weights the
+//! same attributes (see [`crate::git::scoring`]).
+
+use crate::config::GeneratedConfig;
+use crate::policy::expand;
+use crate::rules::glob_to_regex;
+use anyhow::Result;
+use git2::{AttrCheckFlags, AttrValue, Repository};
+use regex::Regex;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+/// How much of a file is searched for markers
+const MARKER_WINDOW: u64 = 1024;
+
+/// .gitattributes attributes marking files that aren't the project's own
+/// source: generated code, vendored dependencies, and files left out of
+/// release archives
+pub const EXCLUDING_ATTRIBUTES: &[&str] =
+    &["linguist-generated", "linguist-vendored", "export-ignore"];
+
+/// Compiled `[generated]` settings. The default detects nothing.
+#[derive(Debug, Clone, Default)]
+pub struct GeneratedFiles {
+    globs: Vec<Regex>,
+    markers: Vec<String>,
+    linguist: bool,
+}
+
+impl GeneratedFiles {
+    pub fn from_config(config: &GeneratedConfig) -> Result<Self> {
+        let globs = config
+            .paths
+            .iter()
+            .map(|pattern| glob_to_regex(&expand(pattern.trim())))
+            .collect::<Result<_>>()?;
+        Ok(Self {
+            globs,
+            markers: config.markers.iter().filter(|m| !m.is_empty()).cloned().collect(),
+            linguist: config.linguist,
+        })
+    }
+
+    /// True if `rel_path` (relative to the root of `repo`) is generated
+    pub fn is_generated(&self, repo: &Repository, rel_path: &str) -> bool {
+        let rel_path = rel_path.trim_start_matches("./");
+        if self.globs.iter().any(|glob| glob.is_match(rel_path)) {
+            return true;
+        }
+        if self.linguist && has_excluding_attribute(repo, Path::new(rel_path)) {
+            return true;
+        }
+        if self.markers.is_empty() {
+            return false;
+        }
+        let Some(workdir) = repo.workdir() else {
+            return false;
+        };
+        let head = read_head(&workdir.join(rel_path));
+        self.markers.iter().any(|marker| head.contains(marker.as_str()))
+    }
+}
+
+/// True if .gitattributes sets one of [`EXCLUDING_ATTRIBUTES`] on
+/// `rel_path`
+pub fn has_excluding_attribute(repo: &Repository, rel_path: &Path) -> bool {
+    EXCLUDING_ATTRIBUTES.iter().any(|name| {
+        let value = repo
+            .get_attr_bytes(rel_path, name, AttrCheckFlags::default())
+            .ok()
+            .flatten();
+        match AttrValue::from_bytes(value) {
+            AttrValue::True => true,
+            AttrValue::String(value) => value.eq_ignore_ascii_case("true"),
+            _ => false,
+        }
+    })
+}
+
+/// The first [`MARKER_WINDOW`] bytes of `path`, lossily decoded
+fn read_head(path: &Path) -> String {
+    let mut head = Vec::new();
+    if let Ok(file) = File::open(path) {
+        let _ = file.take(MARKER_WINDOW).read_to_end(&mut head);
+    }
+    String::from_utf8_lossy(&head).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_detects_paths_attributes_and_markers() {
+        let temp_dir = TempDir::new().unwrap();
+        let repo = Repository::init(temp_dir.path()).unwrap();
+        let files = [
+            ("api/user.pb.go", "package api\n"),
+            ("Cargo.lock", "version = 3\n"),
+            ("gen/schema.rs", "pub struct Schema;\n"),
+            ("src/parser.rs", "// @generated by lalrpop\nfn parse() {}\n"),
+            ("src/lib.rs", "pub fn add() {}\n"),
+            ("third_party/zlib/inflate.c", "int inflate(void);\n"),
+            ("tests/fixtures/big.json", "{}\n"),
+        ];
+        for (path, contents) in files {
+            let path = temp_dir.path().join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, contents).unwrap();
+        }
+        fs::write(
+            temp_dir.path().join(".gitattributes"),
+            "gen/** linguist-generated\nthird_party/** linguist-vendored\ntests/fixtures/** export-ignore\n",
+        )
+        .unwrap();
+
+        let generated = GeneratedFiles::from_config(&GeneratedConfig::default()).unwrap();
+        assert!(generated.is_generated(&repo, "api/user.pb.go"));
+        assert!(generated.is_generated(&repo, "Cargo.lock"));
+        assert!(generated.is_generated(&repo, "gen/schema.rs"));
+        assert!(generated.is_generated(&repo, "src/parser.rs"));
+        assert!(generated.is_generated(&repo, "third_party/zlib/inflate.c"));
+        assert!(generated.is_generated(&repo, "tests/fixtures/big.json"));
+        assert!(!generated.is_generated(&repo, "src/lib.rs"));
+
+        let config = GeneratedConfig {
+            paths: Vec::new(),
+            markers: Vec::new(),
+            linguist: false,
+        };
+        let nothing = GeneratedFiles::from_config(&config).unwrap();
+        assert!(!nothing.is_generated(&repo, "gen/schema.rs"));
+        assert!(!nothing.is_generated(&repo, "third_party/zlib/inflate.c"));
+        assert!(!nothing.is_generated(&repo, "src/parser.rs"));
+        assert!(!GeneratedFiles::default().is_generated(&repo, "api/user.pb.go"));
+    }
+}
@@ -0,0 +1,591 @@
+//! Project glossary: domain terms, internal acronyms, and core type names.
+//!
+//! [`mine_terms`] finds candidates without a model: type declarations used
+//! across several files, defined by the doc comment above them, and
+//! acronyms that recur across files, defined where the source spells one
+//! out as `Augmented Reasoning Format (ARF)`. Lite runs turn defined
+//! candidates straight into entries with [`glossary_facts`]; other runs ask
+//! providers to define them (see
+//! [`crate::learn::prompts::build_glossary_prompt`]). Either way entries are
+//! tagged [`GLOSSARY_TAG`], filed under [`GLOSSARY_DIR`], and record their
+//! term as `context.term`, which `ask` ranks first for definitional
+//! questions such as "what is a manifest?". What each file contributes is
+//! cached by content hash ([`GLOSSARY_CACHE`]), so a run only re-reads the
+//! files that changed.
+
+use crate::arf::{ArfFile, ArfSource};
+use crate::config::GlossaryConfig;
+use crate::policy::NeverSend;
+use crate::storage::{in_dir, KnowledgeBackend};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::fs;
+use std::path::Path;
+use std::sync::OnceLock;
+
+/// Tag on every glossary entry
+pub const GLOSSARY_TAG: &str = "glossary";
+
+/// Where glossary entries are filed, relative to .noggin/
+pub const GLOSSARY_DIR: &str = "facts/glossary";
+
+/// Prompt type of the glossary prompt
+pub const GLOSSARY_PROMPT: &str = "glossary";
+
+/// Where mined terms are cached between runs, relative to .noggin/
+pub const GLOSSARY_CACHE: &str = "cache/glossary.json";
+
+/// `context` key holding the term an entry defines
+pub const TERM_KEY: &str = "term";
+
+/// Files larger than this are not mined
+const MAX_SCAN_BYTES: u64 = 1024 * 1024;
+
+/// Characters of a doc comment kept as a definition
+const MAX_DEFINITION_CHARS: usize = 300;
+
+/// Comment lines read above a declaration
+const MAX_DOC_LINES: usize = 8;
+
+/// Acronyms too common to be worth defining
+const COMMON_ACRONYMS: &[&str] = &[
+    "API", "ASCII", "AWS", "CI", "CLI", "CPU", "CSS", "CSV", "DB", "DNS", "EOF", "FIXME", "GET",
+    "GPU", "HACK", "HEAD", "HTML", "HTTP", "HTTPS", "ID", "IO", "IP", "JSON", "JWT", "MAX", "MIN",
+    "MIT", "NOTE", "OK", "OS", "POST", "PR", "PUT", "README", "SAFETY", "SHA", "SQL", "SSH", "SSL",
+    "TCP", "TLS", "TODO", "TOML", "UDP", "UI", "URI", "URL", "UTC", "UTF", "UUID", "XML", "XXX",
+    "YAML",
+];
+
+/// A type declaration, capturing the (CamelCase) name
+fn type_declaration() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(
+            r"^\s*(?:(?:pub(?:\([^)]*\))?|export|public|abstract|sealed|final|data|default)\s+)*(?:struct|enum|trait|class|interface|type|union)\s+([A-Z][A-Za-z0-9]*[a-z][A-Za-z0-9]*)\b",
+        )
+        .unwrap()
+    })
+}
+
+fn identifier() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"[A-Za-z_][A-Za-z0-9_]*").unwrap())
+}
+
+/// Capitalized words followed by an acronym in parentheses
+fn expansion() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(r"((?:[A-Z][A-Za-z]*[\s-]+){1,5}[A-Z][A-Za-z]*)\s*\(([A-Z][A-Z0-9]{1,5})\)").unwrap()
+    })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum TermKind {
+    /// A type declared in the repository
+    Type,
+    /// An all-caps abbreviation
+    Acronym,
+}
+
+impl TermKind {
+    pub fn label(self) -> &'static str {
+        match self {
+            TermKind::Type => "type",
+            TermKind::Acronym => "acronym",
+        }
+    }
+}
+
+/// A glossary candidate
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Term {
+    pub name: String,
+    pub kind: TermKind,
+    /// Doc comment of a type, or the spelled-out form of an acronym
+    pub definition: Option<String>,
+    /// Where the type is declared or the acronym spelled out, as path
+    /// and 1-based line
+    pub source: Option<(String, usize)>,
+    /// Files the term appears in, sorted
+    pub files: Vec<String>,
+}
+
+/// What one file contributes to the glossary, as cached between runs
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+struct FileTerms {
+    /// Content hash the file was mined at
+    hash: String,
+    /// Capitalized identifiers the file uses
+    words: BTreeSet<String>,
+    /// Types declared in the file: name, doc comment, 1-based line
+    declared: Vec<(String, Option<String>, usize)>,
+    /// Acronyms spelled out in the file: acronym, expansion, 1-based line
+    expanded: Vec<(String, String, usize)>,
+}
+
+/// Mined terms by file, kept under .noggin/ so later runs only re-read
+/// the files that changed
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct GlossaryCache {
+    files: BTreeMap<String, FileTerms>,
+}
+
+/// Mine glossary candidates from `files` (repository-relative paths).
+/// Contents of `never_send` files are never read. Terms must appear in at
+/// least `config.min_files` files; the most widely used come first.
+pub fn mine_terms(
+    repo_path: &Path,
+    files: &[String],
+    never_send: &NeverSend,
+    config: &GlossaryConfig,
+) -> Vec<Term> {
+    let mined: BTreeMap<String, FileTerms> = files
+        .iter()
+        .filter_map(|path| Some((path.clone(), mine_file(repo_path, path, never_send)?)))
+        .collect();
+    collect_terms(&mined, config)
+}
+
+/// [`mine_terms`] over `files` (path to content hash), reading only the
+/// files whose hash differs from the one cached in [`GLOSSARY_CACHE`] by
+/// the last run. The cache is rewritten to hold exactly `files`.
+pub fn mine_terms_cached(
+    repo_path: &Path,
+    noggin_path: &Path,
+    files: &BTreeMap<String, String>,
+    never_send: &NeverSend,
+    config: &GlossaryConfig,
+) -> Vec<Term> {
+    let cache_path = noggin_path.join(GLOSSARY_CACHE);
+    let mut cache: GlossaryCache = fs::read_to_string(&cache_path)
+        .ok()
+        .and_then(|contents| serde_json::from_str(&contents).ok())
+        .unwrap_or_default();
+
+    let mut mined = BTreeMap::new();
+    for (path, hash) in files {
+        if never_send.matches(path) {
+            continue;
+        }
+        let cached = cache.files.remove(path).filter(|terms| terms.hash == *hash);
+        let Some(mut terms) = cached.or_else(|| mine_file(repo_path, path, never_send)) else {
+            continue;
+        };
+        terms.hash = hash.clone();
+        mined.insert(path.clone(), terms);
+    }
+
+    let terms = collect_terms(&mined, config);
+    // Only a cache: a failed write costs the next run a full re-read
+    if let Some(parent) = cache_path.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    if let Ok(contents) = serde_json::to_string(&GlossaryCache { files: mined }) {
+        let _ = fs::write(&cache_path, contents);
+    }
+    terms
+}
+
+/// The words, declarations, and expansions in one file, or None if it
+/// can't be read or mustn't be
+fn mine_file(repo_path: &Path, path: &str, never_send: &NeverSend) -> Option<FileTerms> {
+    if never_send.matches(path) {
+        return None;
+    }
+    let full_path = repo_path.join(path);
+    if fs::metadata(&full_path).map_or(true, |m| m.len() > MAX_SCAN_BYTES) {
+        return None;
+    }
+    let contents = fs::read_to_string(&full_path).ok()?;
+
+    let mut terms = FileTerms {
+        words: identifier()
+            .find_iter(&contents)
+            .map(|m| m.as_str())
+            .filter(|word| word.starts_with(|c: char| c.is_ascii_uppercase()))
+            .map(str::to_string)
+            .collect(),
+        ..Default::default()
+    };
+
+    let lines: Vec<&str> = contents.lines().collect();
+    for (idx, line) in lines.iter().enumerate() {
+        if let Some(name) = type_declaration().captures(line).map(|c| c[1].to_string()) {
+            terms.declared.push((name, doc_comment(&lines, idx), idx + 1));
+        }
+        for captures in expansion().captures_iter(line) {
+            let (words, acronym) = (&captures[1], &captures[2]);
+            if let Some(spelled) = spelled_out(words, acronym) {
+                terms.expanded.push((acronym.to_string(), spelled, idx + 1));
+            }
+        }
+    }
+    Some(terms)
+}
+
+/// Candidates from the files' mined terms. Where several files declare or
+/// spell out the same term, the first path in order wins.
+fn collect_terms(mined: &BTreeMap<String, FileTerms>, config: &GlossaryConfig) -> Vec<Term> {
+    let mut usage: BTreeMap<&str, BTreeSet<&str>> = BTreeMap::new();
+    let mut declared: BTreeMap<&str, (&Option<String>, &str, usize)> = BTreeMap::new();
+    let mut expanded: BTreeMap<&str, (&str, &str, usize)> = BTreeMap::new();
+
+    for (path, terms) in mined {
+        for word in &terms.words {
+            usage.entry(word).or_default().insert(path);
+        }
+        for (name, doc, line) in &terms.declared {
+            declared.entry(name).or_insert((doc, path, *line));
+        }
+        for (acronym, spelled, line) in &terms.expanded {
+            expanded.entry(acronym).or_insert((spelled, path, *line));
+        }
+    }
+
+    let mut terms = Vec::new();
+    for (name, (definition, path, line)) in &declared {
+        let Some(used_in) = usage.get(name) else {
+            continue;
+        };
+        if used_in.len() < config.min_files {
+            continue;
+        }
+        terms.push(Term {
+            name: name.to_string(),
+            kind: TermKind::Type,
+            definition: (*definition).clone(),
+            source: Some((path.to_string(), *line)),
+            files: used_in.iter().map(|f| f.to_string()).collect(),
+        });
+    }
+    for (name, used_in) in &usage {
+        if !is_acronym(name) || declared.contains_key(name) || used_in.len() < config.min_files {
+            continue;
+        }
+        let spelled = expanded.get(name);
+        terms.push(Term {
+            name: name.to_string(),
+            kind: TermKind::Acronym,
+            definition: spelled.map(|(definition, _, _)| definition.to_string()),
+            source: spelled.map(|(_, path, line)| (path.to_string(), *line)),
+            files: used_in.iter().map(|f| f.to_string()).collect(),
+        });
+    }
+
+    terms.sort_by(|a, b| b.files.len().cmp(&a.files.len()).then(a.name.cmp(&b.name)));
+    terms
+}
+
+/// An all-caps word of 2 to 6 characters that isn't common vocabulary
+fn is_acronym(word: &str) -> bool {
+    (2..=6).contains(&word.len())
+        && word.starts_with(|c: char| c.is_ascii_uppercase())
+        && word.chars().all(|c| c.is_ascii_uppercase() || c.is_ascii_digit())
+        && !COMMON_ACRONYMS.contains(&word)
+}
+
+/// `words` when their initials spell `acronym`, e.g. "Augmented Reasoning
+/// Format" for ARF
+fn spelled_out(words: &str, acronym: &str) -> Option<String> {
+    let words: Vec<&str> = words.split([' ', '\t', '-']).filter(|w| !w.is_empty()).collect();
+    // The expansion is the last N capitalized words before the parenthesis
+    let letters = acronym.chars().filter(|c| c.is_ascii_alphabetic()).count();
+    let start = words.len().checked_sub(letters)?;
+    let words = &words[start..];
+    let initials: String = words.iter().filter_map(|w| w.chars().next()).collect();
+    (initials == acronym).then(|| words.join(" "))
+}
+
+/// The comment directly above line `idx`, skipping attributes and
+/// decorators, or a Python docstring directly below it
+fn doc_comment(lines: &[&str], idx: usize) -> Option<String> {
+    let mut comment: Vec<&str> = Vec::new();
+    for line in lines[..idx].iter().rev().take(MAX_DOC_LINES) {
+        let line = line.trim();
+        if line.starts_with("#[") || line.starts_with('@') {
+            continue;
+        }
+        let text = ["///", "//!", "//", "/**", "*/", "*", "#"]
+            .iter()
+            .find_map(|marker| line.strip_prefix(marker));
+        match text {
+            Some(text) => comment.push(text.trim_end_matches("*/").trim()),
+            None => break,
+        }
+    }
+    comment.reverse();
+    let mut text = comment.join(" ");
+
+    if text.trim().is_empty() && lines[idx].trim_end().ends_with(':') {
+        if let Some(next) = lines.get(idx + 1).map(|l| l.trim()) {
+            for quote in ["\"\"\"", "'''"] {
+                if let Some(rest) = next.strip_prefix(quote) {
+                    text = rest.split(quote).next().unwrap_or(rest).to_string();
+                }
+            }
+        }
+    }
+
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if text.is_empty() {
+        return None;
+    }
+    if text.chars().count() <= MAX_DEFINITION_CHARS {
+        return Some(text);
+    }
+    let cut: String = text.chars().take(MAX_DEFINITION_CHARS).collect();
+    Some(format!("{}...", cut.trim_end()))
+}
+
+/// The first sentence of `text`
+fn first_sentence(text: &str) -> &str {
+    match text.find(". ") {
+        Some(end) => &text[..end],
+        None => text.trim_end_matches('.'),
+    }
+}
+
+/// Glossary entries for the candidates that carry a definition
+pub fn glossary_facts(terms: &[Term]) -> Vec<ArfFile> {
+    terms
+        .iter()
+        .filter_map(|term| {
+            let definition = term.definition.as_deref()?;
+            let why = match term.kind {
+                TermKind::Type => format!("Core type used in {} files", term.files.len()),
+                TermKind::Acronym => format!("Acronym used in {} files", term.files.len()),
+            };
+            let how = match (&term.source, term.kind) {
+                (Some((path, line)), TermKind::Type) => {
+                    format!("{}\nDeclared in {}:{}", definition, path, line)
+                }
+                (Some((path, line)), TermKind::Acronym) => {
+                    format!("{} stands for {}.\nSpelled out in {}:{}", term.name, definition, path, line)
+                }
+                (None, _) => definition.to_string(),
+            };
+
+            let mut arf = ArfFile::new(
+                format!("{}: {}", term.name, first_sentence(definition)),
+                why,
+                how,
+            );
+            arf.category = Some("facts".to_string());
+            arf.source = Some(ArfSource::FileAnalysis);
+            arf.tags = vec![GLOSSARY_TAG.to_string(), term.kind.label().to_string()];
+            if let Some((path, _)) = &term.source {
+                arf.add_file(path.clone());
+            }
+            arf.context
+                .extra
+                .insert(TERM_KEY.to_string(), toml::Value::String(term.name.clone()));
+            Some(arf)
+        })
+        .collect()
+}
+
+/// True if `arf` is a glossary entry
+pub fn is_glossary(arf: &ArfFile) -> bool {
+    arf.tags.iter().any(|tag| tag == GLOSSARY_TAG)
+}
+
+/// The term a glossary entry defines: `context.term`, else the text of
+/// `what` before its first colon
+pub fn term_of(arf: &ArfFile) -> Option<&str> {
+    if let Some(term) = arf.context.extra.get(TERM_KEY).and_then(|v| v.as_str()) {
+        return Some(term.trim());
+    }
+    arf.what.split_once(':').map(|(term, _)| term.trim()).filter(|t| !t.is_empty())
+}
+
+/// Tag the entries synthesized from the glossary prompt so they are filed
+/// as glossary facts, recording the term they define
+pub fn mark_entries(arfs: &mut [ArfFile]) {
+    for arf in arfs {
+        if !arf.context.prompt_types.iter().any(|t| t == GLOSSARY_PROMPT) {
+            continue;
+        }
+        if !is_glossary(arf) {
+            arf.tags.push(GLOSSARY_TAG.to_string());
+        }
+        arf.category = Some("facts".to_string());
+        if !arf.context.extra.contains_key(TERM_KEY) {
+            if let Some(term) = term_of(arf).map(str::to_string) {
+                arf.context.extra.insert(TERM_KEY.to_string(), toml::Value::String(term));
+            }
+        }
+    }
+}
+
+/// Terms already in the glossary in `backend`, lowercased
+pub fn existing_terms(backend: &dyn KnowledgeBackend) -> HashSet<String> {
+    backend
+        .list_arfs()
+        .unwrap_or_default()
+        .iter()
+        .filter(|path| in_dir(path, GLOSSARY_DIR))
+        .filter_map(|path| backend.read_arf(path).ok().flatten())
+        .filter_map(|arf| term_of(&arf).map(str::to_lowercase))
+        .collect()
+}
+
+/// Question forms that ask for a definition, capturing the subject
+fn definitional_patterns() -> &'static [Regex] {
+    static RE: OnceLock<Vec<Regex>> = OnceLock::new();
+    RE.get_or_init(|| {
+        [
+            r"^(?:what\s+(?:is|are|was|were)|what's|whats|define|definition\s+of|meaning\s+of|explain)\s+(?:an?\s+|the\s+)?(.+)$",
+            r"^what\s+(?:does|do)\s+(?:an?\s+|the\s+)?(.+?)\s+(?:mean|stand\s+for)$",
+            r"^(.+?)\s+(?:meaning|definition)$",
+        ]
+        .iter()
+        .map(|pattern| Regex::new(pattern).unwrap())
+        .collect()
+    })
+}
+
+/// The term a question asks to have defined, such as `manifest` for
+/// "What is a manifest?". Only short subjects count, so "what is the
+/// reason we retry on 503s" isn't treated as a definition request.
+pub fn definitional_term(query: &str) -> Option<String> {
+    let query = query.trim().trim_end_matches(['?', '.', '!']).trim().to_lowercase();
+    let subject = definitional_patterns()
+        .iter()
+        .find_map(|pattern| pattern.captures(&query).map(|c| c[1].to_string()))?;
+    let subject = subject.trim_matches(|c: char| c == '"' || c == '\'' || c == '`' || c.is_whitespace());
+    let words = subject.split_whitespace().count();
+    ((1..=3).contains(&words)).then(|| subject.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn config(min_files: usize) -> GlossaryConfig {
+        GlossaryConfig {
+            min_files,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_mine_terms() {
+        let dir = TempDir::new().unwrap();
+        let files = [
+            (
+                "src/manifest.rs",
+                "/// Tracks scanned files. Saved as TOML.\n#[derive(Debug)]\npub struct Manifest {}\n\n// Augmented Reasoning Format (ARF) files\n",
+            ),
+            ("src/learn.rs", "use Manifest;\n// write an ARF\nlet x = HTTP;\n"),
+            ("src/status.rs", "fn f(m: &Manifest) {} // ARF and HTTP\n"),
+            ("tool.py", "class Helper:\n    \"\"\"Runs one-off jobs.\"\"\"\n"),
+        ];
+        let mut paths = Vec::new();
+        for (path, contents) in files {
+            let full = dir.path().join(path);
+            fs::create_dir_all(full.parent().unwrap()).unwrap();
+            fs::write(full, contents).unwrap();
+            paths.push(path.to_string());
+        }
+
+        let terms = mine_terms(dir.path(), &paths, &NeverSend::default(), &config(3));
+        let names: Vec<&str> = terms.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(names, vec!["ARF", "Manifest"]);
+
+        let arf = &terms[0];
+        assert_eq!(arf.kind, TermKind::Acronym);
+        assert_eq!(arf.definition.as_deref(), Some("Augmented Reasoning Format"));
+        assert_eq!(arf.source, Some(("src/manifest.rs".to_string(), 5)));
+
+        let manifest = &terms[1];
+        assert_eq!(manifest.kind, TermKind::Type);
+        assert_eq!(manifest.definition.as_deref(), Some("Tracks scanned files. Saved as TOML."));
+        assert_eq!(manifest.files.len(), 3);
+
+        // Python docstrings define classes too
+        let terms = mine_terms(dir.path(), &paths, &NeverSend::default(), &config(1));
+        let helper = terms.iter().find(|t| t.name == "Helper").unwrap();
+        assert_eq!(helper.definition.as_deref(), Some("Runs one-off jobs."));
+    }
+
+    #[test]
+    fn test_mine_terms_cached_rereads_only_changed_files() {
+        let dir = TempDir::new().unwrap();
+        let noggin = dir.path().join(".noggin");
+        fs::write(dir.path().join("a.rs"), "/// A pool.\npub struct Pool {}\n").unwrap();
+        fs::write(dir.path().join("b.rs"), "fn f(p: Pool) {}\n").unwrap();
+        let mut files = BTreeMap::from([
+            ("a.rs".to_string(), "h1".to_string()),
+            ("b.rs".to_string(), "h1".to_string()),
+        ]);
+        let mine = |files: &BTreeMap<String, String>| {
+            mine_terms_cached(dir.path(), &noggin, files, &NeverSend::default(), &config(2))
+        };
+        assert_eq!(mine(&files)[0].definition.as_deref(), Some("A pool."));
+        assert!(noggin.join(GLOSSARY_CACHE).exists());
+
+        // Same hash: served from the cache without reading the file
+        fs::write(dir.path().join("a.rs"), "/// A queue.\npub struct Pool {}\n").unwrap();
+        assert_eq!(mine(&files)[0].definition.as_deref(), Some("A pool."));
+
+        files.insert("a.rs".to_string(), "h2".to_string());
+        assert_eq!(mine(&files)[0].definition.as_deref(), Some("A queue."));
+    }
+
+    #[test]
+    fn test_glossary_facts() {
+        let terms = vec![
+            Term {
+                name: "Manifest".to_string(),
+                kind: TermKind::Type,
+                definition: Some("Tracks scanned files. Saved as TOML.".to_string()),
+                source: Some(("src/manifest.rs".to_string(), 3)),
+                files: vec!["a".to_string(), "b".to_string()],
+            },
+            Term {
+                name: "QPS".to_string(),
+                kind: TermKind::Acronym,
+                definition: None,
+                source: None,
+                files: vec!["a".to_string()],
+            },
+        ];
+
+        let arfs = glossary_facts(&terms);
+        assert_eq!(arfs.len(), 1);
+        assert_eq!(arfs[0].what, "Manifest: Tracks scanned files");
+        assert_eq!(arfs[0].why, "Core type used in 2 files");
+        assert!(arfs[0].how.ends_with("Declared in src/manifest.rs:3"));
+        assert!(is_glossary(&arfs[0]));
+        assert_eq!(term_of(&arfs[0]), Some("Manifest"));
+    }
+
+    #[test]
+    fn test_mark_entries() {
+        let mut defined = ArfFile::new("Shard: one partition of the index", "Used widely", "...");
+        defined.context.prompt_types = vec![GLOSSARY_PROMPT.to_string()];
+        let mut other = ArfFile::new("Use sqlx", "Compile-time checks", "...");
+        other.context.prompt_types = vec!["files".to_string()];
+        let mut arfs = vec![defined, other];
+
+        mark_entries(&mut arfs);
+
+        assert!(is_glossary(&arfs[0]));
+        assert_eq!(arfs[0].category.as_deref(), Some("facts"));
+        assert_eq!(arfs[0].context.extra[TERM_KEY].as_str(), Some("Shard"));
+        assert!(!is_glossary(&arfs[1]));
+    }
+
+    #[test]
+    fn test_definitional_term() {
+        assert_eq!(definitional_term("What is a manifest?").as_deref(), Some("manifest"));
+        assert_eq!(definitional_term("what does ARF stand for").as_deref(), Some("arf"));
+        assert_eq!(definitional_term("define `PromptBudget`").as_deref(), Some("promptbudget"));
+        assert_eq!(definitional_term("shard meaning").as_deref(), Some("shard"));
+        assert!(definitional_term("what is the reason we retry on 503 responses").is_none());
+        assert!(definitional_term("why do we use sqlx?").is_none());
+    }
+}
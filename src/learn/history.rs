@@ -0,0 +1,149 @@
+//! History of learn runs in `.noggin/reports/<timestamp>.toml`.
+//!
+//! Every learn run that writes to the knowledge base saves a [`RunRecord`]:
+//! how much it analyzed, what synthesis made of the model outputs (model
+//! agreement, conflicts found and settled), what was written, and the
+//! warnings it printed. `noggin log` lists the records newest first. Cost
+//! reports share the directory as `cost-<timestamp>.json`.
+
+use crate::learn::budget::REPORTS_DIR;
+use crate::synthesis::SynthesisReport;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Outcome of one learn run, as saved under `.noggin/reports/`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunRecord {
+    pub finished_at: DateTime<Utc>,
+    /// Learn mode: "full", "incremental", or "lite"
+    pub mode: String,
+    pub files_analyzed: usize,
+    pub commits_processed: usize,
+    /// Entries synthesized, before writing
+    pub arf_entries: usize,
+    /// ARF files written or updated
+    pub arfs_written: usize,
+    /// Conflict records opened, for approved ARFs or unsettled votes
+    pub conflicts: usize,
+    /// New entries held in `pending/` by the per-run cap
+    #[serde(default)]
+    pub pending: usize,
+    pub requests: u32,
+    pub tokens: u64,
+    pub estimated_cost_usd: f64,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+    /// Absent for lite runs, which don't synthesize
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub synthesis: Option<SynthesisReport>,
+}
+
+impl RunRecord {
+    /// Write the record to `.noggin/reports/<timestamp>.toml` and return
+    /// its path
+    pub fn save(&self, noggin_path: &Path) -> Result<PathBuf> {
+        let dir = noggin_path.join(REPORTS_DIR);
+        fs::create_dir_all(&dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+        let path = dir.join(format!(
+            "{}.toml",
+            self.finished_at.format("%Y%m%dT%H%M%S%3fZ")
+        ));
+        let contents = toml::to_string_pretty(self).context("Failed to serialize run record")?;
+        fs::write(&path, contents).with_context(|| format!("Failed to write {}", path.display()))?;
+        Ok(path)
+    }
+}
+
+/// Saved run records, newest first. Unreadable records are skipped.
+pub fn list_runs(noggin_path: &Path) -> Result<Vec<RunRecord>> {
+    let dir = noggin_path.join(REPORTS_DIR);
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut runs = Vec::new();
+    for entry in fs::read_dir(&dir).with_context(|| format!("Failed to read {}", dir.display()))? {
+        let path = entry?.path();
+        if path.extension().is_none_or(|ext| ext != "toml") {
+            continue;
+        }
+        let Ok(contents) = fs::read_to_string(&path) else {
+            continue;
+        };
+        if let Ok(run) = toml::from_str::<RunRecord>(&contents) {
+            runs.push(run);
+        }
+    }
+
+    runs.sort_by_key(|run| std::cmp::Reverse(run.finished_at));
+    Ok(runs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+    use tempfile::TempDir;
+
+    fn record(finished_at: &str, synthesis: Option<SynthesisReport>) -> RunRecord {
+        RunRecord {
+            finished_at: finished_at.parse().unwrap(),
+            mode: "incremental".to_string(),
+            files_analyzed: 3,
+            commits_processed: 2,
+            arf_entries: 4,
+            arfs_written: 4,
+            conflicts: 1,
+            pending: 0,
+            requests: 2,
+            tokens: 900,
+            estimated_cost_usd: 0.01,
+            warnings: vec!["Synthesis dropped 1 entry".to_string()],
+            synthesis,
+        }
+    }
+
+    #[test]
+    fn test_save_and_list_newest_first() {
+        let temp_dir = TempDir::new().unwrap();
+        let synthesis = SynthesisReport {
+            total_input_arfs: 6,
+            total_output_arfs: 4,
+            conflicts_detected: 2,
+            conflicts_resolved: 1,
+            conflicts_manual: 1,
+            model_agreement_pct: 66.7,
+            models_used: vec!["claude".to_string(), "gemini".to_string()],
+            by_prompt_type: BTreeMap::from([("files".to_string(), 4)]),
+            cross_prompt_duplicates: 0,
+            below_min_models: 0,
+            sampled_out: 0,
+            partitioned: false,
+        };
+
+        let path = record("2026-03-04T05:06:07Z", Some(synthesis))
+            .save(temp_dir.path())
+            .unwrap();
+        assert!(path.ends_with("reports/20260304T050607000Z.toml"));
+        record("2026-03-05T05:06:07Z", None).save(temp_dir.path()).unwrap();
+        fs::write(temp_dir.path().join("reports/cost-x.json"), "{}").unwrap();
+        fs::write(temp_dir.path().join("reports/broken.toml"), "mode = 1").unwrap();
+
+        let runs = list_runs(temp_dir.path()).unwrap();
+        assert_eq!(runs.len(), 2);
+        assert!(runs[0].synthesis.is_none());
+        let synthesis = runs[1].synthesis.as_ref().unwrap();
+        assert_eq!(synthesis.models_used, vec!["claude", "gemini"]);
+        assert_eq!(synthesis.conflicts_manual, 1);
+        assert_eq!(runs[1].warnings.len(), 1);
+    }
+
+    #[test]
+    fn test_list_without_reports() {
+        let temp_dir = TempDir::new().unwrap();
+        assert!(list_runs(temp_dir.path()).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,270 @@
+//! Keeping provider output in the configured language.
+//!
+//! Prompts end with [`language_instruction`], but a provider can still
+//! answer in the user's locale (Gemini does this), which leaves merged
+//! ARFs bilingual. [`matches_language`] is a cheap heuristic check on the
+//! parsed entries: it looks at the share of non-Latin script and of each
+//! language's common function words in [`STOPWORDS`], and takes the
+//! language with the most. A configured language without a stopword set
+//! (Japanese, say) matches prose that is none of those. Off-language
+//! entries can be rewritten with [`build_translation_prompt`] and
+//! [`apply_translation`].
+//!
+//! Entries written before the setting changed, or merged from a response
+//! that slipped through, are found by [`off_language_arfs`]; `noggin check`
+//! reports them and `noggin translate` rewrites them.
+
+use crate::arf::ArfFile;
+use crate::storage::KnowledgeBackend;
+use crate::synthesis;
+use anyhow::Result;
+
+/// Responses with fewer words than this are too short to judge
+const MIN_WORDS: usize = 12;
+
+/// Share of a language's function words above which prose is in it
+const MIN_STOPWORD_SHARE: f64 = 0.08;
+
+/// Common function words of the Latin-script languages prose is
+/// recognized in, keyed by English name and ISO 639-1 code
+pub const STOPWORDS: &[(&str, &str, &[&str])] = &[
+    ("english", "en", &[
+        "a", "all", "an", "and", "are", "as", "at", "be", "because", "by", "can", "each", "for",
+        "from", "has", "have", "if", "in", "instead", "into", "is", "it", "its", "not", "of", "on",
+        "only", "or", "so", "than", "that", "the", "their", "them", "this", "to", "uses", "via",
+        "was", "when", "which", "with", "without",
+    ]),
+    ("german", "de", &[
+        "als", "auch", "auf", "bei", "das", "dem", "den", "der", "des", "die", "durch", "ein",
+        "eine", "einen", "es", "für", "im", "in", "ist", "jede", "jedem", "jeder", "kann", "mit",
+        "nicht", "nur", "ohne", "oder", "sich", "sind", "und", "von", "wenn", "werden", "wird",
+        "zu", "zum",
+    ]),
+    ("french", "fr", &[
+        "au", "aux", "avec", "ce", "ces", "cette", "chaque", "dans", "de", "des", "du", "elle",
+        "en", "est", "et", "il", "ils", "la", "le", "les", "leur", "mais", "ne", "ou", "par",
+        "pas", "plus", "pour", "que", "qui", "sa", "sans", "ses", "son", "sont", "sur", "un",
+        "une",
+    ]),
+    ("spanish", "es", &[
+        "al", "cada", "como", "con", "cuando", "de", "del", "el", "en", "es", "esta", "este",
+        "la", "las", "lo", "los", "no", "o", "para", "pero", "por", "que", "se", "sin", "son",
+        "su", "sus", "un", "una", "unos", "y",
+    ]),
+    ("portuguese", "pt", &[
+        "a", "ao", "as", "cada", "com", "como", "da", "das", "de", "do", "dos", "e", "em", "mas",
+        "na", "no", "nos", "não", "o", "os", "para", "por", "quando", "que", "se", "sem", "seu",
+        "sua", "são", "um", "uma", "é",
+    ]),
+    ("italian", "it", &[
+        "al", "che", "come", "con", "da", "del", "della", "di", "e", "gli", "il", "in", "la",
+        "le", "lo", "ma", "nel", "nella", "non", "ogni", "per", "quando", "questo", "si",
+        "senza", "sono", "un", "una", "è",
+    ]),
+    ("dutch", "nl", &[
+        "aan", "als", "bij", "de", "die", "dat", "door", "een", "elke", "en", "het", "in", "is",
+        "maar", "met", "naar", "niet", "of", "om", "ook", "op", "te", "van", "voor", "wordt",
+        "worden", "zijn", "zonder",
+    ]),
+];
+
+/// Sentence appended to every analysis prompt
+pub fn language_instruction(language: &str) -> String {
+    format!(
+        "\nWrite every what, why, and how value in {}, regardless of the language \
+         of the code, comments, or commit messages.\n",
+        language
+    )
+}
+
+/// True when the entries' prose appears to be written in `language`
+pub fn matches_language(arfs: &[ArfFile], language: &str) -> bool {
+    let text: String = arfs
+        .iter()
+        .map(|arf| format!("{} {} {} ", arf.what, arf.why, arf.how))
+        .collect();
+
+    let words: Vec<String> = text
+        .split(|c: char| !c.is_alphabetic())
+        .filter(|w| !w.is_empty())
+        .map(str::to_lowercase)
+        .collect();
+    if words.len() < MIN_WORDS {
+        return true;
+    }
+
+    let letters: Vec<char> = text.chars().filter(|c| c.is_alphabetic()).collect();
+    let latin = letters.iter().filter(|c| c.is_ascii()).count() as f64 / letters.len() as f64;
+    let share = |stopwords: &[&str]| {
+        words.iter().filter(|w| stopwords.contains(&w.as_str())).count() as f64 / words.len() as f64
+    };
+    let detected = STOPWORDS
+        .iter()
+        .map(|(name, _, stopwords)| (*name, share(stopwords)))
+        .filter(|(_, share)| latin >= 0.9 && *share >= MIN_STOPWORD_SHARE)
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(name, _)| name);
+
+    let configured = STOPWORDS
+        .iter()
+        .find(|(name, code, _)| {
+            language.eq_ignore_ascii_case(name) || language.eq_ignore_ascii_case(code)
+        })
+        .map(|(name, _, _)| *name);
+    detected == configured
+}
+
+/// ARFs in `backend` whose prose doesn't appear to be in `language`.
+/// Unparseable ARFs are skipped; schema checks report those.
+pub fn off_language_arfs(backend: &dyn KnowledgeBackend, language: &str) -> Result<Vec<String>> {
+    let mut off = Vec::new();
+    for path in backend.list_arfs()? {
+        let Ok(Some(arf)) = backend.read_arf(&path) else {
+            continue;
+        };
+        if !matches_language(std::slice::from_ref(&arf), language) {
+            off.push(path);
+        }
+    }
+    Ok(off)
+}
+
+/// Prompt asking a provider to translate the entries' prose into
+/// `language`, one `[[entry]]` per input entry in the same order
+pub fn build_translation_prompt(arfs: &[ArfFile], language: &str) -> String {
+    let mut prompt = format!(
+        "Translate the what, why, and how values of the following entries into {}. \
+         Keep code identifiers, file paths, and crate names unchanged. Output exactly \
+         one TOML [[entry]] block per input entry, in the same order, with only the \
+         what, why, and how keys.\n\n",
+        language
+    );
+
+    #[derive(serde::Serialize)]
+    struct Prose<'a> {
+        what: &'a str,
+        why: &'a str,
+        how: &'a str,
+    }
+    #[derive(serde::Serialize)]
+    struct Entries<'a> {
+        entry: Vec<Prose<'a>>,
+    }
+
+    let entries = Entries {
+        entry: arfs
+            .iter()
+            .map(|arf| Prose {
+                what: &arf.what,
+                why: &arf.why,
+                how: &arf.how,
+            })
+            .collect(),
+    };
+    prompt.push_str(&toml::to_string_pretty(&entries).unwrap_or_default());
+    prompt
+}
+
+/// Replace the prose of `arfs` with a translation response, keeping tags,
+/// context, and everything else. Fails if the response doesn't contain
+/// one entry per ARF.
+pub fn apply_translation(model: &str, arfs: &mut [ArfFile], response: &str) -> Result<()> {
+    let translated = synthesis::parse_model_response(model, response)?;
+    if translated.len() != arfs.len() {
+        anyhow::bail!(
+            "translation returned {} entries for {}",
+            translated.len(),
+            arfs.len()
+        );
+    }
+
+    for (arf, translation) in arfs.iter_mut().zip(translated) {
+        arf.what = translation.what;
+        arf.why = translation.why;
+        arf.how = translation.how;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn english() -> Vec<ArfFile> {
+        vec![ArfFile::new(
+            "Errors are propagated with anyhow",
+            "It keeps the call sites short and attaches context to each failure",
+            "Every fallible function returns anyhow::Result and wraps errors with context()",
+        )]
+    }
+
+    fn german() -> Vec<ArfFile> {
+        vec![ArfFile::new(
+            "Fehler werden mit anyhow weitergereicht",
+            "Das hält die Aufrufstellen kurz und fügt jedem Fehler Kontext hinzu",
+            "Jede fehlbare Funktion gibt anyhow::Result zurück und umhüllt Fehler mit context()",
+        )]
+    }
+
+    #[test]
+    fn test_matches_language() {
+        assert!(matches_language(&english(), "English"));
+        assert!(!matches_language(&german(), "English"));
+        assert!(matches_language(&german(), "German"));
+        assert!(!matches_language(&english(), "German"));
+
+        let japanese = vec![ArfFile::new(
+            "エラーは anyhow で伝播される",
+            "呼び出し側を短く保ち、各エラーに文脈を付けるため",
+            "すべての関数が anyhow::Result を返し、context() でエラーを包む。これは全体で一貫している",
+        )];
+        assert!(!matches_language(&japanese, "english"));
+    }
+
+    #[test]
+    fn test_matches_the_configured_language_not_just_any_other() {
+        let french = vec![ArfFile::new(
+            "Les erreurs sont propagées avec anyhow",
+            "Cela garde les appels courts et ajoute du contexte à chaque erreur",
+            "Chaque fonction renvoie anyhow::Result et enveloppe les erreurs avec context()",
+        )];
+        assert!(matches_language(&french, "French"));
+        assert!(matches_language(&french, "fr"));
+        assert!(!matches_language(&french, "German"));
+        assert!(!matches_language(&french, "Japanese"));
+        assert!(!matches_language(&german(), "French"));
+    }
+
+    #[test]
+    fn test_off_language_arfs() {
+        let backend = crate::storage::MemoryBackend::default();
+        backend.write_arf("patterns/en.arf", &english()[0]).unwrap();
+        backend.write_arf("patterns/de.arf", &german()[0]).unwrap();
+        backend.write("patterns/broken.arf", "not toml [").unwrap();
+
+        assert_eq!(off_language_arfs(&backend, "English").unwrap(), vec!["patterns/de.arf"]);
+        assert_eq!(off_language_arfs(&backend, "German").unwrap(), vec!["patterns/en.arf"]);
+    }
+
+    #[test]
+    fn test_short_text_is_not_judged() {
+        let arfs = vec![ArfFile::new("Nutze tokio", "Asynchron", "Abhängigkeit")];
+        assert!(matches_language(&arfs, "English"));
+    }
+
+    #[test]
+    fn test_apply_translation_keeps_context() {
+        let mut arfs = german();
+        arfs[0].add_file("src/error.rs");
+        let prompt = build_translation_prompt(&arfs, "English");
+        assert!(prompt.contains("Fehler werden mit anyhow weitergereicht"));
+
+        let response = "[[entry]]\nwhat = \"Errors are propagated with anyhow\"\nwhy = \"Short call sites\"\nhow = \"anyhow::Result everywhere\"\n";
+        apply_translation("gemini", &mut arfs, response).unwrap();
+        assert_eq!(arfs[0].what, "Errors are propagated with anyhow");
+        assert_eq!(arfs[0].context.files, vec!["src/error.rs"]);
+
+        let two = format!("{}\n{}", response, response);
+        assert!(apply_translation("gemini", &mut arfs, &two).is_err());
+    }
+}
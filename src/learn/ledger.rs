@@ -0,0 +1,551 @@
+//! Machine-level provider budget shared by every repository.
+//!
+//! `[budget]` caps a single learn run. When one machine runs noggin over
+//! many repositories, a backfill in one can still use up the API quota the
+//! others need, so provider usage is also recorded per day in a ledger
+//! under the user config directory (`$XDG_CONFIG_HOME/noggin/`, falling
+//! back to `~/.config/noggin/`):
+//!
+//! - `ledger.toml`: requests, tokens, and estimated spend per day, by
+//!   provider and by repository, kept for [`KEEP_DAYS`] days
+//! - `budget.toml`: optional daily caps across all repositories
+//!
+//! ```toml
+//! max_requests = 2000
+//! max_cost_usd = 25.0
+//!
+//! [providers.claude]
+//! max_tokens = 4000000
+//! ```
+//!
+//! Learn checks the caps before each prompt, alongside the per-run ones,
+//! and defers the rest of the run once a daily cap would be exceeded.
+//! Other commands that query providers (`verify-knowledge`, `review`,
+//! `translate`, `describe`, `experiment`) check the same caps and stop
+//! before a prompt that would exceed them. Each check re-reads the
+//! ledger, and learn adds its usage after every wave of prompts (other
+//! commands when they finish), holding [`LEDGER_LOCK`] so concurrent runs
+//! don't overwrite each other. A concurrent run's usage counts once it is
+//! recorded, so runs going at the same moment can together pass a cap by
+//! what they have in flight. Days follow the local calendar.
+
+use crate::config::BudgetConfig;
+use crate::learn::budget::{estimate_tokens, Budget, BudgetLimit, ProviderUsage};
+use crate::lock::KnowledgeLock;
+use anyhow::{Context, Result};
+use chrono::{Local, NaiveDate};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+use tracing::warn;
+
+/// Daily caps, in the user config directory
+pub const CAPS_FILE: &str = "budget.toml";
+
+/// Usage per day, in the user config directory
+pub const LEDGER_FILE: &str = "ledger.toml";
+
+/// Days of history the ledger keeps
+pub const KEEP_DAYS: i64 = 30;
+
+/// Lock held while updating the ledger, in the user config directory
+pub const LEDGER_LOCK: &str = "ledger.lock";
+
+/// Times to try the ledger lock before giving up, a tenth of a second
+/// apart
+const LOCK_ATTEMPTS: u32 = 50;
+
+/// User config directory for noggin: `$XDG_CONFIG_HOME/noggin`, falling
+/// back to `~/.config/noggin`
+pub fn default_config_dir() -> Result<PathBuf> {
+    let base = match env::var_os("XDG_CONFIG_HOME").filter(|v| !v.is_empty()) {
+        Some(dir) => PathBuf::from(dir),
+        None => {
+            let home = env::var_os("HOME").context("Neither XDG_CONFIG_HOME nor HOME is set")?;
+            PathBuf::from(home).join(".config")
+        }
+    };
+    Ok(base.join("noggin"))
+}
+
+/// Contents of `budget.toml`. Every cap is optional; an unset cap is
+/// unlimited.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DailyCaps {
+    /// Provider requests per day, across all providers
+    #[serde(default)]
+    pub max_requests: Option<u32>,
+    /// Estimated tokens per day, across all providers
+    #[serde(default)]
+    pub max_tokens: Option<u64>,
+    /// Estimated spend per day, in USD
+    #[serde(default)]
+    pub max_cost_usd: Option<f64>,
+    /// Caps for single providers, by name
+    #[serde(default)]
+    pub providers: BTreeMap<String, ProviderCaps>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ProviderCaps {
+    #[serde(default)]
+    pub max_requests: Option<u32>,
+    #[serde(default)]
+    pub max_tokens: Option<u64>,
+}
+
+impl DailyCaps {
+    pub fn is_empty(&self) -> bool {
+        self.max_requests.is_none()
+            && self.max_tokens.is_none()
+            && self.max_cost_usd.is_none()
+            && self
+                .providers
+                .values()
+                .all(|caps| caps.max_requests.is_none() && caps.max_tokens.is_none())
+    }
+}
+
+/// Usage recorded for one day
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DayUsage {
+    #[serde(default)]
+    pub providers: BTreeMap<String, ProviderUsage>,
+    /// Totals by repository path
+    #[serde(default)]
+    pub repos: BTreeMap<String, ProviderUsage>,
+}
+
+impl DayUsage {
+    pub fn total(&self) -> ProviderUsage {
+        let mut total = ProviderUsage::default();
+        for usage in self.providers.values() {
+            add_usage(&mut total, usage);
+        }
+        total
+    }
+}
+
+/// Contents of `ledger.toml`, keyed by `YYYY-MM-DD`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Ledger {
+    #[serde(default)]
+    pub days: BTreeMap<NaiveDate, DayUsage>,
+}
+
+impl Ledger {
+    /// Load the ledger in `dir`, empty if it doesn't exist yet
+    pub fn load(dir: &Path) -> Result<Self> {
+        let path = dir.join(LEDGER_FILE);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let contents = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        toml::from_str(&contents).with_context(|| format!("Failed to parse {}", path.display()))
+    }
+
+    /// Save atomically to `dir`, creating it if needed
+    pub fn save(&self, dir: &Path) -> Result<()> {
+        fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+        let path = dir.join(LEDGER_FILE);
+        let tmp = path.with_extension("toml.tmp");
+        let contents = toml::to_string_pretty(self).context("Failed to serialize ledger")?;
+        fs::write(&tmp, contents).with_context(|| format!("Failed to write {}", tmp.display()))?;
+        fs::rename(&tmp, &path).with_context(|| format!("Failed to replace {}", path.display()))
+    }
+
+    /// Usage recorded on `day`
+    pub fn day(&self, day: NaiveDate) -> DayUsage {
+        self.days.get(&day).cloned().unwrap_or_default()
+    }
+
+    /// Add a run's per-provider usage for `repo` to `day`, and forget days
+    /// more than [`KEEP_DAYS`] before it
+    pub fn record(&mut self, day: NaiveDate, repo: &str, providers: &BTreeMap<String, ProviderUsage>) {
+        let entry = self.days.entry(day).or_default();
+        for (name, usage) in providers {
+            add_usage(entry.providers.entry(name.clone()).or_default(), usage);
+            add_usage(entry.repos.entry(repo.to_string()).or_default(), usage);
+        }
+        let oldest = day - chrono::Duration::days(KEEP_DAYS);
+        self.days.retain(|recorded, _| *recorded > oldest);
+    }
+}
+
+fn add_usage(total: &mut ProviderUsage, usage: &ProviderUsage) {
+    total.requests += usage.requests;
+    total.prompt_tokens += usage.prompt_tokens;
+    total.response_tokens += usage.response_tokens;
+    total.estimated_cost_usd += usage.estimated_cost_usd;
+}
+
+/// `total` less `usage`, never below zero
+fn sub_usage(total: &ProviderUsage, usage: &ProviderUsage) -> ProviderUsage {
+    ProviderUsage {
+        requests: total.requests.saturating_sub(usage.requests),
+        prompt_tokens: total.prompt_tokens.saturating_sub(usage.prompt_tokens),
+        response_tokens: total.response_tokens.saturating_sub(usage.response_tokens),
+        estimated_cost_usd: (total.estimated_cost_usd - usage.estimated_cost_usd).max(0.0),
+    }
+}
+
+/// Today's caps, and the part of this run's usage already in the ledger
+#[derive(Debug)]
+pub struct MachineBudget {
+    dir: PathBuf,
+    day: NaiveDate,
+    caps: DailyCaps,
+    /// Today's usage when the budget was loaded, for when the ledger
+    /// can't be re-read
+    prior: DayUsage,
+    recorded: Mutex<BTreeMap<String, ProviderUsage>>,
+}
+
+impl MachineBudget {
+    /// The budget in the user config directory, or None with a warning
+    /// when it can't be read, in which case the command runs uncapped
+    pub fn for_user() -> Option<Self> {
+        match default_config_dir().and_then(|dir| Self::load(&dir)) {
+            Ok(machine) => Some(machine),
+            Err(e) => {
+                warn!("Machine budget unavailable: {:#}", e);
+                None
+            }
+        }
+    }
+
+    /// Load the caps and today's usage from `dir`
+    pub fn load(dir: &Path) -> Result<Self> {
+        let caps_path = dir.join(CAPS_FILE);
+        let caps = if caps_path.exists() {
+            let contents = fs::read_to_string(&caps_path)
+                .with_context(|| format!("Failed to read {}", caps_path.display()))?;
+            toml::from_str(&contents)
+                .with_context(|| format!("Failed to parse {}", caps_path.display()))?
+        } else {
+            DailyCaps::default()
+        };
+        let day = Local::now().date_naive();
+        let prior = Ledger::load(dir)?.day(day);
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            day,
+            caps,
+            prior,
+            recorded: Mutex::new(BTreeMap::new()),
+        })
+    }
+
+    /// The run's usage not yet added to the ledger
+    fn unrecorded(&self, run: &Budget) -> BTreeMap<String, ProviderUsage> {
+        let recorded = self.recorded.lock().unwrap();
+        run.by_provider()
+            .iter()
+            .map(|(name, usage)| {
+                let delta = match recorded.get(name) {
+                    Some(done) => sub_usage(usage, done),
+                    None => usage.clone(),
+                };
+                (name.clone(), delta)
+            })
+            .collect()
+    }
+
+    /// Whether sending a prompt of `prompt_tokens` to each of `providers`
+    /// would take today's usage, as the ledger has it now plus what this
+    /// run hasn't recorded yet, over a daily cap. Responses not yet
+    /// received count at the run's response allowance.
+    pub fn check(&self, run: &Budget, prompt_tokens: u64, providers: &[&str]) -> Option<BudgetLimit> {
+        if self.caps.is_empty() {
+            return None;
+        }
+
+        let (mut today, unrecorded) = match Ledger::load(&self.dir) {
+            Ok(ledger) => (ledger.day(self.day).providers, self.unrecorded(run)),
+            Err(e) => {
+                warn!("Failed to re-read the machine budget ledger: {:#}", e);
+                (self.prior.providers.clone(), run.by_provider().clone())
+            }
+        };
+        for (name, usage) in &unrecorded {
+            add_usage(today.entry(name.clone()).or_default(), usage);
+        }
+        for name in providers {
+            let usage = today.entry(name.to_string()).or_default();
+            let response_tokens = run.reserved(name) + run.response_allowance();
+            usage.requests += 1;
+            usage.prompt_tokens += prompt_tokens;
+            usage.response_tokens += response_tokens;
+            usage.estimated_cost_usd +=
+                (prompt_tokens + response_tokens) as f64 / 1_000_000.0 * run.price_of(name);
+        }
+
+        for (name, caps) in &self.caps.providers {
+            let Some(usage) = today.get(name) else {
+                continue;
+            };
+            if caps.max_requests.is_some_and(|max| usage.requests > max)
+                || caps.max_tokens.is_some_and(|max| usage.tokens() > max)
+            {
+                return Some(BudgetLimit::Daily);
+            }
+        }
+
+        let total = DayUsage {
+            providers: today,
+            repos: BTreeMap::new(),
+        }
+        .total();
+        let over = self.caps.max_requests.is_some_and(|max| total.requests > max)
+            || self.caps.max_tokens.is_some_and(|max| total.tokens() > max)
+            || self.caps.max_cost_usd.is_some_and(|max| total.estimated_cost_usd > max);
+        over.then_some(BudgetLimit::Daily)
+    }
+
+    /// [`check`](Self::check) as an error, for commands that stop at the
+    /// cap rather than defer
+    pub fn ensure(&self, run: &Budget, prompt_tokens: u64, providers: &[&str]) -> Result<()> {
+        match self.check(run, prompt_tokens, providers) {
+            Some(limit) => anyhow::bail!(
+                "Stopping before the {} is exceeded; caps are set in {}",
+                limit,
+                self.dir.join(CAPS_FILE).display()
+            ),
+            None => Ok(()),
+        }
+    }
+
+    /// Add the run's usage since it was last recorded to the ledger, so
+    /// this can be called as the run goes. The ledger is re-read first so
+    /// runs in other repositories since this one started aren't lost.
+    pub fn record(&self, repo: &str, run: &Budget) -> Result<()> {
+        let unrecorded = self.unrecorded(run);
+        if unrecorded.values().all(|usage| *usage == ProviderUsage::default()) {
+            return Ok(());
+        }
+        let _lock = lock_ledger(&self.dir)?;
+        let mut ledger = Ledger::load(&self.dir)?;
+        ledger.record(self.day, repo, &unrecorded);
+        ledger.save(&self.dir)?;
+        *self.recorded.lock().unwrap() = run.by_provider().clone();
+        Ok(())
+    }
+
+    /// [`record`](Self::record) for the repository at `repo`, warning
+    /// instead of failing a command whose work is already done
+    pub fn record_or_warn(&self, repo: &Path, run: &Budget) {
+        if let Err(e) = self.record(&repo.display().to_string(), run) {
+            warn!("Failed to update the machine budget ledger: {:#}", e);
+        }
+    }
+}
+
+/// Provider usage of a command other than learn: each prompt is checked
+/// against the daily caps before it is sent, and the total is added to the
+/// ledger when the command finishes
+pub struct MeteredRun {
+    machine: Option<MachineBudget>,
+    budget: Budget,
+}
+
+impl MeteredRun {
+    /// Metering against `machine`'s caps, or none; `limits` prices the
+    /// usage
+    pub fn new(machine: Option<MachineBudget>, limits: BudgetConfig) -> Self {
+        Self {
+            machine,
+            budget: Budget::new(limits),
+        }
+    }
+
+    /// Bill `prompt` to `provider`, unless it would exceed a daily cap
+    pub fn admit(&mut self, provider: &str, prompt: &str) -> Result<()> {
+        let prompt_tokens = estimate_tokens(prompt);
+        if let Some(machine) = &self.machine {
+            machine.ensure(&self.budget, prompt_tokens, &[provider])?;
+        }
+        self.budget.record_provider(provider, prompt_tokens, 0);
+        Ok(())
+    }
+
+    /// Bill `provider`'s response to an admitted prompt
+    pub fn answered(&mut self, provider: &str, response: &str) {
+        self.budget.record_response(provider, estimate_tokens(response));
+    }
+
+    /// Add the usage to the ledger for the repository at `repo`
+    pub fn finish(&self, repo: &Path) {
+        if let Some(machine) = &self.machine {
+            machine.record_or_warn(repo, &self.budget);
+        }
+    }
+}
+
+/// Hold the ledger's lock in `dir`, waiting while another run holds it
+fn lock_ledger(dir: &Path) -> Result<KnowledgeLock> {
+    fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+    let mut attempt = 1;
+    loop {
+        match KnowledgeLock::acquire_file(dir.join(LEDGER_LOCK), "ledger") {
+            Ok(lock) => return Ok(lock),
+            Err(e) if attempt >= LOCK_ATTEMPTS => {
+                return Err(e).context("Failed to lock the machine budget ledger")
+            }
+            Err(_) => {
+                attempt += 1;
+                thread::sleep(Duration::from_millis(100));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_ledger_records_and_prunes() {
+        let temp_dir = TempDir::new().unwrap();
+        let day = NaiveDate::from_ymd_opt(2026, 3, 31).unwrap();
+        let mut run = BTreeMap::new();
+        run.insert(
+            "claude".to_string(),
+            ProviderUsage {
+                requests: 2,
+                prompt_tokens: 1000,
+                response_tokens: 500,
+                estimated_cost_usd: 0.01,
+            },
+        );
+
+        let mut ledger = Ledger::default();
+        ledger.record(day - chrono::Duration::days(KEEP_DAYS), "/old", &run);
+        ledger.record(day, "/repo/a", &run);
+        ledger.record(day, "/repo/b", &run);
+        ledger.save(temp_dir.path()).unwrap();
+
+        let loaded = Ledger::load(temp_dir.path()).unwrap();
+        assert_eq!(loaded.days.len(), 1);
+        let today = loaded.day(day);
+        assert_eq!(today.providers["claude"].requests, 4);
+        assert_eq!(today.repos["/repo/b"].tokens(), 1500);
+        assert_eq!(today.total().requests, 4);
+    }
+
+    #[test]
+    fn test_concurrent_records_are_all_kept() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut run = Budget::new(BudgetConfig::default());
+        run.record_provider("claude", 100, 100);
+
+        std::thread::scope(|scope| {
+            for i in 0..8 {
+                let (dir, run) = (temp_dir.path(), &run);
+                scope.spawn(move || {
+                    let machine = MachineBudget::load(dir).unwrap();
+                    machine.record(&format!("/repo/{}", i), run).unwrap()
+                });
+            }
+        });
+
+        let machine = MachineBudget::load(temp_dir.path()).unwrap();
+        let today = Ledger::load(temp_dir.path()).unwrap().day(machine.day);
+        assert_eq!(today.total().requests, 8);
+        assert_eq!(today.repos.len(), 8);
+        assert!(!temp_dir.path().join(LEDGER_LOCK).exists());
+    }
+
+    #[test]
+    fn test_metered_run_stops_at_the_daily_cap() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(temp_dir.path().join(CAPS_FILE), "max_requests = 1\n").unwrap();
+        let machine = MachineBudget::load(temp_dir.path()).unwrap();
+
+        let mut metered = MeteredRun::new(Some(machine), BudgetConfig::default());
+        metered.admit("claude", "first prompt").unwrap();
+        metered.answered("claude", "a response");
+        let err = metered.admit("claude", "second prompt").unwrap_err();
+        assert!(err.to_string().contains("daily machine budget"));
+
+        metered.finish(temp_dir.path());
+        let machine = MachineBudget::load(temp_dir.path()).unwrap();
+        assert_eq!(machine.prior.total().requests, 1);
+    }
+
+    #[test]
+    fn test_daily_caps_include_other_repos() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(
+            temp_dir.path().join(CAPS_FILE),
+            "max_requests = 10\n\n[providers.gemini]\nmax_tokens = 5000\n",
+        )
+        .unwrap();
+
+        let mut other_repo = Budget::new(BudgetConfig::default());
+        for _ in 0..8 {
+            other_repo.record_provider("claude", 100, 100);
+        }
+        let machine = MachineBudget::load(temp_dir.path()).unwrap();
+        machine.record("/repo/a", &other_repo).unwrap();
+
+        let machine = MachineBudget::load(temp_dir.path()).unwrap();
+        let run = Budget::new(BudgetConfig::default());
+        assert_eq!(machine.check(&run, 100, &["claude"]), None);
+        assert_eq!(machine.check(&run, 100, &["claude", "codex"]), None);
+        assert_eq!(
+            machine.check(&run, 100, &["claude", "codex", "gemini"]),
+            Some(BudgetLimit::Daily)
+        );
+        assert_eq!(machine.check(&run, 6000, &["gemini"]), Some(BudgetLimit::Daily));
+
+        let uncapped = MachineBudget::load(&temp_dir.path().join("none")).unwrap();
+        assert_eq!(uncapped.check(&run, u64::MAX / 2, &["claude"]), None);
+    }
+
+    #[test]
+    fn test_check_sees_usage_recorded_after_load() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(temp_dir.path().join(CAPS_FILE), "max_requests = 3
+").unwrap();
+        let first = MachineBudget::load(temp_dir.path()).unwrap();
+        let second = MachineBudget::load(temp_dir.path()).unwrap();
+
+        let mut first_run = Budget::new(BudgetConfig::default());
+        first_run.record_provider("claude", 100, 100);
+        first_run.record_provider("claude", 100, 100);
+        first.record("/repo/a", &first_run).unwrap();
+
+        let second_run = Budget::new(BudgetConfig::default());
+        assert_eq!(second.check(&second_run, 100, &["claude"]), None);
+        first_run.record_provider("claude", 100, 100);
+        first.record("/repo/a", &first_run).unwrap();
+        assert_eq!(
+            second.check(&second_run, 100, &["claude"]),
+            Some(BudgetLimit::Daily)
+        );
+    }
+
+    #[test]
+    fn test_repeated_records_add_only_new_usage() {
+        let temp_dir = TempDir::new().unwrap();
+        let machine = MachineBudget::load(temp_dir.path()).unwrap();
+        let mut run = Budget::new(BudgetConfig::default());
+        run.record_provider("claude", 100, 100);
+        machine.record("/repo/a", &run).unwrap();
+        machine.record("/repo/a", &run).unwrap();
+        run.record_provider("codex", 100, 100);
+        machine.record("/repo/a", &run).unwrap();
+
+        let today = Ledger::load(temp_dir.path()).unwrap().day(machine.day);
+        assert_eq!(today.total().requests, 2);
+        assert_eq!(today.providers["claude"].requests, 1);
+        assert_eq!(today.providers["codex"].requests, 1);
+    }
+}
@@ -0,0 +1,822 @@
+//! Deterministic analyzers for `noggin learn --lite`.
+//!
+//! Builds structural facts without querying any provider: a catalog of
+//! TODO-style markers left in comments, the dependencies each package
+//! manifest declares, the most active authors per top-level directory,
+//! an index of the most significant recent commits, and glossary entries
+//! for the types and acronyms the source itself defines (see
+//! [`crate::learn::glossary`]). Every entry is tagged `lite` and filed
+//! under `facts/`.
+
+use crate::arf::{ArfFile, ArfSource};
+use crate::config::Config;
+use crate::git::scoring::{score_commit, CommitScore, ScoreCategory, ScoringConfig};
+use crate::learn::glossary::{self, Term};
+use crate::policy::NeverSend;
+use crate::text::short_hash;
+use anyhow::{Context, Result};
+use git2::{Repository, Sort};
+use regex::Regex;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+/// Tag on every ARF produced by the lite analyzers
+pub const LITE_TAG: &str = "lite";
+
+/// Most recent commits read for ownership and the significance index
+pub const HISTORY_LIMIT: usize = 1000;
+
+/// Comment markers collected into the debt catalog
+pub const MARKERS: &[&str] = &["TODO", "FIXME", "HACK", "XXX"];
+
+/// Marker lines listed in the catalog; the rest are only counted
+const MAX_DEBT_ENTRIES: usize = 100;
+
+/// Characters of marker text kept per entry
+const MAX_MARKER_TEXT: usize = 120;
+
+/// Files larger than this are not searched for markers
+const MAX_SCAN_BYTES: u64 = 1024 * 1024;
+
+/// Commits listed in the significance index
+const MAX_INDEXED_COMMITS: usize = 50;
+
+/// Directories that get an ownership fact, most active first
+const MAX_OWNERSHIP_AREAS: usize = 20;
+
+/// One TODO-style marker found in a comment
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Marker {
+    /// 1-based line number
+    pub line: usize,
+    pub kind: String,
+    pub text: String,
+}
+
+/// Dependencies declared by one package manifest
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Dependencies {
+    pub runtime: Vec<String>,
+    /// Development, test, and build-only dependencies
+    pub dev: Vec<String>,
+}
+
+/// A commit as read for the history-based analyzers
+#[derive(Debug, Clone)]
+pub struct HistoryCommit {
+    pub hash: String,
+    pub short_hash: String,
+    pub author: String,
+    pub summary: String,
+    /// Paths the commit touched
+    pub paths: Vec<String>,
+    /// None when the commit could not be scored (e.g. a shallow boundary)
+    pub score: Option<CommitScore>,
+}
+
+/// Run every lite analyzer. `files` are repository-relative paths to
+/// search; contents of `never_send` files are never read. `terms` are the
+/// glossary candidates mined from them (see [`glossary::mine_terms_cached`]).
+pub fn analyze(
+    repo_path: &Path,
+    files: &[String],
+    mut terms: Vec<Term>,
+    never_send: &NeverSend,
+    config: &Config,
+) -> Result<Vec<ArfFile>> {
+    let mut arfs = Vec::new();
+    arfs.extend(debt_catalog(repo_path, files, never_send));
+    arfs.extend(dependency_facts(repo_path, files, never_send));
+    if config.glossary.enabled {
+        terms.retain(|term| term.definition.is_some());
+        terms.truncate(config.glossary.max_terms);
+        arfs.extend(glossary::glossary_facts(&terms));
+    }
+
+    let history = read_history(repo_path, HISTORY_LIMIT, &config.scoring)?;
+    if config.people.enabled {
+        arfs.extend(ownership_facts(&history, config.people.max_per_entry));
+    }
+    arfs.extend(significance_index(&history));
+
+    for arf in &mut arfs {
+        arf.tags.push(LITE_TAG.to_string());
+        arf.confidence = Some(1.0);
+    }
+    Ok(arfs)
+}
+
+/// Catalog every TODO/FIXME/HACK/XXX comment in `files` as one ARF
+pub fn debt_catalog(repo_path: &Path, files: &[String], never_send: &NeverSend) -> Option<ArfFile> {
+    let pattern = marker_pattern();
+    let mut found: Vec<(&str, Marker)> = Vec::new();
+
+    for path in files {
+        if never_send.matches(path) {
+            continue;
+        }
+        let full_path = repo_path.join(path);
+        if fs::metadata(&full_path).map_or(true, |m| m.len() > MAX_SCAN_BYTES) {
+            continue;
+        }
+        let Ok(contents) = fs::read_to_string(&full_path) else {
+            continue;
+        };
+        for marker in find_markers(&pattern, &contents) {
+            found.push((path.as_str(), marker));
+        }
+    }
+    if found.is_empty() {
+        return None;
+    }
+    found.sort_by(|a, b| a.0.cmp(b.0).then(a.1.line.cmp(&b.1.line)));
+
+    let mut files: Vec<String> = found.iter().map(|(path, _)| path.to_string()).collect();
+    files.dedup();
+
+    let counts: Vec<String> = MARKERS
+        .iter()
+        .filter_map(|kind| {
+            let count = found.iter().filter(|(_, m)| m.kind == *kind).count();
+            (count > 0).then(|| format!("{} {}", count, kind))
+        })
+        .collect();
+
+    let mut how = format!(
+        "{} markers in {} files: {}\n",
+        found.len(),
+        files.len(),
+        counts.join(", ")
+    );
+    for (path, marker) in found.iter().take(MAX_DEBT_ENTRIES) {
+        how.push_str(&format!("- {}:{} {}", path, marker.line, marker.kind));
+        if !marker.text.is_empty() {
+            how.push_str(&format!(" {}", marker.text));
+        }
+        how.push('\n');
+    }
+    if found.len() > MAX_DEBT_ENTRIES {
+        how.push_str(&format!(
+            "- ... and {} more\n",
+            found.len() - MAX_DEBT_ENTRIES
+        ));
+    }
+
+    let mut arf = ArfFile::new(
+        "Open TODO and FIXME markers",
+        "Comments marked TODO, FIXME, HACK, or XXX record known debt and unfinished work",
+        how.trim_end(),
+    );
+    arf.source = Some(ArfSource::FileAnalysis);
+    arf.context.files = files;
+    Some(arf)
+}
+
+/// A marker directly after a comment opener, e.g. `// TODO: ...`
+fn marker_pattern() -> Regex {
+    let pattern = format!(r"(?://|#|/\*|<!--|--|;|\*)\s*({})\b(.*)", MARKERS.join("|"));
+    Regex::new(&pattern).expect("marker pattern is valid")
+}
+
+/// Markers in `contents`, one per line at most
+pub fn find_markers(pattern: &Regex, contents: &str) -> Vec<Marker> {
+    contents
+        .lines()
+        .enumerate()
+        .filter_map(|(i, line)| {
+            let captures = pattern.captures(line)?;
+            let text = captures[2]
+                .trim()
+                .trim_end_matches("*/")
+                .trim_end_matches("-->")
+                .trim_start_matches([':', '-'])
+                .trim();
+            Some(Marker {
+                line: i + 1,
+                kind: captures[1].to_string(),
+                text: text.chars().take(MAX_MARKER_TEXT).collect(),
+            })
+        })
+        .collect()
+}
+
+/// One ARF per package manifest among `files` that declares dependencies
+pub fn dependency_facts(
+    repo_path: &Path,
+    files: &[String],
+    never_send: &NeverSend,
+) -> Vec<ArfFile> {
+    let mut arfs = Vec::new();
+
+    for path in files {
+        let name = path.rsplit('/').next().unwrap_or(path);
+        if never_send.matches(path) || !is_package_manifest(name) {
+            continue;
+        }
+        let Ok(contents) = fs::read_to_string(repo_path.join(path)) else {
+            continue;
+        };
+        let Some(deps) = parse_manifest(name, &contents) else {
+            continue;
+        };
+        if deps.runtime.is_empty() && deps.dev.is_empty() {
+            continue;
+        }
+
+        let mut how = Vec::new();
+        if !deps.runtime.is_empty() {
+            how.push(format!("Runtime: {}", deps.runtime.join(", ")));
+        }
+        if !deps.dev.is_empty() {
+            how.push(format!("Development: {}", deps.dev.join(", ")));
+        }
+
+        let mut arf = ArfFile::new(
+            format!("Dependencies declared in {}", path),
+            format!(
+                "{} declares {} runtime and {} development dependencies",
+                path,
+                deps.runtime.len(),
+                deps.dev.len()
+            ),
+            how.join("\n"),
+        );
+        arf.source = Some(ArfSource::FileAnalysis);
+        arf.add_file(path.clone());
+        arf.context.dependencies = deps.runtime.into_iter().chain(deps.dev).collect();
+        arfs.push(arf);
+    }
+
+    arfs
+}
+
+fn is_package_manifest(name: &str) -> bool {
+    matches!(
+        name,
+        "Cargo.toml" | "package.json" | "go.mod" | "pyproject.toml"
+    ) || (name.starts_with("requirements") && name.ends_with(".txt"))
+}
+
+/// Parse a package manifest by file name; None if it isn't one or it
+/// doesn't parse
+pub fn parse_manifest(name: &str, contents: &str) -> Option<Dependencies> {
+    let mut deps = match name {
+        "Cargo.toml" => parse_cargo_toml(contents)?,
+        "package.json" => parse_package_json(contents)?,
+        "go.mod" => parse_go_mod(contents),
+        "pyproject.toml" => parse_pyproject(contents)?,
+        _ if name.starts_with("requirements") && name.ends_with(".txt") => {
+            let names = parse_requirements(contents);
+            if name.contains("dev") || name.contains("test") {
+                Dependencies {
+                    runtime: Vec::new(),
+                    dev: names,
+                }
+            } else {
+                Dependencies {
+                    runtime: names,
+                    dev: Vec::new(),
+                }
+            }
+        }
+        _ => return None,
+    };
+    for list in [&mut deps.runtime, &mut deps.dev] {
+        list.sort();
+        list.dedup();
+    }
+    Some(deps)
+}
+
+fn table_keys(value: Option<&toml::Value>) -> Vec<String> {
+    value
+        .and_then(|v| v.as_table())
+        .map(|table| table.keys().cloned().collect())
+        .unwrap_or_default()
+}
+
+fn parse_cargo_toml(contents: &str) -> Option<Dependencies> {
+    let doc: toml::Value = toml::from_str(contents).ok()?;
+    let mut deps = Dependencies {
+        runtime: table_keys(doc.get("dependencies")),
+        dev: table_keys(doc.get("dev-dependencies")),
+    };
+    deps.runtime.extend(table_keys(
+        doc.get("workspace").and_then(|w| w.get("dependencies")),
+    ));
+    deps.dev.extend(table_keys(doc.get("build-dependencies")));
+    if let Some(targets) = doc.get("target").and_then(|t| t.as_table()) {
+        for target in targets.values() {
+            deps.runtime.extend(table_keys(target.get("dependencies")));
+            deps.dev.extend(table_keys(target.get("dev-dependencies")));
+        }
+    }
+    Some(deps)
+}
+
+fn parse_package_json(contents: &str) -> Option<Dependencies> {
+    let doc: serde_json::Value = serde_json::from_str(contents).ok()?;
+    let keys = |field: &str| -> Vec<String> {
+        doc.get(field)
+            .and_then(|v| v.as_object())
+            .map(|object| object.keys().cloned().collect())
+            .unwrap_or_default()
+    };
+    let mut runtime = keys("dependencies");
+    runtime.extend(keys("peerDependencies"));
+    runtime.extend(keys("optionalDependencies"));
+    Some(Dependencies {
+        runtime,
+        dev: keys("devDependencies"),
+    })
+}
+
+/// Direct requirements only; `// indirect` ones are left out
+fn parse_go_mod(contents: &str) -> Dependencies {
+    let mut runtime = Vec::new();
+    let mut in_block = false;
+
+    for line in contents.lines() {
+        let line = line.trim();
+        let spec = if in_block {
+            if line == ")" {
+                in_block = false;
+                continue;
+            }
+            line
+        } else if line == "require (" {
+            in_block = true;
+            continue;
+        } else if let Some(spec) = line.strip_prefix("require ") {
+            spec
+        } else {
+            continue;
+        };
+        if spec.contains("// indirect") {
+            continue;
+        }
+        if let Some(module) = spec
+            .split_whitespace()
+            .next()
+            .filter(|m| !m.starts_with("//"))
+        {
+            runtime.push(module.to_string());
+        }
+    }
+
+    Dependencies {
+        runtime,
+        dev: Vec::new(),
+    }
+}
+
+/// Distribution name at the start of a PEP 508 requirement
+fn requirement_name(spec: &str) -> Option<String> {
+    let name: String = spec
+        .trim()
+        .chars()
+        .take_while(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+        .collect();
+    (!name.is_empty()).then_some(name)
+}
+
+fn parse_requirements(contents: &str) -> Vec<String> {
+    contents
+        .lines()
+        .map(|line| line.split('#').next().unwrap_or("").trim())
+        .filter(|line| !line.is_empty() && !line.starts_with('-'))
+        .filter_map(requirement_name)
+        .collect()
+}
+
+fn parse_pyproject(contents: &str) -> Option<Dependencies> {
+    let doc: toml::Value = toml::from_str(contents).ok()?;
+    let mut deps = Dependencies::default();
+    let specs = |value: Option<&toml::Value>| -> Vec<String> {
+        value
+            .and_then(|v| v.as_array())
+            .map(|items| {
+                items
+                    .iter()
+                    .filter_map(|item| item.as_str().and_then(requirement_name))
+                    .collect()
+            })
+            .unwrap_or_default()
+    };
+
+    if let Some(project) = doc.get("project") {
+        deps.runtime.extend(specs(project.get("dependencies")));
+        if let Some(extras) = project
+            .get("optional-dependencies")
+            .and_then(|e| e.as_table())
+        {
+            for extra in extras.values() {
+                deps.dev.extend(specs(Some(extra)));
+            }
+        }
+    }
+
+    if let Some(poetry) = doc.get("tool").and_then(|t| t.get("poetry")) {
+        deps.runtime.extend(
+            table_keys(poetry.get("dependencies"))
+                .into_iter()
+                .filter(|name| name != "python"),
+        );
+        deps.dev.extend(table_keys(poetry.get("dev-dependencies")));
+        if let Some(groups) = poetry.get("group").and_then(|g| g.as_table()) {
+            for group in groups.values() {
+                deps.dev.extend(table_keys(group.get("dependencies")));
+            }
+        }
+    }
+
+    Some(deps)
+}
+
+/// Read up to `limit` non-merge commits reachable from HEAD, newest first,
+/// scored with `scoring`. An unborn HEAD yields no commits.
+pub fn read_history(
+    repo_path: &Path,
+    limit: usize,
+    scoring: &ScoringConfig,
+) -> Result<Vec<HistoryCommit>> {
+    let repo = Repository::open(repo_path)
+        .with_context(|| format!("Failed to open git repository at {}", repo_path.display()))?;
+    let mut revwalk = repo.revwalk()?;
+    if revwalk.push_head().is_err() {
+        return Ok(Vec::new());
+    }
+    revwalk.set_sorting(Sort::TIME)?;
+
+    let mut history = Vec::new();
+
+    for oid in revwalk {
+        if history.len() >= limit {
+            break;
+        }
+        let commit = repo.find_commit(oid?)?;
+        if commit.parent_count() > 1 {
+            continue;
+        }
+        let hash = commit.id().to_string();
+        history.push(HistoryCommit {
+            short_hash: short_hash(&hash).to_string(),
+            hash,
+            author: commit.author().name().unwrap_or("unknown").to_string(),
+            summary: commit.summary().unwrap_or("").to_string(),
+            paths: touched_paths(&repo, &commit).unwrap_or_default(),
+            score: score_commit(&repo, &commit, scoring).ok(),
+        });
+    }
+
+    Ok(history)
+}
+
+/// Paths a commit changed relative to its first parent
+pub fn touched_paths(repo: &Repository, commit: &git2::Commit) -> Result<Vec<String>> {
+    let parent_tree = match commit.parent_count() {
+        0 => None,
+        _ => Some(commit.parent(0)?.tree()?),
+    };
+    let diff = repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&commit.tree()?), None)?;
+    Ok(diff
+        .deltas()
+        .filter_map(|delta| delta.new_file().path().or_else(|| delta.old_file().path()))
+        .map(|path| path.to_string_lossy().into_owned())
+        .collect())
+}
+
+/// Top-level directory of a path, or "." for files at the root
+fn area(path: &str) -> &str {
+    path.split_once('/').map_or(".", |(dir, _)| dir)
+}
+
+/// Commits touching one area, and how many each author made
+#[derive(Default)]
+struct AreaActivity<'a> {
+    commits: usize,
+    /// In first-seen order
+    authors: Vec<(&'a str, usize)>,
+}
+
+/// One ARF per top-level directory naming the authors of most of the
+/// commits touching it, most active directories first
+pub fn ownership_facts(history: &[HistoryCommit], max_people: usize) -> Vec<ArfFile> {
+    let mut areas: BTreeMap<&str, AreaActivity> = BTreeMap::new();
+
+    for commit in history {
+        let mut touched: Vec<&str> = commit.paths.iter().map(|p| area(p)).collect();
+        touched.sort();
+        touched.dedup();
+        for name in touched {
+            let activity = areas.entry(name).or_default();
+            activity.commits += 1;
+            match activity
+                .authors
+                .iter_mut()
+                .find(|(author, _)| *author == commit.author)
+            {
+                Some((_, count)) => *count += 1,
+                None => activity.authors.push((&commit.author, 1)),
+            }
+        }
+    }
+
+    let mut ranked: Vec<_> = areas.into_iter().collect();
+    // Stable sort keeps alphabetical order among equals
+    ranked.sort_by_key(|(_, activity)| std::cmp::Reverse(activity.commits));
+    ranked.truncate(MAX_OWNERSHIP_AREAS);
+
+    ranked
+        .into_iter()
+        .map(|(name, activity)| {
+            let total = activity.commits;
+            let mut authors = activity.authors;
+            authors.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+            authors.truncate(max_people);
+
+            let label = if name == "." {
+                "top-level files".to_string()
+            } else {
+                format!("{}/", name)
+            };
+            let how: Vec<String> = authors
+                .iter()
+                .map(|(author, count)| {
+                    format!(
+                        "- {}: {} commits ({:.0}%)",
+                        author,
+                        count,
+                        *count as f64 / total as f64 * 100.0
+                    )
+                })
+                .collect();
+
+            let mut arf = ArfFile::new(
+                format!("Ownership of {}", label),
+                format!(
+                    "{} of the last {} commits touched {}; these authors made the most of them",
+                    total,
+                    history.len(),
+                    label
+                ),
+                how.join("\n"),
+            );
+            arf.source = Some(ArfSource::CommitAnalysis);
+            if name != "." {
+                arf.add_file(label);
+            }
+            arf.context.people = authors
+                .iter()
+                .map(|(author, _)| author.to_string())
+                .collect();
+            arf
+        })
+        .collect()
+}
+
+/// The highest-scoring commits of Medium significance or above, as one ARF
+pub fn significance_index(history: &[HistoryCommit]) -> Option<ArfFile> {
+    let mut scored: Vec<(&HistoryCommit, &CommitScore)> = history
+        .iter()
+        .filter_map(|commit| commit.score.as_ref().map(|score| (commit, score)))
+        .filter(|(_, score)| {
+            matches!(
+                score.category,
+                ScoreCategory::Critical | ScoreCategory::High | ScoreCategory::Medium
+            )
+        })
+        .collect();
+    if scored.is_empty() {
+        return None;
+    }
+    // Stable sort keeps newest first among equal scores
+    scored.sort_by(|a, b| b.1.significance.total_cmp(&a.1.significance));
+    scored.truncate(MAX_INDEXED_COMMITS);
+
+    let how: Vec<String> = scored
+        .iter()
+        .map(|(commit, score)| {
+            format!(
+                "- {} {} ({:.2}): {} ({})",
+                commit.short_hash,
+                score.category,
+                score.significance,
+                commit.summary,
+                commit.author
+            )
+        })
+        .collect();
+
+    let mut arf = ArfFile::new(
+        "Most significant recent commits",
+        format!(
+            "Ranked by diff size, touched files, and message keywords over the last {} commits",
+            history.len()
+        ),
+        how.join("\n"),
+    );
+    arf.source = Some(ArfSource::CommitAnalysis);
+    for (commit, _) in &scored {
+        arf.add_commit(commit.hash.clone());
+    }
+    Some(arf)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn commit(author: &str, paths: &[&str], significance: Option<f32>) -> HistoryCommit {
+        HistoryCommit {
+            hash: format!("{:0<40}", author.len()),
+            short_hash: format!("{:0<7}", author.len()),
+            author: author.to_string(),
+            summary: format!("Change by {}", author),
+            paths: paths.iter().map(|p| p.to_string()).collect(),
+            score: significance.map(|significance| CommitScore {
+                significance,
+                category: ScoreCategory::from_score(significance),
+                factors: Vec::new(),
+            }),
+        }
+    }
+
+    #[test]
+    fn test_find_markers() {
+        let contents = "fn main() {\n    // TODO: handle errors\n    let x = \"TODO\";\n    /* FIXME(alice) leaks */\n}\n# HACK - pinned\n";
+        let markers = find_markers(&marker_pattern(), contents);
+
+        assert_eq!(markers.len(), 3);
+        assert_eq!(
+            markers[0],
+            Marker {
+                line: 2,
+                kind: "TODO".into(),
+                text: "handle errors".into()
+            }
+        );
+        assert_eq!(markers[1].kind, "FIXME");
+        assert_eq!(markers[1].text, "(alice) leaks");
+        assert_eq!(
+            markers[2],
+            Marker {
+                line: 6,
+                kind: "HACK".into(),
+                text: "pinned".into()
+            }
+        );
+    }
+
+    #[test]
+    fn test_debt_catalog() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("a.rs"), "// TODO: one\n// XXX two\n").unwrap();
+        fs::write(dir.path().join("b.rs"), "fn clean() {}\n").unwrap();
+        fs::write(dir.path().join(".env"), "# TODO rotate key\n").unwrap();
+        let files = vec!["a.rs".to_string(), "b.rs".to_string(), ".env".to_string()];
+        let never_send = NeverSend::new(&[".env".to_string()]).unwrap();
+
+        let arf = debt_catalog(dir.path(), &files, &never_send).unwrap();
+        assert_eq!(arf.context.files, vec!["a.rs"]);
+        assert!(arf.how.starts_with("2 markers in 1 files: 1 TODO, 1 XXX"));
+        assert!(arf.how.contains("- a.rs:1 TODO one"));
+        assert!(arf.how.contains("- a.rs:2 XXX two"));
+        assert!(!arf.how.contains("rotate"));
+
+        assert!(debt_catalog(dir.path(), &files[1..2], &never_send).is_none());
+    }
+
+    #[test]
+    fn test_parse_cargo_toml() {
+        let contents = r#"
+[package]
+name = "demo"
+
+[dependencies]
+serde = "1"
+anyhow = "1"
+
+[dev-dependencies]
+tempfile = "3"
+
+[target.'cfg(unix)'.dependencies]
+libc = "0.2"
+"#;
+        let deps = parse_manifest("Cargo.toml", contents).unwrap();
+        assert_eq!(deps.runtime, vec!["anyhow", "libc", "serde"]);
+        assert_eq!(deps.dev, vec!["tempfile"]);
+    }
+
+    #[test]
+    fn test_parse_package_json() {
+        let contents = r#"{"dependencies": {"react": "^18"}, "devDependencies": {"vite": "^5"}}"#;
+        let deps = parse_manifest("package.json", contents).unwrap();
+        assert_eq!(deps.runtime, vec!["react"]);
+        assert_eq!(deps.dev, vec!["vite"]);
+        assert!(parse_manifest("package.json", "not json").is_none());
+    }
+
+    #[test]
+    fn test_parse_go_mod() {
+        let contents = "module example.com/demo\n\nrequire github.com/spf13/cobra v1.8.0\n\nrequire (\n\tgolang.org/x/sync v0.7.0\n\tgithub.com/inconshreveable/mousetrap v1.1.0 // indirect\n)\n";
+        let deps = parse_manifest("go.mod", contents).unwrap();
+        assert_eq!(
+            deps.runtime,
+            vec!["github.com/spf13/cobra", "golang.org/x/sync"]
+        );
+    }
+
+    #[test]
+    fn test_parse_python_manifests() {
+        let requirements =
+            "# pinned\nrequests>=2.31\n-r base.txt\nDjango==5.0 ; python_version > '3.10'\n";
+        let deps = parse_manifest("requirements.txt", requirements).unwrap();
+        assert_eq!(deps.runtime, vec!["Django", "requests"]);
+        assert_eq!(
+            parse_manifest("requirements-dev.txt", "pytest\n")
+                .unwrap()
+                .dev,
+            vec!["pytest"]
+        );
+
+        let pyproject = r#"
+[project]
+dependencies = ["httpx>=0.27", "pydantic[email]"]
+
+[project.optional-dependencies]
+test = ["pytest"]
+
+[tool.poetry.dependencies]
+python = "^3.11"
+rich = "^13"
+"#;
+        let deps = parse_manifest("pyproject.toml", pyproject).unwrap();
+        assert_eq!(deps.runtime, vec!["httpx", "pydantic", "rich"]);
+        assert_eq!(deps.dev, vec!["pytest"]);
+    }
+
+    #[test]
+    fn test_dependency_facts() {
+        let dir = TempDir::new().unwrap();
+        fs::create_dir_all(dir.path().join("web")).unwrap();
+        fs::write(
+            dir.path().join("web/package.json"),
+            r#"{"dependencies": {"react": "^18"}}"#,
+        )
+        .unwrap();
+        fs::write(
+            dir.path().join("Cargo.toml"),
+            "[package]\nname = \"demo\"\n",
+        )
+        .unwrap();
+        let files = vec!["Cargo.toml".to_string(), "web/package.json".to_string()];
+
+        let arfs = dependency_facts(dir.path(), &files, &NeverSend::default());
+        assert_eq!(arfs.len(), 1);
+        assert_eq!(arfs[0].what, "Dependencies declared in web/package.json");
+        assert_eq!(arfs[0].context.files, vec!["web/package.json"]);
+        assert_eq!(arfs[0].context.dependencies, vec!["react"]);
+    }
+
+    #[test]
+    fn test_ownership_facts() {
+        let history = vec![
+            commit("Alice", &["src/a.rs", "src/b.rs"], None),
+            commit("Bob", &["src/a.rs", "README.md"], None),
+            commit("Alice", &["src/c.rs"], None),
+            commit("Carol", &["docs/guide.md"], None),
+        ];
+
+        let arfs = ownership_facts(&history, 1);
+        assert_eq!(arfs.len(), 3);
+        assert_eq!(arfs[0].what, "Ownership of src/");
+        assert_eq!(arfs[0].context.people, vec!["Alice"]);
+        assert_eq!(arfs[0].context.files, vec!["src/"]);
+        assert!(arfs[0].how.contains("- Alice: 2 commits (67%)"));
+        assert_eq!(arfs[1].what, "Ownership of top-level files");
+        assert!(arfs[1].context.files.is_empty());
+        assert_eq!(arfs[2].what, "Ownership of docs/");
+    }
+
+    #[test]
+    fn test_significance_index() {
+        let history = vec![
+            commit("Al", &["a.rs"], Some(0.45)),
+            commit("Bea", &["b.rs"], Some(0.1)),
+            commit("Cyd", &["c.rs"], Some(0.9)),
+            commit("Dana", &["d.rs"], None),
+        ];
+
+        let arf = significance_index(&history).unwrap();
+        let lines: Vec<&str> = arf.how.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("- 3000000 Critical (0.90)"));
+        assert!(lines[1].starts_with("- 2000000 Medium (0.45)"));
+        assert_eq!(arf.context.commits.len(), 2);
+
+        assert!(significance_index(&history[1..2]).is_none());
+    }
+}
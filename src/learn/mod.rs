@@ -1,3 +1,16 @@
+pub mod budget;
+pub mod checkpoint;
+pub mod encoding;
+pub mod events;
+pub mod generated;
+pub mod glossary;
+pub mod history;
+pub mod language;
+pub mod ledger;
+pub mod lite;
 pub mod prompts;
 pub mod scanner;
+pub mod stack;
+pub mod survey;
+pub mod tokens;
 pub mod writer;
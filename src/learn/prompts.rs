@@ -2,85 +2,146 @@
 //!
 //! Generates structured prompts that instruct models to output
 //! findings in TOML ARF format for parsing by the synthesis pipeline.
+//!
+//! File contents are sized in estimated tokens against a
+//! [`PromptBudget`]: each file is cut to `max_file_tokens`, and file
+//! batches that don't fit in one prompt are split across several.
 
+use crate::arf::ArfFile;
+use crate::git::releases::{ReleaseGroup, RELEASE_KEY};
 use crate::git::walker::CommitMetadata;
+use crate::learn::encoding::{decode, read_prefix};
+use crate::learn::glossary::{Term, GLOSSARY_PROMPT, GLOSSARY_TAG, TERM_KEY};
 use crate::learn::scanner::FileToAnalyze;
-use std::fs;
+use crate::learn::tokens::{count_tokens, token_prefix, PromptBudget, RESERVED_TOKENS};
+use crate::policy::{placeholder, NeverSend};
+use serde::Serialize;
+use std::collections::HashMap;
 use std::path::Path;
 
-/// Maximum lines to include per file in prompts
-const MAX_LINES_PER_FILE: usize = 200;
-
-/// Maximum files to include in a single prompt
-const MAX_FILES_PER_PROMPT: usize = 50;
-
-/// Build a prompt for analyzing source files.
-///
-/// Includes file paths and truncated contents, asks the model to
-/// identify patterns, conventions, architecture decisions, and facts.
-pub fn build_file_analysis_prompt(repo_path: &Path, files: &[FileToAnalyze]) -> String {
-    let mut prompt = String::from(
-        "Analyze the following source files from a codebase. \
-         Identify architectural patterns, coding conventions, error handling \
-         approaches, testing strategies, and notable design decisions.\n\n\
-         Output your findings as TOML entries using this exact format:\n\n\
-         ```\n\
-         [[entry]]\n\
-         what = \"one-sentence description of the finding\"\n\
-         why = \"reasoning and motivation behind this pattern or decision\"\n\
-         how = \"how it's implemented, key files, and relevant details\"\n\n\
-         [entry.context]\n\
-         files = [\"path/to/file.rs\"]\n\
-         dependencies = [\"crate-name\"]\n\
-         ```\n\n\
-         Include multiple [[entry]] blocks. Focus on findings that would help \
-         a developer understand the codebase architecture and conventions.\n\n\
-         --- FILES ---\n\n",
-    );
+/// Prompt types learn sends, which name the responses to them
+pub const FILES_PROMPT: &str = "files";
+pub const COMMITS_PROMPT: &str = "commits";
+pub const PATTERNS_PROMPT: &str = "patterns";
+pub const RELEASES_PROMPT: &str = "releases";
+pub const PROMPT_TYPES: &[&str] =
+    &[FILES_PROMPT, COMMITS_PROMPT, PATTERNS_PROMPT, RELEASES_PROMPT, GLOSSARY_PROMPT];
+
+/// Tokens allowed for a file section's header and truncation note
+const FILE_OVERHEAD_TOKENS: usize = 64;
+
+/// Room a file needs in a full prompt to be worth including at all
+const MIN_SECTION_TOKENS: usize = 2 * FILE_OVERHEAD_TOKENS;
+
+const FILE_ANALYSIS_HEADER: &str = "Analyze the following source files from a codebase. \
+     Identify architectural patterns, coding conventions, error handling \
+     approaches, testing strategies, and notable design decisions.\n\n\
+     Output your findings as TOML entries using this exact format:\n\n\
+     ```\n\
+     [[entry]]\n\
+     what = \"one-sentence description of the finding\"\n\
+     why = \"reasoning and motivation behind this pattern or decision\"\n\
+     how = \"how it's implemented, key files, and relevant details\"\n\
+     tags = [\"short-topic-label\"]\n\n\
+     [entry.context]\n\
+     files = [\"path/to/file.rs\"]\n\
+     dependencies = [\"crate-name\"]\n\
+     ```\n\n\
+     Include multiple [[entry]] blocks. Focus on findings that would help \
+     a developer understand the codebase architecture and conventions.\n\n\
+     --- FILES ---\n\n";
+
+/// How much source was cut from a prompt to fit the token budget
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct TruncationStats {
+    /// Files left out entirely because the prompt was full
+    pub files_omitted: usize,
+    /// Files cut short by the per-file token limit
+    pub files_truncated: usize,
+    /// Files that could not be read and were sent without contents
+    pub files_unreadable: usize,
+    /// Lines removed from truncated files
+    pub lines_dropped: usize,
+    /// Bytes removed from truncated files plus the size of omitted files
+    pub bytes_dropped: u64,
+}
 
-    let limit = files.len().min(MAX_FILES_PER_PROMPT);
-
-    for file in &files[..limit] {
-        let full_path = repo_path.join(&file.path);
-        prompt.push_str(&format!("=== {} ({} bytes) ===\n", file.path, file.size));
-
-        if let Ok(contents) = fs::read_to_string(&full_path) {
-            let truncated: String = contents
-                .lines()
-                .take(MAX_LINES_PER_FILE)
-                .collect::<Vec<_>>()
-                .join("\n");
-            prompt.push_str(&truncated);
-
-            let line_count = contents.lines().count();
-            if line_count > MAX_LINES_PER_FILE {
-                prompt.push_str(&format!(
-                    "\n... ({} more lines truncated)\n",
-                    line_count - MAX_LINES_PER_FILE
-                ));
-            }
-        } else {
-            prompt.push_str("(unable to read file)\n");
-        }
+impl TruncationStats {
+    /// True when anything was dropped from the prompt
+    pub fn is_truncated(&self) -> bool {
+        self.files_omitted > 0 || self.files_truncated > 0 || self.files_unreadable > 0
+    }
 
-        prompt.push_str("\n\n");
+    fn merge(&mut self, other: TruncationStats) {
+        self.files_omitted += other.files_omitted;
+        self.files_truncated += other.files_truncated;
+        self.files_unreadable += other.files_unreadable;
+        self.lines_dropped += other.lines_dropped;
+        self.bytes_dropped += other.bytes_dropped;
     }
+}
 
-    if files.len() > MAX_FILES_PER_PROMPT {
-        prompt.push_str(&format!(
-            "({} more files not shown)\n",
-            files.len() - MAX_FILES_PER_PROMPT
-        ));
+/// A file-analysis prompt covering one batch of files
+#[derive(Debug, Clone)]
+pub struct FilePrompt {
+    pub prompt: String,
+    pub truncation: TruncationStats,
+    /// Paths of the files in this batch
+    pub files: Vec<String>,
+}
+
+/// Build prompts for analyzing source files.
+///
+/// Includes file paths and contents, and asks the model to identify
+/// patterns, conventions, architecture decisions, and facts. Files are
+/// packed into as few prompts as fit `budget`, in order. Files matching
+/// `never_send` are listed without their contents.
+pub fn build_file_analysis_prompts(
+    repo_path: &Path,
+    files: &[FileToAnalyze],
+    never_send: &NeverSend,
+    budget: &PromptBudget,
+) -> Vec<FilePrompt> {
+    let available = budget
+        .max_tokens
+        .saturating_sub(count_tokens(FILE_ANALYSIS_HEADER) + RESERVED_TOKENS);
+    let mut batches: Vec<FilePrompt> = Vec::new();
+    let mut used = 0;
+
+    for file in files {
+        let (section, stats) = render_file(repo_path, file, never_send, budget, available);
+        let tokens = count_tokens(&section);
+
+        if batches.is_empty() || used + tokens > available {
+            used = 0;
+            batches.push(FilePrompt {
+                prompt: FILE_ANALYSIS_HEADER.to_string(),
+                truncation: TruncationStats::default(),
+                files: Vec::new(),
+            });
+        }
+        let batch = batches.last_mut().expect("a batch was pushed above");
+        batch.prompt.push_str(&section);
+        batch.truncation.merge(stats);
+        batch.files.push(file.path.clone());
+        used += tokens;
     }
 
-    prompt
+    batches
 }
 
 /// Build a prompt for analyzing git commit history.
 ///
 /// Includes commit metadata (hash, message, diff stats) and asks
 /// the model to identify decisions, migrations, and notable fixes.
-pub fn build_commit_analysis_prompt(commits: &[CommitMetadata]) -> String {
+/// Commits with an entry in `diffs` (by full hash; see
+/// [`crate::git::diff::commit_patch`]) also get their diff, as long as it
+/// fits `budget`; later diffs that don't fit are left out.
+pub fn build_commit_analysis_prompt(
+    commits: &[CommitMetadata],
+    diffs: &HashMap<String, String>,
+    budget: &PromptBudget,
+) -> String {
     let mut prompt = String::from(
         "Analyze the following git commits from a codebase. \
          Identify architectural decisions, migrations, notable bug fixes, \
@@ -89,30 +150,114 @@ pub fn build_commit_analysis_prompt(commits: &[CommitMetadata]) -> String {
          ```\n\
          [[entry]]\n\
          what = \"one-sentence description of the decision or change\"\n\
-         why = \"inferred reasoning based on commit message and context\"\n\
-         how = \"what was changed and how it was implemented\"\n\n\
+         why = \"inferred reasoning based on commit message, diff, and context\"\n\
+         how = \"what was changed and how it was implemented\"\n\
+         tags = [\"short-topic-label\"]\n\n\
          [entry.context]\n\
          commits = [\"abc1234\"]\n\
          files = [\"affected/files.rs\"]\n\
          ```\n\n\
          Focus on commits that represent important decisions, breaking changes, \
-         migrations, or lessons learned. Skip trivial commits.\n\n\
+         migrations, or lessons learned. Skip trivial commits. Where a diff is \
+         included, base why and how on the actual changes.\n\n\
          --- COMMITS ---\n\n",
     );
+    push_commits(&mut prompt, commits, diffs, budget);
+    prompt
+}
+
+/// Build a prompt for the commits first released in `group`, asking for
+/// the migrations and decisions that make up the release as a whole.
+/// Diffs are included as in [`build_commit_analysis_prompt`].
+pub fn build_release_analysis_prompt(
+    group: &ReleaseGroup,
+    diffs: &HashMap<String, String>,
+    budget: &PromptBudget,
+) -> String {
+    let mut prompt = format!(
+        "Analyze the following git commits, which make up one release of a \
+         codebase: {}. Describe the release as a whole rather than commit by \
+         commit: the migrations someone upgrading across it has to make \
+         (breaking changes, renamed or removed APIs, schema and config changes) \
+         and the architectural decisions it embodies.\n\n",
+        group.title()
+    );
+    // Unreleased work gets no release key, which would go stale once tagged
+    let release_line = match &group.release {
+        Some(release) => {
+            prompt.push_str(&format!(
+                "Name the release in each entry's what, e.g. \"{} replaces ...\".\n\n",
+                release.tag
+            ));
+            if let Some(message) = &release.message {
+                prompt.push_str(&format!("The {} tag reads: {}\n\n", release.tag, message));
+            }
+            format!("{} = \"{}\"\n", RELEASE_KEY, release.tag)
+        }
+        None => String::new(),
+    };
+    prompt.push_str(&format!(
+        "Output your findings as TOML entries using this exact format:\n\n\
+         ```\n\
+         [[entry]]\n\
+         what = \"one-sentence description of the migration or decision\"\n\
+         why = \"inferred reasoning based on the commit messages, diffs, and context\"\n\
+         how = \"what changed across the release and how to adapt to it\"\n\
+         tags = [\"short-topic-label\"]\n\n\
+         [entry.context]\n\
+         commits = [\"abc1234\"]\n\
+         files = [\"affected/files.rs\"]\n\
+         {}\
+         ```\n\n\
+         Skip trivial changes. Where a diff is included, base why and how on \
+         the actual changes.\n\n\
+         --- COMMITS ---\n\n",
+        release_line
+    ));
+    push_commits(&mut prompt, &group.commits, diffs, budget);
+    prompt
+}
+
+/// Append a section per commit to `prompt`, with its diff from `diffs`
+/// while the prompt's token budget allows
+fn push_commits(
+    prompt: &mut String,
+    commits: &[CommitMetadata],
+    diffs: &HashMap<String, String>,
+    budget: &PromptBudget,
+) {
+    let available = budget.max_tokens.saturating_sub(RESERVED_TOKENS);
+    let mut used = count_tokens(prompt);
 
     for commit in commits {
-        prompt.push_str(&format!(
-            "commit {} ({})\n  {}\n  {} files changed, +{} -{}\n\n",
+        let branch = commit
+            .branch
+            .as_ref()
+            .map(|b| format!(" [{}]", b))
+            .unwrap_or_default();
+        let header = format!(
+            "commit {}{} ({})\n  {}\n  {} files changed, +{} -{}\n\n",
             &commit.short_hash,
+            branch,
             commit.author,
             commit.message_summary,
             commit.files_changed,
             commit.insertions,
             commit.deletions,
-        ));
+        );
+        used += count_tokens(&header);
+        prompt.push_str(&header);
+
+        let Some(diff) = diffs.get(&commit.hash) else {
+            continue;
+        };
+        let section = format!("```diff\n{}```\n\n", diff);
+        let tokens = count_tokens(&section);
+        if used + tokens <= available {
+            prompt.push_str(&section);
+            used += tokens;
+        }
     }
-
-    prompt
 }
 
 /// Build a prompt for re-analyzing invalidated patterns.
@@ -120,11 +265,16 @@ pub fn build_commit_analysis_prompt(commits: &[CommitMetadata]) -> String {
 /// Takes the names of patterns that need re-analysis and the files
 /// that contribute to those patterns. Asks models to re-evaluate
 /// whether the patterns still hold given the updated file contents.
+/// Files matching `never_send` are listed without their contents.
+/// Files that don't fit in `budget` are left out and counted as omitted.
+/// Returns the prompt along with what was cut to fit.
 pub fn build_pattern_reanalysis_prompt(
     repo_path: &Path,
     pattern_ids: &[String],
     files: &[FileToAnalyze],
-) -> String {
+    never_send: &NeverSend,
+    budget: &PromptBudget,
+) -> (String, TruncationStats) {
     let mut prompt = String::from(
         "The following codebase patterns were previously identified but the \
          files they reference have changed. Re-analyze the files below and \
@@ -135,7 +285,8 @@ pub fn build_pattern_reanalysis_prompt(
          [[entry]]\n\
          what = \"one-sentence description of the pattern\"\n\
          why = \"reasoning, noting any changes from the previous pattern\"\n\
-         how = \"current implementation approach based on the updated files\"\n\n\
+         how = \"current implementation approach based on the updated files\"\n\
+         tags = [\"short-topic-label\"]\n\n\
          [entry.context]\n\
          files = [\"path/to/file.rs\"]\n\
          ```\n\n\
@@ -147,43 +298,228 @@ pub fn build_pattern_reanalysis_prompt(
     for id in pattern_ids {
         prompt.push_str(&format!("- {}\n", id));
     }
-    prompt.push_str("\n");
+    prompt.push('\n');
 
     prompt.push_str("--- CONTRIBUTING FILES ---\n\n");
+    let stats = push_files(&mut prompt, repo_path, files, never_send, budget);
+
+    (prompt, stats)
+}
+
+/// Files listed per glossary term; the rest are counted
+const GLOSSARY_FILES_SHOWN: usize = 3;
+
+/// Build a prompt asking for glossary definitions of mined `terms`.
+///
+/// Lists each term with its kind, where it is declared or spelled out,
+/// any doc comment, and the files that use it. Models are asked to skip
+/// generic vocabulary and to tag what they define as glossary entries.
+pub fn build_glossary_prompt(terms: &[Term]) -> String {
+    let mut prompt = format!(
+        "The following terms recur throughout a codebase: core type names, \
+         internal acronyms, and domain vocabulary. Write a glossary entry for \
+         each term a newcomer would need explained, defining it as this \
+         codebase uses it. Skip generic programming vocabulary.\n\n\
+         Output your definitions as TOML entries using this exact format:\n\n\
+         ```\n\
+         [[entry]]\n\
+         what = \"Term: one-sentence definition\"\n\
+         why = \"why the term matters in this codebase\"\n\
+         how = \"where and how it is used, with key files\"\n\
+         tags = [\"{tag}\"]\n\n\
+         [entry.context]\n\
+         {key} = \"Term\"\n\
+         files = [\"path/to/file.rs\"]\n\
+         ```\n\n\
+         --- TERMS ---\n\n",
+        tag = GLOSSARY_TAG,
+        key = TERM_KEY,
+    );
+
+    for term in terms {
+        let source = match &term.source {
+            Some((path, line)) => format!(", see {}:{}", path, line),
+            None => String::new(),
+        };
+        let mut files: Vec<&str> = term
+            .files
+            .iter()
+            .take(GLOSSARY_FILES_SHOWN)
+            .map(String::as_str)
+            .collect();
+        let more = term.files.len().saturating_sub(GLOSSARY_FILES_SHOWN);
+        let more_note = format!("{} more", more);
+        if more > 0 {
+            files.push(&more_note);
+        }
+        prompt.push_str(&format!(
+            "- {} ({}{}; used in {} files: {})\n",
+            term.name,
+            term.kind.label(),
+            source,
+            term.files.len(),
+            files.join(", ")
+        ));
+        if let Some(definition) = &term.definition {
+            prompt.push_str(&format!("  Source says: {}\n", definition));
+        }
+    }
+
+    prompt
+}
+
+/// Build a prompt asking whether an existing ARF still matches the
+/// current contents of the files it cites
+pub fn build_verification_prompt(
+    repo_path: &Path,
+    arf: &ArfFile,
+    files: &[FileToAnalyze],
+    never_send: &NeverSend,
+    budget: &PromptBudget,
+) -> (String, TruncationStats) {
+    let mut prompt = String::from(
+        "The following knowledge entry was recorded about this codebase \
+         earlier. Check it against the current code below and decide \
+         whether it is still accurate.\n\n\
+         Answer in TOML using this exact format:\n\n\
+         ```\n\
+         verdict = \"accurate\"  # or \"inaccurate\" or \"unsure\"\n\
+         reason = \"one or two sentences pointing at the relevant code\"\n\n\
+         [correction]\n\
+         what = \"corrected one-sentence description\"\n\
+         why = \"corrected reasoning\"\n\
+         how = \"corrected implementation details\"\n\
+         ```\n\n\
+         Include [correction] only for an inaccurate entry, with just the \
+         fields that should change. Answer unsure if the code shown is not \
+         enough to tell.\n\n",
+    );
+
+    prompt.push_str("--- ENTRY ---\n\n");
+    prompt.push_str(&format!("What: {}\nWhy: {}\nHow: {}\n\n", arf.what, arf.why, arf.how));
+
+    prompt.push_str("--- CURRENT CODE ---\n\n");
+    if files.is_empty() {
+        prompt.push_str("(No files are linked to this entry.)\n");
+        return (prompt, TruncationStats::default());
+    }
+    let stats = push_files(&mut prompt, repo_path, files, never_send, budget);
 
-    let limit = files.len().min(MAX_FILES_PER_PROMPT);
-    for file in &files[..limit] {
-        let full_path = repo_path.join(&file.path);
-        prompt.push_str(&format!("=== {} ({} bytes) ===\n", file.path, file.size));
-
-        if let Ok(contents) = fs::read_to_string(&full_path) {
-            let truncated: String = contents
-                .lines()
-                .take(MAX_LINES_PER_FILE)
-                .collect::<Vec<_>>()
-                .join("\n");
-            prompt.push_str(&truncated);
-
-            let line_count = contents.lines().count();
-            if line_count > MAX_LINES_PER_FILE {
-                prompt.push_str(&format!(
-                    "\n... ({} more lines truncated)\n",
-                    line_count - MAX_LINES_PER_FILE
+    (prompt, stats)
+}
+
+/// Append file sections to `prompt` until its token budget runs out,
+/// noting how many files were left out
+fn push_files(
+    prompt: &mut String,
+    repo_path: &Path,
+    files: &[FileToAnalyze],
+    never_send: &NeverSend,
+    budget: &PromptBudget,
+) -> TruncationStats {
+    let mut stats = TruncationStats::default();
+    let mut available = budget
+        .max_tokens
+        .saturating_sub(count_tokens(prompt) + RESERVED_TOKENS);
+
+    for (idx, file) in files.iter().enumerate() {
+        let (section, file_stats) = render_file(repo_path, file, never_send, budget, available);
+        let tokens = count_tokens(&section);
+        if available < MIN_SECTION_TOKENS || tokens > available {
+            stats.files_omitted += files.len() - idx;
+            stats.bytes_dropped += files[idx..].iter().map(|f| f.size).sum::<u64>();
+            prompt.push_str(&format!("({} more files not shown)\n", files.len() - idx));
+            break;
+        }
+        prompt.push_str(&section);
+        stats.merge(file_stats);
+        available -= tokens;
+    }
+
+    stats
+}
+
+/// Render one file's section: a header line and its contents, cut (at a
+/// line boundary where possible) to `budget.max_file_tokens` and so the
+/// whole section fits in `max_section_tokens`. Files matching
+/// `never_send` get a placeholder and are never read. Only the first
+/// `budget.max_file_bytes` are read; contents that aren't plain UTF-8 are
+/// decoded (see [`crate::learn::encoding`]) with a note saying how.
+fn render_file(
+    repo_path: &Path,
+    file: &FileToAnalyze,
+    never_send: &NeverSend,
+    budget: &PromptBudget,
+    max_section_tokens: usize,
+) -> (String, TruncationStats) {
+    let mut stats = TruncationStats::default();
+    let mut section = format!("=== {} ({} bytes) ===\n", file.path, file.size);
+    let max_tokens = budget
+        .max_file_tokens
+        .min(max_section_tokens.saturating_sub(count_tokens(&section) + FILE_OVERHEAD_TOKENS));
+
+    if never_send.matches(&file.path) {
+        section.push_str(&placeholder(&file.path));
+        section.push_str("\n\n");
+        return (section, stats);
+    }
+
+    match read_prefix(&repo_path.join(&file.path), budget.max_file_bytes) {
+        Ok((bytes, complete)) => {
+            let decoded = decode(&bytes, complete);
+            if let Some(note) = decoded.note() {
+                section.push_str(&note);
+                section.push('\n');
+            }
+            if !complete {
+                section.push_str(&format!(
+                    "(only the first {} bytes were read)\n",
+                    budget.max_file_bytes
                 ));
+                stats.files_truncated += 1;
+                stats.bytes_dropped += file.size.saturating_sub(bytes.len() as u64);
             }
-        } else {
-            prompt.push_str("(unable to read file)\n");
-        }
 
-        prompt.push_str("\n\n");
+            let contents = decoded.text;
+            let end = token_prefix(&contents, max_tokens);
+            if end == contents.len() {
+                section.push_str(&contents);
+            } else {
+                let cut = &contents[..end];
+                let kept = match cut.rfind('\n') {
+                    Some(newline) => {
+                        let rest = contents[newline + 1..].lines().count();
+                        section.push_str(&contents[..newline]);
+                        section.push_str(&format!("\n... ({} more lines truncated)\n", rest));
+                        stats.lines_dropped += rest;
+                        newline + 1
+                    }
+                    None => {
+                        section.push_str(cut);
+                        section.push_str(&format!("\n... (truncated at {} tokens)\n", max_tokens));
+                        cut.len()
+                    }
+                };
+                if complete {
+                    stats.files_truncated += 1;
+                }
+                stats.bytes_dropped += (contents.len() - kept) as u64;
+            }
+        }
+        Err(_) => {
+            section.push_str("(unable to read file)\n");
+            stats.files_unreadable += 1;
+        }
     }
 
-    prompt
+    section.push_str("\n\n");
+    (section, stats)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::fs;
     use tempfile::TempDir;
 
     fn make_file(path: &str, hash: &str, size: u64) -> FileToAnalyze {
@@ -208,6 +544,29 @@ mod tests {
             insertions: 42,
             deletions: 10,
             parent_hashes: vec![],
+            shallow_boundary: false,
+            branch: None,
+        }
+    }
+
+    /// Build file prompts and expect them to fit in one
+    fn single_prompt(
+        repo_path: &Path,
+        files: &[FileToAnalyze],
+        never_send: &NeverSend,
+        budget: &PromptBudget,
+    ) -> (String, TruncationStats) {
+        let mut prompts = build_file_analysis_prompts(repo_path, files, never_send, budget);
+        assert_eq!(prompts.len(), 1);
+        let prompt = prompts.remove(0);
+        (prompt.prompt, prompt.truncation)
+    }
+
+    fn small_files(max_file_tokens: usize) -> PromptBudget {
+        PromptBudget {
+            max_tokens: 100_000,
+            max_file_tokens,
+            max_file_bytes: 1024 * 1024,
         }
     }
 
@@ -217,7 +576,7 @@ mod tests {
         fs::write(temp_dir.path().join("main.rs"), "fn main() {}").unwrap();
 
         let files = vec![make_file("main.rs", "abc123", 12)];
-        let prompt = build_file_analysis_prompt(temp_dir.path(), &files);
+        let (prompt, _) = single_prompt(temp_dir.path(), &files, &NeverSend::default(), &PromptBudget::default());
 
         assert!(prompt.contains("[[entry]]"));
         assert!(prompt.contains("what ="));
@@ -232,29 +591,99 @@ mod tests {
         fs::write(temp_dir.path().join("main.rs"), "fn main() {\n    println!(\"hello\");\n}").unwrap();
 
         let files = vec![make_file("main.rs", "abc123", 40)];
-        let prompt = build_file_analysis_prompt(temp_dir.path(), &files);
+        let (prompt, _) = single_prompt(temp_dir.path(), &files, &NeverSend::default(), &PromptBudget::default());
 
         assert!(prompt.contains("fn main()"));
         assert!(prompt.contains("println!"));
     }
 
     #[test]
-    fn test_file_analysis_prompt_truncates_long_files() {
+    fn test_file_analysis_prompt_reports_truncation() {
         let temp_dir = TempDir::new().unwrap();
 
-        let long_content: String = (0..500)
-            .map(|i| format!("line {}\n", i))
-            .collect();
+        // Three tokens per line
+        let long_content: String = (0..250).map(|i| format!("line {}\n", i)).collect();
         fs::write(temp_dir.path().join("big.rs"), &long_content).unwrap();
+        fs::write(temp_dir.path().join("small.rs"), "fn small() {}").unwrap();
+
+        let files = vec![
+            make_file("big.rs", "abc", long_content.len() as u64),
+            make_file("small.rs", "def", 13),
+            make_file("missing.rs", "ghi", 10),
+        ];
+
+        let (prompt, stats) = single_prompt(temp_dir.path(), &files, &NeverSend::default(), &small_files(300));
+
+        assert!(prompt.contains("line 99\n... (150 more lines truncated)"));
+        assert!(stats.is_truncated());
+        assert_eq!(stats.files_truncated, 1);
+        assert_eq!(stats.lines_dropped, 150);
+        assert_eq!(stats.files_unreadable, 1);
+        assert_eq!(stats.files_omitted, 0);
+        let dropped_lines: u64 = (100..250).map(|i| format!("line {}\n", i).len() as u64).sum();
+        assert_eq!(stats.bytes_dropped, dropped_lines);
+    }
+
+    #[test]
+    fn test_file_analysis_prompt_decodes_legacy_and_huge_files() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(temp_dir.path().join("legacy.c"), b"/* caf\xE9 cr\xE8me */\n").unwrap();
+        let mut utf16 = vec![0xFF, 0xFE];
+        utf16.extend("Hello".encode_utf16().flat_map(u16::to_le_bytes));
+        fs::write(temp_dir.path().join("notes.txt"), &utf16).unwrap();
+        fs::write(temp_dir.path().join("dump.sql"), "x".repeat(5_000)).unwrap();
+
+        let files = vec![
+            make_file("legacy.c", "abc", 23),
+            make_file("notes.txt", "def", utf16.len() as u64),
+            make_file("dump.sql", "ghi", 5_000),
+        ];
+        let budget = PromptBudget {
+            max_file_bytes: 1_000,
+            ..small_files(8_000)
+        };
+        let (prompt, stats) = single_prompt(temp_dir.path(), &files, &NeverSend::default(), &budget);
+
+        assert!(prompt.contains("(decoded from Windows-1252)\n/* café crème */"));
+        assert!(prompt.contains("(decoded from UTF-16LE)\nHello"));
+        assert!(prompt.contains("(only the first 1000 bytes were read)"));
+        assert!(!prompt.contains("unable to read"));
+        assert_eq!(stats.files_unreadable, 0);
+        assert_eq!(stats.files_truncated, 1);
+        assert_eq!(stats.bytes_dropped, 4_000);
+    }
+
+    #[test]
+    fn test_file_analysis_prompt_cuts_long_lines_on_char_boundary() {
+        let temp_dir = TempDir::new().unwrap();
+
+        // One huge line of multi-byte characters
+        let line = "日本".repeat(10_000);
+        fs::write(temp_dir.path().join("min.js"), &line).unwrap();
 
-        let files = vec![make_file("big.rs", "abc123", long_content.len() as u64)];
-        let prompt = build_file_analysis_prompt(temp_dir.path(), &files);
+        let files = vec![make_file("min.js", "abc", line.len() as u64)];
+        let (prompt, stats) = single_prompt(temp_dir.path(), &files, &NeverSend::default(), &small_files(1_000));
 
-        assert!(prompt.contains("more lines truncated"));
+        assert!(prompt.contains("truncated at 1000 tokens"));
+        assert_eq!(stats.files_truncated, 1);
+        assert_eq!(stats.lines_dropped, 0);
+        assert_eq!(stats.bytes_dropped, (line.len() - 3_000) as u64);
     }
 
     #[test]
-    fn test_file_analysis_prompt_limits_file_count() {
+    fn test_file_analysis_prompt_no_truncation() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(temp_dir.path().join("main.rs"), "fn main() {}").unwrap();
+
+        let files = vec![make_file("main.rs", "abc123", 12)];
+        let (_, stats) = single_prompt(temp_dir.path(), &files, &NeverSend::default(), &PromptBudget::default());
+
+        assert_eq!(stats, TruncationStats::default());
+        assert!(!stats.is_truncated());
+    }
+
+    #[test]
+    fn test_file_analysis_prompts_split_to_fit_budget() {
         let temp_dir = TempDir::new().unwrap();
 
         let mut files = Vec::new();
@@ -263,16 +692,28 @@ mod tests {
             fs::write(temp_dir.path().join(&name), "content").unwrap();
             files.push(make_file(&name, "abc", 7));
         }
-
-        let prompt = build_file_analysis_prompt(temp_dir.path(), &files);
-
-        assert!(prompt.contains("more files not shown"));
+        let budget = PromptBudget {
+            max_tokens: count_tokens(FILE_ANALYSIS_HEADER) + RESERVED_TOKENS + 200,
+            max_file_tokens: 8_000,
+            max_file_bytes: 1024 * 1024,
+        };
+
+        let prompts = build_file_analysis_prompts(temp_dir.path(), &files, &NeverSend::default(), &budget);
+
+        assert!(prompts.len() > 1);
+        let covered: Vec<String> = prompts.iter().flat_map(|p| p.files.clone()).collect();
+        let expected: Vec<String> = files.iter().map(|f| f.path.clone()).collect();
+        assert_eq!(covered, expected);
+        for prompt in &prompts {
+            assert!(count_tokens(&prompt.prompt) + RESERVED_TOKENS <= budget.max_tokens);
+            assert!(!prompt.truncation.is_truncated());
+        }
     }
 
     #[test]
     fn test_commit_analysis_prompt_contains_format_instructions() {
         let commits = vec![make_commit("abc1234def", "Add authentication module")];
-        let prompt = build_commit_analysis_prompt(&commits);
+        let prompt = build_commit_analysis_prompt(&commits, &HashMap::new(), &PromptBudget::default());
 
         assert!(prompt.contains("[[entry]]"));
         assert!(prompt.contains("abc1234"));
@@ -286,12 +727,59 @@ mod tests {
             make_commit("abc1234def", "Refactor database layer"),
             make_commit("def5678abc", "Fix auth bypass vulnerability"),
         ];
-        let prompt = build_commit_analysis_prompt(&commits);
+        let prompt = build_commit_analysis_prompt(&commits, &HashMap::new(), &PromptBudget::default());
 
         assert!(prompt.contains("Refactor database layer"));
         assert!(prompt.contains("Fix auth bypass vulnerability"));
     }
 
+    #[test]
+    fn test_commit_analysis_prompt_includes_diffs_within_budget() {
+        let commits = vec![
+            make_commit("abc1234def", "Switch to deadpool"),
+            make_commit("def5678abc", "Retry on timeout"),
+        ];
+        let mut diffs = HashMap::new();
+        diffs.insert(
+            "abc1234def".to_string(),
+            "--- src/db.rs (modified)\n@@ -1 +1 @@\n-use r2d2;\n+use deadpool;\n".to_string(),
+        );
+        diffs.insert("def5678abc".to_string(), "+retry\n".repeat(400));
+
+        let prompt = build_commit_analysis_prompt(&commits, &diffs, &PromptBudget::default());
+        assert!(prompt.contains("```diff\n--- src/db.rs (modified)"));
+        assert!(prompt.contains("+use deadpool;"));
+        assert!(prompt.contains("+retry"));
+
+        let budget = PromptBudget {
+            max_tokens: count_tokens(&prompt) - 100,
+            ..PromptBudget::default()
+        };
+        let prompt = build_commit_analysis_prompt(&commits, &diffs, &budget);
+        assert!(prompt.contains("+use deadpool;"));
+        assert!(!prompt.contains("+retry"));
+        assert!(prompt.contains("Retry on timeout"));
+    }
+
+    #[test]
+    fn test_release_analysis_prompt_names_the_release() {
+        let group = ReleaseGroup {
+            previous: Some("v1.2".to_string()),
+            release: Some(crate::git::releases::Release {
+                tag: "v1.3".to_string(),
+                commit: "def5678abc".to_string(),
+                timestamp: 0,
+                message: Some("Connection pooling".to_string()),
+            }),
+            commits: vec![make_commit("abc1234def", "Switch to deadpool")],
+        };
+        let prompt = build_release_analysis_prompt(&group, &HashMap::new(), &PromptBudget::default());
+        assert!(prompt.contains("Changes between v1.2 and v1.3"));
+        assert!(prompt.contains("The v1.3 tag reads: Connection pooling"));
+        assert!(prompt.contains("release = \"v1.3\""));
+        assert!(prompt.contains("Switch to deadpool"));
+    }
+
     #[test]
     fn test_pattern_reanalysis_prompt_includes_patterns_and_files() {
         let temp_dir = TempDir::new().unwrap();
@@ -303,7 +791,13 @@ mod tests {
 
         let patterns = vec!["error-handling".to_string()];
         let files = vec![make_file("errors.rs", "abc123", 50)];
-        let prompt = build_pattern_reanalysis_prompt(temp_dir.path(), &patterns, &files);
+        let (prompt, _) = build_pattern_reanalysis_prompt(
+            temp_dir.path(),
+            &patterns,
+            &files,
+            &NeverSend::default(),
+            &PromptBudget::default(),
+        );
 
         assert!(prompt.contains("PATTERNS TO RE-ANALYZE"));
         assert!(prompt.contains("error-handling"));
@@ -311,4 +805,102 @@ mod tests {
         assert!(prompt.contains("handle_error"));
         assert!(prompt.contains("still hold"));
     }
+
+    #[test]
+    fn test_pattern_reanalysis_prompt_omits_files_past_budget() {
+        let temp_dir = TempDir::new().unwrap();
+        let content = "word ".repeat(400);
+        let mut files = Vec::new();
+        for i in 0..5 {
+            let name = format!("f{}.rs", i);
+            fs::write(temp_dir.path().join(&name), &content).unwrap();
+            files.push(make_file(&name, "abc", content.len() as u64));
+        }
+        let patterns = vec!["naming".to_string()];
+        let (full, _) = build_pattern_reanalysis_prompt(
+            temp_dir.path(),
+            &patterns,
+            &[],
+            &NeverSend::default(),
+            &PromptBudget::default(),
+        );
+        let budget = PromptBudget {
+            max_tokens: count_tokens(&full) + RESERVED_TOKENS + 1_000,
+            max_file_tokens: 8_000,
+            max_file_bytes: 1024 * 1024,
+        };
+
+        let (prompt, stats) =
+            build_pattern_reanalysis_prompt(temp_dir.path(), &patterns, &files, &NeverSend::default(), &budget);
+
+        assert!(count_tokens(&prompt) + RESERVED_TOKENS <= budget.max_tokens + 16);
+        assert!(stats.files_omitted > 0);
+        assert!(prompt.contains(&format!("({} more files not shown)", stats.files_omitted)));
+    }
+
+    #[test]
+    fn test_glossary_prompt_lists_terms() {
+        let terms = vec![Term {
+            name: "Manifest".to_string(),
+            kind: crate::learn::glossary::TermKind::Type,
+            definition: Some("Tracks scanned files".to_string()),
+            source: Some(("src/manifest.rs".to_string(), 12)),
+            files: (1..=5).map(|i| format!("src/f{}.rs", i)).collect(),
+        }];
+
+        let prompt = build_glossary_prompt(&terms);
+        assert!(prompt.contains("tags = [\"glossary\"]"));
+        assert!(prompt.contains("term = \"Term\""));
+        assert!(prompt.contains(
+            "- Manifest (type, see src/manifest.rs:12; used in 5 files: src/f1.rs, src/f2.rs, src/f3.rs, 2 more)"
+        ));
+        assert!(prompt.contains("Source says: Tracks scanned files"));
+    }
+
+    #[test]
+    fn test_verification_prompt_includes_entry_and_code() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(temp_dir.path().join("db.rs"), "fn pool() -> Pool { Pool::new(10) }").unwrap();
+        let arf = ArfFile::new("Connection pooling", "Reduces overhead", "Pool of 10");
+        let budget = PromptBudget::default();
+
+        let files = vec![make_file("db.rs", "abc", 35)];
+        let (prompt, stats) =
+            build_verification_prompt(temp_dir.path(), &arf, &files, &NeverSend::default(), &budget);
+        assert!(prompt.contains("verdict = \"accurate\""));
+        assert!(prompt.contains("What: Connection pooling"));
+        assert!(prompt.contains("Pool::new(10)"));
+        assert!(!stats.is_truncated());
+
+        let (prompt, _) =
+            build_verification_prompt(temp_dir.path(), &arf, &[], &NeverSend::default(), &budget);
+        assert!(prompt.contains("(No files are linked to this entry.)"));
+    }
+
+    #[test]
+    fn test_never_send_files_are_withheld() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::create_dir(temp_dir.path().join("secrets")).unwrap();
+        fs::write(temp_dir.path().join("secrets/prod.toml"), "api_key = \"sk-live-123\"").unwrap();
+        fs::write(temp_dir.path().join("main.rs"), "fn main() {}").unwrap();
+
+        let never_send = NeverSend::new(&["secrets/".to_string()]).unwrap();
+        let files = vec![
+            make_file("secrets/prod.toml", "abc123", 22),
+            make_file("main.rs", "def456", 12),
+        ];
+        let patterns = vec!["config".to_string()];
+        let budget = PromptBudget::default();
+
+        let (file_prompt, stats) = single_prompt(temp_dir.path(), &files, &never_send, &budget);
+        let (pattern_prompt, _) =
+            build_pattern_reanalysis_prompt(temp_dir.path(), &patterns, &files, &never_send, &budget);
+
+        for prompt in [&file_prompt, &pattern_prompt] {
+            assert!(!prompt.contains("sk-live-123"));
+            assert!(prompt.contains(&placeholder("secrets/prod.toml")));
+            assert!(prompt.contains("fn main() {}"));
+        }
+        assert!(!stats.is_truncated());
+    }
 }
@@ -1,11 +1,48 @@
 //! File discovery and hash-based change detection.
 //!
 //! Walks the repository, calculates SHA-256 hashes, and compares against
-//! the manifest to identify files that need analysis.
-
-use crate::manifest::{calculate_file_hash, Manifest};
+//! the manifest to identify files that need analysis. Generated files (see
+//! [`crate::learn::generated`]) go in a separate bucket: tracked for drift,
+//! never analyzed.
+//!
+//! The manifest remembers the hash each file had on every branch it was
+//! analyzed on. After switching branches, a file whose content was already
+//! analyzed on some branch is only re-recorded, and a file missing here
+//! but still tracked on another branch that still exists is not treated
+//! as deleted.
+//!
+//! A non-empty `paths` list restricts the scan to files matching those git
+//! pathspecs (e.g. `services/api` or `*.rs`); tracked files outside them
+//! are left alone rather than reported as deleted.
+//!
+//! A tracked file that disappeared while a new one appeared is reported as
+//! a rename rather than a delete and an add when the two have the same
+//! content, or when git's rename detection pairs them (comparing the tree
+//! of the last processed commit with the working tree), so the manifest
+//! entry and its pattern links can follow the file.
+//!
+//! Changed files with identical content (copies of the same config or
+//! boilerplate across a monorepo) are grouped under one representative, the
+//! first by path, so only it is sent for analysis. Empty and near-empty
+//! files (an `__init__.py`, a `{}`) say nothing about each other and are
+//! never grouped.
+//!
+//! Git submodules, and any other repository nested in the working tree, are
+//! skipped rather than hashed as the parent's files. With
+//! `recurse_submodules`, the files of checked-out submodules are scanned
+//! under the submodule's path, honoring the submodule's own ignore rules.
+
+use crate::learn::encoding::has_utf16_bom;
+use crate::learn::generated::GeneratedFiles;
+use crate::manifest::{
+    calculate_file_hash, hash_contents, normalize_path, FileEntry, Manifest, SubmoduleEntry,
+};
+use crate::policy::NeverSend;
 use anyhow::{Context, Result};
+use git2::{Pathspec, PathspecFlags};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fs;
+use std::io::Read;
 use std::path::Path;
 use walkdir::WalkDir;
 
@@ -24,31 +61,135 @@ pub struct FileToAnalyze {
     pub is_changed: bool,
 }
 
+/// A tracked file found under a new path
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileRename {
+    pub from: String,
+    pub to: String,
+}
+
 /// Result of scanning the repository
 #[derive(Debug)]
 pub struct ScanResult {
     /// Files that need analysis (new or changed)
     pub changed: Vec<FileToAnalyze>,
+    /// New or changed generated files: re-hashed, not analyzed
+    pub generated: Vec<FileToAnalyze>,
+    /// Generated files examined, changed or not
+    pub generated_total: usize,
+    /// Files whose content was already analyzed, on this branch or
+    /// another, that the manifest needs to record for this branch
+    pub restored: Vec<FileToAnalyze>,
     /// Files tracked in manifest but no longer on disk
     pub deleted: Vec<String>,
+    /// Tracked files that moved. The new path is in `restored` if its
+    /// content is unchanged, or in `changed`/`generated` if it was edited.
+    pub renamed: Vec<FileRename>,
+    /// Tracked files missing here that another branch still has
+    pub elsewhere: Vec<String>,
+    /// Changed files whose content matches another changed file's, keyed
+    /// by the representative analyzed on their behalf. All of them stay
+    /// in `changed`.
+    pub duplicates: BTreeMap<String, Vec<String>>,
+    /// Submodules of the repository, keyed by path
+    pub submodules: BTreeMap<String, SubmoduleEntry>,
+    /// Branch checked out, None for a detached or unborn HEAD
+    pub branch: Option<String>,
     /// Number of unchanged files skipped
     pub unchanged: usize,
     /// Total files examined
     pub total: usize,
 }
 
+impl ScanResult {
+    /// Paths of every file in the repository as of this scan: those
+    /// tracked in `manifest` (with renames applied) that weren't deleted,
+    /// plus new ones
+    pub fn current_paths(&self, manifest: &Manifest) -> Vec<String> {
+        let mut paths: Vec<String> = manifest
+            .files
+            .values()
+            .map(|entry| &entry.path)
+            .filter(|path| !self.deleted.contains(path))
+            .chain(self.changed.iter().chain(&self.generated).map(|f| &f.path))
+            .cloned()
+            .collect();
+        paths.sort();
+        paths.dedup();
+        paths
+    }
+
+    /// Changed files to send for analysis: all but the duplicates, whose
+    /// results come from their representative
+    pub fn representatives(&self) -> Vec<FileToAnalyze> {
+        let duplicates: HashSet<&String> = self.duplicates.values().flatten().collect();
+        self.changed
+            .iter()
+            .filter(|file| !duplicates.contains(&file.path))
+            .cloned()
+            .collect()
+    }
+
+    /// Make the first sendable copy the representative of groups whose
+    /// representative is withheld by `never_send`, so the copies that may
+    /// be sent are still analyzed
+    pub fn prefer_sendable(&mut self, never_send: &NeverSend) {
+        let withheld: Vec<String> = self
+            .duplicates
+            .keys()
+            .filter(|path| never_send.matches(path))
+            .cloned()
+            .collect();
+        for representative in withheld {
+            let copies = &self.duplicates[&representative];
+            let Some(idx) = copies.iter().position(|path| !never_send.matches(path)) else {
+                continue;
+            };
+            let mut copies = self.duplicates.remove(&representative).unwrap_or_default();
+            let sendable = copies.remove(idx);
+            copies.push(representative);
+            copies.sort();
+            self.duplicates.insert(sendable, copies);
+        }
+    }
+
+    /// Number of changed files left to their representative
+    pub fn duplicate_count(&self) -> usize {
+        self.duplicates.values().map(Vec::len).sum()
+    }
+}
+
 /// Scan repository for files needing analysis.
 ///
 /// Walks the repo, skips ignored/binary files, calculates hashes,
 /// and compares against manifest to find changed files.
 /// If `full` is true, all files are returned regardless of manifest state.
-pub fn scan_files(repo_path: &Path, manifest: &Manifest, full: bool) -> Result<ScanResult> {
+/// Files `generated` recognizes land in [`ScanResult::generated`] instead
+/// of `changed`. With `paths`, only files matching one of them are scanned.
+/// Submodules are skipped unless `recurse_submodules` is set.
+pub fn scan_files(
+    repo_path: &Path,
+    manifest: &Manifest,
+    full: bool,
+    generated: &GeneratedFiles,
+    paths: &[String],
+    recurse_submodules: bool,
+) -> Result<ScanResult> {
     let repo = git2::Repository::open(repo_path)
         .with_context(|| format!("Failed to open git repository at {}", repo_path.display()))?;
+    let in_scope = scope(paths, manifest)?;
+    let submodules = find_submodules(&repo, recurse_submodules);
+    let submodule_repos: Vec<(String, git2::Repository)> = submodules
+        .iter()
+        .filter(|(_, entry)| entry.recursed)
+        .filter_map(|(path, _)| {
+            let submodule = git2::Repository::open(repo_path.join(path)).ok()?;
+            Some((path.clone(), submodule))
+        })
+        .collect();
 
-    let mut changed = Vec::new();
-    let mut unchanged = 0usize;
-    let mut total = 0usize;
+    let branch = current_branch(&repo);
+    let mut sorted = Sorted::default();
     let mut seen_paths = std::collections::HashSet::new();
 
     for entry in WalkDir::new(repo_path)
@@ -57,7 +198,17 @@ pub fn scan_files(repo_path: &Path, manifest: &Manifest, full: bool) -> Result<S
         .filter_entry(|e| {
             let name = e.file_name().to_string_lossy();
             // Skip .git and .noggin directories at walk level
-            name != ".git" && name != ".noggin"
+            if name == ".git" || name == ".noggin" {
+                return false;
+            }
+            // Nested repositories are only entered as recursed submodules
+            e.depth() == 0
+                || !e.file_type().is_dir()
+                || !e.path().join(".git").exists()
+                || e.path().strip_prefix(repo_path).is_ok_and(|rel| {
+                    let rel = normalize_path(&rel.to_string_lossy());
+                    submodule_repos.iter().any(|(path, _)| *path == rel)
+                })
         })
     {
         let entry = entry.context("Failed to read directory entry")?;
@@ -70,12 +221,15 @@ pub fn scan_files(repo_path: &Path, manifest: &Manifest, full: bool) -> Result<S
 
         // Get relative path
         let rel_path = match full_path.strip_prefix(repo_path) {
-            Ok(p) => p.to_string_lossy().to_string(),
+            Ok(p) => normalize_path(&p.to_string_lossy()),
             Err(_) => continue,
         };
+        if !in_scope(&rel_path) {
+            continue;
+        }
 
         // Skip files ignored by git
-        if repo.is_path_ignored(Path::new(&rel_path)).unwrap_or(false) {
+        if is_ignored(&repo, &submodule_repos, &rel_path) {
             continue;
         }
 
@@ -84,8 +238,7 @@ pub fn scan_files(repo_path: &Path, manifest: &Manifest, full: bool) -> Result<S
             continue;
         }
 
-        total += 1;
-        seen_paths.insert(rel_path.clone());
+        seen_paths.insert(manifest.path_key(&rel_path));
 
         // Calculate hash
         let hash = calculate_file_hash(full_path)
@@ -94,53 +247,421 @@ pub fn scan_files(repo_path: &Path, manifest: &Manifest, full: bool) -> Result<S
         let metadata = fs::metadata(full_path)
             .with_context(|| format!("Failed to read metadata for {}", rel_path))?;
 
+        let is_generated = generated.is_generated(&repo, &rel_path);
+        sorted.add(manifest, branch.as_deref(), full, is_generated, (rel_path, hash, metadata.len()));
+    }
+
+    // Detect files tracked in manifest but no longer on disk. Those
+    // another branch still has are only gone from this one, as long as
+    // that branch still exists. With HEAD detached every branch is another.
+    let live_elsewhere = |name: &str| {
+        Some(name) != branch.as_deref()
+            && repo.find_branch(name, git2::BranchType::Local).is_ok()
+    };
+    let (elsewhere, deleted): (Vec<&FileEntry>, Vec<&FileEntry>) = manifest
+        .files
+        .iter()
+        .filter(|(key, _)| !seen_paths.contains(*key))
+        .map(|(_, entry)| entry)
+        .filter(|entry| in_scope(&entry.path))
+        .partition(|entry| entry.branches.keys().any(|b| live_elsewhere(b)));
+    let paths = |entries: Vec<&FileEntry>| entries.into_iter().map(|e| e.path.clone()).collect();
+
+    let renamed = detect_renames(&repo, repo_path, manifest, &deleted, &sorted.added());
+    let deleted: Vec<&FileEntry> = deleted
+        .into_iter()
+        .filter(|entry| !renamed.iter().any(|r| r.from == entry.path))
+        .collect();
+    Ok(ScanResult {
+        submodules,
+        branch,
+        ..sorted.finish(manifest, full, renamed, paths(deleted), paths(elsewhere))
+    })
+}
+
+/// Scan `paths` as they are in the tree `tree_id` rather than the working
+/// tree, for analyzing a commit range that may not be checked out. Each
+/// path is taken literally; those missing from the tree are deleted if
+/// the manifest tracks them. Renames are only found by identical content.
+pub fn scan_tree(
+    repo_path: &Path,
+    tree_id: git2::Oid,
+    manifest: &Manifest,
+    full: bool,
+    generated: &GeneratedFiles,
+    paths: &[String],
+) -> Result<ScanResult> {
+    let repo = git2::Repository::open(repo_path)
+        .with_context(|| format!("Failed to open git repository at {}", repo_path.display()))?;
+    let tree = repo.find_tree(tree_id)?;
+    let branch = current_branch(&repo);
+    let mut sorted = Sorted::default();
+    let mut deleted = Vec::new();
+
+    for rel_path in paths {
+        let blob = tree
+            .get_path(Path::new(rel_path))
+            .ok()
+            .and_then(|entry| entry.to_object(&repo).ok())
+            .and_then(|object| object.into_blob().ok());
+        let Some(blob) = blob else {
+            if let Some(entry) = manifest.get_file(rel_path) {
+                deleted.push(entry);
+            }
+            continue;
+        };
+        if looks_binary(blob.content()) {
+            continue;
+        }
+        let is_generated = generated.is_generated(&repo, rel_path);
+        let file = (rel_path.clone(), hash_contents(blob.content()), blob.size() as u64);
+        sorted.add(manifest, branch.as_deref(), full, is_generated, file);
+    }
+
+    let renamed = exact_renames(&deleted, &sorted.added());
+    let deleted = deleted
+        .into_iter()
+        .filter(|entry| !renamed.iter().any(|r| r.from == entry.path))
+        .map(|entry| entry.path.clone())
+        .collect();
+    Ok(ScanResult {
+        submodules: manifest.submodules.clone(),
+        branch,
+        ..sorted.finish(manifest, full, renamed, deleted, Vec::new())
+    })
+}
+
+/// Scanned files sorted by what the manifest already records of them
+#[derive(Default)]
+struct Sorted {
+    changed: Vec<FileToAnalyze>,
+    generated: Vec<FileToAnalyze>,
+    generated_total: usize,
+    restored: Vec<FileToAnalyze>,
+    unchanged: usize,
+    total: usize,
+}
+
+impl Sorted {
+    /// File for analysis if it's new or changed (or always, when `full`),
+    /// otherwise counted unchanged and restored if this branch lacks it
+    fn add(
+        &mut self,
+        manifest: &Manifest,
+        branch: Option<&str>,
+        full: bool,
+        is_generated: bool,
+        (path, hash, size): (String, String, u64),
+    ) {
+        self.total += 1;
+        let bucket = if is_generated {
+            self.generated_total += 1;
+            &mut self.generated
+        } else {
+            &mut self.changed
+        };
+
         if full {
             // In full mode, analyze everything
-            let is_new = manifest.get_file_hash(&rel_path).is_none();
-            changed.push(FileToAnalyze {
-                path: rel_path,
+            let is_new = manifest.get_file_hash(&path).is_none();
+            bucket.push(FileToAnalyze {
+                path,
                 hash,
-                size: metadata.len(),
+                size,
                 is_new,
                 is_changed: true,
             });
-        } else if manifest.is_file_changed(&rel_path, &hash) {
-            let is_new = manifest.get_file_hash(&rel_path).is_none();
-            changed.push(FileToAnalyze {
-                path: rel_path,
+        } else if manifest.is_file_changed(&path, &hash)
+            && manifest.analyzed_on_branch(&path, &hash).is_none()
+        {
+            let is_new = manifest.get_file_hash(&path).is_none();
+            bucket.push(FileToAnalyze {
+                path,
                 hash,
-                size: metadata.len(),
+                size,
                 is_new,
                 is_changed: !is_new,
             });
         } else {
-            unchanged += 1;
+            self.unchanged += 1;
+            let recorded = branch.is_none_or(|branch| {
+                manifest
+                    .get_file(&path)
+                    .is_some_and(|entry| entry.branches.get(branch) == Some(&hash))
+            });
+            if manifest.is_file_changed(&path, &hash) || !recorded {
+                self.restored.push(FileToAnalyze {
+                    path,
+                    hash,
+                    size,
+                    is_new: false,
+                    is_changed: false,
+                });
+            }
         }
     }
 
-    // Detect files tracked in manifest but no longer on disk
-    let deleted: Vec<String> = manifest
-        .files
-        .keys()
-        .filter(|path| !seen_paths.contains(*path))
-        .cloned()
-        .collect();
+    /// New files, which may be renames of deleted ones
+    fn added(&self) -> Vec<&FileToAnalyze> {
+        self.changed
+            .iter()
+            .chain(&self.generated)
+            .filter(|file| file.is_new)
+            .collect()
+    }
 
-    Ok(ScanResult {
-        changed,
-        deleted,
-        unchanged,
-        total,
+    /// Result with renamed files moved off their old paths. Submodules
+    /// and branch are left for the caller.
+    fn finish(
+        mut self,
+        manifest: &Manifest,
+        full: bool,
+        renamed: Vec<FileRename>,
+        deleted: Vec<String>,
+        elsewhere: Vec<String>,
+    ) -> ScanResult {
+        for rename in &renamed {
+            let old_hash = manifest.get_file_hash(&rename.from).unwrap_or_default();
+            for bucket in [&mut self.changed, &mut self.generated] {
+                let Some(idx) = bucket.iter().position(|f| f.path == rename.to) else {
+                    continue;
+                };
+                bucket[idx].is_new = false;
+                bucket[idx].is_changed = bucket[idx].hash != old_hash;
+                // Content already analyzed under the old path
+                if !full && !bucket[idx].is_changed {
+                    self.restored.push(bucket.remove(idx));
+                }
+            }
+        }
+
+        let duplicates = group_duplicates(&self.changed);
+        ScanResult {
+            duplicates,
+            changed: self.changed,
+            generated: self.generated,
+            generated_total: self.generated_total,
+            restored: self.restored,
+            deleted,
+            renamed,
+            elsewhere,
+            submodules: BTreeMap::new(),
+            branch: None,
+            unchanged: self.unchanged,
+            total: self.total,
+        }
+    }
+}
+
+/// Submodules registered in the repository, marked recursed when
+/// `recurse` is set and the submodule is checked out
+fn find_submodules(repo: &git2::Repository, recurse: bool) -> BTreeMap<String, SubmoduleEntry> {
+    let Ok(submodules) = repo.submodules() else {
+        return BTreeMap::new();
+    };
+    submodules
+        .iter()
+        .map(|submodule| {
+            let commit = submodule.workdir_id().map(|id| id.to_string());
+            let entry = SubmoduleEntry {
+                url: submodule.url().map(str::to_string),
+                recursed: recurse && commit.is_some(),
+                commit,
+            };
+            (normalize_path(&submodule.path().to_string_lossy()), entry)
+        })
+        .collect()
+}
+
+/// Whether git ignores `rel_path`, by the rules of the recursed submodule
+/// containing it if there is one
+fn is_ignored(
+    repo: &git2::Repository,
+    submodule_repos: &[(String, git2::Repository)],
+    rel_path: &str,
+) -> bool {
+    for (prefix, submodule) in submodule_repos {
+        if let Some(inner) = rel_path
+            .strip_prefix(prefix.as_str())
+            .and_then(|rest| rest.strip_prefix('/'))
+        {
+            return submodule.is_path_ignored(Path::new(inner)).unwrap_or(false);
+        }
+    }
+    repo.is_path_ignored(Path::new(rel_path)).unwrap_or(false)
+}
+
+/// Files this small or smaller are never grouped as duplicates
+const NEAR_EMPTY_BYTES: u64 = 10;
+
+/// Changed files sharing a hash with another, keyed by the first of
+/// them by path
+fn group_duplicates(changed: &[FileToAnalyze]) -> BTreeMap<String, Vec<String>> {
+    let mut by_hash: HashMap<&str, Vec<&str>> = HashMap::new();
+    for file in changed.iter().filter(|file| file.size > NEAR_EMPTY_BYTES) {
+        by_hash.entry(&file.hash).or_default().push(&file.path);
+    }
+    by_hash
+        .into_values()
+        .filter(|paths| paths.len() > 1)
+        .map(|mut paths| {
+            paths.sort();
+            let representative = paths.remove(0).to_string();
+            (representative, paths.into_iter().map(str::to_string).collect())
+        })
+        .collect()
+}
+
+/// Pair deleted tracked files with new files they were renamed to: first
+/// by identical content, then by git's similarity-based rename detection
+/// between the last processed commit (or HEAD) and the working tree
+fn detect_renames(
+    repo: &git2::Repository,
+    repo_path: &Path,
+    manifest: &Manifest,
+    deleted: &[&FileEntry],
+    added: &[&FileToAnalyze],
+) -> Vec<FileRename> {
+    let mut renames = exact_renames(deleted, added);
+    if renames.len() == deleted.len().min(added.len()) {
+        return renames;
+    }
+
+    for (from, to) in similar_renames(repo, manifest).unwrap_or_default() {
+        let from = deleted
+            .iter()
+            .find(|entry| manifest.path_key(&entry.path) == manifest.path_key(&from));
+        let to = added
+            .iter()
+            .find(|file| manifest.path_key(&file.path) == manifest.path_key(&to));
+        if let (Some(from), Some(to)) = (from, to) {
+            if unclaimed(&renames, &from.path, &to.path) && repo_path.join(&to.path).exists() {
+                renames.push(FileRename {
+                    from: from.path.clone(),
+                    to: to.path.clone(),
+                });
+            }
+        }
+    }
+    renames
+}
+
+/// Renames of deleted files to new files with identical content
+fn exact_renames(deleted: &[&FileEntry], added: &[&FileToAnalyze]) -> Vec<FileRename> {
+    let mut renames: Vec<FileRename> = Vec::new();
+    for file in added {
+        let mut same = deleted.iter().filter(|entry| entry.hash == file.hash);
+        // Ambiguous when several deleted files had this content
+        if let (Some(entry), None) = (same.next(), same.next()) {
+            if unclaimed(&renames, &entry.path, &file.path) {
+                renames.push(FileRename {
+                    from: entry.path.clone(),
+                    to: file.path.clone(),
+                });
+            }
+        }
+    }
+    renames
+}
+
+/// Whether neither side of a candidate rename is already paired
+fn unclaimed(renames: &[FileRename], from: &str, to: &str) -> bool {
+    !renames.iter().any(|r| r.from == from || r.to == to)
+}
+
+/// (old, new) paths git considers renamed between the most recently
+/// processed commit still in the repository (or HEAD) and the working
+/// tree. Compacted commits, all processed before any with an entry, are
+/// only considered when none of those remain, newest first by commit time.
+fn similar_renames(repo: &git2::Repository, manifest: &Manifest) -> Result<Vec<(String, String)>> {
+    let find = |sha: &str| {
+        let oid = git2::Oid::from_str(sha).ok()?;
+        repo.find_commit(oid).ok()
+    };
+    let mut processed: Vec<_> = manifest.commits.values().collect();
+    processed.sort_by_key(|entry| std::cmp::Reverse(entry.processed_at));
+    let base = processed
+        .iter()
+        .find_map(|entry| find(&entry.sha))
+        .or_else(|| {
+            manifest
+                .compacted_commits
+                .iter()
+                .filter_map(|sha| find(sha))
+                .max_by_key(|commit| commit.time().seconds())
+        })
+        .map_or_else(|| repo.head()?.peel_to_commit(), Ok)?;
+
+    let mut opts = git2::DiffOptions::new();
+    opts.include_untracked(true).recurse_untracked_dirs(true);
+    let mut diff = repo.diff_tree_to_workdir_with_index(Some(&base.tree()?), Some(&mut opts))?;
+    diff.find_similar(Some(
+        git2::DiffFindOptions::new()
+            .renames(true)
+            .for_untracked(true),
+    ))?;
+
+    Ok(diff
+        .deltas()
+        .filter(|delta| delta.status() == git2::Delta::Renamed)
+        .filter_map(|delta| {
+            let from = delta.old_file().path()?.to_string_lossy().into_owned();
+            let to = delta.new_file().path()?.to_string_lossy().into_owned();
+            Some((normalize_path(&from), normalize_path(&to)))
+        })
+        .collect())
+}
+
+/// Whether a repo-relative path falls within the `paths` pathspecs;
+/// everything does when there are none
+fn scope(paths: &[String], manifest: &Manifest) -> Result<impl Fn(&str) -> bool> {
+    let pathspec = if paths.is_empty() {
+        None
+    } else {
+        Some(Pathspec::new(paths).context("Invalid --path pathspec")?)
+    };
+    let flags = if manifest.path_case.is_sensitive() {
+        PathspecFlags::DEFAULT
+    } else {
+        PathspecFlags::IGNORE_CASE
+    };
+    Ok(move |path: &str| {
+        pathspec
+            .as_ref()
+            .is_none_or(|spec| spec.matches_path(Path::new(path), flags))
     })
 }
 
+/// Name of the checked-out branch
+fn current_branch(repo: &git2::Repository) -> Option<String> {
+    let head = repo.head().ok()?;
+    if !head.is_branch() {
+        return None;
+    }
+    head.shorthand().map(str::to_string)
+}
+
 /// Check if a file is binary by looking for null bytes in the first 512 bytes.
+/// UTF-16 text with a byte order mark is not binary.
 fn is_binary(path: &Path) -> bool {
-    let Ok(bytes) = fs::read(path) else {
+    let Ok(file) = fs::File::open(path) else {
         return false;
     };
-    let check_len = bytes.len().min(512);
-    bytes[..check_len].contains(&0)
+    let mut bytes = Vec::with_capacity(BINARY_CHECK_LEN);
+    if file.take(BINARY_CHECK_LEN as u64).read_to_end(&mut bytes).is_err() {
+        return false;
+    }
+    looks_binary(&bytes)
+}
+
+/// How many leading bytes are checked for a null byte
+const BINARY_CHECK_LEN: usize = 512;
+
+/// Whether contents have a null byte in the first 512 bytes and no
+/// UTF-16 byte order mark
+fn looks_binary(bytes: &[u8]) -> bool {
+    let check_len = bytes.len().min(BINARY_CHECK_LEN);
+    bytes[..check_len].contains(&0) && !has_utf16_bom(bytes)
 }
 
 #[cfg(test)]
@@ -168,7 +689,7 @@ mod tests {
         fs::write(temp_dir.path().join("lib.rs"), "pub fn add() {}")?;
 
         let manifest = Manifest::default();
-        let result = scan_files(temp_dir.path(), &manifest, false)?;
+        let result = scan_files(temp_dir.path(), &manifest, false, &GeneratedFiles::default(), &[], false)?;
 
         assert_eq!(result.total, 2);
         assert_eq!(result.changed.len(), 2);
@@ -189,7 +710,7 @@ mod tests {
         let mut manifest = Manifest::default();
         manifest.add_or_update_file("hello.rs".to_string(), hash, vec![]);
 
-        let result = scan_files(temp_dir.path(), &manifest, false)?;
+        let result = scan_files(temp_dir.path(), &manifest, false, &GeneratedFiles::default(), &[], false)?;
 
         assert_eq!(result.total, 1);
         assert_eq!(result.changed.len(), 0);
@@ -211,7 +732,7 @@ mod tests {
             vec![],
         );
 
-        let result = scan_files(temp_dir.path(), &manifest, false)?;
+        let result = scan_files(temp_dir.path(), &manifest, false, &GeneratedFiles::default(), &[], false)?;
 
         assert_eq!(result.changed.len(), 1);
         assert!(result.changed[0].is_changed);
@@ -232,7 +753,7 @@ mod tests {
         manifest.add_or_update_file("hello.rs".to_string(), hash, vec![]);
 
         // Even though file is unchanged, --full should include it
-        let result = scan_files(temp_dir.path(), &manifest, true)?;
+        let result = scan_files(temp_dir.path(), &manifest, true, &GeneratedFiles::default(), &[], false)?;
 
         assert_eq!(result.changed.len(), 1);
 
@@ -246,7 +767,7 @@ mod tests {
         fs::write(temp_dir.path().join("hello.rs"), "fn main() {}")?;
 
         let manifest = Manifest::default();
-        let result = scan_files(temp_dir.path(), &manifest, false)?;
+        let result = scan_files(temp_dir.path(), &manifest, false, &GeneratedFiles::default(), &[], false)?;
 
         // Should not include any .git/ files
         assert!(result.changed.iter().all(|f| !f.path.starts_with(".git")));
@@ -266,7 +787,7 @@ mod tests {
         binary.write_all(&[0x89, 0x50, 0x4E, 0x47, 0x00, 0x00])?;
 
         let manifest = Manifest::default();
-        let result = scan_files(temp_dir.path(), &manifest, false)?;
+        let result = scan_files(temp_dir.path(), &manifest, false, &GeneratedFiles::default(), &[], false)?;
 
         assert_eq!(result.changed.len(), 1);
         assert_eq!(result.changed[0].path, "hello.rs");
@@ -274,6 +795,30 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_scan_tree_reads_the_tree_not_the_worktree() -> Result<()> {
+        let (temp_dir, repo) = create_test_repo()?;
+        let mut builder = repo.treebuilder(None)?;
+        builder.insert("a[1].rs", repo.blob(b"fn committed() {}")?, 0o100644)?;
+        builder.insert("a1.rs", repo.blob(b"fn sibling() {}")?, 0o100644)?;
+        let tree_id = builder.write()?;
+        // The working tree has other content, and no a[1].rs at all
+        fs::write(temp_dir.path().join("a1.rs"), "fn edited() {}")?;
+
+        let mut manifest = Manifest::default();
+        manifest.add_or_update_file("gone.rs".to_string(), "old".to_string(), vec![]);
+        let paths = vec!["a[1].rs".to_string(), "gone.rs".to_string()];
+        let result = scan_tree(temp_dir.path(), tree_id, &manifest, false, &GeneratedFiles::default(), &paths)?;
+
+        // Only the named path, matched literally rather than as a glob
+        assert_eq!(result.changed.len(), 1);
+        assert_eq!(result.changed[0].path, "a[1].rs");
+        assert_eq!(result.changed[0].hash, hash_contents(b"fn committed() {}"));
+        assert_eq!(result.deleted, vec!["gone.rs"]);
+
+        Ok(())
+    }
+
     #[test]
     fn test_is_binary() {
         let temp_dir = TempDir::new().unwrap();
@@ -283,10 +828,94 @@ mod tests {
         assert!(!is_binary(&text_path));
 
         let binary_path = temp_dir.path().join("binary.bin");
-        fs::write(&binary_path, &[0x00, 0x01, 0x02]).unwrap();
+        fs::write(&binary_path, [0x00, 0x01, 0x02]).unwrap();
         assert!(is_binary(&binary_path));
     }
 
+    #[test]
+    fn test_scan_after_switching_branches() -> Result<()> {
+        let (temp_dir, repo) = create_test_repo()?;
+        fs::write(temp_dir.path().join("a.rs"), "fn v1() {}")?;
+        let mut index = repo.index()?;
+        index.add_path(Path::new("a.rs"))?;
+        let tree = repo.find_tree(index.write_tree()?)?;
+        let sig = repo.signature()?;
+        let head = repo.commit(Some("HEAD"), &sig, &sig, "init", &tree, &[])?;
+        let branch = current_branch(&repo).unwrap();
+        repo.branch("feature", &repo.find_commit(head)?, false)?;
+
+        // Last analyzed on `feature`, where a.rs differs and b.rs exists;
+        // a.rs's content here was analyzed on `feature` earlier
+        let v1 = calculate_file_hash(&temp_dir.path().join("a.rs"))?;
+        let mut manifest = Manifest::default();
+        manifest.add_or_update_file("a.rs".to_string(), v1, vec![]);
+        manifest.mark_file_on_branch("a.rs", "feature");
+        manifest.add_or_update_file("a.rs".to_string(), "v2".to_string(), vec![]);
+        manifest.add_or_update_file("b.rs".to_string(), "b".to_string(), vec![]);
+        manifest.mark_file_on_branch("b.rs", "feature");
+        manifest.add_or_update_file("c.rs".to_string(), "c".to_string(), vec![]);
+        manifest.mark_file_on_branch("c.rs", &branch);
+
+        let result = scan_files(temp_dir.path(), &manifest, false, &GeneratedFiles::default(), &[], false)?;
+        assert_eq!(result.branch.as_deref(), Some(branch.as_str()));
+        assert!(result.changed.is_empty());
+        assert_eq!(result.restored.len(), 1);
+        assert_eq!(result.restored[0].path, "a.rs");
+        assert_eq!(result.elsewhere, vec!["b.rs"]);
+        assert_eq!(result.deleted, vec!["c.rs"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_scan_with_detached_head() -> Result<()> {
+        let (temp_dir, repo) = create_test_repo()?;
+        fs::write(temp_dir.path().join("a.rs"), "fn a() {}")?;
+        let mut index = repo.index()?;
+        index.add_path(Path::new("a.rs"))?;
+        let tree = repo.find_tree(index.write_tree()?)?;
+        let sig = repo.signature()?;
+        let head = repo.commit(Some("HEAD"), &sig, &sig, "init", &tree, &[])?;
+        let branch = current_branch(&repo).unwrap();
+        repo.set_head_detached(head)?;
+
+        // Files missing here count as elsewhere only on branches that
+        // still exist, the one HEAD was detached from included
+        let mut manifest = Manifest::default();
+        manifest.add_or_update_file("b.rs".to_string(), "b".to_string(), vec![]);
+        manifest.mark_file_on_branch("b.rs", &branch);
+        manifest.add_or_update_file("c.rs".to_string(), "c".to_string(), vec![]);
+        manifest.mark_file_on_branch("c.rs", "deleted-branch");
+
+        let result = scan_files(temp_dir.path(), &manifest, false, &GeneratedFiles::default(), &[], false)?;
+        assert_eq!(result.branch, None);
+        assert_eq!(result.elsewhere, vec!["b.rs"]);
+        assert_eq!(result.deleted, vec!["c.rs"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_scan_limited_to_paths() -> Result<()> {
+        let (temp_dir, _repo) = create_test_repo()?;
+        fs::create_dir_all(temp_dir.path().join("services/api"))?;
+        fs::write(temp_dir.path().join("services/api/main.rs"), "fn main() {}")?;
+        fs::write(temp_dir.path().join("lib.rs"), "fn lib() {}")?;
+
+        // Missing files outside the paths aren't deletions
+        let mut manifest = Manifest::default();
+        manifest.add_or_update_file("services/api/gone.rs".to_string(), "a".to_string(), vec![]);
+        manifest.add_or_update_file("web/gone.rs".to_string(), "b".to_string(), vec![]);
+
+        let paths = vec!["services/api".to_string()];
+        let result = scan_files(temp_dir.path(), &manifest, false, &GeneratedFiles::default(), &paths, false)?;
+        assert_eq!(result.total, 1);
+        assert_eq!(result.changed[0].path, "services/api/main.rs");
+        assert_eq!(result.deleted, vec!["services/api/gone.rs"]);
+
+        Ok(())
+    }
+
     #[test]
     fn test_scan_detects_deleted_files() -> Result<()> {
         let (temp_dir, _repo) = create_test_repo()?;
@@ -303,7 +932,7 @@ mod tests {
             vec!["some-pattern".to_string()],
         );
 
-        let result = scan_files(temp_dir.path(), &manifest, false)?;
+        let result = scan_files(temp_dir.path(), &manifest, false, &GeneratedFiles::default(), &[], false)?;
 
         assert_eq!(result.deleted.len(), 1);
         assert_eq!(result.deleted[0], "removed.rs");
@@ -311,6 +940,51 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_scan_detects_renames() -> Result<()> {
+        let (temp_dir, repo) = create_test_repo()?;
+        let root = temp_dir.path();
+        let body: String = (0..30).map(|i| format!("fn f{}() {{}}\n", i)).collect();
+        fs::write(root.join("db.rs"), &body)?;
+        fs::write(root.join("util.rs"), "pub fn util() {}\n")?;
+        let mut index = repo.index()?;
+        index.add_path(Path::new("db.rs"))?;
+        index.add_path(Path::new("util.rs"))?;
+        index.write()?;
+        let tree = repo.find_tree(index.write_tree()?)?;
+        let signature = repo.signature()?;
+        repo.commit(Some("HEAD"), &signature, &signature, "Initial", &tree, &[])?;
+
+        let mut manifest = Manifest::default();
+        for path in ["db.rs", "util.rs"] {
+            let hash = calculate_file_hash(&root.join(path))?;
+            manifest.add_or_update_file(path.to_string(), hash, vec![]);
+        }
+
+        // util.rs moves as is; db.rs moves and gains a line
+        fs::create_dir_all(root.join("src"))?;
+        fs::rename(root.join("util.rs"), root.join("src/util.rs"))?;
+        fs::remove_file(root.join("db.rs"))?;
+        fs::write(root.join("src/db.rs"), format!("{}fn extra() {{}}\n", body))?;
+
+        let result = scan_files(root, &manifest, false, &GeneratedFiles::default(), &[], false)?;
+
+        assert!(result.deleted.is_empty());
+        assert_eq!(
+            result.renamed,
+            vec![
+                FileRename { from: "util.rs".to_string(), to: "src/util.rs".to_string() },
+                FileRename { from: "db.rs".to_string(), to: "src/db.rs".to_string() },
+            ]
+        );
+        assert_eq!(result.changed.len(), 1);
+        assert_eq!(result.changed[0].path, "src/db.rs");
+        assert!(!result.changed[0].is_new && result.changed[0].is_changed);
+        assert_eq!(result.restored[0].path, "src/util.rs");
+
+        Ok(())
+    }
+
     #[test]
     fn test_scan_skips_gitignored_files() -> Result<()> {
         let (temp_dir, _repo) = create_test_repo()?;
@@ -322,7 +996,7 @@ mod tests {
         fs::write(temp_dir.path().join("hello.rs"), "fn main() {}")?;
 
         let manifest = Manifest::default();
-        let result = scan_files(temp_dir.path(), &manifest, false)?;
+        let result = scan_files(temp_dir.path(), &manifest, false, &GeneratedFiles::default(), &[], false)?;
 
         let paths: Vec<&str> = result.changed.iter().map(|f| f.path.as_str()).collect();
         assert!(paths.contains(&"hello.rs"));
@@ -332,4 +1006,145 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_scan_skips_or_recurses_into_submodules() -> Result<()> {
+        let (temp_dir, repo) = create_test_repo()?;
+        let (upstream_dir, upstream) = create_test_repo()?;
+
+        fs::write(upstream_dir.path().join("lib.rs"), "pub fn lib() {}")?;
+        fs::write(upstream_dir.path().join(".gitignore"), "*.tmp\n")?;
+        let mut index = upstream.index()?;
+        index.add_all(["*"], git2::IndexAddOption::DEFAULT, None)?;
+        let tree = upstream.find_tree(index.write_tree()?)?;
+        let signature = upstream.signature()?;
+        upstream.commit(Some("HEAD"), &signature, &signature, "Initial", &tree, &[])?;
+
+        let url = upstream_dir.path().to_string_lossy().into_owned();
+        let mut submodule = repo.submodule(&url, Path::new("vendor/lib"), true)?;
+        submodule.clone(None)?;
+        submodule.add_finalize()?;
+        fs::write(temp_dir.path().join("vendor/lib/scratch.tmp"), "notes")?;
+
+        // A repository that isn't a submodule is never scanned
+        git2::Repository::init(temp_dir.path().join("tools/other"))?;
+        fs::write(temp_dir.path().join("tools/other/main.rs"), "fn main() {}")?;
+        fs::write(temp_dir.path().join("main.rs"), "fn main() {}")?;
+
+        let manifest = Manifest::default();
+        let skipped = scan_files(temp_dir.path(), &manifest, false, &GeneratedFiles::default(), &[], false)?;
+        let mut paths: Vec<&str> = skipped.changed.iter().map(|f| f.path.as_str()).collect();
+        paths.sort();
+        assert_eq!(paths, vec![".gitmodules", "main.rs"]);
+        let entry = &skipped.submodules["vendor/lib"];
+        assert_eq!(entry.url.as_deref(), Some(url.as_str()));
+        assert!(entry.commit.is_some());
+        assert!(!entry.recursed);
+
+        let recursed = scan_files(temp_dir.path(), &manifest, false, &GeneratedFiles::default(), &[], true)?;
+        let mut paths: Vec<&str> = recursed.changed.iter().map(|f| f.path.as_str()).collect();
+        paths.sort();
+        assert_eq!(
+            paths,
+            vec![".gitmodules", "main.rs", "vendor/lib/.gitignore", "vendor/lib/lib.rs"]
+        );
+        assert!(recursed.submodules["vendor/lib"].recursed);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_scan_separates_generated_files() -> Result<()> {
+        let (temp_dir, _repo) = create_test_repo()?;
+
+        fs::write(temp_dir.path().join("hello.rs"), "fn main() {}")?;
+        fs::write(temp_dir.path().join("parser.rs"), "// @generated
+fn parse() {}")?;
+        fs::write(temp_dir.path().join("Cargo.lock"), "version = 3")?;
+        let hash = calculate_file_hash(&temp_dir.path().join("Cargo.lock"))?;
+        let mut manifest = Manifest::default();
+        manifest.add_or_update_file("Cargo.lock".to_string(), hash, vec![]);
+
+        let generated = GeneratedFiles::from_config(&crate::config::GeneratedConfig::default())?;
+        let result = scan_files(temp_dir.path(), &manifest, false, &generated, &[], false)?;
+
+        assert_eq!(result.total, 3);
+        assert_eq!(result.changed.len(), 1);
+        assert_eq!(result.changed[0].path, "hello.rs");
+        assert_eq!(result.generated.len(), 1);
+        assert_eq!(result.generated[0].path, "parser.rs");
+        assert_eq!(result.generated_total, 2);
+        assert_eq!(result.unchanged, 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_scan_groups_identical_files() -> Result<()> {
+        let (temp_dir, _repo) = create_test_repo()?;
+
+        for dir in ["api", "web", "worker"] {
+            fs::create_dir_all(temp_dir.path().join(dir))?;
+            fs::write(temp_dir.path().join(dir).join(".eslintrc"), "{ \"root\": true }")?;
+        }
+        fs::write(temp_dir.path().join("main.rs"), "fn main() {}")?;
+
+        let manifest = Manifest::default();
+        let result = scan_files(temp_dir.path(), &manifest, false, &GeneratedFiles::default(), &[], false)?;
+
+        assert_eq!(result.changed.len(), 4);
+        assert_eq!(
+            result.duplicates.get("api/.eslintrc"),
+            Some(&vec!["web/.eslintrc".to_string(), "worker/.eslintrc".to_string()])
+        );
+        assert_eq!(result.duplicate_count(), 2);
+        let mut analyzed: Vec<String> = result.representatives().into_iter().map(|f| f.path).collect();
+        analyzed.sort();
+        assert_eq!(analyzed, vec!["api/.eslintrc", "main.rs"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_near_empty_files_are_not_grouped() -> Result<()> {
+        let (temp_dir, _repo) = create_test_repo()?;
+
+        for dir in ["api", "web"] {
+            fs::create_dir_all(temp_dir.path().join(dir))?;
+            fs::write(temp_dir.path().join(dir).join("__init__.py"), "")?;
+            fs::write(temp_dir.path().join(dir).join("config.json"), "{}\n")?;
+        }
+
+        let manifest = Manifest::default();
+        let result = scan_files(temp_dir.path(), &manifest, false, &GeneratedFiles::default(), &[], false)?;
+
+        assert!(result.duplicates.is_empty());
+        assert_eq!(result.representatives().len(), 4);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_withheld_representative_hands_over_to_a_sendable_copy() -> Result<()> {
+        let (temp_dir, _repo) = create_test_repo()?;
+
+        for dir in ["api", "secrets", "web"] {
+            fs::create_dir_all(temp_dir.path().join(dir))?;
+            fs::write(temp_dir.path().join(dir).join(".env.example"), "DATABASE_URL=postgres://")?;
+        }
+
+        let manifest = Manifest::default();
+        let mut result =
+            scan_files(temp_dir.path(), &manifest, false, &GeneratedFiles::default(), &[], false)?;
+        result.prefer_sendable(&NeverSend::new(&["api/".to_string()])?);
+
+        assert_eq!(
+            result.duplicates.get("secrets/.env.example"),
+            Some(&vec!["api/.env.example".to_string(), "web/.env.example".to_string()])
+        );
+        let analyzed: Vec<String> = result.representatives().into_iter().map(|f| f.path).collect();
+        assert_eq!(analyzed, vec!["secrets/.env.example"]);
+
+        Ok(())
+    }
 }
@@ -0,0 +1,344 @@
+//! Detecting the repository's languages, frameworks, and build systems.
+//!
+//! Prompts only show a sample of files, often truncated, so providers
+//! otherwise guess the stack from whatever made it in. [`detect`] works it
+//! out up front without a model: languages from file extensions, build
+//! systems from build files and lockfiles, and frameworks from the
+//! dependencies those build files declare. Learn records the result in the
+//! manifest, `status` shows it, and [`prompt_context`] adds it to every
+//! analysis prompt.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+/// Languages listed at most
+const MAX_LANGUAGES: usize = 5;
+
+/// Share of source files a language needs to be listed, unless it is the
+/// most common one
+const MIN_LANGUAGE_SHARE: f64 = 0.05;
+
+/// How much of a build file is read for dependencies
+const MAX_BUILD_FILE_BYTES: u64 = 256 * 1024;
+
+/// Directories holding third-party code, never counted
+const VENDORED_DIRS: &[&str] = &["node_modules", "vendor", "third_party", ".venv"];
+
+/// Programming language of a file extension
+const LANGUAGES: &[(&str, &str)] = &[
+    ("rs", "Rust"),
+    ("go", "Go"),
+    ("py", "Python"),
+    ("rb", "Ruby"),
+    ("js", "JavaScript"),
+    ("jsx", "JavaScript"),
+    ("mjs", "JavaScript"),
+    ("cjs", "JavaScript"),
+    ("ts", "TypeScript"),
+    ("tsx", "TypeScript"),
+    ("java", "Java"),
+    ("kt", "Kotlin"),
+    ("kts", "Kotlin"),
+    ("scala", "Scala"),
+    ("swift", "Swift"),
+    ("c", "C"),
+    ("h", "C"),
+    ("cc", "C++"),
+    ("cpp", "C++"),
+    ("cxx", "C++"),
+    ("hpp", "C++"),
+    ("cs", "C#"),
+    ("fs", "F#"),
+    ("php", "PHP"),
+    ("ex", "Elixir"),
+    ("exs", "Elixir"),
+    ("erl", "Erlang"),
+    ("hs", "Haskell"),
+    ("ml", "OCaml"),
+    ("clj", "Clojure"),
+    ("dart", "Dart"),
+    ("lua", "Lua"),
+    ("zig", "Zig"),
+    ("nix", "Nix"),
+    ("sh", "Shell"),
+    ("bash", "Shell"),
+    ("vue", "Vue"),
+    ("svelte", "Svelte"),
+    ("sql", "SQL"),
+];
+
+/// Build system implied by a build file
+const BUILD_FILES: &[(&str, &str)] = &[
+    ("Cargo.toml", "Cargo"),
+    ("package.json", "npm"),
+    ("go.mod", "Go modules"),
+    ("pom.xml", "Maven"),
+    ("build.gradle", "Gradle"),
+    ("build.gradle.kts", "Gradle"),
+    ("CMakeLists.txt", "CMake"),
+    ("meson.build", "Meson"),
+    ("Makefile", "Make"),
+    ("pyproject.toml", "pip"),
+    ("setup.py", "pip"),
+    ("requirements.txt", "pip"),
+    ("Pipfile", "Pipenv"),
+    ("Gemfile", "Bundler"),
+    ("composer.json", "Composer"),
+    ("mix.exs", "Mix"),
+    ("Package.swift", "SwiftPM"),
+    ("stack.yaml", "Stack"),
+    ("deno.json", "Deno"),
+    ("MODULE.bazel", "Bazel"),
+    ("WORKSPACE", "Bazel"),
+    ("flake.nix", "Nix"),
+];
+
+/// Lockfiles naming the package manager in use, and the build system
+/// they stand in for
+const LOCKFILES: &[(&str, &str, &str)] = &[
+    ("yarn.lock", "Yarn", "npm"),
+    ("pnpm-lock.yaml", "pnpm", "npm"),
+    ("bun.lockb", "Bun", "npm"),
+    ("poetry.lock", "Poetry", "pip"),
+    ("uv.lock", "uv", "pip"),
+    ("Pipfile.lock", "Pipenv", "pip"),
+];
+
+/// Frameworks by a dependency declared in one of the listed build files.
+/// A dependency ending in `-` matches as a prefix, and any dependency
+/// matches its own `/`-separated subpaths (`echo` matches `echo/v4`).
+const FRAMEWORKS: &[(&[&str], &str, &str)] = &[
+    (&["Cargo.toml"], "tokio", "Tokio"),
+    (&["Cargo.toml"], "actix-web", "Actix Web"),
+    (&["Cargo.toml"], "axum", "Axum"),
+    (&["Cargo.toml"], "rocket", "Rocket"),
+    (&["Cargo.toml"], "warp", "Warp"),
+    (&["Cargo.toml"], "clap", "clap"),
+    (&["Cargo.toml"], "diesel", "Diesel"),
+    (&["Cargo.toml"], "sqlx", "SQLx"),
+    (&["Cargo.toml"], "bevy", "Bevy"),
+    (&["Cargo.toml"], "tauri", "Tauri"),
+    (&["Cargo.toml"], "leptos", "Leptos"),
+    (&["package.json"], "react", "React"),
+    (&["package.json"], "react-native", "React Native"),
+    (&["package.json"], "next", "Next.js"),
+    (&["package.json"], "vue", "Vue"),
+    (&["package.json"], "nuxt", "Nuxt"),
+    (&["package.json"], "svelte", "Svelte"),
+    (&["package.json"], "@sveltejs/kit", "SvelteKit"),
+    (&["package.json"], "@angular/core", "Angular"),
+    (&["package.json"], "express", "Express"),
+    (&["package.json"], "fastify", "Fastify"),
+    (&["package.json"], "@nestjs/core", "NestJS"),
+    (&["package.json"], "electron", "Electron"),
+    (&["package.json"], "jest", "Jest"),
+    (&["package.json"], "vitest", "Vitest"),
+    (&["pyproject.toml", "requirements.txt", "setup.py", "Pipfile"], "django", "Django"),
+    (&["pyproject.toml", "requirements.txt", "setup.py", "Pipfile"], "flask", "Flask"),
+    (&["pyproject.toml", "requirements.txt", "setup.py", "Pipfile"], "fastapi", "FastAPI"),
+    (&["pyproject.toml", "requirements.txt", "setup.py", "Pipfile"], "sqlalchemy", "SQLAlchemy"),
+    (&["pyproject.toml", "requirements.txt", "setup.py", "Pipfile"], "pytest", "pytest"),
+    (&["Gemfile"], "rails", "Rails"),
+    (&["Gemfile"], "sinatra", "Sinatra"),
+    (&["Gemfile"], "rspec", "RSpec"),
+    (&["go.mod"], "github.com/gin-gonic/gin", "Gin"),
+    (&["go.mod"], "github.com/labstack/echo", "Echo"),
+    (&["go.mod"], "github.com/gofiber/fiber", "Fiber"),
+    (&["go.mod"], "github.com/spf13/cobra", "Cobra"),
+    (&["pom.xml", "build.gradle", "build.gradle.kts"], "spring-boot-", "Spring Boot"),
+    (&["pom.xml", "build.gradle", "build.gradle.kts"], "quarkus-", "Quarkus"),
+    (&["composer.json"], "laravel/framework", "Laravel"),
+    (&["composer.json"], "symfony/framework-bundle", "Symfony"),
+    (&["mix.exs"], "phoenix", "Phoenix"),
+];
+
+/// What a repository is built with
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Stack {
+    /// Most common first
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub languages: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub frameworks: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub build_systems: Vec<String>,
+}
+
+impl Stack {
+    pub fn is_empty(&self) -> bool {
+        self.languages.is_empty() && self.frameworks.is_empty() && self.build_systems.is_empty()
+    }
+
+    /// One line such as `Rust, Shell; frameworks: Tokio; build: Cargo`
+    pub fn summary(&self) -> String {
+        let mut parts = Vec::new();
+        if !self.languages.is_empty() {
+            parts.push(self.languages.join(", "));
+        }
+        if !self.frameworks.is_empty() {
+            parts.push(format!("frameworks: {}", self.frameworks.join(", ")));
+        }
+        if !self.build_systems.is_empty() {
+            parts.push(format!("build: {}", self.build_systems.join(", ")));
+        }
+        parts.join("; ")
+    }
+}
+
+/// Detect the stack of the repository at `repo_path` from its repo-relative
+/// file `paths`. Build files among them are read for dependencies.
+pub fn detect<'a>(repo_path: &Path, paths: impl IntoIterator<Item = &'a str>) -> Stack {
+    let mut language_counts: BTreeMap<&str, usize> = BTreeMap::new();
+    let mut build_systems: BTreeSet<&str> = BTreeSet::new();
+    let mut replaced: HashSet<&str> = HashSet::new();
+    let mut frameworks: BTreeSet<&str> = BTreeSet::new();
+
+    for path in paths {
+        if path.split('/').any(|segment| VENDORED_DIRS.contains(&segment)) {
+            continue;
+        }
+        let name = path.rsplit('/').next().unwrap_or(path);
+
+        if let Some(language) = name
+            .rsplit_once('.')
+            .and_then(|(_, ext)| LANGUAGES.iter().find(|(e, _)| ext.eq_ignore_ascii_case(e)))
+            .map(|(_, language)| *language)
+        {
+            *language_counts.entry(language).or_default() += 1;
+        }
+
+        if let Some((_, system, stands_in_for)) = LOCKFILES.iter().find(|(file, _, _)| *file == name) {
+            build_systems.insert(system);
+            replaced.insert(stands_in_for);
+        }
+        if let Some((_, system)) = BUILD_FILES.iter().find(|(file, _)| *file == name) {
+            build_systems.insert(system);
+        }
+
+        let declared: Vec<_> = FRAMEWORKS
+            .iter()
+            .filter(|(files, _, _)| files.contains(&name))
+            .collect();
+        if declared.is_empty() {
+            continue;
+        }
+        let Some(contents) = read_prefix(&repo_path.join(path)) else {
+            continue;
+        };
+        let dependencies = dependency_tokens(&contents);
+        for (_, dependency, framework) in declared {
+            if dependencies.iter().any(|token| matches_dependency(token, dependency)) {
+                frameworks.insert(framework);
+            }
+        }
+    }
+    build_systems.retain(|system| !replaced.contains(system));
+
+    Stack {
+        languages: rank_languages(language_counts),
+        frameworks: frameworks.into_iter().map(String::from).collect(),
+        build_systems: build_systems.into_iter().map(String::from).collect(),
+    }
+}
+
+/// Context line added to analysis prompts; empty when nothing was detected
+pub fn prompt_context(stack: &Stack) -> String {
+    if stack.is_empty() {
+        return String::new();
+    }
+    format!(
+        "\nProject stack, detected from build files and file extensions: {}. \
+         Read the code with this stack in mind instead of inferring it from the \
+         files shown.\n",
+        stack.summary()
+    )
+}
+
+/// Languages by file count, most common first; rare ones are dropped
+fn rank_languages(counts: BTreeMap<&str, usize>) -> Vec<String> {
+    let total: usize = counts.values().sum();
+    let mut ranked: Vec<(&str, usize)> = counts.into_iter().collect();
+    ranked.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+    ranked
+        .into_iter()
+        .enumerate()
+        .filter(|(i, (_, count))| *i == 0 || *count as f64 / total as f64 >= MIN_LANGUAGE_SHARE)
+        .take(MAX_LANGUAGES)
+        .map(|(_, (language, _))| language.to_string())
+        .collect()
+}
+
+/// Lowercased names in a build file, split on anything that can't be part
+/// of a package name
+fn dependency_tokens(contents: &str) -> HashSet<String> {
+    contents
+        .split(|c: char| !(c.is_ascii_alphanumeric() || "_-.@/".contains(c)))
+        .filter(|token| !token.is_empty())
+        .map(|token| token.to_ascii_lowercase())
+        .collect()
+}
+
+fn matches_dependency(token: &str, dependency: &str) -> bool {
+    if dependency.ends_with('-') {
+        return token.starts_with(dependency);
+    }
+    token == dependency
+        || token
+            .strip_prefix(dependency)
+            .is_some_and(|rest| rest.starts_with('/'))
+}
+
+fn read_prefix(path: &Path) -> Option<String> {
+    let mut buf = Vec::new();
+    File::open(path)
+        .ok()?
+        .take(MAX_BUILD_FILE_BYTES)
+        .read_to_end(&mut buf)
+        .ok()?;
+    Some(String::from_utf8_lossy(&buf).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_detect_stack_from_build_files_and_extensions() {
+        let temp_dir = TempDir::new().unwrap();
+        let write = |path: &str, contents: &str| {
+            let full = temp_dir.path().join(path);
+            fs::create_dir_all(full.parent().unwrap()).unwrap();
+            fs::write(full, contents).unwrap();
+        };
+        write(
+            "Cargo.toml",
+            "[dependencies]\ntokio = { version = \"1\" }\nclap = \"4\"\nserde = \"1\"\n",
+        );
+        write(
+            "web/package.json",
+            "{\"dependencies\": {\"react\": \"^18\", \"react-dom\": \"^18\"}}",
+        );
+        write("web/yarn.lock", "");
+        write("node_modules/express/package.json", "{\"name\": \"express\"}");
+
+        let mut paths = vec!["Cargo.toml", "web/package.json", "web/yarn.lock", "web/app.tsx"];
+        paths.extend(["src/main.rs", "src/lib.rs", "src/cli.rs", "src/error.rs"]);
+        paths.extend(["node_modules/express/package.json", "node_modules/express/index.js"]);
+        let stack = detect(temp_dir.path(), paths.iter().copied());
+
+        assert_eq!(stack.languages, vec!["Rust", "TypeScript"]);
+        assert_eq!(stack.frameworks, vec!["React", "Tokio", "clap"]);
+        assert_eq!(stack.build_systems, vec!["Cargo", "Yarn"]);
+        assert_eq!(
+            stack.summary(),
+            "Rust, TypeScript; frameworks: React, Tokio, clap; build: Cargo, Yarn"
+        );
+        assert!(prompt_context(&stack).contains("build: Cargo, Yarn"));
+        assert!(prompt_context(&Stack::default()).is_empty());
+    }
+}
@@ -0,0 +1,404 @@
+//! A free look at a repository before its first learn
+//!
+//! [`survey`] scans files and walks history the way learn would, but
+//! queries no provider: it detects the stack, ranks the most significant
+//! commits, and estimates what a full learn would cost with the enabled
+//! providers. From that it suggests a scoped starter command, so a large
+//! repository doesn't go straight from `noggin init` to an expensive full
+//! learn. Scoring diffs every commit, so in a long history only the first
+//! [`SAMPLE_COMMITS`] are scored and the rest is extrapolated from them.
+
+use crate::config::Config;
+use crate::git::scoring::{score_commit, ScoreCategory};
+use crate::git::walker::{count_commits, walk_commits, WalkOptions};
+use crate::learn::generated::GeneratedFiles;
+use crate::learn::scanner::{scan_files, FileToAnalyze};
+use crate::learn::stack::{self, Stack};
+use crate::learn::tokens::count_tokens;
+use crate::manifest::Manifest;
+use anyhow::{Context, Result};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+/// Significant commits listed by the survey
+pub const TOP_COMMITS: usize = 20;
+
+/// Commits the suggested starter learn processes
+pub const STARTER_COMMITS: usize = 50;
+
+/// Commits scored, oldest first; longer histories are extrapolated
+pub const SAMPLE_COMMITS: usize = 1000;
+
+/// Tokens a commit takes in the commit prompt without its diff
+const TOKENS_PER_COMMIT: u64 = 80;
+
+/// Tokens per diff line when `prompts.commit_diffs` is on
+const TOKENS_PER_DIFF_LINE: u64 = 12;
+
+/// Response size as a fraction of the prompt, for the cost estimate
+const RESPONSE_RATIO: f64 = 0.25;
+
+/// What the survey found
+#[derive(Debug, Clone)]
+pub struct Survey {
+    /// Files a full learn would analyze
+    pub files: usize,
+    /// Generated files, tracked but never analyzed
+    pub generated: usize,
+    pub stack: Stack,
+    /// Commits in history, merges excluded
+    pub commits: usize,
+    /// Commits scored; when fewer than `commits`, `significant` and the
+    /// full estimate are extrapolated from them
+    pub sampled: usize,
+    /// Commits scoring Medium or above, which learn analyzes
+    pub significant: usize,
+    /// Up to [`TOP_COMMITS`] significant commits among those scored,
+    /// highest score first
+    pub top_commits: Vec<RankedCommit>,
+    /// Enabled providers every prompt would go to
+    pub providers: Vec<String>,
+    /// A full learn
+    pub full: Estimate,
+    /// A learn scoped to one directory and [`STARTER_COMMITS`] commits
+    pub starter: Starter,
+}
+
+#[derive(Debug, Clone)]
+pub struct RankedCommit {
+    pub short_hash: String,
+    pub summary: String,
+    pub significance: f32,
+    pub category: ScoreCategory,
+}
+
+/// Provider usage of a learn, before it runs
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Estimate {
+    pub files: usize,
+    pub commits: usize,
+    /// Prompt and response tokens across all providers
+    pub tokens: u64,
+    pub cost_usd: f64,
+}
+
+/// The suggested first learn
+#[derive(Debug, Clone)]
+pub struct Starter {
+    /// Directory to restrict it to, None when there's no single one worth
+    /// starting with
+    pub path: Option<String>,
+    pub max_commits: usize,
+    /// Upper bound: the commits counted aren't limited to `path`
+    pub estimate: Estimate,
+}
+
+impl Starter {
+    /// The `noggin learn` invocation to run
+    pub fn command(&self) -> String {
+        let mut command = format!("noggin learn --max-commits {}", self.max_commits);
+        if let Some(path) = &self.path {
+            command.push_str(&format!(" --path {}", path));
+        }
+        command
+    }
+}
+
+/// Survey the repository at `repo_path` with the providers, scoring, and
+/// prompt settings in `config`
+pub fn survey(repo_path: &Path, config: &Config) -> Result<Survey> {
+    survey_sampled(repo_path, config, SAMPLE_COMMITS)
+}
+
+/// [`survey`], scoring at most `sample` commits
+fn survey_sampled(repo_path: &Path, config: &Config, sample: usize) -> Result<Survey> {
+    let manifest = Manifest::default();
+    let generated = GeneratedFiles::from_config(&config.generated)?;
+    let scan = scan_files(
+        repo_path,
+        &manifest,
+        false,
+        &generated,
+        &[],
+        config.submodules.recurse,
+    )
+    .context("Failed to scan files")?;
+    let stack = stack::detect(repo_path, scan.changed.iter().map(|f| f.path.as_str()));
+
+    let options = WalkOptions {
+        skip_merges: true,
+        refs: config.history.refs.clone(),
+        all_branches: config.history.all_branches,
+        limit: Some(sample),
+        ..Default::default()
+    };
+    let walk = walk_commits(repo_path, options.clone()).context("Failed to walk git history")?;
+    let commits = match walk.next_hash {
+        Some(_) => count_commits(repo_path, &options).context("Failed to count git history")?,
+        None => walk.commits.len(),
+    };
+    let scale = commits as f64 / walk.commits.len().max(1) as f64;
+
+    // Significant commits in walk order (what learn would process, oldest
+    // first), each with its prompt tokens
+    let repo = git2::Repository::open(repo_path)?;
+    let mut significant: Vec<(RankedCommit, u64)> = Vec::new();
+    for metadata in walk.commits.iter().filter(|c| !c.shallow_boundary) {
+        let commit = repo.find_commit(git2::Oid::from_str(&metadata.hash)?)?;
+        let Ok(score) = score_commit(&repo, &commit, &config.scoring) else {
+            continue;
+        };
+        if !matches!(
+            score.category,
+            ScoreCategory::Critical | ScoreCategory::High | ScoreCategory::Medium
+        ) {
+            continue;
+        }
+        let tokens = commit_tokens(config, (metadata.insertions + metadata.deletions) as usize);
+        significant.push((
+            RankedCommit {
+                short_hash: metadata.short_hash.clone(),
+                summary: metadata.message_summary.clone(),
+                significance: score.significance,
+                category: score.category,
+            },
+            tokens,
+        ));
+    }
+
+    let file_tokens: Vec<(FileToAnalyze, u64)> = scan
+        .representatives()
+        .into_iter()
+        .map(|file| {
+            let tokens = file_tokens(repo_path, config, &file);
+            (file, tokens)
+        })
+        .collect();
+
+    let providers: Vec<String> = config
+        .llm
+        .providers
+        .iter()
+        .filter(|name| config.llm.is_enabled(name))
+        .cloned()
+        .collect();
+
+    let sampled_tokens: u64 = significant.iter().map(|(_, tokens)| tokens).sum();
+    let significant_total = (significant.len() as f64 * scale).round() as usize;
+    let full = estimate(
+        config,
+        &providers,
+        file_tokens.len(),
+        file_tokens.iter().map(|(_, tokens)| tokens).sum(),
+        significant_total,
+        (sampled_tokens as f64 * scale) as u64,
+    );
+
+    let path = starter_path(file_tokens.iter().map(|(file, _)| file.path.as_str()));
+    let in_path = |file: &FileToAnalyze| {
+        path.as_ref()
+            .is_none_or(|dir| file.path.starts_with(&format!("{}/", dir)))
+    };
+    let starter_files: Vec<u64> = file_tokens
+        .iter()
+        .filter(|(file, _)| in_path(file))
+        .map(|(_, tokens)| *tokens)
+        .collect();
+    // The starter's commits are the first significant ones, scored unless
+    // the sample holds fewer than it takes
+    let starter_commits = significant_total.min(STARTER_COMMITS);
+    let starter_commit_tokens = if starter_commits <= significant.len() {
+        significant[..starter_commits].iter().map(|(_, tokens)| tokens).sum()
+    } else {
+        (sampled_tokens as f64 / significant.len() as f64 * starter_commits as f64) as u64
+    };
+    let starter = Starter {
+        path,
+        max_commits: STARTER_COMMITS,
+        estimate: estimate(
+            config,
+            &providers,
+            starter_files.len(),
+            starter_files.iter().sum(),
+            starter_commits,
+            starter_commit_tokens,
+        ),
+    };
+
+    let mut top_commits: Vec<RankedCommit> =
+        significant.into_iter().map(|(commit, _)| commit).collect();
+    top_commits.sort_by(|a, b| b.significance.total_cmp(&a.significance));
+    top_commits.truncate(TOP_COMMITS);
+
+    Ok(Survey {
+        files: scan.changed.len(),
+        generated: scan.generated_total,
+        stack,
+        commits,
+        sampled: walk.commits.len(),
+        significant: significant_total,
+        top_commits,
+        providers,
+        full,
+        starter,
+    })
+}
+
+/// Tokens `file` would take in a file prompt, capped like learn caps it
+fn file_tokens(repo_path: &Path, config: &Config, file: &FileToAnalyze) -> u64 {
+    if file.size > config.prompts.max_file_bytes {
+        return config.prompts.max_file_tokens as u64;
+    }
+    let tokens = fs::read_to_string(repo_path.join(&file.path))
+        .map(|contents| count_tokens(&contents))
+        .unwrap_or(0);
+    tokens.min(config.prompts.max_file_tokens) as u64
+}
+
+/// Tokens a commit with `changed_lines` takes in the commit prompt
+fn commit_tokens(config: &Config, changed_lines: usize) -> u64 {
+    let prompts = &config.prompts;
+    if !prompts.commit_diffs {
+        return TOKENS_PER_COMMIT;
+    }
+    let lines = changed_lines.min(prompts.max_diff_files * prompts.max_diff_lines);
+    TOKENS_PER_COMMIT + lines as u64 * TOKENS_PER_DIFF_LINE
+}
+
+/// Usage of sending `file_tokens` and `commit_tokens` of prompts to each of
+/// `providers`, with their responses
+fn estimate(
+    config: &Config,
+    providers: &[String],
+    files: usize,
+    file_tokens: u64,
+    commits: usize,
+    commit_tokens: u64,
+) -> Estimate {
+    let per_provider = ((file_tokens + commit_tokens) as f64 * (1.0 + RESPONSE_RATIO)) as u64;
+    let cost_usd = providers
+        .iter()
+        .map(|name| {
+            let price = config
+                .budget
+                .provider_costs
+                .get(name)
+                .copied()
+                .unwrap_or(config.budget.cost_per_million_tokens);
+            per_provider as f64 / 1_000_000.0 * price
+        })
+        .sum();
+    Estimate {
+        files,
+        commits,
+        tokens: per_provider * providers.len() as u64,
+        cost_usd,
+    }
+}
+
+/// Top-level directory holding the most files, when it holds less than
+/// all of them
+fn starter_path<'a>(paths: impl IntoIterator<Item = &'a str>) -> Option<String> {
+    let mut total = 0;
+    let mut by_dir: BTreeMap<&str, usize> = BTreeMap::new();
+    for path in paths {
+        total += 1;
+        if let Some((dir, _)) = path.split_once('/') {
+            *by_dir.entry(dir).or_default() += 1;
+        }
+    }
+    // First by name among equals
+    let (dir, count) = by_dir
+        .into_iter()
+        .fold(None, |best: Option<(&str, usize)>, (dir, count)| match best {
+            Some((_, best_count)) if best_count >= count => best,
+            _ => Some((dir, count)),
+        })?;
+    (count < total).then(|| dir.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use git2::{Repository, Signature};
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_survey_suggests_scoped_starter() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let repo = Repository::init(temp_dir.path())?;
+        let root = temp_dir.path();
+
+        fs::create_dir_all(root.join("src/db"))?;
+        fs::create_dir_all(root.join("docs"))?;
+        fs::write(root.join("Cargo.toml"), "[package]\nname = \"demo\"\n")?;
+        fs::write(root.join("src/main.rs"), "fn main() {}\n")?;
+        fs::write(root.join("src/db/pool.rs"), "pub fn pool() {}\n")?;
+        fs::write(root.join("docs/guide.md"), "# Guide\n")?;
+
+        let mut index = repo.index()?;
+        index.add_all(["*"], git2::IndexAddOption::DEFAULT, None)?;
+        let tree = repo.find_tree(index.write_tree()?)?;
+        let signature = Signature::now("Ana", "ana@example.com")?;
+        repo.commit(
+            Some("HEAD"),
+            &signature,
+            &signature,
+            "Migrate database layer to connection pooling",
+            &tree,
+            &[],
+        )?;
+
+        let survey = survey(root, &Config::default())?;
+        assert_eq!(survey.files, 4);
+        assert_eq!(survey.commits, 1);
+        assert!(survey.stack.languages.contains(&"Rust".to_string()));
+        assert_eq!(survey.starter.path.as_deref(), Some("src"));
+        assert_eq!(survey.starter.command(), "noggin learn --max-commits 50 --path src");
+        assert_eq!(survey.starter.estimate.files, 2);
+        assert!(survey.starter.estimate.tokens <= survey.full.tokens);
+        assert_eq!(survey.providers, Config::default().llm.providers);
+        assert!(survey.full.cost_usd > 0.0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_survey_extrapolates_long_history() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let repo = Repository::init(temp_dir.path())?;
+        let root = temp_dir.path();
+        let signature = Signature::now("Ana", "ana@example.com")?;
+
+        let mut parent: Option<git2::Commit> = None;
+        for i in 0..4 {
+            fs::write(root.join(format!("module{}.rs", i)), "pub fn run() {}\n")?;
+            let mut index = repo.index()?;
+            index.add_all(["*"], git2::IndexAddOption::DEFAULT, None)?;
+            let tree = repo.find_tree(index.write_tree()?)?;
+            let parents: Vec<&git2::Commit> = parent.iter().collect();
+            let message = format!("Security fix in session handling, part {}", i);
+            let oid = repo.commit(Some("HEAD"), &signature, &signature, &message, &tree, &parents)?;
+            parent = Some(repo.find_commit(oid)?);
+        }
+
+        let full = survey_sampled(root, &Config::default(), 10)?;
+        let sampled = survey_sampled(root, &Config::default(), 2)?;
+        assert_eq!((full.commits, full.sampled), (4, 4));
+        assert_eq!((sampled.commits, sampled.sampled), (4, 2));
+        assert!(full.significant > 0);
+        assert_eq!(sampled.significant, full.significant);
+        assert!(sampled.top_commits.len() <= 2);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_starter_path_needs_a_dominant_directory() {
+        assert_eq!(starter_path(["a/x", "a/y", "b/z", "README"]), Some("a".to_string()));
+        assert_eq!(starter_path(["b/x", "a/y"]), Some("a".to_string()));
+        assert_eq!(starter_path(["src/x", "src/y"]), None);
+        assert_eq!(starter_path(["README"]), None);
+    }
+}
@@ -0,0 +1,178 @@
+//! Token counting for prompt budgets.
+//!
+//! Approximates the byte-pair tokenizers providers use without shipping
+//! their vocabularies. Text is split the way those tokenizers pre-split
+//! it (letter runs, digit runs, whitespace, symbols) and each piece is
+//! priced from its length. Counts err slightly high on code, which keeps
+//! prompts under a provider's real limit.
+
+use crate::config::Config;
+
+/// Tokens held back from the file budget for text appended after the
+/// prompt is built (output language instruction and the like)
+pub const RESERVED_TOKENS: usize = 256;
+
+/// Size limits applied while building a prompt
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PromptBudget {
+    /// Most tokens in one prompt; larger inputs are split or truncated
+    pub max_tokens: usize,
+    /// Most tokens of a single file's contents
+    pub max_file_tokens: usize,
+    /// Most bytes read from a single file
+    pub max_file_bytes: u64,
+}
+
+impl Default for PromptBudget {
+    fn default() -> Self {
+        Self::from_config(&Config::default())
+    }
+}
+
+impl PromptBudget {
+    /// The budget every enabled provider can accept: the smallest of
+    /// their `max_prompt_tokens`, each defaulting to `prompts.max_tokens`
+    pub fn from_config(config: &Config) -> Self {
+        let default = config.prompts.max_tokens;
+        let max_tokens = config
+            .llm
+            .providers
+            .iter()
+            .filter(|name| config.llm.is_enabled(name))
+            .map(|name| config.llm.max_prompt_tokens(name).unwrap_or(default))
+            .min()
+            .unwrap_or(default);
+
+        Self {
+            max_tokens,
+            max_file_tokens: config.prompts.max_file_tokens,
+            max_file_bytes: config.prompts.max_file_bytes,
+        }
+    }
+}
+
+/// Estimated token count of `text`
+pub fn count_tokens(text: &str) -> usize {
+    pieces(text).map(|(_, tokens)| tokens).sum()
+}
+
+/// Byte length of the longest prefix of `text` that fits in `max_tokens`.
+/// Always lands on a char boundary.
+pub fn token_prefix(text: &str, max_tokens: usize) -> usize {
+    let mut used = 0;
+    let mut end = 0;
+    for (piece_end, tokens) in pieces(text) {
+        if used + tokens > max_tokens {
+            break;
+        }
+        used += tokens;
+        end = piece_end;
+    }
+    end
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Class {
+    /// ASCII letters; common words are one token, longer runs split
+    /// about every five letters
+    Letter,
+    /// Other scripts cost about a token per character
+    OtherLetter,
+    /// Digits group in threes
+    Digit,
+    Space,
+    Symbol,
+}
+
+impl Class {
+    fn of(c: char) -> Self {
+        if c.is_ascii_alphabetic() {
+            Class::Letter
+        } else if c.is_alphabetic() {
+            Class::OtherLetter
+        } else if c.is_ascii_digit() {
+            Class::Digit
+        } else if c.is_whitespace() {
+            Class::Space
+        } else {
+            Class::Symbol
+        }
+    }
+
+    /// Longest run priced as a single token
+    fn max_run(self) -> usize {
+        match self {
+            Class::Letter => 5,
+            Class::Digit => 3,
+            Class::Space => usize::MAX,
+            Class::OtherLetter | Class::Symbol => 1,
+        }
+    }
+}
+
+/// Split into one-token pieces (plus free single spaces, which merge into
+/// the following word), yielding each piece's end offset and its cost
+fn pieces(text: &str) -> impl Iterator<Item = (usize, usize)> + '_ {
+    let mut chars = text.char_indices().peekable();
+    std::iter::from_fn(move || {
+        let (start, first) = chars.next()?;
+        let kind = Class::of(first);
+        let mut end = start + first.len_utf8();
+        let mut len = 1;
+
+        while len < kind.max_run() {
+            match chars.peek() {
+                Some(&(idx, c)) if Class::of(c) == kind => {
+                    chars.next();
+                    end = idx + c.len_utf8();
+                    len += 1;
+                }
+                _ => break,
+            }
+        }
+
+        let tokens = if kind == Class::Space && &text[start..end] == " " {
+            0
+        } else {
+            1
+        };
+        Some((end, tokens))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_count_tokens() {
+        assert_eq!(count_tokens(""), 0);
+        assert_eq!(count_tokens("hello world"), 2);
+        assert_eq!(count_tokens("fn main() {}"), 6);
+        assert_eq!(count_tokens("1234567"), 3);
+        assert_eq!(count_tokens("    indented\n"), 4);
+        assert_eq!(count_tokens("日本語"), 3);
+    }
+
+    #[test]
+    fn test_token_prefix_stays_on_boundaries() {
+        let text = "alpha beta 日本 gamma";
+        assert_eq!(token_prefix(text, 0), 0);
+        assert_eq!(&text[..token_prefix(text, 1)], "alpha ");
+        assert_eq!(&text[..token_prefix(text, 3)], "alpha beta 日");
+        assert_eq!(&text[..token_prefix(text, 4)], "alpha beta 日本 ");
+        assert_eq!(token_prefix(text, 100), text.len());
+    }
+
+    #[test]
+    fn test_budget_takes_smallest_provider_limit() {
+        let mut config = Config::default();
+        assert_eq!(PromptBudget::from_config(&config).max_tokens, 100_000);
+
+        config.llm.codex.max_prompt_tokens = Some(32_000);
+        assert_eq!(PromptBudget::from_config(&config).max_tokens, 32_000);
+
+        config.llm.codex.enabled = false;
+        assert_eq!(PromptBudget::from_config(&config).max_tokens, 100_000);
+    }
+}
@@ -2,12 +2,51 @@
 //!
 //! Takes synthesized ARF files, infers their category, generates
 //! filenames, and writes them to the appropriate subdirectory.
+//!
+//! `plan_arfs` runs the same decisions without touching disk, for
+//! `--dry-run`. The `_in` variants file every ARF under one given
+//! category instead of inferring it. [`apply_arfs`] takes all of these as
+//! [`WriteOptions`], plus a cap on new files: once it is reached, further
+//! new entries are held in `.noggin/pending/<category>/` for review rather
+//! than added to the knowledge base. With `dedupe_similarity` set, a new
+//! entry that is semantically close to an unapproved one already on disk
+//! or written earlier in the batch (under another slug) is merged into
+//! that file instead, and fields the two disagreed on are reported in
+//! [`WriteResult::merge_conflicts`]. Glossary facts
+//! (see [`crate::learn::glossary`]) are filed under `facts/glossary/`.
+//!
+//! New files are named by [`FileNaming`] (`writer.naming` in config).
+//! New entries missing fields their category template requires (see
+//! [`crate::templates`]) are held in `pending/` as well.
+//!
+//! Every entry carries a stable `id`, and an entry whose id is already in
+//! its directory updates that file whatever it is named, so switching
+//! strategies doesn't duplicate knowledge. Entries written before ids
+//! existed are matched by the slug of their `what`.
+//!
+//! [`apply_arfs`] reads and writes entries through a [`KnowledgeBackend`];
+//! the other entry points use the filesystem.
 
-use crate::arf::ArfFile;
-use crate::synthesis::merger::{infer_category, ArfCategory};
+use crate::arf::{ArfFile, NESTED_ARF_DIRS};
+use crate::conflicts::{contradicting_fields, ConflictRecord};
+use crate::index::embed::HashingEmbedder;
+use crate::index::SemanticIndex;
+use crate::learn::glossary::{is_glossary, GLOSSARY_DIR};
+use crate::learn::scanner::FileRename;
+use crate::manifest::Manifest;
+use crate::storage::{FilesystemBackend, KnowledgeBackend};
+use crate::synthesis::merger::{infer_category, merge_arf_fields, ArfCategory};
+use crate::templates::Templates;
+use crate::text::{line_diff, slugify};
 use anyhow::{Context, Result};
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
 use std::path::Path;
 
+pub use crate::storage::dry_run::{PlannedWrite, WriteAction};
+
 /// Result of writing ARF files
 #[derive(Debug)]
 pub struct WriteResult {
@@ -17,6 +56,73 @@ pub struct WriteResult {
     pub updated: usize,
     /// Number of unchanged ARF files skipped
     pub skipped: usize,
+    /// New entries merged into a similar existing ARF (counted in
+    /// `updated` or `skipped` as well)
+    pub merged: usize,
+    /// Fields a merged entry disagreed on with the ARF it was merged
+    /// into, as `<path>: <field>`; the merge kept one value
+    pub merge_conflicts: Vec<String>,
+    /// Paths of written or updated files, relative to .noggin/
+    pub paths: Vec<String>,
+    /// Conflict records created instead of overwriting approved ARFs,
+    /// relative to .noggin/
+    pub conflicts: Vec<String>,
+    /// New entries held in `pending/` by the cap, relative to .noggin/
+    pub pending: Vec<String>,
+    /// New entries held in `pending/` because they lack fields their
+    /// category template requires, relative to .noggin/
+    pub incomplete: Vec<String>,
+    /// Where each input ARF now lives, relative to .noggin/, in input
+    /// order; None if it was parked as a conflict or held in `pending/`
+    pub locations: Vec<Option<String>>,
+    /// Where each input ARF waits in `pending/`, relative to .noggin/, in
+    /// input order; None unless it was held there
+    pub held: Vec<Option<String>>,
+    /// What a dry run would have done; empty for real writes
+    pub planned: Vec<PlannedWrite>,
+}
+
+/// Knowledge base category subdirectories
+pub const CATEGORY_DIRS: &[&str] = &["decisions", "patterns", "bugs", "migrations", "facts"];
+
+/// Where new entries over the per-run cap wait for review
+pub const PENDING_DIR: &str = "pending";
+
+/// How new ARF files are named
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum FileNaming {
+    /// `use-pooling.arf`
+    #[default]
+    Slug,
+    /// `2025-06-01-use-pooling.arf`, dated when first written
+    DateSlug,
+    /// `0042-use-pooling.arf`, numbered per directory like ADRs
+    Sequence,
+    /// `3f2a9c41d0b7.arf`, from the entry's what/why/how when first written
+    Hash,
+}
+
+/// Digits in a [`FileNaming::Sequence`] number
+const SEQUENCE_WIDTH: usize = 4;
+
+/// Hex characters in a [`FileNaming::Hash`] name
+const HASH_LEN: usize = 12;
+
+/// How [`apply_arfs`] files a batch
+#[derive(Debug, Clone, Default)]
+pub struct WriteOptions {
+    /// File every ARF under this category instead of inferring it
+    pub category: Option<ArfCategory>,
+    /// Decide without writing anything (see [`plan_arfs`])
+    pub dry_run: bool,
+    /// New files the batch may add; later new entries go to `pending/`
+    pub max_new: Option<usize>,
+    /// Merge a new entry into an unapproved ARF at least this similar
+    /// (cosine) instead of writing it under its own slug
+    pub dedupe_similarity: Option<f64>,
+    /// Names for new files
+    pub naming: FileNaming,
 }
 
 /// Write ARF files to the appropriate .noggin/ subdirectories.
@@ -24,44 +130,487 @@ pub struct WriteResult {
 /// For each ARF, infers the category (decisions/patterns/bugs/migrations/facts),
 /// generates a filename from the `what` field, and writes the TOML file.
 /// Skips writing if an identical file already exists.
+///
+/// An ARF that lands on an approved one (same filename, or a `what` within
+/// edit distance 3) never overwrites it if `what` or `why` changed
+/// materially; a conflict record is written to `.noggin/conflicts/`
+/// instead. Non-contradicting updates keep the approval.
 pub fn write_arfs(noggin_path: &Path, arfs: &[ArfFile]) -> Result<WriteResult> {
+    apply_arfs(&FilesystemBackend::new(noggin_path), noggin_path, arfs, &WriteOptions::default())
+}
+
+/// Decide what `write_arfs` would do without writing anything. Counts,
+/// paths, and locations match a real write; `planned` lists each change
+/// with its diff.
+pub fn plan_arfs(noggin_path: &Path, arfs: &[ArfFile]) -> Result<WriteResult> {
+    let opts = WriteOptions {
+        dry_run: true,
+        ..Default::default()
+    };
+    apply_arfs(&FilesystemBackend::new(noggin_path), noggin_path, arfs, &opts)
+}
+
+/// `write_arfs`, with every ARF filed under `category`
+pub fn write_arfs_in(
+    noggin_path: &Path,
+    arfs: &[ArfFile],
+    category: ArfCategory,
+) -> Result<WriteResult> {
+    let opts = WriteOptions {
+        category: Some(category),
+        ..Default::default()
+    };
+    apply_arfs(&FilesystemBackend::new(noggin_path), noggin_path, arfs, &opts)
+}
+
+/// `plan_arfs`, with every ARF filed under `category`
+pub fn plan_arfs_in(
+    noggin_path: &Path,
+    arfs: &[ArfFile],
+    category: ArfCategory,
+) -> Result<WriteResult> {
+    let opts = WriteOptions {
+        category: Some(category),
+        dry_run: true,
+        ..Default::default()
+    };
+    apply_arfs(&FilesystemBackend::new(noggin_path), noggin_path, arfs, &opts)
+}
+
+/// Write (or with `dry_run`, plan) `arfs` to `backend` as `opts`
+/// describes. Conflict records and templates are kept under `noggin_path`.
+pub fn apply_arfs(
+    backend: &dyn KnowledgeBackend,
+    noggin_path: &Path,
+    arfs: &[ArfFile],
+    opts: &WriteOptions,
+) -> Result<WriteResult> {
+    let dry_run = opts.dry_run;
     let mut written = 0;
     let mut updated = 0;
     let mut skipped = 0;
+    let mut paths = Vec::new();
+    let mut conflicts = Vec::new();
+    let mut pending = Vec::new();
+    let mut locations = Vec::new();
+    let mut held_at = Vec::new();
+    let mut planned = Vec::new();
+
+    let approved = load_approved(backend);
+    let templates = Templates::load(noggin_path)?;
+    let mut incomplete = Vec::new();
+    let mut ids = load_ids(backend);
+    // Next sequence number per directory, read from disk on first use
+    let mut sequences: HashMap<&str, u32> = HashMap::new();
+    let embedder = HashingEmbedder::new();
+    let mut index = SemanticIndex::default();
+    if opts.dedupe_similarity.is_some() {
+        index.update(backend, &embedder)?;
+    }
+    let mut merged = 0;
+    let mut merge_conflicts = Vec::new();
+    // Entries written so far, for merging later ones into before they
+    // can be read back (dry runs write nothing)
+    let mut batch: HashMap<String, ArfFile> = HashMap::new();
+    let now = Utc::now();
 
     for arf in arfs {
-        let category_dir = category_dirname(&infer_category(arf));
-        let filename = slugify(&arf.what);
-        let file_path = noggin_path.join(category_dir).join(format!("{}.arf", filename));
+        // A category the entry names itself wins over the inferred one
+        let category_dir = match &opts.category {
+            Some(category) => category_dirname(category),
+            None => arf
+                .category
+                .as_deref()
+                .and_then(|named| CATEGORY_DIRS.iter().copied().find(|dir| *dir == named))
+                .unwrap_or_else(|| category_dirname(&infer_category(arf))),
+        };
+        // Glossary facts have a namespace of their own
+        let dir = if category_dir == "facts" && is_glossary(arf) {
+            GLOSSARY_DIR
+        } else {
+            category_dir
+        };
+        let id = arf.id_or_slug();
+        let key = format!("{}/{}", dir, id);
+        let is_new = !ids.contains_key(&key);
+        let mut relative = match ids.get(&key) {
+            Some(existing) => existing.clone(),
+            None => {
+                let next = match opts.naming {
+                    FileNaming::Sequence => {
+                        *sequences.entry(dir).or_insert_with(|| next_sequence(backend, dir))
+                    }
+                    _ => 0,
+                };
+                format!("{}/{}.arf", dir, file_stem(opts.naming, &id, arf, now, next))
+            }
+        };
+        // Approval only comes from people, never from model output
+        let mut arf = arf.clone();
+        arf.id = Some(id);
+        arf.approved = false;
+        arf.category = Some(category_dir.to_string());
+        arf.created_at = Some(now);
+        arf.updated_at = Some(now);
+
+        if let Some((approved_path, existing)) = find_approved_match(&approved, &relative, &arf) {
+            let fields = contradicting_fields(existing, &arf);
+            if !fields.is_empty() {
+                let record = ConflictRecord::new(approved_path.clone(), fields, existing.clone(), arf);
+                if dry_run {
+                    planned.push(PlannedWrite {
+                        path: record.relative_path(),
+                        action: WriteAction::Conflict,
+                        diff: String::new(),
+                    });
+                } else {
+                    record.save(noggin_path)?;
+                }
+                conflicts.push(record.relative_path());
+                locations.push(None);
+                held_at.push(None);
+                continue;
+            }
+            relative = approved_path.clone();
+            arf.approved = true;
+        }
+
+        // A near-duplicate under another slug absorbs the new entry
+        if let Some(threshold) = opts.dedupe_similarity {
+            if !arf.approved && !backend.exists(&relative) {
+                if let Some((similar, existing)) =
+                    find_similar(backend, &batch, &index, &embedder, &arf, threshold)
+                {
+                    let (mut combined, field_conflicts) = merge_arf_fields(&[
+                        ("existing".to_string(), existing),
+                        ("new".to_string(), arf),
+                    ]);
+                    // Differently worded whats are what makes it a near
+                    // duplicate rather than a conflict
+                    merge_conflicts.extend(
+                        field_conflicts
+                            .iter()
+                            .filter(|conflict| conflict.field != "what")
+                            .map(|conflict| format!("{}: {}", similar, conflict.field)),
+                    );
+                    combined.what = combined.what.trim().to_string();
+                    arf = combined;
+                    relative = similar;
+                    merged += 1;
+                }
+            }
+        }
+
+        let existing = batch
+            .get(&relative)
+            .cloned()
+            .or_else(|| backend.read_arf(&relative).ok().flatten());
+
+        // Fields people filled in for the category template survive
+        // updates; a new entry still missing some waits to be completed
+        if let Some(existing) = &existing {
+            templates.carry_over(category_dir, existing, &mut arf);
+        } else if !templates.missing(category_dir, &arf).is_empty() {
+            let held = hold_pending(backend, &relative, &arf, dry_run, &mut planned)?;
+            locations.push(None);
+            held_at.push(Some(held.clone()));
+            incomplete.push(held);
+            continue;
+        }
+
+        // Over the cap, a new entry waits for review instead
+        if !backend.exists(&relative) && opts.max_new.is_some_and(|max| written >= max) {
+            let held = hold_pending(backend, &relative, &arf, dry_run, &mut planned)?;
+            locations.push(None);
+            held_at.push(Some(held.clone()));
+            pending.push(held);
+            continue;
+        }
+
+        locations.push(Some(relative.clone()));
+        held_at.push(None);
 
         // Check if identical file already exists
-        if file_path.exists() {
-            if let Ok(existing) = ArfFile::from_toml(&file_path) {
-                if existing == *arf {
+        if backend.exists(&relative) {
+            if let Some(existing) = existing {
+                // Rules are written by people; models never propose them
+                if arf.rules.is_empty() {
+                    arf.rules = existing.rules.clone();
+                }
+                arf.source = existing.source.or(arf.source);
+                // Entries from before ids existed keep their file as is
+                arf.id = existing.id.clone();
+                arf.created_at = existing.created_at;
+                arf.updated_at = existing.updated_at;
+                // Entries from before categories were recorded get one only
+                // when they change anyway
+                if existing.category.is_none() {
+                    arf.category = None;
+                }
+                if existing == arf {
                     skipped += 1;
                     continue;
                 }
+                arf.category = Some(category_dir.to_string());
+                arf.updated_at = Some(now);
                 // File exists but content changed
-                arf.to_toml(&file_path)
-                    .with_context(|| format!("Failed to update {}", file_path.display()))?;
+                if dry_run {
+                    let before = backend.read(&relative).ok().flatten().unwrap_or_default();
+                    planned.push(PlannedWrite {
+                        path: relative.clone(),
+                        action: WriteAction::Update,
+                        diff: line_diff(&before, &arf_toml(&arf)?),
+                    });
+                } else {
+                    backend
+                        .write_arf(&relative, &arf)
+                        .with_context(|| format!("Failed to update {}", relative))?;
+                }
+                if opts.dedupe_similarity.is_some() {
+                    index.insert(&relative, &arf, &embedder);
+                    batch.insert(relative.clone(), arf);
+                }
                 updated += 1;
+                paths.push(relative);
                 continue;
             }
         }
 
+        // Later entries in the batch with this id update the new file
+        if is_new {
+            ids.insert(key, relative.clone());
+            if let Some(next) = sequences.get_mut(dir) {
+                *next += 1;
+            }
+        }
+
         // Write new file
-        arf.to_toml(&file_path)
-            .with_context(|| format!("Failed to write {}", file_path.display()))?;
+        if dry_run {
+            planned.push(PlannedWrite {
+                path: relative.clone(),
+                action: WriteAction::Create,
+                diff: line_diff("", &arf_toml(&arf)?),
+            });
+        } else {
+            backend
+                .write_arf(&relative, &arf)
+                .with_context(|| format!("Failed to write {}", relative))?;
+        }
+        if opts.dedupe_similarity.is_some() {
+            index.insert(&relative, &arf, &embedder);
+            batch.insert(relative.clone(), arf);
+        }
         written += 1;
+        paths.push(relative);
     }
 
     Ok(WriteResult {
         written,
         updated,
         skipped,
+        merged,
+        merge_conflicts,
+        paths,
+        conflicts,
+        pending,
+        incomplete,
+        locations,
+        held: held_at,
+        planned,
     })
 }
 
+/// Put `arf` in `pending/` at `relative` instead of the knowledge base,
+/// returning where it went
+fn hold_pending(
+    backend: &dyn KnowledgeBackend,
+    relative: &str,
+    arf: &ArfFile,
+    dry_run: bool,
+    planned: &mut Vec<PlannedWrite>,
+) -> Result<String> {
+    let held = format!("{}/{}", PENDING_DIR, relative);
+    if dry_run {
+        planned.push(PlannedWrite {
+            path: held.clone(),
+            action: WriteAction::Pending,
+            diff: String::new(),
+        });
+    } else {
+        backend
+            .write_arf(&held, arf)
+            .with_context(|| format!("Failed to write {}", held))?;
+    }
+    Ok(held)
+}
+
+/// The TOML `ArfFile::to_toml` would write
+fn arf_toml(arf: &ArfFile) -> Result<String> {
+    toml::to_string_pretty(arf).context("Failed to serialize ARF file to TOML")
+}
+
+/// Load every approved ARF in the knowledge base, keyed by relative path.
+/// Unreadable files are ignored here; they surface elsewhere.
+fn load_approved(backend: &dyn KnowledgeBackend) -> Vec<(String, ArfFile)> {
+    category_arfs(backend)
+        .into_iter()
+        .filter(|(_, arf)| arf.approved)
+        .collect()
+}
+
+/// Every ARF's file keyed by `<dir>/<id>`, where `dir` is relative to
+/// .noggin/. An ARF without an id is keyed by the slug of its `what`.
+fn load_ids(backend: &dyn KnowledgeBackend) -> HashMap<String, String> {
+    let mut ids = HashMap::new();
+    let mut aliases = Vec::new();
+    for (path, arf) in category_arfs(backend) {
+        let Some((dir, _)) = path.rsplit_once('/') else {
+            continue;
+        };
+        let slug = slugify(&arf.what);
+        let id = arf.id.unwrap_or_else(|| slug.clone());
+        if id != slug {
+            aliases.push((format!("{}/{}", dir, slug), path.clone()));
+        }
+        ids.entry(format!("{}/{}", dir, id)).or_insert(path);
+    }
+    // Ids slugged before slugify transliterated (`café` rather than
+    // `cafe`) are also found by today's slug, so updates keep the entry's
+    // file and id instead of duplicating it; real ids win over aliases
+    for (key, path) in aliases {
+        ids.entry(key).or_insert(path);
+    }
+    ids
+}
+
+/// Point entries citing a renamed file at its new path, so prune
+/// doesn't take them for orphans once the old path is gone. Returns how
+/// many were rewritten.
+pub fn rename_cited_files(
+    backend: &dyn KnowledgeBackend,
+    manifest: &Manifest,
+    renames: &[FileRename],
+) -> Result<usize> {
+    if renames.is_empty() {
+        return Ok(0);
+    }
+    let mut rewritten = 0;
+    for (path, mut arf) in category_arfs(backend) {
+        let mut changed = false;
+        for rename in renames {
+            let from = manifest.path_key(&rename.from);
+            if !arf.context.files.iter().any(|f| manifest.path_key(f) == from) {
+                continue;
+            }
+            let to = manifest.path_key(&rename.to);
+            arf.context
+                .files
+                .retain(|f| manifest.path_key(f) != from && manifest.path_key(f) != to);
+            arf.context.files.push(rename.to.clone());
+            changed = true;
+        }
+        if changed {
+            backend.write_arf(&path, &arf)?;
+            rewritten += 1;
+        }
+    }
+    Ok(rewritten)
+}
+
+/// Readable ARFs in the category directories and their nested
+/// namespaces, sorted by path
+fn category_arfs(backend: &dyn KnowledgeBackend) -> Vec<(String, ArfFile)> {
+    backend
+        .list_arfs()
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|path| {
+            path.rsplit_once('/')
+                .is_some_and(|(dir, _)| CATEGORY_DIRS.contains(&dir) || NESTED_ARF_DIRS.contains(&dir))
+        })
+        .filter_map(|path| {
+            let arf = backend.read_arf(&path).ok().flatten()?;
+            Some((path, arf))
+        })
+        .collect()
+}
+
+/// File name, without `.arf`, for a new entry
+fn file_stem(naming: FileNaming, id: &str, arf: &ArfFile, now: DateTime<Utc>, sequence: u32) -> String {
+    match naming {
+        FileNaming::Slug => id.to_string(),
+        FileNaming::DateSlug => format!("{}-{}", now.format("%Y-%m-%d"), id),
+        FileNaming::Sequence => format!("{:0width$}-{}", sequence, id, width = SEQUENCE_WIDTH),
+        FileNaming::Hash => {
+            let mut hasher = Sha256::new();
+            hasher.update(format!("{}\n{}\n{}", arf.what.trim(), arf.why.trim(), arf.how.trim()));
+            let hex = format!("{:x}", hasher.finalize());
+            hex[..HASH_LEN].to_string()
+        }
+    }
+}
+
+/// One past the highest number prefixing a file in `dir`. Dates from
+/// [`FileNaming::DateSlug`] names don't count.
+fn next_sequence(backend: &dyn KnowledgeBackend, dir: &str) -> u32 {
+    backend
+        .list_arfs()
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|path| path.strip_prefix(dir)?.strip_prefix('/'))
+        .filter(|name| !name.contains('/'))
+        .filter_map(|name| {
+            let dated = name
+                .get(..10)
+                .is_some_and(|date| NaiveDate::parse_from_str(date, "%Y-%m-%d").is_ok());
+            let digits: String = name.chars().take_while(char::is_ascii_digit).collect();
+            let numbered = !dated && name[digits.len()..].starts_with('-');
+            numbered.then(|| digits.parse::<u32>().ok()).flatten()
+        })
+        .max()
+        .map_or(1, |highest| highest + 1)
+}
+
+/// The unapproved ARF most similar to `arf`, if it reaches `threshold`:
+/// one written earlier in the batch, or on disk
+fn find_similar(
+    backend: &dyn KnowledgeBackend,
+    batch: &HashMap<String, ArfFile>,
+    index: &SemanticIndex,
+    embedder: &HashingEmbedder,
+    arf: &ArfFile,
+    threshold: f64,
+) -> Option<(String, ArfFile)> {
+    index
+        .similar_to(arf, embedder)
+        .into_iter()
+        .take_while(|(_, score)| f64::from(*score) >= threshold)
+        .find_map(|(path, _)| {
+            let existing = match batch.get(&path) {
+                Some(written) => written.clone(),
+                None => backend.read_arf(&path).ok().flatten()?,
+            };
+            (!existing.approved).then_some((path, existing))
+        })
+}
+
+/// Find the approved ARF a new ARF would replace: the same target path,
+/// else one whose `what` is in the same similarity cluster.
+fn find_approved_match<'a>(
+    approved: &'a [(String, ArfFile)],
+    relative: &str,
+    arf: &ArfFile,
+) -> Option<&'a (String, ArfFile)> {
+    if let Some(found) = approved.iter().find(|(path, _)| path == relative) {
+        return Some(found);
+    }
+
+    let what = arf.what.to_lowercase();
+    approved
+        .iter()
+        .find(|(_, existing)| edit_distance::edit_distance(&what, existing.what.to_lowercase()) < 3)
+}
+
 /// Map ArfCategory to subdirectory name
 fn category_dirname(category: &ArfCategory) -> &'static str {
     match category {
@@ -73,50 +622,12 @@ fn category_dirname(category: &ArfCategory) -> &'static str {
     }
 }
 
-/// Convert a `what` field to a filename-safe slug.
-///
-/// Lowercases, replaces non-alphanumeric with hyphens, collapses
-/// multiple hyphens, trims leading/trailing hyphens, truncates to 50 chars.
-fn slugify(text: &str) -> String {
-    let slug: String = text
-        .to_lowercase()
-        .chars()
-        .map(|c| if c.is_alphanumeric() { c } else { '-' })
-        .collect();
-
-    // Collapse multiple hyphens and trim
-    let mut result = String::new();
-    let mut prev_hyphen = false;
-    for c in slug.chars() {
-        if c == '-' {
-            if !prev_hyphen && !result.is_empty() {
-                result.push('-');
-            }
-            prev_hyphen = true;
-        } else {
-            result.push(c);
-            prev_hyphen = false;
-        }
-    }
-
-    // Trim trailing hyphen and truncate
-    let trimmed = result.trim_end_matches('-');
-    if trimmed.len() > 50 {
-        // Find a clean break point
-        let truncated = &trimmed[..50];
-        truncated
-            .rfind('-')
-            .map(|i| &truncated[..i])
-            .unwrap_or(truncated)
-            .to_string()
-    } else {
-        trimmed.to_string()
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::arf::ArfSource;
+    use crate::rules::{PatternRule, RuleKind};
+    use std::fs;
     use tempfile::TempDir;
 
     fn setup_noggin_dir() -> TempDir {
@@ -156,6 +667,20 @@ mod tests {
         assert_eq!(slugify("foo   bar---baz"), "foo-bar-baz");
     }
 
+    #[test]
+    fn test_write_long_non_ascii_what() -> Result<()> {
+        let noggin_dir = setup_noggin_dir();
+        let what = "Verwende Verbindungspooling für größere Datenbankzugriffe über mehrere Dienste";
+        let arf = ArfFile::new(what, "Weniger Overhead", "PgBouncer konfigurieren");
+
+        let result = write_arfs(noggin_dir.path(), &[arf])?;
+        assert_eq!(result.written, 1);
+        assert!(result.paths[0].contains("/verwende-verbindungspooling-fur-grossere"));
+        assert!(result.paths[0].is_ascii());
+
+        Ok(())
+    }
+
     #[test]
     fn test_category_dirname() {
         assert_eq!(category_dirname(&ArfCategory::Decision), "decisions");
@@ -165,6 +690,34 @@ mod tests {
         assert_eq!(category_dirname(&ArfCategory::Fact), "facts");
     }
 
+    #[test]
+    fn test_rename_cited_files() -> Result<()> {
+        let noggin_dir = setup_noggin_dir();
+        let mut moved = ArfFile::new("Pool connections", "Overhead", "PgBouncer");
+        moved.context.files = vec!["src/DB.rs".to_string(), "src/main.rs".to_string()];
+        let untouched = ArfFile::new("Log errors", "Debugging", "tracing");
+        write_arfs(noggin_dir.path(), &[moved, untouched])?;
+
+        let backend = FilesystemBackend::new(noggin_dir.path());
+        let manifest = Manifest {
+            path_case: crate::manifest::PathCase::Insensitive,
+            ..Default::default()
+        };
+        let renames = vec![FileRename {
+            from: "src/db.rs".to_string(),
+            to: "src/storage/db.rs".to_string(),
+        }];
+        assert_eq!(rename_cited_files(&backend, &manifest, &renames)?, 1);
+
+        let (_, arf) = category_arfs(&backend)
+            .into_iter()
+            .find(|(_, arf)| arf.what == "Pool connections")
+            .unwrap();
+        assert_eq!(arf.context.files, vec!["src/main.rs", "src/storage/db.rs"]);
+
+        Ok(())
+    }
+
     #[test]
     fn test_write_new_arf() -> Result<()> {
         let noggin_dir = setup_noggin_dir();
@@ -188,6 +741,139 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_write_in_fixed_category() -> Result<()> {
+        let noggin_dir = setup_noggin_dir();
+        let arf = ArfFile::new(
+            "Open TODO markers",
+            "FIXME: retry on timeout",
+            "- src/net.rs:12 FIXME retry on timeout",
+        );
+
+        let result = write_arfs_in(noggin_dir.path(), &[arf], ArfCategory::Fact)?;
+        assert_eq!(result.paths, vec!["facts/open-todo-markers.arf"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_glossary_entries_get_their_own_namespace() -> Result<()> {
+        let noggin_dir = setup_noggin_dir();
+        let mut arf = ArfFile::new("Shard: one partition of the index", "Used widely", "See src/shard.rs");
+        arf.tags = vec!["glossary".to_string()];
+        arf.category = Some("facts".to_string());
+
+        let result = write_arfs(noggin_dir.path(), &[arf])?;
+
+        assert_eq!(result.paths, vec!["facts/glossary/shard-one-partition-of-the-index.arf"]);
+        assert!(noggin_dir
+            .path()
+            .join("facts/glossary/shard-one-partition-of-the-index.arf")
+            .exists());
+        Ok(())
+    }
+
+    #[test]
+    fn test_naming_strategies() -> Result<()> {
+        let noggin_dir = setup_noggin_dir();
+        let noggin = noggin_dir.path();
+        fs::write(noggin.join("decisions/0041-older-decision.arf"), "what = [").unwrap();
+        fs::write(noggin.join("decisions/2025-06-01-dated.arf"), "what = [").unwrap();
+        let arfs = [
+            ArfFile::new("Decided to use pooling", "Limits", "deadpool"),
+            ArfFile::new("Decided to use sqlx", "Compile-time checks", "sqlx::query!"),
+        ];
+
+        let opts = |naming| WriteOptions {
+            category: Some(ArfCategory::Decision),
+            naming,
+            ..Default::default()
+        };
+        let result = apply_arfs(&FilesystemBackend::new(noggin), noggin, &arfs, &opts(FileNaming::Sequence))?;
+        assert_eq!(
+            result.paths,
+            vec![
+                "decisions/0042-decided-to-use-pooling.arf",
+                "decisions/0043-decided-to-use-sqlx.arf",
+            ]
+        );
+        let written = ArfFile::from_toml(&noggin.join(&result.paths[0]))?;
+        assert_eq!(written.id.as_deref(), Some("decided-to-use-pooling"));
+
+        // Another strategy finds the same entries by id
+        let mut changed = arfs.clone();
+        changed[0].how = "deadpool with a 30s timeout".to_string();
+        let result = apply_arfs(&FilesystemBackend::new(noggin), noggin, &changed, &opts(FileNaming::Hash))?;
+        assert_eq!((result.written, result.updated, result.skipped), (0, 1, 1));
+        assert_eq!(result.paths, vec!["decisions/0042-decided-to-use-pooling.arf"]);
+
+        let now = Utc::now();
+        let stem = file_stem(FileNaming::DateSlug, "use-pooling", &arfs[0], now, 0);
+        assert_eq!(stem, format!("{}-use-pooling", now.format("%Y-%m-%d")));
+        let hashed = file_stem(FileNaming::Hash, "use-pooling", &arfs[0], now, 0);
+        assert_eq!(hashed.len(), HASH_LEN);
+        assert!(hashed.chars().all(|c| c.is_ascii_hexdigit()));
+        Ok(())
+    }
+
+    #[test]
+    fn test_entries_without_id_match_by_slug() -> Result<()> {
+        let noggin_dir = setup_noggin_dir();
+        let noggin = noggin_dir.path();
+        let legacy = ArfFile::new("Decided to use pooling", "Limits", "deadpool");
+        legacy.to_toml(&noggin.join("decisions/decided-to-use-pooling.arf"))?;
+
+        let opts = WriteOptions {
+            category: Some(ArfCategory::Decision),
+            naming: FileNaming::DateSlug,
+            ..Default::default()
+        };
+        let mut updated = legacy.clone();
+        updated.why = "Limits connections".to_string();
+        let result = apply_arfs(&FilesystemBackend::new(noggin), noggin, &[updated], &opts)?;
+
+        assert_eq!(result.paths, vec!["decisions/decided-to-use-pooling.arf"]);
+        let on_disk = ArfFile::from_toml(&noggin.join(&result.paths[0]))?;
+        assert_eq!(on_disk.id, None);
+        assert_eq!(on_disk.why, "Limits connections");
+        Ok(())
+    }
+
+    #[test]
+    fn test_template_fields_are_enforced() -> Result<()> {
+        let noggin_dir = setup_noggin_dir();
+        let noggin = noggin_dir.path();
+        fs::create_dir_all(noggin.join("templates"))?;
+        fs::write(
+            noggin.join("templates/migrations.toml"),
+            "[fields]\nrollback = \"How to undo it\"\n",
+        )?;
+        let mut existing = ArfFile::new("Move to Postgres", "Scale", "pgloader");
+        existing
+            .context
+            .extra
+            .insert("rollback".to_string(), toml::Value::String("Restore the dump".to_string()));
+        existing.to_toml(&noggin.join("migrations/move-to-postgres.arf"))?;
+
+        let mut update = ArfFile::new("Move to Postgres", "Scale out", "pgloader");
+        update.category = Some("migrations".to_string());
+        let mut new = ArfFile::new("Split users table", "Size", "Online migration");
+        new.category = Some("migrations".to_string());
+        let result = write_arfs(noggin, &[update, new])?;
+
+        assert_eq!(result.paths, vec!["migrations/move-to-postgres.arf"]);
+        assert_eq!(result.incomplete, vec!["pending/migrations/split-users-table.arf"]);
+        assert!(result.pending.is_empty());
+        let updated = ArfFile::from_toml(&noggin.join("migrations/move-to-postgres.arf"))?;
+        assert_eq!(updated.why, "Scale out");
+        assert_eq!(
+            updated.context.extra.get("rollback").and_then(|v| v.as_str()),
+            Some("Restore the dump")
+        );
+        assert!(noggin.join("pending/migrations/split-users-table.arf").exists());
+        Ok(())
+    }
+
     #[test]
     fn test_write_skips_identical() -> Result<()> {
         let noggin_dir = setup_noggin_dir();
@@ -198,7 +884,7 @@ mod tests {
         );
 
         // Write once
-        write_arfs(noggin_dir.path(), &[arf.clone()])?;
+        write_arfs(noggin_dir.path(), std::slice::from_ref(&arf))?;
 
         // Write again - should skip
         let result = write_arfs(noggin_dir.path(), &[arf])?;
@@ -232,6 +918,103 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_write_stamps_category_and_times() -> Result<()> {
+        let noggin_dir = setup_noggin_dir();
+        let path = noggin_dir.path().join("decisions/retry-payment-webhooks.arf");
+        let mut arf = ArfFile::new("Retry payment webhooks", "Providers time out", "Backoff v1");
+        arf.category = Some("decisions".to_string());
+        arf.source = Some(ArfSource::CommitAnalysis);
+
+        write_arfs(noggin_dir.path(), std::slice::from_ref(&arf))?;
+        let first = ArfFile::from_toml(&path)?;
+        assert_eq!(first.category.as_deref(), Some("decisions"));
+        assert!(first.created_at.is_some());
+        assert_eq!(first.created_at, first.updated_at);
+
+        // Unchanged content keeps both times
+        assert_eq!(write_arfs(noggin_dir.path(), std::slice::from_ref(&arf))?.skipped, 1);
+
+        arf.how = "Backoff v2".to_string();
+        arf.source = Some(ArfSource::FileAnalysis);
+        write_arfs(noggin_dir.path(), &[arf])?;
+        let second = ArfFile::from_toml(&path)?;
+        assert_eq!(second.created_at, first.created_at);
+        assert!(second.updated_at > first.updated_at);
+        assert_eq!(second.source, Some(ArfSource::CommitAnalysis));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_unchanged_v1_entry_left_alone() -> Result<()> {
+        let noggin_dir = setup_noggin_dir();
+        let path = noggin_dir.path().join("decisions/retry-payment-webhooks.arf");
+        let v1 = "what = \"Retry payment webhooks\"\nwhy = \"Providers time out\"\nhow = \"Backoff\"\n\n[context]\n";
+        fs::write(&path, v1)?;
+
+        let mut arf = ArfFile::new("Retry payment webhooks", "Providers time out", "Backoff");
+        arf.category = Some("decisions".to_string());
+        assert_eq!(write_arfs(noggin_dir.path(), std::slice::from_ref(&arf))?.skipped, 1);
+        assert_eq!(fs::read_to_string(&path)?, v1);
+
+        arf.how = "Backoff with jitter".to_string();
+        write_arfs(noggin_dir.path(), &[arf])?;
+        assert_eq!(ArfFile::from_toml(&path)?.category.as_deref(), Some("decisions"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_update_finds_entry_slugged_before_transliteration() -> Result<()> {
+        let noggin_dir = setup_noggin_dir();
+        let path = noggin_dir.path().join("decisions/café-au-lait-caching.arf");
+        let mut old = ArfFile::new("Café au lait caching", "Warm reads", "Redis v1");
+        old.id = Some("café-au-lait-caching".to_string());
+        old.to_toml(&path)?;
+
+        let mut arf = ArfFile::new("Café au lait caching", "Warm reads", "Redis v2");
+        arf.category = Some("decisions".to_string());
+        let result = write_arfs(noggin_dir.path(), &[arf])?;
+        assert_eq!((result.written, result.updated), (0, 1));
+        let updated = ArfFile::from_toml(&path)?;
+        assert_eq!(updated.how, "Redis v2");
+        assert_eq!(updated.id.as_deref(), Some("café-au-lait-caching"));
+        assert!(!noggin_dir.path().join("decisions/cafe-au-lait-caching.arf").exists());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_update_keeps_rules() -> Result<()> {
+        let noggin_dir = setup_noggin_dir();
+        let arf = ArfFile::new(
+            "Use connection pooling pattern",
+            "Reduces database overhead",
+            "Configure PgBouncer v1",
+        );
+        write_arfs(noggin_dir.path(), std::slice::from_ref(&arf))?;
+
+        let path = noggin_dir.path().join("patterns/use-connection-pooling-pattern.arf");
+        let mut ruled = ArfFile::from_toml(&path)?;
+        ruled.rules.push(PatternRule {
+            kind: RuleKind::Forbid,
+            pattern: "PgConnection::connect".to_string(),
+            when: None,
+            paths: Vec::new(),
+            message: None,
+        });
+        ruled.to_toml(&path)?;
+
+        let mut changed = arf;
+        changed.how = "Configure PgBouncer v2".to_string();
+        let result = write_arfs(noggin_dir.path(), &[changed])?;
+        assert_eq!(result.updated, 1);
+        assert_eq!(ArfFile::from_toml(&path)?.rules.len(), 1);
+
+        Ok(())
+    }
+
     #[test]
     fn test_write_categorizes_correctly() -> Result<()> {
         let noggin_dir = setup_noggin_dir();
@@ -261,4 +1044,235 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_contradiction_of_approved_creates_conflict() -> Result<()> {
+        let noggin_dir = setup_noggin_dir();
+        let mut approved = ArfFile::new(
+            "Use connection pooling pattern",
+            "Reduces database overhead",
+            "Configure PgBouncer with transaction mode",
+        );
+        approved.approved = true;
+        approved.to_toml(&noggin_dir.path().join("patterns/use-connection-pooling-pattern.arf"))?;
+
+        let proposed = ArfFile::new(
+            "Use connection pooling pattern",
+            "Required by the hosting provider's connection limits",
+            "Configure PgBouncer with transaction mode",
+        );
+        let result = write_arfs(noggin_dir.path(), &[proposed])?;
+
+        assert_eq!(result.written + result.updated, 0);
+        assert_eq!(result.conflicts, vec!["conflicts/use-connection-pooling-pattern.toml"]);
+
+        let on_disk = ArfFile::from_toml(
+            &noggin_dir.path().join("patterns/use-connection-pooling-pattern.arf"),
+        )?;
+        assert_eq!(on_disk, approved);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_plan_writes_nothing() -> Result<()> {
+        let noggin_dir = setup_noggin_dir();
+        let arf = ArfFile::new(
+            "Use connection pooling pattern",
+            "Reduces database overhead",
+            "Configure PgBouncer v1",
+        );
+        let path = noggin_dir.path().join("patterns/use-connection-pooling-pattern.arf");
+
+        let plan = plan_arfs(noggin_dir.path(), std::slice::from_ref(&arf))?;
+        assert_eq!(plan.written, 1);
+        assert_eq!(plan.planned[0].action, WriteAction::Create);
+        assert!(plan.planned[0].diff.contains("+how = \"Configure PgBouncer v1\""));
+        assert!(!path.exists());
+
+        write_arfs(noggin_dir.path(), std::slice::from_ref(&arf))?;
+        let mut changed = arf;
+        changed.how = "Configure PgBouncer v2".to_string();
+        let plan = plan_arfs(noggin_dir.path(), std::slice::from_ref(&changed))?;
+        assert_eq!(plan.updated, 1);
+        assert_eq!(plan.planned[0].action, WriteAction::Update);
+        let diff = &plan.planned[0].diff;
+        assert!(diff.starts_with(
+            "-how = \"Configure PgBouncer v1\"\n+how = \"Configure PgBouncer v2\"\n"
+        ));
+        assert!(diff.contains("-updated_at = ") && diff.contains("+updated_at = "));
+        assert_eq!(diff.lines().count(), 4);
+        assert_eq!(ArfFile::from_toml(&path)?.how, "Configure PgBouncer v1");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_cap_holds_new_entries_in_pending() -> Result<()> {
+        let noggin_dir = setup_noggin_dir();
+        let existing = ArfFile::new("Use connection pooling pattern", "Why", "How v1");
+        write_arfs(noggin_dir.path(), std::slice::from_ref(&existing))?;
+
+        let mut updated = existing;
+        updated.how = "How v2".to_string();
+        let arfs = vec![
+            ArfFile::new("Use retry pattern", "Why", "How"),
+            updated,
+            ArfFile::new("Use builder pattern", "Why", "How"),
+        ];
+        let opts = WriteOptions {
+            max_new: Some(1),
+            ..Default::default()
+        };
+        let result = apply_arfs(&FilesystemBackend::new(noggin_dir.path()), noggin_dir.path(), &arfs, &opts)?;
+
+        // Updates don't count against the cap
+        assert_eq!(result.written, 1);
+        assert_eq!(result.updated, 1);
+        assert_eq!(result.pending, vec!["pending/patterns/use-builder-pattern.arf"]);
+        assert_eq!(result.locations[2], None);
+        assert!(noggin_dir.path().join("pending/patterns/use-builder-pattern.arf").exists());
+        assert!(!noggin_dir.path().join("patterns/use-builder-pattern.arf").exists());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_similar_entry_merges_into_existing() -> Result<()> {
+        let noggin_dir = setup_noggin_dir();
+        let mut existing = ArfFile::new(
+            "Use connection pooling for Postgres",
+            "Reduces connection overhead",
+            "PgBouncer in transaction mode",
+        );
+        existing.add_file("src/db.rs");
+        write_arfs(noggin_dir.path(), &[existing])?;
+
+        let mut similar = ArfFile::new(
+            "Use Postgres connection pooling",
+            "Reduce database connection overhead",
+            "Configure PgBouncer",
+        );
+        similar.add_file("src/pool.rs");
+        let unrelated = ArfFile::new("Use builder pattern", "Readable construction", "Builder structs");
+        let opts = WriteOptions {
+            dedupe_similarity: Some(0.75),
+            ..Default::default()
+        };
+        let result = apply_arfs(&FilesystemBackend::new(noggin_dir.path()), noggin_dir.path(), &[similar, unrelated], &opts)?;
+
+        assert_eq!(result.merged, 1);
+        assert_eq!(result.written, 1);
+        assert_eq!(
+            result.locations[0].as_deref(),
+            Some("facts/use-connection-pooling-for-postgres.arf")
+        );
+        assert!(!noggin_dir.path().join("facts/use-postgres-connection-pooling.arf").exists());
+        let on_disk = ArfFile::from_toml(
+            &noggin_dir.path().join("facts/use-connection-pooling-for-postgres.arf"),
+        )?;
+        assert_eq!(on_disk.context.files, vec!["src/db.rs", "src/pool.rs"]);
+        assert!(result.merge_conflicts.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_similar_entries_in_one_batch_merge() -> Result<()> {
+        let noggin_dir = setup_noggin_dir();
+        let mut first = ArfFile::new(
+            "Use connection pooling for Postgres",
+            "Reduces connection overhead",
+            "PgBouncer in transaction mode",
+        );
+        first.add_outcome("result", "p99 latency halved");
+        let mut second = ArfFile::new(
+            "Use Postgres connection pooling",
+            "Reduce database connection overhead",
+            "Configure PgBouncer",
+        );
+        second.add_outcome("result", "fewer connection errors");
+        let opts = WriteOptions {
+            dedupe_similarity: Some(0.75),
+            ..Default::default()
+        };
+        let result = apply_arfs(
+            &FilesystemBackend::new(noggin_dir.path()),
+            noggin_dir.path(),
+            &[first, second],
+            &opts,
+        )?;
+
+        assert_eq!((result.written, result.merged), (1, 1));
+        assert_eq!(result.locations[0], result.locations[1]);
+        assert_eq!(
+            result.merge_conflicts,
+            vec!["facts/use-connection-pooling-for-postgres.arf: context.outcome.result"]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_model_cannot_self_approve() -> Result<()> {
+        let noggin_dir = setup_noggin_dir();
+        let mut arf = ArfFile::new("Use connection pooling pattern", "Why", "How");
+        arf.approved = true;
+        write_arfs(noggin_dir.path(), &[arf])?;
+
+        let on_disk = ArfFile::from_toml(
+            &noggin_dir.path().join("patterns/use-connection-pooling-pattern.arf"),
+        )?;
+        assert!(!on_disk.approved);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_elaboration_of_approved_keeps_approval() -> Result<()> {
+        let noggin_dir = setup_noggin_dir();
+        let mut approved = ArfFile::new(
+            "Use connection pooling pattern",
+            "Reduces database overhead",
+            "Configure PgBouncer",
+        );
+        approved.approved = true;
+        approved.to_toml(&noggin_dir.path().join("patterns/use-connection-pooling-pattern.arf"))?;
+
+        let proposed = ArfFile::new(
+            "Use connection-pooling pattern",
+            "Reduces database overhead.",
+            "Configure PgBouncer with transaction mode",
+        );
+        let result = write_arfs(noggin_dir.path(), &[proposed])?;
+
+        assert_eq!(result.updated, 1);
+        assert!(result.conflicts.is_empty());
+
+        let on_disk = ArfFile::from_toml(
+            &noggin_dir.path().join("patterns/use-connection-pooling-pattern.arf"),
+        )?;
+        assert!(on_disk.approved);
+        assert_eq!(on_disk.how, "Configure PgBouncer with transaction mode");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_apply_arfs_to_memory_backend() -> Result<()> {
+        let noggin_dir = TempDir::new()?;
+        let backend = crate::storage::MemoryBackend::default();
+        let arfs = [ArfFile::new("Use connection pooling", "Limits connections", "deadpool")];
+
+        let opts = WriteOptions::default();
+        let result = apply_arfs(&backend, noggin_dir.path(), &arfs, &opts)?;
+        assert_eq!(result.written, 1);
+        assert_eq!(backend.list_arfs()?, result.paths);
+        assert!(crate::arf::arf_paths(noggin_dir.path()).is_empty());
+
+        let again = apply_arfs(&backend, noggin_dir.path(), &arfs, &opts)?;
+        assert_eq!((again.written, again.skipped), (0, 1));
+
+        Ok(())
+    }
 }
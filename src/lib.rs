@@ -1,13 +1,32 @@
 pub mod arf;
 pub mod commands;
+pub mod config;
+pub mod conflicts;
+pub mod decay;
 pub mod error;
 pub mod git;
+pub mod grep;
+pub mod index;
+pub mod issues;
 pub mod learn;
 pub mod llm;
+pub mod lock;
 pub mod manifest;
+pub mod markdown;
 pub mod mcp;
+pub mod policy;
+pub mod metrics;
 pub mod query;
+pub mod retention;
+pub mod rules;
+pub mod saved_queries;
+pub mod schema;
+pub mod snapshot;
+pub mod storage;
 pub mod synthesis;
+pub mod templates;
+pub mod text;
+pub mod time;
 
 pub use arf::{ArfFile, ArfContext};
 pub use error::{Error, Result};
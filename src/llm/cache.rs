@@ -0,0 +1,135 @@
+//! In-memory cache of provider responses
+//!
+//! Keyed by provider name and a hash of the prompt, so running the same
+//! prompt against the same provider twice in one process (e.g. both arms
+//! of `noggin experiment`) only spends one request.
+
+use crate::llm::parallel::{ModelFailure, ModelResult, ParallelResult, Scheduler};
+use crate::llm::stream::ignore_chunks;
+use crate::llm::LLMProvider;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+
+#[derive(Debug, Default)]
+pub struct ResponseCache {
+    entries: HashMap<(String, String), String>,
+    hits: usize,
+    misses: usize,
+}
+
+impl ResponseCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Responses served from the cache
+    pub fn hits(&self) -> usize {
+        self.hits
+    }
+
+    /// Requests actually sent to a provider
+    pub fn misses(&self) -> usize {
+        self.misses
+    }
+
+    /// Whether `provider`'s response to `prompt` would come from the cache
+    pub fn is_cached(&self, provider: &str, prompt: &str) -> bool {
+        let prompt_hash = format!("{:x}", Sha256::digest(prompt.as_bytes()));
+        self.entries.contains_key(&(provider.to_string(), prompt_hash))
+    }
+
+    /// Query every provider, answering from the cache where possible and
+    /// sending the rest concurrently through `scheduler`. Failures are not
+    /// cached.
+    pub async fn query(
+        &mut self,
+        scheduler: &Scheduler,
+        providers: &[Box<dyn LLMProvider>],
+        prompt: &str,
+    ) -> ParallelResult {
+        let prompt_hash = format!("{:x}", Sha256::digest(prompt.as_bytes()));
+        let mut successes = Vec::new();
+        let mut pending = Vec::new();
+
+        for provider in providers {
+            let key = (provider.name().to_string(), prompt_hash.clone());
+            match self.entries.get(&key) {
+                Some(response) => {
+                    self.hits += 1;
+                    successes.push(ModelResult {
+                        model: key.0,
+                        response: response.clone(),
+                    });
+                }
+                None => pending.push(provider),
+            }
+        }
+
+        self.misses += pending.len();
+        let results = futures::future::join_all(pending.into_iter().map(|provider| async move {
+            let result = scheduler
+                .query(provider.as_ref(), prompt, &ignore_chunks)
+                .await;
+            (provider.name().to_string(), result)
+        }))
+        .await;
+
+        let mut failures = Vec::new();
+        for (model, result) in results {
+            match result {
+                Ok(response) => {
+                    self.entries
+                        .insert((model.clone(), prompt_hash.clone()), response.clone());
+                    successes.push(ModelResult { model, response });
+                }
+                Err(e) => failures.push(ModelFailure {
+                    model,
+                    error: e.to_string(),
+                }),
+            }
+        }
+
+        ParallelResult { successes, failures }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::Error;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    struct Counting {
+        calls: Arc<AtomicUsize>,
+    }
+
+    #[async_trait::async_trait]
+    impl LLMProvider for Counting {
+        async fn query(&self, prompt: &str) -> Result<String, Error> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            Ok(format!("echo: {}", prompt))
+        }
+
+        fn name(&self) -> &str {
+            "counting"
+        }
+    }
+
+    #[tokio::test]
+    async fn test_repeated_prompt_is_served_from_cache() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let providers: Vec<Box<dyn LLMProvider>> = vec![Box::new(Counting { calls: calls.clone() })];
+        let mut cache = ResponseCache::new();
+        let scheduler = Scheduler::default();
+
+        let first = cache.query(&scheduler, &providers, "hello").await;
+        let second = cache.query(&scheduler, &providers, "hello").await;
+        cache.query(&scheduler, &providers, "other").await;
+
+        assert_eq!(first.successes[0].response, second.successes[0].response);
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+        assert_eq!(cache.hits(), 1);
+        assert_eq!(cache.misses(), 2);
+    }
+}
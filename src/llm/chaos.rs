@@ -0,0 +1,278 @@
+//! Fault injection for the provider layer
+//!
+//! With `NOGGIN_CHAOS=1` in the environment, every configured provider is
+//! wrapped so its requests misbehave the way real ones do: some are slow,
+//! some time out, some come back as malformed TOML, and some are rate
+//! limited. How often each happens is set in `[llm.chaos]`:
+//!
+//! ```toml
+//! [llm.chaos]
+//! delay = 0.3        # chance a request is held back by delay_ms
+//! delay_ms = 2000
+//! timeout = 0.1      # chance it fails as a timeout after delay_ms
+//! malformed = 0.1    # chance the response is cut short and garbled
+//! rate_limit = 0.1   # chance it is rejected with a 429
+//! seed = 42          # repeatable faults; random per run when unset
+//! ```
+//!
+//! It exists to check that partial failures (warnings, checkpoints, the
+//! manifest) are handled the way learn promises, and never runs unless the
+//! variable is set.
+
+use crate::error::{Error, LlmError};
+use crate::llm::stream::OnChunk;
+use crate::llm::LLMProvider;
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::{DefaultHasher, RandomState};
+use std::env;
+use std::hash::{BuildHasher, Hash, Hasher};
+use std::sync::Mutex;
+use std::time::Duration;
+use tracing::{debug, warn};
+
+/// Environment variable that turns fault injection on
+pub const CHAOS_ENV: &str = "NOGGIN_CHAOS";
+
+/// Fault probabilities from `[llm.chaos]`, each 0.0 to 1.0
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChaosConfig {
+    #[serde(default = "default_delay")]
+    pub delay: f64,
+    #[serde(default = "default_delay_ms")]
+    pub delay_ms: u64,
+    #[serde(default = "default_fault")]
+    pub timeout: f64,
+    #[serde(default = "default_fault")]
+    pub malformed: f64,
+    #[serde(default = "default_fault")]
+    pub rate_limit: f64,
+    /// `retry_after` reported with injected rate limits
+    #[serde(default = "default_retry_after_secs")]
+    pub retry_after_secs: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>,
+}
+
+fn default_delay() -> f64 {
+    0.2
+}
+
+fn default_delay_ms() -> u64 {
+    2000
+}
+
+fn default_fault() -> f64 {
+    0.1
+}
+
+fn default_retry_after_secs() -> u64 {
+    1
+}
+
+impl Default for ChaosConfig {
+    fn default() -> Self {
+        Self {
+            delay: default_delay(),
+            delay_ms: default_delay_ms(),
+            timeout: default_fault(),
+            malformed: default_fault(),
+            rate_limit: default_fault(),
+            retry_after_secs: default_retry_after_secs(),
+            seed: None,
+        }
+    }
+}
+
+/// Whether `NOGGIN_CHAOS` asks for fault injection
+pub fn enabled() -> bool {
+    env::var(CHAOS_ENV).is_ok_and(|value| !value.is_empty() && value != "0")
+}
+
+/// What happens to one request
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Fault {
+    None,
+    Timeout,
+    Malformed,
+    RateLimit,
+}
+
+/// A provider whose requests fail at random, per [`ChaosConfig`]
+pub struct ChaosProvider {
+    inner: Box<dyn LLMProvider>,
+    config: ChaosConfig,
+    /// SplitMix64 state
+    state: Mutex<u64>,
+}
+
+impl ChaosProvider {
+    pub fn new(inner: Box<dyn LLMProvider>, config: ChaosConfig) -> Self {
+        // Each provider gets its own sequence, repeatable when seeded
+        let mut hasher = match config.seed {
+            Some(_) => DefaultHasher::new(),
+            None => RandomState::new().build_hasher(),
+        };
+        inner.name().hash(&mut hasher);
+        let state = config.seed.unwrap_or(0) ^ hasher.finish();
+        Self {
+            inner,
+            config,
+            state: Mutex::new(state),
+        }
+    }
+
+    /// A number in [0, 1)
+    fn roll(&self) -> f64 {
+        let mut state = self.state.lock().unwrap();
+        *state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = *state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^= z >> 31;
+        (z >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Pick this request's fault and whether it is delayed
+    fn plan(&self) -> (Fault, bool) {
+        let delayed = self.roll() < self.config.delay;
+        let roll = self.roll();
+        let mut threshold = 0.0;
+        for (chance, fault) in [
+            (self.config.rate_limit, Fault::RateLimit),
+            (self.config.timeout, Fault::Timeout),
+            (self.config.malformed, Fault::Malformed),
+        ] {
+            threshold += chance.clamp(0.0, 1.0);
+            if roll < threshold {
+                return (fault, delayed);
+            }
+        }
+        (Fault::None, delayed)
+    }
+}
+
+#[async_trait::async_trait]
+impl LLMProvider for ChaosProvider {
+    async fn query(&self, prompt: &str) -> Result<String, Error> {
+        self.query_streaming(prompt, &|_| {}).await
+    }
+
+    async fn query_streaming(&self, prompt: &str, on_chunk: OnChunk<'_>) -> Result<String, Error> {
+        let (fault, delayed) = self.plan();
+        let name = self.name().to_string();
+        debug!("Chaos for {}: {:?}{}", name, fault, if delayed { ", delayed" } else { "" });
+        if delayed || fault == Fault::Timeout {
+            tokio::time::sleep(Duration::from_millis(self.config.delay_ms)).await;
+        }
+
+        match fault {
+            Fault::RateLimit => Err(Error::Llm(LlmError::RateLimitExceeded {
+                model: name,
+                retry_after: Some(self.config.retry_after_secs),
+            })),
+            Fault::Timeout => Err(Error::Llm(LlmError::RequestFailed {
+                model: name,
+                source: format!("Timeout after {}ms (injected)", self.config.delay_ms),
+            })),
+            Fault::Malformed => {
+                let response = self.inner.query(prompt).await?;
+                let garbled = garble(&response);
+                on_chunk(&garbled);
+                Ok(garbled)
+            }
+            Fault::None => self.inner.query_streaming(prompt, on_chunk).await,
+        }
+    }
+
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+}
+
+/// Cut `response` off mid-way and leave an unterminated table and string,
+/// like a stream that died or a model that lost track
+fn garble(response: &str) -> String {
+    let cut = response
+        .char_indices()
+        .nth(response.chars().count() / 2)
+        .map_or(response.len(), |(idx, _)| idx);
+    format!("{}\n[[entry\nwhat = \"unterminated\n", &response[..cut])
+}
+
+/// Wrap each provider for fault injection when [`enabled`]
+pub fn wrap_if_enabled(
+    providers: Vec<Box<dyn LLMProvider>>,
+    config: &ChaosConfig,
+) -> Vec<Box<dyn LLMProvider>> {
+    if !enabled() {
+        return providers;
+    }
+    warn!("{} is set: injecting provider faults", CHAOS_ENV);
+    providers
+        .into_iter()
+        .map(|provider| Box::new(ChaosProvider::new(provider, config.clone())) as Box<dyn LLMProvider>)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::synthesis::parse_model_response;
+
+    struct Fixed;
+
+    #[async_trait::async_trait]
+    impl LLMProvider for Fixed {
+        async fn query(&self, _prompt: &str) -> Result<String, Error> {
+            Ok("what = \"Use pooling\"\nwhy = \"Limits\"\nhow = \"deadpool\"\n".to_string())
+        }
+
+        fn name(&self) -> &str {
+            "fixed"
+        }
+    }
+
+    fn chaos(config: ChaosConfig) -> ChaosProvider {
+        ChaosProvider::new(Box::new(Fixed), config)
+    }
+
+    fn only(timeout: f64, malformed: f64, rate_limit: f64) -> ChaosConfig {
+        ChaosConfig {
+            delay: 0.0,
+            delay_ms: 0,
+            timeout,
+            malformed,
+            rate_limit,
+            retry_after_secs: 0,
+            seed: Some(7),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_each_fault() {
+        let response = chaos(only(0.0, 0.0, 0.0)).query("p").await.unwrap();
+        assert!(parse_model_response("fixed", &response).is_ok());
+
+        let garbled = chaos(only(0.0, 1.0, 0.0)).query("p").await.unwrap();
+        assert!(parse_model_response("fixed", &garbled).is_err());
+
+        let err = chaos(only(1.0, 0.0, 0.0)).query("p").await.unwrap_err();
+        assert!(err.to_string().contains("injected"));
+
+        let err = chaos(only(0.0, 0.0, 1.0)).query("p").await.unwrap_err();
+        assert!(matches!(err, Error::Llm(LlmError::RateLimitExceeded { .. })));
+    }
+
+    #[test]
+    fn test_seeded_faults_repeat() {
+        let config = ChaosConfig {
+            seed: Some(42),
+            ..ChaosConfig::default()
+        };
+        let plans = |provider: ChaosProvider| (0..50).map(|_| provider.plan()).collect::<Vec<_>>();
+        let first = plans(chaos(config.clone()));
+        assert_eq!(first, plans(chaos(config)));
+        assert!(first.iter().any(|(fault, _)| *fault != Fault::None));
+        assert!(first.iter().any(|(fault, _)| *fault == Fault::None));
+    }
+}
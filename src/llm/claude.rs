@@ -1,29 +1,38 @@
 //! Claude CLI subprocess invocation with JSON parsing
 //!
 //! Invokes the `claude` CLI as a subprocess with JSON output mode,
-//! handles timeouts, rate limits, and provides retry logic.
+//! handles timeouts, classifies errors (see
+//! [`classify_cli_error`]), and retries transient failures
+//! under its [`RetryPolicy`]. Rate limits are left to the run's
+//! [`Scheduler`](crate::llm::parallel::Scheduler).
 
 use crate::error::{Error, LlmError};
+use crate::llm::cli_error::classify_cli_error;
+use crate::llm::retry::RetryPolicy;
 use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
 use std::process::Stdio;
 use std::time::Duration;
 use tokio::process::Command;
-use tracing::{debug, warn};
+use tracing::debug;
 
 /// Configuration for Claude CLI client
 #[derive(Debug, Clone)]
 pub struct ClaudeConfig {
+    /// CLI to spawn (default: `claude`, resolved on PATH)
+    pub binary: PathBuf,
     /// Timeout for subprocess execution (default: 30s)
     pub timeout_secs: u64,
-    /// Maximum retry attempts (default: 3)
-    pub max_retries: u32,
+    /// Retries for transient failures (default: 3 attempts)
+    pub retry: RetryPolicy,
 }
 
 impl Default for ClaudeConfig {
     fn default() -> Self {
         Self {
+            binary: PathBuf::from("claude"),
             timeout_secs: 30,
-            max_retries: 3,
+            retry: RetryPolicy::default(),
         }
     }
 }
@@ -48,37 +57,16 @@ impl ClaudeClient {
 
     /// Query Claude CLI with retry logic
     pub async fn query(&self, prompt: &str) -> Result<String, Error> {
-        let mut attempts = 0;
-        let mut backoff_ms = 1000;
-
-        loop {
-            attempts += 1;
-            debug!("Claude query attempt {} of {}", attempts, self.config.max_retries);
-
-            match self.query_once(prompt).await {
-                Ok(response) => return Ok(response),
-                Err(e) if attempts >= self.config.max_retries => {
-                    warn!("Claude query failed after {} attempts", attempts);
-                    return Err(e);
-                }
-                Err(e) => {
-                    if self.should_retry(&e) {
-                        warn!("Claude query failed (attempt {}), retrying in {}ms: {}", attempts, backoff_ms, e);
-                        tokio::time::sleep(Duration::from_millis(backoff_ms)).await;
-                        backoff_ms *= 2; // Exponential backoff
-                    } else {
-                        warn!("Claude query failed with non-retryable error: {}", e);
-                        return Err(e);
-                    }
-                }
-            }
-        }
+        self.config
+            .retry
+            .run("claude", || self.query_once(prompt))
+            .await
     }
 
     /// Execute a single query attempt without retry
     async fn query_once(&self, prompt: &str) -> Result<String, Error> {
         // Build command: claude exec --json -s read-only "prompt"
-        let mut cmd = Command::new("claude");
+        let mut cmd = Command::new(&self.config.binary);
         cmd.args(["exec", "--json", "-s", "read-only", prompt])
             .stdout(Stdio::piped())
             .stderr(Stdio::piped())
@@ -113,7 +101,7 @@ impl ClaudeClient {
         // Check exit code
         if !output.status.success() {
             let stderr = String::from_utf8_lossy(&output.stderr);
-            return Err(self.parse_error(&stderr));
+            return Err(classify_cli_error("claude", &stderr));
         }
 
         // Parse JSON response
@@ -134,58 +122,6 @@ impl ClaudeClient {
         debug!("Claude query completed successfully");
         Ok(response.agent_message)
     }
-
-    /// Parse error from stderr to determine error type
-    fn parse_error(&self, stderr: &str) -> Error {
-        let lower = stderr.to_lowercase();
-        
-        // Check for rate limit indicators
-        if lower.contains("429") || lower.contains("rate limit") || lower.contains("quota exceeded") {
-            // Try to extract retry-after from stderr
-            let retry_after = self.extract_retry_after(stderr);
-            return Error::Llm(LlmError::RateLimitExceeded {
-                model: "claude".to_string(),
-                retry_after,
-            });
-        }
-
-        // Check for authentication errors
-        if lower.contains("unauthorized") || lower.contains("authentication") || lower.contains("401") {
-            return Error::Llm(LlmError::AuthenticationFailed("claude".to_string()));
-        }
-
-        // Check for model unavailable (503)
-        if lower.contains("503") || lower.contains("unavailable") || lower.contains("service unavailable") {
-            return Error::Llm(LlmError::ModelUnavailable("claude".to_string()));
-        }
-
-        // Generic error
-        Error::Llm(LlmError::RequestFailed {
-            model: "claude".to_string(),
-            source: stderr.to_string(),
-        })
-    }
-
-    /// Extract retry-after duration from error message
-    fn extract_retry_after(&self, stderr: &str) -> Option<u64> {
-        // Look for patterns like "retry after 60 seconds" or "retry-after: 60"
-        let re = regex::Regex::new(r"(?i)retry[- ]after:?\s*(\d+)").ok()?;
-        re.captures(stderr)?
-            .get(1)?
-            .as_str()
-            .parse()
-            .ok()
-    }
-
-    /// Check if error should be retried
-    fn should_retry(&self, error: &Error) -> bool {
-        matches!(
-            error,
-            Error::Llm(LlmError::RequestFailed { .. })
-                | Error::Llm(LlmError::RateLimitExceeded { .. })
-                | Error::Llm(LlmError::ModelUnavailable(_))
-        )
-    }
 }
 
 impl Default for ClaudeClient {
@@ -223,67 +159,7 @@ mod tests {
     fn test_config_defaults() {
         let config = ClaudeConfig::default();
         assert_eq!(config.timeout_secs, 30);
-        assert_eq!(config.max_retries, 3);
-    }
-
-    #[test]
-    fn test_parse_rate_limit_error() {
-        let client = ClaudeClient::new();
-        let stderr = "Error: 429 Too Many Requests - rate limit exceeded";
-        let error = client.parse_error(stderr);
-        assert!(matches!(
-            error,
-            Error::Llm(LlmError::RateLimitExceeded { .. })
-        ));
-    }
-
-    #[test]
-    fn test_parse_auth_error() {
-        let client = ClaudeClient::new();
-        let stderr = "Error: 401 Unauthorized - authentication failed";
-        let error = client.parse_error(stderr);
-        assert!(matches!(
-            error,
-            Error::Llm(LlmError::AuthenticationFailed(_))
-        ));
-    }
-
-    #[test]
-    fn test_parse_unavailable_error() {
-        let client = ClaudeClient::new();
-        let stderr = "Error: 503 Service Unavailable";
-        let error = client.parse_error(stderr);
-        assert!(matches!(
-            error,
-            Error::Llm(LlmError::ModelUnavailable(_))
-        ));
-    }
-
-    #[test]
-    fn test_extract_retry_after() {
-        let client = ClaudeClient::new();
-        assert_eq!(
-            client.extract_retry_after("retry after 60 seconds"),
-            Some(60)
-        );
-        assert_eq!(
-            client.extract_retry_after("retry-after: 120"),
-            Some(120)
-        );
-        assert_eq!(client.extract_retry_after("no retry info"), None);
-    }
-
-    #[test]
-    fn test_should_retry() {
-        let client = ClaudeClient::new();
-        let retryable = Error::Llm(LlmError::RateLimitExceeded {
-            model: "claude".to_string(),
-            retry_after: None,
-        });
-        assert!(client.should_retry(&retryable));
-
-        let not_retryable = Error::Llm(LlmError::AuthenticationFailed("claude".to_string()));
-        assert!(!client.should_retry(&not_retryable));
+        assert_eq!(config.retry.max_attempts, 3);
     }
 
     #[test]
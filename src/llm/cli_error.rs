@@ -0,0 +1,162 @@
+//! Classify failures of subprocess providers from their stderr
+//!
+//! The provider CLIs exit non-zero for everything, so the only hint about
+//! why is what they print. Sorting rate limits, authentication problems,
+//! and outages into their own [`LlmError`] variants keeps the retry policy
+//! from retrying failures that can't succeed and lets the scheduler back
+//! off on rate limits.
+
+use crate::error::{Error, LlmError};
+
+const RATE_LIMIT: &[&str] = &[
+    "429",
+    "rate limit",
+    "rate-limit",
+    "too many requests",
+    "quota exceeded",
+    "resource_exhausted",
+];
+
+const AUTHENTICATION: &[&str] = &[
+    "401",
+    "unauthorized",
+    "authentication",
+    "api key not valid",
+    "invalid api key",
+    "not logged in",
+];
+
+const UNAVAILABLE: &[&str] = &["503", "unavailable", "overloaded"];
+
+const TIMEOUT: &[&str] = &["timed out", "timeout", "deadline exceeded"];
+
+/// Error for a `model` CLI that exited unsuccessfully with `stderr`.
+/// Codes and phrases only count as whole words, so a request id or a
+/// line number containing `401` isn't an authentication failure.
+pub fn classify_cli_error(model: &str, stderr: &str) -> Error {
+    let lower = stderr.to_lowercase();
+    let mentions = |needles: &[&str]| whole_words(needles).is_match(&lower);
+
+    if mentions(RATE_LIMIT) {
+        return Error::Llm(LlmError::RateLimitExceeded {
+            model: model.to_string(),
+            retry_after: extract_retry_after(stderr),
+        });
+    }
+    if mentions(AUTHENTICATION) {
+        return Error::Llm(LlmError::AuthenticationFailed(model.to_string()));
+    }
+    if mentions(UNAVAILABLE) {
+        return Error::Llm(LlmError::ModelUnavailable(model.to_string()));
+    }
+
+    let source = if mentions(TIMEOUT) {
+        format!("Timeout: {}", stderr.trim())
+    } else {
+        stderr.to_string()
+    };
+    Error::Llm(LlmError::RequestFailed {
+        model: model.to_string(),
+        source,
+    })
+}
+
+/// Pattern matching any of `needles` between word boundaries
+fn whole_words(needles: &[&str]) -> regex::Regex {
+    let alternatives: Vec<String> = needles.iter().map(|needle| regex::escape(needle)).collect();
+    regex::Regex::new(&format!(r"\b(?:{})\b", alternatives.join("|")))
+        .expect("escaped needles form a valid pattern")
+}
+
+/// Extract retry-after duration from error message
+fn extract_retry_after(stderr: &str) -> Option<u64> {
+    // Look for patterns like "retry after 60 seconds" or "retry-after: 60"
+    let re = regex::Regex::new(r"(?i)retry[- ]after:?\s*(\d+)").ok()?;
+    re.captures(stderr)?
+        .get(1)?
+        .as_str()
+        .parse()
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify_rate_limit() {
+        let error = classify_cli_error("claude", "Error: 429 Too Many Requests, retry after 60 seconds");
+        assert!(matches!(
+            error,
+            Error::Llm(LlmError::RateLimitExceeded {
+                retry_after: Some(60),
+                ..
+            })
+        ));
+
+        let error = classify_cli_error("gemini", "[API Error: RESOURCE_EXHAUSTED]");
+        assert!(matches!(
+            error,
+            Error::Llm(LlmError::RateLimitExceeded {
+                retry_after: None,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_classify_auth_and_unavailable() {
+        assert!(matches!(
+            classify_cli_error("codex", "Error: 401 Unauthorized - authentication failed"),
+            Error::Llm(LlmError::AuthenticationFailed(model)) if model == "codex"
+        ));
+        assert!(matches!(
+            classify_cli_error("gemini", "API key not valid. Please pass a valid API key."),
+            Error::Llm(LlmError::AuthenticationFailed(_))
+        ));
+        assert!(matches!(
+            classify_cli_error("claude", "Error: 503 Service Unavailable"),
+            Error::Llm(LlmError::ModelUnavailable(_))
+        ));
+    }
+
+    #[test]
+    fn test_classify_timeout_and_other() {
+        match classify_cli_error("codex", "stream error: request timed out\n") {
+            Error::Llm(LlmError::RequestFailed { source, .. }) => {
+                assert_eq!(source, "Timeout: stream error: request timed out")
+            }
+            other => panic!("unexpected error: {:?}", other),
+        }
+        assert!(matches!(
+            classify_cli_error("codex", "unexpected argument '--json'"),
+            Error::Llm(LlmError::RequestFailed { .. })
+        ));
+    }
+
+    #[test]
+    fn test_codes_and_words_match_whole() {
+        for stderr in [
+            "request 84012 failed: invalid JSON at line 4290",
+            "upstream id 5031 not found",
+            "Error: unauthorizedAccessHandler missing",
+            "config option read_timeout_ms is not supported",
+        ] {
+            assert!(
+                matches!(
+                    classify_cli_error("codex", stderr),
+                    Error::Llm(LlmError::RequestFailed { ref source, .. }) if !source.starts_with("Timeout")
+                ),
+                "{}",
+                stderr
+            );
+        }
+    }
+
+    #[test]
+    fn test_extract_retry_after() {
+        assert_eq!(extract_retry_after("retry after 60 seconds"), Some(60));
+        assert_eq!(extract_retry_after("retry-after: 120"), Some(120));
+        assert_eq!(extract_retry_after("no retry info"), None);
+    }
+}
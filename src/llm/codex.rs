@@ -4,7 +4,10 @@
 //! Codex writes JSON to stderr instead of stdout.
 
 use crate::error::{Error, LlmError};
+use crate::llm::cli_error::classify_cli_error;
+use crate::llm::retry::RetryPolicy;
 use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
 use std::process::Stdio;
 use std::time::Duration;
 use tokio::process::Command;
@@ -13,20 +16,33 @@ use tracing::debug;
 /// Codex CLI client
 #[derive(Debug, Clone)]
 pub struct CodexClient {
+    /// CLI to spawn (default: `codex`, resolved on PATH)
+    pub binary: PathBuf,
     /// Timeout for subprocess execution (default: 120s)
     pub timeout_secs: u64,
+    /// Retries for transient failures (default: 3 attempts)
+    pub retry: RetryPolicy,
 }
 
 impl CodexClient {
     /// Create a new Codex client with default configuration
     pub fn new() -> Self {
-        Self { timeout_secs: 120 }
+        Self {
+            binary: PathBuf::from("codex"),
+            timeout_secs: 120,
+            retry: RetryPolicy::default(),
+        }
     }
 
-    /// Query Codex CLI and return the response
+    /// Query Codex CLI and return the response, retrying transient failures
     pub async fn query(&self, prompt: &str) -> Result<String, Error> {
+        self.retry.run("codex", || self.query_once(prompt)).await
+    }
+
+    /// Execute a single query attempt without retry
+    async fn query_once(&self, prompt: &str) -> Result<String, Error> {
         // Build command: codex exec --json -s read-only "prompt"
-        let mut cmd = Command::new("codex");
+        let mut cmd = Command::new(&self.binary);
         cmd.args(["exec", "--json", "-s", "read-only", prompt])
             .stdout(Stdio::piped())
             .stderr(Stdio::piped())
@@ -60,10 +76,7 @@ impl CodexClient {
         // Check exit code
         if !output.status.success() {
             let stderr = String::from_utf8_lossy(&output.stderr);
-            return Err(Error::Llm(LlmError::RequestFailed {
-                model: "codex".to_string(),
-                source: stderr.to_string(),
-            }));
+            return Err(classify_cli_error("codex", &stderr));
         }
 
         // Parse JSON response from stderr (codex writes to stderr)
@@ -0,0 +1,396 @@
+//! User-defined providers backed by a local command
+//!
+//! Each `[[llm.custom]]` entry in config names a command template. The
+//! prompt reaches the command through one of:
+//!
+//! - `{prompt_file}`: replaced with the path of a temp file holding the prompt
+//! - `{prompt}`: replaced with the prompt itself, as a single argument
+//! - stdin, when the template has neither placeholder
+//!
+//! The template is split into arguments like a shell would (whitespace,
+//! single and double quotes) but no shell runs it. The response is read
+//! from stdout, optionally through a JSON pointer and then an `extract`
+//! regex, so wrappers that add banners or JSON envelopes can participate
+//! without code changes. Stdout is streamed to
+//! [`LLMProvider::query_streaming`](crate::llm::LLMProvider::query_streaming)
+//! callers as it is written, before any of those rules apply.
+
+use crate::error::{Error, LlmError};
+use crate::llm::cli_error::classify_cli_error;
+use crate::llm::retry::{RetryPolicy, RetryStream};
+use crate::llm::stream::{ignore_chunks, wait_with_streamed_stdout, OnChunk};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::Path;
+use std::process::Stdio;
+use std::time::Duration;
+use tempfile::NamedTempFile;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+use tracing::debug;
+
+const PROMPT_FILE: &str = "{prompt_file}";
+const PROMPT: &str = "{prompt}";
+
+/// How the command's stdout is read
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    /// Stdout is the response
+    #[default]
+    Text,
+    /// Stdout is JSON; the response is the string at `json_pointer`
+    Json,
+}
+
+/// Configuration for a custom command provider
+#[derive(Debug, Clone)]
+pub struct CustomConfig {
+    /// Provider name used in `llm.providers` and reports
+    pub name: String,
+    /// Command template, e.g. `mytool --input {prompt_file}`
+    pub command: String,
+    pub output: OutputFormat,
+    /// JSON pointer (RFC 6901) to the response, e.g. `/result/text`;
+    /// empty means the whole document
+    pub json_pointer: String,
+    /// Regex applied last; the first capture group (or the whole match)
+    /// becomes the response
+    pub extract: Option<String>,
+    /// Timeout for subprocess execution (default: 300s)
+    pub timeout_secs: u64,
+    /// Retries for transient failures
+    pub retry: RetryPolicy,
+}
+
+/// Provider running a user-defined command
+#[derive(Debug)]
+pub struct CustomClient {
+    config: CustomConfig,
+    args: Vec<String>,
+    extract: Option<Regex>,
+}
+
+impl CustomClient {
+    /// Create a client, checking the template and `extract` regex up front
+    pub fn new(config: CustomConfig) -> anyhow::Result<Self> {
+        let args = split_command(&config.command)?;
+        if args.is_empty() {
+            anyhow::bail!("Custom provider '{}' has an empty command", config.name);
+        }
+        let extract = match &config.extract {
+            Some(pattern) => Some(Regex::new(pattern).map_err(|e| {
+                anyhow::anyhow!("Invalid extract pattern for '{}': {}", config.name, e)
+            })?),
+            None => None,
+        };
+
+        Ok(Self {
+            config,
+            args,
+            extract,
+        })
+    }
+
+    /// Run the command on a prompt and return the parsed response
+    pub async fn query(&self, prompt: &str) -> Result<String, Error> {
+        self.query_streaming(prompt, &ignore_chunks).await
+    }
+
+    /// Run the command, passing raw stdout to `on_chunk` as it arrives,
+    /// and return the parsed response, retrying transient failures
+    pub async fn query_streaming(&self, prompt: &str, on_chunk: OnChunk<'_>) -> Result<String, Error> {
+        let stream = RetryStream::new(on_chunk);
+        let forward = |chunk: &str| stream.forward(chunk);
+        self.config
+            .retry
+            .run_streaming(&self.config.name, &stream, || self.query_once(prompt, &forward))
+            .await
+    }
+
+    /// Run the command once without retry
+    async fn query_once(&self, prompt: &str, on_chunk: OnChunk<'_>) -> Result<String, Error> {
+        // The file is removed when the handle drops, after the command exits
+        let prompt_file = if self.args.iter().any(|a| a.contains(PROMPT_FILE)) {
+            Some(write_prompt_file(&self.config.name, prompt).map_err(|e| self.failed(e))?)
+        } else {
+            None
+        };
+        let stdout = self
+            .run(prompt, prompt_file.as_ref().map(NamedTempFile::path), on_chunk)
+            .await?;
+        self.parse_output(&stdout)
+    }
+
+    async fn run(
+        &self,
+        prompt: &str,
+        prompt_file: Option<&Path>,
+        on_chunk: OnChunk<'_>,
+    ) -> Result<String, Error> {
+        let use_stdin = !self
+            .args
+            .iter()
+            .any(|a| a.contains(PROMPT_FILE) || a.contains(PROMPT));
+        let args: Vec<String> = self
+            .args
+            .iter()
+            .map(|arg| {
+                let arg = match prompt_file {
+                    Some(path) => arg.replace(PROMPT_FILE, &path.to_string_lossy()),
+                    None => arg.clone(),
+                };
+                arg.replace(PROMPT, prompt)
+            })
+            .collect();
+
+        let mut cmd = Command::new(&args[0]);
+        cmd.args(&args[1..])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .stdin(if use_stdin {
+                Stdio::piped()
+            } else {
+                Stdio::null()
+            });
+
+        debug!(
+            "Executing custom provider {}: {} [prompt: {} chars]",
+            self.config.name,
+            self.args[0],
+            prompt.len()
+        );
+
+        let mut child = cmd
+            .spawn()
+            .map_err(|e| self.failed(format!("Failed to spawn process: {}", e)))?;
+        if use_stdin {
+            if let Some(mut stdin) = child.stdin.take() {
+                stdin
+                    .write_all(prompt.as_bytes())
+                    .await
+                    .map_err(|e| self.failed(format!("Failed to write prompt: {}", e)))?;
+            }
+        }
+
+        let timeout_duration = Duration::from_secs(self.config.timeout_secs);
+        let output = tokio::time::timeout(timeout_duration, wait_with_streamed_stdout(child, on_chunk))
+            .await
+            .map_err(|_| self.failed(format!("Timeout after {}s", self.config.timeout_secs)))?
+            .map_err(|e| self.failed(format!("Process error: {}", e)))?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(classify_cli_error(&self.config.name, &stderr));
+        }
+
+        String::from_utf8(output.stdout)
+            .map_err(|e| self.invalid(format!("Invalid UTF-8 in stdout: {}", e)))
+    }
+
+    /// Apply the configured output rules to the command's stdout
+    fn parse_output(&self, stdout: &str) -> Result<String, Error> {
+        let text = match self.config.output {
+            OutputFormat::Text => stdout.to_string(),
+            OutputFormat::Json => {
+                let document: serde_json::Value = serde_json::from_str(stdout).map_err(|e| {
+                    self.invalid(format!(
+                        "Failed to parse JSON: {}. Output: {}",
+                        e,
+                        stdout.chars().take(200).collect::<String>()
+                    ))
+                })?;
+                document
+                    .pointer(&self.config.json_pointer)
+                    .and_then(|value| value.as_str())
+                    .map(str::to_string)
+                    .ok_or_else(|| {
+                        self.invalid(format!(
+                            "No string at JSON pointer '{}'",
+                            self.config.json_pointer
+                        ))
+                    })?
+            }
+        };
+
+        match &self.extract {
+            Some(re) => {
+                let captures = re.captures(&text).ok_or_else(|| {
+                    self.invalid("Output did not match the extract pattern".into())
+                })?;
+                let matched = captures.get(1).or_else(|| captures.get(0));
+                Ok(matched.map(|m| m.as_str().to_string()).unwrap_or_default())
+            }
+            None => Ok(text),
+        }
+    }
+
+    fn failed(&self, source: String) -> Error {
+        Error::Llm(LlmError::RequestFailed {
+            model: self.config.name.clone(),
+            source,
+        })
+    }
+
+    fn invalid(&self, details: String) -> Error {
+        Error::Llm(LlmError::InvalidResponse {
+            model: self.config.name.clone(),
+            details,
+        })
+    }
+}
+
+#[async_trait::async_trait]
+impl crate::llm::LLMProvider for CustomClient {
+    async fn query(&self, prompt: &str) -> Result<String, Error> {
+        self.query(prompt).await
+    }
+
+    async fn query_streaming(&self, prompt: &str, on_chunk: OnChunk<'_>) -> Result<String, Error> {
+        self.query_streaming(prompt, on_chunk).await
+    }
+
+    fn name(&self) -> &str {
+        &self.config.name
+    }
+}
+
+/// Split a command template into arguments, honoring single and double
+/// quotes and backslash escapes outside single quotes
+pub fn split_command(command: &str) -> anyhow::Result<Vec<String>> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut in_arg = false;
+    let mut quote: Option<char> = None;
+    let mut chars = command.chars();
+
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some('\''), c) => current.push(c),
+            (_, '\\') => {
+                if let Some(next) = chars.next() {
+                    current.push(next);
+                }
+                in_arg = true;
+            }
+            (None, '\'' | '"') => {
+                quote = Some(c);
+                in_arg = true;
+            }
+            (None, c) if c.is_whitespace() => {
+                if in_arg {
+                    args.push(std::mem::take(&mut current));
+                    in_arg = false;
+                }
+            }
+            (_, c) => {
+                current.push(c);
+                in_arg = true;
+            }
+        }
+    }
+
+    if quote.is_some() {
+        anyhow::bail!("Unterminated quote in command: {}", command);
+    }
+    if in_arg {
+        args.push(current);
+    }
+    Ok(args)
+}
+
+/// Write `prompt` to a private temporary file, deleted when dropped
+fn write_prompt_file(name: &str, prompt: &str) -> Result<NamedTempFile, String> {
+    let mut file = tempfile::Builder::new()
+        .prefix(&format!("noggin-prompt-{}-", name))
+        .suffix(".txt")
+        .tempfile()
+        .map_err(|e| format!("Failed to create prompt file: {}", e))?;
+    file.write_all(prompt.as_bytes())
+        .and_then(|_| file.flush())
+        .map_err(|e| format!("Failed to write prompt file {}: {}", file.path().display(), e))?;
+    Ok(file)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(command: &str) -> CustomConfig {
+        CustomConfig {
+            name: "local".to_string(),
+            command: command.to_string(),
+            output: OutputFormat::Text,
+            json_pointer: String::new(),
+            extract: None,
+            timeout_secs: 10,
+            retry: RetryPolicy {
+                max_attempts: 1,
+                ..Default::default()
+            },
+        }
+    }
+
+    #[test]
+    fn test_split_command() {
+        assert_eq!(
+            split_command(r#"mytool --system "be brief" 'a b' {prompt_file} x\ y"#).unwrap(),
+            vec![
+                "mytool",
+                "--system",
+                "be brief",
+                "a b",
+                "{prompt_file}",
+                "x y"
+            ]
+        );
+        assert_eq!(split_command(r#"tool """#).unwrap(), vec!["tool", ""]);
+        assert!(split_command("tool 'open").is_err());
+    }
+
+    #[test]
+    fn test_parse_output_json_and_extract() {
+        let client = CustomClient::new(CustomConfig {
+            output: OutputFormat::Json,
+            json_pointer: "/result/text".to_string(),
+            extract: Some(r"(?s)```toml\n(.*)```".to_string()),
+            ..config("tool")
+        })
+        .unwrap();
+
+        let stdout = r#"{"result":{"text":"Sure!\n```toml\n[[entry]]\n```"}}"#;
+        assert_eq!(client.parse_output(stdout).unwrap(), "[[entry]]\n");
+        assert!(client.parse_output(r#"{"result":{}}"#).is_err());
+        assert!(client.parse_output("not json").is_err());
+    }
+
+    #[test]
+    fn test_new_rejects_bad_config() {
+        assert!(CustomClient::new(config("   ")).is_err());
+        assert!(CustomClient::new(CustomConfig {
+            extract: Some("(".to_string()),
+            ..config("tool")
+        })
+        .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_query_passes_prompt() {
+        for template in ["cat {prompt_file}", "echo {prompt}", "cat"] {
+            let client = CustomClient::new(config(template)).unwrap();
+            let response = client.query("[[entry]]").await.unwrap();
+            assert_eq!(response.trim(), "[[entry]]", "template: {}", template);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_query_reports_failure() {
+        let client = CustomClient::new(config("false")).unwrap();
+        assert!(matches!(
+            client.query("x").await,
+            Err(Error::Llm(LlmError::RequestFailed { .. }))
+        ));
+    }
+}
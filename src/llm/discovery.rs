@@ -0,0 +1,235 @@
+//! Finding provider CLIs on disk
+//!
+//! Runs started from a GUI, cron, or a service manager get a minimal PATH
+//! that usually lacks npm's, Homebrew's, or the user's own bin directory,
+//! so spawning a bare `claude` fails there even though it works in a
+//! terminal. [`find_binary`] searches PATH and then the places each OS's
+//! installers put these CLIs. `noggin init` and `noggin doctor` record what
+//! they find as `llm.<provider>.binary` (see [`record_binaries`]), and
+//! providers spawn that path.
+
+use anyhow::{Context, Result};
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Built-in providers that run a local CLI, and the binary each one runs
+pub const CLI_PROVIDERS: &[(&str, &str)] = &[
+    ("claude", "claude"),
+    ("codex", "codex"),
+    ("gemini", "gemini"),
+];
+
+/// The binary to spawn for `name`: the configured path if it still
+/// exists, else one found by [`find_binary`], else the bare name for the
+/// OS to resolve
+pub fn resolve_binary(name: &str, configured: Option<&Path>) -> PathBuf {
+    locate_binary(name, configured).unwrap_or_else(|| PathBuf::from(name))
+}
+
+/// [`resolve_binary`] without the bare-name fallback, for providers that
+/// have another way to run when the CLI isn't installed
+pub fn locate_binary(name: &str, configured: Option<&Path>) -> Option<PathBuf> {
+    configured
+        .filter(|path| path.is_file())
+        .map(Path::to_path_buf)
+        .or_else(|| find_binary(name))
+}
+
+/// Absolute path of the executable `name` on PATH or in a common install
+/// location, None if it isn't installed anywhere we look
+pub fn find_binary(name: &str) -> Option<PathBuf> {
+    let path_dirs = env::var_os("PATH")
+        .map(|path| env::split_paths(&path).collect::<Vec<_>>())
+        .unwrap_or_default();
+    find_in(name, path_dirs.iter().chain(&install_dirs()))
+}
+
+/// First executable named `name` (with `.exe`, `.cmd`, or `.bat` on
+/// Windows) in `dirs`
+fn find_in<'a>(name: &str, dirs: impl IntoIterator<Item = &'a PathBuf>) -> Option<PathBuf> {
+    let names: Vec<String> = if cfg!(windows) {
+        ["exe", "cmd", "bat"]
+            .iter()
+            .map(|ext| format!("{}.{}", name, ext))
+            .collect()
+    } else {
+        vec![name.to_string()]
+    };
+    dirs.into_iter()
+        .filter(|dir| dir.is_absolute())
+        .flat_map(|dir| names.iter().map(move |name| dir.join(name)))
+        .find(|candidate| is_executable(candidate))
+}
+
+/// Where installers for these CLIs put binaries, beyond PATH
+fn install_dirs() -> Vec<PathBuf> {
+    let home = env::var_os(if cfg!(windows) { "USERPROFILE" } else { "HOME" })
+        .filter(|v| !v.is_empty())
+        .map(PathBuf::from);
+    let mut dirs = Vec::new();
+
+    if cfg!(windows) {
+        if let Some(appdata) = env::var_os("APPDATA") {
+            dirs.push(PathBuf::from(appdata).join("npm"));
+        }
+        if let Some(local) = env::var_os("LOCALAPPDATA").map(PathBuf::from) {
+            dirs.push(local.join("Microsoft").join("WinGet").join("Links"));
+            dirs.push(local.join("Volta").join("bin"));
+        }
+        if let Some(home) = &home {
+            dirs.push(home.join(".local").join("bin"));
+            dirs.push(home.join(".bun").join("bin"));
+        }
+        return dirs;
+    }
+
+    if let Some(home) = &home {
+        for dir in [".local/bin", ".claude/local", ".npm-global/bin", ".bun/bin", ".volta/bin"] {
+            dirs.push(home.join(dir));
+        }
+        dirs.extend(nvm_bin_dirs(&home.join(".nvm/versions/node")));
+    }
+    if cfg!(target_os = "macos") {
+        dirs.push(PathBuf::from("/opt/homebrew/bin"));
+    } else {
+        dirs.push(PathBuf::from("/home/linuxbrew/.linuxbrew/bin"));
+        dirs.push(PathBuf::from("/snap/bin"));
+    }
+    dirs.push(PathBuf::from("/usr/local/bin"));
+    dirs.push(PathBuf::from("/usr/bin"));
+    dirs
+}
+
+/// `bin` directories of nvm-installed Node versions, newest first
+fn nvm_bin_dirs(versions: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(versions) else {
+        return Vec::new();
+    };
+    let mut dirs: Vec<PathBuf> = entries
+        .flatten()
+        .map(|entry| entry.path().join("bin"))
+        .collect();
+    dirs.sort_by_key(|dir| std::cmp::Reverse(node_version(dir)));
+    dirs
+}
+
+/// Numeric parts of `.../v20.11.1/bin`, for ordering
+fn node_version(bin: &Path) -> Vec<u64> {
+    bin.parent()
+        .and_then(|dir| dir.file_name())
+        .and_then(|name| name.to_str())
+        .map(|name| {
+            name.trim_start_matches('v')
+                .split('.')
+                .map(|part| part.parse().unwrap_or(0))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    fs::metadata(path).is_ok_and(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file()
+}
+
+/// Set `llm.<provider>.binary` for each of `binaries` in
+/// `.noggin/config.toml`, creating the file if needed. The rest of the
+/// file, comments included, is kept as written.
+pub fn record_binaries(noggin_path: &Path, binaries: &[(&str, PathBuf)]) -> Result<()> {
+    if binaries.is_empty() {
+        return Ok(());
+    }
+    let path = noggin_path.join("config.toml");
+    let contents = match fs::read_to_string(&path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(e).with_context(|| format!("Failed to read {}", path.display())),
+    };
+    let mut doc: toml_edit::DocumentMut = contents
+        .parse()
+        .with_context(|| format!("Failed to parse {}", path.display()))?;
+
+    for (provider, binary) in binaries {
+        let llm = doc
+            .entry("llm")
+            .or_insert_with(toml_edit::table)
+            .as_table_mut()
+            .context("llm in config.toml is not a table")?;
+        llm.set_implicit(true);
+        let section = llm
+            .entry(provider)
+            .or_insert_with(toml_edit::table)
+            .as_table_mut()
+            .with_context(|| format!("llm.{} in config.toml is not a table", provider))?;
+        section["binary"] = toml_edit::value(binary.to_string_lossy().as_ref());
+    }
+
+    fs::write(&path, doc.to_string())
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use tempfile::TempDir;
+
+    #[cfg(unix)]
+    #[test]
+    fn test_find_in_skips_non_executables_and_relative_dirs() {
+        use std::os::unix::fs::PermissionsExt;
+        let temp_dir = TempDir::new().unwrap();
+        let plain = temp_dir.path().join("plain");
+        let bin = temp_dir.path().join("bin");
+        fs::create_dir_all(&plain).unwrap();
+        fs::create_dir_all(&bin).unwrap();
+        fs::write(plain.join("codex"), "").unwrap();
+        fs::write(bin.join("codex"), "#!/bin/sh\n").unwrap();
+        fs::set_permissions(bin.join("codex"), fs::Permissions::from_mode(0o755)).unwrap();
+
+        let dirs = vec![PathBuf::from("bin"), plain, bin.clone()];
+        assert_eq!(find_in("codex", &dirs), Some(bin.join("codex")));
+        assert_eq!(find_in("claude", &dirs), None);
+        assert_eq!(
+            resolve_binary("codex", Some(&bin.join("codex"))),
+            bin.join("codex")
+        );
+        // A recorded path that's gone no longer wins
+        assert_eq!(
+            resolve_binary("noggin-missing-cli", Some(&bin.join("noggin-missing-cli"))),
+            PathBuf::from("noggin-missing-cli")
+        );
+        assert_eq!(locate_binary("noggin-missing-cli", Some(&bin.join("noggin-missing-cli"))), None);
+    }
+
+    #[test]
+    fn test_record_binaries_keeps_existing_config() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("config.toml");
+        fs::write(&path, "# tuned for CI\n[llm.claude]\ntimeout_secs = 90\n").unwrap();
+
+        record_binaries(
+            temp_dir.path(),
+            &[
+                ("claude", PathBuf::from("/opt/homebrew/bin/claude")),
+                ("gemini", PathBuf::from("/usr/local/bin/gemini")),
+            ],
+        )
+        .unwrap();
+
+        let contents = fs::read_to_string(&path).unwrap();
+        assert!(contents.starts_with("# tuned for CI\n"));
+        let config = Config::load(temp_dir.path()).unwrap();
+        assert_eq!(config.llm.claude.timeout_secs, 90);
+        assert_eq!(config.llm.binary("claude"), Some(Path::new("/opt/homebrew/bin/claude")));
+        assert_eq!(config.llm.binary("gemini"), Some(Path::new("/usr/local/bin/gemini")));
+        assert_eq!(config.llm.binary("codex"), None);
+    }
+}
@@ -2,8 +2,13 @@
 //!
 //! Invokes the `@google/gemini-cli` via npx as a subprocess.
 //! Gemini provides deep security audits and thorough multi-file analysis.
+//! Its answers can take minutes, so stdout is streamed as it is written.
 
 use crate::error::{Error, LlmError};
+use crate::llm::cli_error::classify_cli_error;
+use crate::llm::retry::{RetryPolicy, RetryStream};
+use crate::llm::stream::{ignore_chunks, wait_with_streamed_stdout, OnChunk};
+use std::path::PathBuf;
 use std::process::Stdio;
 use std::time::Duration;
 use tokio::process::Command;
@@ -12,27 +17,59 @@ use tracing::debug;
 /// Gemini CLI client
 #[derive(Debug, Clone)]
 pub struct GeminiClient {
+    /// Installed `gemini` CLI to spawn; None runs `@google/gemini-cli`
+    /// through npx
+    pub binary: Option<PathBuf>,
     /// Timeout for subprocess execution (default: 300s / 5 minutes)
     pub timeout_secs: u64,
+    /// Retries for transient failures (default: 3 attempts)
+    pub retry: RetryPolicy,
 }
 
 impl GeminiClient {
     /// Create a new Gemini client with default configuration
     pub fn new() -> Self {
-        Self { timeout_secs: 300 }
+        Self {
+            binary: None,
+            timeout_secs: 300,
+            retry: RetryPolicy::default(),
+        }
     }
 
     /// Query Gemini CLI and return the response
     pub async fn query(&self, prompt: &str) -> Result<String, Error> {
-        // Build command: npx @google/gemini-cli "prompt"
-        let mut cmd = Command::new("npx");
-        cmd.args(["@google/gemini-cli", prompt])
+        self.query_streaming(prompt, &ignore_chunks).await
+    }
+
+    /// Query Gemini CLI, passing stdout to `on_chunk` as it arrives and
+    /// retrying transient failures
+    pub async fn query_streaming(&self, prompt: &str, on_chunk: OnChunk<'_>) -> Result<String, Error> {
+        let stream = RetryStream::new(on_chunk);
+        let forward = |chunk: &str| stream.forward(chunk);
+        self.retry
+            .run_streaming("gemini", &stream, || self.query_once(prompt, &forward))
+            .await
+    }
+
+    /// Execute a single query attempt without retry
+    async fn query_once(&self, prompt: &str, on_chunk: OnChunk<'_>) -> Result<String, Error> {
+        // Build command: gemini "prompt", or npx @google/gemini-cli "prompt"
+        let mut cmd = match &self.binary {
+            Some(binary) => Command::new(binary),
+            None => {
+                let mut cmd = Command::new("npx");
+                cmd.arg("@google/gemini-cli");
+                cmd
+            }
+        };
+        cmd.arg(prompt)
             .stdout(Stdio::piped())
             .stderr(Stdio::piped())
             .stdin(Stdio::null());
 
         debug!(
-            "Executing: npx @google/gemini-cli [prompt: {} chars]",
+            "Executing: {:?} [prompt: {} chars]",
+            cmd.as_std().get_program(),
             prompt.len()
         );
 
@@ -45,7 +82,7 @@ impl GeminiClient {
             })
         })?;
 
-        let output = tokio::time::timeout(timeout_duration, child.wait_with_output())
+        let output = tokio::time::timeout(timeout_duration, wait_with_streamed_stdout(child, on_chunk))
             .await
             .map_err(|_| Error::Llm(LlmError::RequestFailed {
                 model: "gemini".to_string(),
@@ -59,10 +96,7 @@ impl GeminiClient {
         // Check exit code
         if !output.status.success() {
             let stderr = String::from_utf8_lossy(&output.stderr);
-            return Err(Error::Llm(LlmError::RequestFailed {
-                model: "gemini".to_string(),
-                source: stderr.to_string(),
-            }));
+            return Err(classify_cli_error("gemini", &stderr));
         }
 
         // Get response from stdout (plain text)
@@ -90,6 +124,10 @@ impl crate::llm::LLMProvider for GeminiClient {
         self.query(prompt).await
     }
 
+    async fn query_streaming(&self, prompt: &str, on_chunk: OnChunk<'_>) -> Result<String, Error> {
+        self.query_streaming(prompt, on_chunk).await
+    }
+
     fn name(&self) -> &str {
         "gemini"
     }
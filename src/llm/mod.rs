@@ -1,21 +1,129 @@
 //! LLM provider abstraction and implementations
 //!
-//! Supports multiple LLM providers (Claude, Codex, Gemini) via subprocess invocation.
+//! Supports multiple LLM providers: Claude, Codex, and Gemini via subprocess
+//! invocation, OpenAI over its HTTPS API, and user-defined commands from
+//! `[[llm.custom]]`.
 //! Each provider implements the LLMProvider trait for consistent querying.
+//! Providers that can report output before they finish (Gemini and custom
+//! commands) also override [`LLMProvider::query_streaming`].
 
+pub mod cache;
+pub mod chaos;
 pub mod claude;
+pub mod cli_error;
 pub mod codex;
+pub mod custom;
+pub mod discovery;
 pub mod gemini;
+pub mod openai;
 pub mod parallel;
+pub mod retry;
+pub mod stream;
 
+use crate::config::LlmConfig;
 use crate::error::Error;
+use stream::OnChunk;
 
 /// Common trait for LLM providers
 #[async_trait::async_trait]
 pub trait LLMProvider: Send + Sync {
     /// Query the LLM with a prompt and return the response
     async fn query(&self, prompt: &str) -> Result<String, Error>;
-    
+
+    /// Query like [`query`](Self::query), passing response text to
+    /// `on_chunk` as it arrives. Providers that can't stream pass the
+    /// whole response once it's complete.
+    async fn query_streaming(&self, prompt: &str, on_chunk: OnChunk<'_>) -> Result<String, Error> {
+        let response = self.query(prompt).await?;
+        on_chunk(&response);
+        Ok(response)
+    }
+
     /// Get the provider name (e.g., "claude", "codex")
     fn name(&self) -> &str;
 }
+
+/// Built-in names accepted by [`provider_by_name`]
+pub const PROVIDER_NAMES: &[&str] = &["claude", "codex", "gemini", "openai"];
+
+/// Providers queried when `llm.providers` isn't set. OpenAI is opt-in
+/// since it needs an API key.
+pub const DEFAULT_PROVIDERS: &[&str] = &["claude", "codex", "gemini"];
+
+/// Construct a built-in provider by name with default settings, None if
+/// the name is unknown
+pub fn provider_by_name(name: &str) -> Option<Box<dyn LLMProvider>> {
+    provider_from_config(name, &LlmConfig::default()).ok().flatten()
+}
+
+/// Construct a provider by name with the settings from its `[llm.<name>]`
+/// section or `[[llm.custom]]` entry. None if the name is unknown; fails
+/// if a custom entry is invalid.
+pub fn provider_from_config(
+    name: &str,
+    config: &LlmConfig,
+) -> anyhow::Result<Option<Box<dyn LLMProvider>>> {
+    if let Some(custom) = config.custom_provider(name) {
+        let client = custom::CustomClient::new(custom::CustomConfig {
+            name: custom.name.clone(),
+            command: custom.command.clone(),
+            output: custom.output,
+            json_pointer: custom.json_pointer.clone(),
+            extract: custom.extract.clone(),
+            timeout_secs: custom.timeout_secs,
+            retry: config.retry_policy(name),
+        })?;
+        return Ok(Some(Box::new(client)));
+    }
+
+    Ok(match name {
+        "claude" => Some(Box::new(claude::ClaudeClient::with_config(claude::ClaudeConfig {
+            binary: discovery::resolve_binary("claude", config.binary("claude")),
+            timeout_secs: config.claude.timeout_secs,
+            retry: config.retry_policy("claude"),
+        }))),
+        "codex" => Some(Box::new(codex::CodexClient {
+            binary: discovery::resolve_binary("codex", config.binary("codex")),
+            timeout_secs: config.codex.timeout_secs,
+            retry: config.retry_policy("codex"),
+        })),
+        "gemini" => Some(Box::new(gemini::GeminiClient {
+            binary: discovery::locate_binary("gemini", config.binary("gemini")),
+            timeout_secs: config.gemini.timeout_secs,
+            retry: config.retry_policy("gemini"),
+        })),
+        "openai" => Some(Box::new(openai::OpenAiClient::with_config(openai::OpenAiConfig {
+            model: config.openai.model.clone(),
+            base_url: config.openai.base_url.clone(),
+            api_key: config.openai.api_key.clone(),
+            timeout_secs: config.openai.timeout_secs,
+            retry: config.retry_policy("openai"),
+        }))),
+        _ => None,
+    })
+}
+
+/// The enabled providers from `llm.providers`, in the listed order and
+/// with their configured settings. Fails on an unknown name or when
+/// every listed provider is disabled.
+pub fn configured_providers(config: &LlmConfig) -> anyhow::Result<Vec<Box<dyn LLMProvider>>> {
+    let providers = config
+        .providers
+        .iter()
+        .filter(|name| config.is_enabled(name))
+        .map(|name| {
+            provider_from_config(name, config)?.ok_or_else(|| {
+                anyhow::anyhow!(
+                    "Unknown provider '{}' in llm.providers. Expected one of: {}",
+                    name,
+                    config.provider_names().join(", ")
+                )
+            })
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    if providers.is_empty() {
+        anyhow::bail!("No enabled providers in llm.providers");
+    }
+    Ok(chaos::wrap_if_enabled(providers, &config.chaos))
+}
@@ -0,0 +1,280 @@
+//! OpenAI Chat Completions API client
+//!
+//! Unlike the other providers this one talks to the API directly over
+//! HTTPS instead of shelling out to a CLI. The API key comes from the
+//! config or the `OPENAI_API_KEY` environment variable. Timeouts, rate
+//! limits, and retries behave like the other clients.
+
+use crate::error::{Error, LlmError};
+use crate::llm::retry::RetryPolicy;
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tracing::debug;
+
+/// Environment variable read when no key is configured
+pub const API_KEY_ENV: &str = "OPENAI_API_KEY";
+
+/// Configuration for the OpenAI client
+#[derive(Debug, Clone)]
+pub struct OpenAiConfig {
+    /// Model to request (default: gpt-4o-mini)
+    pub model: String,
+    /// API root, overridable for compatible gateways
+    pub base_url: String,
+    /// API key; falls back to `OPENAI_API_KEY`
+    pub api_key: Option<String>,
+    /// Timeout for each HTTP request (default: 60s)
+    pub timeout_secs: u64,
+    /// Retries for transient failures (default: 3 attempts)
+    pub retry: RetryPolicy,
+}
+
+impl Default for OpenAiConfig {
+    fn default() -> Self {
+        Self {
+            model: "gpt-4o-mini".to_string(),
+            base_url: "https://api.openai.com/v1".to_string(),
+            api_key: None,
+            timeout_secs: 60,
+            retry: RetryPolicy::default(),
+        }
+    }
+}
+
+/// OpenAI API client
+pub struct OpenAiClient {
+    config: OpenAiConfig,
+    http: reqwest::Client,
+}
+
+impl OpenAiClient {
+    /// Create a new OpenAI client with default configuration
+    pub fn new() -> Self {
+        Self::with_config(OpenAiConfig::default())
+    }
+
+    /// Create a new OpenAI client with custom configuration
+    pub fn with_config(config: OpenAiConfig) -> Self {
+        Self {
+            config,
+            http: reqwest::Client::new(),
+        }
+    }
+
+    /// Query the API with retry logic
+    pub async fn query(&self, prompt: &str) -> Result<String, Error> {
+        self.config
+            .retry
+            .run("openai", || self.query_once(prompt))
+            .await
+    }
+
+    /// Send a single request without retry
+    async fn query_once(&self, prompt: &str) -> Result<String, Error> {
+        let api_key = self.api_key()?;
+        let url = format!("{}/chat/completions", self.config.base_url.trim_end_matches('/'));
+        let request = ChatRequest {
+            model: &self.config.model,
+            messages: vec![ChatMessage {
+                role: "user".to_string(),
+                content: prompt.to_string(),
+            }],
+        };
+
+        debug!("POST {} [model: {}, prompt: {} chars]", url, self.config.model, prompt.len());
+
+        let response = self
+            .http
+            .post(&url)
+            .bearer_auth(api_key)
+            .timeout(Duration::from_secs(self.config.timeout_secs))
+            .json(&request)
+            .send()
+            .await
+            .map_err(|e| {
+                let source = if e.is_timeout() {
+                    format!("Timeout after {}s", self.config.timeout_secs)
+                } else {
+                    e.to_string()
+                };
+                Error::Llm(LlmError::RequestFailed {
+                    model: "openai".to_string(),
+                    source,
+                })
+            })?;
+
+        let status = response.status();
+        let retry_after = response
+            .headers()
+            .get(reqwest::header::RETRY_AFTER)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.trim().parse().ok());
+        let body = response.text().await.map_err(|e| {
+            Error::Llm(LlmError::RequestFailed {
+                model: "openai".to_string(),
+                source: format!("Failed to read response body: {}", e),
+            })
+        })?;
+
+        if !status.is_success() {
+            return Err(status_error(status, retry_after, &body));
+        }
+
+        let content = parse_response(&body)?;
+        debug!("OpenAI query completed successfully");
+        Ok(content)
+    }
+
+    fn api_key(&self) -> Result<String, Error> {
+        self.config
+            .api_key
+            .clone()
+            .filter(|key| !key.is_empty())
+            .or_else(|| std::env::var(API_KEY_ENV).ok().filter(|key| !key.is_empty()))
+            .ok_or_else(|| {
+                Error::Llm(LlmError::AuthenticationFailed(format!(
+                    "openai: set {} or llm.openai.api_key",
+                    API_KEY_ENV
+                )))
+            })
+    }
+}
+
+impl Default for OpenAiClient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct ChatRequest<'a> {
+    model: &'a str,
+    messages: Vec<ChatMessage>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ChatMessage {
+    role: String,
+    content: String,
+}
+
+/// Response from the Chat Completions endpoint (the parts we read)
+#[derive(Debug, Deserialize)]
+struct ChatResponse {
+    choices: Vec<ChatChoice>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatChoice {
+    message: ChatMessage,
+}
+
+/// Extract the first choice's message text
+fn parse_response(body: &str) -> Result<String, Error> {
+    let response: ChatResponse = serde_json::from_str(body).map_err(|e| {
+        Error::Llm(LlmError::InvalidResponse {
+            model: "openai".to_string(),
+            details: format!("Failed to parse JSON: {}. Output: {}", e, body.chars().take(200).collect::<String>()),
+        })
+    })?;
+
+    response
+        .choices
+        .into_iter()
+        .next()
+        .map(|choice| choice.message.content)
+        .ok_or_else(|| {
+            Error::Llm(LlmError::InvalidResponse {
+                model: "openai".to_string(),
+                details: "Response contained no choices".to_string(),
+            })
+        })
+}
+
+/// Map a non-success HTTP status to an error
+fn status_error(status: StatusCode, retry_after: Option<u64>, body: &str) -> Error {
+    match status {
+        StatusCode::TOO_MANY_REQUESTS => Error::Llm(LlmError::RateLimitExceeded {
+            model: "openai".to_string(),
+            retry_after,
+        }),
+        StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => {
+            Error::Llm(LlmError::AuthenticationFailed("openai".to_string()))
+        }
+        StatusCode::SERVICE_UNAVAILABLE | StatusCode::BAD_GATEWAY | StatusCode::GATEWAY_TIMEOUT => {
+            Error::Llm(LlmError::ModelUnavailable("openai".to_string()))
+        }
+        _ if status.is_server_error() => Error::Llm(LlmError::RequestFailed {
+            model: "openai".to_string(),
+            source: format!("HTTP {}: {}", status, body.chars().take(200).collect::<String>()),
+        }),
+        _ => Error::Llm(LlmError::InvalidResponse {
+            model: "openai".to_string(),
+            details: format!("HTTP {}: {}", status, body.chars().take(200).collect::<String>()),
+        }),
+    }
+}
+
+#[async_trait::async_trait]
+impl crate::llm::LLMProvider for OpenAiClient {
+    async fn query(&self, prompt: &str) -> Result<String, Error> {
+        self.query(prompt).await
+    }
+
+    fn name(&self) -> &str {
+        "openai"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::retry::is_retryable;
+
+    #[test]
+    fn test_config_defaults() {
+        let config = OpenAiConfig::default();
+        assert_eq!(config.model, "gpt-4o-mini");
+        assert_eq!(config.timeout_secs, 60);
+        assert_eq!(config.retry.max_attempts, 3);
+    }
+
+    #[test]
+    fn test_parse_response() {
+        let body = r#"{"id":"x","choices":[{"index":0,"message":{"role":"assistant","content":"[[entry]]"}}]}"#;
+        assert_eq!(parse_response(body).unwrap(), "[[entry]]");
+        assert!(parse_response(r#"{"choices":[]}"#).is_err());
+        assert!(parse_response("not json").is_err());
+    }
+
+    #[test]
+    fn test_status_errors() {
+        assert!(matches!(
+            status_error(StatusCode::TOO_MANY_REQUESTS, Some(20), ""),
+            Error::Llm(LlmError::RateLimitExceeded { retry_after: Some(20), .. })
+        ));
+        assert!(matches!(
+            status_error(StatusCode::UNAUTHORIZED, None, ""),
+            Error::Llm(LlmError::AuthenticationFailed(_))
+        ));
+        assert!(matches!(
+            status_error(StatusCode::SERVICE_UNAVAILABLE, None, ""),
+            Error::Llm(LlmError::ModelUnavailable(_))
+        ));
+
+        let bad_request = status_error(StatusCode::BAD_REQUEST, None, "bad model");
+        assert!(!is_retryable(&bad_request));
+        assert!(!is_retryable(&status_error(StatusCode::TOO_MANY_REQUESTS, Some(20), "")));
+        assert!(is_retryable(&status_error(StatusCode::INTERNAL_SERVER_ERROR, None, "")));
+    }
+
+    #[test]
+    fn test_configured_key_wins() {
+        let client = OpenAiClient::with_config(OpenAiConfig {
+            api_key: Some("sk-test".to_string()),
+            ..Default::default()
+        });
+        assert_eq!(client.api_key().unwrap(), "sk-test");
+    }
+}
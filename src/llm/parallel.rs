@@ -3,12 +3,128 @@
 //! Spawns Claude, Codex, and Gemini concurrently via tokio,
 //! collects outputs, and handles partial failures gracefully.
 //! If at least one model succeeds, the analysis proceeds.
+//! [`query_all_streaming`] also reports how much each model has sent so
+//! far, for progress display.
+//!
+//! Every request goes through a [`Scheduler`] shared by the whole run. It
+//! caps how many requests each provider has in flight and, when one
+//! request is rate limited, holds back every request to that provider
+//! until the limit's `retry_after` has passed, then retries it. Clients
+//! leave rate limits to the scheduler rather than retrying on their own.
 
+use crate::config::LlmConfig;
 use crate::error::{Error, LlmError};
+use crate::llm::stream::OnChunk;
 use crate::llm::LLMProvider;
 use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::Semaphore;
+use tokio::time::Instant;
 use tracing::{debug, info, warn};
 
+/// Run-wide concurrency and rate limits per provider
+#[derive(Debug)]
+pub struct Scheduler {
+    config: LlmConfig,
+    slots: Mutex<HashMap<String, Arc<ProviderSlot>>>,
+}
+
+/// One provider's share of the scheduler
+#[derive(Debug)]
+struct ProviderSlot {
+    permits: Semaphore,
+    /// No request starts before this, after a rate limit
+    resume_at: Mutex<Option<Instant>>,
+}
+
+impl ProviderSlot {
+    /// Sleep until any rate limit on the provider has passed
+    async fn wait_for_resume(&self) {
+        loop {
+            let resume_at = *self.resume_at.lock().unwrap();
+            match resume_at {
+                Some(at) if at > Instant::now() => tokio::time::sleep_until(at).await,
+                _ => return,
+            }
+        }
+    }
+
+    /// Hold back requests until `at`, unless already held back longer
+    fn pause_until(&self, at: Instant) {
+        let mut resume_at = self.resume_at.lock().unwrap();
+        if resume_at.is_none_or(|current| current < at) {
+            *resume_at = Some(at);
+        }
+    }
+}
+
+impl Scheduler {
+    /// A scheduler using the limits in `config`
+    pub fn new(config: &LlmConfig) -> Self {
+        Self {
+            config: config.clone(),
+            slots: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn slot(&self, name: &str) -> Arc<ProviderSlot> {
+        let mut slots = self.slots.lock().unwrap();
+        slots
+            .entry(name.to_string())
+            .or_insert_with(|| {
+                Arc::new(ProviderSlot {
+                    permits: Semaphore::new(self.config.max_concurrency(name)),
+                    resume_at: Mutex::new(None),
+                })
+            })
+            .clone()
+    }
+
+    /// Query `provider` once a slot is free and no rate limit is pending,
+    /// retrying rate-limited requests up to `llm.rate_limit_retries` times
+    pub async fn query(
+        &self,
+        provider: &dyn LLMProvider,
+        prompt: &str,
+        on_chunk: OnChunk<'_>,
+    ) -> Result<String, Error> {
+        let name = provider.name();
+        let slot = self.slot(name);
+        let mut retries = 0;
+
+        loop {
+            let _permit = slot.permits.acquire().await.expect("scheduler semaphore closed");
+            slot.wait_for_resume().await;
+
+            match provider.query_streaming(prompt, on_chunk).await {
+                Err(Error::Llm(LlmError::RateLimitExceeded { retry_after, .. }))
+                    if retries < self.config.rate_limit_retries =>
+                {
+                    retries += 1;
+                    let wait = Duration::from_secs(retry_after.unwrap_or(self.config.rate_limit_wait_secs));
+                    warn!(
+                        "{} rate limited (retry {} of {}), pausing its requests for {}s",
+                        name,
+                        retries,
+                        self.config.rate_limit_retries,
+                        wait.as_secs()
+                    );
+                    slot.pause_until(Instant::now() + wait);
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+impl Default for Scheduler {
+    fn default() -> Self {
+        Self::new(&LlmConfig::default())
+    }
+}
+
 /// Result from a single model's analysis
 #[derive(Debug, Clone)]
 pub struct ModelResult {
@@ -67,8 +183,20 @@ impl ParallelResult {
 /// as long as at least one provider returns a result. If all providers
 /// fail, returns an error.
 pub async fn query_all(
+    scheduler: &Scheduler,
     providers: &[Box<dyn LLMProvider>],
     prompt: &str,
+) -> Result<ParallelResult, Error> {
+    query_all_streaming(scheduler, providers, prompt, &|_, _| {}).await
+}
+
+/// [`query_all`], calling `on_progress` with a provider's name and the
+/// number of characters it has returned so far each time more arrive
+pub async fn query_all_streaming(
+    scheduler: &Scheduler,
+    providers: &[Box<dyn LLMProvider>],
+    prompt: &str,
+    on_progress: &(dyn Fn(&str, usize) + Send + Sync),
 ) -> Result<ParallelResult, Error> {
     if providers.is_empty() {
         return Err(Error::Llm(LlmError::RequestFailed {
@@ -86,7 +214,13 @@ pub async fn query_all(
             let name = provider.name().to_string();
             debug!("Spawning query for {}", name);
             async move {
-                let result = provider.query(prompt).await;
+                let received = AtomicUsize::new(0);
+                let on_chunk = |chunk: &str| {
+                    let chars = chunk.chars().count();
+                    let total = received.fetch_add(chars, Ordering::Relaxed) + chars;
+                    on_progress(&name, total);
+                };
+                let result = scheduler.query(provider.as_ref(), prompt, &on_chunk).await;
                 (name, result)
             }
         })
@@ -122,13 +256,17 @@ pub async fn query_all(
     };
 
     if !result.has_results() {
-        let models: Vec<_> = result.failures.iter().map(|f| f.model.as_str()).collect();
+        let models: Vec<_> = result
+            .failures
+            .iter()
+            .map(|f| format!("{} ({})", f.model, f.error))
+            .collect();
         return Err(Error::Llm(LlmError::RequestFailed {
             model: "parallel".to_string(),
             source: format!(
                 "All {} providers failed: {}",
                 result.failure_count(),
-                models.join(", ")
+                models.join("; ")
             ),
         }));
     }
@@ -145,6 +283,7 @@ pub async fn query_all(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::llm::stream::ignore_chunks;
     use async_trait::async_trait;
 
     /// Mock provider that succeeds with a fixed response
@@ -200,7 +339,7 @@ mod tests {
             }),
         ];
 
-        let result = query_all(&providers, "test prompt").await.unwrap();
+        let result = query_all(&Scheduler::default(), &providers, "test prompt").await.unwrap();
         assert_eq!(result.success_count(), 3);
         assert_eq!(result.failure_count(), 0);
         assert!(result.has_results());
@@ -227,7 +366,7 @@ mod tests {
             }),
         ];
 
-        let result = query_all(&providers, "test prompt").await.unwrap();
+        let result = query_all(&Scheduler::default(), &providers, "test prompt").await.unwrap();
         assert_eq!(result.success_count(), 2);
         assert_eq!(result.failure_count(), 1);
         assert!(result.has_results());
@@ -245,16 +384,17 @@ mod tests {
             }),
         ];
 
-        let result = query_all(&providers, "test prompt").await;
+        let result = query_all(&Scheduler::default(), &providers, "test prompt").await;
         assert!(result.is_err());
         let err = result.unwrap_err();
         assert!(err.to_string().contains("All 2 providers failed"));
+        assert!(err.to_string().contains("mock failure"));
     }
 
     #[tokio::test]
     async fn test_no_providers() {
         let providers: Vec<Box<dyn LLMProvider>> = vec![];
-        let result = query_all(&providers, "test prompt").await;
+        let result = query_all(&Scheduler::default(), &providers, "test prompt").await;
         assert!(result.is_err());
         let err = result.unwrap_err();
         assert!(err.to_string().contains("No providers configured"));
@@ -269,11 +409,128 @@ mod tests {
             }),
         ];
 
-        let result = query_all(&providers, "test prompt").await.unwrap();
+        let result = query_all(&Scheduler::default(), &providers, "test prompt").await.unwrap();
         assert_eq!(result.success_count(), 1);
         assert_eq!(result.failure_count(), 0);
     }
 
+    #[tokio::test]
+    async fn test_streaming_reports_progress() {
+        let providers: Vec<Box<dyn LLMProvider>> = vec![
+            Box::new(MockProvider {
+                name: "claude".to_string(),
+                response: "héllo".to_string(),
+            }),
+            Box::new(FailingProvider {
+                name: "codex".to_string(),
+            }),
+        ];
+
+        let progress = std::sync::Mutex::new(Vec::new());
+        let on_progress =
+            |model: &str, chars: usize| progress.lock().unwrap().push((model.to_string(), chars));
+        let result = query_all_streaming(&Scheduler::default(), &providers, "test prompt", &on_progress)
+            .await
+            .unwrap();
+
+        assert_eq!(result.success_count(), 1);
+        assert_eq!(
+            progress.into_inner().unwrap(),
+            vec![("claude".to_string(), 5)]
+        );
+    }
+
+    /// Mock provider that is rate limited `limited` times, then succeeds,
+    /// tracking how many requests it has in flight
+    #[derive(Default)]
+    struct LimitedProvider {
+        limited: AtomicUsize,
+        attempts: AtomicUsize,
+        in_flight: AtomicUsize,
+        max_in_flight: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl LLMProvider for LimitedProvider {
+        async fn query(&self, _prompt: &str) -> Result<String, Error> {
+            self.attempts.fetch_add(1, Ordering::SeqCst);
+            let in_flight = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+            self.max_in_flight.fetch_max(in_flight, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            self.in_flight.fetch_sub(1, Ordering::SeqCst);
+
+            let limited = self
+                .limited
+                .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1))
+                .is_ok();
+            if limited {
+                return Err(Error::Llm(LlmError::RateLimitExceeded {
+                    model: "limited".to_string(),
+                    retry_after: Some(0),
+                }));
+            }
+            Ok("ok".to_string())
+        }
+
+        fn name(&self) -> &str {
+            "limited"
+        }
+    }
+
+    #[tokio::test]
+    async fn test_scheduler_caps_concurrency() {
+        let scheduler = Scheduler::new(&LlmConfig {
+            max_concurrency: 1,
+            ..Default::default()
+        });
+        let provider = LimitedProvider::default();
+
+        let queries = (0..3).map(|_| scheduler.query(&provider, "prompt", &ignore_chunks));
+        let results = futures::future::join_all(queries).await;
+
+        assert!(results.iter().all(|r| r.is_ok()));
+        assert_eq!(provider.max_in_flight.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_scheduler_retries_rate_limits() {
+        let scheduler = Scheduler::new(&LlmConfig {
+            rate_limit_retries: 2,
+            ..Default::default()
+        });
+
+        let provider = LimitedProvider {
+            limited: AtomicUsize::new(2),
+            ..Default::default()
+        };
+        assert!(scheduler.query(&provider, "prompt", &ignore_chunks).await.is_ok());
+        assert_eq!(provider.attempts.load(Ordering::SeqCst), 3);
+
+        let provider = LimitedProvider {
+            limited: AtomicUsize::new(5),
+            ..Default::default()
+        };
+        let result = scheduler.query(&provider, "prompt", &ignore_chunks).await;
+        assert!(matches!(
+            result,
+            Err(Error::Llm(LlmError::RateLimitExceeded { .. }))
+        ));
+        assert_eq!(provider.attempts.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit_pauses_provider() {
+        let scheduler = Scheduler::default();
+        let slot = scheduler.slot("claude");
+        let started = Instant::now();
+        slot.pause_until(started + Duration::from_millis(50));
+        // A shorter pause doesn't cut the longer one short
+        slot.pause_until(started + Duration::from_millis(10));
+
+        scheduler.slot("claude").wait_for_resume().await;
+        assert!(started.elapsed() >= Duration::from_millis(50));
+    }
+
     #[test]
     fn test_parallel_result_responses_map() {
         let result = ParallelResult {
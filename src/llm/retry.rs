@@ -0,0 +1,382 @@
+//! Retry policy shared by every provider client
+//!
+//! Transient failures (request errors, an unavailable model) are retried
+//! with exponential backoff and random jitter, so several clients that fail
+//! together don't retry in lockstep. Timeouts are not retried unless
+//! `retry_timeouts` is set: a prompt that ran out the clock once usually
+//! does again, and each attempt costs the whole timeout. Rate limits are
+//! not retried here; the run's
+//! [`Scheduler`](crate::llm::parallel::Scheduler) waits them out for every
+//! request to the provider at once.
+//!
+//! A streaming attempt that fails after passing on some output is retried
+//! without streaming (see [`RetryStream`]), so the caller never sees the
+//! same output twice.
+//!
+//! The policy comes from `[llm.retry]`, overridden per provider by
+//! `[llm.<name>.retry]` or a `[[llm.custom]]` entry's `retry` table.
+
+use crate::error::{Error, LlmError};
+use crate::llm::stream::OnChunk;
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::RandomState;
+use std::future::Future;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tracing::{debug, warn};
+
+/// How a client retries failed requests
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RetryPolicy {
+    /// Attempts in total, including the first (1 disables retries)
+    #[serde(default = "default_max_attempts")]
+    pub max_attempts: u32,
+    /// Wait before the first retry
+    #[serde(default = "default_initial_backoff_ms")]
+    pub initial_backoff_ms: u64,
+    /// Factor the wait grows by after each retry
+    #[serde(default = "default_multiplier")]
+    pub multiplier: f64,
+    /// Longest wait between attempts
+    #[serde(default = "default_max_backoff_ms")]
+    pub max_backoff_ms: u64,
+    /// Fraction of each wait added or removed at random (0.0 to 1.0)
+    #[serde(default = "default_jitter")]
+    pub jitter: f64,
+    /// Retry requests that timed out too
+    #[serde(default)]
+    pub retry_timeouts: bool,
+}
+
+fn default_max_attempts() -> u32 {
+    3
+}
+
+fn default_initial_backoff_ms() -> u64 {
+    1000
+}
+
+fn default_multiplier() -> f64 {
+    2.0
+}
+
+fn default_max_backoff_ms() -> u64 {
+    30_000
+}
+
+fn default_jitter() -> f64 {
+    0.2
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: default_max_attempts(),
+            initial_backoff_ms: default_initial_backoff_ms(),
+            multiplier: default_multiplier(),
+            max_backoff_ms: default_max_backoff_ms(),
+            jitter: default_jitter(),
+            retry_timeouts: false,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Wait before retry number `retry` (1 for the first), without jitter
+    pub fn backoff(&self, retry: u32) -> Duration {
+        let exponent = retry.saturating_sub(1).min(i32::MAX as u32) as i32;
+        let ms = self.initial_backoff_ms as f64 * self.multiplier.max(1.0).powi(exponent);
+        Duration::from_millis(ms.min(self.max_backoff_ms as f64) as u64)
+    }
+
+    /// [`backoff`](Self::backoff) moved by up to `jitter` of itself
+    /// either way, `unit` being a random number in [0, 1)
+    pub fn jittered(&self, retry: u32, unit: f64) -> Duration {
+        let jitter = self.jitter.clamp(0.0, 1.0);
+        self.backoff(retry)
+            .mul_f64(1.0 + jitter * (2.0 * unit - 1.0))
+    }
+
+    /// Run `attempt` until it succeeds, fails in a way retrying won't fix,
+    /// or `max_attempts` is used up. `model` names the provider in logs.
+    pub async fn run<T, F, Fut>(&self, model: &str, attempt: F) -> Result<T, Error>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, Error>>,
+    {
+        self.run_with(model, || {}, attempt).await
+    }
+
+    /// [`run`](Self::run) for an attempt streaming its output through
+    /// `stream`, which stops passing output on once an attempt fails after
+    /// streaming some
+    pub async fn run_streaming<T, F, Fut>(
+        &self,
+        model: &str,
+        stream: &RetryStream<'_>,
+        attempt: F,
+    ) -> Result<T, Error>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, Error>>,
+    {
+        self.run_with(model, || stream.attempt_failed(), attempt).await
+    }
+
+    /// Whether `error` is worth another attempt under this policy
+    fn should_retry(&self, error: &Error) -> bool {
+        is_retryable(error) && (self.retry_timeouts || !is_timeout(error))
+    }
+
+    async fn run_with<T, F, Fut>(
+        &self,
+        model: &str,
+        on_retry: impl Fn(),
+        mut attempt: F,
+    ) -> Result<T, Error>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, Error>>,
+    {
+        let max_attempts = self.max_attempts.max(1);
+        let mut attempts = 0;
+
+        loop {
+            attempts += 1;
+            debug!("{} query attempt {} of {}", model, attempts, max_attempts);
+
+            match attempt().await {
+                Ok(response) => return Ok(response),
+                Err(e) if !self.should_retry(&e) => {
+                    warn!("{} query failed with non-retryable error: {}", model, e);
+                    return Err(e);
+                }
+                Err(e) if attempts >= max_attempts => {
+                    warn!("{} query failed after {} attempts", model, attempts);
+                    return Err(e);
+                }
+                Err(e) => {
+                    let wait = self.jittered(attempts, random_unit());
+                    warn!(
+                        "{} query failed (attempt {}), retrying in {}ms: {}",
+                        model,
+                        attempts,
+                        wait.as_millis(),
+                        e
+                    );
+                    on_retry();
+                    tokio::time::sleep(wait).await;
+                }
+            }
+        }
+    }
+}
+
+/// Whether retrying might help. Rate limits are left to the scheduler.
+pub fn is_retryable(error: &Error) -> bool {
+    matches!(
+        error,
+        Error::Llm(LlmError::RequestFailed { .. }) | Error::Llm(LlmError::ModelUnavailable(_))
+    )
+}
+
+/// Whether `error` is a request that ran out of time. Every client
+/// reports timeouts as a failed request whose source starts "Timeout".
+pub fn is_timeout(error: &Error) -> bool {
+    matches!(
+        error,
+        Error::Llm(LlmError::RequestFailed { source, .. }) if source.starts_with("Timeout")
+    )
+}
+
+/// The caller's chunk callback for a retried streaming request. Chunks
+/// pass through until an attempt fails after streaming some; later
+/// attempts stream nothing, and their whole response is returned instead.
+pub struct RetryStream<'a> {
+    on_chunk: OnChunk<'a>,
+    streamed: AtomicBool,
+    muted: AtomicBool,
+}
+
+impl<'a> RetryStream<'a> {
+    pub fn new(on_chunk: OnChunk<'a>) -> Self {
+        Self {
+            on_chunk,
+            streamed: AtomicBool::new(false),
+            muted: AtomicBool::new(false),
+        }
+    }
+
+    /// Pass `chunk` on, unless an earlier attempt already streamed output
+    pub fn forward(&self, chunk: &str) {
+        if !self.muted.load(Ordering::SeqCst) {
+            self.streamed.store(true, Ordering::SeqCst);
+            (self.on_chunk)(chunk);
+        }
+    }
+
+    fn attempt_failed(&self) {
+        if self.streamed.load(Ordering::SeqCst) {
+            self.muted.store(true, Ordering::SeqCst);
+        }
+    }
+}
+
+/// A random number in [0, 1), from the standard library's randomly keyed
+/// hasher
+fn random_unit() -> f64 {
+    let bits = RandomState::new().build_hasher().finish();
+    (bits >> 11) as f64 / (1u64 << 53) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    fn quick() -> RetryPolicy {
+        RetryPolicy {
+            initial_backoff_ms: 1,
+            max_backoff_ms: 1,
+            ..Default::default()
+        }
+    }
+
+    fn failed() -> Error {
+        Error::Llm(LlmError::RequestFailed {
+            model: "test".to_string(),
+            source: "boom".to_string(),
+        })
+    }
+
+    #[test]
+    fn test_backoff_grows_and_caps() {
+        let policy = RetryPolicy {
+            max_backoff_ms: 5000,
+            ..Default::default()
+        };
+        assert_eq!(policy.backoff(1), Duration::from_millis(1000));
+        assert_eq!(policy.backoff(2), Duration::from_millis(2000));
+        assert_eq!(policy.backoff(3), Duration::from_millis(4000));
+        assert_eq!(policy.backoff(4), Duration::from_millis(5000));
+        assert_eq!(policy.backoff(100), Duration::from_millis(5000));
+    }
+
+    #[test]
+    fn test_jitter_bounds() {
+        let policy = RetryPolicy::default();
+        assert_eq!(policy.jittered(1, 0.0), Duration::from_millis(800));
+        assert_eq!(policy.jittered(1, 0.5), Duration::from_millis(1000));
+        assert!(policy.jittered(1, 0.999) < Duration::from_millis(1200));
+
+        let unit = random_unit();
+        assert!((0.0..1.0).contains(&unit));
+    }
+
+    #[tokio::test]
+    async fn test_timeouts_are_retried_only_when_asked() {
+        let timed_out = || {
+            Err::<(), _>(Error::Llm(LlmError::RequestFailed {
+                model: "test".to_string(),
+                source: "Timeout after 60s".to_string(),
+            }))
+        };
+        let calls = AtomicU32::new(0);
+        let result = quick()
+            .run("test", || async {
+                calls.fetch_add(1, Ordering::SeqCst);
+                timed_out()
+            })
+            .await;
+        assert!(result.is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        let policy = RetryPolicy {
+            retry_timeouts: true,
+            ..quick()
+        };
+        let calls = AtomicU32::new(0);
+        let _ = policy
+            .run("test", || async {
+                calls.fetch_add(1, Ordering::SeqCst);
+                timed_out()
+            })
+            .await;
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_streaming_retry_does_not_repeat_output() {
+        let received = std::sync::Mutex::new(String::new());
+        let on_chunk = |chunk: &str| received.lock().unwrap().push_str(chunk);
+        let stream = RetryStream::new(&on_chunk);
+        let calls = AtomicU32::new(0);
+        let result = quick()
+            .run_streaming("test", &stream, || async {
+                stream.forward("partial ");
+                match calls.fetch_add(1, Ordering::SeqCst) {
+                    0 => Err(failed()),
+                    _ => Ok("whole response"),
+                }
+            })
+            .await;
+        assert_eq!(result.unwrap(), "whole response");
+        assert_eq!(*received.lock().unwrap(), "partial ");
+    }
+
+    #[test]
+    fn test_is_retryable() {
+        assert!(is_retryable(&failed()));
+        assert!(is_retryable(&Error::Llm(LlmError::ModelUnavailable(
+            "x".to_string()
+        ))));
+        assert!(!is_retryable(&Error::Llm(LlmError::AuthenticationFailed(
+            "x".to_string()
+        ))));
+        assert!(!is_retryable(&Error::Llm(LlmError::RateLimitExceeded {
+            model: "x".to_string(),
+            retry_after: None,
+        })));
+    }
+
+    #[tokio::test]
+    async fn test_run_retries_until_success() {
+        let calls = AtomicU32::new(0);
+        let result = quick()
+            .run("test", || async {
+                match calls.fetch_add(1, Ordering::SeqCst) {
+                    0 => Err(failed()),
+                    _ => Ok("done"),
+                }
+            })
+            .await;
+        assert_eq!(result.unwrap(), "done");
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_run_gives_up() {
+        let calls = AtomicU32::new(0);
+        let result: Result<(), Error> = quick()
+            .run("test", || async {
+                calls.fetch_add(1, Ordering::SeqCst);
+                Err(failed())
+            })
+            .await;
+        assert!(result.is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+
+        let calls = AtomicU32::new(0);
+        let result: Result<(), Error> = quick()
+            .run("test", || async {
+                calls.fetch_add(1, Ordering::SeqCst);
+                Err(Error::Llm(LlmError::AuthenticationFailed(
+                    "test".to_string(),
+                )))
+            })
+            .await;
+        assert!(result.is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+}
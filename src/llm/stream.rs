@@ -0,0 +1,137 @@
+//! Incremental output from subprocess providers
+//!
+//! Subprocess providers normally wait for the child to exit before reading
+//! anything. [`wait_with_streamed_stdout`] reads stdout as it is written
+//! instead, handing each decoded chunk to a callback so callers can show
+//! progress while a slow model is still answering.
+
+use std::io;
+use std::process::Output;
+use tokio::io::{AsyncRead, AsyncReadExt};
+use tokio::process::Child;
+
+/// Receives each piece of response text as it arrives
+pub type OnChunk<'a> = &'a (dyn Fn(&str) + Send + Sync);
+
+/// A callback that ignores every chunk
+pub fn ignore_chunks(_chunk: &str) {}
+
+/// Like [`Child::wait_with_output`], but passes stdout to `on_chunk` as
+/// it is read. Stderr is collected in full.
+pub async fn wait_with_streamed_stdout(
+    mut child: Child,
+    on_chunk: OnChunk<'_>,
+) -> io::Result<Output> {
+    let stdout = child.stdout.take();
+    let stderr = child.stderr.take();
+
+    let read_stdout = async {
+        let mut collected = Vec::new();
+        if let Some(stdout) = stdout {
+            read_streamed(stdout, &mut collected, on_chunk).await?;
+        }
+        Ok::<_, io::Error>(collected)
+    };
+    let read_stderr = async {
+        let mut collected = Vec::new();
+        if let Some(mut stderr) = stderr {
+            stderr.read_to_end(&mut collected).await?;
+        }
+        Ok::<_, io::Error>(collected)
+    };
+
+    let (stdout, stderr, status) = tokio::join!(read_stdout, read_stderr, child.wait());
+    Ok(Output {
+        status: status?,
+        stdout: stdout?,
+        stderr: stderr?,
+    })
+}
+
+/// Read `reader` to the end into `collected`, passing each complete run
+/// of UTF-8 to `on_chunk`. A character split across reads is held back
+/// until the rest of it arrives; invalid bytes are passed on lossily.
+async fn read_streamed<R: AsyncRead + Unpin>(
+    mut reader: R,
+    collected: &mut Vec<u8>,
+    on_chunk: OnChunk<'_>,
+) -> io::Result<()> {
+    let mut buf = [0u8; 4096];
+    // Start of bytes not yet passed to `on_chunk`
+    let mut pending = 0;
+
+    loop {
+        let n = reader.read(&mut buf).await?;
+        if n == 0 {
+            break;
+        }
+        collected.extend_from_slice(&buf[..n]);
+
+        let unsent = &collected[pending..];
+        let valid = match std::str::from_utf8(unsent) {
+            Ok(text) => text.len(),
+            // An incomplete character at the end: wait for more bytes
+            Err(e) if e.error_len().is_none() => e.valid_up_to(),
+            Err(_) => unsent.len(),
+        };
+        if valid > 0 {
+            on_chunk(&String::from_utf8_lossy(&unsent[..valid]));
+            pending += valid;
+        }
+    }
+
+    if pending < collected.len() {
+        on_chunk(&String::from_utf8_lossy(&collected[pending..]));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    #[tokio::test]
+    async fn test_read_streamed_keeps_split_characters_together() {
+        // "héllo" with the two-byte é split between reads
+        let (mut writer, reader) = tokio::io::duplex(64);
+        let write = async move {
+            use tokio::io::AsyncWriteExt;
+            writer.write_all(b"h\xc3").await.unwrap();
+            writer.flush().await.unwrap();
+            tokio::task::yield_now().await;
+            writer.write_all(b"\xa9llo").await.unwrap();
+        };
+
+        let chunks = Mutex::new(Vec::new());
+        let on_chunk = |chunk: &str| chunks.lock().unwrap().push(chunk.to_string());
+        let mut collected = Vec::new();
+        let (_, result) = tokio::join!(write, read_streamed(reader, &mut collected, &on_chunk));
+        result.unwrap();
+
+        assert_eq!(collected, "héllo".as_bytes());
+        let chunks = chunks.into_inner().unwrap();
+        assert_eq!(chunks.concat(), "héllo");
+        assert!(chunks.iter().all(|c| !c.contains('\u{FFFD}')));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_wait_with_streamed_stdout() {
+        let child = tokio::process::Command::new("sh")
+            .args(["-c", "printf one; printf two; printf err >&2"])
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::piped())
+            .spawn()
+            .unwrap();
+
+        let received = Mutex::new(String::new());
+        let on_chunk = |chunk: &str| received.lock().unwrap().push_str(chunk);
+        let output = wait_with_streamed_stdout(child, &on_chunk).await.unwrap();
+
+        assert!(output.status.success());
+        assert_eq!(output.stdout, b"onetwo");
+        assert_eq!(output.stderr, b"err");
+        assert_eq!(received.into_inner().unwrap(), "onetwo");
+    }
+}
@@ -0,0 +1,285 @@
+//! `.noggin/lock`: one writer at a time
+//!
+//! Commands that change the knowledge base (`learn`, `resolve`, `approve`,
+//! `consolidate`, ...) hold the lock while they run, so two invocations,
+//! or an editor plugin and the CLI, can't interleave writes to the
+//! manifest and ARF files. Read-only commands and dry runs don't take it.
+//!
+//! The lock file records the process holding it. A lock whose process is
+//! no longer running, or that is older than [`STALE_AFTER_HOURS`], is
+//! stale: it is replaced with a warning rather than blocking forever.
+//! Only one process at a time may clear a stale lock, so two that found
+//! it together can't each take the lock.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use tracing::warn;
+
+/// Lock file name under `.noggin/`
+pub const LOCK_FILE: &str = "lock";
+
+/// Age after which a lock is stale even if its process seems alive (the
+/// pid may have been reused)
+pub const STALE_AFTER_HOURS: i64 = 24;
+
+/// A lock file too new to have been written yet is still being acquired
+const WRITE_GRACE_SECS: u64 = 10;
+
+/// Who holds the lock, as stored in the lock file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LockHolder {
+    pub pid: u32,
+    /// Command that took the lock, e.g. "learn"
+    pub command: String,
+    pub started_at: DateTime<Utc>,
+}
+
+impl LockHolder {
+    fn current(command: &str) -> Self {
+        Self {
+            pid: std::process::id(),
+            command: command.to_string(),
+            started_at: Utc::now(),
+        }
+    }
+
+    /// The holder's process is gone, or the lock is too old to trust
+    pub fn is_stale(&self) -> bool {
+        Utc::now() - self.started_at > Duration::hours(STALE_AFTER_HOURS)
+            || !process_alive(self.pid)
+    }
+}
+
+/// Exclusive hold on a knowledge base, released when dropped
+#[derive(Debug)]
+pub struct KnowledgeLock {
+    path: PathBuf,
+}
+
+impl KnowledgeLock {
+    /// Lock the knowledge base at `noggin_path` for `command`. Fails if
+    /// another live process holds it; a stale lock is replaced.
+    pub fn acquire(noggin_path: &Path, command: &str) -> Result<Self> {
+        Self::acquire_file(noggin_path.join(LOCK_FILE), command)
+    }
+
+    /// [`acquire`](Self::acquire) with the lock file at `path`, for state
+    /// kept outside a knowledge base
+    pub fn acquire_file(path: PathBuf, command: &str) -> Result<Self> {
+        let holder = LockHolder::current(command);
+        let contents = toml::to_string(&holder).context("Failed to serialize lock")?;
+
+        // Twice: once more after clearing a stale lock
+        for _ in 0..2 {
+            match OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(mut file) => {
+                    file.write_all(contents.as_bytes())
+                        .with_context(|| format!("Failed to write {}", path.display()))?;
+                    return Ok(Self { path });
+                }
+                Err(e) if e.kind() == ErrorKind::AlreadyExists => {}
+                Err(e) => {
+                    return Err(e).with_context(|| format!("Failed to create {}", path.display()))
+                }
+            }
+
+            let stale = match read_holder(&path) {
+                Some(current) if !current.is_stale() => anyhow::bail!(
+                    "Knowledge base is locked by 'noggin {}' (pid {}, since {}). \
+                     Wait for it to finish, or remove {} if it is no longer running.",
+                    current.command,
+                    current.pid,
+                    current.started_at.format("%Y-%m-%d %H:%M:%S UTC"),
+                    path.display()
+                ),
+                Some(current) => {
+                    warn!(
+                        "Replacing stale lock from 'noggin {}' (pid {})",
+                        current.command, current.pid
+                    );
+                    Some(current)
+                }
+                None if recently_modified(&path) => anyhow::bail!(
+                    "Knowledge base is being locked by another process; try again"
+                ),
+                None => {
+                    warn!("Replacing unreadable lock {}", path.display());
+                    None
+                }
+            };
+            clear_stale(&path, stale.as_ref())?;
+        }
+        anyhow::bail!("Failed to lock {}: another process took it first", path.display())
+    }
+}
+
+impl Drop for KnowledgeLock {
+    fn drop(&mut self) {
+        // Only remove the lock if it is still ours
+        let ours = read_holder(&self.path).is_some_and(|h| h.pid == std::process::id());
+        if ours {
+            let _ = fs::remove_file(&self.path);
+        }
+    }
+}
+
+/// The current holder of the lock at `noggin_path`, if any
+pub fn holder(noggin_path: &Path) -> Option<LockHolder> {
+    read_holder(&noggin_path.join(LOCK_FILE))
+}
+
+/// Remove the lock at `path` if it still holds `stale` (or is still
+/// unreadable, for None). This happens under a breaker file only one
+/// process can create, so a process that found the same stale lock late
+/// can't remove the lock another has just taken in its place.
+fn clear_stale(path: &Path, stale: Option<&LockHolder>) -> Result<()> {
+    let breaker = path.with_extension("break");
+    let mut created = OpenOptions::new().write(true).create_new(true).open(&breaker);
+    // A breaker left by a process that died while clearing
+    if matches!(&created, Err(e) if e.kind() == ErrorKind::AlreadyExists)
+        && !recently_modified(&breaker)
+    {
+        let _ = fs::remove_file(&breaker);
+        created = OpenOptions::new().write(true).create_new(true).open(&breaker);
+    }
+    match created {
+        Ok(_) => {}
+        Err(e) if e.kind() == ErrorKind::AlreadyExists => {
+            anyhow::bail!("Knowledge base is being locked by another process; try again")
+        }
+        Err(e) => return Err(e).with_context(|| format!("Failed to create {}", breaker.display())),
+    }
+
+    let current = read_holder(path);
+    let unchanged = match stale {
+        Some(stale) => current.as_ref() == Some(stale),
+        None => current.is_none() && !recently_modified(path),
+    };
+    let removed = if unchanged {
+        match fs::remove_file(path) {
+            Err(e) if e.kind() != ErrorKind::NotFound => {
+                Err(e).with_context(|| format!("Failed to remove {}", path.display()))
+            }
+            _ => Ok(()),
+        }
+    } else {
+        Ok(())
+    };
+    let _ = fs::remove_file(&breaker);
+    removed
+}
+
+fn read_holder(path: &Path) -> Option<LockHolder> {
+    let contents = fs::read_to_string(path).ok()?;
+    toml::from_str(&contents).ok()
+}
+
+fn recently_modified(path: &Path) -> bool {
+    fs::metadata(path)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+        .is_some_and(|age| age.as_secs() < WRITE_GRACE_SECS)
+}
+
+#[cfg(target_os = "linux")]
+fn process_alive(pid: u32) -> bool {
+    Path::new("/proc").join(pid.to_string()).exists()
+}
+
+#[cfg(all(unix, not(target_os = "linux")))]
+fn process_alive(pid: u32) -> bool {
+    std::process::Command::new("kill")
+        .args(["-0", &pid.to_string()])
+        .stderr(std::process::Stdio::null())
+        .status()
+        .is_ok_and(|status| status.success())
+}
+
+/// Without a cheap liveness check, only age makes a lock stale
+#[cfg(not(unix))]
+fn process_alive(_pid: u32) -> bool {
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_lock_excludes_second_holder() {
+        let temp_dir = TempDir::new().unwrap();
+        let noggin_path = temp_dir.path();
+
+        let lock = KnowledgeLock::acquire(noggin_path, "learn").unwrap();
+        assert_eq!(holder(noggin_path).unwrap().command, "learn");
+
+        let err = KnowledgeLock::acquire(noggin_path, "resolve").unwrap_err();
+        assert!(err.to_string().contains("locked by 'noggin learn'"));
+
+        drop(lock);
+        assert!(holder(noggin_path).is_none());
+        KnowledgeLock::acquire(noggin_path, "resolve").unwrap();
+    }
+
+    #[test]
+    fn test_stale_lock_is_replaced() {
+        let temp_dir = TempDir::new().unwrap();
+        let noggin_path = temp_dir.path();
+        let write = |holder: &LockHolder| {
+            fs::write(noggin_path.join(LOCK_FILE), toml::to_string(holder).unwrap()).unwrap();
+        };
+
+        // Our own pid is alive, but the lock is two days old
+        write(&LockHolder {
+            pid: std::process::id(),
+            command: "learn".to_string(),
+            started_at: Utc::now() - Duration::hours(48),
+        });
+        let lock = KnowledgeLock::acquire(noggin_path, "approve").unwrap();
+        assert_eq!(holder(noggin_path).unwrap().command, "approve");
+        drop(lock);
+
+        // No process has this pid
+        write(&LockHolder {
+            pid: u32::MAX,
+            command: "learn".to_string(),
+            started_at: Utc::now(),
+        });
+        assert!(holder(noggin_path).unwrap().is_stale());
+        KnowledgeLock::acquire(noggin_path, "approve").unwrap();
+    }
+
+    #[test]
+    fn test_clearing_a_stale_lock_spares_its_replacement() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join(LOCK_FILE);
+        let stale = LockHolder {
+            pid: u32::MAX,
+            command: "learn".to_string(),
+            started_at: Utc::now(),
+        };
+
+        // Another process cleared the stale lock and took it since we read it
+        let lock = KnowledgeLock::acquire_file(path.clone(), "approve").unwrap();
+        clear_stale(&path, Some(&stale)).unwrap();
+        assert_eq!(read_holder(&path).unwrap().command, "approve");
+
+        // Another process is clearing it right now
+        fs::write(path.with_extension("break"), "").unwrap();
+        drop(lock);
+        fs::write(&path, toml::to_string(&stale).unwrap()).unwrap();
+        let err = KnowledgeLock::acquire_file(path.clone(), "resolve").unwrap_err();
+        assert!(err.to_string().contains("try again"));
+
+        fs::remove_file(path.with_extension("break")).unwrap();
+        KnowledgeLock::acquire_file(path.clone(), "resolve").unwrap();
+        assert!(!path.with_extension("break").exists());
+    }
+}
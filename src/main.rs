@@ -1,27 +1,119 @@
-use clap::{Parser, Subcommand};
-use colored::Colorize;
-use llm_noggin::commands::init::init_command;
-use llm_noggin::commands::learn::learn_command;
+use clap::{CommandFactory, Parser, Subcommand};
+use clap_complete::{generate, Shell};
+use llm_noggin::commands::approve::approve_command;
+use llm_noggin::commands::ask::{ask_command, AskOptions};
+use llm_noggin::commands::bundle::{bundle_command, BundleOptions};
+use llm_noggin::commands::check::check_command;
+use llm_noggin::commands::ci::{ci_command, CiMode, CiOptions};
+use llm_noggin::commands::conflicts::{conflicts_explain_command, ConflictsExplainOptions};
+use llm_noggin::commands::consolidate::{consolidate_command, ConsolidateOptions};
+use llm_noggin::commands::coverage::{coverage_command, CoverageOptions};
+use llm_noggin::commands::describe::{describe_command, DescribeOptions};
+use llm_noggin::commands::diff::{diff_command, DiffOptions};
+use llm_noggin::commands::doctor::{doctor_command, DoctorOptions};
+use llm_noggin::commands::examples::examples_command;
+use llm_noggin::commands::experiment::{experiment_command, ExperimentOptions};
+use llm_noggin::commands::export::{export_command, ExportFormat, ExportOptions};
+use llm_noggin::commands::graph::{graph_command, GraphFormat, GraphOptions};
+use llm_noggin::commands::grep::{grep_command, GrepOptions};
+use llm_noggin::commands::hook::{hook_install_command, prepare_commit_msg_command};
+use llm_noggin::commands::init::{init_command, InitOptions};
+use llm_noggin::commands::learn::{
+    learn_command, learn_remote_command, LearnOptions, RemoteOptions,
+};
+use llm_noggin::commands::log::{log_command, LogOptions};
+use llm_noggin::commands::maintain::{maintain_command, MaintainOptions};
+use llm_noggin::commands::manifest::{
+    manifest_export_command, manifest_import_command, ManifestExportOptions, ManifestFormat,
+    ManifestImportOptions,
+};
+use llm_noggin::commands::prune::{prune_command, PruneOptions};
+use llm_noggin::commands::purge::{purge_command, PurgeOptions};
+use llm_noggin::commands::resolve::{resolve_command, ResolveOptions};
+use llm_noggin::commands::review::{review_command, ReviewOptions};
+use llm_noggin::commands::schema::schema_command;
 use llm_noggin::commands::serve::serve_command;
+use llm_noggin::commands::snapshot::{snapshot_command, SnapshotOptions};
+use llm_noggin::commands::stats::stats_command;
 use llm_noggin::commands::status::status_command;
-use llm_noggin::git::walker::{walk_commits, WalkOptions};
-use llm_noggin::query::{QueryEngine, QueryOptions};
+use llm_noggin::commands::synthesize::{synthesize_command, SynthesizeOptions};
+use llm_noggin::commands::translate::{translate_command, TranslateOptions};
+use llm_noggin::commands::verify_knowledge::{verify_knowledge_command, VerifyKnowledgeOptions};
+use llm_noggin::git::remote::DEFAULT_CLONE_DEPTH;
+use llm_noggin::git::walker::{walk_commits, CommitMetadata, WalkOptions};
+use llm_noggin::query::{parse_since, parse_until, SortBy};
+use llm_noggin::retention::PurgeTarget;
+use llm_noggin::time::{format_unix, unix_to_iso8601};
+use serde::Serialize;
 use std::env;
+use std::io;
+use std::path::PathBuf;
 
 #[derive(Parser)]
 #[command(name = "noggin")]
 #[command(about = "Your codebase's noggin - extract and query codebase knowledge", long_about = None)]
+#[command(after_help = "Run 'noggin examples' for common workflows.")]
 struct Cli {
+    /// Show times in UTC instead of the local timezone
+    #[arg(long, global = true)]
+    utc: bool,
+
     #[command(subcommand)]
     command: Commands,
 }
 
+/// A walked commit with its timestamp rendered as ISO-8601 for JSON output
+#[derive(Serialize)]
+struct WalkedCommit<'a> {
+    #[serde(flatten)]
+    commit: &'a CommitMetadata,
+    date: String,
+}
+
 #[derive(Subcommand)]
 enum Commands {
     /// Initialize .noggin/ directory in current repository
-    Init,
+    #[command(after_help = "\
+Examples:
+  noggin init               Offers a survey of the repository on a terminal
+  noggin init --guide       Survey without asking: stack, key commits, cost
+  noggin init --no-guide")]
+    Init {
+        /// Survey the repository and suggest a first learn, without asking
+        #[arg(long, conflicts_with = "no_guide")]
+        guide: bool,
+
+        /// Skip the survey
+        #[arg(long)]
+        no_guide: bool,
+    },
+
+    /// Find the provider CLIs and record their paths in config
+    #[command(after_help = "\
+Examples:
+  noggin doctor          Re-check after installing or moving a provider CLI
+  noggin doctor --json")]
+    Doctor {
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
 
     /// Analyze codebase and generate/update knowledge base
+    #[command(after_help = "\
+Examples:
+  noggin learn                      Process changed files and new commits
+  noggin learn --full               Re-analyze everything
+  noggin learn --verify             Check for drift without writing
+  noggin learn --dry-run            Analyze and show the diffs it would write
+  noggin learn --max-cost 2.50      Stop once estimated spend reaches $2.50
+  noggin learn --max-commits 50     Only the 50 oldest unprocessed significant commits
+  noggin learn --path services/api  Only files and commits under services/api
+  noggin learn --since-date 90d     Only commits from the last 90 days
+  noggin learn --range main..HEAD   Only the commits and files of a pull request
+  noggin learn --ref release/2.x    Learn from a release branch instead of HEAD
+  noggin learn --events -           Print progress as JSON lines instead
+  noggin learn --remote https://github.com/owner/repo")]
     Learn {
         /// Verify manifest without overwriting
         #[arg(long)]
@@ -30,40 +122,700 @@ enum Commands {
         /// Force full analysis (ignore manifest, re-analyze everything)
         #[arg(long)]
         full: bool,
+
+        /// Query providers but only report what would be written
+        #[arg(long, conflicts_with = "verify")]
+        dry_run: bool,
+
+        /// Stop issuing prompts once estimated spend reaches this many USD
+        #[arg(long, value_name = "USD")]
+        max_cost: Option<f64>,
+
+        /// Stop issuing prompts after this many seconds
+        #[arg(long, value_name = "SECS")]
+        max_time: Option<u64>,
+
+        /// Analyze at most this many significant commits, oldest first;
+        /// the rest are left for later runs
+        #[arg(long, value_name = "N")]
+        max_commits: Option<usize>,
+
+        /// Write refs/notes/noggin notes linking commits to their ARFs
+        #[arg(long)]
+        notes: bool,
+
+        /// Rebind a knowledge base copied from another repository or fork
+        #[arg(long)]
+        force_adopt: bool,
+
+        /// Walk this branch, tag, or commit instead of HEAD, along with any
+        /// refs in config (repeatable). Files are read from the working
+        /// tree, so the ref must be checked out
+        #[arg(long = "ref", value_name = "REF")]
+        refs: Vec<String>,
+
+        /// Walk every local and remote-tracking branch
+        #[arg(long)]
+        all_branches: bool,
+
+        /// Only analyze files and commits matching this pathspec (repeatable)
+        #[arg(long = "path", value_name = "GLOB")]
+        paths: Vec<String>,
+
+        /// Only analyze the commits in this range and the files they changed
+        #[arg(long, value_name = "BASE..HEAD", conflicts_with_all = ["refs", "all_branches"])]
+        range: Option<String>,
+
+        /// Analyze commits per release tag, writing entries for each release
+        #[arg(long)]
+        by_release: bool,
+
+        /// Only commits whose author name or email contains this (repeatable)
+        #[arg(long = "author", value_name = "NAME")]
+        authors: Vec<String>,
+
+        /// Only commits authored since this date (YYYY-MM-DD, RFC 3339, or e.g. 90d)
+        #[arg(long, value_name = "DATE")]
+        since_date: Option<String>,
+
+        /// Only commits authored up to this date, inclusive (YYYY-MM-DD or RFC 3339)
+        #[arg(long, value_name = "DATE")]
+        until_date: Option<String>,
+
+        /// Build structural facts without any provider (TODOs, dependencies, ownership)
+        #[arg(long)]
+        lite: bool,
+
+        /// Write JSONL progress events to a file or named pipe (`-` for stdout)
+        #[arg(long, value_name = "PATH")]
+        events: Option<PathBuf>,
+
+        /// Clone and analyze a remote repository instead of the current one
+        #[arg(long, value_name = "URL")]
+        remote: Option<String>,
+
+        /// Commits to fetch for --remote (0 for full history)
+        #[arg(long, value_name = "N", default_value_t = DEFAULT_CLONE_DEPTH, requires = "remote")]
+        depth: u32,
+
+        /// Knowledge base directory for --remote (default: ./<repo>.noggin)
+        #[arg(long, value_name = "DIR", requires = "remote")]
+        output: Option<PathBuf>,
+    },
+
+    /// Run verify or incremental learn unattended (JSON logs, report files)
+    #[command(after_help = "\
+Examples:
+  noggin ci                                 Fail when the knowledge base has drifted
+  noggin ci --mode learn --commit --push    Update, commit, and push the knowledge")]
+    Ci {
+        /// verify: fail on drift; learn: update the knowledge base
+        #[arg(long, value_enum, default_value = "verify")]
+        mode: CiMode,
+
+        /// Directory for report.json, drift-report.json, and arfs.json
+        #[arg(long, default_value = "noggin-output")]
+        output_dir: PathBuf,
+
+        /// Commit knowledge and manifest changes after learning
+        #[arg(long)]
+        commit: bool,
+
+        /// Push the knowledge commit (implies --commit)
+        #[arg(long)]
+        push: bool,
+
+        /// Environment variable holding the push token
+        #[arg(long, default_value = "GITHUB_TOKEN")]
+        token_env: String,
+
+        /// Remote to push to
+        #[arg(long, default_value = "origin")]
+        remote: String,
+
+        /// Branch to push to (defaults to the current branch)
+        #[arg(long)]
+        branch: Option<String>,
+
+        /// Stop issuing prompts once estimated spend reaches this many USD
+        #[arg(long, value_name = "USD")]
+        max_cost: Option<f64>,
+
+        /// Stop issuing prompts after this many seconds
+        #[arg(long, value_name = "SECS")]
+        max_time: Option<u64>,
+    },
+
+    /// Do a bounded slice of deferred work (gc, index, refresh, backfill)
+    Maintain {
+        /// Maximum unprocessed commits to backfill this run
+        #[arg(long)]
+        max_commits: Option<usize>,
+
+        /// Refresh patterns not updated in this many days
+        #[arg(long, value_name = "DAYS")]
+        stale_days: Option<u32>,
+
+        /// Maximum stale patterns to refresh this run
+        #[arg(long)]
+        max_refresh: Option<usize>,
+
+        /// Report what would change without touching disk
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Stop issuing prompts once estimated spend reaches this many USD
+        #[arg(long, value_name = "USD")]
+        max_cost: Option<f64>,
+
+        /// Stop issuing prompts after this many seconds
+        #[arg(long, value_name = "SECS")]
+        max_time: Option<u64>,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Drop manifest entries, patterns, and ARFs for files and commits that are gone
+    Prune {
+        /// Also compact commit entries processed more than this many days ago
+        #[arg(long, value_name = "DAYS")]
+        compact_commits: Option<u32>,
+
+        /// Show what would be removed without changing anything
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Remove raw responses, caches, logs, and checkpoints
+    Purge {
+        /// Raw provider responses (.noggin/raw)
+        #[arg(long)]
+        raw: bool,
+
+        /// Caches, including the search index
+        #[arg(long)]
+        cache: bool,
+
+        /// Log files (.noggin/logs)
+        #[arg(long)]
+        logs: bool,
+
+        /// The learn checkpoint of deferred prompts
+        #[arg(long)]
+        checkpoint: bool,
+
+        /// Only remove files past the [retention] age and size limits
+        #[arg(long, conflicts_with_all = ["raw", "cache", "logs", "checkpoint"])]
+        expired: bool,
+
+        /// Show what would be removed without deleting anything
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Mark ARF files as reviewed so learn won't overwrite them
+    Approve {
+        /// ARF files to approve (relative to the repo or .noggin/)
+        #[arg(required = true)]
+        paths: Vec<String>,
+
+        /// Remove approval instead
+        #[arg(long)]
+        revoke: bool,
+
+        /// Show what would change without saving
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Draft a PR description for the current branch, with knowledge impact
+    #[command(after_help = "\
+Examples:
+  noggin describe                   Draft against main using claude
+  noggin describe --base develop -o pr.md
+  noggin describe --no-llm          Draft from the diff alone")]
+    Describe {
+        /// Branch the PR will merge into
+        #[arg(long, default_value = "main")]
+        base: String,
+
+        /// Provider that drafts the description (claude, codex, gemini, openai)
+        #[arg(long, default_value = "claude")]
+        provider: String,
+
+        /// Write the description to a file instead of stdout
+        #[arg(long, short)]
+        output: Option<PathBuf>,
+
+        /// Skip the provider and print a draft built from the diff and knowledge base
+        #[arg(long)]
+        no_llm: bool,
+    },
+
+    /// Check a diff against documented patterns and decisions (advisory)
+    #[command(after_help = "\
+Examples:
+  noggin review-diff main..HEAD     Review the current branch
+  noggin review-diff --staged       Review what's about to be committed
+  noggin review-diff HEAD --strict  Fail on error-severity findings")]
+    ReviewDiff {
+        /// Revision range (e.g. main..HEAD) or a single commit
+        #[arg(required_unless_present = "staged")]
+        range: Option<String>,
+
+        /// Review staged changes instead of a range
+        #[arg(long, conflicts_with = "range")]
+        staged: bool,
+
+        /// Provider that performs the review (claude, codex, gemini, openai)
+        #[arg(long, default_value = "claude")]
+        provider: String,
+
+        /// Output the report as JSON
+        #[arg(long)]
+        json: bool,
+
+        /// Exit non-zero when any finding has error severity
+        #[arg(long)]
+        strict: bool,
+    },
+
+    /// Validate ARF files and evaluate pattern rules against the codebase (no LLM)
+    #[command(after_help = "\
+Examples:
+  noggin check                      Report rule violations
+  noggin check --json               Machine-readable report for CI")]
+    Check {
+        /// Output the report as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Write knowledge-base artifacts for other tools
+    #[command(after_help = "\
+Examples:
+  noggin export --format editor     Schema, grammar, snippets, and templates for editing .arf
+  noggin export --format markdown   Pages for mkdocs or Docusaurus in .noggin/site
+  noggin export --format markdown -o docs/knowledge
+  noggin export --format json --dry-run
+  noggin export --format json       Every entry plus manifest stats in .noggin/knowledge.json")]
+    Export {
+        /// What to export
+        #[arg(long, value_enum)]
+        format: ExportFormat,
+
+        /// Where to write [default: .noggin/editor, .noggin/site for
+        /// markdown, .noggin/knowledge.json for json]
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+
+        /// Show what would be written without writing it
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Pack the knowledge base into one file to share or attach to a release
+    #[command(after_help = "\
+Examples:
+  noggin bundle                     Write noggin-bundle.tar.gz
+  noggin bundle -o dist/knowledge-v1.2.tar.gz
+  noggin bundle --dry-run           Show what would be written
+  noggin ask --bundle dist/knowledge-v1.2.tar.gz \"why sqlx?\"")]
+    Bundle {
+        /// File to write
+        #[arg(short, long, default_value = "noggin-bundle.tar.gz")]
+        output: PathBuf,
+
+        /// Show what would be written without writing it
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Export a graph of ARFs and the files and commits they cite
+    #[command(after_help = "\
+Examples:
+  noggin graph | dot -Tsvg > knowledge.svg
+  noggin graph --format json -o graph.json")]
+    Graph {
+        /// Output format
+        #[arg(long, value_enum, default_value = "dot")]
+        format: GraphFormat,
+
+        /// Write to this file instead of stdout
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+
+    /// Export or import tracking state (files, commits, patterns)
+    #[command(after_help = "\
+Examples:
+  noggin manifest export --format json > manifest.json
+  noggin manifest import manifest.json --force")]
+    Manifest {
+        #[command(subcommand)]
+        action: ManifestAction,
+    },
+
+    /// Print the JSON Schema for ARF files, for editors and external tools
+    Schema {
+        /// Write the schema to this file instead of stdout
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+
+    /// Synthesize raw model responses produced outside noggin and write
+    /// the consensus to the knowledge base
+    #[command(after_help = "\
+Examples:
+  noggin synthesize --input responses/       Files named <prompt_type>-<model>.txt
+  noggin synthesize --input outputs.json --dry-run
+  my-pipeline | noggin synthesize --json     JSON array of {model, prompt_type, response}")]
+    Synthesize {
+        /// Directory of response files or a JSON file; stdin if omitted or `-`
+        #[arg(long, short)]
+        input: Option<PathBuf>,
+
+        /// Show what would be written without writing it
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Output the report as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Compare two configurations on the same analysis scope (writes nothing)
+    #[command(after_help = "\
+Examples:
+  noggin experiment --config-a a.toml --config-b b.toml
+  noggin experiment --config-a a.toml --config-b b.toml --max-files 5 --json")]
+    Experiment {
+        /// First configuration file
+        #[arg(long, value_name = "FILE")]
+        config_a: PathBuf,
+
+        /// Second configuration file
+        #[arg(long, value_name = "FILE")]
+        config_b: PathBuf,
+
+        /// Most changed files to analyze
+        #[arg(long, default_value_t = 20)]
+        max_files: usize,
+
+        /// Most recent significant commits to analyze
+        #[arg(long, default_value_t = 10)]
+        max_commits: usize,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Propose merges of similar entries in an oversized category
+    #[command(after_help = "\
+Examples:
+  noggin consolidate patterns           Show proposed merges
+  noggin consolidate patterns --apply   Write them")]
+    Consolidate {
+        /// Category to consolidate (decisions, patterns, bugs, migrations, facts)
+        category: String,
+
+        /// Write the merges instead of only proposing them
+        #[arg(long)]
+        apply: bool,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Git hook integration
+    Hook {
+        #[command(subcommand)]
+        action: HookAction,
     },
 
     /// Query the knowledge base
+    #[command(after_help = "\
+Examples:
+  noggin ask \"why do we use sqlx?\"
+  noggin ask retry --category bugs --since 30d
+  noggin ask auth --tag security --min-confidence 0.8
+  noggin ask --saved onboarding     Run a question from .noggin/queries.toml
+  noggin ask retry -o retry.md      Save the answer as Markdown to share
+  noggin ask --bundle knowledge.tar.gz \"how are releases cut?\"
+  noggin ask --batch todo.json      Answer many questions as one JSON array")]
     Ask {
         /// Question to ask about the codebase
-        query: String,
+        #[arg(required_unless_present_any = ["saved", "batch"])]
+        query: Option<String>,
+
+        /// Run a named question from .noggin/queries.toml
+        #[arg(long, value_name = "NAME", conflicts_with = "query")]
+        saved: Option<String>,
 
         /// Maximum number of results (default 10)
-        #[arg(long, default_value = "10")]
-        max_results: usize,
+        #[arg(long)]
+        max_results: Option<usize>,
 
         /// Filter by category (decisions, patterns, bugs, migrations, facts)
         #[arg(long)]
         category: Option<String>,
 
+        /// Only entries with this tag (repeatable; all must match)
+        #[arg(long = "tag", value_name = "TAG")]
+        tags: Vec<String>,
+
+        /// Only entries with at least this confidence (0.0 to 1.0)
+        #[arg(long, value_name = "SCORE")]
+        min_confidence: Option<f64>,
+
+        /// Only entries updated since (YYYY-MM-DD, RFC 3339, or e.g. 30d)
+        #[arg(long, value_name = "DATE")]
+        since: Option<String>,
+
+        /// Order results by relevance or by confidence
+        #[arg(long, value_enum, default_value_t = SortBy::Relevance)]
+        sort: SortBy,
+
         /// Output as JSON
         #[arg(long)]
         json: bool,
+
+        /// Write the answer to a file as Markdown (JSON with --json)
+        #[arg(short, long, value_name = "FILE")]
+        output: Option<PathBuf>,
+
+        /// Query a bundle made by 'noggin bundle' instead of .noggin/
+        #[arg(long, value_name = "FILE")]
+        bundle: Option<PathBuf>,
+
+        /// Answer a JSON array of questions (strings, or objects with their
+        /// own filters) as one JSON array of answers; '-' reads stdin
+        #[arg(long, value_name = "FILE", conflicts_with_all = ["query", "saved"])]
+        batch: Option<PathBuf>,
+    },
+
+    /// Search ARF fields with a structured expression, without a model
+    #[command(after_help = "\
+Fields: what, why, how, tag, category, file, path, issue, person, confidence.
+Terms are joined with AND (or juxtaposition), OR, NOT, and parentheses;
+a term without a field searches what, why, how, and tags.
+
+Examples:
+  noggin grep 'what:pooling AND category:decisions AND file:src/db/**'
+  noggin grep '(tag:retry OR tag:backoff) NOT category:facts'
+  noggin grep 'confidence:<0.5' --files-only
+  noggin grep 'why:\"connection limits\"' --json")]
+    Grep {
+        /// Search expression
+        expression: String,
+
+        /// Maximum number of matches (default: all)
+        #[arg(long, short = 'n')]
+        limit: Option<usize>,
+
+        /// Print only the paths of matching ARFs
+        #[arg(long, short = 'l')]
+        files_only: bool,
+
+        /// Output as JSON
+        #[arg(long, conflicts_with = "files_only")]
+        json: bool,
     },
 
     /// Start MCP server for tool integration
     Serve,
 
-    /// Show what's scanned and what's pending
+    /// Show what's scanned and what's pending (exits non-zero on drift)
+    #[command(after_help = "\
+Examples:
+  noggin status                     Summary of scanned files and pending commits
+  noggin status -v                  List individual files and commits
+  noggin status --json --utc")]
     Status {
         /// Show detailed file and commit listings
         #[arg(long, short)]
         verbose: bool,
 
+        /// List entries below this confidence (default: decay.low_threshold)
+        #[arg(long, value_name = "SCORE")]
+        below: Option<f64>,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Ask providers whether the least-confident entries still match the code
+    #[command(after_help = "\
+Examples:
+  noggin verify-knowledge                 Check the 10 lowest-confidence entries
+  noggin verify-knowledge --sample 25
+  noggin verify-knowledge --dry-run --json")]
+    VerifyKnowledge {
+        /// Number of entries to check, lowest confidence first
+        #[arg(long, default_value_t = 10)]
+        sample: usize,
+
+        /// Query providers but don't update entries or open reviews
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Record the knowledge base's entries under a name, e.g. a release
+    #[command(after_help = "\
+Examples:
+  noggin snapshot v1.4                Record entry hashes in .noggin/snapshots/v1.4.toml
+  noggin snapshot v1.4 --tag          Also tag HEAD as noggin/v1.4 (.noggin/ must be committed)")]
+    Snapshot {
+        /// Snapshot name (letters, digits, '.', '-', '_')
+        name: String,
+
+        /// Tag HEAD as noggin/<name> so diffs can show changed fields
+        #[arg(long)]
+        tag: bool,
+
+        /// Replace an existing snapshot and tag of the same name
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Show entries added, removed, or changed since a snapshot
+    #[command(after_help = "\
+Examples:
+  noggin diff --snapshot v1.4             Changes since v1.4
+  noggin diff --snapshot v1.3 --to v1.4   Changes between two releases")]
+    Diff {
+        /// Snapshot to compare from
+        #[arg(long, value_name = "NAME")]
+        snapshot: String,
+
+        /// Snapshot to compare to (default: the current knowledge base)
+        #[arg(long, value_name = "NAME")]
+        to: Option<String>,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Rewrite entries not written in llm.output_language
+    Translate {
+        /// Provider that translates (default: the first configured provider)
+        #[arg(long)]
+        provider: Option<String>,
+
+        /// Query the provider but don't update entries
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Show how much of the significant history produced knowledge, per
+    /// directory
+    #[command(after_help = "\
+Examples:
+  noggin coverage                   Coverage per top-level directory
+  noggin coverage --depth 2 --json")]
+    Coverage {
+        /// Path components that make up a directory
+        #[arg(long, default_value_t = 1)]
+        depth: usize,
+
         /// Output as JSON
         #[arg(long)]
         json: bool,
     },
 
+    /// Choose between conflicting values learn left for review
+    #[command(after_help = "\
+Examples:
+  noggin resolve                    Walk through every open conflict
+  noggin resolve --list             Show open conflicts
+  noggin resolve use-pooling.how    Resolve one conflict
+  noggin resolve use-pooling.how --pick 2")]
+    Resolve {
+        /// Conflict ID, or its path under .noggin/
+        id: Option<String>,
+
+        /// List open conflicts without resolving any
+        #[arg(long, conflicts_with = "pick")]
+        list: bool,
+
+        /// Keep candidate N for every field instead of asking
+        #[arg(long, value_name = "N")]
+        pick: Option<usize>,
+
+        /// Output the list as JSON
+        #[arg(long, requires = "list")]
+        json: bool,
+    },
+
+    /// Inspect open conflicts
+    Conflicts {
+        #[command(subcommand)]
+        action: ConflictsAction,
+    },
+
+    /// List previous learn runs and their results
+    #[command(after_help = "\
+Examples:
+  noggin log                        Most recent learn runs
+  noggin log -n 5 --verbose         Last five runs with their warnings
+  noggin log --json")]
+    Log {
+        /// Number of runs to show
+        #[arg(long, short = 'n', default_value_t = 20)]
+        limit: usize,
+
+        /// Show each run's warnings
+        #[arg(long, short)]
+        verbose: bool,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Show local usage counters (asks, learn runs, cache hit rate)
+    Stats {
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Generate shell completions
+    #[command(after_help = "\
+Examples:
+  noggin completions bash > ~/.local/share/bash-completion/completions/noggin
+  noggin completions zsh > ~/.zfunc/_noggin
+  noggin completions fish > ~/.config/fish/completions/noggin.fish")]
+    Completions {
+        /// Shell to generate completions for
+        shell: Shell,
+    },
+
+    /// Print common workflows
+    Examples,
+
     /// Walk git commits and display metadata (debug)
     GitWalk {
         /// Start from specific commit hash
@@ -74,97 +826,517 @@ enum Commands {
         #[arg(long)]
         limit: Option<usize>,
 
+        /// Walk this branch, tag, or commit instead of HEAD (repeatable)
+        #[arg(long = "ref", value_name = "REF")]
+        refs: Vec<String>,
+
+        /// Only show commits touching this pathspec (repeatable)
+        #[arg(long = "path", value_name = "GLOB")]
+        paths: Vec<String>,
+
+        /// Only commits whose author name or email contains this (repeatable)
+        #[arg(long = "author", value_name = "NAME")]
+        authors: Vec<String>,
+
+        /// Only commits authored since this date (YYYY-MM-DD, RFC 3339, or e.g. 90d)
+        #[arg(long, value_name = "DATE")]
+        since_date: Option<String>,
+
+        /// Only commits authored up to this date, inclusive (YYYY-MM-DD or RFC 3339)
+        #[arg(long, value_name = "DATE")]
+        until_date: Option<String>,
+
         /// Output as JSON
         #[arg(long)]
         json: bool,
     },
 }
 
-#[tokio::main]
-async fn main() -> anyhow::Result<()> {
-    let cli = Cli::parse();
+#[derive(Subcommand)]
+enum ManifestAction {
+    /// Write the complete manifest to stdout or a file
+    Export {
+        /// Output format
+        #[arg(long, value_enum, default_value = "json")]
+        format: ManifestFormat,
 
-    match cli.command {
-        Commands::Init => init_command(),
-        Commands::Learn { verify, full } => learn_command(full, verify).await,
-        Commands::Ask { query, max_results, category, json } => {
-            let repo_path = env::current_dir()?;
-            let noggin_path = repo_path.join(".noggin");
+        /// Write to this file instead of stdout
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
 
-            if !noggin_path.exists() {
-                anyhow::bail!("Not initialized. Run 'noggin init' first.");
-            }
+    /// Replace .noggin/manifest.toml with an exported manifest
+    Import {
+        /// Exported manifest
+        input: PathBuf,
 
-            let engine = QueryEngine::new(noggin_path);
-            let opts = QueryOptions {
-                max_results,
-                category,
-            };
+        /// Input format (inferred from the file extension by default)
+        #[arg(long, value_enum)]
+        format: Option<ManifestFormat>,
 
-            let results = engine.search(&query, &opts)?;
+        /// Replace a manifest that already tracks files or commits
+        #[arg(long)]
+        force: bool,
 
-            if results.is_empty() {
-                if json {
-                    println!("[]");
-                } else {
-                    println!("No results for \"{}\"", query);
-                    println!("Try a broader query or run {} to learn more.", "'noggin learn'".cyan());
-                }
-                return Ok(());
-            }
+        /// Show what would change without saving
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
 
-            if json {
-                println!("{}", serde_json::to_string_pretty(&results)?);
-                return Ok(());
-            }
+#[derive(Subcommand)]
+enum ConflictsAction {
+    /// Replay the vote behind a conflict: each model's value and weight,
+    /// and why the outcome won
+    #[command(after_help = "\
+Examples:
+  noggin conflicts explain use-pooling.how
+  noggin conflicts explain use-pooling.how --weight gemini=1.5   Replay with another weight")]
+    Explain {
+        /// Conflict ID, or its path under .noggin/
+        id: String,
 
-            println!("{} results for \"{}\"\n", results.len(), query);
+        /// Replay with this weight for a model (repeatable)
+        #[arg(long = "weight", value_name = "MODEL=WEIGHT")]
+        weights: Vec<String>,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+}
 
-            let mut current_category = String::new();
-            for result in &results {
-                if result.category != current_category {
-                    current_category = result.category.clone();
-                    println!("{}", current_category.to_uppercase().bold());
+#[derive(Subcommand)]
+enum HookAction {
+    /// Install the prepare-commit-msg hook into .git/hooks
+    Install {
+        /// Replace an existing hook not installed by noggin
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Append Noggin-Knowledge trailers for ARFs linked to staged files
+    /// (invoked by git)
+    PrepareCommitMsg {
+        /// Commit message file
+        file: PathBuf,
+
+        /// Message source (message, template, merge, squash, commit)
+        source: Option<String>,
+
+        /// Commit SHA (when amending)
+        sha: Option<String>,
+    },
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let cli = Cli::parse();
+
+    match cli.command {
+        Commands::Init { guide, no_guide } => init_command(InitOptions { guide, no_guide }),
+        Commands::Doctor { json } => doctor_command(DoctorOptions { json }),
+        Commands::Learn {
+            verify,
+            full,
+            dry_run,
+            max_cost,
+            max_time,
+            max_commits,
+            notes,
+            force_adopt,
+            refs,
+            all_branches,
+            paths,
+            range,
+            by_release,
+            authors,
+            since_date,
+            until_date,
+            lite,
+            events,
+            remote,
+            depth,
+            output,
+        } => {
+            let opts = LearnOptions {
+                full,
+                verify,
+                dry_run,
+                max_cost,
+                max_time,
+                max_commits,
+                notes,
+                force_adopt,
+                refs,
+                all_branches,
+                paths,
+                range,
+                authors,
+                since_date,
+                until_date,
+                lite,
+                events,
+                by_release,
+                ..Default::default()
+            };
+            match remote {
+                Some(url) => {
+                    let remote = RemoteOptions {
+                        url,
+                        depth,
+                        output,
+                        cache_dir: None,
+                    };
+                    learn_remote_command(remote, opts).await
                 }
-                println!("  {} {}", result.file_path.dimmed(), format!("[{}]", result.matched_fields.join(", ")).dimmed());
-                println!("  {}", result.what.cyan());
-                println!("  {}", result.why);
-                println!();
+                None => learn_command(opts).await,
             }
-
-            Ok(())
         }
+        Commands::Ci {
+            mode,
+            output_dir,
+            commit,
+            push,
+            token_env,
+            remote,
+            branch,
+            max_cost,
+            max_time,
+        } => {
+            ci_command(CiOptions {
+                mode,
+                output_dir,
+                commit,
+                push,
+                token_env,
+                remote,
+                branch,
+                max_cost,
+                max_time,
+            })
+            .await
+        }
+        Commands::Maintain {
+            max_commits,
+            stale_days,
+            max_refresh,
+            dry_run,
+            max_cost,
+            max_time,
+            json,
+        } => {
+            maintain_command(MaintainOptions {
+                max_commits,
+                stale_days,
+                max_refresh,
+                max_cost,
+                max_time,
+                json,
+                dry_run,
+            })
+            .await
+        }
+        Commands::Prune {
+            compact_commits,
+            dry_run,
+            json,
+        } => prune_command(PruneOptions {
+            compact_days: compact_commits,
+            dry_run,
+            json,
+        }),
+        Commands::Purge {
+            raw,
+            cache,
+            logs,
+            checkpoint,
+            expired,
+            dry_run,
+            json,
+        } => {
+            let targets = [
+                (raw, PurgeTarget::Raw),
+                (cache, PurgeTarget::Cache),
+                (logs, PurgeTarget::Logs),
+                (checkpoint, PurgeTarget::Checkpoint),
+            ]
+            .into_iter()
+            .filter_map(|(selected, target)| selected.then_some(target))
+            .collect();
+            purge_command(PurgeOptions {
+                targets,
+                expired,
+                dry_run,
+                json,
+            })
+        }
+        Commands::Approve {
+            paths,
+            revoke,
+            dry_run,
+        } => approve_command(&paths, revoke, dry_run),
+        Commands::Describe { base, provider, output, no_llm } => {
+            describe_command(DescribeOptions {
+                base,
+                provider,
+                output,
+                no_llm,
+            })
+            .await
+        }
+        Commands::ReviewDiff {
+            range,
+            staged,
+            provider,
+            json,
+            strict,
+        } => {
+            review_command(ReviewOptions {
+                range,
+                staged,
+                provider,
+                json,
+                strict,
+            })
+            .await
+        }
+        Commands::Check { json } => check_command(json),
+        Commands::Export {
+            format,
+            output,
+            dry_run,
+        } => export_command(ExportOptions {
+            format,
+            output: output.unwrap_or_else(|| PathBuf::from(format.default_output())),
+            dry_run,
+        }),
+        Commands::Bundle { output, dry_run } => bundle_command(BundleOptions { output, dry_run }),
+        Commands::Graph { format, output } => graph_command(GraphOptions { format, output }),
+        Commands::Manifest { action } => match action {
+            ManifestAction::Export { format, output } => {
+                manifest_export_command(ManifestExportOptions { format, output })
+            }
+            ManifestAction::Import {
+                input,
+                format,
+                force,
+                dry_run,
+            } => manifest_import_command(ManifestImportOptions {
+                input,
+                format,
+                force,
+                dry_run,
+            }),
+        },
+        Commands::Schema { output } => schema_command(output.as_deref()),
+        Commands::Synthesize {
+            input,
+            dry_run,
+            json,
+        } => synthesize_command(SynthesizeOptions {
+            input,
+            dry_run,
+            json,
+        }),
+        Commands::Experiment {
+            config_a,
+            config_b,
+            max_files,
+            max_commits,
+            json,
+        } => {
+            experiment_command(ExperimentOptions {
+                config_a,
+                config_b,
+                max_files,
+                max_commits,
+                json,
+            })
+            .await
+        }
+        Commands::Consolidate {
+            category,
+            apply,
+            json,
+        } => consolidate_command(ConsolidateOptions {
+            category,
+            apply,
+            json,
+        }),
+        Commands::Conflicts { action } => match action {
+            ConflictsAction::Explain { id, weights, json } => {
+                conflicts_explain_command(ConflictsExplainOptions { id, weights, json })
+            }
+        },
+        Commands::Hook { action } => match action {
+            HookAction::Install { force } => hook_install_command(force),
+            HookAction::PrepareCommitMsg { file, source, .. } => {
+                prepare_commit_msg_command(&file, source.as_deref())
+            }
+        },
+        Commands::Ask {
+            query,
+            saved,
+            max_results,
+            category,
+            tags,
+            min_confidence,
+            since,
+            sort,
+            json,
+            output,
+            bundle,
+            batch,
+        } => ask_command(AskOptions {
+            query,
+            saved,
+            max_results,
+            category,
+            tags,
+            min_confidence,
+            since,
+            sort,
+            json,
+            output,
+            bundle,
+            batch,
+        }),
+        Commands::Grep {
+            expression,
+            limit,
+            files_only,
+            json,
+        } => grep_command(GrepOptions {
+            expression,
+            limit,
+            files_only,
+            json,
+        }),
         Commands::Serve => serve_command().await,
-        Commands::Status { verbose, json } => status_command(verbose, json),
-        Commands::GitWalk { since, limit, json } => {
+        Commands::Status {
+            verbose,
+            below,
+            json,
+        } => status_command(verbose, json, cli.utc, below),
+        Commands::Snapshot { name, tag, force } => {
+            snapshot_command(SnapshotOptions { name, tag, force })
+        }
+        Commands::Diff { snapshot, to, json } => diff_command(DiffOptions { snapshot, to, json }),
+        Commands::Translate {
+            provider,
+            dry_run,
+            json,
+        } => {
+            translate_command(TranslateOptions {
+                provider,
+                dry_run,
+                json,
+            })
+            .await
+        }
+        Commands::VerifyKnowledge {
+            sample,
+            dry_run,
+            json,
+        } => {
+            verify_knowledge_command(VerifyKnowledgeOptions {
+                sample,
+                dry_run,
+                json,
+            })
+            .await
+        }
+        Commands::Coverage { depth, json } => coverage_command(CoverageOptions { depth, json }),
+        Commands::Resolve {
+            id,
+            list,
+            pick,
+            json,
+        } => resolve_command(ResolveOptions {
+            id,
+            list,
+            pick,
+            json,
+        }),
+        Commands::Log {
+            limit,
+            verbose,
+            json,
+        } => log_command(LogOptions {
+            limit,
+            verbose,
+            json,
+            utc: cli.utc,
+        }),
+        Commands::Stats { json } => stats_command(json, cli.utc),
+        Commands::Completions { shell } => {
+            generate(shell, &mut Cli::command(), "noggin", &mut io::stdout());
+            Ok(())
+        }
+        Commands::Examples => {
+            examples_command();
+            Ok(())
+        }
+        Commands::GitWalk {
+            since,
+            limit,
+            refs,
+            paths,
+            authors,
+            since_date,
+            until_date,
+            json,
+        } => {
             let repo_path = env::current_dir()?;
             let options = WalkOptions {
                 since_commit: since,
                 limit,
+                pathspec: (!paths.is_empty()).then_some(paths),
+                refs,
+                authors,
+                since_date: since_date.as_deref().map(parse_since).transpose()?,
+                until_date: until_date.as_deref().map(parse_until).transpose()?,
                 ..Default::default()
             };
 
             let result = walk_commits(&repo_path, options)?;
 
             if json {
-                println!("{}", serde_json::to_string_pretty(&result.commits)?);
+                let commits: Vec<_> = result
+                    .commits
+                    .iter()
+                    .map(|commit| WalkedCommit {
+                        commit,
+                        date: unix_to_iso8601(commit.timestamp),
+                    })
+                    .collect();
+                println!("{}", serde_json::to_string_pretty(&commits)?);
             } else {
                 println!("Commits ({})", result.commits.len());
                 println!();
                 for commit in &result.commits {
                     println!("commit {}", commit.hash);
                     println!("Author: {}", commit.author);
-                    println!("Date:   {}", commit.timestamp);
+                    println!("Date:   {}", format_unix(commit.timestamp, cli.utc));
                     println!();
                     println!("    {}", commit.message_summary);
                     println!();
-                    println!(
-                        "    {} files changed, {} insertions(+), {} deletions(-)",
-                        commit.files_changed, commit.insertions, commit.deletions
-                    );
+                    if commit.shallow_boundary {
+                        println!("    (shallow boundary, diff stats unavailable)");
+                    } else {
+                        println!(
+                            "    {} files changed, {} insertions(+), {} deletions(-)",
+                            commit.files_changed, commit.insertions, commit.deletions
+                        );
+                    }
                     println!();
                 }
 
+                if result.shallow {
+                    println!("Shallow clone: history before the boundary commits is unavailable.");
+                }
+
                 if let Some(next_hash) = result.next_hash {
                     println!("More commits available. Resume with: --since {}", next_hash);
                 }
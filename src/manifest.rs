@@ -1,21 +1,104 @@
+use crate::git::fingerprint::RepositoryFingerprint;
+use crate::learn::stack::Stack;
 use anyhow::{Context, Result};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
-use std::collections::HashMap;
+use std::collections::{btree_map, BTreeMap, BTreeSet, HashMap, HashSet};
 use std::fs;
 use std::path::{Path, PathBuf};
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct Manifest {
+    /// Repository this knowledge base is bound to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub repository: Option<RepositoryFingerprint>,
+    /// Case policy `files` is keyed with (see [`path_key`])
+    #[serde(default, skip_serializing_if = "PathCase::is_sensitive")]
+    pub path_case: PathCase,
+    /// Keyed by [`path_key`]; each entry keeps the file's own spelling
     #[serde(default)]
-    pub files: HashMap<String, FileEntry>,
+    pub files: BTreeMap<String, FileEntry>,
     #[serde(default)]
-    pub commits: HashMap<String, CommitEntry>,
+    pub commits: BTreeMap<String, CommitEntry>,
+    /// Processed commits whose entries were compacted away by
+    /// `noggin prune --compact-commits`; only the SHA is kept, so learn
+    /// still skips them
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    pub compacted_commits: BTreeSet<String>,
     #[serde(default)]
-    pub patterns: HashMap<String, PatternEntry>,
+    pub patterns: BTreeMap<String, PatternEntry>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub synthesis: Option<SynthesisMetadata>,
+    /// Languages, frameworks, and build systems found by the last learn
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stack: Option<Stack>,
+    /// Git submodules found by the last learn, keyed by path
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub submodules: BTreeMap<String, SubmoduleEntry>,
+    /// Set while learn has only seen truncated history, so a run with the
+    /// full history knows it is backfilling older commits
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub truncated_history: Option<TruncatedHistory>,
+}
+
+/// Whether manifest keys distinguish paths that differ only in case
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PathCase {
+    /// `Src/Lib.rs` and `src/lib.rs` are different files, as on Linux
+    #[default]
+    Sensitive,
+    /// Paths differing only in case are the same file, as on macOS and
+    /// Windows
+    Insensitive,
+}
+
+impl PathCase {
+    pub fn is_sensitive(&self) -> bool {
+        *self == PathCase::Sensitive
+    }
+}
+
+/// Repo-relative, forward-slash form of `path`: backslashes become
+/// slashes, and `.` and empty segments are dropped
+pub fn normalize_path(path: &str) -> String {
+    path.split(['/', '\\'])
+        .filter(|segment| !segment.is_empty() && *segment != ".")
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Key `path` is tracked under in the manifest for the given case policy
+pub fn path_key(path: &str, case: PathCase) -> String {
+    let normalized = normalize_path(path);
+    match case {
+        PathCase::Sensitive => normalized,
+        PathCase::Insensitive => normalized.to_lowercase(),
+    }
+}
+
+/// A git submodule as of the last learn
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SubmoduleEntry {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    /// Commit checked out in the working tree, None if not initialized
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub commit: Option<String>,
+    /// Its files were scanned and are tracked in `files` under its path;
+    /// otherwise they were skipped
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub recursed: bool,
+}
+
+/// History cut off by a shallow clone or grafts, as last walked
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TruncatedHistory {
+    /// Commits whose parents were unavailable, left unprocessed
+    pub boundaries: BTreeSet<String>,
+    /// First walk that found history truncated
+    pub since: DateTime<Utc>,
 }
 
 /// Metadata about the last synthesis run
@@ -36,6 +119,13 @@ pub struct FileEntry {
     pub last_scanned: DateTime<Utc>,
     #[serde(default)]
     pub pattern_ids: Vec<String>,
+    /// Generated code: tracked for drift, never analyzed
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub generated: bool,
+    /// Hash last analyzed on each branch, so switching branches doesn't
+    /// look like a change to every file that differs between them
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub branches: BTreeMap<String, String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -44,6 +134,9 @@ pub struct CommitEntry {
     pub processed_at: DateTime<Utc>,
     pub category: CommitCategory,
     pub arf_path: String,
+    /// Branches the commit was processed on
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub branches: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -81,8 +174,12 @@ impl Manifest {
         let contents = fs::read_to_string(path)
             .with_context(|| format!("Failed to read manifest from {}", path.display()))?;
 
-        toml::from_str(&contents)
-            .with_context(|| format!("Failed to parse manifest from {}", path.display()))
+        let mut manifest: Self = toml::from_str(&contents)
+            .with_context(|| format!("Failed to parse manifest from {}", path.display()))?;
+        // Manifests written before keys were normalized may hold
+        // backslashed or duplicate paths
+        manifest.normalize_keys();
+        Ok(manifest)
     }
 
     /// Save manifest to file atomically
@@ -107,20 +204,92 @@ impl Manifest {
         Ok(())
     }
 
-    /// Add or update a file entry
+    /// Key `path` is tracked under (see [`path_key`])
+    pub fn path_key(&self, path: &str) -> String {
+        path_key(path, self.path_case)
+    }
+
+    /// Add or update a file entry. Branch records are kept.
     pub fn add_or_update_file(&mut self, path: String, hash: String, pattern_ids: Vec<String>) {
+        let path = normalize_path(&path);
+        let key = self.path_key(&path);
+        let branches = self
+            .files
+            .remove(&key)
+            .map(|entry| entry.branches)
+            .unwrap_or_default();
         let entry = FileEntry {
-            path: path.clone(),
+            path,
             hash,
             last_scanned: Utc::now(),
             pattern_ids,
+            generated: false,
+            branches,
         };
-        self.files.insert(path, entry);
+        self.files.insert(key, entry);
+    }
+
+    /// Point a file back at content already analyzed, as after switching
+    /// to a branch where it has that hash. Pattern links are kept.
+    pub fn restore_file_hash(&mut self, path: &str, hash: &str) {
+        if let Some(entry) = self.get_file_mut(path) {
+            if entry.hash != hash {
+                entry.hash = hash.to_string();
+                entry.last_scanned = Utc::now();
+            }
+        }
+    }
+
+    /// Record that `path` has its current hash on `branch`
+    pub fn mark_file_on_branch(&mut self, path: &str, branch: &str) {
+        if let Some(entry) = self.get_file_mut(path) {
+            entry.branches.insert(branch.to_string(), entry.hash.clone());
+        }
+    }
+
+    /// Forget `path` on `branch`, where it no longer exists
+    pub fn unmark_file_on_branch(&mut self, path: &str, branch: &str) {
+        if let Some(entry) = self.get_file_mut(path) {
+            entry.branches.remove(branch);
+        }
+    }
+
+    /// Branch on which `path` was analyzed with content `hash`, if any
+    pub fn analyzed_on_branch(&self, path: &str, hash: &str) -> Option<&str> {
+        self.get_file(path).and_then(|entry| {
+            entry
+                .branches
+                .iter()
+                .find(|(_, branch_hash)| *branch_hash == hash)
+                .map(|(branch, _)| branch.as_str())
+        })
+    }
+
+    /// Add or update a generated file, which no pattern can draw on
+    pub fn add_or_update_generated_file(&mut self, path: String, hash: String) {
+        self.add_or_update_file(path.clone(), hash, vec![]);
+        if let Some(entry) = self.get_file_mut(&path) {
+            entry.generated = true;
+        }
+    }
+
+    /// Entry tracking `path`, however it is spelled
+    pub fn get_file(&self, path: &str) -> Option<&FileEntry> {
+        self.files.get(&self.path_key(path))
+    }
+
+    pub fn get_file_mut(&mut self, path: &str) -> Option<&mut FileEntry> {
+        let key = self.path_key(path);
+        self.files.get_mut(&key)
+    }
+
+    pub fn contains_file(&self, path: &str) -> bool {
+        self.get_file(path).is_some()
     }
 
     /// Get file hash if tracked
     pub fn get_file_hash(&self, path: &str) -> Option<&str> {
-        self.files.get(path).map(|entry| entry.hash.as_str())
+        self.get_file(path).map(|entry| entry.hash.as_str())
     }
 
     /// Check if file has changed compared to tracked hash
@@ -131,20 +300,147 @@ impl Manifest {
         }
     }
 
-    /// Add a processed commit
+    /// Switch to a case policy, re-keying files and merging entries that
+    /// now collide. Returns the number of entries merged away.
+    pub fn set_path_case(&mut self, case: PathCase) -> usize {
+        self.path_case = case;
+        self.normalize_keys()
+    }
+
+    /// Re-key every file under its normalized path and merge duplicates:
+    /// the most recently scanned entry wins, keeping the pattern links of
+    /// both. Contributing files of patterns are normalized and deduped the
+    /// same way. Returns the number of file entries merged away.
+    pub fn normalize_keys(&mut self) -> usize {
+        let case = self.path_case;
+        let mut merged = 0;
+        let mut files: BTreeMap<String, FileEntry> = BTreeMap::new();
+        for (key, mut entry) in std::mem::take(&mut self.files) {
+            entry.path = normalize_path(if entry.path.is_empty() { &key } else { &entry.path });
+            match files.entry(path_key(&entry.path, case)) {
+                btree_map::Entry::Vacant(slot) => {
+                    slot.insert(entry);
+                }
+                btree_map::Entry::Occupied(mut slot) => {
+                    merged += 1;
+                    let existing = slot.get_mut();
+                    if entry.last_scanned > existing.last_scanned {
+                        std::mem::swap(existing, &mut entry);
+                    }
+                    for id in entry.pattern_ids {
+                        if !existing.pattern_ids.contains(&id) {
+                            existing.pattern_ids.push(id);
+                        }
+                    }
+                    for (branch, hash) in entry.branches {
+                        existing.branches.entry(branch).or_insert(hash);
+                    }
+                }
+            }
+        }
+        self.files = files;
+
+        for pattern in self.patterns.values_mut() {
+            let mut seen = HashSet::new();
+            pattern.contributing_files = std::mem::take(&mut pattern.contributing_files)
+                .into_iter()
+                .map(|file| normalize_path(&file))
+                .filter(|file| seen.insert(path_key(file, case)))
+                .collect();
+        }
+
+        merged
+    }
+
+    /// Add a processed commit. Branch records are kept.
     pub fn add_commit(&mut self, sha: String, category: CommitCategory, arf_path: String) {
+        let branches = self
+            .commits
+            .remove(&sha)
+            .map(|entry| entry.branches)
+            .unwrap_or_default();
+        self.compacted_commits.remove(&sha);
         let entry = CommitEntry {
             sha: sha.clone(),
             processed_at: Utc::now(),
             category,
             arf_path,
+            branches,
         };
         self.commits.insert(sha, entry);
     }
 
+    /// Point commits derived from the ARF at `from` to the one at `to`
+    /// instead, e.g. after the first was merged into the second. Returns
+    /// how many were moved.
+    pub fn repoint_arf(&mut self, from: &str, to: &str) -> usize {
+        let mut moved = 0;
+        for entry in self.commits.values_mut().filter(|e| e.arf_path == from) {
+            entry.arf_path = to.to_string();
+            moved += 1;
+        }
+        moved
+    }
+
+    /// Record that a processed commit is on `branch`
+    pub fn mark_commit_on_branch(&mut self, sha: &str, branch: &str) {
+        if let Some(entry) = self.commits.get_mut(sha) {
+            if !entry.branches.iter().any(|b| b == branch) {
+                entry.branches.push(branch.to_string());
+                entry.branches.sort();
+            }
+        }
+    }
+
+    /// Forget the branches `exists` rejects on every file and commit entry,
+    /// returning their names, sorted
+    pub fn prune_branches(&mut self, exists: impl Fn(&str) -> bool) -> Vec<String> {
+        let mut gone = BTreeSet::new();
+        for entry in self.files.values_mut() {
+            entry.branches.retain(|branch, _| {
+                if exists(branch) {
+                    return true;
+                }
+                gone.insert(branch.clone());
+                false
+            });
+        }
+        for entry in self.commits.values_mut() {
+            entry.branches.retain(|branch| {
+                if exists(branch) {
+                    return true;
+                }
+                gone.insert(branch.clone());
+                false
+            });
+        }
+        gone.into_iter().collect()
+    }
+
     /// Check if commit has been processed
     pub fn is_commit_processed(&self, sha: &str) -> bool {
-        self.commits.contains_key(sha)
+        self.commits.contains_key(sha) || self.compacted_commits.contains(sha)
+    }
+
+    /// Commits processed, counting those compacted to bare SHAs
+    pub fn processed_commits(&self) -> usize {
+        self.commits.len() + self.compacted_commits.len()
+    }
+
+    /// Reduce commit entries processed before `cutoff` to bare SHAs,
+    /// returning how many were compacted
+    pub fn compact_commits(&mut self, cutoff: DateTime<Utc>) -> usize {
+        let before = self.commits.len();
+        let compacted = &mut self.compacted_commits;
+        self.commits.retain(|sha, entry| {
+            if entry.processed_at < cutoff {
+                compacted.insert(sha.clone());
+                false
+            } else {
+                true
+            }
+        });
+        before - self.commits.len()
     }
 
     /// Get all commits processed after the given SHA (chronologically)
@@ -166,30 +462,59 @@ impl Manifest {
 
     /// Remove a file entry from the manifest
     pub fn remove_file(&mut self, path: &str) {
-        self.files.remove(path);
+        let key = self.path_key(path);
+        self.files.remove(&key);
+    }
+
+    /// Move the entry for `from` to `to`, keeping its hash, branches, and
+    /// pattern links, and point patterns that cite `from` at `to`.
+    /// Does nothing if `from` isn't tracked.
+    pub fn rename_file(&mut self, from: &str, to: &str) {
+        let Some(mut entry) = self.files.remove(&self.path_key(from)) else {
+            return;
+        };
+        entry.path = normalize_path(to);
+        self.files.insert(self.path_key(to), entry);
+
+        let case = self.path_case;
+        let (from_key, to_key) = (path_key(from, case), path_key(to, case));
+        for pattern in self.patterns.values_mut() {
+            if !pattern.contributing_files.iter().any(|f| path_key(f, case) == from_key) {
+                continue;
+            }
+            pattern
+                .contributing_files
+                .retain(|f| path_key(f, case) != from_key && path_key(f, case) != to_key);
+            pattern.contributing_files.push(normalize_path(to));
+        }
     }
 
     /// Link a pattern to a contributing file
     pub fn link_pattern_to_file(&mut self, pattern_id: &str, file_path: &str) {
         // Add pattern_id to file's pattern list
-        if let Some(file_entry) = self.files.get_mut(file_path) {
+        if let Some(file_entry) = self.get_file_mut(file_path) {
             if !file_entry.pattern_ids.contains(&pattern_id.to_string()) {
                 file_entry.pattern_ids.push(pattern_id.to_string());
             }
         }
 
         // Add file to pattern's contributing_files list
+        let case = self.path_case;
+        let key = path_key(file_path, case);
         if let Some(pattern_entry) = self.patterns.get_mut(pattern_id) {
-            if !pattern_entry.contributing_files.contains(&file_path.to_string()) {
-                pattern_entry.contributing_files.push(file_path.to_string());
+            if !pattern_entry
+                .contributing_files
+                .iter()
+                .any(|file| path_key(file, case) == key)
+            {
+                pattern_entry.contributing_files.push(normalize_path(file_path));
             }
         }
     }
 
     /// Get all patterns associated with a file
     pub fn get_patterns_for_file(&self, path: &str) -> Vec<String> {
-        self.files
-            .get(path)
+        self.get_file(path)
             .map(|entry| entry.pattern_ids.clone())
             .unwrap_or_default()
     }
@@ -206,12 +531,58 @@ impl Manifest {
         let entry = PatternEntry {
             id: id.clone(),
             name,
-            contributing_files,
+            contributing_files: contributing_files
+                .iter()
+                .map(|file| normalize_path(file))
+                .collect(),
             last_updated: Utc::now(),
         };
         self.patterns.insert(id, entry);
     }
 
+    /// Pattern IDs last updated before `cutoff`, oldest first
+    pub fn stale_patterns(&self, cutoff: DateTime<Utc>) -> Vec<String> {
+        let mut stale: Vec<&PatternEntry> = self
+            .patterns
+            .values()
+            .filter(|entry| entry.last_updated < cutoff)
+            .collect();
+
+        stale.sort_by(|a, b| a.last_updated.cmp(&b.last_updated).then(a.id.cmp(&b.id)));
+        stale.into_iter().map(|entry| entry.id.clone()).collect()
+    }
+
+    /// Rebuild the file -> pattern links from each pattern's contributing
+    /// files, dropping links to patterns that no longer exist.
+    /// Returns the number of file entries that changed.
+    pub fn rebuild_pattern_index(&mut self) -> usize {
+        let mut expected: HashMap<String, Vec<String>> = HashMap::new();
+        for pattern in self.patterns.values() {
+            for file in &pattern.contributing_files {
+                expected
+                    .entry(path_key(file, self.path_case))
+                    .or_default()
+                    .push(pattern.id.clone());
+            }
+        }
+
+        let mut changed = 0;
+        for (key, entry) in self.files.iter_mut() {
+            let mut ids = expected.remove(key).unwrap_or_default();
+            ids.sort();
+            ids.dedup();
+
+            let mut current = entry.pattern_ids.clone();
+            current.sort();
+            if current != ids {
+                entry.pattern_ids = ids;
+                changed += 1;
+            }
+        }
+
+        changed
+    }
+
     /// Get manifest statistics
     pub fn stats(&self) -> ManifestStats {
         let last_scan = self
@@ -222,7 +593,7 @@ impl Manifest {
 
         ManifestStats {
             files_scanned: self.files.len(),
-            commits_processed: self.commits.len(),
+            commits_processed: self.processed_commits(),
             patterns_extracted: self.patterns.len(),
             last_scan,
         }
@@ -233,19 +604,22 @@ impl Manifest {
 pub fn calculate_file_hash(path: &Path) -> Result<String> {
     let contents = fs::read(path)
         .with_context(|| format!("Failed to read file for hashing: {}", path.display()))?;
+    Ok(hash_contents(&contents))
+}
 
+/// SHA-256 hash of file contents, as recorded in the manifest
+pub fn hash_contents(contents: &[u8]) -> String {
     let mut hasher = Sha256::new();
-    hasher.update(&contents);
-    let result = hasher.finalize();
-
-    Ok(format!("{:x}", result))
+    hasher.update(contents);
+    format!("{:x}", hasher.finalize())
 }
 
 /// Detect files that have changed since last scan
 pub fn detect_file_changes(manifest: &Manifest, repo_path: &Path) -> Result<Vec<PathBuf>> {
     let mut changed_files = Vec::new();
 
-    for (path_str, entry) in &manifest.files {
+    for entry in manifest.files.values() {
+        let path_str = &entry.path;
         let full_path = repo_path.join(path_str);
 
         if !full_path.exists() {
@@ -335,6 +709,22 @@ mod tests {
         assert_eq!(deserialized.commits.len(), 1);
     }
 
+    #[test]
+    fn test_serialization_is_sorted() {
+        let mut manifest = Manifest::default();
+        for path in ["src/z.rs", "src/a.rs", "src/m.rs"] {
+            manifest.add_or_update_file(path.to_string(), "abc123".to_string(), vec![]);
+        }
+
+        let toml = toml::to_string_pretty(&manifest).unwrap();
+        let position = |needle: &str| toml.find(needle).unwrap();
+        assert!(position("src/a.rs") < position("src/m.rs"));
+        assert!(position("src/m.rs") < position("src/z.rs"));
+
+        let reloaded: Manifest = toml::from_str(&toml).unwrap();
+        assert_eq!(toml::to_string_pretty(&reloaded).unwrap(), toml);
+    }
+
     #[test]
     fn test_is_file_changed() {
         let mut manifest = Manifest::default();
@@ -349,6 +739,34 @@ mod tests {
         assert!(manifest.is_file_changed("nonexistent.rs", "abc123"));
     }
 
+    #[test]
+    fn test_branch_records() {
+        let mut manifest = Manifest::default();
+        manifest.add_or_update_file("src/a.rs".to_string(), "on-main".to_string(), vec![]);
+        manifest.mark_file_on_branch("src/a.rs", "main");
+        manifest.add_or_update_file("src/a.rs".to_string(), "on-feature".to_string(), vec![]);
+        manifest.mark_file_on_branch("src/a.rs", "feature");
+
+        assert_eq!(manifest.analyzed_on_branch("src/a.rs", "on-main"), Some("main"));
+        assert_eq!(manifest.analyzed_on_branch("src/a.rs", "on-feature"), Some("feature"));
+        assert_eq!(manifest.analyzed_on_branch("src/a.rs", "new"), None);
+
+        manifest.get_file_mut("src/a.rs").unwrap().pattern_ids = vec!["p".to_string()];
+        manifest.restore_file_hash("src/a.rs", "on-main");
+        let entry = manifest.get_file("src/a.rs").unwrap();
+        assert_eq!(entry.hash, "on-main");
+        assert_eq!(entry.pattern_ids, vec!["p"]);
+
+        manifest.unmark_file_on_branch("src/a.rs", "feature");
+        assert_eq!(manifest.analyzed_on_branch("src/a.rs", "on-feature"), None);
+
+        manifest.add_commit("abc".to_string(), CommitCategory::Bug, String::new());
+        manifest.mark_commit_on_branch("abc", "main");
+        manifest.mark_commit_on_branch("abc", "main");
+        manifest.add_commit("abc".to_string(), CommitCategory::Bug, String::new());
+        assert_eq!(manifest.commits["abc"].branches, vec!["main"]);
+    }
+
     #[test]
     fn test_commit_tracking() {
         let mut manifest = Manifest::default();
@@ -362,6 +780,25 @@ mod tests {
         assert!(!manifest.is_commit_processed("commit2"));
     }
 
+    #[test]
+    fn test_compact_commits() {
+        let mut manifest = Manifest::default();
+        manifest.add_commit("old".to_string(), CommitCategory::Bug, String::new());
+        manifest.add_commit("new".to_string(), CommitCategory::Bug, String::new());
+        manifest.commits.get_mut("old").unwrap().processed_at =
+            Utc::now() - chrono::Duration::days(90);
+
+        assert_eq!(manifest.compact_commits(Utc::now() - chrono::Duration::days(30)), 1);
+        assert!(!manifest.commits.contains_key("old"));
+        assert!(manifest.is_commit_processed("old"));
+        assert!(manifest.is_commit_processed("new"));
+        assert_eq!(manifest.stats().commits_processed, 2);
+
+        // Reprocessing restores the full entry
+        manifest.add_commit("old".to_string(), CommitCategory::Bug, String::new());
+        assert!(manifest.compacted_commits.is_empty());
+    }
+
     #[test]
     fn test_pattern_invalidation() {
         let mut manifest = Manifest::default();
@@ -405,6 +842,35 @@ mod tests {
         assert!(pattern.contributing_files.contains(&"src/main.rs".to_string()));
     }
 
+    #[test]
+    fn test_rename_file_migrates_pattern_links() {
+        let mut manifest = Manifest::default();
+        manifest.add_or_update_file("src/db.rs".to_string(), "abc123".to_string(), vec![]);
+        manifest.mark_file_on_branch("src/db.rs", "main");
+        manifest.add_or_update_pattern(
+            "pooling".to_string(),
+            "Pooling".to_string(),
+            vec!["src/db.rs".to_string(), "src/lib.rs".to_string()],
+        );
+        manifest.link_pattern_to_file("pooling", "src/db.rs");
+
+        manifest.rename_file("src/db.rs", "src/db/mod.rs");
+
+        assert!(!manifest.contains_file("src/db.rs"));
+        let entry = manifest.get_file("src/db/mod.rs").unwrap();
+        assert_eq!(entry.path, "src/db/mod.rs");
+        assert_eq!(entry.hash, "abc123");
+        assert_eq!(entry.branches["main"], "abc123");
+        assert_eq!(manifest.get_patterns_for_file("src/db/mod.rs"), vec!["pooling"]);
+        assert_eq!(
+            manifest.patterns["pooling"].contributing_files,
+            vec!["src/lib.rs", "src/db/mod.rs"]
+        );
+
+        manifest.rename_file("missing.rs", "other.rs");
+        assert!(!manifest.contains_file("other.rs"));
+    }
+
     #[test]
     fn test_manifest_stats() {
         let mut manifest = Manifest::default();
@@ -459,4 +925,115 @@ mod tests {
         assert_eq!(loaded.files.len(), 1);
         assert_eq!(loaded.get_file_hash("src/main.rs"), Some("abc123"));
     }
+
+    #[test]
+    fn test_stale_patterns_oldest_first() {
+        let mut manifest = Manifest::default();
+        manifest.add_or_update_pattern("recent".to_string(), "Recent".to_string(), vec![]);
+        manifest.add_or_update_pattern("old".to_string(), "Old".to_string(), vec![]);
+        manifest.add_or_update_pattern("older".to_string(), "Older".to_string(), vec![]);
+
+        let now = Utc::now();
+        manifest.patterns.get_mut("old").unwrap().last_updated = now - chrono::Duration::days(40);
+        manifest.patterns.get_mut("older").unwrap().last_updated = now - chrono::Duration::days(90);
+
+        let stale = manifest.stale_patterns(now - chrono::Duration::days(30));
+        assert_eq!(stale, vec!["older", "old"]);
+    }
+
+    #[test]
+    fn test_rebuild_pattern_index() {
+        let mut manifest = Manifest::default();
+        manifest.add_or_update_file("src/a.rs".to_string(), "h1".to_string(), vec![]);
+        manifest.add_or_update_file(
+            "src/b.rs".to_string(),
+            "h2".to_string(),
+            vec!["gone".to_string()],
+        );
+        manifest.add_or_update_pattern(
+            "errors".to_string(),
+            "Errors".to_string(),
+            vec!["src/a.rs".to_string()],
+        );
+
+        assert_eq!(manifest.rebuild_pattern_index(), 2);
+        assert_eq!(manifest.get_patterns_for_file("src/a.rs"), vec!["errors"]);
+        assert!(manifest.get_patterns_for_file("src/b.rs").is_empty());
+
+        // Already consistent
+        assert_eq!(manifest.rebuild_pattern_index(), 0);
+    }
+
+    #[test]
+    fn test_normalize_path() {
+        assert_eq!(normalize_path("src\\learn\\mod.rs"), "src/learn/mod.rs");
+        assert_eq!(normalize_path("./src//main.rs"), "src/main.rs");
+        assert_eq!(path_key("Src\\Main.rs", PathCase::Sensitive), "Src/Main.rs");
+        assert_eq!(path_key("Src\\Main.rs", PathCase::Insensitive), "src/main.rs");
+    }
+
+    #[test]
+    fn test_lookups_ignore_path_spelling() {
+        let mut manifest = Manifest::default();
+        manifest.add_or_update_file("src\\main.rs".to_string(), "abc".to_string(), vec![]);
+        assert_eq!(manifest.get_file_hash("./src/main.rs"), Some("abc"));
+        assert!(manifest.get_file_hash("SRC/main.rs").is_none());
+
+        manifest.set_path_case(PathCase::Insensitive);
+        assert_eq!(manifest.get_file_hash("SRC/Main.rs"), Some("abc"));
+        // The entry keeps the file's own spelling
+        assert_eq!(manifest.get_file("SRC/Main.rs").unwrap().path, "src/main.rs");
+
+        manifest.remove_file("Src\\Main.rs");
+        assert!(manifest.files.is_empty());
+    }
+
+    #[test]
+    fn test_load_dedupes_legacy_keys() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let manifest_path = temp_dir.path().join("manifest.toml");
+
+        let mut manifest = Manifest::default();
+        for (key, hash, pattern, days_ago) in [
+            ("src/Lib.rs", "old", "errors", 3),
+            ("src\\Lib.rs", "new", "logging", 1),
+            ("src/lib.rs", "other", "config", 2),
+        ] {
+            manifest.files.insert(
+                key.to_string(),
+                FileEntry {
+                    path: key.to_string(),
+                    hash: hash.to_string(),
+                    last_scanned: Utc::now() - chrono::Duration::days(days_ago),
+                    pattern_ids: vec![pattern.to_string()],
+                    generated: false,
+                    branches: BTreeMap::new(),
+                },
+            );
+        }
+        manifest.patterns.insert(
+            "errors".to_string(),
+            PatternEntry {
+                id: "errors".to_string(),
+                name: "Errors".to_string(),
+                contributing_files: vec!["src/Lib.rs".to_string(), "src\\Lib.rs".to_string()],
+                last_updated: Utc::now(),
+            },
+        );
+        manifest.save(&manifest_path).unwrap();
+
+        // Backslashed keys merge on load; the newest entry wins
+        let mut loaded = Manifest::load(&manifest_path).unwrap();
+        assert_eq!(loaded.files.len(), 2);
+        let entry = loaded.get_file("src/Lib.rs").unwrap();
+        assert_eq!(entry.hash, "new");
+        assert_eq!(entry.pattern_ids, vec!["logging", "errors"]);
+        assert_eq!(loaded.patterns["errors"].contributing_files, vec!["src/Lib.rs"]);
+
+        // Case-only duplicates merge once the policy is insensitive
+        assert_eq!(loaded.set_path_case(PathCase::Insensitive), 1);
+        assert_eq!(loaded.files.len(), 1);
+        assert_eq!(loaded.get_file_hash("src/lib.rs"), Some("new"));
+        assert_eq!(loaded.get_patterns_for_file("SRC/LIB.RS").len(), 3);
+    }
 }
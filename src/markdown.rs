@@ -0,0 +1,286 @@
+//! Rendering Markdown answers for the terminal.
+//!
+//! [`render_terminal`] turns the Markdown that `ask` produces into styled
+//! text: bold headings, emphasis, lists, quotes, and fenced code blocks
+//! highlighted by their language. Links and `.noggin/` paths are citations
+//! and are underlined so they stand out from the prose. Styling follows
+//! `colored`, so `NO_COLOR` gets plain text; [`render_with_color`] takes
+//! the choice explicitly.
+
+use colored::{ColoredString, Colorize};
+use pulldown_cmark::{CodeBlockKind, Event, HeadingLevel, Options, Parser, Tag, TagEnd};
+use std::ops::Deref;
+use std::sync::OnceLock;
+use syntect::easy::HighlightLines;
+use syntect::highlighting::{Theme, ThemeSet};
+use syntect::parsing::SyntaxSet;
+use syntect::util::{as_24_bit_terminal_escaped, LinesWithEndings};
+
+/// Indent for code blocks and nested list items
+const INDENT: &str = "  ";
+
+fn syntaxes() -> &'static SyntaxSet {
+    static SET: OnceLock<SyntaxSet> = OnceLock::new();
+    SET.get_or_init(SyntaxSet::load_defaults_newlines)
+}
+
+fn theme() -> &'static Theme {
+    static THEME: OnceLock<Theme> = OnceLock::new();
+    THEME.get_or_init(|| {
+        let mut themes = ThemeSet::load_defaults();
+        themes
+            .themes
+            .remove("base16-ocean.dark")
+            .unwrap_or_default()
+    })
+}
+
+fn should_colorize() -> bool {
+    colored::control::SHOULD_COLORIZE.should_colorize()
+}
+
+/// `styled` with its styling when `color` is on, plain otherwise
+fn paint(styled: ColoredString, color: bool) -> String {
+    if color {
+        styled.to_string()
+    } else {
+        styled.deref().to_string()
+    }
+}
+
+/// Inline styles in effect
+#[derive(Debug, Default, Clone, Copy)]
+struct Style {
+    strong: bool,
+    emphasis: bool,
+    heading: bool,
+    link: bool,
+}
+
+impl Style {
+    fn apply(self, text: &str, color: bool) -> String {
+        let mut styled = text.normal();
+        if self.strong || self.heading {
+            styled = styled.bold();
+        }
+        if self.emphasis {
+            styled = styled.italic();
+        }
+        if self.link {
+            styled = styled.underline();
+        }
+        paint(styled, color)
+    }
+}
+
+/// Render `markdown` as styled terminal text
+pub fn render_terminal(markdown: &str) -> String {
+    render_with_color(markdown, should_colorize())
+}
+
+/// Render `markdown` as terminal text, styled only when `color` is on
+pub fn render_with_color(markdown: &str, color: bool) -> String {
+    let mut out = String::new();
+    let mut style = Style::default();
+    // Ordered lists keep their next number; unordered ones have None
+    let mut lists: Vec<Option<u64>> = Vec::new();
+    let mut quote_depth = 0;
+    let mut code: Option<(String, String)> = None;
+    // URL and label of the link being rendered
+    let mut link: Option<(String, String)> = None;
+
+    let parser = Parser::new_ext(markdown, Options::ENABLE_STRIKETHROUGH);
+    for event in parser {
+        match event {
+            Event::Start(tag) => match tag {
+                Tag::Heading { level, .. } => {
+                    style.heading = true;
+                    if level > HeadingLevel::H1 {
+                        out.push_str(&paint("#".repeat(level as usize).dimmed(), color));
+                        out.push(' ');
+                    }
+                }
+                Tag::Paragraph => push_prefix(&mut out, quote_depth, color),
+                Tag::BlockQuote(_) => quote_depth += 1,
+                Tag::CodeBlock(kind) => {
+                    let lang = match kind {
+                        CodeBlockKind::Fenced(info) => {
+                            info.split_whitespace().next().unwrap_or("").to_string()
+                        }
+                        CodeBlockKind::Indented => String::new(),
+                    };
+                    code = Some((lang, String::new()));
+                }
+                Tag::List(start) => {
+                    if !lists.is_empty() && !out.ends_with('\n') {
+                        out.push('\n');
+                    }
+                    lists.push(start);
+                }
+                Tag::Item => {
+                    push_prefix(&mut out, quote_depth, color);
+                    out.push_str(&INDENT.repeat(lists.len().saturating_sub(1)));
+                    match lists.last_mut() {
+                        Some(Some(number)) => {
+                            out.push_str(&format!("{}. ", number));
+                            *number += 1;
+                        }
+                        _ => out.push_str("• "),
+                    }
+                }
+                Tag::Emphasis => style.emphasis = true,
+                Tag::Strong => style.strong = true,
+                Tag::Link { dest_url, .. } => {
+                    style.link = true;
+                    link = Some((dest_url.to_string(), String::new()));
+                }
+                _ => {}
+            },
+            Event::End(tag) => match tag {
+                TagEnd::Heading(level) => {
+                    style.heading = false;
+                    out.push('\n');
+                    if level == HeadingLevel::H1 {
+                        out.push('\n');
+                    }
+                }
+                TagEnd::Paragraph => {
+                    out.push('\n');
+                    if lists.is_empty() {
+                        out.push('\n');
+                    }
+                }
+                TagEnd::BlockQuote(_) => quote_depth -= 1,
+                TagEnd::CodeBlock => {
+                    if let Some((lang, body)) = code.take() {
+                        out.push_str(&highlight(&body, &lang, color));
+                        out.push('\n');
+                    }
+                }
+                TagEnd::List(_) => {
+                    lists.pop();
+                    if lists.is_empty() {
+                        out.push('\n');
+                    }
+                }
+                TagEnd::Item if !out.ends_with('\n') => out.push('\n'),
+                TagEnd::Emphasis => style.emphasis = false,
+                TagEnd::Strong => style.strong = false,
+                TagEnd::Link => {
+                    style.link = false;
+                    // Show where a labeled link goes, unless the label is the URL
+                    if let Some((url, label)) = link.take() {
+                        if label != url && !url.starts_with('#') {
+                            out.push_str(&paint(format!(" ({})", url).dimmed(), color));
+                        }
+                    }
+                }
+                _ => {}
+            },
+            Event::Text(text) => match &mut code {
+                Some((_, body)) => body.push_str(&text),
+                None => {
+                    if let Some((_, label)) = &mut link {
+                        label.push_str(&text);
+                    }
+                    out.push_str(&style.apply(&text, color));
+                }
+            },
+            Event::Code(text) => {
+                if is_citation(&text) {
+                    out.push_str(&paint(text.blue().underline(), color));
+                } else {
+                    out.push_str(&paint(text.cyan(), color));
+                }
+            }
+            Event::SoftBreak => out.push(' '),
+            Event::HardBreak => {
+                out.push('\n');
+                push_prefix(&mut out, quote_depth, color);
+            }
+            Event::Rule => out.push_str(&format!("{}\n\n", paint("─".repeat(40).dimmed(), color))),
+            _ => {}
+        }
+    }
+
+    while out.ends_with("\n\n") {
+        out.pop();
+    }
+    out
+}
+
+fn push_prefix(out: &mut String, quote_depth: usize, color: bool) {
+    for _ in 0..quote_depth {
+        out.push_str(&paint("│ ".dimmed(), color));
+    }
+}
+
+/// True for inline code naming a knowledge-base entry
+fn is_citation(text: &str) -> bool {
+    text.starts_with(".noggin/") && text.ends_with(".arf")
+}
+
+/// Indented code, highlighted for `lang` when colors are on and the
+/// language is known
+fn highlight(code: &str, lang: &str, color: bool) -> String {
+    let syntax = (!lang.is_empty() && color)
+        .then(|| syntaxes().find_syntax_by_token(lang))
+        .flatten();
+
+    let mut out = String::new();
+    let mut highlighter = syntax.map(|syntax| HighlightLines::new(syntax, theme()));
+    for line in LinesWithEndings::from(code) {
+        out.push_str(INDENT);
+        let styled = highlighter.as_mut().and_then(|h| {
+            h.highlight_line(line, syntaxes())
+                .ok()
+                .map(|ranges| as_24_bit_terminal_escaped(&ranges, false))
+        });
+        match styled {
+            Some(styled) => out.push_str(&format!("{}\x1b[0m", styled)),
+            None => out.push_str(&paint(line.dimmed(), color)),
+        }
+        if !line.ends_with('\n') {
+            out.push('\n');
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plain(markdown: &str) -> String {
+        render_with_color(markdown, false)
+    }
+
+    #[test]
+    fn test_render_terminal_structure() {
+        let rendered = plain(
+            "# Retries\n\n## Decisions\n\n**Why:** Flaky *network*.\n\n\
+             - one\n- two\n\n1. first\n2. second\n\n> quoted\n\n\
+             See [#12](https://github.com/o/r/issues/12) and `.noggin/bugs/x.arf`.\n\n\
+             <https://example.com>\n",
+        );
+
+        assert!(rendered.starts_with("Retries\n\n## Decisions\n"));
+        assert!(rendered.contains("Why: Flaky network.\n"));
+        assert!(rendered.contains("• one\n• two\n"));
+        assert!(rendered.contains("1. first\n2. second\n"));
+        assert!(rendered.contains("│ quoted"));
+        assert!(rendered.contains("#12 (https://github.com/o/r/issues/12)"));
+        assert!(rendered.contains(".noggin/bugs/x.arf."));
+        assert!(rendered.ends_with("\nhttps://example.com\n"));
+        assert!(!rendered.ends_with("\n\n"));
+    }
+
+    #[test]
+    fn test_code_blocks_are_indented() {
+        let rendered = plain("```rust\nfn main() {}\n```\n\n```\nplain\n```\n");
+        assert!(rendered.contains("  fn main() {}\n"));
+        assert!(rendered.contains("  plain\n"));
+        assert!(syntaxes().find_syntax_by_token("rust").is_some());
+        assert!(is_citation(".noggin/facts/glossary/shard.arf"));
+    }
+}
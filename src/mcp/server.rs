@@ -1,5 +1,7 @@
-use crate::arf::ArfFile;
-use crate::query::{QueryEngine, QueryOptions};
+use crate::config::StorageConfig;
+use crate::metrics::record_ask;
+use crate::query::{parse_since, BatchQuestion, QueryEngine, QueryOptions, SortBy};
+use crate::storage::{self, KnowledgeBackend};
 use rmcp::{
     ErrorData as McpError, ServerHandler,
     handler::server::{tool::ToolRouter, wrapper::Parameters},
@@ -9,11 +11,12 @@ use rmcp::{
 use schemars::JsonSchema;
 use serde::Deserialize;
 use std::path::PathBuf;
-use walkdir::WalkDir;
+use std::time::Instant;
 
 #[derive(Clone)]
 pub struct NogginServer {
     noggin_path: PathBuf,
+    storage: StorageConfig,
     tool_router: ToolRouter<Self>,
 }
 
@@ -25,6 +28,18 @@ pub struct QueryParams {
     pub category: Option<String>,
     /// Maximum number of results (default 10)
     pub max_results: Option<usize>,
+    /// Only entries carrying all of these tags
+    pub tags: Option<Vec<String>>,
+    /// Only entries with at least this confidence (0.0 to 1.0)
+    pub min_confidence: Option<f64>,
+    /// Only entries modified since this date (YYYY-MM-DD, RFC 3339, or e.g. "30d")
+    pub since: Option<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct BatchQueryParams {
+    /// Questions to answer, each with optional filters
+    pub questions: Vec<BatchQuestion>,
 }
 
 #[derive(Debug, Deserialize, JsonSchema)]
@@ -37,28 +52,49 @@ pub struct GetArfParams {
 
 #[tool_router]
 impl NogginServer {
-    pub fn new(noggin_path: PathBuf) -> Self {
+    pub fn new(noggin_path: PathBuf, storage: StorageConfig) -> Self {
         Self {
             noggin_path,
+            storage,
             tool_router: Self::tool_router(),
         }
     }
 
-    #[tool(description = "Search the noggin knowledge base for codebase knowledge matching a query. Returns ranked results from ARF files containing architectural decisions, code patterns, bug fixes, migrations, and facts.")]
+    fn backend(&self) -> Box<dyn KnowledgeBackend> {
+        storage::open(&self.noggin_path, &self.storage)
+    }
+
+    fn engine(&self) -> QueryEngine {
+        QueryEngine::with_backend(self.noggin_path.clone(), self.backend())
+    }
+
+    #[tool(description = "Search the noggin knowledge base for codebase knowledge matching a query. Returns ranked results from ARF files containing architectural decisions, code patterns, bug fixes, migrations, and facts. Optionally filter by category, tags, minimum confidence, and modification date.")]
     async fn query_knowledge(
         &self,
         params: Parameters<QueryParams>,
     ) -> Result<CallToolResult, McpError> {
         let params = params.0;
-        let engine = QueryEngine::new(self.noggin_path.clone());
+        let started = Instant::now();
+        let engine = self.engine();
+        let since = params
+            .since
+            .as_deref()
+            .map(parse_since)
+            .transpose()
+            .map_err(|e| McpError::invalid_params(e.to_string(), None))?;
         let opts = QueryOptions {
             max_results: params.max_results.unwrap_or(10),
             category: params.category,
+            tags: params.tags.unwrap_or_default(),
+            min_confidence: params.min_confidence,
+            since,
+            sort: SortBy::Relevance,
         };
 
         let results = engine
-            .search(&params.query, &opts)
+            .semantic_search(&params.query, &opts)
             .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+        record_ask(&self.noggin_path, started.elapsed());
 
         if results.is_empty() {
             return Ok(CallToolResult::success(vec![Content::text(format!(
@@ -70,34 +106,53 @@ impl NogginServer {
         let mut output = String::new();
         for result in &results {
             output.push_str(&format!(
-                "[{}] {}\n  What: {}\n  Why: {}\n  How: {}\n\n",
+                "[{}] {}\n  What: {}\n  Why: {}\n  How: {}\n",
                 result.category, result.file_path, result.what, result.why, result.how,
             ));
+            if !result.issues.is_empty() {
+                output.push_str(&format!("  Issues: {}\n", result.issues.join(", ")));
+            }
+            if !result.people.is_empty() {
+                output.push_str(&format!("  People: {}\n", result.people.join(", ")));
+            }
+            output.push('\n');
         }
 
         Ok(CallToolResult::success(vec![Content::text(output)]))
     }
 
+    #[tool(description = "Answer many questions against the noggin knowledge base in one call, e.g. everything needed for a task list. Each question may set its own category, max_results (default 10), tags, min_confidence, and since filters. Returns a JSON array with, per question, the ranked results; each result's file_path cites the ARF it came from.")]
+    async fn query_knowledge_batch(
+        &self,
+        params: Parameters<BatchQueryParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let started = Instant::now();
+        let engine = self.engine();
+        let answers = engine.answer_batch(&params.0.questions, &QueryOptions::default());
+        record_ask(&self.noggin_path, started.elapsed());
+
+        let output = serde_json::to_string_pretty(&answers)
+            .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+        Ok(CallToolResult::success(vec![Content::text(output)]))
+    }
+
     #[tool(description = "Read a specific ARF (Augmented Reasoning Format) file from the knowledge base. Provide the category (decisions, patterns, bugs, migrations, facts) and the file name (without .arf extension).")]
     async fn get_arf(
         &self,
         params: Parameters<GetArfParams>,
     ) -> Result<CallToolResult, McpError> {
         let params = params.0;
-        let path = self
-            .noggin_path
-            .join(&params.category)
-            .join(format!("{}.arf", params.name));
-
-        if !path.exists() {
-            return Ok(CallToolResult::error(vec![Content::text(format!(
-                "ARF file not found: {}/{}.arf",
-                params.category, params.name
-            ))]));
-        }
-
-        let arf = ArfFile::from_toml(&path)
-            .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+        let path = format!("{}/{}.arf", params.category, params.name);
+        let arf = match self.backend().read_arf(&path) {
+            Ok(Some(arf)) => arf,
+            Ok(None) => {
+                return Ok(CallToolResult::error(vec![Content::text(format!(
+                    "ARF file not found: {}",
+                    path
+                ))]));
+            }
+            Err(e) => return Err(McpError::internal_error(e.to_string(), None)),
+        };
 
         let mut output = format!(
             "What: {}\nWhy: {}\nHow: {}",
@@ -116,6 +171,12 @@ impl NogginServer {
                 arf.context.dependencies.join(", ")
             ));
         }
+        if !arf.context.provenance.models.is_empty() {
+            output.push_str(&format!(
+                "\nSynthesized from: {}",
+                arf.context.provenance.models.join(", ")
+            ));
+        }
 
         Ok(CallToolResult::success(vec![Content::text(output)]))
     }
@@ -123,34 +184,27 @@ impl NogginServer {
     #[tool(description = "List all categories in the noggin knowledge base with the number of ARF files in each. Categories include decisions, patterns, bugs, migrations, and facts.")]
     async fn list_categories(&self) -> Result<CallToolResult, McpError> {
         let categories = ["decisions", "patterns", "bugs", "migrations", "facts"];
+        let paths = self
+            .backend()
+            .list_arfs()
+            .map_err(|e| McpError::internal_error(e.to_string(), None))?;
         let mut output = String::new();
 
         for category in &categories {
-            let dir = self.noggin_path.join(category);
-            let count = if dir.exists() {
-                WalkDir::new(&dir)
-                    .into_iter()
-                    .filter_map(|e| e.ok())
-                    .filter(|e| e.path().extension().map(|ext| ext == "arf").unwrap_or(false))
-                    .count()
-            } else {
-                0
-            };
+            let count = paths
+                .iter()
+                .filter(|path| path.split('/').next() == Some(*category))
+                .count();
             output.push_str(&format!("{}: {} files\n", category, count));
         }
 
-        let other_count = WalkDir::new(&self.noggin_path)
-            .into_iter()
-            .filter_map(|e| e.ok())
-            .filter(|e| {
-                let path = e.path();
-                path.extension().map(|ext| ext == "arf").unwrap_or(false)
-                    && path
-                        .parent()
-                        .and_then(|p| p.file_name())
-                        .and_then(|n| n.to_str())
-                        .map(|n| !categories.contains(&n))
-                        .unwrap_or(false)
+        let other_count = paths
+            .iter()
+            .filter(|path| {
+                !path
+                    .split('/')
+                    .next()
+                    .is_some_and(|dir| categories.contains(&dir))
             })
             .count();
 
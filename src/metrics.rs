@@ -0,0 +1,233 @@
+//! Local usage counters in `.noggin/metrics.toml`.
+//!
+//! Nothing here leaves the machine. `ask`, the MCP query tool, and learn
+//! runs bump counters so `noggin stats` can show whether the knowledge
+//! base is actually being used. Recording is best-effort: a failure to
+//! update the file never fails the command that triggered it. Set
+//! `metrics.enabled = false` in config to stop recording.
+
+use crate::config::Config;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Datelike, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+use tracing::warn;
+
+const METRICS_FILE: &str = "metrics.toml";
+
+/// Contents of `.noggin/metrics.toml`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Metrics {
+    #[serde(default)]
+    pub asks: AskMetrics,
+    #[serde(default)]
+    pub learn: LearnMetrics,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AskMetrics {
+    pub total: u64,
+    /// Asks per ISO week, keyed like `2026-W07`
+    #[serde(default)]
+    pub by_week: BTreeMap<String, u64>,
+    /// Sum of answer latencies, for the average
+    #[serde(default)]
+    pub latency_ms_total: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct LearnMetrics {
+    pub runs: u64,
+    /// Files scanned across runs
+    #[serde(default)]
+    pub files_scanned: u64,
+    /// Scanned files whose manifest hash matched, so no prompt was needed
+    #[serde(default)]
+    pub files_unchanged: u64,
+    #[serde(default)]
+    pub provider_requests: u64,
+    #[serde(default)]
+    pub provider_tokens: u64,
+    /// Runs that synthesized at least one entry
+    #[serde(default)]
+    pub entry_runs: u64,
+    /// Entries those runs synthesized
+    #[serde(default)]
+    pub entries: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_at: Option<DateTime<Utc>>,
+}
+
+/// Runs with entries needed before their average means anything
+const MIN_ENTRY_RUNS: u64 = 3;
+
+/// One learn run's contribution to the counters
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LearnSample {
+    pub files_scanned: usize,
+    pub files_unchanged: usize,
+    pub provider_requests: u32,
+    pub provider_tokens: u64,
+    /// Entries synthesized, before writing
+    pub entries: usize,
+}
+
+impl Metrics {
+    /// Load `.noggin/metrics.toml`, or empty counters if it doesn't exist
+    pub fn load(noggin_path: &Path) -> Result<Self> {
+        let path = noggin_path.join(METRICS_FILE);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let contents = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read metrics from {}", path.display()))?;
+
+        toml::from_str(&contents)
+            .with_context(|| format!("Failed to parse metrics from {}", path.display()))
+    }
+
+    pub fn save(&self, noggin_path: &Path) -> Result<()> {
+        let path = noggin_path.join(METRICS_FILE);
+        let contents = toml::to_string_pretty(self).context("Failed to serialize metrics")?;
+        fs::write(&path, contents)
+            .with_context(|| format!("Failed to write metrics to {}", path.display()))
+    }
+
+    pub fn add_ask(&mut self, at: DateTime<Utc>, latency: Duration) {
+        self.asks.total += 1;
+        *self.asks.by_week.entry(iso_week(&at)).or_default() += 1;
+        self.asks.latency_ms_total += latency.as_millis() as u64;
+        self.asks.last_at = Some(at);
+    }
+
+    pub fn add_learn(&mut self, at: DateTime<Utc>, sample: &LearnSample) {
+        self.learn.runs += 1;
+        self.learn.files_scanned += sample.files_scanned as u64;
+        self.learn.files_unchanged += sample.files_unchanged as u64;
+        self.learn.provider_requests += u64::from(sample.provider_requests);
+        self.learn.provider_tokens += sample.provider_tokens;
+        if sample.entries > 0 {
+            self.learn.entry_runs += 1;
+            self.learn.entries += sample.entries as u64;
+        }
+        self.learn.last_at = Some(at);
+    }
+
+    /// Mean entries per run that produced any, once there are enough runs
+    pub fn average_entries(&self) -> Option<f64> {
+        (self.learn.entry_runs >= MIN_ENTRY_RUNS)
+            .then(|| self.learn.entries as f64 / self.learn.entry_runs as f64)
+    }
+
+    /// Mean answer latency, if anything has been asked
+    pub fn average_ask_latency(&self) -> Option<Duration> {
+        (self.asks.total > 0)
+            .then(|| Duration::from_millis(self.asks.latency_ms_total / self.asks.total))
+    }
+
+    /// Share of scanned files served from the manifest without a prompt
+    pub fn cache_hit_rate(&self) -> Option<f64> {
+        (self.learn.files_scanned > 0)
+            .then(|| self.learn.files_unchanged as f64 / self.learn.files_scanned as f64)
+    }
+}
+
+/// Count one `ask` (or MCP query) that took `latency`
+pub fn record_ask(noggin_path: &Path, latency: Duration) {
+    record(noggin_path, |metrics| metrics.add_ask(Utc::now(), latency));
+}
+
+/// Count one learn run
+pub fn record_learn(noggin_path: &Path, sample: &LearnSample) {
+    record(noggin_path, |metrics| metrics.add_learn(Utc::now(), sample));
+}
+
+fn record(noggin_path: &Path, update: impl FnOnce(&mut Metrics)) {
+    let enabled = Config::load(noggin_path)
+        .map(|config| config.metrics.enabled)
+        .unwrap_or(true);
+    if !enabled {
+        return;
+    }
+
+    let result = Metrics::load(noggin_path).and_then(|mut metrics| {
+        update(&mut metrics);
+        metrics.save(noggin_path)
+    });
+    if let Err(e) = result {
+        warn!("Failed to record usage metrics: {:#}", e);
+    }
+}
+
+/// ISO week label for `at`, e.g. `2026-W07`
+pub fn iso_week(at: &DateTime<Utc>) -> String {
+    let week = at.iso_week();
+    format!("{}-W{:02}", week.year(), week.week())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_iso_week() {
+        let at: DateTime<Utc> = "2027-01-01T12:00:00Z".parse().unwrap();
+        assert_eq!(iso_week(&at), "2026-W53");
+        let at: DateTime<Utc> = "2026-02-10T12:00:00Z".parse().unwrap();
+        assert_eq!(iso_week(&at), "2026-W07");
+    }
+
+    #[test]
+    fn test_record_round_trip() {
+        let temp_dir = TempDir::new().unwrap();
+        record_ask(temp_dir.path(), Duration::from_millis(30));
+        record_ask(temp_dir.path(), Duration::from_millis(10));
+        record_learn(
+            temp_dir.path(),
+            &LearnSample {
+                files_scanned: 10,
+                files_unchanged: 8,
+                provider_requests: 3,
+                provider_tokens: 1200,
+                entries: 4,
+            },
+        );
+
+        let metrics = Metrics::load(temp_dir.path()).unwrap();
+        assert_eq!(metrics.asks.total, 2);
+        assert_eq!(metrics.asks.by_week.values().sum::<u64>(), 2);
+        assert_eq!(metrics.average_ask_latency(), Some(Duration::from_millis(20)));
+        assert_eq!(metrics.learn.runs, 1);
+        assert_eq!(metrics.cache_hit_rate(), Some(0.8));
+        assert_eq!(metrics.learn.entries, 4);
+    }
+
+    #[test]
+    fn test_average_entries_needs_history() {
+        let mut metrics = Metrics::default();
+        let at = Utc::now();
+        for entries in [0, 4, 8] {
+            metrics.add_learn(at, &LearnSample { entries, ..Default::default() });
+        }
+        // Runs without entries don't count
+        assert_eq!(metrics.average_entries(), None);
+
+        metrics.add_learn(at, &LearnSample { entries: 12, ..Default::default() });
+        assert_eq!(metrics.average_entries(), Some(8.0));
+    }
+
+    #[test]
+    fn test_disabled_records_nothing() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(temp_dir.path().join("config.toml"), "[metrics]\nenabled = false\n").unwrap();
+        record_ask(temp_dir.path(), Duration::from_millis(5));
+        assert!(!temp_dir.path().join(METRICS_FILE).exists());
+    }
+}
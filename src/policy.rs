@@ -0,0 +1,100 @@
+//! The `never_send` policy: paths whose contents must never reach a
+//! provider.
+//!
+//! Unlike ignore rules, matching files are still scanned, hashed, and
+//! tracked in the manifest; only their contents are withheld. Every
+//! prompt builder that embeds file contents or patches takes a
+//! [`NeverSend`] and substitutes [`placeholder`] for matching files.
+//!
+//! Patterns are path globs (`**` spans directories, `*` stays within one
+//! segment). A pattern ending in `/` covers a directory at any depth, and
+//! one without a `/` matches the file name at any depth.
+
+use crate::config::PrivacyConfig;
+use crate::rules::glob_to_regex;
+use anyhow::Result;
+use regex::Regex;
+
+/// Compiled `privacy.never_send` patterns
+#[derive(Debug, Clone, Default)]
+pub struct NeverSend {
+    globs: Vec<Regex>,
+}
+
+impl NeverSend {
+    pub fn new(patterns: &[String]) -> Result<Self> {
+        let globs = patterns
+            .iter()
+            .map(|pattern| glob_to_regex(&expand(pattern.trim())))
+            .collect::<Result<_>>()?;
+        Ok(Self { globs })
+    }
+
+    pub fn from_config(config: &PrivacyConfig) -> Result<Self> {
+        Self::new(&config.never_send)
+    }
+
+    /// True if the contents of `path` (relative to the repo root) must be
+    /// withheld
+    pub fn matches(&self, path: &str) -> bool {
+        let path = path.trim_start_matches("./");
+        self.globs.iter().any(|glob| glob.is_match(path))
+    }
+}
+
+/// Text sent in place of a withheld file's contents
+pub fn placeholder(path: &str) -> String {
+    format!("(contents of {} withheld by the never_send policy)", path)
+}
+
+/// Rewrite a policy pattern as a plain path glob: a trailing `/` covers the
+/// directory, and a pattern without `/` matches the file name at any depth
+pub(crate) fn expand(pattern: &str) -> String {
+    let pattern = pattern.trim_start_matches("./").trim_start_matches('/');
+    if let Some(dir) = pattern.strip_suffix('/') {
+        if dir.contains('/') {
+            format!("{}/**", dir)
+        } else {
+            format!("**/{}/**", dir)
+        }
+    } else if pattern.contains('/') {
+        pattern.to_string()
+    } else {
+        format!("**/{}", pattern)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(patterns: &[&str]) -> NeverSend {
+        NeverSend::new(&patterns.iter().map(|p| p.to_string()).collect::<Vec<_>>()).unwrap()
+    }
+
+    #[test]
+    fn test_directory_patterns() {
+        let never_send = policy(&["secrets/", "compliance/pci/"]);
+        assert!(never_send.matches("secrets/prod.toml"));
+        assert!(never_send.matches("deploy/secrets/prod.toml"));
+        assert!(never_send.matches("compliance/pci/cardholder.rs"));
+        assert!(!never_send.matches("src/compliance/pci/cardholder.rs"));
+        assert!(!never_send.matches("src/secrets.rs"));
+    }
+
+    #[test]
+    fn test_file_name_and_path_patterns() {
+        let never_send = policy(&["*.pem", ".env", "config/prod.toml"]);
+        assert!(never_send.matches("server.pem"));
+        assert!(never_send.matches("certs/ca/server.pem"));
+        assert!(never_send.matches("./.env"));
+        assert!(never_send.matches("config/prod.toml"));
+        assert!(!never_send.matches("app/config/prod.toml"));
+        assert!(!never_send.matches("src/main.rs"));
+    }
+
+    #[test]
+    fn test_empty_policy_matches_nothing() {
+        assert!(!NeverSend::default().matches("secrets/key.pem"));
+    }
+}
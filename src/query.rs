@@ -2,14 +2,33 @@
 //!
 //! Searches `.noggin/` directory for ARF files matching a query string,
 //! ranks results by match location and category, and returns structured
-//! results with context.
+//! results with context. [`QueryEngine::semantic_search`] ranks by
+//! embedding similarity from the [`crate::index`] instead, so questions
+//! find entries that share meaning rather than an exact substring.
 
-use crate::arf::ArfFile;
+use crate::arf::{arf_category, ArfFile};
+use crate::git::blame::{parse_authors, AUTHORS_KEY};
+use crate::learn::glossary::{definitional_term, is_glossary, term_of};
+use crate::index::embed::HashingEmbedder;
+use crate::index::SemanticIndex;
+use crate::storage::{FilesystemBackend, KnowledgeBackend};
 use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, NaiveDate, NaiveTime, Utc};
 use regex::RegexBuilder;
-use serde::Serialize;
-use std::path::PathBuf;
-use walkdir::WalkDir;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+/// Least embedding similarity for a result with no literal match
+const MIN_SIMILARITY: f32 = 0.2;
+
+/// Bonus for glossary entries when the query asks for a definition
+const GLOSSARY_WEIGHT: f64 = 15.0;
+
+/// Bonus for the glossary entry defining the very term asked about
+const GLOSSARY_TERM_WEIGHT: f64 = 60.0;
 
 /// Options controlling query behavior
 #[derive(Debug, Clone)]
@@ -18,6 +37,24 @@ pub struct QueryOptions {
     pub max_results: usize,
     /// Filter to a specific category (decisions, patterns, bugs, migrations, facts)
     pub category: Option<String>,
+    /// Only ARFs carrying all of these tags (case-insensitive)
+    pub tags: Vec<String>,
+    /// Only ARFs with at least this confidence; ARFs without one are excluded
+    pub min_confidence: Option<f64>,
+    /// Only ARFs updated (or, never updated, created) at or after this time
+    pub since: Option<DateTime<Utc>>,
+    /// Order of the results
+    pub sort: SortBy,
+}
+
+/// How query results are ordered
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum SortBy {
+    /// Best match first
+    #[default]
+    Relevance,
+    /// Most confident first (entries without a score last), ties by relevance
+    Confidence,
 }
 
 impl Default for QueryOptions {
@@ -25,6 +62,10 @@ impl Default for QueryOptions {
         Self {
             max_results: 10,
             category: None,
+            tags: Vec::new(),
+            min_confidence: None,
+            since: None,
+            sort: SortBy::Relevance,
         }
     }
 }
@@ -40,20 +81,88 @@ pub struct QueryResult {
     pub what: String,
     pub why: String,
     pub how: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub confidence: Option<f64>,
+    /// Linked issues and pull requests
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub issues: Vec<String>,
+    /// Authors most associated with the ARF: those of the commits it
+    /// cites, then those owning most lines of its files
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub people: Vec<String>,
     /// Which field(s) matched the query
     pub matched_fields: Vec<String>,
     /// Relevance score (higher is better)
     pub score: f64,
 }
 
+/// One question of a batch (see [`QueryEngine::answer_batch`]). Filters
+/// left out fall back to the batch's defaults; tags add to them.
+#[derive(Debug, Clone, Default, Deserialize, JsonSchema)]
+pub struct BatchQuestion {
+    /// Question or search terms
+    pub question: String,
+    /// Filter by category (decisions, patterns, bugs, migrations, facts)
+    pub category: Option<String>,
+    /// Maximum number of results
+    pub max_results: Option<usize>,
+    /// Only entries carrying all of these tags
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Only entries with at least this confidence (0.0 to 1.0)
+    pub min_confidence: Option<f64>,
+    /// Only entries updated since this date (YYYY-MM-DD, RFC 3339, or e.g. "30d")
+    pub since: Option<String>,
+}
+
+/// The answer to one [`BatchQuestion`]
+#[derive(Debug, Clone, Serialize)]
+pub struct BatchAnswer {
+    pub question: String,
+    /// Matching ARFs, best first; each `file_path` is a citation
+    pub results: Vec<QueryResult>,
+    /// Why the question couldn't be answered, e.g. an invalid `since`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
 /// Query engine that searches ARF files in .noggin/
 pub struct QueryEngine {
     noggin_path: PathBuf,
+    /// Where the ARFs are read from; the search index stays in
+    /// `noggin_path`
+    backend: Box<dyn KnowledgeBackend>,
+    /// Semantic index, refreshed on first use and shared by every search
+    /// this engine runs
+    index: OnceLock<SemanticIndex>,
 }
 
 impl QueryEngine {
     pub fn new(noggin_path: PathBuf) -> Self {
-        Self { noggin_path }
+        let backend = Box::new(FilesystemBackend::new(&noggin_path));
+        Self::with_backend(noggin_path, backend)
+    }
+
+    /// An engine reading ARFs from `backend`
+    pub fn with_backend(noggin_path: PathBuf, backend: Box<dyn KnowledgeBackend>) -> Self {
+        Self {
+            noggin_path,
+            backend,
+            index: OnceLock::new(),
+        }
+    }
+
+    /// The semantic index, refreshing `.noggin/index/` the first time
+    fn index(&self) -> Result<&SemanticIndex> {
+        if let Some(index) = self.index.get() {
+            return Ok(index);
+        }
+        let embedder = HashingEmbedder::new();
+        let (index, _) = SemanticIndex::refresh(&self.noggin_path, self.backend.as_ref(), &embedder)
+            .context("Failed to refresh search index")?;
+        Ok(self.index.get_or_init(|| index))
     }
 
     /// Search ARF files for the given query string.
@@ -69,86 +178,372 @@ impl QueryEngine {
 
         let mut results = Vec::new();
 
-        for entry in WalkDir::new(&self.noggin_path)
-            .into_iter()
-            .filter_map(|e| e.ok())
-        {
-            let path = entry.path();
+        for rel_path in self.backend.list_arfs()? {
+            let Some((category, arf)) = load_filtered(self.backend.as_ref(), &rel_path, opts) else {
+                continue;
+            };
 
-            // Only search .arf files
-            if path.extension().map(|e| e != "arf").unwrap_or(true) {
+            // Check which fields match
+            let (matched_fields, mut score) = literal_matches(&pattern, &arf);
+
+            if matched_fields.is_empty() {
                 continue;
             }
 
-            // Extract category from directory name
-            let category = path
-                .parent()
-                .and_then(|p| p.file_name())
-                .and_then(|n| n.to_str())
-                .unwrap_or("unknown")
-                .to_string();
-
-            // Apply category filter
-            if let Some(ref filter) = opts.category {
-                if &category != filter {
+            // Category weight bonus
+            score += category_weight(&category);
+
+            results.push(QueryResult::new(rel_path, category, arf, matched_fields, score));
+        }
+
+        rank(&mut results, opts);
+        Ok(results)
+    }
+
+    /// Search by meaning using the semantic index.
+    ///
+    /// Refreshes `.noggin/index/` first (once per engine), then scores
+    /// each ARF by embedding similarity to `query` (scaled to 0-100) plus
+    /// the literal match and category weights `search` uses. An ARF qualifies when it is similar
+    /// enough or contains the query literally; semantic-only results list
+    /// `semantic` as their matched field. For a definitional question
+    /// ("what is a manifest?") glossary entries rank first, above all the
+    /// one defining that term, which qualifies as a `term` match.
+    pub fn semantic_search(&self, query: &str, opts: &QueryOptions) -> Result<Vec<QueryResult>> {
+        let embedder = HashingEmbedder::new();
+        let index = self.index()?;
+        let pattern = RegexBuilder::new(&regex::escape(query.trim()))
+            .case_insensitive(true)
+            .build()
+            .context("Failed to build search regex")?;
+
+        let defined = definitional_term(query);
+
+        let mut results = Vec::new();
+        for (rel_path, similarity) in index.search(query, &embedder) {
+            let Some((category, arf)) = load_filtered(self.backend.as_ref(), &rel_path, opts) else {
+                continue;
+            };
+
+            let (mut matched_fields, literal_score) = literal_matches(&pattern, &arf);
+            let glossary_score = defined.as_deref().map_or(0.0, |term| glossary_weight(&arf, term));
+            if glossary_score >= GLOSSARY_TERM_WEIGHT {
+                matched_fields.push("term".to_string());
+            }
+            if matched_fields.is_empty() {
+                if similarity < MIN_SIMILARITY {
                     continue;
                 }
+                matched_fields.push("semantic".to_string());
             }
 
-            // Parse ARF file
-            let arf = match ArfFile::from_toml(path) {
-                Ok(a) => a,
-                Err(_) => continue, // skip malformed files
-            };
+            let score = f64::from(similarity.max(0.0)) * 100.0
+                + literal_score
+                + category_weight(&category)
+                + glossary_score;
+            results.push(QueryResult::new(rel_path, category, arf, matched_fields, score));
+        }
 
-            // Check which fields match
-            let mut matched_fields = Vec::new();
-            let mut score = 0.0;
+        rank(&mut results, opts);
+        Ok(results)
+    }
 
-            if pattern.is_match(&arf.what) {
-                matched_fields.push("what".to_string());
-                score += 10.0;
-            }
-            if pattern.is_match(&arf.why) {
-                matched_fields.push("why".to_string());
-                score += 5.0;
+    /// Semantic search for several terms, merging the results.
+    ///
+    /// A file matching more than one term appears once, with the highest
+    /// score and the union of matched fields.
+    pub fn search_any(&self, terms: &[String], opts: &QueryOptions) -> Result<Vec<QueryResult>> {
+        let unbounded = QueryOptions {
+            max_results: usize::MAX,
+            ..opts.clone()
+        };
+
+        let mut merged: Vec<QueryResult> = Vec::new();
+        for term in terms {
+            for result in self.semantic_search(term, &unbounded)? {
+                match merged.iter_mut().find(|r| r.file_path == result.file_path) {
+                    Some(existing) => {
+                        existing.score = existing.score.max(result.score);
+                        for field in result.matched_fields {
+                            if !existing.matched_fields.contains(&field) {
+                                existing.matched_fields.push(field);
+                            }
+                        }
+                    }
+                    None => merged.push(result),
+                }
             }
-            if pattern.is_match(&arf.how) {
-                matched_fields.push("how".to_string());
-                score += 3.0;
+        }
+
+        rank(&mut merged, opts);
+        Ok(merged)
+    }
+
+    /// Answer each of `questions` in order, sharing one index refresh.
+    /// `defaults` supplies the filters a question leaves out. A question
+    /// that fails gets an answer carrying the error, so the rest are
+    /// still answered.
+    pub fn answer_batch(&self, questions: &[BatchQuestion], defaults: &QueryOptions) -> Vec<BatchAnswer> {
+        questions
+            .iter()
+            .map(|question| {
+                let results = question
+                    .options(defaults)
+                    .and_then(|opts| self.semantic_search(&question.question, &opts));
+                let (results, error) = match results {
+                    Ok(results) => (results, None),
+                    Err(e) => (Vec::new(), Some(format!("{:#}", e))),
+                };
+                BatchAnswer {
+                    question: question.question.clone(),
+                    results,
+                    error,
+                }
+            })
+            .collect()
+    }
+}
+
+impl BatchQuestion {
+    /// `defaults` with this question's filters applied
+    pub fn options(&self, defaults: &QueryOptions) -> Result<QueryOptions> {
+        let mut opts = defaults.clone();
+        if let Some(max_results) = self.max_results {
+            opts.max_results = max_results;
+        }
+        if self.category.is_some() {
+            opts.category = self.category.clone();
+        }
+        for tag in &self.tags {
+            if !opts.tags.contains(tag) {
+                opts.tags.push(tag.clone());
             }
+        }
+        if self.min_confidence.is_some() {
+            opts.min_confidence = self.min_confidence;
+        }
+        if let Some(since) = &self.since {
+            opts.since = Some(parse_since(since)?);
+        }
+        Ok(opts)
+    }
+}
 
-            if matched_fields.is_empty() {
-                continue;
+impl QueryResult {
+    pub(crate) fn new(file_path: String, category: String, arf: ArfFile, matched_fields: Vec<String>, score: f64) -> Self {
+        let mut people = arf.context.people;
+        if let Some(authors) = arf.context.outcome.get(AUTHORS_KEY) {
+            for author in parse_authors(authors) {
+                if !people.contains(&author) {
+                    people.push(author);
+                }
             }
+        }
+        Self {
+            file_path,
+            category,
+            what: arf.what,
+            why: arf.why,
+            how: arf.how,
+            tags: arf.tags,
+            confidence: arf.confidence,
+            issues: arf.context.issues,
+            people,
+            matched_fields,
+            score,
+        }
+    }
+}
 
-            // Category weight bonus
-            score += category_weight(&category);
+/// Read the ARF at `path` from `backend` and apply the category, date, tag, and
+/// confidence filters, returning its category if it passes
+fn load_filtered(
+    backend: &dyn KnowledgeBackend,
+    path: &str,
+    opts: &QueryOptions,
+) -> Option<(String, ArfFile)> {
+    let category = arf_category(Path::new(path));
+
+    // Apply category filter
+    if opts.category.as_ref().is_some_and(|filter| &category != filter) {
+        return None;
+    }
 
-            let rel_path = path
-                .strip_prefix(&self.noggin_path)
-                .unwrap_or(path)
-                .display()
-                .to_string();
-
-            results.push(QueryResult {
-                file_path: rel_path,
-                category,
-                what: arf.what,
-                why: arf.why,
-                how: arf.how,
-                matched_fields,
-                score,
-            });
+    // Parse ARF file, skipping malformed ones
+    let arf = backend.read_arf(path).ok()??;
+
+    // Apply date filter; only entries from before timestamps were recorded
+    // fall back to the file's modification time, which checkouts reset
+    if let Some(since) = opts.since {
+        let changed = arf
+            .updated_at
+            .or(arf.created_at)
+            .or_else(|| backend.modified(path));
+        if changed.is_none_or(|m| m < since) {
+            return None;
         }
+    }
 
-        // Sort by score descending
-        results.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(std::cmp::Ordering::Equal));
+    // Apply tag and confidence filters
+    if !opts
+        .tags
+        .iter()
+        .all(|tag| arf.tags.iter().any(|t| t.eq_ignore_ascii_case(tag)))
+    {
+        return None;
+    }
+    if let Some(min) = opts.min_confidence {
+        if arf.confidence.is_none_or(|c| c < min) {
+            return None;
+        }
+    }
 
-        // Limit results
-        results.truncate(opts.max_results);
+    Some((category, arf))
+}
 
-        Ok(results)
+/// Order `results` as `opts.sort` asks and keep the first `max_results`
+fn rank(results: &mut Vec<QueryResult>, opts: &QueryOptions) {
+    let by_score = |a: &QueryResult, b: &QueryResult| {
+        b.score.partial_cmp(&a.score).unwrap_or(Ordering::Equal)
+    };
+    match opts.sort {
+        SortBy::Relevance => results.sort_by(by_score),
+        SortBy::Confidence => results.sort_by(|a, b| {
+            let confidence = |r: &QueryResult| r.confidence.unwrap_or(-1.0);
+            confidence(b)
+                .partial_cmp(&confidence(a))
+                .unwrap_or(Ordering::Equal)
+                .then_with(|| by_score(a, b))
+        }),
+    }
+    results.truncate(opts.max_results);
+}
+
+/// Fields of `arf` that `pattern` matches, and their combined weight
+fn literal_matches(pattern: &regex::Regex, arf: &ArfFile) -> (Vec<String>, f64) {
+    let mut matched_fields = Vec::new();
+    let mut score = 0.0;
+
+    if pattern.is_match(&arf.what) {
+        matched_fields.push("what".to_string());
+        score += 10.0;
+    }
+    if pattern.is_match(&arf.why) {
+        matched_fields.push("why".to_string());
+        score += 5.0;
+    }
+    if pattern.is_match(&arf.how) {
+        matched_fields.push("how".to_string());
+        score += 3.0;
+    }
+
+    (matched_fields, score)
+}
+
+/// ARFs in `backend` whose `context.files` cover any of `files`, as
+/// (path, ARF) sorted by path. A context entry matches the file itself
+/// or, for a directory, any file under it.
+pub fn linked_arfs(
+    backend: &dyn KnowledgeBackend,
+    files: &[String],
+) -> Result<Vec<(String, ArfFile)>> {
+    let mut linked = Vec::new();
+
+    for path in backend.list_arfs()? {
+        let Ok(Some(arf)) = backend.read_arf(&path) else {
+            continue;
+        };
+
+        let touched = arf
+            .context
+            .files
+            .iter()
+            .any(|context_file| files.iter().any(|f| covers(context_file, f)));
+        if touched {
+            linked.push((path, arf));
+        }
+    }
+
+    Ok(linked)
+}
+
+fn covers(context_file: &str, file: &str) -> bool {
+    let context_file = context_file.trim_start_matches("./").trim_end_matches('/');
+    !context_file.is_empty()
+        && (file == context_file
+            || file
+                .strip_prefix(context_file)
+                .is_some_and(|rest| rest.starts_with('/')))
+}
+
+/// Parse a `--since` value: `YYYY-MM-DD`, RFC 3339, or a relative
+/// age like `30d`, `12h`, or `2w`.
+pub fn parse_since(value: &str) -> Result<DateTime<Utc>> {
+    let value = value.trim();
+
+    if let Ok(dt) = DateTime::parse_from_rfc3339(value) {
+        return Ok(dt.with_timezone(&Utc));
+    }
+
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        return Ok(date.and_time(NaiveTime::MIN).and_utc());
+    }
+
+    if let Some(unit) = value.chars().last() {
+        // Ages are never negative, and those too large to subtract from
+        // now are rejected rather than overflowing
+        let amount = value[..value.len() - unit.len_utf8()]
+            .parse::<i64>()
+            .ok()
+            .filter(|amount| *amount >= 0);
+        if let Some(amount) = amount {
+            let age = match unit {
+                'h' => Some(Duration::try_hours(amount)),
+                'd' => Some(Duration::try_days(amount)),
+                'w' => Some(Duration::try_weeks(amount)),
+                _ => None,
+            };
+            if let Some(age) = age {
+                return age
+                    .and_then(|age| Utc::now().checked_sub_signed(age))
+                    .with_context(|| format!("Invalid date '{}': out of range", value));
+            }
+        }
+    }
+
+    anyhow::bail!(
+        "Invalid date '{}': expected YYYY-MM-DD, RFC 3339, or a relative age like 30d",
+        value
+    )
+}
+
+/// Parse an `--until-date` value like [`parse_since`] into the exclusive
+/// end of the range, so the given date or time is itself included: a bare
+/// `YYYY-MM-DD` ends at the start of the next day, anything else at the
+/// next whole second (commit times have one-second precision)
+pub fn parse_until(value: &str) -> Result<DateTime<Utc>> {
+    match NaiveDate::parse_from_str(value.trim(), "%Y-%m-%d") {
+        Ok(date) => date
+            .and_time(NaiveTime::MIN)
+            .and_utc()
+            .checked_add_signed(Duration::days(1))
+            .with_context(|| format!("Invalid date '{}': out of range", value)),
+        Err(_) => {
+            let at = parse_since(value)?;
+            DateTime::from_timestamp(at.timestamp() + 1, 0)
+                .with_context(|| format!("Invalid date '{}': out of range", value))
+        }
+    }
+}
+
+/// Ranking bonus for a glossary entry answering a question about `term`
+fn glossary_weight(arf: &ArfFile, term: &str) -> f64 {
+    if !is_glossary(arf) {
+        return 0.0;
+    }
+    match term_of(arf) {
+        Some(defined) if defined.eq_ignore_ascii_case(term) => GLOSSARY_TERM_WEIGHT,
+        _ => GLOSSARY_WEIGHT,
     }
 }
 
@@ -169,7 +564,6 @@ mod tests {
     use super::*;
     use crate::arf::ArfFile;
     use std::fs;
-    use std::path::Path;
     use tempfile::TempDir;
 
     fn setup_test_noggin(dir: &Path) {
@@ -343,6 +737,316 @@ mod tests {
         assert!(results.is_empty());
     }
 
+    #[test]
+    fn test_tag_and_confidence_filters() {
+        let tmp = TempDir::new().unwrap();
+        let decisions = tmp.path().join("decisions");
+        fs::create_dir_all(&decisions).unwrap();
+
+        let mut tagged = ArfFile::new("Use tokio runtime", "Async", "tokio::main");
+        tagged.tags = vec!["Runtime".to_string(), "async".to_string()];
+        tagged.confidence = Some(0.9);
+        tagged.to_toml(&decisions.join("tagged.arf")).unwrap();
+
+        let mut low = ArfFile::new("Use tokio channels", "Async", "mpsc");
+        low.tags = vec!["runtime".to_string()];
+        low.confidence = Some(0.4);
+        low.to_toml(&decisions.join("low.arf")).unwrap();
+
+        ArfFile::new("Use tokio timers", "Async", "sleep")
+            .to_toml(&decisions.join("untagged.arf"))
+            .unwrap();
+
+        let engine = QueryEngine::new(tmp.path().to_path_buf());
+
+        let by_tag = QueryOptions {
+            tags: vec!["runtime".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(engine.search("tokio", &by_tag).unwrap().len(), 2);
+
+        let by_both_tags = QueryOptions {
+            tags: vec!["runtime".to_string(), "async".to_string()],
+            ..Default::default()
+        };
+        let results = engine.search("tokio", &by_both_tags).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].file_path, "decisions/tagged.arf");
+
+        let by_confidence = QueryOptions {
+            min_confidence: Some(0.5),
+            ..Default::default()
+        };
+        let results = engine.search("tokio", &by_confidence).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].confidence, Some(0.9));
+    }
+
+    #[test]
+    fn test_sort_by_confidence() {
+        let tmp = TempDir::new().unwrap();
+        let decisions = tmp.path().join("decisions");
+        fs::create_dir_all(&decisions).unwrap();
+
+        let mut sure = ArfFile::new("Pick a runtime", "Async", "tokio");
+        sure.confidence = Some(0.9);
+        sure.to_toml(&decisions.join("sure.arf")).unwrap();
+
+        let mut unsure = ArfFile::new("Use tokio channels", "Async", "mpsc");
+        unsure.confidence = Some(0.4);
+        unsure.to_toml(&decisions.join("unsure.arf")).unwrap();
+
+        ArfFile::new("Use tokio timers", "Async", "tokio sleep")
+            .to_toml(&decisions.join("unscored.arf"))
+            .unwrap();
+
+        let engine = QueryEngine::new(tmp.path().to_path_buf());
+        let relevance = engine.search("tokio", &QueryOptions::default()).unwrap();
+        assert_ne!(relevance[0].file_path, "decisions/sure.arf");
+
+        let opts = QueryOptions {
+            sort: SortBy::Confidence,
+            ..Default::default()
+        };
+        let paths: Vec<String> = engine
+            .search("tokio", &opts)
+            .unwrap()
+            .into_iter()
+            .map(|r| r.file_path)
+            .collect();
+        assert_eq!(
+            paths,
+            vec![
+                "decisions/sure.arf",
+                "decisions/unsure.arf",
+                "decisions/unscored.arf"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_since_filter() {
+        let tmp = TempDir::new().unwrap();
+        setup_test_noggin(tmp.path());
+        let engine = QueryEngine::new(tmp.path().to_path_buf());
+
+        let past = QueryOptions {
+            since: Some(Utc::now() - Duration::days(1)),
+            ..Default::default()
+        };
+        assert!(!engine.search("tokio", &past).unwrap().is_empty());
+
+        let future = QueryOptions {
+            since: Some(Utc::now() + Duration::days(1)),
+            ..Default::default()
+        };
+        assert!(engine.search("tokio", &future).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_since_uses_entry_timestamps() {
+        let tmp = TempDir::new().unwrap();
+        setup_test_noggin(tmp.path());
+        let mut stale = ArfFile::new("Pin tokio version", "Stable runtime", "tokio = 1.35");
+        stale.created_at = Some(Utc::now() - Duration::days(90));
+        stale.to_toml(&tmp.path().join("decisions/pin-tokio.arf")).unwrap();
+        let mut revised = ArfFile::new("Upgrade tokio", "Runtime fixes", "tokio = 1.40");
+        revised.created_at = Some(Utc::now() - Duration::days(90));
+        revised.updated_at = Some(Utc::now() - Duration::days(2));
+        revised.to_toml(&tmp.path().join("decisions/upgrade-tokio.arf")).unwrap();
+        let engine = QueryEngine::new(tmp.path().to_path_buf());
+
+        let opts = QueryOptions {
+            since: Some(Utc::now() - Duration::days(30)),
+            ..Default::default()
+        };
+        let paths: Vec<String> = engine
+            .search("tokio", &opts)
+            .unwrap()
+            .into_iter()
+            .map(|r| r.file_path)
+            .collect();
+        assert!(paths.contains(&"decisions/upgrade-tokio.arf".to_string()));
+        assert!(!paths.contains(&"decisions/pin-tokio.arf".to_string()));
+    }
+
+    #[test]
+    fn test_covers() {
+        assert!(covers("src/pool.rs", "src/pool.rs"));
+        assert!(covers("app/services/", "app/services/federation.rb"));
+        assert!(covers("./src", "src/main.rs"));
+        assert!(!covers("src/pool", "src/pool.rs"));
+        assert!(!covers("", "src/main.rs"));
+    }
+
+    #[test]
+    fn test_linked_arfs() {
+        let tmp = TempDir::new().unwrap();
+        let noggin = tmp.path();
+
+        let mut pool = ArfFile::new("Use pooling", "Why", "How");
+        pool.add_file("src/db/");
+        pool.to_toml(&noggin.join("decisions/use-pooling.arf")).unwrap();
+
+        let mut errors = ArfFile::new("Errors via anyhow", "Why", "How");
+        errors.add_file("src/error.rs");
+        errors.to_toml(&noggin.join("patterns/errors.arf")).unwrap();
+
+        let files = vec!["src/db/pool.rs".to_string(), "README.md".to_string()];
+        let linked = linked_arfs(&FilesystemBackend::new(noggin), &files).unwrap();
+        assert_eq!(linked.len(), 1);
+        assert_eq!(linked[0].0, "decisions/use-pooling.arf");
+        assert_eq!(linked[0].1.what, "Use pooling");
+    }
+
+    #[test]
+    fn test_parse_since() {
+        let date = parse_since("2024-03-01").unwrap();
+        assert_eq!(date.to_rfc3339(), "2024-03-01T00:00:00+00:00");
+
+        let rfc = parse_since("2024-03-01T12:00:00+02:00").unwrap();
+        assert_eq!(rfc.to_rfc3339(), "2024-03-01T10:00:00+00:00");
+
+        let relative = parse_since("7d").unwrap();
+        let expected = Utc::now() - Duration::days(7);
+        assert!((relative - expected).num_seconds().abs() < 5);
+
+        assert!(parse_since("yesterday").is_err());
+        assert!(parse_since("7x").is_err());
+        for value in ["-30d", "99999999999999d", "100000000d", "9223372036854775807h"] {
+            let err = parse_since(value).unwrap_err();
+            assert!(err.to_string().starts_with("Invalid date"), "{}", value);
+            assert!(parse_until(value).is_err());
+        }
+
+        let until = parse_until("2024-03-31").unwrap();
+        assert_eq!(until.to_rfc3339(), "2024-04-01T00:00:00+00:00");
+        assert_eq!(parse_until("2024-03-01T12:00:00Z").unwrap().to_rfc3339(), "2024-03-01T12:00:01+00:00");
+        assert_eq!(parse_until("2024-03-01T12:00:00.5Z").unwrap().to_rfc3339(), "2024-03-01T12:00:01+00:00");
+    }
+
+    #[test]
+    fn test_search_any_merges_terms() {
+        let tmp = TempDir::new().unwrap();
+        setup_test_noggin(tmp.path());
+
+        let engine = QueryEngine::new(tmp.path().to_path_buf());
+        let terms = vec!["tokio".to_string(), "async".to_string(), "serde".to_string()];
+        let results = engine.search_any(&terms, &QueryOptions::default()).unwrap();
+
+        // use-tokio matches both tokio and async but appears once
+        let tokio_hits = results
+            .iter()
+            .filter(|r| r.file_path.ends_with("use-tokio.arf"))
+            .count();
+        assert_eq!(tokio_hits, 1);
+        assert!(results.iter().any(|r| r.file_path.ends_with("adopt-serde.arf")));
+
+        for window in results.windows(2) {
+            assert!(window[0].score >= window[1].score);
+        }
+    }
+
+    #[test]
+    fn test_answer_batch() {
+        let tmp = TempDir::new().unwrap();
+        setup_test_noggin(tmp.path());
+
+        let engine = QueryEngine::new(tmp.path().to_path_buf());
+        let questions = vec![
+            BatchQuestion {
+                question: "tokio".to_string(),
+                category: Some("bugs".to_string()),
+                ..Default::default()
+            },
+            BatchQuestion {
+                question: "serde".to_string(),
+                ..Default::default()
+            },
+            BatchQuestion {
+                question: "anyhow".to_string(),
+                since: Some("yesterday-ish".to_string()),
+                ..Default::default()
+            },
+        ];
+        let defaults = QueryOptions {
+            max_results: 1,
+            ..Default::default()
+        };
+        let answers = engine.answer_batch(&questions, &defaults);
+
+        assert_eq!(answers.len(), 3);
+        assert_eq!(answers[0].results.len(), 1);
+        assert_eq!(answers[0].results[0].category, "bugs");
+        assert!(answers[1].results[0].file_path.ends_with("adopt-serde.arf"));
+        assert!(answers[1].error.is_none());
+        assert!(answers[2].results.is_empty());
+        assert!(answers[2].error.as_deref().unwrap().contains("Invalid date"));
+    }
+
+    #[test]
+    fn test_semantic_search_finds_related_wording() {
+        let tmp = TempDir::new().unwrap();
+        setup_test_noggin(tmp.path());
+
+        let engine = QueryEngine::new(tmp.path().to_path_buf());
+        let query = "how do we propagate errors?";
+        assert!(engine.search(query, &QueryOptions::default()).unwrap().is_empty());
+
+        let results = engine.semantic_search(query, &QueryOptions::default()).unwrap();
+        assert_eq!(results[0].file_path, "patterns/error-handling.arf");
+        assert_eq!(results[0].matched_fields, vec!["semantic"]);
+        assert!(tmp.path().join("index/embeddings.json").exists());
+
+        let opts = QueryOptions {
+            category: Some("bugs".to_string()),
+            ..Default::default()
+        };
+        let results = engine.semantic_search(query, &opts).unwrap();
+        assert!(results.iter().all(|r| r.category == "bugs"));
+        assert!(engine
+            .semantic_search("nonexistent_term_xyz", &QueryOptions::default())
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_definitional_questions_prefer_glossary() {
+        let tmp = TempDir::new().unwrap();
+        setup_test_noggin(tmp.path());
+        let mut tokio = ArfFile::new(
+            "Tokio: the async runtime every command runs on",
+            "Core dependency used in 12 files",
+            "Started in main via #[tokio::main]",
+        );
+        tokio.tags = vec!["glossary".to_string()];
+        tokio.context.extra.insert("term".to_string(), toml::Value::String("Tokio".to_string()));
+        tokio.to_toml(&tmp.path().join("facts/glossary/tokio.arf")).unwrap();
+
+        let engine = QueryEngine::new(tmp.path().to_path_buf());
+        let results = engine.semantic_search("What is tokio?", &QueryOptions::default()).unwrap();
+        assert_eq!(results[0].file_path, "facts/glossary/tokio.arf");
+        assert_eq!(results[0].category, "facts");
+        assert!(results[0].matched_fields.contains(&"term".to_string()));
+
+        // Other questions get no glossary bonus
+        let results = engine
+            .semantic_search("tokio runtime dependency", &QueryOptions::default())
+            .unwrap();
+        let glossary = results.iter().find(|r| r.file_path.starts_with("facts/glossary/")).unwrap();
+        assert!(!glossary.matched_fields.contains(&"term".to_string()));
+    }
+
+    #[test]
+    fn test_people_include_file_authors() {
+        let mut arf = ArfFile::new("Pool connections", "Latency", "bb8");
+        arf.context.people = vec!["Ana".to_string()];
+        arf.add_outcome(AUTHORS_KEY, "Bo, Ana");
+
+        let result = QueryResult::new("patterns/pool.arf".to_string(), "patterns".to_string(), arf, vec![], 1.0);
+        assert_eq!(result.people, vec!["Ana", "Bo"]);
+    }
+
     #[test]
     fn test_json_serialization() {
         let result = QueryResult {
@@ -351,12 +1055,17 @@ mod tests {
             what: "Use tokio".to_string(),
             why: "Async".to_string(),
             how: "Add dep".to_string(),
+            tags: vec![],
+            confidence: None,
+            issues: vec![],
+            people: vec![],
             matched_fields: vec!["what".to_string()],
             score: 13.0,
         };
 
         let json = serde_json::to_string(&result).unwrap();
         assert!(json.contains("\"category\":\"decisions\""));
+        assert!(!json.contains("issues"));
         assert!(json.contains("\"score\":13.0"));
     }
 }
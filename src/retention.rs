@@ -0,0 +1,284 @@
+//! Retention of local artifacts: raw provider responses, caches, logs,
+//! and checkpoints.
+//!
+//! Learn keeps each provider response under `.noggin/raw/` (unless
+//! `retention.store_raw_responses` is off) so parse failures can be
+//! debugged. Files under `raw/`, `cache/`, and `logs/` are expired by age
+//! and total size after every learn run; `noggin purge` removes them on
+//! demand. The search index under `index/` counts as cache.
+
+use crate::config::RetentionConfig;
+use crate::learn::checkpoint::CHECKPOINT_FILE;
+use anyhow::{Context, Result};
+use chrono::Utc;
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use walkdir::WalkDir;
+
+pub const RAW_DIR: &str = "raw";
+pub const CACHE_DIR: &str = "cache";
+pub const LOGS_DIR: &str = "logs";
+
+/// Directories whose files are subject to the age and size limits
+const RETAINED_DIRS: &[&str] = &[RAW_DIR, CACHE_DIR, LOGS_DIR];
+
+/// What `noggin purge` can remove
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PurgeTarget {
+    Raw,
+    Cache,
+    Logs,
+    Checkpoint,
+}
+
+impl PurgeTarget {
+    pub const ALL: &'static [PurgeTarget] = &[
+        PurgeTarget::Raw,
+        PurgeTarget::Cache,
+        PurgeTarget::Logs,
+        PurgeTarget::Checkpoint,
+    ];
+
+    /// Paths under .noggin/ the target covers
+    fn paths(self) -> &'static [&'static str] {
+        match self {
+            PurgeTarget::Raw => &[RAW_DIR],
+            PurgeTarget::Cache => &[CACHE_DIR, "index"],
+            PurgeTarget::Logs => &[LOGS_DIR],
+            PurgeTarget::Checkpoint => &[CHECKPOINT_FILE],
+        }
+    }
+}
+
+/// Files removed (or, in a dry run, that would be)
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PurgeReport {
+    /// Paths relative to .noggin/
+    pub removed: Vec<String>,
+    pub bytes: u64,
+    pub dry_run: bool,
+}
+
+/// Save a provider response under `.noggin/raw/`, unless raw storage is
+/// disabled. Returns the path written.
+pub fn store_raw_response(
+    noggin_path: &Path,
+    config: &RetentionConfig,
+    model: &str,
+    prompt_type: &str,
+    response: &str,
+) -> Result<Option<PathBuf>> {
+    if !config.store_raw_responses {
+        return Ok(None);
+    }
+
+    let dir = noggin_path.join(RAW_DIR);
+    fs::create_dir_all(&dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+    let name = format!(
+        "{}-{}-{}.txt",
+        Utc::now().format("%Y%m%dT%H%M%S%3f"),
+        prompt_type,
+        model
+    );
+    let path = dir.join(name);
+    fs::write(&path, response).with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(Some(path))
+}
+
+/// Remove retained files older than `max_age_days`, then the oldest ones
+/// until the total is within `max_size_mb`
+pub fn expire(noggin_path: &Path, config: &RetentionConfig, dry_run: bool) -> Result<PurgeReport> {
+    let mut files: Vec<(PathBuf, SystemTime, u64)> = RETAINED_DIRS
+        .iter()
+        .flat_map(|dir| files_under(&noggin_path.join(dir)))
+        .collect();
+    files.sort_by_key(|(_, modified, _)| *modified);
+
+    let mut expired: Vec<(PathBuf, u64)> = Vec::new();
+    if let Some(days) = config.max_age_days {
+        let cutoff = SystemTime::now() - Duration::from_secs(days * 24 * 60 * 60);
+        files.retain(|(path, modified, size)| {
+            let keep = *modified >= cutoff;
+            if !keep {
+                expired.push((path.clone(), *size));
+            }
+            keep
+        });
+    }
+    if let Some(max_mb) = config.max_size_mb {
+        let limit = max_mb * 1024 * 1024;
+        let mut total: u64 = files.iter().map(|(_, _, size)| size).sum();
+        for (path, _, size) in &files {
+            if total <= limit {
+                break;
+            }
+            expired.push((path.clone(), *size));
+            total -= size;
+        }
+    }
+
+    remove(noggin_path, expired, dry_run)
+}
+
+/// Remove everything the given targets cover
+pub fn purge(noggin_path: &Path, targets: &[PurgeTarget], dry_run: bool) -> Result<PurgeReport> {
+    let mut doomed: Vec<(PathBuf, u64)> = Vec::new();
+    for target in targets {
+        for rel in target.paths() {
+            let path = noggin_path.join(rel);
+            if path.is_file() {
+                let size = path.metadata().map(|m| m.len()).unwrap_or(0);
+                doomed.push((path, size));
+            } else {
+                doomed.extend(files_under(&path).into_iter().map(|(p, _, size)| (p, size)));
+            }
+        }
+    }
+
+    let report = remove(noggin_path, doomed, dry_run)?;
+    if !dry_run {
+        for target in targets {
+            for rel in target.paths() {
+                let path = noggin_path.join(rel);
+                if path.is_dir() {
+                    fs::remove_dir_all(&path)
+                        .with_context(|| format!("Failed to remove {}", path.display()))?;
+                }
+            }
+        }
+    }
+    Ok(report)
+}
+
+fn files_under(dir: &Path) -> Vec<(PathBuf, SystemTime, u64)> {
+    WalkDir::new(dir)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file())
+        .filter_map(|e| {
+            let metadata = e.metadata().ok()?;
+            Some((e.into_path(), metadata.modified().ok()?, metadata.len()))
+        })
+        .collect()
+}
+
+fn remove(noggin_path: &Path, files: Vec<(PathBuf, u64)>, dry_run: bool) -> Result<PurgeReport> {
+    let mut report = PurgeReport {
+        dry_run,
+        ..Default::default()
+    };
+    for (path, size) in files {
+        if !dry_run {
+            fs::remove_file(&path)
+                .with_context(|| format!("Failed to remove {}", path.display()))?;
+        }
+        let rel = path.strip_prefix(noggin_path).unwrap_or(&path);
+        report.removed.push(rel.to_string_lossy().into_owned());
+        report.bytes += size;
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn write(noggin: &Path, rel: &str, bytes: usize) {
+        let path = noggin.join(rel);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, "x".repeat(bytes)).unwrap();
+    }
+
+    #[test]
+    fn test_store_raw_response_respects_switch() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut config = RetentionConfig::default();
+
+        let path = store_raw_response(temp_dir.path(), &config, "gemini", "files", "[[entry]]")
+            .unwrap()
+            .unwrap();
+        assert!(path.starts_with(temp_dir.path().join(RAW_DIR)));
+        assert!(path.to_string_lossy().ends_with("-files-gemini.txt"));
+
+        config.store_raw_responses = false;
+        fs::remove_dir_all(temp_dir.path().join(RAW_DIR)).unwrap();
+        assert!(
+            store_raw_response(temp_dir.path(), &config, "gemini", "files", "x")
+                .unwrap()
+                .is_none()
+        );
+        assert!(!temp_dir.path().join(RAW_DIR).exists());
+    }
+
+    #[test]
+    fn test_expire_by_size_removes_oldest() {
+        let temp_dir = TempDir::new().unwrap();
+        let noggin = temp_dir.path();
+        write(noggin, "raw/old.txt", 700_000);
+        std::thread::sleep(Duration::from_millis(20));
+        write(noggin, "logs/new.log", 700_000);
+        write(noggin, "decisions/keep.arf", 700_000);
+
+        let config = RetentionConfig {
+            max_age_days: None,
+            max_size_mb: Some(1),
+            ..Default::default()
+        };
+        let report = expire(noggin, &config, true).unwrap();
+        assert_eq!(report.removed, vec!["raw/old.txt"]);
+        assert!(noggin.join("raw/old.txt").exists());
+
+        expire(noggin, &config, false).unwrap();
+        assert!(!noggin.join("raw/old.txt").exists());
+        assert!(noggin.join("logs/new.log").exists());
+        assert!(noggin.join("decisions/keep.arf").exists());
+    }
+
+    #[test]
+    fn test_expire_by_age() {
+        let temp_dir = TempDir::new().unwrap();
+        write(temp_dir.path(), "raw/a.txt", 10);
+        let config = RetentionConfig {
+            max_age_days: Some(0),
+            max_size_mb: None,
+            ..Default::default()
+        };
+        std::thread::sleep(Duration::from_millis(20));
+        assert_eq!(
+            expire(temp_dir.path(), &config, false)
+                .unwrap()
+                .removed
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_purge_targets() {
+        let temp_dir = TempDir::new().unwrap();
+        let noggin = temp_dir.path();
+        write(noggin, "raw/a.txt", 10);
+        write(noggin, "index/embeddings.json", 20);
+        write(noggin, CHECKPOINT_FILE, 5);
+        write(noggin, "patterns/keep.arf", 5);
+
+        let report = purge(
+            noggin,
+            &[PurgeTarget::Cache, PurgeTarget::Checkpoint],
+            false,
+        )
+        .unwrap();
+        assert_eq!(report.bytes, 25);
+        assert!(!noggin.join("index").exists());
+        assert!(!noggin.join(CHECKPOINT_FILE).exists());
+        assert!(noggin.join("raw/a.txt").exists());
+
+        purge(noggin, PurgeTarget::ALL, false).unwrap();
+        assert!(!noggin.join("raw").exists());
+        assert!(noggin.join("patterns/keep.arf").exists());
+    }
+}
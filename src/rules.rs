@@ -0,0 +1,358 @@
+//! Deterministic rules attached to patterns.
+//!
+//! An ARF may carry a `rules` section that `noggin check` evaluates
+//! against the tracked files of the repository, without any LLM:
+//!
+//! ```toml
+//! [[rules]]
+//! kind = "forbid"
+//! pattern = "^use std::sync::Mutex"
+//! paths = ["src/**/*.rs"]
+//! message = "Use parking_lot::Mutex"
+//!
+//! [[rules]]
+//! kind = "require"
+//! when = "sqlx::query"
+//! pattern = "with_retry\\("
+//! paths = ["src/db/**"]
+//! ```
+//!
+//! `forbid` reports every line matching `pattern`. `require` reports files
+//! that match `when` (or every file in scope, if `when` is unset) but never
+//! match `pattern`. Patterns are regular expressions matched per line.
+
+use crate::arf::ArfFile;
+use crate::storage::KnowledgeBackend;
+use anyhow::{Context, Result};
+use git2::Repository;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RuleKind {
+    /// No line in scope may match `pattern`
+    Forbid,
+    /// Files matching `when` must also match `pattern`
+    Require,
+}
+
+/// A rule evaluated by `noggin check`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PatternRule {
+    pub kind: RuleKind,
+    pub pattern: String,
+    /// For `require`: only files containing this pattern must comply
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub when: Option<String>,
+    /// Globs (`*`, `**`, `?`) limiting which files are checked; empty means all
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub paths: Vec<String>,
+    /// Shown with each violation
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+/// A file that breaks a rule
+#[derive(Debug, Clone, Serialize)]
+pub struct Violation {
+    /// ARF declaring the rule, relative to .noggin/
+    pub arf: String,
+    /// `what` of that ARF
+    pub what: String,
+    /// Index of the rule within the ARF's `rules`
+    pub rule: usize,
+    pub file: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+impl PatternRule {
+    /// Check that every regex and glob compiles
+    pub fn validate(&self) -> Result<()> {
+        self.compile().map(|_| ())
+    }
+
+    fn compile(&self) -> Result<CompiledRule<'_>> {
+        let pattern = Regex::new(&self.pattern)
+            .with_context(|| format!("Invalid rule pattern: {}", self.pattern))?;
+        let when = match &self.when {
+            Some(when) => Some(
+                Regex::new(when).with_context(|| format!("Invalid rule 'when' pattern: {}", when))?,
+            ),
+            None => None,
+        };
+        let paths = self
+            .paths
+            .iter()
+            .map(|glob| glob_to_regex(glob))
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(CompiledRule {
+            rule: self,
+            pattern,
+            when,
+            paths,
+        })
+    }
+
+    fn describe(&self) -> String {
+        match &self.message {
+            Some(message) => message.clone(),
+            None => match self.kind {
+                RuleKind::Forbid => format!("matches forbidden pattern `{}`", self.pattern),
+                RuleKind::Require => format!("missing required pattern `{}`", self.pattern),
+            },
+        }
+    }
+}
+
+struct CompiledRule<'a> {
+    rule: &'a PatternRule,
+    pattern: Regex,
+    when: Option<Regex>,
+    paths: Vec<Regex>,
+}
+
+impl CompiledRule<'_> {
+    fn applies_to(&self, file: &str) -> bool {
+        self.paths.is_empty() || self.paths.iter().any(|glob| glob.is_match(file))
+    }
+
+    /// Violations of this rule in one file: (line, message)
+    fn check(&self, contents: &str) -> Vec<(Option<usize>, String)> {
+        let message = self.rule.describe();
+        match self.rule.kind {
+            RuleKind::Forbid => contents
+                .lines()
+                .enumerate()
+                .filter(|(_, line)| self.pattern.is_match(line))
+                .map(|(idx, _)| (Some(idx + 1), message.clone()))
+                .collect(),
+            RuleKind::Require => {
+                let trigger = match &self.when {
+                    Some(when) => match contents.lines().position(|line| when.is_match(line)) {
+                        Some(idx) => Some(idx + 1),
+                        None => return Vec::new(),
+                    },
+                    None => None,
+                };
+                if contents.lines().any(|line| self.pattern.is_match(line)) {
+                    Vec::new()
+                } else {
+                    vec![(trigger, message)]
+                }
+            }
+        }
+    }
+}
+
+/// Translate a path glob into an anchored regex. `**` spans directories,
+/// `*` and `?` stay within one path segment.
+pub fn glob_to_regex(glob: &str) -> Result<Regex> {
+    let mut out = String::from("^");
+    let mut chars = glob.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+                if chars.peek() == Some(&'/') {
+                    chars.next();
+                    out.push_str("(?:.*/)?");
+                } else {
+                    out.push_str(".*");
+                }
+            }
+            '*' => out.push_str("[^/]*"),
+            '?' => out.push_str("[^/]"),
+            c => out.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    out.push('$');
+
+    Regex::new(&out).with_context(|| format!("Invalid path glob: {}", glob))
+}
+
+/// Every ARF in `backend` that declares rules, keyed by path
+pub fn load_ruled_arfs(backend: &dyn KnowledgeBackend) -> Result<Vec<(String, ArfFile)>> {
+    let mut ruled = Vec::new();
+
+    for path in backend.list_arfs()? {
+        let Some(arf) = backend.read_arf(&path)? else {
+            continue;
+        };
+        if !arf.rules.is_empty() {
+            ruled.push((path, arf));
+        }
+    }
+
+    Ok(ruled)
+}
+
+/// Evaluate the rules of `arfs` against the files tracked in the
+/// repository's index. Unreadable and non-UTF-8 files are skipped.
+pub fn check_repository(repo_path: &Path, arfs: &[(String, ArfFile)]) -> Result<Vec<Violation>> {
+    let repo = Repository::open(repo_path)
+        .with_context(|| format!("Failed to open git repository at {}", repo_path.display()))?;
+    let index = repo.index().context("Failed to read git index")?;
+
+    let mut files: Vec<String> = index
+        .iter()
+        .map(|entry| String::from_utf8_lossy(&entry.path).into_owned())
+        .filter(|path| !path.starts_with(".noggin/"))
+        .collect();
+    files.sort();
+    files.dedup();
+
+    let mut compiled = Vec::new();
+    for (arf_path, arf) in arfs {
+        for (idx, rule) in arf.rules.iter().enumerate() {
+            let rule = rule
+                .compile()
+                .with_context(|| format!("Invalid rule {} in {}", idx, arf_path))?;
+            compiled.push((arf_path, arf, idx, rule));
+        }
+    }
+
+    let mut violations = Vec::new();
+    for file in &files {
+        let applicable: Vec<_> = compiled
+            .iter()
+            .filter(|(_, _, _, rule)| rule.applies_to(file))
+            .collect();
+        if applicable.is_empty() {
+            continue;
+        }
+        let Ok(contents) = fs::read_to_string(repo_path.join(file)) else {
+            continue;
+        };
+
+        for (arf_path, arf, idx, rule) in applicable {
+            for (line, message) in rule.check(&contents) {
+                violations.push(Violation {
+                    arf: (*arf_path).clone(),
+                    what: arf.what.clone(),
+                    rule: *idx,
+                    file: file.clone(),
+                    line,
+                    message,
+                });
+            }
+        }
+    }
+
+    violations.sort_by(|a, b| (&a.arf, &a.file, a.line).cmp(&(&b.arf, &b.file, b.line)));
+    Ok(violations)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn rule(kind: RuleKind, pattern: &str) -> PatternRule {
+        PatternRule {
+            kind,
+            pattern: pattern.to_string(),
+            when: None,
+            paths: Vec::new(),
+            message: None,
+        }
+    }
+
+    #[test]
+    fn test_glob_to_regex() {
+        let glob = glob_to_regex("src/**/*.rs").unwrap();
+        assert!(glob.is_match("src/main.rs"));
+        assert!(glob.is_match("src/db/pool.rs"));
+        assert!(!glob.is_match("tests/main.rs"));
+        assert!(!glob.is_match("src/main.rsx"));
+
+        let glob = glob_to_regex("*.toml").unwrap();
+        assert!(glob.is_match("Cargo.toml"));
+        assert!(!glob.is_match("config/app.toml"));
+    }
+
+    #[test]
+    fn test_forbid_reports_each_line() {
+        let forbid = rule(RuleKind::Forbid, r"^use std::sync::Mutex");
+        let compiled = forbid.compile().unwrap();
+        let found = compiled.check("use std::sync::Mutex;\nfn main() {}\nuse std::sync::Mutex as M;\n");
+        let lines: Vec<_> = found.iter().map(|(line, _)| *line).collect();
+        assert_eq!(lines, vec![Some(1), Some(3)]);
+    }
+
+    #[test]
+    fn test_require_with_trigger() {
+        let mut require = rule(RuleKind::Require, r"with_retry\(");
+        require.when = Some("sqlx::query".to_string());
+        require.message = Some("Wrap queries in with_retry".to_string());
+        let compiled = require.compile().unwrap();
+
+        assert!(compiled.check("fn a() {}\n").is_empty());
+        assert!(compiled.check("sqlx::query(q);\nwith_retry(f);\n").is_empty());
+
+        let found = compiled.check("fn a() {}\nsqlx::query(q);\n");
+        assert_eq!(found, vec![(Some(2), "Wrap queries in with_retry".to_string())]);
+    }
+
+    #[test]
+    fn test_invalid_rule_is_dropped_by_validation() {
+        let mut arf = ArfFile::new("What", "Why", "How");
+        arf.rules.push(rule(RuleKind::Forbid, "("));
+        arf.rules.push(rule(RuleKind::Forbid, "unwrap"));
+        let notes = arf.validate().unwrap();
+        assert_eq!(arf.rules.len(), 1);
+        assert!(notes[0].starts_with("dropped rule 1"));
+    }
+
+    #[test]
+    fn test_rules_round_trip_through_toml() {
+        let toml = r#"
+what = "Use parking_lot"
+why = "Faster"
+how = "parking_lot::Mutex"
+
+[[rules]]
+kind = "forbid"
+pattern = "std::sync::Mutex"
+paths = ["src/**"]
+"#;
+        let arf: ArfFile = toml::from_str(toml).unwrap();
+        assert_eq!(arf.rules.len(), 1);
+        assert_eq!(arf.rules[0].kind, RuleKind::Forbid);
+
+        let back: ArfFile = toml::from_str(&toml::to_string_pretty(&arf).unwrap()).unwrap();
+        assert_eq!(back, arf);
+    }
+
+    #[test]
+    fn test_check_repository() {
+        let temp_dir = TempDir::new().unwrap();
+        let repo = Repository::init(temp_dir.path()).unwrap();
+        fs::create_dir_all(temp_dir.path().join("src")).unwrap();
+        fs::write(temp_dir.path().join("src/a.rs"), "use std::sync::Mutex;\n").unwrap();
+        fs::write(temp_dir.path().join("src/b.rs"), "use parking_lot::Mutex;\n").unwrap();
+        fs::write(temp_dir.path().join("notes.md"), "std::sync::Mutex is banned\n").unwrap();
+        let mut index = repo.index().unwrap();
+        for file in ["src/a.rs", "src/b.rs", "notes.md"] {
+            index.add_path(Path::new(file)).unwrap();
+        }
+        index.write().unwrap();
+
+        let mut arf = ArfFile::new("Use parking_lot", "Faster", "parking_lot::Mutex");
+        let mut forbid = rule(RuleKind::Forbid, "std::sync::Mutex");
+        forbid.paths = vec!["src/**/*.rs".to_string()];
+        arf.rules.push(forbid);
+
+        let violations =
+            check_repository(temp_dir.path(), &[("patterns/parking-lot.arf".to_string(), arf)]).unwrap();
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].file, "src/a.rs");
+        assert_eq!(violations[0].line, Some(1));
+        assert_eq!(violations[0].what, "Use parking_lot");
+    }
+}
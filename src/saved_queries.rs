@@ -0,0 +1,172 @@
+//! Named questions from `.noggin/queries.toml`.
+//!
+//! Lets a team define common questions once, each with its own search
+//! terms, filters, and output format, and run them with
+//! `noggin ask --saved <name>`:
+//!
+//! ```toml
+//! [queries.migrations]
+//! question = "How do we do migrations?"
+//! terms = ["migration", "schema"]
+//! category = "migrations"
+//! max_results = 20
+//! format = "markdown"
+//! ```
+
+use crate::query::{QueryOptions, SortBy};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+const QUERIES_FILE: &str = "queries.toml";
+
+/// How `ask` renders results
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    #[default]
+    Text,
+    Json,
+    Markdown,
+}
+
+/// Contents of `.noggin/queries.toml`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SavedQueries {
+    #[serde(default)]
+    pub queries: BTreeMap<String, SavedQuery>,
+}
+
+/// A named question with its retrieval settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedQuery {
+    /// Human-readable question, used as the report title
+    pub question: String,
+    /// Search terms; results matching any term are merged.
+    /// Defaults to the question itself.
+    #[serde(default)]
+    pub terms: Vec<String>,
+    #[serde(default)]
+    pub category: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub min_confidence: Option<f64>,
+    #[serde(default)]
+    pub max_results: Option<usize>,
+    #[serde(default)]
+    pub format: OutputFormat,
+}
+
+impl SavedQueries {
+    /// Load `.noggin/queries.toml`, empty if the file doesn't exist
+    pub fn load(noggin_path: &Path) -> Result<Self> {
+        let path = noggin_path.join(QUERIES_FILE);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let contents = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read saved queries from {}", path.display()))?;
+
+        toml::from_str(&contents)
+            .with_context(|| format!("Failed to parse saved queries from {}", path.display()))
+    }
+
+    /// Look up a saved query by name
+    pub fn get(&self, name: &str) -> Result<&SavedQuery> {
+        self.queries.get(name).with_context(|| {
+            if self.queries.is_empty() {
+                format!("No saved query '{}'. Define queries in .noggin/queries.toml", name)
+            } else {
+                let names: Vec<&str> = self.queries.keys().map(|k| k.as_str()).collect();
+                format!("No saved query '{}'. Available: {}", name, names.join(", "))
+            }
+        })
+    }
+}
+
+impl SavedQuery {
+    /// Search terms, falling back to the question text
+    pub fn search_terms(&self) -> Vec<String> {
+        if self.terms.is_empty() {
+            vec![self.question.clone()]
+        } else {
+            self.terms.clone()
+        }
+    }
+
+    /// Query options for this saved query, with `default_max` used when
+    /// the query doesn't set its own limit
+    pub fn options(&self, default_max: usize) -> QueryOptions {
+        QueryOptions {
+            max_results: self.max_results.unwrap_or(default_max),
+            category: self.category.clone(),
+            tags: self.tags.clone(),
+            min_confidence: self.min_confidence,
+            since: None,
+            sort: SortBy::Relevance,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_load_saved_queries() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(
+            temp_dir.path().join("queries.toml"),
+            r#"
+[queries.release]
+question = "Release checklist"
+terms = ["release", "version"]
+max_results = 5
+format = "markdown"
+
+[queries.security]
+question = "auth"
+category = "decisions"
+"#,
+        )
+        .unwrap();
+
+        let saved = SavedQueries::load(temp_dir.path()).unwrap();
+        let release = saved.get("release").unwrap();
+        assert_eq!(release.search_terms(), vec!["release", "version"]);
+        assert_eq!(release.format, OutputFormat::Markdown);
+        assert_eq!(release.options(10).max_results, 5);
+
+        let security = saved.get("security").unwrap();
+        assert_eq!(security.search_terms(), vec!["auth"]);
+        assert_eq!(security.format, OutputFormat::Text);
+        assert_eq!(security.options(10).category.as_deref(), Some("decisions"));
+    }
+
+    #[test]
+    fn test_unknown_query_lists_available() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(
+            temp_dir.path().join("queries.toml"),
+            "[queries.release]\nquestion = \"Release checklist\"\n",
+        )
+        .unwrap();
+
+        let saved = SavedQueries::load(temp_dir.path()).unwrap();
+        let err = saved.get("missing").unwrap_err().to_string();
+        assert!(err.contains("Available: release"));
+    }
+
+    #[test]
+    fn test_missing_file_is_empty() {
+        let temp_dir = TempDir::new().unwrap();
+        let saved = SavedQueries::load(temp_dir.path()).unwrap();
+        assert!(saved.queries.is_empty());
+        assert!(saved.get("anything").is_err());
+    }
+}
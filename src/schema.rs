@@ -0,0 +1,451 @@
+//! JSON Schema for the ARF format
+//!
+//! `noggin schema` prints the schema so editors and external tools can
+//! validate `.arf` files (TOML maps onto JSON one to one). The same schema
+//! is checked here by a small validator covering the keywords it uses, so
+//! `noggin check` and `noggin approve` catch hand-written mistakes that
+//! serde would otherwise ignore, such as misspelled keys.
+
+use crate::arf::{arf_category, ArfFile};
+use crate::storage::KnowledgeBackend;
+use crate::templates::Templates;
+use anyhow::{Context, Result};
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+use std::path::Path;
+
+pub const SCHEMA_ID: &str = "https://github.com/ducks/noggin/schemas/arf.schema.json";
+
+/// One place where a document breaks the schema
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SchemaError {
+    /// Dotted path to the offending value, empty for the document root
+    pub field: String,
+    pub message: String,
+}
+
+/// A schema error in a file of the knowledge base
+#[derive(Debug, Clone, Serialize)]
+pub struct SchemaViolation {
+    /// ARF path relative to .noggin/
+    pub arf: String,
+    pub field: String,
+    pub message: String,
+}
+
+/// The JSON Schema (draft 2020-12) for an ARF file
+pub fn arf_schema() -> Value {
+    let string_list = |description: &str| {
+        json!({
+            "type": "array",
+            "description": description,
+            "items": { "type": "string" }
+        })
+    };
+
+    let provenance = json!({
+        "type": "object",
+        "description": "Where a synthesized entry came from",
+        "additionalProperties": false,
+        "properties": {
+            "models": string_list("Models whose output contributed"),
+            "fields": {
+                "type": "object",
+                "description": "Models whose value won the vote, per field",
+                "additionalProperties": {
+                    "type": "array",
+                    "items": { "type": "string" }
+                }
+            }
+        }
+    });
+
+    json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "$id": SCHEMA_ID,
+        "title": "ARF",
+        "description": "A unit of codebase knowledge stored by noggin",
+        "type": "object",
+        "required": ["what", "why", "how"],
+        "additionalProperties": false,
+        "properties": {
+            "what": {
+                "type": "string",
+                "minLength": 1,
+                "description": "Concise description of the knowledge"
+            },
+            "why": {
+                "type": "string",
+                "minLength": 1,
+                "description": "Reason or motivation behind it"
+            },
+            "how": {
+                "type": "string",
+                "minLength": 1,
+                "description": "Implementation details or process"
+            },
+            "id": {
+                "type": "string",
+                "minLength": 1,
+                "description": "Stable identifier that survives file renames"
+            },
+            "category": {
+                "enum": ["decisions", "patterns", "bugs", "migrations", "facts"],
+                "description": "Category directory the entry belongs in"
+            },
+            "source": {
+                "enum": ["file-analysis", "commit-analysis", "manual"],
+                "description": "How the entry was produced"
+            },
+            "approved": {
+                "type": "boolean",
+                "description": "Reviewed by a person; learn will not silently overwrite it"
+            },
+            "tags": string_list("Free-form labels for filtering"),
+            "confidence": {
+                "type": "number",
+                "minimum": 0.0,
+                "maximum": 1.0,
+                "description": "How sure the extraction is"
+            },
+            "created_at": {
+                "type": "string",
+                "description": "When the entry was first written (RFC 3339)"
+            },
+            "updated_at": {
+                "type": "string",
+                "description": "When the entry's content last changed (RFC 3339)"
+            },
+            "rules": {
+                "type": "array",
+                "description": "Machine-checkable rules evaluated by noggin check",
+                "items": {
+                    "type": "object",
+                    "required": ["kind", "pattern"],
+                    "additionalProperties": false,
+                    "properties": {
+                        "kind": { "enum": ["forbid", "require"] },
+                        "pattern": {
+                            "type": "string",
+                            "description": "Regular expression matched per line"
+                        },
+                        "when": {
+                            "type": "string",
+                            "description": "For require: only files containing this must comply"
+                        },
+                        "paths": string_list("Globs limiting which files are checked"),
+                        "message": {
+                            "type": "string",
+                            "description": "Shown with each violation"
+                        }
+                    }
+                }
+            },
+            "context": {
+                "type": "object",
+                "description": "Unknown keys are kept as written",
+                "properties": {
+                    "files": string_list("Files related to this knowledge"),
+                    "commits": string_list("Commits related to this knowledge"),
+                    "branches": string_list("Branches the commits were analyzed from"),
+                    "issues": string_list("Issue URLs or references like #123"),
+                    "people": string_list("Authors of the cited commits"),
+                    "dependencies": string_list("Dependencies required"),
+                    "prompt_types": string_list("Learn prompts the entry was synthesized from"),
+                    "term": {
+                        "type": "string",
+                        "description": "Term a glossary entry defines"
+                    },
+                    "outcome": {
+                        "type": "object",
+                        "description": "Outcome or result as key-value pairs",
+                        "additionalProperties": { "type": "string" }
+                    },
+                    "provenance": provenance
+                }
+            }
+        }
+    })
+}
+
+/// Validate a document against a schema, returning every error found
+pub fn validate(schema: &Value, document: &Value) -> Vec<SchemaError> {
+    let mut errors = Vec::new();
+    validate_at(schema, document, "", &mut errors);
+    errors
+}
+
+/// Parse ARF TOML and validate it against [`arf_schema`]
+pub fn validate_arf_toml(contents: &str) -> Result<Vec<SchemaError>> {
+    let document: toml::Value = toml::from_str(contents).context("Failed to parse TOML")?;
+    let document = serde_json::to_value(document).context("Failed to convert TOML to JSON")?;
+    Ok(validate(&arf_schema(), &document))
+}
+
+/// Validate every ARF in `backend`, including the fields its category
+/// template under `noggin_path` requires (see [`crate::templates`]).
+/// Files that aren't valid TOML are reported too.
+pub fn check_knowledge_base(
+    noggin_path: &Path,
+    backend: &dyn KnowledgeBackend,
+) -> Result<Vec<SchemaViolation>> {
+    let mut violations = Vec::new();
+    let templates = Templates::load(noggin_path)?;
+
+    for arf in backend.list_arfs()? {
+        let Some(contents) = backend
+            .read(&arf)
+            .with_context(|| format!("Failed to read ARF file: {}", arf))?
+        else {
+            continue;
+        };
+        let mut errors = match validate_arf_toml(&contents) {
+            Ok(errors) => errors,
+            Err(e) => vec![SchemaError {
+                field: String::new(),
+                message: format!("{:#}", e),
+            }],
+        };
+        if errors.is_empty() {
+            if let Ok(parsed) = toml::from_str::<ArfFile>(&contents) {
+                let category = arf_category(Path::new(&arf));
+                errors.extend(templates.missing(&category, &parsed).into_iter().map(|name| {
+                    SchemaError {
+                        field: format!("context.{}", name),
+                        message: format!("is required by templates/{}.toml", category),
+                    }
+                }));
+            }
+        }
+        violations.extend(errors.into_iter().map(|e| SchemaViolation {
+            arf: arf.clone(),
+            field: e.field,
+            message: e.message,
+        }));
+    }
+
+    Ok(violations)
+}
+
+fn validate_at(schema: &Value, value: &Value, field: &str, errors: &mut Vec<SchemaError>) {
+    let mut fail = |message: String| {
+        errors.push(SchemaError {
+            field: field.to_string(),
+            message,
+        })
+    };
+
+    if let Some(allowed) = schema.get("enum").and_then(Value::as_array) {
+        if !allowed.contains(value) {
+            let names: Vec<String> = allowed.iter().map(Value::to_string).collect();
+            fail(format!("must be one of {}", names.join(", ")));
+            return;
+        }
+    }
+
+    if let Some(expected) = schema.get("type").and_then(Value::as_str) {
+        if type_name(value) != expected && !(expected == "number" && value.is_number()) {
+            fail(format!("expected {}, found {}", expected, type_name(value)));
+            return;
+        }
+    }
+
+    match value {
+        Value::String(s) => {
+            if let Some(min) = schema.get("minLength").and_then(Value::as_u64) {
+                if (s.trim().chars().count() as u64) < min {
+                    fail("must not be empty".to_string());
+                }
+            }
+        }
+        Value::Number(n) => {
+            let n = n.as_f64().unwrap_or_default();
+            if let Some(min) = schema.get("minimum").and_then(Value::as_f64) {
+                if n < min {
+                    fail(format!("must be at least {}", min));
+                }
+            }
+            if let Some(max) = schema.get("maximum").and_then(Value::as_f64) {
+                if n > max {
+                    fail(format!("must be at most {}", max));
+                }
+            }
+        }
+        Value::Array(items) => {
+            if let Some(item_schema) = schema.get("items") {
+                for (idx, item) in items.iter().enumerate() {
+                    validate_at(item_schema, item, &format!("{}[{}]", field, idx), errors);
+                }
+            }
+        }
+        Value::Object(map) => validate_object(schema, map, field, errors),
+        _ => {}
+    }
+}
+
+fn validate_object(
+    schema: &Value,
+    map: &Map<String, Value>,
+    field: &str,
+    errors: &mut Vec<SchemaError>,
+) {
+    let child = |key: &str| {
+        if field.is_empty() {
+            key.to_string()
+        } else {
+            format!("{}.{}", field, key)
+        }
+    };
+    let properties = schema.get("properties").and_then(Value::as_object);
+
+    for required in schema
+        .get("required")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(Value::as_str)
+    {
+        if !map.contains_key(required) {
+            errors.push(SchemaError {
+                field: child(required),
+                message: "is required".to_string(),
+            });
+        }
+    }
+
+    for (key, value) in map {
+        match properties.and_then(|p| p.get(key)) {
+            Some(property) => validate_at(property, value, &child(key), errors),
+            None => match schema.get("additionalProperties") {
+                Some(Value::Bool(false)) => errors.push(SchemaError {
+                    field: child(key),
+                    message: "is not a known field".to_string(),
+                }),
+                Some(extra @ Value::Object(_)) => validate_at(extra, value, &child(key), errors),
+                _ => {}
+            },
+        }
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(n) if n.is_i64() || n.is_u64() => "integer",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::FilesystemBackend;
+    use std::fs;
+    use crate::arf::{ArfFile, ArfSource};
+    use chrono::Utc;
+    use crate::rules::{PatternRule, RuleKind};
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_serialized_arf_is_valid() {
+        let mut arf = ArfFile::new("Use PgBouncer", "Connection limits", "Transaction pooling");
+        arf.approved = true;
+        arf.tags = vec!["database".to_string()];
+        arf.confidence = Some(1.0);
+        arf.category = Some("decisions".to_string());
+        arf.source = Some(ArfSource::Manual);
+        arf.created_at = Some(Utc::now());
+        arf.updated_at = arf.created_at;
+        arf.rules.push(PatternRule {
+            kind: RuleKind::Forbid,
+            pattern: "^use std::sync::Mutex".to_string(),
+            when: None,
+            paths: vec!["src/**/*.rs".to_string()],
+            message: None,
+        });
+        arf.add_file("config/pgbouncer.ini");
+        arf.add_issue("#42");
+        arf.add_outcome("result", "success");
+        arf.context.provenance.models = vec!["claude".to_string(), "gemini".to_string()];
+        arf.context
+            .provenance
+            .fields
+            .insert("what".to_string(), vec!["claude".to_string()]);
+
+        let contents = toml::to_string_pretty(&arf).unwrap();
+        assert_eq!(validate_arf_toml(&contents).unwrap(), Vec::new());
+    }
+
+    #[test]
+    fn test_reports_hand_written_mistakes() {
+        let contents = r#"
+what = "Retry payments"
+why = ""
+tags = "billing"
+confidence = 2
+file = ["src/pay.rs"]
+
+[[rules]]
+kind = "ban"
+pattern = "x"
+
+[context]
+severity = "high"
+"#;
+        let errors = validate_arf_toml(contents).unwrap();
+        let fields: Vec<&str> = errors.iter().map(|e| e.field.as_str()).collect();
+
+        assert!(fields.contains(&"how"));
+        assert!(fields.contains(&"why"));
+        assert!(fields.contains(&"tags"));
+        assert!(fields.contains(&"confidence"));
+        assert!(fields.contains(&"rules[0].kind"));
+        assert!(fields.contains(&"file"));
+        assert_eq!(errors.len(), 6);
+    }
+
+    #[test]
+    fn test_check_knowledge_base_reports_unparseable_files() {
+        let temp_dir = TempDir::new().unwrap();
+        let noggin = temp_dir.path();
+        fs::create_dir_all(noggin.join("decisions")).unwrap();
+        ArfFile::new("What", "Why", "How")
+            .to_toml(&noggin.join("decisions/good.arf"))
+            .unwrap();
+        fs::write(noggin.join("decisions/broken.arf"), "what = [").unwrap();
+
+        let violations = check_knowledge_base(noggin, &FilesystemBackend::new(noggin)).unwrap();
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].arf, "decisions/broken.arf");
+    }
+
+    #[test]
+    fn test_check_knowledge_base_enforces_templates() {
+        let temp_dir = TempDir::new().unwrap();
+        let noggin = temp_dir.path();
+        fs::create_dir_all(noggin.join("templates")).unwrap();
+        fs::write(
+            noggin.join("templates/migrations.toml"),
+            "[fields]\nrollback = \"How to undo it\"\n",
+        )
+        .unwrap();
+        let mut done = ArfFile::new("Move to Postgres", "Scale", "pgloader");
+        done.context
+            .extra
+            .insert("rollback".to_string(), toml::Value::String("Restore the dump".to_string()));
+        done.to_toml(&noggin.join("migrations/done.arf")).unwrap();
+        ArfFile::new("Split users table", "Size", "Online migration")
+            .to_toml(&noggin.join("migrations/todo.arf"))
+            .unwrap();
+
+        let violations = check_knowledge_base(noggin, &FilesystemBackend::new(noggin)).unwrap();
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].arf, "migrations/todo.arf");
+        assert_eq!(violations[0].field, "context.rollback");
+        assert!(violations[0].message.contains("templates/migrations.toml"));
+    }
+}
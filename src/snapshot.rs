@@ -0,0 +1,375 @@
+//! Named snapshots of the knowledge base, for comparing releases
+//!
+//! `noggin snapshot v1.4` records a content hash (and the title) of every
+//! ARF in `.noggin/snapshots/v1.4.toml`, along with the commit it was taken
+//! at. `noggin diff --snapshot v1.4` then lists entries added, removed,
+//! or changed since.
+//!
+//! Hashes say *that* an entry changed, not how. When `.noggin/` is
+//! committed, a snapshot can also tag the commit (`noggin/<name>`); the
+//! diff then reads the old entries from the tag and shows which of
+//! what/why/how changed.
+
+use crate::arf::ArfFile;
+use crate::storage::KnowledgeBackend;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use git2::{Repository, Status, StatusOptions};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Directory under `.noggin/` snapshots are kept in
+pub const SNAPSHOT_DIR: &str = "snapshots";
+
+/// Prefix of the git tags snapshots create
+pub const TAG_PREFIX: &str = "noggin/";
+
+/// The knowledge base as of one point in time
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub name: String,
+    pub created_at: DateTime<Utc>,
+    /// HEAD when the snapshot was taken
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub commit: Option<String>,
+    /// Git tag holding the committed `.noggin/` at that point
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tag: Option<String>,
+    /// By ARF path
+    #[serde(default)]
+    pub entries: BTreeMap<String, SnapshotEntry>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SnapshotEntry {
+    /// SHA-256 of the ARF file's contents
+    pub hash: String,
+    pub what: String,
+}
+
+impl Snapshot {
+    /// Record every ARF `backend` holds now
+    pub fn capture(name: &str, backend: &dyn KnowledgeBackend) -> Result<Self> {
+        let mut entries = BTreeMap::new();
+        for path in backend.list_arfs()? {
+            let Some(contents) = backend.read(&path)? else {
+                continue;
+            };
+            let what = toml::from_str::<ArfFile>(&contents)
+                .map(|arf| arf.what)
+                .unwrap_or_default();
+            entries.insert(
+                path,
+                SnapshotEntry {
+                    hash: format!("{:x}", Sha256::digest(contents.as_bytes())),
+                    what,
+                },
+            );
+        }
+        Ok(Self {
+            name: name.to_string(),
+            created_at: Utc::now(),
+            commit: None,
+            tag: None,
+            entries,
+        })
+    }
+
+    pub fn load(noggin_path: &Path, name: &str) -> Result<Self> {
+        let path = snapshot_path(noggin_path, name);
+        if !path.exists() {
+            anyhow::bail!(
+                "No snapshot named '{}'. Available: {}",
+                name,
+                list(noggin_path)?.join(", ")
+            );
+        }
+        let contents = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        toml::from_str(&contents).with_context(|| format!("Failed to parse {}", path.display()))
+    }
+
+    pub fn save(&self, noggin_path: &Path) -> Result<()> {
+        let path = snapshot_path(noggin_path, &self.name);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create directory: {}", parent.display()))?;
+        }
+        let contents = toml::to_string_pretty(self).context("Failed to serialize snapshot")?;
+        fs::write(&path, contents).with_context(|| format!("Failed to write {}", path.display()))
+    }
+}
+
+/// Where the snapshot called `name` is stored
+pub fn snapshot_path(noggin_path: &Path, name: &str) -> PathBuf {
+    noggin_path.join(SNAPSHOT_DIR).join(format!("{}.toml", name))
+}
+
+/// Snapshot names must be usable as a file name and a tag
+pub fn validate_name(name: &str) -> Result<()> {
+    let valid = !name.is_empty()
+        && !name.starts_with('.')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_'));
+    if !valid {
+        anyhow::bail!(
+            "Invalid snapshot name '{}': use letters, digits, '.', '-', and '_'",
+            name
+        );
+    }
+    Ok(())
+}
+
+/// Names of the recorded snapshots, sorted
+pub fn list(noggin_path: &Path) -> Result<Vec<String>> {
+    let dir = noggin_path.join(SNAPSHOT_DIR);
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+    let mut names: Vec<String> = fs::read_dir(&dir)
+        .with_context(|| format!("Failed to read {}", dir.display()))?
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let path = entry.path();
+            if path.extension()? != "toml" {
+                return None;
+            }
+            Some(path.file_stem()?.to_string_lossy().into_owned())
+        })
+        .collect();
+    names.sort();
+    Ok(names)
+}
+
+/// Tag HEAD as `noggin/<name>`, provided every ARF in `snapshot` is
+/// committed as it is now, replacing an existing tag with `force`.
+/// Returns the tag name.
+pub fn tag_snapshot(repo: &Repository, snapshot: &Snapshot, force: bool) -> Result<String> {
+    let mut opts = StatusOptions::new();
+    opts.include_untracked(true).recurse_untracked_dirs(true);
+    let statuses = repo.statuses(Some(&mut opts)).context("Failed to read git status")?;
+    let uncommitted = statuses.iter().find(|status| {
+        status.status() != Status::CURRENT
+            && !status.status().contains(Status::IGNORED)
+            && status
+                .path()
+                .and_then(|p| p.strip_prefix(".noggin/"))
+                .is_some_and(|p| snapshot.entries.contains_key(p))
+    });
+    if let Some(status) = uncommitted {
+        anyhow::bail!(
+            "{} has uncommitted changes; commit .noggin/ before tagging a snapshot",
+            status.path().unwrap_or_default()
+        );
+    }
+    let head = repo
+        .head()
+        .and_then(|h| h.peel_to_commit())
+        .context("Failed to resolve HEAD")?;
+    let all_committed = snapshot.entries.keys().all(|path| {
+        head.tree()
+            .and_then(|tree| tree.get_path(Path::new(".noggin").join(path).as_path()))
+            .is_ok()
+    });
+    if !all_committed {
+        anyhow::bail!("The knowledge base isn't committed; commit .noggin/ before tagging a snapshot");
+    }
+
+    let tag = format!("{}{}", TAG_PREFIX, snapshot.name);
+    repo.tag_lightweight(&tag, head.as_object(), force)
+        .with_context(|| format!("Failed to create tag '{}'", tag))?;
+    Ok(tag)
+}
+
+/// How one entry changed between two snapshots
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct EntryChange {
+    pub path: String,
+    pub what: String,
+    /// Fields whose text changed, when both versions could be read
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub fields: Vec<FieldChange>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FieldChange {
+    pub field: String,
+    pub before: String,
+    pub after: String,
+}
+
+/// Entries that differ between two snapshots
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SnapshotDiff {
+    pub from: String,
+    pub to: String,
+    pub added: Vec<EntryChange>,
+    pub removed: Vec<EntryChange>,
+    pub changed: Vec<EntryChange>,
+}
+
+impl SnapshotDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// Compare two snapshots. `read_before` and `read_after` return the full
+/// entry at a path, if it can still be read, to describe changed fields.
+pub fn diff_snapshots(
+    from: &Snapshot,
+    to: &Snapshot,
+    read_before: impl Fn(&str) -> Option<ArfFile>,
+    read_after: impl Fn(&str) -> Option<ArfFile>,
+) -> SnapshotDiff {
+    let entry = |path: &str, entry: &SnapshotEntry| EntryChange {
+        path: path.to_string(),
+        what: entry.what.clone(),
+        fields: Vec::new(),
+    };
+
+    let mut diff = SnapshotDiff {
+        from: from.name.clone(),
+        to: to.name.clone(),
+        ..Default::default()
+    };
+    for (path, after) in &to.entries {
+        match from.entries.get(path) {
+            None => diff.added.push(entry(path, after)),
+            Some(before) if before.hash != after.hash => {
+                let mut change = entry(path, after);
+                if let (Some(old), Some(new)) = (read_before(path), read_after(path)) {
+                    change.fields = changed_fields(&old, &new);
+                }
+                diff.changed.push(change);
+            }
+            Some(_) => {}
+        }
+    }
+    for (path, before) in &from.entries {
+        if !to.entries.contains_key(path) {
+            diff.removed.push(entry(path, before));
+        }
+    }
+    diff
+}
+
+fn changed_fields(old: &ArfFile, new: &ArfFile) -> Vec<FieldChange> {
+    [
+        ("what", &old.what, &new.what),
+        ("why", &old.why, &new.why),
+        ("how", &old.how, &new.how),
+    ]
+    .into_iter()
+    .filter(|(_, before, after)| before != after)
+    .map(|(field, before, after)| FieldChange {
+        field: field.to_string(),
+        before: before.clone(),
+        after: after.clone(),
+    })
+    .collect()
+}
+
+/// The ARF at `path` under `.noggin/` in the tree `tag` points at
+pub fn read_tagged_arf(repo: &Repository, tag: &str, path: &str) -> Option<ArfFile> {
+    let tree = repo
+        .revparse_single(&format!("refs/tags/{}", tag))
+        .and_then(|object| object.peel_to_tree())
+        .ok()?;
+    let entry = tree.get_path(&Path::new(".noggin").join(path)).ok()?;
+    let blob = entry.to_object(repo).ok()?.peel_to_blob().ok()?;
+    toml::from_str(std::str::from_utf8(blob.content()).ok()?).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::MemoryBackend;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_snapshot_round_trip_and_diff() {
+        let temp_dir = TempDir::new().unwrap();
+        let backend = MemoryBackend::default();
+        let pool = ArfFile::new("Use pooling", "Limits connections", "deadpool");
+        backend.write_arf("decisions/pool.arf", &pool).unwrap();
+        backend
+            .write_arf("bugs/retry.arf", &ArfFile::new("Retry storms", "Why", "How"))
+            .unwrap();
+
+        let v1 = Snapshot::capture("v1.0", &backend).unwrap();
+        v1.save(temp_dir.path()).unwrap();
+        assert_eq!(Snapshot::load(temp_dir.path(), "v1.0").unwrap(), v1);
+        assert_eq!(list(temp_dir.path()).unwrap(), vec!["v1.0"]);
+        assert!(Snapshot::load(temp_dir.path(), "v9").is_err());
+
+        let mut pool_v2 = pool.clone();
+        pool_v2.why = "Caps connections per worker".to_string();
+        backend.write_arf("decisions/pool.arf", &pool_v2).unwrap();
+        backend.remove("bugs/retry.arf").unwrap();
+        backend
+            .write_arf("facts/msrv.arf", &ArfFile::new("MSRV is 1.80", "Why", "How"))
+            .unwrap();
+        let now = Snapshot::capture("working tree", &backend).unwrap();
+
+        let diff = diff_snapshots(&v1, &now, |_| Some(pool.clone()), |_| Some(pool_v2.clone()));
+        assert_eq!(diff.added[0].path, "facts/msrv.arf");
+        assert_eq!(diff.removed[0].what, "Retry storms");
+        assert_eq!(diff.changed.len(), 1);
+        assert_eq!(diff.changed[0].fields.len(), 1);
+        assert_eq!(diff.changed[0].fields[0].field, "why");
+        assert!(diff_snapshots(&now, &now, |_| None, |_| None).is_empty());
+    }
+
+    #[test]
+    fn test_forced_tag_still_requires_committed_knowledge() {
+        let temp_dir = TempDir::new().unwrap();
+        let repo = Repository::init(temp_dir.path()).unwrap();
+        let backend = crate::storage::FilesystemBackend::new(&temp_dir.path().join(".noggin"));
+        let commit_all = |message: &str| {
+            let mut index = repo.index().unwrap();
+            index.add_all(["*"], git2::IndexAddOption::DEFAULT, None).unwrap();
+            index.write().unwrap();
+            let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+            let sig = git2::Signature::now("Test", "test@example.com").unwrap();
+            let parent = repo.head().ok().and_then(|h| h.peel_to_commit().ok());
+            repo.commit(Some("HEAD"), &sig, &sig, message, &tree, &parent.iter().collect::<Vec<_>>())
+                .unwrap()
+        };
+        let tagged = |repo: &Repository| {
+            repo.find_reference("refs/tags/noggin/v1").unwrap().target().unwrap()
+        };
+
+        let pool = ArfFile::new("Use pooling", "Limits connections", "deadpool");
+        backend.write_arf("decisions/pool.arf", &pool).unwrap();
+        let first = commit_all("first");
+        let snapshot = Snapshot::capture("v1", &backend).unwrap();
+        assert_eq!(tag_snapshot(&repo, &snapshot, false).unwrap(), "noggin/v1");
+
+        let mut pool_v2 = pool.clone();
+        pool_v2.why = "Caps connections per worker".to_string();
+        backend.write_arf("decisions/pool.arf", &pool_v2).unwrap();
+        let snapshot = Snapshot::capture("v1", &backend).unwrap();
+        let err = tag_snapshot(&repo, &snapshot, true).unwrap_err();
+        assert!(err.to_string().contains("uncommitted"));
+        assert_eq!(tagged(&repo), first);
+
+        let second = commit_all("second");
+        assert!(tag_snapshot(&repo, &snapshot, false).is_err());
+        tag_snapshot(&repo, &snapshot, true).unwrap();
+        assert_eq!(tagged(&repo), second);
+    }
+
+    #[test]
+    fn test_validate_name() {
+        assert!(validate_name("v1.4").is_ok());
+        assert!(validate_name("release_2024-03").is_ok());
+        assert!(validate_name("").is_err());
+        assert!(validate_name("../x").is_err());
+        assert!(validate_name(".hidden").is_err());
+    }
+}
@@ -0,0 +1,249 @@
+//! Dry runs at the storage layer
+//!
+//! Commands that change the knowledge base take `--dry-run` by writing
+//! through a [`DryRunBackend`] instead of the real backend: reads see the
+//! changes made so far, nothing reaches the wrapped backend, and
+//! [`DryRunBackend::planned`] lists what a real run would have written.
+//! Outputs outside the knowledge base (exports, bundles) are planned with
+//! [`plan_file`].
+
+use super::KnowledgeBackend;
+use crate::manifest::Manifest;
+use crate::text::line_diff;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::Mutex;
+
+/// A change a dry run found but did not make
+#[derive(Debug, Clone, Serialize)]
+pub struct PlannedWrite {
+    /// Path relative to .noggin/, or to the repository for outputs
+    /// outside the knowledge base
+    pub path: String,
+    pub action: WriteAction,
+    /// Changed lines (`-`/`+`) of the file's text
+    pub diff: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WriteAction {
+    Create,
+    Update,
+    Remove,
+    /// A conflict record instead of overwriting an approved ARF
+    Conflict,
+    /// A new entry held in `pending/` by the per-run cap or for missing
+    /// template fields
+    Pending,
+}
+
+impl std::fmt::Display for WriteAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let label = match self {
+            WriteAction::Create => "create",
+            WriteAction::Update => "update",
+            WriteAction::Remove => "remove",
+            WriteAction::Conflict => "conflict",
+            WriteAction::Pending => "pending",
+        };
+        f.write_str(label)
+    }
+}
+
+/// Path the manifest is reported under
+const MANIFEST_PATH: &str = "manifest.toml";
+
+/// A backend that records writes instead of making them
+pub struct DryRunBackend<'a> {
+    inner: &'a dyn KnowledgeBackend,
+    /// Contents written by path; None where the path was removed
+    overlay: Mutex<BTreeMap<String, Option<String>>>,
+    /// The manifest as saved, serialized
+    manifest: Mutex<Option<String>>,
+}
+
+impl<'a> DryRunBackend<'a> {
+    pub fn new(inner: &'a dyn KnowledgeBackend) -> Self {
+        Self {
+            inner,
+            overlay: Mutex::new(BTreeMap::new()),
+            manifest: Mutex::new(None),
+        }
+    }
+
+    /// What the run would have changed in the wrapped backend, manifest
+    /// last; writes that leave a path as it was are left out
+    pub fn planned(&self) -> Result<Vec<PlannedWrite>> {
+        let mut planned = Vec::new();
+        for (path, contents) in self.overlay.lock().unwrap().iter() {
+            let old = self.inner.read(path)?;
+            if let Some(write) = plan_change(path, old.as_deref(), contents.as_deref()) {
+                planned.push(write);
+            }
+        }
+        if let Some(contents) = &*self.manifest.lock().unwrap() {
+            let old = toml::to_string_pretty(&self.inner.load_manifest()?)
+                .context("Failed to serialize manifest to TOML")?;
+            if let Some(write) = plan_change(MANIFEST_PATH, Some(&old), Some(contents)) {
+                planned.push(write);
+            }
+        }
+        Ok(planned)
+    }
+}
+
+impl KnowledgeBackend for DryRunBackend<'_> {
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    fn load_manifest(&self) -> Result<Manifest> {
+        match &*self.manifest.lock().unwrap() {
+            Some(contents) => toml::from_str(contents).context("Failed to parse manifest"),
+            None => self.inner.load_manifest(),
+        }
+    }
+
+    fn save_manifest(&self, manifest: &Manifest) -> Result<()> {
+        let contents =
+            toml::to_string_pretty(manifest).context("Failed to serialize manifest to TOML")?;
+        *self.manifest.lock().unwrap() = Some(contents);
+        Ok(())
+    }
+
+    fn list_arfs(&self) -> Result<Vec<String>> {
+        let overlay = self.overlay.lock().unwrap();
+        let mut paths: Vec<String> = self
+            .inner
+            .list_arfs()?
+            .into_iter()
+            .filter(|path| !matches!(overlay.get(path), Some(None)))
+            .collect();
+        for (path, contents) in overlay.iter() {
+            if contents.is_some() && super::is_knowledge_arf(path) && !paths.contains(path) {
+                paths.push(path.clone());
+            }
+        }
+        paths.sort();
+        Ok(paths)
+    }
+
+    fn read(&self, path: &str) -> Result<Option<String>> {
+        match self.overlay.lock().unwrap().get(path) {
+            Some(contents) => Ok(contents.clone()),
+            None => self.inner.read(path),
+        }
+    }
+
+    fn write(&self, path: &str, contents: &str) -> Result<()> {
+        self.overlay
+            .lock()
+            .unwrap()
+            .insert(path.to_string(), Some(contents.to_string()));
+        Ok(())
+    }
+
+    fn remove(&self, path: &str) -> Result<()> {
+        self.overlay.lock().unwrap().insert(path.to_string(), None);
+        Ok(())
+    }
+
+    fn modified(&self, path: &str) -> Option<DateTime<Utc>> {
+        match self.overlay.lock().unwrap().get(path) {
+            Some(Some(_)) => Some(Utc::now()),
+            Some(None) => None,
+            None => self.inner.modified(path),
+        }
+    }
+}
+
+/// The change from `old` to `new` at `path`, or None if there is none
+fn plan_change(path: &str, old: Option<&str>, new: Option<&str>) -> Option<PlannedWrite> {
+    let (action, diff) = match (old, new) {
+        (None, None) => return None,
+        (Some(old), Some(new)) if old == new => return None,
+        (None, Some(new)) => (WriteAction::Create, line_diff("", new)),
+        (Some(old), Some(new)) => (WriteAction::Update, line_diff(old, new)),
+        (Some(old), None) => (WriteAction::Remove, line_diff(old, "")),
+    };
+    Some(PlannedWrite {
+        path: path.to_string(),
+        action,
+        diff,
+    })
+}
+
+/// Plan writing `contents` to `path` on disk, reported as `display`;
+/// binary contents get no diff
+pub fn plan_file(path: &Path, display: &str, contents: &[u8]) -> PlannedWrite {
+    let old = std::fs::read(path).ok();
+    let action = if old.is_some() {
+        WriteAction::Update
+    } else {
+        WriteAction::Create
+    };
+    let diff = match std::str::from_utf8(contents) {
+        Ok(new) => {
+            let old = old.as_deref().and_then(|old| std::str::from_utf8(old).ok());
+            line_diff(old.unwrap_or(""), new)
+        }
+        Err(_) => String::new(),
+    };
+    PlannedWrite {
+        path: display.to_string(),
+        action,
+        diff,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::arf::ArfFile;
+    use crate::storage::MemoryBackend;
+
+    #[test]
+    fn test_dry_run_leaves_backend_untouched() {
+        let inner = MemoryBackend::default();
+        let arf = ArfFile::new("Use pooling", "Limits connections", "deadpool");
+        inner.write_arf("decisions/pool.arf", &arf).unwrap();
+        inner.write_arf("bugs/timeout.arf", &arf).unwrap();
+
+        let dry_run = DryRunBackend::new(&inner);
+        dry_run.remove("decisions/pool.arf").unwrap();
+        dry_run.write_arf("facts/new.arf", &arf).unwrap();
+        dry_run.write_arf("bugs/timeout.arf", &arf).unwrap();
+        let mut manifest = dry_run.load_manifest().unwrap();
+        manifest.add_or_update_file("src/db.rs".to_string(), "abc".to_string(), vec![]);
+        dry_run.save_manifest(&manifest).unwrap();
+
+        assert_eq!(
+            dry_run.list_arfs().unwrap(),
+            vec!["bugs/timeout.arf", "facts/new.arf"]
+        );
+        assert!(dry_run.load_manifest().unwrap().files.contains_key("src/db.rs"));
+
+        let planned: Vec<(String, WriteAction)> = dry_run
+            .planned()
+            .unwrap()
+            .into_iter()
+            .map(|write| (write.path, write.action))
+            .collect();
+        assert_eq!(
+            planned,
+            vec![
+                ("decisions/pool.arf".to_string(), WriteAction::Remove),
+                ("facts/new.arf".to_string(), WriteAction::Create),
+                ("manifest.toml".to_string(), WriteAction::Update),
+            ]
+        );
+
+        assert!(inner.exists("decisions/pool.arf"));
+        assert!(!inner.exists("facts/new.arf"));
+        assert!(inner.load_manifest().unwrap().files.is_empty());
+    }
+}
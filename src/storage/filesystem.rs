@@ -0,0 +1,87 @@
+//! The default backend: files under `.noggin/`
+
+use super::KnowledgeBackend;
+use crate::arf::arf_paths;
+use crate::manifest::Manifest;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+const MANIFEST_FILE: &str = "manifest.toml";
+
+/// Knowledge base stored as files under a `.noggin/` directory
+#[derive(Debug, Clone)]
+pub struct FilesystemBackend {
+    root: PathBuf,
+}
+
+impl FilesystemBackend {
+    pub fn new(noggin_path: &Path) -> Self {
+        Self {
+            root: noggin_path.to_path_buf(),
+        }
+    }
+}
+
+impl KnowledgeBackend for FilesystemBackend {
+    fn name(&self) -> &str {
+        "filesystem"
+    }
+
+    fn load_manifest(&self) -> Result<Manifest> {
+        Manifest::load(&self.root.join(MANIFEST_FILE))
+    }
+
+    fn save_manifest(&self, manifest: &Manifest) -> Result<()> {
+        manifest.save(&self.root.join(MANIFEST_FILE))
+    }
+
+    fn list_arfs(&self) -> Result<Vec<String>> {
+        Ok(arf_paths(&self.root)
+            .iter()
+            .filter_map(|path| path.strip_prefix(&self.root).ok())
+            .map(|rel| rel.to_string_lossy().replace('\\', "/"))
+            .collect())
+    }
+
+    fn read(&self, path: &str) -> Result<Option<String>> {
+        let full = self.root.join(path);
+        match fs::read_to_string(&full) {
+            Ok(contents) => Ok(Some(contents)),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e).with_context(|| format!("Failed to read {}", full.display())),
+        }
+    }
+
+    fn write(&self, path: &str, contents: &str) -> Result<()> {
+        let full = self.root.join(path);
+        if let Some(parent) = full.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create directory: {}", parent.display()))?;
+        }
+        fs::write(&full, contents).with_context(|| format!("Failed to write {}", full.display()))
+    }
+
+    fn remove(&self, path: &str) -> Result<()> {
+        let full = self.root.join(path);
+        match fs::remove_file(&full) {
+            Err(e) if e.kind() != ErrorKind::NotFound => {
+                Err(e).with_context(|| format!("Failed to remove {}", full.display()))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn modified(&self, path: &str) -> Option<DateTime<Utc>> {
+        fs::metadata(self.root.join(path))
+            .and_then(|m| m.modified())
+            .ok()
+            .map(DateTime::<Utc>::from)
+    }
+
+    fn exists(&self, path: &str) -> bool {
+        self.root.join(path).is_file()
+    }
+}
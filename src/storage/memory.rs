@@ -0,0 +1,69 @@
+//! A knowledge base held in memory, for tests
+
+use super::{is_knowledge_arf, KnowledgeBackend};
+use crate::manifest::Manifest;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+/// Knowledge base that lives only as long as the value
+#[derive(Debug, Default)]
+pub struct MemoryBackend {
+    /// Stored contents and when they were written, by path
+    files: Mutex<BTreeMap<String, (String, DateTime<Utc>)>>,
+    /// The manifest as saved, serialized so loads return a fresh copy
+    manifest: Mutex<Option<String>>,
+}
+
+impl KnowledgeBackend for MemoryBackend {
+    fn name(&self) -> &str {
+        "memory"
+    }
+
+    fn load_manifest(&self) -> Result<Manifest> {
+        match &*self.manifest.lock().unwrap() {
+            Some(contents) => toml::from_str(contents).context("Failed to parse manifest"),
+            None => Ok(Manifest::default()),
+        }
+    }
+
+    fn save_manifest(&self, manifest: &Manifest) -> Result<()> {
+        let contents =
+            toml::to_string_pretty(manifest).context("Failed to serialize manifest to TOML")?;
+        *self.manifest.lock().unwrap() = Some(contents);
+        Ok(())
+    }
+
+    fn list_arfs(&self) -> Result<Vec<String>> {
+        Ok(self
+            .files
+            .lock()
+            .unwrap()
+            .keys()
+            .filter(|path| is_knowledge_arf(path))
+            .cloned()
+            .collect())
+    }
+
+    fn read(&self, path: &str) -> Result<Option<String>> {
+        Ok(self.files.lock().unwrap().get(path).map(|(contents, _)| contents.clone()))
+    }
+
+    fn write(&self, path: &str, contents: &str) -> Result<()> {
+        self.files
+            .lock()
+            .unwrap()
+            .insert(path.to_string(), (contents.to_string(), Utc::now()));
+        Ok(())
+    }
+
+    fn remove(&self, path: &str) -> Result<()> {
+        self.files.lock().unwrap().remove(path);
+        Ok(())
+    }
+
+    fn modified(&self, path: &str) -> Option<DateTime<Utc>> {
+        self.files.lock().unwrap().get(path).map(|(_, at)| *at)
+    }
+}
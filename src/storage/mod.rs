@@ -0,0 +1,155 @@
+//! Where the knowledge base is persisted.
+//!
+//! ARF entries and the manifest are read and written through a
+//! [`KnowledgeBackend`], chosen by `[storage] backend` in config. Paths are
+//! relative to the knowledge base and use `/`, e.g. `decisions/use-pooling.arf`.
+//!
+//! - [`FilesystemBackend`] (`filesystem`, the default) keeps them as files
+//!   under `.noggin/`.
+//! - `MemoryBackend` keeps them in memory. It is only built for tests and
+//!   can't be selected in config, since nothing would survive the run.
+//!
+//! - [`dry_run::DryRunBackend`] wraps another backend for `--dry-run`,
+//!   recording writes instead of making them.
+//!
+//! Derived and local data (the search index, raw responses, reports,
+//! conflict records, the lock) always stays under `.noggin/`.
+
+pub mod dry_run;
+pub mod filesystem;
+#[cfg(test)]
+mod memory;
+
+pub use filesystem::FilesystemBackend;
+#[cfg(test)]
+pub(crate) use memory::MemoryBackend;
+
+use crate::arf::{ArfFile, NESTED_ARF_DIRS};
+use crate::config::StorageConfig;
+use crate::manifest::Manifest;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Backend named by `[storage] backend`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum StorageBackend {
+    /// Files under `.noggin/`
+    #[default]
+    Filesystem,
+}
+
+/// Persistence for ARF entries and the manifest
+pub trait KnowledgeBackend: Send + Sync {
+    /// Backend name, for messages
+    fn name(&self) -> &str;
+
+    /// The manifest, or an empty one if none was saved yet
+    fn load_manifest(&self) -> Result<Manifest>;
+
+    fn save_manifest(&self, manifest: &Manifest) -> Result<()>;
+
+    /// Every ARF in the knowledge base (not `pending/`), sorted
+    fn list_arfs(&self) -> Result<Vec<String>>;
+
+    /// Contents stored at `path`, or None if there are none
+    fn read(&self, path: &str) -> Result<Option<String>>;
+
+    /// Store `contents` at `path`, replacing what was there
+    fn write(&self, path: &str, contents: &str) -> Result<()>;
+
+    /// Remove `path`; removing a missing path is not an error
+    fn remove(&self, path: &str) -> Result<()>;
+
+    /// When `path` was last written
+    fn modified(&self, path: &str) -> Option<DateTime<Utc>>;
+
+    fn exists(&self, path: &str) -> bool {
+        matches!(self.read(path), Ok(Some(_)))
+    }
+
+    /// The ARF at `path`, or None if there is none
+    fn read_arf(&self, path: &str) -> Result<Option<ArfFile>> {
+        let Some(contents) = self.read(path)? else {
+            return Ok(None);
+        };
+        let arf = toml::from_str(&contents)
+            .with_context(|| format!("Failed to parse TOML in: {}", path))?;
+        Ok(Some(arf))
+    }
+
+    fn write_arf(&self, path: &str, arf: &ArfFile) -> Result<()> {
+        let contents =
+            toml::to_string_pretty(arf).context("Failed to serialize ARF file to TOML")?;
+        self.write(path, &contents)
+    }
+}
+
+/// The backend `config` selects for the knowledge base at `noggin_path`
+pub fn open(noggin_path: &Path, config: &StorageConfig) -> Box<dyn KnowledgeBackend> {
+    match config.backend {
+        StorageBackend::Filesystem => Box::new(FilesystemBackend::new(noggin_path)),
+    }
+}
+
+/// Whether `path` is a knowledge base ARF, by the rule
+/// [`arf_paths`](crate::arf::arf_paths) applies on disk
+pub(crate) fn is_knowledge_arf(path: &str) -> bool {
+    let Some((dir, name)) = path.rsplit_once('/') else {
+        return false;
+    };
+    name.ends_with(".arf") && (!dir.contains('/') || NESTED_ARF_DIRS.contains(&dir))
+}
+
+/// Whether `path` is directly in `dir`, not in a directory under it
+/// (e.g. `facts/glossary/` under `facts`)
+pub(crate) fn in_dir(path: &str, dir: &str) -> bool {
+    path.strip_prefix(dir)
+        .and_then(|rest| rest.strip_prefix('/'))
+        .is_some_and(|name| !name.contains('/'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    /// Both backends behave the same through the trait
+    fn exercise(backend: &dyn KnowledgeBackend) {
+        assert!(backend.list_arfs().unwrap().is_empty());
+        assert!(backend.read_arf("decisions/pool.arf").unwrap().is_none());
+
+        let arf = ArfFile::new("Use pooling", "Limits connections", "deadpool");
+        backend.write_arf("decisions/pool.arf", &arf).unwrap();
+        backend.write_arf("facts/glossary/arf.arf", &arf).unwrap();
+        backend.write_arf("pending/decisions/held.arf", &arf).unwrap();
+        assert_eq!(
+            backend.list_arfs().unwrap(),
+            vec!["decisions/pool.arf", "facts/glossary/arf.arf"]
+        );
+        assert_eq!(backend.read_arf("decisions/pool.arf").unwrap(), Some(arf));
+        assert!(backend.modified("decisions/pool.arf").is_some());
+
+        backend.remove("decisions/pool.arf").unwrap();
+        backend.remove("decisions/pool.arf").unwrap();
+        assert!(!backend.exists("decisions/pool.arf"));
+
+        let mut manifest = backend.load_manifest().unwrap();
+        assert!(manifest.files.is_empty());
+        manifest.add_or_update_file("src/db.rs".to_string(), "abc".to_string(), vec![]);
+        backend.save_manifest(&manifest).unwrap();
+        assert!(backend.load_manifest().unwrap().files.contains_key("src/db.rs"));
+    }
+
+    #[test]
+    fn test_backends_round_trip() {
+        let temp_dir = TempDir::new().unwrap();
+        exercise(&FilesystemBackend::new(temp_dir.path()));
+        assert!(temp_dir.path().join("facts/glossary/arf.arf").is_file());
+        assert!(temp_dir.path().join("manifest.toml").is_file());
+
+        exercise(&MemoryBackend::default());
+    }
+}
@@ -0,0 +1,117 @@
+//! Confidence scores for synthesized entries
+//!
+//! An entry is more trustworthy when several models produced it
+//! independently, when they agreed on its fields (or one value won the
+//! vote clearly), and when more than one near-duplicate backs it up.
+//! [`score`] weighs those three signals; a confidence the models reported
+//! themselves is averaged in by [`blend`].
+
+use super::conflict::FieldConflict;
+use super::vote::{model_weight, resolve_conflict, Resolution};
+
+/// Share of the score from the fraction of models that produced the entry
+const AGREEMENT_WEIGHT: f64 = 0.5;
+/// Share of the score from how clearly conflicting fields were decided
+const MARGIN_WEIGHT: f64 = 0.3;
+/// Share of the score from the number of entries merged into this one
+const SIZE_WEIGHT: f64 = 0.2;
+/// Cluster size at which the size signal is saturated
+const FULL_CLUSTER: usize = 3;
+
+/// Confidence of an entry merged from `members` outputs of `models`
+/// distinct models, when `answered` models took part in the run and the
+/// field conflicts were decided with `margin` (see [`vote_margin`]).
+///
+/// A single answering model counts as half agreement: there was nobody to
+/// corroborate it.
+pub fn score(models: usize, answered: usize, members: usize, margin: f64) -> f64 {
+    let agreement = models as f64 / answered.max(2) as f64;
+    let size = members.min(FULL_CLUSTER) as f64 / FULL_CLUSTER as f64;
+    let raw = AGREEMENT_WEIGHT * agreement.min(1.0)
+        + MARGIN_WEIGHT * margin.clamp(0.0, 1.0)
+        + SIZE_WEIGHT * size;
+    round(raw)
+}
+
+/// How clearly the vote settled `conflicts`, from 0.0 (left for a person)
+/// to 1.0 (no conflicts, or values that merged cleanly)
+pub fn vote_margin(conflicts: &[FieldConflict]) -> f64 {
+    if conflicts.is_empty() {
+        return 1.0;
+    }
+    let total: f64 = conflicts.iter().map(conflict_margin).sum();
+    total / conflicts.len() as f64
+}
+
+/// Average `computed` with the confidence the models reported, if any
+pub fn blend(computed: f64, reported: Option<f64>) -> f64 {
+    match reported {
+        Some(reported) => round((computed + reported.clamp(0.0, 1.0)) / 2.0),
+        None => computed,
+    }
+}
+
+/// Winning share of the total vote weight
+fn conflict_margin(conflict: &FieldConflict) -> f64 {
+    let total: f64 = conflict
+        .values
+        .iter()
+        .map(|(model, _)| model_weight(model))
+        .sum();
+    if total <= 0.0 {
+        return 0.0;
+    }
+    match resolve_conflict(conflict) {
+        Resolution::MajorityVote { vote_score, .. } => vote_score / total,
+        Resolution::HighestWeight { weight, .. } => weight / total,
+        Resolution::Merged => 1.0,
+        Resolution::KeepAll => 0.0,
+    }
+}
+
+fn round(value: f64) -> f64 {
+    (value * 100.0).round() / 100.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::synthesis::conflict::ConflictKind;
+
+    fn conflict(values: &[(&str, &str)]) -> FieldConflict {
+        FieldConflict {
+            field: "how".to_string(),
+            kind: ConflictKind::DifferentValues,
+            values: values
+                .iter()
+                .map(|(m, v)| (m.to_string(), v.to_string()))
+                .collect(),
+            resolution: None,
+        }
+    }
+
+    #[test]
+    fn test_score_rewards_agreement_and_size() {
+        assert_eq!(score(3, 3, 3, 1.0), 1.0);
+        assert_eq!(score(1, 1, 1, 1.0), 0.62);
+        assert!(score(1, 3, 1, 1.0) < score(2, 3, 2, 1.0));
+        assert!(score(2, 3, 2, 0.3) < score(2, 3, 2, 1.0));
+    }
+
+    #[test]
+    fn test_vote_margin() {
+        assert_eq!(vote_margin(&[]), 1.0);
+
+        let majority = conflict(&[("claude", "A"), ("gemini", "a"), ("codex", "B")]);
+        assert!((vote_margin(&[majority]) - 2.3 / 3.3).abs() < 1e-9);
+
+        let split = conflict(&[("claude", "A"), ("codex", "B")]);
+        assert!((vote_margin(&[split]) - 1.2 / 2.2).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_blend() {
+        assert_eq!(blend(0.8, None), 0.8);
+        assert_eq!(blend(0.8, Some(0.4)), 0.6);
+    }
+}
@@ -0,0 +1,183 @@
+//! Keeping synthesis tractable on huge inputs.
+//!
+//! Clustering compares entries pairwise, so a provider that returns
+//! thousands of entries can stall a run. Two guards apply, both set in
+//! `[synthesis]`:
+//!
+//! - `max_entries` caps the entries synthesized per prompt. The most
+//!   specific ones are kept: those citing the most files and commits, then
+//!   the longest.
+//! - Above `partition_above` entries, a group is first split into
+//!   partitions of entries sharing a file, and clustering only compares
+//!   entries within a partition. Partitions are cut at
+//!   [`MAX_PARTITION`] entries, so a file (or word) many entries share
+//!   can't rebuild one huge group, and the conflicts found by merging
+//!   each cluster stay bounded too.
+//!
+//! Either one kicking in is recorded in the [`SynthesisReport`] so learn
+//! can warn that the run was degraded.
+//!
+//! [`SynthesisReport`]: super::SynthesisReport
+
+use super::merger::{self, Clustering, DisjointSets};
+use crate::arf::ArfFile;
+use std::collections::HashMap;
+
+/// Most entries a partition holds; larger ones are cut into chunks
+pub const MAX_PARTITION: usize = 256;
+
+/// How specific an entry is: files and commits cited, then text length
+fn specificity(arf: &ArfFile) -> (usize, usize) {
+    (
+        arf.context.files.len() + arf.context.commits.len(),
+        arf.what.len() + arf.why.len() + arf.how.len(),
+    )
+}
+
+/// Keep the `max` most specific entries, in their input order. Returns
+/// the kept entries and how many were dropped. `max` of 0 keeps all.
+pub fn sample(tagged: Vec<(String, ArfFile)>, max: usize) -> (Vec<(String, ArfFile)>, usize) {
+    if max == 0 || tagged.len() <= max {
+        return (tagged, 0);
+    }
+
+    let mut ranked: Vec<usize> = (0..tagged.len()).collect();
+    // Stable, so equally specific entries keep input order
+    ranked.sort_by(|&a, &b| specificity(&tagged[b].1).cmp(&specificity(&tagged[a].1)));
+    let mut keep = vec![false; tagged.len()];
+    for &i in &ranked[..max] {
+        keep[i] = true;
+    }
+
+    let dropped = tagged.len() - max;
+    let kept = tagged
+        .into_iter()
+        .zip(keep)
+        .filter_map(|(item, keep)| keep.then_some(item))
+        .collect();
+    (kept, dropped)
+}
+
+/// Split entries into partitions of entries connected by a shared file.
+/// Entries citing no file are partitioned by the longest word of `what`,
+/// which near-duplicates share far more often than they share a leading
+/// "Use" or "The". Partitions keep the order of their first member, and
+/// members keep input order; none has more than [`MAX_PARTITION`].
+pub fn partition_by_files(tagged: &[(String, ArfFile)]) -> Vec<Vec<(String, ArfFile)>> {
+    let mut sets = DisjointSets::new(tagged.len());
+    let mut first_with: HashMap<String, usize> = HashMap::new();
+    for (i, (_, arf)) in tagged.iter().enumerate() {
+        let keys: Vec<String> = if arf.context.files.is_empty() {
+            vec![format!("\0{}", longest_word(&arf.what))]
+        } else {
+            arf.context.files.clone()
+        };
+        for key in keys {
+            let first = *first_with.entry(key).or_insert(i);
+            sets.join(first, i);
+        }
+    }
+    sets.groups(tagged)
+        .into_iter()
+        .flat_map(|partition| {
+            partition
+                .chunks(MAX_PARTITION)
+                .map(<[_]>::to_vec)
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+/// Longest word of `text`, lowercased, ignoring punctuation; the first
+/// of equally long ones
+fn longest_word(text: &str) -> String {
+    text.split(|c: char| !c.is_alphanumeric())
+        .fold("", |longest, word| {
+            if word.chars().count() > longest.chars().count() {
+                word
+            } else {
+                longest
+            }
+        })
+        .to_lowercase()
+}
+
+/// Cluster like [`merger::cluster`], partitioning by file overlap first
+/// when there are more than `partition_above` entries (0 never does).
+/// Returns the clusters and whether they were partitioned.
+pub fn cluster(
+    tagged: &[(String, ArfFile)],
+    max_distance: usize,
+    clustering: Clustering,
+    partition_above: usize,
+) -> (Vec<Vec<(String, ArfFile)>>, bool) {
+    if partition_above == 0 || tagged.len() <= partition_above {
+        return (merger::cluster(tagged, max_distance, clustering), false);
+    }
+    let clusters = partition_by_files(tagged)
+        .iter()
+        .flat_map(|partition| merger::cluster(partition, max_distance, clustering))
+        .collect();
+    (clusters, true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(what: &str, files: &[&str]) -> (String, ArfFile) {
+        let mut arf = ArfFile::new(what, "Why", "How");
+        arf.context.files = files.iter().map(|f| f.to_string()).collect();
+        ("claude".to_string(), arf)
+    }
+
+    #[test]
+    fn test_sample_keeps_most_specific() {
+        let tagged = vec![
+            entry("Short", &[]),
+            entry("Cites two files", &["a.rs", "b.rs"]),
+            entry("A much longer entry without files", &[]),
+            entry("Cites one", &["a.rs"]),
+        ];
+
+        let (kept, dropped) = sample(tagged.clone(), 2);
+        assert_eq!(dropped, 2);
+        let whats: Vec<&str> = kept.iter().map(|(_, a)| a.what.as_str()).collect();
+        assert_eq!(whats, vec!["Cites two files", "Cites one"]);
+
+        let (kept, dropped) = sample(tagged, 0);
+        assert_eq!((kept.len(), dropped), (4, 0));
+    }
+
+    #[test]
+    fn test_partition_by_file_overlap() {
+        let tagged = vec![
+            entry("Use pooling", &["src/db.rs"]),
+            entry("Retry payments", &["src/pay.rs"]),
+            entry("Use pooling.", &["src/db.rs", "src/pool.rs"]),
+            entry("Pool size", &["src/pool.rs"]),
+            entry("Indentation uses tabs", &[]),
+            entry("Use tabs for indentation.", &[]),
+            entry("Use snake_case", &[]),
+        ];
+
+        let partitions = partition_by_files(&tagged);
+        let sizes: Vec<usize> = partitions.iter().map(Vec::len).collect();
+        assert_eq!(sizes, vec![3, 1, 2, 1]);
+
+        let (clusters, partitioned) = cluster(&tagged, 3, Clustering::Representative, 4);
+        assert!(partitioned);
+        assert_eq!(clusters.len(), 6);
+        let (_, partitioned) = cluster(&tagged, 3, Clustering::Representative, 0);
+        assert!(!partitioned);
+    }
+
+    #[test]
+    fn test_partitions_are_capped() {
+        let tagged: Vec<_> = (0..MAX_PARTITION * 2 + 1)
+            .map(|i| entry(&format!("Entry {}", i), &["README.md"]))
+            .collect();
+        let sizes: Vec<usize> = partition_by_files(&tagged).iter().map(Vec::len).collect();
+        assert_eq!(sizes, vec![MAX_PARTITION, MAX_PARTITION, 1]);
+    }
+}
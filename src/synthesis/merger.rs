@@ -1,6 +1,7 @@
-use crate::arf::{ArfContext, ArfFile};
+use crate::arf::{extra_text, ArfContext, ArfFile, Provenance};
 use super::conflict::FieldConflict;
-use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
 
 /// Inferred ARF category for grouping
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -60,6 +61,15 @@ pub fn infer_category(arf: &ArfFile) -> ArfCategory {
 /// same concept.
 pub fn group_by_similarity(
     tagged: &[(String, ArfFile)],
+) -> Vec<Vec<(String, ArfFile)>> {
+    group_by_similarity_within(tagged, 3)
+}
+
+/// Cluster ARFs whose lowercased `what` is within edit distance
+/// `max_distance` (exclusive) of a cluster's first member.
+pub fn group_by_similarity_within(
+    tagged: &[(String, ArfFile)],
+    max_distance: usize,
 ) -> Vec<Vec<(String, ArfFile)>> {
     let mut clusters: Vec<Vec<(String, ArfFile)>> = Vec::new();
 
@@ -70,7 +80,7 @@ pub fn group_by_similarity(
         for cluster in &mut clusters {
             let representative = cluster[0].1.what.to_lowercase();
             let distance = edit_distance::edit_distance(&what_lower, &representative);
-            if distance < 3 {
+            if distance < max_distance {
                 cluster.push(item.clone());
                 found = true;
                 break;
@@ -85,6 +95,94 @@ pub fn group_by_similarity(
     clusters
 }
 
+/// How entries are grouped into clusters of the same knowledge
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Clustering {
+    /// Join the first cluster whose first member is close enough
+    #[default]
+    Representative,
+    /// Single linkage: join every cluster with any member close enough,
+    /// merging clusters the entry bridges
+    Linkage,
+}
+
+/// Cluster ARFs whose lowercased `what` values are within edit distance
+/// `max_distance` (exclusive), using `clustering`
+pub fn cluster(
+    tagged: &[(String, ArfFile)],
+    max_distance: usize,
+    clustering: Clustering,
+) -> Vec<Vec<(String, ArfFile)>> {
+    match clustering {
+        Clustering::Representative => group_by_similarity_within(tagged, max_distance),
+        Clustering::Linkage => group_by_linkage(tagged, max_distance),
+    }
+}
+
+/// Single-linkage clustering. Clusters keep the order of their first
+/// member, and members keep input order.
+fn group_by_linkage(
+    tagged: &[(String, ArfFile)],
+    max_distance: usize,
+) -> Vec<Vec<(String, ArfFile)>> {
+    let whats: Vec<String> = tagged.iter().map(|(_, arf)| arf.what.to_lowercase()).collect();
+    let mut sets = DisjointSets::new(tagged.len());
+    for i in 0..whats.len() {
+        for j in (i + 1)..whats.len() {
+            if edit_distance::edit_distance(&whats[i], &whats[j]) < max_distance {
+                sets.join(i, j);
+            }
+        }
+    }
+    sets.groups(tagged)
+}
+
+/// Union-find over entry indices, each root being its set's lowest index
+pub(super) struct DisjointSets {
+    parent: Vec<usize>,
+}
+
+impl DisjointSets {
+    pub(super) fn new(len: usize) -> Self {
+        Self {
+            parent: (0..len).collect(),
+        }
+    }
+
+    fn root(&mut self, mut i: usize) -> usize {
+        while self.parent[i] != i {
+            self.parent[i] = self.parent[self.parent[i]];
+            i = self.parent[i];
+        }
+        i
+    }
+
+    /// Put entries `i` and `j` in the same set
+    pub(super) fn join(&mut self, i: usize, j: usize) {
+        let (a, b) = (self.root(i), self.root(j));
+        if a != b {
+            self.parent[a.max(b)] = a.min(b);
+        }
+    }
+
+    /// The entries of `tagged` grouped by set. Groups keep the order of
+    /// their first member, and members keep input order.
+    pub(super) fn groups(mut self, tagged: &[(String, ArfFile)]) -> Vec<Vec<(String, ArfFile)>> {
+        let mut groups: Vec<Vec<(String, ArfFile)>> = Vec::new();
+        let mut group_of: HashMap<usize, usize> = HashMap::new();
+        for (i, item) in tagged.iter().enumerate() {
+            let r = self.root(i);
+            let idx = *group_of.entry(r).or_insert_with(|| {
+                groups.push(Vec::new());
+                groups.len() - 1
+            });
+            groups[idx].push(item.clone());
+        }
+        groups
+    }
+}
+
 /// Merge a cluster of similar ARFs into a single unified ARF.
 /// Returns the merged ARF and any field conflicts detected during merge.
 pub fn merge_arf_fields(
@@ -100,11 +198,22 @@ pub fn merge_arf_fields(
     let why = merge_why(cluster);
     let how = merge_how(cluster);
     let context = merge_context(cluster, &mut conflicts);
+    let tags = merge_tags(cluster);
+    let confidence = merge_confidence(cluster);
 
     let arf = ArfFile {
         what,
         why,
         how,
+        id: cluster.iter().find_map(|(_, a)| a.id.clone()),
+        category: cluster.iter().find_map(|(_, a)| a.category.clone()),
+        source: cluster.iter().find_map(|(_, a)| a.source),
+        approved: false,
+        tags,
+        confidence,
+        created_at: cluster.iter().filter_map(|(_, a)| a.created_at).min(),
+        updated_at: cluster.iter().filter_map(|(_, a)| a.updated_at).max(),
+        rules: Vec::new(),
         context,
     };
 
@@ -154,21 +263,48 @@ fn merge_what(cluster: &[(String, ArfFile)], conflicts: &mut Vec<FieldConflict>)
     all.first().map(|v| v.to_string()).unwrap_or_default()
 }
 
-/// Merge `why` fields: split on sentence boundaries, collect unique sentences.
+/// Share of words two sentences must have in common to be the same
+/// reason worded differently
+const SAME_SENTENCE_OVERLAP: f64 = 0.8;
+
+/// Merge `why` fields: split on sentence boundaries, collect sentences,
+/// dropping any that rewords one already kept.
 fn merge_why(cluster: &[(String, ArfFile)]) -> String {
-    let mut seen = Vec::new();
+    let mut seen: Vec<(String, HashSet<String>)> = Vec::new();
 
     for (_, arf) in cluster {
         let sentences = split_sentences(&arf.why);
         for sentence in sentences {
             let trimmed = sentence.trim().to_string();
-            if !trimmed.is_empty() && !seen.contains(&trimmed) {
-                seen.push(trimmed);
+            if trimmed.is_empty() {
+                continue;
+            }
+            let words = sentence_words(&trimmed);
+            if !seen.iter().any(|(_, kept)| word_overlap(kept, &words) >= SAME_SENTENCE_OVERLAP) {
+                seen.push((trimmed, words));
             }
         }
     }
 
-    seen.join(". ")
+    seen.into_iter().map(|(sentence, _)| sentence).collect::<Vec<_>>().join(". ")
+}
+
+/// Lowercased words of `sentence`
+fn sentence_words(sentence: &str) -> HashSet<String> {
+    sentence
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// Words in both sets over words in either (1.0 for two empty sets)
+fn word_overlap(a: &HashSet<String>, b: &HashSet<String>) -> f64 {
+    let union = a.union(b).count();
+    if union == 0 {
+        return 1.0;
+    }
+    a.intersection(b).count() as f64 / union as f64
 }
 
 /// Merge `how` fields: split on newlines, collect unique steps preserving
@@ -194,6 +330,27 @@ fn merge_how(cluster: &[(String, ArfFile)]) -> String {
     all_steps.join("\n")
 }
 
+/// Merge `tags`: sorted union across models.
+fn merge_tags(cluster: &[(String, ArfFile)]) -> Vec<String> {
+    let mut tags: Vec<String> = cluster
+        .iter()
+        .flat_map(|(_, arf)| arf.tags.iter().cloned())
+        .collect();
+    tags.sort();
+    tags.dedup();
+    tags
+}
+
+/// Merge `confidence`: mean of the models that reported one.
+fn merge_confidence(cluster: &[(String, ArfFile)]) -> Option<f64> {
+    let values: Vec<f64> = cluster.iter().filter_map(|(_, arf)| arf.confidence).collect();
+    if values.is_empty() {
+        None
+    } else {
+        Some(values.iter().sum::<f64>() / values.len() as f64)
+    }
+}
+
 /// Merge context fields from all ARFs in a cluster.
 fn merge_context(
     cluster: &[(String, ArfFile)],
@@ -201,8 +358,14 @@ fn merge_context(
 ) -> ArfContext {
     let mut files: Vec<String> = Vec::new();
     let mut commits: Vec<String> = Vec::new();
+    let mut branches: Vec<String> = Vec::new();
+    let mut issues: Vec<String> = Vec::new();
+    let mut people: Vec<String> = Vec::new();
     let mut dependencies: Vec<String> = Vec::new();
-    let mut outcomes: HashMap<String, Vec<(String, String)>> = HashMap::new();
+    let mut prompt_types: Vec<String> = Vec::new();
+    let mut outcomes: BTreeMap<String, Vec<(String, String)>> = BTreeMap::new();
+    let mut provenance = Provenance::default();
+    let mut extras: BTreeMap<String, Vec<(String, toml::Value)>> = BTreeMap::new();
 
     for (model, arf) in cluster {
         for f in &arf.context.files {
@@ -215,11 +378,48 @@ fn merge_context(
                 commits.push(c.clone());
             }
         }
+        for b in &arf.context.branches {
+            if !branches.contains(b) {
+                branches.push(b.clone());
+            }
+        }
+        for i in &arf.context.issues {
+            if !issues.contains(i) {
+                issues.push(i.clone());
+            }
+        }
+        for p in &arf.context.people {
+            if !people.contains(p) {
+                people.push(p.clone());
+            }
+        }
         for d in &arf.context.dependencies {
             if !dependencies.contains(d) {
                 dependencies.push(d.clone());
             }
         }
+        for t in &arf.context.prompt_types {
+            if !prompt_types.contains(t) {
+                prompt_types.push(t.clone());
+            }
+        }
+        for m in &arf.context.provenance.models {
+            if !provenance.models.contains(m) {
+                provenance.models.push(m.clone());
+            }
+        }
+        for (field, models) in &arf.context.provenance.fields {
+            provenance
+                .fields
+                .entry(field.clone())
+                .or_insert_with(|| models.clone());
+        }
+        for (key, value) in &arf.context.extra {
+            extras
+                .entry(key.clone())
+                .or_default()
+                .push((model.clone(), value.clone()));
+        }
         for (key, value) in &arf.context.outcome {
             outcomes
                 .entry(key.clone())
@@ -230,10 +430,13 @@ fn merge_context(
 
     files.sort();
     commits.sort();
+    branches.sort();
     dependencies.sort();
+    prompt_types.sort();
+    provenance.models.sort();
 
     // Merge outcomes, flagging conflicts
-    let mut merged_outcome: HashMap<String, String> = HashMap::new();
+    let mut merged_outcome: BTreeMap<String, String> = BTreeMap::new();
     for (key, model_values) in &outcomes {
         let unique_values: Vec<&String> = {
             let mut vals: Vec<&String> = model_values.iter().map(|(_, v)| v).collect();
@@ -257,11 +460,36 @@ fn merge_context(
         }
     }
 
+    // Unknown keys the models disagree on are voted on like outcomes
+    let mut extra: BTreeMap<String, toml::Value> = BTreeMap::new();
+    for (key, model_values) in extras {
+        if model_values.iter().any(|(_, v)| *v != model_values[0].1) {
+            conflicts.push(FieldConflict {
+                field: format!("context.extra.{}", key),
+                kind: super::conflict::ConflictKind::DifferentValues,
+                values: model_values
+                    .iter()
+                    .map(|(model, v)| (model.clone(), extra_text(v).into_owned()))
+                    .collect(),
+                resolution: None,
+            });
+        }
+        // First value stands in until voting resolves it
+        let (_, first) = model_values.into_iter().next().expect("key came from a value");
+        extra.insert(key, first);
+    }
+
     ArfContext {
         files,
         commits,
+        branches,
+        issues,
+        people,
         dependencies,
+        prompt_types,
         outcome: merged_outcome,
+        provenance,
+        extra,
     }
 }
 
@@ -337,6 +565,24 @@ mod tests {
         assert_eq!(clusters.len(), 3);
     }
 
+    #[test]
+    fn test_linkage_merges_chains() {
+        // The last entry is one edit from each of the others, which are two
+        // edits apart
+        let chain = vec![
+            ("claude".to_string(), ArfFile::new("abcdef", "w", "h")),
+            ("gemini".to_string(), ArfFile::new("abcdgh", "w", "h")),
+            ("codex".to_string(), ArfFile::new("abcdeh", "w", "h")),
+        ];
+        let representative = cluster(&chain, 2, Clustering::Representative);
+        assert_eq!(representative.len(), 2);
+        assert_eq!(representative[0][1].1.what, "abcdeh");
+
+        let linkage = cluster(&chain, 2, Clustering::Linkage);
+        assert_eq!(linkage.len(), 1);
+        assert_eq!(linkage[0][1].1.what, "abcdgh");
+    }
+
     #[test]
     fn test_merge_single_item_cluster() {
         let cluster = vec![
@@ -370,6 +616,22 @@ mod tests {
         assert!(arf.why.contains("Better throughput"));
     }
 
+    #[test]
+    fn test_merge_why_drops_reworded_sentences() {
+        let cluster = vec![
+            (
+                "claude".to_string(),
+                ArfFile::new("X", "Pooling cuts the cost of opening database connections", "Y"),
+            ),
+            (
+                "gemini".to_string(),
+                ArfFile::new("X", "Pooling cuts the cost of opening new database connections", "Y"),
+            ),
+        ];
+        let (arf, _) = merge_arf_fields(&cluster);
+        assert_eq!(arf.why, "Pooling cuts the cost of opening database connections");
+    }
+
     #[test]
     fn test_merge_how_collects_unique_steps() {
         let cluster = vec![
@@ -400,4 +662,30 @@ mod tests {
         let (arf, _) = merge_arf_fields(&cluster);
         assert_eq!(arf.context.files, vec!["a.rs", "b.rs", "c.rs"]);
     }
+
+    #[test]
+    fn test_merge_context_keeps_unknown_keys() {
+        let mut arf1 = ArfFile::new("X", "Y", "Z");
+        arf1.context.extra.insert("severity".to_string(), "high".into());
+        let mut arf2 = ArfFile::new("X", "Y", "Z");
+        arf2.context.extra.insert("severity".to_string(), "low".into());
+        arf2.context.extra.insert("component".to_string(), "billing".into());
+
+        let cluster = vec![
+            ("claude".to_string(), arf1),
+            ("gemini".to_string(), arf2),
+        ];
+        let (arf, conflicts) = merge_arf_fields(&cluster);
+        assert_eq!(arf.context.extra["severity"].as_str(), Some("high"));
+        assert_eq!(arf.context.extra["component"].as_str(), Some("billing"));
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].field, "context.extra.severity");
+        assert_eq!(
+            conflicts[0].values,
+            vec![
+                ("claude".to_string(), "high".to_string()),
+                ("gemini".to_string(), "low".to_string()),
+            ]
+        );
+    }
 }
@@ -1,9 +1,15 @@
+pub mod confidence;
 pub mod conflict;
+pub mod guard;
 pub mod merger;
+pub mod salvage;
 pub mod vote;
 
-use crate::arf::ArfFile;
+use crate::arf::{ArfFile, ArfSource};
+use crate::config::SynthesisConfig;
 use crate::error::{Error, SynthesisError};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
 
 /// Output from a single model's analysis
 #[derive(Debug, Clone)]
@@ -12,15 +18,35 @@ pub struct ModelOutput {
     pub arf_files: Vec<ArfFile>,
 }
 
+/// Model outputs for one prompt sent during learn
+#[derive(Debug, Clone)]
+pub struct PromptOutputs {
+    /// Kind of prompt (`files`, `commits`, `patterns`)
+    pub prompt_type: String,
+    pub outputs: Vec<ModelOutput>,
+}
+
 /// Result of the synthesis pipeline
 #[derive(Debug, Clone)]
 pub struct SynthesisResult {
     pub unified_arfs: Vec<ArfFile>,
+    /// Fields voting could not settle, left for `noggin resolve`
+    pub manual_conflicts: Vec<ManualConflict>,
     pub report: SynthesisReport,
 }
 
-/// Statistics about the synthesis process
+/// A field of a unified entry the models disagreed on irreconcilably
 #[derive(Debug, Clone)]
+pub struct ManualConflict {
+    /// Id of the entry the field belongs to (see [`ArfFile::id_or_slug`])
+    pub id: String,
+    pub field: String,
+    /// The values each model produced: (model_name, value)
+    pub values: Vec<(String, String)>,
+}
+
+/// Statistics about the synthesis process
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SynthesisReport {
     pub total_input_arfs: usize,
     pub total_output_arfs: usize,
@@ -29,14 +55,66 @@ pub struct SynthesisReport {
     pub conflicts_manual: usize,
     pub model_agreement_pct: f64,
     pub models_used: Vec<String>,
+    /// Entries each prompt type produced, before cross-prompt dedup
+    #[serde(default)]
+    pub by_prompt_type: BTreeMap<String, usize>,
+    /// Entries merged because another prompt produced the same one
+    #[serde(default)]
+    pub cross_prompt_duplicates: usize,
+    /// Clusters dropped because too few models produced them
+    #[serde(default)]
+    pub below_min_models: usize,
+    /// Least specific entries left out by `synthesis.max_entries`
+    #[serde(default)]
+    pub sampled_out: usize,
+    /// Whether some entries were only clustered within their file-overlap
+    /// partition because of `synthesis.partition_above`
+    #[serde(default)]
+    pub partitioned: bool,
+}
+
+impl SynthesisReport {
+    /// A warning describing how synthesis was degraded, if it was
+    pub fn degradation_warning(&self) -> Option<String> {
+        let mut parts = Vec::new();
+        if self.sampled_out > 0 {
+            parts.push(format!(
+                "left out {} of {} entries over synthesis.max_entries, keeping the most specific",
+                self.sampled_out, self.total_input_arfs
+            ));
+        }
+        if self.partitioned {
+            parts.push(
+                "only merged entries sharing a file, as there were more than synthesis.partition_above"
+                    .to_string(),
+            );
+        }
+        (!parts.is_empty()).then(|| format!("Synthesis degraded on a large input: {}", parts.join("; ")))
+    }
+}
+
+/// ARF entries parsed from one model response
+#[derive(Debug, Clone, Default)]
+pub struct ParsedResponse {
+    pub arfs: Vec<ArfFile>,
+    /// Fields coerced or dropped, and entries or blocks that were skipped
+    pub warnings: Vec<String>,
 }
 
 /// Parse a model's raw text response into a list of ARF files.
 ///
 /// Tries TOML array-of-tables first (multiple `[[entry]]` blocks),
 /// then falls back to splitting on `---` delimiters and parsing
-/// each section as standalone TOML.
+/// each section as standalone TOML. Warnings are discarded; see
+/// [`parse_model_output`].
 pub fn parse_model_response(model_name: &str, raw: &str) -> Result<Vec<ArfFile>, Error> {
+    parse_model_output(model_name, raw).map(|parsed| parsed.arfs)
+}
+
+/// Like [`parse_model_response`], but entries with a malformed field are
+/// salvaged field by field rather than dropped, and every repair is
+/// reported in [`ParsedResponse::warnings`].
+pub fn parse_model_output(model_name: &str, raw: &str) -> Result<ParsedResponse, Error> {
     let trimmed = raw.trim();
     if trimmed.is_empty() {
         return Err(Error::Synthesis(SynthesisError::ParseFailed {
@@ -45,10 +123,29 @@ pub fn parse_model_response(model_name: &str, raw: &str) -> Result<Vec<ArfFile>,
         }));
     }
 
-    // Strategy 1: Try parsing as a TOML document with [[entry]] array
-    if let Ok(arfs) = parse_toml_array(trimmed) {
-        if !arfs.is_empty() {
-            return Ok(arfs);
+    // Strategy 1: Try parsing as a TOML document with [[entry]] array,
+    // or failing that as a single entry
+    if let Ok(table) = trimmed.parse::<toml::Table>() {
+        let mut parsed = ParsedResponse::default();
+        match table.get("entry") {
+            Some(toml::Value::Array(entries)) => {
+                for (idx, entry) in entries.iter().enumerate() {
+                    match entry {
+                        toml::Value::Table(entry) => {
+                            salvage_into(entry, &format!("entry {}", idx + 1), &mut parsed)
+                        }
+                        other => parsed.warnings.push(format!(
+                            "skipped entry {}: expected a table, found {}",
+                            idx + 1,
+                            other.type_str()
+                        )),
+                    }
+                }
+            }
+            _ => salvage_into(&table, "entry", &mut parsed),
+        }
+        if !parsed.arfs.is_empty() {
+            return Ok(parsed);
         }
     }
 
@@ -59,45 +156,41 @@ pub fn parse_model_response(model_name: &str, raw: &str) -> Result<Vec<ArfFile>,
         .filter(|s| !s.is_empty())
         .collect();
 
-    // If no --- delimiters, try the whole thing as a single TOML doc
-    if blocks.len() <= 1 {
-        if let Ok(arf) = parse_single_toml(trimmed) {
-            return Ok(vec![arf]);
-        }
-    }
-
-    let mut arfs = Vec::new();
-    for block in &blocks {
-        if let Ok(arf) = parse_single_toml(block) {
-            arfs.push(arf);
+    let mut parsed = ParsedResponse::default();
+    if blocks.len() > 1 {
+        for (idx, block) in blocks.iter().enumerate() {
+            let label = format!("block {}", idx + 1);
+            match block.parse::<toml::Table>() {
+                Ok(table) => salvage_into(&table, &label, &mut parsed),
+                Err(_) => parsed.warnings.push(format!("skipped {}: not valid TOML", label)),
+            }
         }
     }
 
-    if arfs.is_empty() {
+    if parsed.arfs.is_empty() {
         return Err(Error::Synthesis(SynthesisError::ParseFailed {
             model: model_name.to_string(),
             details: format!("no valid TOML blocks found in {} chars of output", trimmed.len()),
         }));
     }
 
-    Ok(arfs)
+    Ok(parsed)
 }
 
-/// Try to parse TOML with `[[entry]]` array-of-tables syntax
-fn parse_toml_array(raw: &str) -> Result<Vec<ArfFile>, ()> {
-    #[derive(serde::Deserialize)]
-    struct Wrapper {
-        #[serde(default)]
-        entry: Vec<ArfFile>,
+/// Salvage one entry table into `parsed`, recording why it was skipped if
+/// it can't be
+fn salvage_into(table: &toml::Table, label: &str, parsed: &mut ParsedResponse) {
+    match salvage::salvage_entry(table) {
+        Ok(mut entry) => match entry.arf.validate() {
+            Ok(notes) => {
+                parsed.arfs.push(entry.arf);
+                parsed.warnings.extend(entry.warnings);
+                parsed.warnings.extend(notes);
+            }
+            Err(e) => parsed.warnings.push(format!("skipped {}: {}", label, e)),
+        },
+        Err(reason) => parsed.warnings.push(format!("skipped {}: {}", label, reason)),
     }
-
-    let wrapper: Wrapper = toml::from_str(raw).map_err(|_| ())?;
-    Ok(wrapper.entry)
-}
-
-/// Parse a single TOML block as an ArfFile
-fn parse_single_toml(raw: &str) -> Result<ArfFile, ()> {
-    toml::from_str::<ArfFile>(raw).map_err(|_| ())
 }
 
 /// Run the full synthesis pipeline on outputs from multiple models.
@@ -107,7 +200,14 @@ fn parse_single_toml(raw: &str) -> Result<ArfFile, ()> {
 /// 3. Merge clusters
 /// 4. Detect and resolve conflicts
 /// 5. Normalize and return
-pub fn synthesize(outputs: Vec<ModelOutput>) -> Result<SynthesisResult, Error> {
+///
+/// `config` sets the merge distance, clustering algorithm, whether
+/// categories are inferred, and how many models a cluster needs. Huge
+/// inputs are sampled and partitioned first (see [`guard`]).
+pub fn synthesize(
+    outputs: Vec<ModelOutput>,
+    config: &SynthesisConfig,
+) -> Result<SynthesisResult, Error> {
     let models_used: Vec<String> = outputs.iter().map(|o| o.model_name.clone()).collect();
     let total_input_arfs: usize = outputs.iter().map(|o| o.arf_files.len()).sum();
 
@@ -122,31 +222,75 @@ pub fn synthesize(outputs: Vec<ModelOutput>) -> Result<SynthesisResult, Error> {
             tagged.push((output.model_name.clone(), arf.clone()));
         }
     }
+    let (tagged, sampled_out) = guard::sample(tagged, config.max_entries);
 
-    // Group by inferred category
-    let categories = merger::group_by_category(&tagged);
-
-    // Within each category, cluster by similarity then merge
-    let mut merged_arfs: Vec<ArfFile> = Vec::new();
-    let mut all_conflicts: Vec<conflict::FieldConflict> = Vec::new();
+    // Group by inferred category, unless disabled
+    let groups: Vec<Vec<(String, ArfFile)>> = if config.infer_categories {
+        merger::group_by_category(&tagged).into_values().collect()
+    } else {
+        vec![tagged]
+    };
 
-    for (_category, group) in &categories {
-        let clusters = merger::group_by_similarity(group);
+    // A single answering model can't be outvoted
+    let mut distinct_models = models_used.clone();
+    distinct_models.sort();
+    distinct_models.dedup();
+    let min_models = config.min_models.clamp(1, distinct_models.len().max(1));
+
+    // Within each category, cluster by similarity, merge, then resolve
+    // each cluster's conflicts by voting
+    let mut resolved_arfs: Vec<ArfFile> = Vec::new();
+    let mut conflicts_detected = 0;
+    let mut resolved_count = 0;
+    let mut manual_conflicts = Vec::new();
+    let mut below_min_models = 0;
+    let mut partitioned = false;
+
+    for group in &groups {
+        let (clusters, split) = guard::cluster(
+            group,
+            config.merge_distance,
+            config.clustering,
+            config.partition_above,
+        );
+        partitioned |= split;
         for cluster in &clusters {
-            let (arf, conflicts) = merger::merge_arf_fields(cluster);
-            all_conflicts.extend(conflicts);
-            merged_arfs.push(arf);
+            let mut models: Vec<&str> = cluster.iter().map(|(model, _)| model.as_str()).collect();
+            models.sort();
+            models.dedup();
+            if models.len() < min_models {
+                below_min_models += 1;
+                continue;
+            }
+            let (mut arf, conflicts) = merger::merge_arf_fields(cluster);
+            let detected = conflict::detect_conflicts(&conflicts);
+            let computed = confidence::score(
+                models.len(),
+                distinct_models.len(),
+                cluster.len(),
+                confidence::vote_margin(&detected),
+            );
+            arf.confidence = Some(confidence::blend(computed, arf.confidence));
+            for model in models {
+                if !arf.context.provenance.models.iter().any(|m| m == model) {
+                    arf.context.provenance.models.push(model.to_string());
+                }
+            }
+
+            conflicts_detected += detected.len();
+            let (arfs, resolved, manual) = vote::resolve_all(vec![arf], detected);
+            resolved_count += resolved;
+            if let Some(arf) = arfs.first() {
+                manual_conflicts.extend(manual.into_iter().map(|conflict| ManualConflict {
+                    id: arf.id_or_slug(),
+                    field: conflict.field,
+                    values: conflict.values,
+                }));
+            }
+            resolved_arfs.extend(arfs);
         }
     }
 
-    // Detect any remaining conflicts
-    let detected = conflict::detect_conflicts(&all_conflicts);
-    let conflicts_detected = detected.len();
-
-    // Resolve via voting
-    let (resolved_arfs, resolved_count, manual_count) =
-        vote::resolve_all(merged_arfs, detected);
-
     // Normalize: sort fields within each ARF, then sort ARFs
     let mut final_arfs = normalize_arfs(resolved_arfs);
 
@@ -166,13 +310,153 @@ pub fn synthesize(outputs: Vec<ModelOutput>) -> Result<SynthesisResult, Error> {
         total_output_arfs: final_arfs.len(),
         conflicts_detected,
         conflicts_resolved: resolved_count,
-        conflicts_manual: manual_count,
+        conflicts_manual: manual_conflicts.len(),
         model_agreement_pct: total_agreements,
         models_used,
+        by_prompt_type: BTreeMap::new(),
+        cross_prompt_duplicates: 0,
+        below_min_models,
+        sampled_out,
+        partitioned,
+    };
+
+    Ok(SynthesisResult {
+        unified_arfs: final_arfs,
+        manual_conflicts,
+        report,
+    })
+}
+
+/// Synthesize each prompt's outputs on their own, then merge entries that
+/// different prompts produced about the same subject.
+///
+/// Keeping prompts apart stops unrelated contexts (a file batch and the
+/// commit history, say) from clustering together. Each entry records the
+/// prompt types it came from in `context.prompt_types`.
+pub fn synthesize_by_prompt(
+    prompts: Vec<PromptOutputs>,
+    config: &SynthesisConfig,
+) -> Result<SynthesisResult, Error> {
+    let mut models_used: Vec<String> = Vec::new();
+    let mut total_input_arfs = 0;
+    let mut conflicts_detected = 0;
+    let mut conflicts_resolved = 0;
+    let mut manual_conflicts = Vec::new();
+    let mut below_min_models = 0;
+    let mut sampled_out = 0;
+    let mut partitioned = false;
+    let mut by_prompt_type: BTreeMap<String, usize> = BTreeMap::new();
+    let mut tagged: Vec<(String, ArfFile)> = Vec::new();
+
+    for prompt in prompts {
+        for output in &prompt.outputs {
+            if !models_used.contains(&output.model_name) {
+                models_used.push(output.model_name.clone());
+            }
+        }
+        let input: usize = prompt.outputs.iter().map(|o| o.arf_files.len()).sum();
+        if input == 0 {
+            continue;
+        }
+        total_input_arfs += input;
+
+        let arfs = if prompt.outputs.len() == 1 {
+            let mut tagged = Vec::new();
+            for output in prompt.outputs {
+                for arf in output.arf_files {
+                    tagged.push((output.model_name.clone(), arf));
+                }
+            }
+            let (tagged, dropped) = guard::sample(tagged, config.max_entries);
+            sampled_out += dropped;
+            let arfs = tagged
+                .into_iter()
+                .map(|(model, mut arf)| {
+                    arf.context.provenance.models = vec![model];
+                    arf.confidence =
+                        Some(confidence::blend(confidence::score(1, 1, 1, 1.0), arf.confidence));
+                    arf
+                })
+                .collect();
+            normalize_arfs(arfs)
+        } else {
+            let result = synthesize(prompt.outputs, config)?;
+            below_min_models += result.report.below_min_models;
+            sampled_out += result.report.sampled_out;
+            partitioned |= result.report.partitioned;
+            conflicts_detected += result.report.conflicts_detected;
+            conflicts_resolved += result.report.conflicts_resolved;
+            manual_conflicts.extend(result.manual_conflicts);
+            result.unified_arfs
+        };
+
+        *by_prompt_type.entry(prompt.prompt_type.clone()).or_default() += arfs.len();
+        for mut arf in arfs {
+            arf.context.prompt_types = vec![prompt.prompt_type.clone()];
+            arf.source = Some(ArfSource::from_prompt_type(&prompt.prompt_type));
+            tagged.push((prompt.prompt_type.clone(), arf));
+        }
+    }
+
+    if total_input_arfs == 0 {
+        return Err(Error::Synthesis(SynthesisError::NoValidEntries));
+    }
+
+    // Cross-prompt dedup: near-identical subjects collapse into one entry
+    // carrying the union of their context
+    let (clusters, split) = guard::cluster(
+        &tagged,
+        config.merge_distance,
+        config.clustering,
+        config.partition_above,
+    );
+    partitioned |= split;
+    let cross_prompt_duplicates = tagged.len() - clusters.len();
+    // Ids of merged entries, so their conflicts follow them
+    let mut merged_ids: HashMap<String, String> = HashMap::new();
+    let merged: Vec<ArfFile> = clusters
+        .iter()
+        .map(|cluster| {
+            let (mut arf, _) = merger::merge_arf_fields(cluster);
+            if arf.rules.is_empty() {
+                if let Some((_, with_rules)) = cluster.iter().find(|(_, a)| !a.rules.is_empty()) {
+                    arf.rules = with_rules.rules.clone();
+                }
+            }
+            let id = arf.id_or_slug();
+            for (_, member) in cluster {
+                merged_ids.insert(member.id_or_slug(), id.clone());
+            }
+            arf
+        })
+        .collect();
+    for conflict in &mut manual_conflicts {
+        if let Some(id) = merged_ids.get(&conflict.id) {
+            conflict.id = id.clone();
+        }
+    }
+
+    let mut final_arfs = normalize_arfs(merged);
+    final_arfs.sort_by(|a, b| a.what.cmp(&b.what));
+
+    let report = SynthesisReport {
+        total_input_arfs,
+        total_output_arfs: final_arfs.len(),
+        conflicts_detected,
+        conflicts_resolved,
+        conflicts_manual: manual_conflicts.len(),
+        model_agreement_pct: (final_arfs.len() as f64 / total_input_arfs as f64 * 100.0).min(100.0),
+        models_used,
+        by_prompt_type,
+        cross_prompt_duplicates,
+        below_min_models,
+        sampled_out,
+        partitioned,
     };
 
     Ok(SynthesisResult {
         unified_arfs: final_arfs,
+        manual_conflicts,
         report,
     })
 }
@@ -190,6 +474,10 @@ fn normalize_arfs(arfs: Vec<ArfFile>) -> Vec<ArfFile> {
             arf.context.commits.dedup();
             arf.context.dependencies.sort();
             arf.context.dependencies.dedup();
+            arf.context.prompt_types.sort();
+            arf.context.prompt_types.dedup();
+            arf.context.provenance.models.sort();
+            arf.context.provenance.models.dedup();
             arf
         })
         .collect()
@@ -244,6 +532,38 @@ how = "Step two"
         assert_eq!(arfs[1].what, "Second entry");
     }
 
+    #[test]
+    fn test_parse_salvages_malformed_fields() {
+        let raw = r#"
+[[entry]]
+what = "Use connection pooling"
+why = "Performance"
+how = "PgBouncer"
+context = "src/db.rs"
+
+[[entry]]
+what = "Missing reason"
+how = "Redis"
+
+[[entry]]
+what = "Add caching layer"
+why = "Speed"
+how = "Redis"
+
+[entry.context]
+issues = ["PROJ-12", "someday"]
+"#;
+        let parsed = parse_model_output("claude", raw).unwrap();
+        assert_eq!(parsed.arfs.len(), 2);
+        assert_eq!(parsed.arfs[0].what, "Use connection pooling");
+        assert_eq!(parsed.arfs[1].what, "Add caching layer");
+        assert_eq!(parsed.arfs[1].context.issues, vec!["PROJ-12"]);
+        assert_eq!(parsed.warnings.len(), 3);
+        assert!(parsed.warnings[0].contains("ignored context"));
+        assert!(parsed.warnings[1].starts_with("skipped entry 2"));
+        assert!(parsed.warnings[2].contains("dropped issue 'someday'"));
+    }
+
     #[test]
     fn test_parse_empty_response() {
         let result = parse_model_response("codex", "");
@@ -258,20 +578,26 @@ how = "Step two"
 
     #[test]
     fn test_synthesize_empty_input() {
-        let result = synthesize(vec![ModelOutput {
-            model_name: "claude".to_string(),
-            arf_files: vec![],
-        }]);
+        let result = synthesize(
+            vec![ModelOutput {
+                model_name: "claude".to_string(),
+                arf_files: vec![],
+            }],
+            &SynthesisConfig::default(),
+        );
         assert!(result.is_err());
     }
 
     #[test]
     fn test_synthesize_single_model() {
         let arf = ArfFile::new("Use pooling", "Performance", "PgBouncer");
-        let result = synthesize(vec![ModelOutput {
-            model_name: "claude".to_string(),
-            arf_files: vec![arf],
-        }])
+        let result = synthesize(
+            vec![ModelOutput {
+                model_name: "claude".to_string(),
+                arf_files: vec![arf],
+            }],
+            &SynthesisConfig::default(),
+        )
         .unwrap();
 
         assert_eq!(result.unified_arfs.len(), 1);
@@ -279,6 +605,148 @@ how = "Step two"
         assert_eq!(result.report.models_used, vec!["claude"]);
     }
 
+    #[test]
+    fn test_synthesize_min_models_and_categories() {
+        let outputs = || {
+            vec![
+                ModelOutput {
+                    model_name: "claude".to_string(),
+                    arf_files: vec![
+                        ArfFile::new("Use pooling", "Performance", "PgBouncer"),
+                        ArfFile::new("Retry payments", "Flaky networks", "Backoff"),
+                    ],
+                },
+                ModelOutput {
+                    model_name: "gemini".to_string(),
+                    arf_files: vec![ArfFile::new("Use pooling", "Fix a bug", "PgBouncer")],
+                },
+            ]
+        };
+
+        // "Fix a bug" puts gemini's entry in another category
+        let result = synthesize(outputs(), &SynthesisConfig::default()).unwrap();
+        assert_eq!(result.unified_arfs.len(), 3);
+
+        let config = SynthesisConfig {
+            infer_categories: false,
+            min_models: 2,
+            ..Default::default()
+        };
+        let result = synthesize(outputs(), &config).unwrap();
+        assert_eq!(result.unified_arfs.len(), 1);
+        assert_eq!(result.unified_arfs[0].what, "Use pooling");
+        assert_eq!(result.report.below_min_models, 1);
+    }
+
+    #[test]
+    fn test_synthesize_degrades_huge_inputs() {
+        let arfs: Vec<ArfFile> = (0..20)
+            .map(|i| {
+                let mut arf = ArfFile::new(format!("Module {} owns state", i), "Why", "How");
+                arf.context.files = vec![format!("src/m{}.rs", i % 5)];
+                if i < 3 {
+                    arf.context.commits = vec![format!("abc{}", i)];
+                }
+                arf
+            })
+            .collect();
+        let outputs = vec![ModelOutput {
+            model_name: "claude".to_string(),
+            arf_files: arfs,
+        }];
+
+        let result = synthesize(outputs.clone(), &SynthesisConfig::default()).unwrap();
+        assert!(result.report.degradation_warning().is_none());
+
+        let config = SynthesisConfig {
+            max_entries: 10,
+            partition_above: 4,
+            ..Default::default()
+        };
+        let result = synthesize(outputs, &config).unwrap();
+        assert_eq!(result.report.total_input_arfs, 20);
+        assert_eq!(result.report.sampled_out, 10);
+        assert!(result.report.partitioned);
+        assert!(result.unified_arfs.iter().any(|a| a.what == "Module 2 owns state"));
+        let warning = result.report.degradation_warning().unwrap();
+        assert!(warning.contains("left out 10 of 20 entries"));
+    }
+
+    #[test]
+    fn test_synthesize_scores_confidence() {
+        let outputs = vec![
+            ModelOutput {
+                model_name: "claude".to_string(),
+                arf_files: vec![
+                    ArfFile::new("Use pooling", "Performance", "PgBouncer"),
+                    ArfFile::new("Retry payments", "Flaky networks", "Backoff"),
+                ],
+            },
+            ModelOutput {
+                model_name: "gemini".to_string(),
+                arf_files: vec![ArfFile::new("Use pooling", "Performance", "PgBouncer")],
+            },
+        ];
+
+        let result = synthesize(outputs, &SynthesisConfig::default()).unwrap();
+        let confidence = |what: &str| {
+            result
+                .unified_arfs
+                .iter()
+                .find(|arf| arf.what == what)
+                .and_then(|arf| arf.confidence)
+                .unwrap()
+        };
+        assert!(confidence("Use pooling") > confidence("Retry payments"));
+        assert!(confidence("Use pooling") <= 1.0);
+    }
+
+    #[test]
+    fn test_synthesize_by_prompt_keeps_prompts_apart() {
+        let output = |model: &str, arfs: Vec<ArfFile>| ModelOutput {
+            model_name: model.to_string(),
+            arf_files: arfs,
+        };
+        let prompts = vec![
+            PromptOutputs {
+                prompt_type: "files".to_string(),
+                outputs: vec![
+                    output("claude", vec![ArfFile::new("Use pooling", "Performance", "PgBouncer")]),
+                    output("gemini", vec![ArfFile::new("Use pooling.", "Performance", "PgBouncer")]),
+                ],
+            },
+            PromptOutputs {
+                prompt_type: "commits".to_string(),
+                outputs: vec![output(
+                    "claude",
+                    vec![
+                        ArfFile::new("use pooling", "Adopted in 2024", "PgBouncer"),
+                        ArfFile::new("Retry payments", "Flaky networks", "Backoff"),
+                    ],
+                )],
+            },
+        ];
+
+        let result = synthesize_by_prompt(prompts, &SynthesisConfig::default()).unwrap();
+        assert_eq!(result.unified_arfs.len(), 2);
+        assert_eq!(result.report.total_input_arfs, 4);
+        assert_eq!(result.report.by_prompt_type["files"], 1);
+        assert_eq!(result.report.by_prompt_type["commits"], 2);
+        assert_eq!(result.report.cross_prompt_duplicates, 1);
+        assert_eq!(result.report.models_used, vec!["claude", "gemini"]);
+
+        let pooling = result
+            .unified_arfs
+            .iter()
+            .find(|a| a.what.to_lowercase().starts_with("use pooling"))
+            .unwrap();
+        assert_eq!(pooling.context.prompt_types, vec!["commits", "files"]);
+        assert_eq!(pooling.context.provenance.models, vec!["claude", "gemini"]);
+        assert_eq!(pooling.context.provenance.fields["what"], vec!["claude"]);
+        let retry = result.unified_arfs.iter().find(|a| a.what == "Retry payments").unwrap();
+        assert_eq!(retry.context.prompt_types, vec!["commits"]);
+    }
+
     #[test]
     fn test_normalize_trims_and_sorts() {
         let mut arf = ArfFile::new("  Test  ", " Why ", " How ");
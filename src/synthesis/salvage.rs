@@ -0,0 +1,403 @@
+//! Field-by-field recovery of ARF entries from model output
+//!
+//! Models sometimes get one field's shape wrong: `context` written as a
+//! string, a lone tag instead of a list, `confidence` as `"0.8"`. Strict
+//! deserialization rejects the whole entry for that. [`salvage_entry`]
+//! reads each field on its own instead, coercing what it safely can and
+//! dropping what it can't, and reports every change as a warning. Only a
+//! missing `what`, `why` or `how` loses the entry.
+
+use crate::arf::{ArfContext, ArfFile, ArfSource, Provenance};
+use crate::rules::PatternRule;
+use chrono::{DateTime, Utc};
+use std::collections::BTreeMap;
+use toml::{Table, Value};
+
+/// An entry recovered from model output, with what was changed to get it
+#[derive(Debug, Clone)]
+pub struct SalvagedEntry {
+    pub arf: ArfFile,
+    /// One line per field that was coerced or dropped
+    pub warnings: Vec<String>,
+}
+
+/// Build an ARF entry from a TOML table, keeping every field that can be
+/// read. Fails only when a required field is missing.
+pub fn salvage_entry(table: &Table) -> Result<SalvagedEntry, String> {
+    if let Ok(arf) = Value::Table(table.clone()).try_into::<ArfFile>() {
+        return Ok(SalvagedEntry {
+            arf,
+            warnings: Vec::new(),
+        });
+    }
+
+    let mut notes = Vec::new();
+    let what = required_text(table, "what", &mut notes)?;
+    let why = required_text(table, "why", &mut notes)?;
+    let how = required_text(table, "how", &mut notes)?;
+    let mut arf = ArfFile::new(what, why, how);
+
+    if let Some(value) = table.get("id") {
+        arf.id = optional_text(value, "id", &mut notes);
+    }
+    if let Some(value) = table.get("category") {
+        arf.category = optional_text(value, "category", &mut notes);
+    }
+    if let Some(value) = table.get("source") {
+        match value.clone().try_into::<ArfSource>() {
+            Ok(source) => arf.source = Some(source),
+            Err(_) => notes.push(format!("ignored source (unknown source {})", value)),
+        }
+    }
+    if let Some(value) = table.get("created_at") {
+        arf.created_at = timestamp(value, "created_at", &mut notes);
+    }
+    if let Some(value) = table.get("updated_at") {
+        arf.updated_at = timestamp(value, "updated_at", &mut notes);
+    }
+    if let Some(value) = table.get("approved") {
+        match value {
+            Value::Boolean(approved) => arf.approved = *approved,
+            other => notes.push(format!(
+                "ignored approved (expected a boolean, found {})",
+                other.type_str()
+            )),
+        }
+    }
+    if let Some(value) = table.get("tags") {
+        arf.tags = string_list(value, "tags", &mut notes);
+    }
+    if let Some(value) = table.get("confidence") {
+        arf.confidence = confidence(value, &mut notes);
+    }
+    if let Some(value) = table.get("rules") {
+        arf.rules = rules(value, &mut notes);
+    }
+    if let Some(value) = table.get("context") {
+        match value {
+            Value::Table(context) => arf.context = salvage_context(context, &mut notes),
+            other => notes.push(format!(
+                "ignored context (expected a table, found {})",
+                other.type_str()
+            )),
+        }
+    }
+
+    let warnings = notes
+        .into_iter()
+        .map(|note| format!("entry '{}': {}", arf.what, note))
+        .collect();
+    Ok(SalvagedEntry { arf, warnings })
+}
+
+/// A required text field; numbers and booleans are written out and a list
+/// of strings is joined into lines
+fn required_text(table: &Table, field: &str, notes: &mut Vec<String>) -> Result<String, String> {
+    let value = table
+        .get(field)
+        .ok_or_else(|| format!("missing required field '{}'", field))?;
+    match value {
+        Value::String(text) => Ok(text.clone()),
+        Value::Integer(_) | Value::Float(_) | Value::Boolean(_) => {
+            notes.push(format!(
+                "{} was a {}, read as text",
+                field,
+                value.type_str()
+            ));
+            Ok(value.to_string())
+        }
+        Value::Array(items) if items.iter().all(Value::is_str) => {
+            notes.push(format!("{} was a list, joined into lines", field));
+            Ok(items
+                .iter()
+                .filter_map(Value::as_str)
+                .collect::<Vec<_>>()
+                .join("\n"))
+        }
+        other => Err(format!(
+            "required field '{}' is a {}, not text",
+            field,
+            other.type_str()
+        )),
+    }
+}
+
+fn optional_text(value: &Value, field: &str, notes: &mut Vec<String>) -> Option<String> {
+    match value {
+        Value::String(text) => Some(text.clone()),
+        other => {
+            notes.push(format!(
+                "ignored {} (expected text, found {})",
+                field,
+                other.type_str()
+            ));
+            None
+        }
+    }
+}
+
+/// An RFC 3339 timestamp, quoted or written as a TOML datetime
+fn timestamp(value: &Value, field: &str, notes: &mut Vec<String>) -> Option<DateTime<Utc>> {
+    let text = match value {
+        Value::String(text) => text.clone(),
+        Value::Datetime(datetime) => datetime.to_string(),
+        other => {
+            notes.push(format!(
+                "ignored {} (expected a timestamp, found {})",
+                field,
+                other.type_str()
+            ));
+            return None;
+        }
+    };
+    match DateTime::parse_from_rfc3339(&text) {
+        Ok(parsed) => Some(parsed.with_timezone(&Utc)),
+        Err(_) => {
+            notes.push(format!("ignored {} ('{}' is not an RFC 3339 timestamp)", field, text));
+            None
+        }
+    }
+}
+
+/// A list of strings; a single string becomes a one-item list and items
+/// that aren't strings are dropped
+fn string_list(value: &Value, field: &str, notes: &mut Vec<String>) -> Vec<String> {
+    match value {
+        Value::String(item) => vec![item.clone()],
+        Value::Array(items) => {
+            let kept: Vec<String> = items
+                .iter()
+                .filter_map(|item| item.as_str().map(str::to_string))
+                .collect();
+            if kept.len() < items.len() {
+                notes.push(format!(
+                    "dropped {} non-text item(s) from {}",
+                    items.len() - kept.len(),
+                    field
+                ));
+            }
+            kept
+        }
+        other => {
+            notes.push(format!(
+                "ignored {} (expected a list, found {})",
+                field,
+                other.type_str()
+            ));
+            Vec::new()
+        }
+    }
+}
+
+fn confidence(value: &Value, notes: &mut Vec<String>) -> Option<f64> {
+    let parsed = match value {
+        Value::Float(f) => Some(*f),
+        Value::Integer(i) => Some(*i as f64),
+        Value::String(s) => s.trim().parse::<f64>().ok(),
+        _ => None,
+    };
+    match parsed {
+        Some(c) if (0.0..=1.0).contains(&c) => Some(c),
+        Some(c) => {
+            notes.push(format!("ignored confidence {} (outside 0.0 to 1.0)", c));
+            None
+        }
+        None => {
+            notes.push(format!(
+                "ignored confidence (expected a number, found {})",
+                value
+            ));
+            None
+        }
+    }
+}
+
+/// Rules are kept one by one, so a malformed rule doesn't cost the others
+fn rules(value: &Value, notes: &mut Vec<String>) -> Vec<PatternRule> {
+    let Value::Array(items) = value else {
+        notes.push(format!(
+            "ignored rules (expected a list, found {})",
+            value.type_str()
+        ));
+        return Vec::new();
+    };
+    let mut kept = Vec::new();
+    for (idx, item) in items.iter().enumerate() {
+        match item.clone().try_into::<PatternRule>() {
+            Ok(rule) => kept.push(rule),
+            Err(e) => notes.push(format!("dropped rule {}: {}", idx + 1, e.message())),
+        }
+    }
+    kept
+}
+
+fn salvage_context(table: &Table, notes: &mut Vec<String>) -> ArfContext {
+    let mut context = ArfContext::default();
+    for (key, value) in table {
+        let field = format!("context.{}", key);
+        match key.as_str() {
+            "files" => context.files = string_list(value, &field, notes),
+            "commits" => context.commits = string_list(value, &field, notes),
+            "branches" => context.branches = string_list(value, &field, notes),
+            "issues" => context.issues = string_list(value, &field, notes),
+            "people" => context.people = string_list(value, &field, notes),
+            "dependencies" => context.dependencies = string_list(value, &field, notes),
+            "prompt_types" => context.prompt_types = string_list(value, &field, notes),
+            "outcome" => context.outcome = outcome(value, notes),
+            "provenance" => match value.clone().try_into::<Provenance>() {
+                Ok(provenance) => context.provenance = provenance,
+                Err(e) => notes.push(format!("ignored context.provenance: {}", e.message())),
+            },
+            _ => {
+                context.extra.insert(key.clone(), value.clone());
+            }
+        }
+    }
+    context
+}
+
+/// Outcome values that are numbers or booleans are written out as text
+fn outcome(value: &Value, notes: &mut Vec<String>) -> BTreeMap<String, String> {
+    let Value::Table(table) = value else {
+        notes.push(format!(
+            "ignored context.outcome (expected a table, found {})",
+            value.type_str()
+        ));
+        return BTreeMap::new();
+    };
+    let mut outcome = BTreeMap::new();
+    for (key, value) in table {
+        match value {
+            Value::String(text) => {
+                outcome.insert(key.clone(), text.clone());
+            }
+            Value::Integer(_) | Value::Float(_) | Value::Boolean(_) => {
+                outcome.insert(key.clone(), value.to_string());
+            }
+            other => notes.push(format!(
+                "dropped context.outcome.{} ({} is not text)",
+                key,
+                other.type_str()
+            )),
+        }
+    }
+    outcome
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table(raw: &str) -> Table {
+        raw.parse::<Table>().unwrap()
+    }
+
+    #[test]
+    fn test_valid_entry_has_no_warnings() {
+        let salvaged = salvage_entry(&table(
+            r#"
+what = "Use pooling"
+why = "Performance"
+how = "PgBouncer"
+tags = ["db"]
+[context]
+files = ["src/db.rs"]
+"#,
+        ))
+        .unwrap();
+        assert!(salvaged.warnings.is_empty());
+        assert_eq!(salvaged.arf.context.files, vec!["src/db.rs"]);
+    }
+
+    #[test]
+    fn test_context_as_string_keeps_entry() {
+        let salvaged = salvage_entry(&table(
+            r#"
+what = "Use pooling"
+why = "Performance"
+how = "PgBouncer"
+tags = "db"
+confidence = "0.7"
+context = "see src/db.rs"
+"#,
+        ))
+        .unwrap();
+        assert_eq!(salvaged.arf.what, "Use pooling");
+        assert_eq!(salvaged.arf.tags, vec!["db"]);
+        assert_eq!(salvaged.arf.confidence, Some(0.7));
+        assert_eq!(salvaged.arf.context, ArfContext::default());
+        assert_eq!(salvaged.warnings.len(), 1);
+        assert!(salvaged.warnings[0].contains("ignored context"));
+    }
+
+    #[test]
+    fn test_bad_context_fields_dropped_individually() {
+        let salvaged = salvage_entry(&table(
+            r#"
+what = "Use pooling"
+why = "Performance"
+how = "PgBouncer"
+[context]
+files = ["src/db.rs", 3]
+commits = "abc123"
+owner = "ops"
+outcome = { latency = 40, notes = ["x"] }
+"#,
+        ))
+        .unwrap();
+        let context = &salvaged.arf.context;
+        assert_eq!(context.files, vec!["src/db.rs"]);
+        assert_eq!(context.commits, vec!["abc123"]);
+        assert_eq!(
+            context.outcome.get("latency").map(String::as_str),
+            Some("40")
+        );
+        assert!(!context.outcome.contains_key("notes"));
+        assert_eq!(context.extra["owner"].as_str(), Some("ops"));
+        assert_eq!(salvaged.warnings.len(), 2);
+    }
+
+    #[test]
+    fn test_entry_metadata_carried_over() {
+        let salvaged = salvage_entry(&table(
+            r#"
+what = "Use pooling"
+why = "Performance"
+how = "PgBouncer"
+id = "use-pooling"
+category = "decisions"
+source = "commit-analysis"
+created_at = "2024-03-01T10:00:00Z"
+updated_at = 2024-03-02T10:00:00Z
+context = "oops"
+"#,
+        ))
+        .unwrap();
+        let arf = &salvaged.arf;
+        assert_eq!(arf.id.as_deref(), Some("use-pooling"));
+        assert_eq!(arf.category.as_deref(), Some("decisions"));
+        assert_eq!(arf.source, Some(ArfSource::CommitAnalysis));
+        assert_eq!(
+            arf.created_at.map(|t| t.to_rfc3339()),
+            Some("2024-03-01T10:00:00+00:00".to_string())
+        );
+        assert_eq!(
+            arf.updated_at.map(|t| t.to_rfc3339()),
+            Some("2024-03-02T10:00:00+00:00".to_string())
+        );
+        assert_eq!(salvaged.warnings.len(), 1);
+    }
+
+    #[test]
+    fn test_missing_required_field_fails() {
+        let err = salvage_entry(&table(
+            r#"
+what = "Use pooling"
+how = "PgBouncer"
+context = "oops"
+"#,
+        ))
+        .unwrap_err();
+        assert!(err.contains("why"));
+    }
+}
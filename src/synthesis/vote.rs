@@ -1,9 +1,10 @@
 use crate::arf::ArfFile;
 use super::conflict::FieldConflict;
-use std::collections::HashMap;
+use serde::Serialize;
 
 /// How a conflict was resolved
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
 pub enum Resolution {
     /// 2+ models agreed (weighted score >= 2.0)
     MajorityVote { winner: String, vote_score: f64 },
@@ -15,8 +16,11 @@ pub enum Resolution {
     KeepAll,
 }
 
+/// Weighted score a value needs to win outright
+pub const MAJORITY_SCORE: f64 = 2.0;
+
 /// Default model weights for voting
-fn model_weight(model: &str) -> f64 {
+pub fn model_weight(model: &str) -> f64 {
     match model.to_lowercase().as_str() {
         "claude" => 1.2,
         "gemini" => 1.1,
@@ -25,72 +29,143 @@ fn model_weight(model: &str) -> f64 {
     }
 }
 
+/// A model's vote for one value
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Voter {
+    pub model: String,
+    pub weight: f64,
+}
+
+/// The models that proposed the same value, once normalized
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct VoteGroup {
+    /// Trimmed, lowercased form values are compared by
+    pub normalized: String,
+    /// The first proposed value with this form, which is what wins
+    pub value: String,
+    pub voters: Vec<Voter>,
+    /// Sum of the voters' weights
+    pub score: f64,
+}
+
+/// Every step of voting on one field, for `noggin conflicts explain`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct VoteExplanation {
+    pub field: String,
+    /// Highest score first
+    pub groups: Vec<VoteGroup>,
+    pub resolution: Resolution,
+    /// Why voting ended in `resolution`
+    pub reason: String,
+}
+
 /// Resolve a single field conflict via weighted majority voting.
 pub fn resolve_conflict(conflict: &FieldConflict) -> Resolution {
-    if conflict.values.is_empty() {
-        return Resolution::KeepAll;
-    }
-
-    // Normalize values for comparison (trim, lowercase) but keep original casing
-    let mut vote_map: HashMap<String, (f64, String)> = HashMap::new();
+    explain_conflict(conflict, model_weight).resolution
+}
 
+/// Vote on `conflict` with the given model weights, recording each step.
+///
+/// Values are grouped by their trimmed, lowercased text and each group
+/// scores the sum of its models' weights. A group reaching
+/// [`MAJORITY_SCORE`] wins. Otherwise, if the values differ, the
+/// heaviest model's value is taken (the first listed, on a tie); if they
+/// are all the same, there is nothing to choose.
+pub fn explain_conflict(conflict: &FieldConflict, weight: impl Fn(&str) -> f64) -> VoteExplanation {
+    let mut groups: Vec<VoteGroup> = Vec::new();
     for (model, value) in &conflict.values {
         let normalized = value.trim().to_lowercase();
-        let weight = model_weight(model);
+        let voter = Voter {
+            model: model.clone(),
+            weight: weight(model),
+        };
+        match groups.iter_mut().find(|g| g.normalized == normalized) {
+            Some(group) => {
+                group.score += voter.weight;
+                group.voters.push(voter);
+            }
+            None => groups.push(VoteGroup {
+                normalized,
+                value: value.clone(),
+                score: voter.weight,
+                voters: vec![voter],
+            }),
+        }
+    }
+    // Stable, so equal scores keep the order values were proposed in
+    groups.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(std::cmp::Ordering::Equal));
 
-        let entry = vote_map
-            .entry(normalized)
-            .or_insert_with(|| (0.0, value.clone()));
-        entry.0 += weight;
+    let explanation = |resolution, reason| VoteExplanation {
+        field: conflict.field.clone(),
+        groups: groups.clone(),
+        resolution,
+        reason,
+    };
+
+    let Some(top) = groups.first() else {
+        return explanation(
+            Resolution::KeepAll,
+            "No model proposed a value, so there was nothing to vote on".to_string(),
+        );
+    };
+
+    if top.score >= MAJORITY_SCORE {
+        let models: Vec<&str> = top.voters.iter().map(|v| v.model.as_str()).collect();
+        return explanation(
+            Resolution::MajorityVote {
+                winner: top.value.clone(),
+                vote_score: top.score,
+            },
+            format!(
+                "{} agreed, scoring {:.1}, which reaches the majority threshold of {:.1}",
+                models.join(" and "),
+                top.score,
+                MAJORITY_SCORE
+            ),
+        );
     }
 
-    // Find the winner
-    let mut candidates: Vec<(String, f64, String)> = vote_map
-        .into_iter()
-        .map(|(norm, (score, original))| (norm, score, original))
-        .collect();
-    candidates.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
-
-    if let Some((_, score, winner)) = candidates.first() {
-        if *score >= 2.0 {
-            return Resolution::MajorityVote {
-                winner: winner.clone(),
-                vote_score: *score,
-            };
-        }
+    if groups.len() == 1 {
+        return explanation(
+            Resolution::Merged,
+            "Every value is the same once trimmed and lowercased, so there was nothing to choose"
+                .to_string(),
+        );
     }
 
-    // All different: pick highest-weight model
+    // All different: pick highest-weight model, the first listed on a tie
     let mut best_model = String::new();
     let mut best_weight: f64 = 0.0;
-
     for (model, _value) in &conflict.values {
-        let weight = model_weight(model);
+        let weight = weight(model);
         if weight > best_weight {
             best_weight = weight;
             best_model = model.clone();
         }
     }
-
-    if candidates.len() > 1 {
+    explanation(
         Resolution::HighestWeight {
-            model: best_model,
+            model: best_model.clone(),
             weight: best_weight,
-        }
-    } else {
-        Resolution::Merged
-    }
+        },
+        format!(
+            "No value reached {:.1} (best: {:.1}), so {}'s value was taken as the highest model weight ({:.1})",
+            MAJORITY_SCORE, top.score, best_model, best_weight
+        ),
+    )
 }
 
-/// Resolve all conflicts and apply resolutions to the merged ARFs.
+/// Resolve all conflicts and apply resolutions to the merged ARFs,
+/// recording the winning models in `context.provenance.fields`.
 ///
-/// Returns (resolved_arfs, resolved_count, manual_count).
+/// Returns (resolved_arfs, resolved_count, manual_conflicts), where the
+/// manual conflicts are those voting could not settle.
 pub fn resolve_all(
     mut arfs: Vec<ArfFile>,
     conflicts: Vec<FieldConflict>,
-) -> (Vec<ArfFile>, usize, usize) {
+) -> (Vec<ArfFile>, usize, Vec<FieldConflict>) {
     let mut resolved_count = 0;
-    let mut manual_count = 0;
+    let mut manual = Vec::new();
 
     for conflict in &conflicts {
         let resolution = resolve_conflict(conflict);
@@ -98,12 +173,21 @@ pub fn resolve_all(
         match &resolution {
             Resolution::MajorityVote { winner, .. } => {
                 apply_resolution(&mut arfs, &conflict.field, winner);
+                let normalized = winner.trim().to_lowercase();
+                let voters = conflict
+                    .values
+                    .iter()
+                    .filter(|(_, value)| value.trim().to_lowercase() == normalized)
+                    .map(|(model, _)| model.clone())
+                    .collect();
+                record_winners(&mut arfs, &conflict.field, voters);
                 resolved_count += 1;
             }
             Resolution::HighestWeight { model, .. } => {
                 // Find the value from the highest-weight model
                 if let Some((_, value)) = conflict.values.iter().find(|(m, _)| m == model) {
                     apply_resolution(&mut arfs, &conflict.field, value);
+                    record_winners(&mut arfs, &conflict.field, vec![model.clone()]);
                 }
                 resolved_count += 1;
             }
@@ -111,35 +195,27 @@ pub fn resolve_all(
                 resolved_count += 1;
             }
             Resolution::KeepAll => {
-                manual_count += 1;
+                manual.push(conflict.clone());
             }
         }
     }
 
-    (arfs, resolved_count, manual_count)
+    (arfs, resolved_count, manual)
 }
 
-/// Apply a resolved value to the appropriate field in the ARF list.
-fn apply_resolution(arfs: &mut [ArfFile], field: &str, value: &str) {
-    if arfs.is_empty() {
-        return;
+/// Note which models' value `field` took after voting
+fn record_winners(arfs: &mut [ArfFile], field: &str, mut models: Vec<String>) {
+    if let Some(arf) = arfs.first_mut() {
+        models.sort();
+        models.dedup();
+        arf.context.provenance.fields.insert(field.to_string(), models);
     }
+}
 
-    match field {
-        "what" => {
-            arfs[0].what = value.to_string();
-        }
-        "why" => {
-            arfs[0].why = value.to_string();
-        }
-        "how" => {
-            arfs[0].how = value.to_string();
-        }
-        f if f.starts_with("context.outcome.") => {
-            let key = f.strip_prefix("context.outcome.").unwrap_or(f);
-            arfs[0].context.outcome.insert(key.to_string(), value.to_string());
-        }
-        _ => {}
+/// Apply a resolved value to the appropriate field in the ARF list.
+fn apply_resolution(arfs: &mut [ArfFile], field: &str, value: &str) {
+    if let Some(arf) = arfs.first_mut() {
+        arf.set_field(field, value);
     }
 }
 
@@ -226,6 +302,41 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_explain_conflict_groups_and_reweighting() {
+        let conflict = FieldConflict {
+            field: "how".to_string(),
+            kind: ConflictKind::DifferentValues,
+            values: vec![
+                ("claude".to_string(), "Use deadpool".to_string()),
+                ("gemini".to_string(), "use deadpool ".to_string()),
+                ("codex".to_string(), "Use bb8".to_string()),
+            ],
+            resolution: None,
+        };
+
+        let explanation = explain_conflict(&conflict, model_weight);
+        assert_eq!(explanation.resolution, resolve_conflict(&conflict));
+        assert_eq!(explanation.groups.len(), 2);
+        assert_eq!(explanation.groups[0].value, "Use deadpool");
+        assert_eq!(explanation.groups[0].voters.len(), 2);
+        assert!(explanation.reason.contains("claude and gemini"));
+
+        // Lighter weights push both groups below the threshold
+        let explanation = explain_conflict(&conflict, |model| match model {
+            "codex" => 1.5,
+            _ => 0.9,
+        });
+        assert_eq!(
+            explanation.resolution,
+            Resolution::HighestWeight {
+                model: "codex".to_string(),
+                weight: 1.5
+            }
+        );
+        assert!(explanation.reason.contains("best: 1.8"));
+    }
+
     #[test]
     fn test_resolve_empty_values() {
         let conflict = FieldConflict {
@@ -253,8 +364,29 @@ mod tests {
 
         let (resolved, count, manual) = resolve_all(arfs, conflicts);
         assert_eq!(resolved[0].what, "Better name");
+        assert_eq!(
+            resolved[0].context.provenance.fields["what"],
+            vec!["claude", "gemini"]
+        );
         assert_eq!(count, 1);
-        assert_eq!(manual, 0);
+        assert!(manual.is_empty());
+    }
+
+    #[test]
+    fn test_resolve_all_returns_manual_conflicts() {
+        let arfs = vec![ArfFile::new("Original", "Reason", "Steps")];
+        let conflicts = vec![FieldConflict {
+            field: "how".to_string(),
+            kind: ConflictKind::MissingInSome,
+            values: vec![],
+            resolution: None,
+        }];
+
+        let (resolved, count, manual) = resolve_all(arfs, conflicts);
+        assert_eq!(resolved[0].how, "Steps");
+        assert_eq!(count, 0);
+        assert_eq!(manual.len(), 1);
+        assert_eq!(manual[0].field, "how");
     }
 
     #[test]
@@ -0,0 +1,183 @@
+//! Per-category ARF templates in `.noggin/templates/<category>.toml`.
+//!
+//! A template names extra fields every entry in its category must fill
+//! in, kept under the entry's `[context]`:
+//!
+//! ```toml
+//! # .noggin/templates/migrations.toml
+//! [fields]
+//! rollback = "How to undo the migration"
+//! ```
+//!
+//! Learn lists each category's fields in its file, commit, and pattern
+//! prompts so providers fill them in. `noggin check` reports entries
+//! missing a field. Learn holds new entries missing one in
+//! `.noggin/pending/` for someone to complete, and carries the fields over
+//! when it updates an existing entry.
+
+use crate::arf::ArfFile;
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+/// Directory under .noggin/ holding the templates
+pub const TEMPLATES_DIR: &str = "templates";
+
+/// Contents of one template file
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct CategoryTemplate {
+    /// Required `[context]` fields, with what each should say
+    #[serde(default)]
+    pub fields: BTreeMap<String, String>,
+}
+
+/// Every template, keyed by category directory
+#[derive(Debug, Clone, Default)]
+pub struct Templates {
+    categories: BTreeMap<String, CategoryTemplate>,
+}
+
+impl Templates {
+    /// Load `.noggin/templates/*.toml`; none if the directory is missing
+    pub fn load(noggin_path: &Path) -> Result<Self> {
+        let mut categories = BTreeMap::new();
+        let Ok(entries) = fs::read_dir(noggin_path.join(TEMPLATES_DIR)) else {
+            return Ok(Self::default());
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.extension().is_none_or(|ext| ext != "toml") {
+                continue;
+            }
+            let Some(category) = path.file_stem().and_then(|s| s.to_str()) else {
+                continue;
+            };
+            let contents = fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read template {}", path.display()))?;
+            let template: CategoryTemplate = toml::from_str(&contents)
+                .with_context(|| format!("Failed to parse template {}", path.display()))?;
+            categories.insert(category.to_string(), template);
+        }
+        Ok(Self { categories })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.categories.values().all(|t| t.fields.is_empty())
+    }
+
+    /// Fields `category`'s template requires
+    pub fn required(&self, category: &str) -> impl Iterator<Item = (&str, &str)> {
+        self.categories
+            .get(category)
+            .into_iter()
+            .flat_map(|t| t.fields.iter().map(|(name, about)| (name.as_str(), about.as_str())))
+    }
+
+    /// Prompt text asking providers to fill in each category's required
+    /// fields; empty when no template requires any
+    pub fn prompt_instruction(&self) -> String {
+        if self.is_empty() {
+            return String::new();
+        }
+        let mut instruction = String::from(
+            "\nEntries in these categories must also set these fields under \
+             [entry.context], each a string saying what is described:\n",
+        );
+        for (category, template) in &self.categories {
+            if template.fields.is_empty() {
+                continue;
+            }
+            let fields: Vec<String> = template
+                .fields
+                .iter()
+                .map(|(name, about)| format!("{} ({})", name, about))
+                .collect();
+            instruction.push_str(&format!("- {}: {}\n", category, fields.join(", ")));
+        }
+        instruction
+    }
+
+    /// Required fields `arf` leaves missing or blank, in name order
+    pub fn missing(&self, category: &str, arf: &ArfFile) -> Vec<String> {
+        self.required(category)
+            .filter(|(name, _)| !has_field(arf, name))
+            .map(|(name, _)| name.to_string())
+            .collect()
+    }
+
+    /// Copy required fields `arf` lacks from `existing`, the version of
+    /// the entry already on disk
+    pub fn carry_over(&self, category: &str, existing: &ArfFile, arf: &mut ArfFile) {
+        for (name, _) in self.required(category) {
+            if !has_field(arf, name) && has_field(existing, name) {
+                if let Some(value) = existing.context.extra.get(name) {
+                    arf.context.extra.insert(name.to_string(), value.clone());
+                }
+            }
+        }
+    }
+}
+
+fn has_field(arf: &ArfFile, name: &str) -> bool {
+    match arf.context.extra.get(name) {
+        Some(toml::Value::String(value)) => !value.trim().is_empty(),
+        Some(toml::Value::Array(values)) => !values.is_empty(),
+        Some(_) => true,
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_load_and_check_templates() {
+        let temp_dir = TempDir::new().unwrap();
+        let none = Templates::load(temp_dir.path()).unwrap();
+        assert!(none.is_empty());
+        assert_eq!(none.prompt_instruction(), "");
+
+        let dir = temp_dir.path().join(TEMPLATES_DIR);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(
+            dir.join("migrations.toml"),
+            "[fields]\nrollback = \"How to undo it\"\nowner = \"Who runs it\"\n",
+        )
+        .unwrap();
+        fs::write(dir.join("notes.md"), "ignored").unwrap();
+        let templates = Templates::load(temp_dir.path()).unwrap();
+
+        let mut arf = ArfFile::new("Move users to Postgres", "Scale", "pgloader");
+        arf.context
+            .extra
+            .insert("owner".to_string(), toml::Value::String("  ".to_string()));
+        assert_eq!(templates.missing("migrations", &arf), vec!["owner", "rollback"]);
+        assert!(templates.missing("bugs", &arf).is_empty());
+        assert!(templates
+            .prompt_instruction()
+            .contains("- migrations: owner (Who runs it), rollback (How to undo it)\n"));
+
+        let mut existing = arf.clone();
+        existing
+            .context
+            .extra
+            .insert("rollback".to_string(), toml::Value::String("Restore the dump".to_string()));
+        templates.carry_over("migrations", &existing, &mut arf);
+        assert_eq!(templates.missing("migrations", &arf), vec!["owner"]);
+    }
+
+    #[test]
+    fn test_malformed_template_is_an_error() {
+        let temp_dir = TempDir::new().unwrap();
+        let dir = temp_dir.path().join(TEMPLATES_DIR);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("bugs.toml"), "fields = [").unwrap();
+
+        let err = Templates::load(temp_dir.path()).unwrap_err();
+        assert!(err.to_string().contains("bugs.toml"));
+    }
+}
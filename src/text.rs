@@ -0,0 +1,214 @@
+//! Unicode-safe string helpers.
+//!
+//! Anything that shortens user- or model-provided text goes through
+//! here, so cuts always land on character boundaries instead of
+//! panicking mid-codepoint.
+
+use sha2::{Digest, Sha256};
+use std::borrow::Cow;
+
+/// Maximum slug length, in characters
+pub const MAX_SLUG_CHARS: usize = 50;
+
+/// The first `max_chars` characters of `text`
+pub fn truncate_chars(text: &str, max_chars: usize) -> &str {
+    match text.char_indices().nth(max_chars) {
+        Some((idx, _)) => &text[..idx],
+        None => text,
+    }
+}
+
+/// Seven-character abbreviation of a commit hash (or the whole input if
+/// shorter)
+pub fn short_hash(hash: &str) -> &str {
+    truncate_chars(hash, 7)
+}
+
+/// Changed lines between two texts: removals prefixed with `-`,
+/// additions with `+`, in order. Unchanged lines are omitted.
+pub fn line_diff(old: &str, new: &str) -> String {
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+
+    // Longest common subsequence lengths of every suffix pair
+    let mut lcs = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i][j] = if old[i] == new[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut diff = String::new();
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            i += 1;
+            j += 1;
+        } else if j == new.len() || (i < old.len() && lcs[i + 1][j] >= lcs[i][j + 1]) {
+            diff.push_str(&format!("-{}\n", old[i]));
+            i += 1;
+        } else {
+            diff.push_str(&format!("+{}\n", new[j]));
+            j += 1;
+        }
+    }
+    diff
+}
+
+/// Convert text to a filename-safe slug.
+///
+/// Lowercases, transliterates common Latin letters with diacritics to
+/// ASCII, drops combining marks, replaces everything else that isn't
+/// alphanumeric with hyphens, collapses and trims hyphens, and cuts to
+/// `MAX_SLUG_CHARS` at a word break where possible. Text with nothing
+/// sluggable (e.g. only emoji) gets a stable `entry-<hash>` slug.
+pub fn slugify(text: &str) -> String {
+    let mut result = String::new();
+    let mut prev_hyphen = false;
+
+    for c in text.chars().flat_map(char::to_lowercase) {
+        if is_combining_mark(c) {
+            continue;
+        }
+        let mapped = transliterate(c);
+        for c in mapped.chars() {
+            if c.is_alphanumeric() {
+                result.push(c);
+                prev_hyphen = false;
+            } else if !prev_hyphen && !result.is_empty() {
+                result.push('-');
+                prev_hyphen = true;
+            }
+        }
+    }
+
+    let trimmed = result.trim_end_matches('-');
+    let slug = if trimmed.chars().count() > MAX_SLUG_CHARS {
+        // Find a clean break point
+        let truncated = truncate_chars(trimmed, MAX_SLUG_CHARS);
+        truncated
+            .rfind('-')
+            .map(|i| &truncated[..i])
+            .unwrap_or(truncated)
+    } else {
+        trimmed
+    };
+
+    if slug.is_empty() {
+        let digest = Sha256::digest(text.as_bytes());
+        return format!("entry-{:x}", digest)[..14].to_string();
+    }
+
+    slug.to_string()
+}
+
+/// Combining diacritical marks (U+0300–U+036F), as left behind by
+/// decomposed input like `e\u{301}`
+fn is_combining_mark(c: char) -> bool {
+    ('\u{0300}'..='\u{036F}').contains(&c)
+}
+
+/// ASCII spelling of a lowercase Latin letter with diacritics; other
+/// characters are returned unchanged
+fn transliterate(c: char) -> Cow<'static, str> {
+    let ascii = match c {
+        'à' | 'á' | 'â' | 'ã' | 'ä' | 'å' | 'ā' | 'ă' | 'ą' => "a",
+        'æ' => "ae",
+        'ç' | 'ć' | 'ĉ' | 'ċ' | 'č' => "c",
+        'ď' | 'đ' | 'ð' => "d",
+        'è' | 'é' | 'ê' | 'ë' | 'ē' | 'ĕ' | 'ė' | 'ę' | 'ě' => "e",
+        'ĝ' | 'ğ' | 'ġ' | 'ģ' => "g",
+        'ĥ' | 'ħ' => "h",
+        'ì' | 'í' | 'î' | 'ï' | 'ĩ' | 'ī' | 'ĭ' | 'į' | 'ı' => "i",
+        'ĵ' => "j",
+        'ķ' => "k",
+        'ĺ' | 'ļ' | 'ľ' | 'ŀ' | 'ł' => "l",
+        'ñ' | 'ń' | 'ņ' | 'ň' => "n",
+        'ò' | 'ó' | 'ô' | 'õ' | 'ö' | 'ø' | 'ō' | 'ŏ' | 'ő' => "o",
+        'œ' => "oe",
+        'ŕ' | 'ŗ' | 'ř' => "r",
+        'ś' | 'ŝ' | 'ş' | 'š' | 'ș' => "s",
+        'ß' => "ss",
+        'ţ' | 'ť' | 'ŧ' | 'ț' => "t",
+        'þ' => "th",
+        'ù' | 'ú' | 'û' | 'ü' | 'ũ' | 'ū' | 'ŭ' | 'ů' | 'ű' | 'ų' => "u",
+        'ŵ' => "w",
+        'ý' | 'ÿ' | 'ŷ' => "y",
+        'ź' | 'ż' | 'ž' => "z",
+        _ => return Cow::Owned(c.to_string()),
+    };
+    Cow::Borrowed(ascii)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_truncate_chars_respects_boundaries() {
+        assert_eq!(truncate_chars("héllo", 2), "hé");
+        assert_eq!(truncate_chars("日本語テキスト", 3), "日本語");
+        assert_eq!(truncate_chars("🦀🦀", 1), "🦀");
+        assert_eq!(truncate_chars("abc", 10), "abc");
+        assert_eq!(truncate_chars("", 3), "");
+    }
+
+    #[test]
+    fn test_short_hash() {
+        assert_eq!(short_hash("abcdef0123456789"), "abcdef0");
+        assert_eq!(short_hash("abc"), "abc");
+        assert_eq!(short_hash("ä€😀bcdefg"), "ä€😀bcde");
+    }
+
+    #[test]
+    fn test_line_diff() {
+        let old = "what = \"a\"\nwhy = \"b\"\nhow = \"c\"\n";
+        let new = "what = \"a\"\nwhy = \"B\"\nhow = \"c\"\nextra = 1\n";
+        assert_eq!(line_diff(old, new), "-why = \"b\"\n+why = \"B\"\n+extra = 1\n");
+        assert_eq!(line_diff(old, old), "");
+        assert_eq!(line_diff("", "x\n"), "+x\n");
+    }
+
+    #[test]
+    fn test_slugify_transliterates_latin() {
+        assert_eq!(slugify("Crème brûlée für Straße"), "creme-brulee-fur-strasse");
+        assert_eq!(slugify("Łódź øresund Æther"), "lodz-oresund-aether");
+    }
+
+    #[test]
+    fn test_slugify_drops_combining_marks() {
+        assert_eq!(slugify("re\u{301}sume\u{301} parsing"), "resume-parsing");
+    }
+
+    #[test]
+    fn test_slugify_keeps_other_scripts() {
+        assert_eq!(slugify("Использовать пул соединений"), "использовать-пул-соединений");
+        assert_eq!(slugify("使用连接池 pattern"), "使用连接池-pattern");
+    }
+
+    #[test]
+    fn test_slugify_truncates_multibyte_without_panicking() {
+        let long = "日本語".repeat(40);
+        let slug = slugify(&long);
+        assert_eq!(slug.chars().count(), MAX_SLUG_CHARS);
+
+        let words = "über ".repeat(30);
+        let slug = slugify(&words);
+        assert!(slug.chars().count() <= MAX_SLUG_CHARS);
+        assert!(!slug.ends_with('-'));
+        assert!(slug.starts_with("uber-uber"));
+    }
+
+    #[test]
+    fn test_slugify_emoji_only_is_stable() {
+        let slug = slugify("🚀🔥");
+        assert!(slug.starts_with("entry-"));
+        assert_eq!(slug.len(), 14);
+        assert_eq!(slug, slugify("🚀🔥"));
+        assert_ne!(slug, slugify("🎉"));
+    }
+}
@@ -0,0 +1,53 @@
+//! Timestamp rendering for human and machine output.
+//!
+//! Human-facing output uses the local timezone (honoring `TZ`) unless
+//! `--utc` is passed; JSON output always uses ISO-8601 in UTC.
+
+use chrono::{DateTime, Local, TimeZone, Utc};
+
+/// Render a timestamp for people: local time with offset, or UTC
+pub fn format_datetime(dt: &DateTime<Utc>, utc: bool) -> String {
+    if utc {
+        dt.format("%Y-%m-%d %H:%M:%S UTC").to_string()
+    } else {
+        dt.with_timezone(&Local).format("%Y-%m-%d %H:%M:%S %:z").to_string()
+    }
+}
+
+/// Render a Unix timestamp (seconds) for people
+pub fn format_unix(secs: i64, utc: bool) -> String {
+    match Utc.timestamp_opt(secs, 0).single() {
+        Some(dt) => format_datetime(&dt, utc),
+        None => secs.to_string(),
+    }
+}
+
+/// ISO-8601 (RFC 3339) rendering of a Unix timestamp, in UTC
+pub fn unix_to_iso8601(secs: i64) -> String {
+    match Utc.timestamp_opt(secs, 0).single() {
+        Some(dt) => dt.to_rfc3339(),
+        None => secs.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_utc() {
+        assert_eq!(format_unix(1_700_000_000, true), "2023-11-14 22:13:20 UTC");
+    }
+
+    #[test]
+    fn test_format_local_includes_offset() {
+        let rendered = format_unix(1_700_000_000, false);
+        let dt = DateTime::parse_from_str(&rendered, "%Y-%m-%d %H:%M:%S %:z").unwrap();
+        assert_eq!(dt.timestamp(), 1_700_000_000);
+    }
+
+    #[test]
+    fn test_unix_to_iso8601() {
+        assert_eq!(unix_to_iso8601(1_700_000_000), "2023-11-14T22:13:20+00:00");
+    }
+}
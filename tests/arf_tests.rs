@@ -45,10 +45,11 @@ fn test_load_migration_fixture() {
 fn test_all_fixtures_validate() {
     for fixture in ["decision.arf", "pattern.arf", "migration.arf"] {
         let path = fixtures_dir().join(fixture);
-        let arf = ArfFile::from_toml(&path)
+        let mut arf = ArfFile::from_toml(&path)
             .unwrap_or_else(|e| panic!("Failed to load {}: {}", fixture, e));
         
-        arf.validate()
+        let notes = arf.validate()
             .unwrap_or_else(|e| panic!("Validation failed for {}: {}", fixture, e));
+        assert!(notes.is_empty(), "{} needed repairs: {:?}", fixture, notes);
     }
 }
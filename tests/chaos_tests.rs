@@ -0,0 +1,229 @@
+//! Learn under injected provider faults (`NOGGIN_CHAOS`)
+//!
+//! Each test runs `learn` against two command-backed providers that always
+//! answer with the same valid response, wrapped by the chaos layer with
+//! fault probabilities chosen per test. Faults must surface as warnings,
+//! never as an aborted run, and the manifest must still be saved. Work
+//! whose prompts got no usable response is checkpointed instead of
+//! recorded, so a later run without faults picks it up, and the CLI exits
+//! non-zero.
+
+use llm_noggin::commands::init::create_knowledge_base;
+use llm_noggin::commands::learn::{run_learn, LearnOptions, LearnReport};
+use llm_noggin::learn::checkpoint::Checkpoint;
+use llm_noggin::llm::chaos::CHAOS_ENV;
+use llm_noggin::manifest::Manifest;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use std::sync::Once;
+use tempfile::TempDir;
+
+/// Probabilities that never inject a fault
+const CALM: &str = "delay = 0.0\ntimeout = 0.0\nrate_limit = 0.0\nmalformed = 0.0";
+
+const RESPONSE: &str = "what = \"Use connection pooling\"\n\
+                        why = \"Opening a connection per request exhausts the database\"\n\
+                        how = \"Share one deadpool pool through the app state\"\n";
+
+/// Turn chaos on and keep the machine budget ledger out of the user's
+/// config directory, once for the whole test binary
+fn enable_chaos() {
+    static ENV: Once = Once::new();
+    ENV.call_once(|| {
+        let config_home = TempDir::new().unwrap().keep();
+        std::env::set_var("XDG_CONFIG_HOME", config_home);
+        std::env::set_var(CHAOS_ENV, "1");
+    });
+}
+
+/// A committed repository with an initialized knowledge base whose
+/// providers run under the given `[llm.chaos]` table
+fn chaos_repo(chaos: &str) -> TempDir {
+    enable_chaos();
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path();
+    let repo = git2::Repository::init(root).unwrap();
+    let mut config = repo.config().unwrap();
+    config.set_str("user.name", "Test User").unwrap();
+    config.set_str("user.email", "test@example.com").unwrap();
+
+    fs::create_dir(root.join("src")).unwrap();
+    fs::write(root.join("src/db.rs"), "pub fn pool() {}\n").unwrap();
+    let mut index = repo.index().unwrap();
+    index.add_path(Path::new("src/db.rs")).unwrap();
+    index.write().unwrap();
+    let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+    let signature = repo.signature().unwrap();
+    repo.commit(Some("HEAD"), &signature, &signature, "Add database pool", &tree, &[])
+        .unwrap();
+
+    let noggin_path = root.join(".noggin");
+    create_knowledge_base(&noggin_path).unwrap();
+    fs::write(noggin_path.join("response.txt"), RESPONSE).unwrap();
+    set_chaos(&temp_dir, chaos);
+    temp_dir
+}
+
+/// Replace the `[llm.chaos]` table the providers run under
+fn set_chaos(repo: &TempDir, chaos: &str) {
+    let noggin_path = repo.path().join(".noggin");
+    let response_path = noggin_path.join("response.txt");
+    let command = format!("sh -c 'cat {}' {{prompt_file}}", response_path.display());
+    let providers: String = ["alpha", "beta"]
+        .iter()
+        .map(|name| format!("[[llm.custom]]\nname = \"{}\"\ncommand = \"{}\"\n\n", name, command))
+        .collect();
+    fs::write(
+        noggin_path.join("config.toml"),
+        format!(
+            "[llm]\nproviders = [\"alpha\", \"beta\"]\nrate_limit_retries = 1\n\
+             rate_limit_wait_secs = 0\n\n[llm.retry]\nmax_attempts = 1\n\n\
+             [llm.chaos]\n{}\n\n{}",
+            chaos, providers
+        ),
+    )
+    .unwrap();
+}
+
+async fn learn(repo: &TempDir) -> LearnReport {
+    let opts = LearnOptions {
+        quiet: true,
+        ..Default::default()
+    };
+    run_learn(repo.path(), &opts).await.unwrap()
+}
+
+fn manifest(repo: &TempDir) -> Manifest {
+    Manifest::load(&repo.path().join(".noggin/manifest.toml")).unwrap()
+}
+
+fn checkpoint(repo: &TempDir) -> Option<Checkpoint> {
+    Checkpoint::load(&repo.path().join(".noggin")).unwrap()
+}
+
+/// Files whose prompts failed, as recorded for the next run
+fn failed_files(repo: &TempDir) -> Vec<String> {
+    checkpoint(repo)
+        .map(|c| c.failed.iter().flat_map(|p| p.files.clone()).collect())
+        .unwrap_or_default()
+}
+
+fn has_warning(report: &LearnReport, text: &str) -> bool {
+    report.warnings.iter().any(|w| w.contains(text))
+}
+
+/// Without faults, a rerun analyzes what failed before and clears the
+/// checkpoint
+async fn assert_resumes(repo: &TempDir) {
+    set_chaos(repo, CALM);
+    let report = learn(repo).await;
+    assert!(!report.up_to_date);
+    assert!(!report.arfs_written.is_empty(), "{:?}", report.warnings);
+    assert!(manifest(repo).files.contains_key("src/db.rs"));
+    assert!(checkpoint(repo).is_none());
+
+    assert!(learn(repo).await.up_to_date);
+}
+
+#[tokio::test]
+async fn test_malformed_responses_become_warnings() {
+    let repo = chaos_repo("delay = 0.0\ntimeout = 0.0\nrate_limit = 0.0\nmalformed = 1.0");
+    let report = learn(&repo).await;
+
+    assert!(has_warning(&report, "Failed to parse alpha output"));
+    assert!(has_warning(&report, "Failed to parse beta output"));
+    assert!(has_warning(&report, "No model outputs to synthesize"));
+    assert!(report.arfs_written.is_empty());
+    assert!(!manifest(&repo).files.contains_key("src/db.rs"));
+    assert_eq!(failed_files(&repo), vec!["src/db.rs"]);
+    assert!(checkpoint(&repo).unwrap().limit.is_none());
+
+    assert_resumes(&repo).await;
+}
+
+#[tokio::test]
+async fn test_timeouts_become_warnings() {
+    let repo = chaos_repo(
+        "delay = 0.0\ndelay_ms = 10\ntimeout = 1.0\nrate_limit = 0.0\nmalformed = 0.0",
+    );
+    let report = learn(&repo).await;
+
+    assert!(has_warning(&report, "All LLMs failed"));
+    assert!(has_warning(&report, "(injected)"), "{:?}", report.warnings);
+    assert!(report.arfs_written.is_empty());
+    assert_eq!(failed_files(&repo), vec!["src/db.rs"]);
+}
+
+#[tokio::test]
+async fn test_rate_limits_are_retried_then_reported() {
+    let repo = chaos_repo(
+        "delay = 0.0\ntimeout = 0.0\nrate_limit = 1.0\nmalformed = 0.0\nretry_after_secs = 0",
+    );
+    let report = learn(&repo).await;
+
+    assert!(has_warning(&report, "All LLMs failed"));
+    assert!(report.arfs_written.is_empty());
+    assert!(!manifest(&repo).files.contains_key("src/db.rs"));
+    assert_eq!(failed_files(&repo), vec!["src/db.rs"]);
+
+    assert_resumes(&repo).await;
+}
+
+#[tokio::test]
+async fn test_slow_providers_still_succeed() {
+    let repo = chaos_repo(
+        "delay = 1.0\ndelay_ms = 50\ntimeout = 0.0\nrate_limit = 0.0\nmalformed = 0.0",
+    );
+    let report = learn(&repo).await;
+
+    assert!(!has_warning(&report, "failed"), "{:?}", report.warnings);
+    assert!(report.arf_entries > 0);
+    assert!(!report.arfs_written.is_empty());
+    assert!(checkpoint(&repo).is_none());
+}
+
+#[tokio::test]
+async fn test_mixed_faults_complete_the_run() {
+    let repo = chaos_repo(
+        "delay = 0.5\ndelay_ms = 10\ntimeout = 0.2\nrate_limit = 0.2\nmalformed = 0.2\n\
+         retry_after_secs = 0\nseed = 7",
+    );
+    let report = learn(&repo).await;
+
+    assert!(report.run_record.is_some());
+    // Each file is either learned from or left for the next run
+    let recorded = manifest(&repo).files.contains_key("src/db.rs");
+    let failed = failed_files(&repo).contains(&"src/db.rs".to_string());
+    assert!(recorded != failed, "recorded: {}, failed: {}", recorded, failed);
+
+    if failed {
+        assert_resumes(&repo).await;
+    } else {
+        assert!(learn(&repo).await.up_to_date);
+    }
+}
+
+#[test]
+fn test_failed_prompts_exit_nonzero_until_resumed() {
+    let repo = chaos_repo("delay = 0.0\ntimeout = 0.0\nrate_limit = 0.0\nmalformed = 1.0");
+    let noggin = || {
+        Command::new(env!("CARGO_BIN_EXE_noggin"))
+            .arg("learn")
+            .current_dir(repo.path())
+            .output()
+            .unwrap()
+    };
+
+    let output = noggin();
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("no usable response"), "{}", stderr);
+    assert_eq!(failed_files(&repo), vec!["src/db.rs"]);
+
+    set_chaos(&repo, CALM);
+    let output = noggin();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert!(manifest(&repo).files.contains_key("src/db.rs"));
+    assert!(checkpoint(&repo).is_none());
+}
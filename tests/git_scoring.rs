@@ -188,3 +188,31 @@ fn test_score_category_conversion() {
     assert_eq!(ScoreCategory::from_score(0.25), ScoreCategory::Low);
     assert_eq!(ScoreCategory::from_score(0.05), ScoreCategory::Trivial);
 }
+
+#[test]
+fn test_score_vendored_files_down_weighted() {
+    let (dir, repo) = create_test_repo();
+    std::fs::write(dir.path().join(".gitattributes"), "vendor/** linguist-vendored\n").unwrap();
+    let config = ScoringConfig::default();
+
+    let content = "pub fn parse() {}\n".repeat(100);
+    let own = create_commit(&repo, "src/parser.rs", &content, "Add parser");
+    let vendored = create_commit(&repo, "vendor/json/src/parser.rs", &content, "Add parser");
+
+    let own = score_commit(&repo, &repo.find_commit(own).unwrap(), &config).unwrap();
+    let vendored = score_commit(&repo, &repo.find_commit(vendored).unwrap(), &config).unwrap();
+    assert!(
+        vendored.significance < own.significance,
+        "Vendored change should score lower: {} vs {}",
+        vendored.significance,
+        own.significance
+    );
+
+    let ignoring = ScoringConfig {
+        honor_attributes: false,
+        ..ScoringConfig::default()
+    };
+    let head = repo.head().unwrap().peel_to_commit().unwrap();
+    let unweighted = score_commit(&repo, &head, &ignoring).unwrap();
+    assert_eq!(unweighted.significance, own.significance);
+}
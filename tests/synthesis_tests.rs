@@ -1,4 +1,5 @@
 use llm_noggin::arf::ArfFile;
+use llm_noggin::config::SynthesisConfig;
 use llm_noggin::synthesis::{
     self, ModelOutput,
     merger, conflict, vote,
@@ -31,7 +32,7 @@ fn test_full_pipeline_three_models_agree() {
         ]),
     ];
 
-    let result = synthesis::synthesize(outputs).unwrap();
+    let result = synthesis::synthesize(outputs, &SynthesisConfig::default()).unwrap();
     assert_eq!(result.unified_arfs.len(), 1);
     assert_eq!(result.unified_arfs[0].what, "Use connection pooling");
     assert_eq!(result.report.models_used.len(), 3);
@@ -51,7 +52,7 @@ fn test_full_pipeline_different_topics() {
         ]),
     ];
 
-    let result = synthesis::synthesize(outputs).unwrap();
+    let result = synthesis::synthesize(outputs, &SynthesisConfig::default()).unwrap();
     // Should produce 2 unified ARFs (pooling + caching)
     assert_eq!(result.unified_arfs.len(), 2);
     assert_eq!(result.report.total_input_arfs, 4);
@@ -72,7 +73,7 @@ fn test_full_pipeline_majority_wins_what_field() {
         ]),
     ];
 
-    let result = synthesis::synthesize(outputs).unwrap();
+    let result = synthesis::synthesize(outputs, &SynthesisConfig::default()).unwrap();
     assert_eq!(result.unified_arfs.len(), 1);
     // "Use pooling" has 2 votes (claude + gemini), should win
     assert_eq!(result.unified_arfs[0].what, "Use pooling");
@@ -93,7 +94,7 @@ fn test_full_pipeline_merges_context() {
         make_output("gemini", vec![arf2]),
     ];
 
-    let result = synthesis::synthesize(outputs).unwrap();
+    let result = synthesis::synthesize(outputs, &SynthesisConfig::default()).unwrap();
     assert_eq!(result.unified_arfs.len(), 1);
     let ctx = &result.unified_arfs[0].context;
     // Files and commits should be unioned and sorted
@@ -119,8 +120,8 @@ fn test_determinism_same_input_same_output() {
         ]
     };
 
-    let result1 = synthesis::synthesize(make_inputs()).unwrap();
-    let result2 = synthesis::synthesize(make_inputs()).unwrap();
+    let result1 = synthesis::synthesize(make_inputs(), &SynthesisConfig::default()).unwrap();
+    let result2 = synthesis::synthesize(make_inputs(), &SynthesisConfig::default()).unwrap();
 
     assert_eq!(result1.unified_arfs.len(), result2.unified_arfs.len());
     for (a, b) in result1.unified_arfs.iter().zip(result2.unified_arfs.iter()) {
@@ -224,7 +225,7 @@ fn test_category_grouping() {
 fn test_synthesize_single_model_single_arf() {
     let result = synthesis::synthesize(vec![
         make_output("claude", vec![make_arf("Only entry", "Only reason", "Only step")]),
-    ]).unwrap();
+    ], &SynthesisConfig::default()).unwrap();
 
     assert_eq!(result.unified_arfs.len(), 1);
     assert_eq!(result.report.conflicts_detected, 0);
@@ -235,7 +236,7 @@ fn test_synthesize_empty_arfs_errors() {
     let result = synthesis::synthesize(vec![
         make_output("claude", vec![]),
         make_output("gemini", vec![]),
-    ]);
+    ], &SynthesisConfig::default());
     assert!(result.is_err());
 }
 
@@ -250,7 +251,7 @@ fn test_why_merges_unique_sentences() {
         ]),
     ];
 
-    let result = synthesis::synthesize(outputs).unwrap();
+    let result = synthesis::synthesize(outputs, &SynthesisConfig::default()).unwrap();
     let why = &result.unified_arfs[0].why;
     assert!(why.contains("Performance boost"));
     assert!(why.contains("Less overhead"));